@@ -1,5 +1,38 @@
+//! Print diagnostics about the SDK and its environment.
+//!
+//! With `export-report <results.json> <report.tar>`, instead bundle the diagnostics with
+//! [`ExecutionData`] serialized as JSON into a report archive, see [`qcs::report`].
+
+use qcs::{report::Report, ExecutionData};
+
+const USAGE: &str = "usage: diagnostics [export-report <results.json> <report.tar>]";
+
 #[tokio::main]
 async fn main() {
-    let diagnostics = qcs::diagnostics::get_report().await;
-    println!("{diagnostics}");
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.as_slice() {
+        [] => {
+            let diagnostics = qcs::diagnostics::get_report().await;
+            println!("{diagnostics}");
+        }
+        [command, results, archive] if command == "export-report" => {
+            if let Err(error) = export_report(results, archive).await {
+                eprintln!("{error}");
+                std::process::exit(1);
+            }
+            println!("wrote {archive}");
+        }
+        _ => {
+            eprintln!("{USAGE}");
+            std::process::exit(2);
+        }
+    }
+}
+
+async fn export_report(results: &str, archive: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let results: ExecutionData = serde_json::from_str(&std::fs::read_to_string(results)?)?;
+    Report::new(results)
+        .with_diagnostics(qcs::diagnostics::get_report().await)
+        .export(archive)?;
+    Ok(())
 }
@@ -0,0 +1,141 @@
+//! Conventions for writing the bits of a register as a bitstring.
+//!
+//! Toolchains disagree about which end of a bitstring holds index 0 of a register. Every helper
+//! in this crate that produces bitstrings takes a [`BitOrder`], and the utilities here convert
+//! between the conventions.
+
+use serde::{Deserialize, Serialize};
+
+/// The order in which the bits of a register are written in a bitstring.
+///
+/// The QCS convention, and the default, is [`BitOrder::AscendingIndex`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BitOrder {
+    /// Index 0 is the leftmost character, e.g. `ro[0]=1, ro[1]=0` is written `"10"`. This matches
+    /// the order of each row of a [`RegisterMatrix`](crate::RegisterMatrix).
+    #[default]
+    AscendingIndex,
+    /// Index 0 is the rightmost character, e.g. `ro[0]=1, ro[1]=0` is written `"01"`. This is the
+    /// convention used by Qiskit.
+    DescendingIndex,
+}
+
+impl BitOrder {
+    /// Write `bits`, given in index order, as a bitstring in this order.
+    #[must_use]
+    pub fn to_bitstring(self, bits: &[bool]) -> String {
+        let bitstring = bits.iter().map(|bit| if *bit { '1' } else { '0' });
+        match self {
+            Self::AscendingIndex => bitstring.collect(),
+            Self::DescendingIndex => bitstring.rev().collect(),
+        }
+    }
+
+    /// Read a bitstring written in this order, returning its bits in index order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bitstring contains a character other than `0` or `1`.
+    pub fn parse_bitstring(self, bitstring: &str) -> Result<Vec<bool>, InvalidBitstring> {
+        let mut bits = bitstring
+            .chars()
+            .map(|c| match c {
+                '0' => Ok(false),
+                '1' => Ok(true),
+                _ => Err(InvalidBitstring(bitstring.to_string())),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if self == Self::DescendingIndex {
+            bits.reverse();
+        }
+        Ok(bits)
+    }
+
+    /// Rewrite a bitstring in this order into the `target` order.
+    #[must_use]
+    pub fn convert(self, bitstring: &str, target: Self) -> String {
+        if self == target {
+            bitstring.to_string()
+        } else {
+            bitstring.chars().rev().collect()
+        }
+    }
+
+    /// The position in a bitstring of `width` bits, written in this order, that holds the bit at
+    /// register `index`. Returns `None` if `index` is out of range.
+    #[must_use]
+    pub fn position_of_index(self, index: usize, width: usize) -> Option<usize> {
+        if index >= width {
+            return None;
+        }
+        match self {
+            Self::AscendingIndex => Some(index),
+            Self::DescendingIndex => Some(width - 1 - index),
+        }
+    }
+}
+
+/// A bitstring contained a character other than `0` or `1`.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("{0:?} is not a bitstring of 0s and 1s")]
+pub struct InvalidBitstring(pub String);
+
+#[cfg(test)]
+mod describe_bit_order {
+    use super::{BitOrder, InvalidBitstring};
+
+    // ro[0] = 1, ro[1] = 1, ro[2] = 0, ro[3] = 0
+    const BITS: [bool; 4] = [true, true, false, false];
+
+    #[test]
+    fn it_defaults_to_ascending_index() {
+        assert_eq!(BitOrder::default(), BitOrder::AscendingIndex);
+    }
+
+    #[test]
+    fn it_writes_index_zero_leftmost_when_ascending() {
+        assert_eq!(BitOrder::AscendingIndex.to_bitstring(&BITS), "1100");
+        assert_eq!(BitOrder::AscendingIndex.position_of_index(0, 4), Some(0));
+    }
+
+    #[test]
+    fn it_writes_index_zero_rightmost_when_descending() {
+        assert_eq!(BitOrder::DescendingIndex.to_bitstring(&BITS), "0011");
+        assert_eq!(BitOrder::DescendingIndex.position_of_index(0, 4), Some(3));
+        assert_eq!(BitOrder::DescendingIndex.position_of_index(4, 4), None);
+    }
+
+    #[test]
+    fn it_round_trips_bitstrings() {
+        for order in [BitOrder::AscendingIndex, BitOrder::DescendingIndex] {
+            assert_eq!(
+                order.parse_bitstring(&order.to_bitstring(&BITS)),
+                Ok(BITS.to_vec())
+            );
+        }
+    }
+
+    #[test]
+    fn it_converts_between_orders() {
+        assert_eq!(
+            BitOrder::AscendingIndex.convert("1100", BitOrder::DescendingIndex),
+            "0011"
+        );
+        assert_eq!(
+            BitOrder::DescendingIndex.convert("0011", BitOrder::AscendingIndex),
+            "1100"
+        );
+        assert_eq!(
+            BitOrder::AscendingIndex.convert("1100", BitOrder::AscendingIndex),
+            "1100"
+        );
+    }
+
+    #[test]
+    fn it_rejects_invalid_bitstrings() {
+        assert_eq!(
+            BitOrder::AscendingIndex.parse_bitstring("10x"),
+            Err(InvalidBitstring("10x".to_string()))
+        );
+    }
+}
@@ -0,0 +1,227 @@
+//! A blocking counterpart of [`crate::Executable`].
+
+#[cfg(feature = "qpu")]
+use std::borrow::Cow;
+use std::ops::{Deref, DerefMut};
+
+use indexmap::IndexMap;
+
+#[cfg(feature = "qpu")]
+use crate::qpu::{api::ExecutionOptions, translation::TranslationOptions};
+use crate::{qvm, Error, ExecutionResult};
+#[cfg(feature = "qpu")]
+use crate::{CompilationArtifacts, JobHandle};
+
+use super::block_on;
+
+/// An [`Executable`](crate::Executable) whose executions block until they're done, see the
+/// [module documentation](super).
+///
+/// Configure the program by building a [`crate::Executable`] and converting it with
+/// [`From`], or with the methods of [`crate::Executable`] which take `&mut self`, which this
+/// dereferences to.
+///
+/// ```no_run
+/// use std::num::NonZeroU16;
+///
+/// use qcs::{blocking, qvm};
+///
+/// let client = qvm::http::HttpClient::new("http://localhost:5000".to_string());
+/// let mut exe = blocking::Executable::from(
+///     qcs::Executable::from_quil("DECLARE theta REAL\nDECLARE ro BIT\nRX(theta) 0\nMEASURE 0 ro")
+///         .with_shots(NonZeroU16::new(10).unwrap()),
+/// );
+/// exe.with_parameter("theta", 0, 1.5);
+/// let data = exe.execute_on_qvm(&client).expect("the QVM should run the program");
+/// ```
+#[derive(Clone)]
+#[allow(missing_debug_implementations)]
+pub struct Executable<'executable, 'execution> {
+    inner: crate::Executable<'executable, 'execution>,
+}
+
+impl<'executable, 'execution> Executable<'executable, 'execution> {
+    /// See [`crate::Executable::from_quil`].
+    #[must_use]
+    pub fn from_quil<Quil: Into<std::sync::Arc<str>>>(quil: Quil) -> Self {
+        crate::Executable::from_quil(quil).into()
+    }
+
+    /// See [`crate::Executable::from_program`].
+    #[must_use]
+    pub fn from_program(program: quil_rs::Program) -> Self {
+        crate::Executable::from_program(program).into()
+    }
+
+    /// The async [`crate::Executable`] this wraps.
+    #[must_use]
+    pub fn into_inner(self) -> crate::Executable<'executable, 'execution> {
+        self.inner
+    }
+
+    /// See [`crate::Executable::execute_on_qvm`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Error`].
+    pub fn execute_on_qvm<V: qvm::Client + ?Sized>(&mut self, client: &V) -> ExecutionResult {
+        block_on(self.inner.execute_on_qvm(client))
+    }
+
+    /// See [`crate::Executable::execute_and_measure_on_qvm`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Error`].
+    pub fn execute_and_measure_on_qvm<V: qvm::Client + ?Sized>(
+        &mut self,
+        qubits: &[u64],
+        client: &V,
+    ) -> ExecutionResult {
+        block_on(self.inner.execute_and_measure_on_qvm(qubits, client))
+    }
+
+    /// See [`crate::Executable::wavefunction_on_qvm`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Error`].
+    pub fn wavefunction_on_qvm<V: qvm::Client + ?Sized>(&mut self, client: &V) -> ExecutionResult {
+        block_on(self.inner.wavefunction_on_qvm(client))
+    }
+
+    /// See [`crate::Executable::expectation_on_qvm`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Error`].
+    pub fn expectation_on_qvm<V, S>(
+        &mut self,
+        pauli_operators: &[S],
+        client: &V,
+    ) -> Result<IndexMap<String, f64>, Error>
+    where
+        V: qvm::Client + ?Sized,
+        S: AsRef<str>,
+    {
+        block_on(self.inner.expectation_on_qvm(pauli_operators, client))
+    }
+}
+
+#[cfg(feature = "qpu")]
+impl<'execution> Executable<'_, 'execution> {
+    /// See [`crate::Executable::execute_on_qpu`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Error`].
+    pub fn execute_on_qpu<S>(
+        &mut self,
+        quantum_processor_id: S,
+        translation_options: Option<TranslationOptions>,
+        execution_options: &ExecutionOptions,
+    ) -> ExecutionResult
+    where
+        S: Into<Cow<'execution, str>>,
+    {
+        block_on(self.inner.execute_on_qpu(
+            quantum_processor_id,
+            translation_options,
+            execution_options,
+        ))
+    }
+
+    /// See [`crate::Executable::submit_to_qpu`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Error`].
+    pub fn submit_to_qpu<S>(
+        &mut self,
+        quantum_processor_id: S,
+        translation_options: Option<TranslationOptions>,
+        execution_options: &ExecutionOptions,
+    ) -> Result<JobHandle<'execution>, Error>
+    where
+        S: Into<Cow<'execution, str>>,
+    {
+        block_on(self.inner.submit_to_qpu(
+            quantum_processor_id,
+            translation_options,
+            execution_options,
+        ))
+    }
+
+    /// See [`crate::Executable::retrieve_results`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Error`].
+    pub fn retrieve_results(&mut self, job_handle: JobHandle<'execution>) -> ExecutionResult {
+        block_on(self.inner.retrieve_results(job_handle))
+    }
+
+    /// See [`crate::Executable::cancel_qpu_job`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Error`].
+    pub fn cancel_qpu_job(&mut self, job_handle: JobHandle<'execution>) -> Result<(), Error> {
+        block_on(self.inner.cancel_qpu_job(job_handle))
+    }
+
+    /// See [`crate::Executable::compile_only`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Error`].
+    pub fn compile_only<S>(
+        &mut self,
+        quantum_processor_id: S,
+    ) -> Result<CompilationArtifacts, Error>
+    where
+        S: Into<Cow<'execution, str>>,
+    {
+        block_on(self.inner.compile_only(quantum_processor_id))
+    }
+}
+
+impl<'executable, 'execution> From<crate::Executable<'executable, 'execution>>
+    for Executable<'executable, 'execution>
+{
+    fn from(inner: crate::Executable<'executable, 'execution>) -> Self {
+        Self { inner }
+    }
+}
+
+impl<'executable, 'execution> Deref for Executable<'executable, 'execution> {
+    type Target = crate::Executable<'executable, 'execution>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl DerefMut for Executable<'_, '_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
+
+#[cfg(all(test, feature = "qvm-http"))]
+mod describe_blocking_executable {
+    use assert2::let_assert;
+
+    use super::Executable;
+    use crate::{qvm, Error, Service};
+
+    #[test]
+    fn it_executes_on_a_qvm_without_a_runtime() {
+        // The QVM at this address is never reached, but the request is made on the shared runtime.
+        let client = qvm::http::HttpClient::new("http://127.0.0.1:1".to_string());
+        let mut exe = Executable::from_quil("DECLARE ro BIT\nX 0\nMEASURE 0 ro");
+        assert!(exe.shot_memory_reset());
+
+        let_assert!(Err(Error::Connection(Service::Qvm)) = exe.execute_on_qvm(&client));
+    }
+}
@@ -0,0 +1,68 @@
+//! A blocking API for synchronous programs, which don't otherwise need an async runtime. It mirrors
+//! [`Executable`](crate::Executable), [`qvm::run`](crate::qvm::run), and [`qpu::api`](crate::qpu::api),
+//! running each call to completion before returning, like `reqwest::blocking`.
+//!
+//! Every call is run on a multi-threaded Tokio runtime which is started the first time it's needed
+//! and shared by the whole process. Use [`block_on`] to run anything else in this crate on it.
+//!
+//! Requires the `blocking` feature.
+//!
+//! # Panics
+//!
+//! Every function in this module panics if it's called from within an async runtime, as blocking
+//! there could deadlock it. Use the async API instead.
+//!
+//! ```no_run
+//! use qcs::{blocking, qvm};
+//!
+//! let client = qvm::http::HttpClient::new("http://localhost:5000".to_string());
+//! let mut exe = blocking::Executable::from_quil("DECLARE ro BIT\nH 0\nMEASURE 0 ro");
+//! let data = exe.execute_on_qvm(&client).expect("the QVM should run the program");
+//! println!("{:?}", data.result_data);
+//! ```
+
+use std::future::Future;
+
+use lazy_static::lazy_static;
+use tokio::runtime::{Builder, Runtime};
+
+mod executable;
+#[cfg(feature = "qpu")]
+pub mod qpu;
+pub mod qvm;
+
+pub use executable::Executable;
+
+lazy_static! {
+    static ref RUNTIME: Runtime = Builder::new_multi_thread()
+        .thread_name("qcs-blocking")
+        .enable_all()
+        .build()
+        .expect("the blocking runtime should start");
+}
+
+/// Run `future` to completion on the runtime shared by this module, blocking the current thread
+/// until it's done.
+///
+/// # Panics
+///
+/// If called from within an async runtime, or if the runtime couldn't be started.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    RUNTIME.block_on(future)
+}
+
+#[cfg(test)]
+mod describe_block_on {
+    use super::block_on;
+
+    #[test]
+    fn it_runs_a_future_to_completion() {
+        assert_eq!(block_on(async { 1 + 1 }), 2);
+    }
+
+    #[tokio::test]
+    #[should_panic]
+    async fn it_refuses_to_block_an_async_runtime() {
+        block_on(async {});
+    }
+}
@@ -0,0 +1,160 @@
+//! Blocking counterparts of the functions in [`crate::qpu::api`].
+
+use std::collections::HashMap;
+
+use qcs_api_client_grpc::models::controller::{
+    ControllerJobExecutionResult, EncryptedControllerJob,
+};
+
+use crate::client::Qcs;
+use crate::qpu::api::{ExecutionOptions, JobId, JobStatus, QpuApiError};
+use crate::qpu::patch_values::PatchValues;
+use crate::qpu::QpuResultData;
+
+use crate::blocking::block_on;
+
+/// See [`crate::qpu::api::submit`].
+///
+/// # Errors
+///
+/// See [`crate::qpu::api::submit`].
+pub fn submit<P>(
+    quantum_processor_id: Option<&str>,
+    program: EncryptedControllerJob,
+    patch_values: &P,
+    client: &Qcs,
+    execution_options: &ExecutionOptions,
+) -> Result<JobId, QpuApiError>
+where
+    P: PatchValues + ?Sized,
+{
+    block_on(crate::qpu::api::submit(
+        quantum_processor_id,
+        program,
+        patch_values,
+        client,
+        execution_options,
+    ))
+}
+
+/// See [`crate::qpu::api::submit_with_parameter_batch`].
+///
+/// # Errors
+///
+/// See [`crate::qpu::api::submit_with_parameter_batch`].
+pub fn submit_with_parameter_batch<'a, I, P>(
+    quantum_processor_id: Option<&str>,
+    program: EncryptedControllerJob,
+    patch_values: I,
+    client: &Qcs,
+    execution_options: &ExecutionOptions,
+) -> Result<Vec<JobId>, QpuApiError>
+where
+    I: IntoIterator<Item = &'a P>,
+    P: PatchValues + ?Sized + 'a,
+{
+    block_on(crate::qpu::api::submit_with_parameter_batch(
+        quantum_processor_id,
+        program,
+        patch_values,
+        client,
+        execution_options,
+    ))
+}
+
+/// See [`crate::qpu::api::cancel_jobs`].
+///
+/// # Errors
+///
+/// See [`crate::qpu::api::cancel_jobs`].
+pub fn cancel_jobs(
+    job_ids: Vec<JobId>,
+    quantum_processor_id: Option<&str>,
+    client: &Qcs,
+    execution_options: &ExecutionOptions,
+) -> Result<(), QpuApiError> {
+    block_on(crate::qpu::api::cancel_jobs(
+        job_ids,
+        quantum_processor_id,
+        client,
+        execution_options,
+    ))
+}
+
+/// See [`crate::qpu::api::cancel_job`].
+///
+/// # Errors
+///
+/// See [`crate::qpu::api::cancel_job`].
+pub fn cancel_job(
+    job_id: JobId,
+    quantum_processor_id: Option<&str>,
+    client: &Qcs,
+    execution_options: &ExecutionOptions,
+) -> Result<(), QpuApiError> {
+    block_on(crate::qpu::api::cancel_job(
+        job_id,
+        quantum_processor_id,
+        client,
+        execution_options,
+    ))
+}
+
+/// See [`crate::qpu::api::get_job_status`].
+///
+/// # Errors
+///
+/// See [`crate::qpu::api::get_job_status`].
+pub fn get_job_status(
+    job_id: JobId,
+    quantum_processor_id: Option<&str>,
+    client: &Qcs,
+    execution_options: &ExecutionOptions,
+) -> Result<JobStatus, QpuApiError> {
+    block_on(crate::qpu::api::get_job_status(
+        job_id,
+        quantum_processor_id,
+        client,
+        execution_options,
+    ))
+}
+
+/// See [`crate::qpu::api::retrieve_results`].
+///
+/// # Errors
+///
+/// See [`crate::qpu::api::retrieve_results`].
+pub fn retrieve_results(
+    job_id: JobId,
+    quantum_processor_id: Option<&str>,
+    client: &Qcs,
+    execution_options: &ExecutionOptions,
+) -> Result<ControllerJobExecutionResult, QpuApiError> {
+    block_on(crate::qpu::api::retrieve_results(
+        job_id,
+        quantum_processor_id,
+        client,
+        execution_options,
+    ))
+}
+
+/// See [`crate::qpu::api::retrieve_results_by_id`].
+///
+/// # Errors
+///
+/// See [`crate::qpu::api::retrieve_results_by_id`].
+pub fn retrieve_results_by_id(
+    job_id: &str,
+    quantum_processor_id: Option<&str>,
+    client: &Qcs,
+    execution_options: &ExecutionOptions,
+    readout_map: &HashMap<String, String>,
+) -> Result<QpuResultData, QpuApiError> {
+    block_on(crate::qpu::api::retrieve_results_by_id(
+        job_id,
+        quantum_processor_id,
+        client,
+        execution_options,
+        readout_map,
+    ))
+}
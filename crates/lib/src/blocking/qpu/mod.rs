@@ -0,0 +1,3 @@
+//! Blocking counterparts of the functions in [`crate::qpu`] which call QCS or a QPU.
+
+pub mod api;
@@ -0,0 +1,156 @@
+//! Blocking counterparts of the functions in [`crate::qvm`] which run programs.
+
+use std::collections::HashMap;
+use std::num::NonZeroU16;
+
+use quil_rs::Program;
+
+use crate::executable::Parameters;
+use crate::qvm::{
+    http::AddressRequest, noise::NoiseModel, Client, Error, QvmOptions, QvmResultData, Wavefunction,
+};
+
+use super::block_on;
+
+/// See [`crate::qvm::run`].
+///
+/// # Errors
+///
+/// See [`Error`].
+#[allow(clippy::too_many_arguments)]
+pub fn run<C: Client + Send + Sync + ?Sized>(
+    quil: &str,
+    shots: NonZeroU16,
+    addresses: HashMap<String, AddressRequest>,
+    params: &Parameters,
+    measurement_noise: Option<(f64, f64, f64)>,
+    gate_noise: Option<(f64, f64, f64)>,
+    rng_seed: Option<i64>,
+    client: &C,
+    options: &QvmOptions,
+) -> Result<QvmResultData, Error> {
+    block_on(crate::qvm::run(
+        quil,
+        shots,
+        addresses,
+        params,
+        measurement_noise,
+        gate_noise,
+        rng_seed,
+        client,
+        options,
+    ))
+}
+
+/// See [`crate::qvm::run_program`].
+///
+/// # Errors
+///
+/// See [`Error`].
+#[allow(clippy::too_many_arguments)]
+pub fn run_program<C: Client + ?Sized>(
+    program: &Program,
+    shots: NonZeroU16,
+    addresses: HashMap<String, AddressRequest>,
+    params: &Parameters,
+    measurement_noise: Option<(f64, f64, f64)>,
+    gate_noise: Option<(f64, f64, f64)>,
+    rng_seed: Option<i64>,
+    client: &C,
+    options: &QvmOptions,
+) -> Result<QvmResultData, Error> {
+    block_on(crate::qvm::run_program(
+        program,
+        shots,
+        addresses,
+        params,
+        measurement_noise,
+        gate_noise,
+        rng_seed,
+        client,
+        options,
+    ))
+}
+
+/// See [`crate::qvm::run_with_noise_model`].
+///
+/// # Errors
+///
+/// See [`Error`].
+#[allow(clippy::too_many_arguments)]
+pub fn run_with_noise_model<C: Client + Send + Sync + ?Sized>(
+    quil: &str,
+    shots: NonZeroU16,
+    addresses: HashMap<String, AddressRequest>,
+    params: &Parameters,
+    noise_model: &NoiseModel,
+    rng_seed: Option<i64>,
+    client: &C,
+    options: &QvmOptions,
+) -> Result<QvmResultData, Error> {
+    block_on(crate::qvm::run_with_noise_model(
+        quil,
+        shots,
+        addresses,
+        params,
+        noise_model,
+        rng_seed,
+        client,
+        options,
+    ))
+}
+
+/// See [`crate::qvm::run_and_measure_program`].
+///
+/// # Errors
+///
+/// See [`Error`].
+#[allow(clippy::too_many_arguments)]
+pub fn run_and_measure_program<C: Client + ?Sized>(
+    program: &Program,
+    shots: NonZeroU16,
+    qubits: &[u64],
+    params: &Parameters,
+    measurement_noise: Option<(f64, f64, f64)>,
+    gate_noise: Option<(f64, f64, f64)>,
+    rng_seed: Option<i64>,
+    client: &C,
+    options: &QvmOptions,
+) -> Result<QvmResultData, Error> {
+    block_on(crate::qvm::run_and_measure_program(
+        program,
+        shots,
+        qubits,
+        params,
+        measurement_noise,
+        gate_noise,
+        rng_seed,
+        client,
+        options,
+    ))
+}
+
+/// See [`crate::qvm::get_program_wavefunction`].
+///
+/// # Errors
+///
+/// See [`Error`].
+pub fn get_program_wavefunction<C: Client + ?Sized>(
+    program: &Program,
+    params: &Parameters,
+    measurement_noise: Option<(f64, f64, f64)>,
+    gate_noise: Option<(f64, f64, f64)>,
+    rng_seed: Option<i64>,
+    client: &C,
+    options: &QvmOptions,
+) -> Result<Wavefunction, Error> {
+    block_on(crate::qvm::get_program_wavefunction(
+        program,
+        params,
+        measurement_noise,
+        gate_noise,
+        rng_seed,
+        client,
+        options,
+    ))
+}
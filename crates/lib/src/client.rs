@@ -2,35 +2,56 @@
 //! desired API (e.g. `gRPC` or `OpenAPI`) and will properly
 //! initialize those clients (e.g. with authentication metadata).
 
-use std::time::Duration;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::{fmt, time::Duration};
 
-use qcs_api_client_common::configuration::{ClientConfiguration, TokenError};
+use qcs_api_client_common::configuration::{ClientConfiguration, TokenError, TokenRefresher};
+#[cfg(feature = "tracing-config")]
+use qcs_api_client_common::tracing_configuration::TracingConfiguration;
 #[cfg(feature = "grpc-web")]
 use qcs_api_client_grpc::tonic::{wrap_channel_with_grpc_web, GrpcWebWrapperLayerService};
+#[cfg(feature = "qpu")]
 use qcs_api_client_grpc::{
     services::translation::translation_client::TranslationClient,
     tonic::{
-        get_channel, parse_uri, wrap_channel_with, wrap_channel_with_retry, RefreshService,
-        RetryService,
+        get_channel_with_endpoint, get_endpoint, parse_uri, wrap_channel_with_retry,
+        wrap_channel_with_token_refresher, ChannelError, RefreshService, RetryService,
     },
 };
+#[cfg(feature = "qcs-api")]
 use qcs_api_client_openapi::apis::configuration::Configuration as OpenApiConfiguration;
-use tonic::transport::Channel;
-use tonic::Status;
+#[cfg(feature = "qpu")]
+use tonic::transport::{Channel, Uri};
+#[cfg(feature = "qpu")]
+use tonic::{Code, Status};
 
+use crate::metrics::{self, CacheStats};
+use crate::random::random_fraction;
+
+mod connection;
+pub mod profiles;
+
+pub(crate) use connection::{secrets_path, settings_path, LoadedConnectionSettings};
+pub use connection::{ConnectionSettings, ConnectionSettingsError};
 pub use qcs_api_client_common::configuration::LoadError;
+#[cfg(feature = "qpu")]
 pub use qcs_api_client_grpc::tonic::Error as GrpcError;
+#[cfg(feature = "qcs-api")]
 pub use qcs_api_client_openapi::apis::Error as OpenApiError;
 
+#[cfg(feature = "qpu")]
 const DEFAULT_MAX_MESSAGE_ENCODING_SIZE: usize = 50 * 1024 * 1024;
+#[cfg(feature = "qpu")]
 const DEFAULT_MAX_MESSAGE_DECODING_SIZE: usize = 50 * 1024 * 1024;
 
 /// A type alias for the underlying gRPC connection used by all gRPC clients within this library.
 /// It is public so that users can create gRPC clients with different APIs using a "raw" connection
 /// initialized by this library. This ensures that the exact Tonic version used for such clients
 /// matches what this library uses.
-#[cfg(not(feature = "grpc-web"))]
-pub type GrpcConnection = RetryService<RefreshService<Channel, ClientConfiguration>>;
+#[cfg(all(feature = "qpu", not(feature = "grpc-web")))]
+pub type GrpcConnection = RetryService<RefreshService<Channel, SharedTokenRefresher>>;
 
 /// A type alias for the underlying gRPC connection used by all gRPC clients within this library.
 /// It is public so that users can create gRPC clients with different APIs using a "raw" connection
@@ -38,23 +59,40 @@ pub type GrpcConnection = RetryService<RefreshService<Channel, ClientConfigurati
 /// matches what this library uses.
 #[cfg(feature = "grpc-web")]
 pub type GrpcConnection =
-    GrpcWebWrapperLayerService<RetryService<RefreshService<Channel, ClientConfiguration>>>;
+    GrpcWebWrapperLayerService<RetryService<RefreshService<Channel, SharedTokenRefresher>>>;
 
 /// TODO: make configurable at the client level.
 /// <https://github.com/rigetti/qcs-sdk-rust/issues/239>
+#[cfg(feature = "qcs-api")]
 pub(crate) static DEFAULT_HTTP_API_TIMEOUT: Duration = Duration::from_secs(10);
 
+/// The number of times [`Qcs::refresh_token`] retries a failed refresh.
+const TOKEN_REFRESH_RETRIES: u32 = 3;
+
+/// The delay before [`Qcs::refresh_token`] first retries a failed refresh. It doubles after each
+/// retry.
+const TOKEN_REFRESH_INITIAL_DELAY: Duration = Duration::from_millis(250);
+
+/// The environment variable which, if set to `1`, `true`, or `yes`, puts every new [`Qcs`] client
+/// into read-only mode. See [`Qcs::with_read_only`].
+pub const READ_ONLY_VAR: &str = "QCS_READ_ONLY";
+
 /// A client providing helper functionality for accessing QCS APIs
 #[derive(Debug, Clone)]
 pub struct Qcs {
     config: ClientConfiguration,
+    client_application: Option<ClientApplication>,
+    read_only: bool,
+    token_refresh: Arc<SingleFlight>,
+    connection: LoadedConnectionSettings,
 }
 
 impl Qcs {
-    /// Create a [`Qcs`] and initialize it with the user's default [`ClientConfiguration`]
+    /// Create a [`Qcs`] and initialize it with the user's default [`ClientConfiguration`] and
+    /// [`ConnectionSettings`].
     #[must_use]
     pub fn load() -> Self {
-        if let Ok(config) = ClientConfiguration::load_default() {
+        let client = if let Ok(config) = ClientConfiguration::load_default() {
             Self::with_config(config)
         } else {
             #[cfg(feature = "tracing")]
@@ -62,23 +100,112 @@ impl Qcs {
                 "No QCS client configuration found. QPU data and QCS will be inaccessible and only generic QVMs will be available for execution"
             );
             Self::default()
-        }
+        };
+        client.with_settings_file_connection(None)
     }
 
     /// Create a [`Qcs`] and initialize it with the given [`ClientConfiguration`]
     #[must_use]
     pub fn with_config(config: ClientConfiguration) -> Self {
-        Self { config }
+        Self {
+            config,
+            client_application: None,
+            read_only: read_only_from_env(),
+            token_refresh: Arc::default(),
+            connection: LoadedConnectionSettings::default(),
+        }
+    }
+
+    /// Connect through a proxy, or with custom certificates, as described by `settings`. See
+    /// [`ConnectionSettings`] for which connections each setting applies to.
+    ///
+    /// Clients created with [`Qcs::load`] or [`Qcs::with_profile`] use the settings in the
+    /// profile's `connection` table, if it has one; this replaces them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the certificate or key files can't be read or used, or the proxy URL
+    /// is invalid.
+    pub fn with_connection_settings(
+        mut self,
+        settings: ConnectionSettings,
+    ) -> Result<Self, ConnectionSettingsError> {
+        self.connection = settings.load()?;
+        Ok(self)
+    }
+
+    /// The settings used for this client's connections. See [`Qcs::with_connection_settings`].
+    #[must_use]
+    pub fn connection_settings(&self) -> &ConnectionSettings {
+        self.connection.settings()
+    }
+
+    #[cfg(any(feature = "qvm-http", feature = "qcs-api"))]
+    pub(crate) fn connection(&self) -> &LoadedConnectionSettings {
+        &self.connection
+    }
+
+    /// Use the connection settings of `profile` in the settings file, unless they can't be read
+    /// or used, in which case the default settings are kept.
+    fn with_settings_file_connection(self, profile: Option<&str>) -> Self {
+        let settings = ConnectionSettings::from_settings_file(profile);
+        match settings.and_then(|settings| self.clone().with_connection_settings(settings)) {
+            Ok(client) => client,
+            #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+            Err(error) => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(%error, "ignoring the connection settings in the QCS settings file");
+                self
+            }
+        }
+    }
+
+    /// Put this client into, or take it out of, read-only mode.
+    ///
+    /// A read-only client refuses to submit jobs to a QPU, failing with
+    /// [`QpuApiError::SubmissionsDisabled`](crate::qpu::api::QpuApiError::SubmissionsDisabled)
+    /// before any request is made, so that submissions can be frozen (e.g. to control costs) while
+    /// results of jobs already submitted can still be retrieved. Compilation, translation,
+    /// retrieval, cancellation, and the QVM are unaffected.
+    ///
+    /// Clients start in read-only mode if the [`READ_ONLY_VAR`] environment variable is set when
+    /// they are created.
+    #[must_use]
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Whether this client is in read-only mode. See [`Qcs::with_read_only`].
+    #[must_use]
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Identify the application using this client in requests to QCS. See [`ClientApplication`].
+    #[must_use]
+    pub fn with_client_application(mut self, client_application: ClientApplication) -> Self {
+        self.client_application = Some(client_application);
+        self
+    }
+
+    /// The application using this client, if one was set with
+    /// [`Qcs::with_client_application`].
+    #[must_use]
+    pub fn client_application(&self) -> Option<&ClientApplication> {
+        self.client_application.as_ref()
     }
 
-    /// Create a [`Qcs`] and initialized with the given `profile`.
+    /// Create a [`Qcs`] and initialized with the given `profile`. Profiles can be created with
+    /// [`profiles::save_profile`].
     ///
     /// # Errors
     ///
     /// A [`LoadError`] will be returned if QCS credentials are
     /// not correctly configured or the given profile is not defined.
     pub fn with_profile(profile: String) -> Result<Qcs, LoadError> {
-        ClientConfiguration::load_profile(profile).map(Self::with_config)
+        let client = ClientConfiguration::load_profile(profile.clone()).map(Self::with_config)?;
+        Ok(client.with_settings_file_connection(Some(&profile)))
     }
 
     /// Return a reference to the underlying [`ClientConfiguration`] with all settings parsed and resolved from configuration sources.
@@ -87,24 +214,149 @@ impl Qcs {
         &self.config
     }
 
+    /// Refresh the access token, returning the new one.
+    ///
+    /// Every clone of a [`Qcs`] shares the same tokens, so when they expire, many tasks may try to
+    /// refresh them at once. Only one refresh runs at a time: a task which asks for a refresh
+    /// while another is running waits for it to finish and then uses its token, instead of
+    /// refreshing again. This keeps load off the auth server and avoids racing to use a refresh
+    /// token which the server rotates.
+    ///
+    /// A failed refresh is retried a few times after a randomized delay, so that clients which
+    /// failed together don't all retry together.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`TokenError`] if the token can't be refreshed, e.g. because no refresh token is
+    /// configured, even after retrying.
+    pub async fn refresh_token(&self) -> Result<String, TokenError> {
+        self.token_refresher().refresh_access_token().await
+    }
+
+    /// The [`SharedTokenRefresher`] which refreshes this client's tokens, for gRPC connections
+    /// and for requests made through [`Qcs::with_refresh`].
+    pub(crate) fn token_refresher(&self) -> SharedTokenRefresher {
+        SharedTokenRefresher {
+            config: self.config.clone(),
+            single_flight: self.token_refresh.clone(),
+        }
+    }
+
+    /// The hit and miss counters of the SDK's caches. These are shared by every client in the
+    /// process; see [`crate::metrics`].
+    #[must_use]
+    pub fn cache_stats(&self) -> CacheStats {
+        metrics::cache_stats()
+    }
+
+    /// Make an `OpenAPI` request with `request`, giving it this client's configuration, and if QCS
+    /// rejects it as unauthenticated, refresh the access token with [`Qcs::refresh_token`] and
+    /// make it once more.
+    ///
+    /// Access tokens are refreshed before a request if they are known to have expired, but QCS
+    /// may still reject one which it considers expired or revoked, e.g. due to clock skew. If the
+    /// refresh fails, the original error is returned, so that it can be reported as an
+    /// authentication failure. gRPC requests are retried the same way by the [`RefreshService`]
+    /// which wraps every [`GrpcConnection`].
+    ///
+    /// An access token which has expired is refreshed before the request, through the same
+    /// single-flight refresh as [`Qcs::refresh_token`], rather than by each request on its own.
+    #[cfg(feature = "qcs-api")]
+    pub(crate) async fn with_refresh<T, E, F, Fut>(
+        &self,
+        mut request: F,
+    ) -> Result<T, OpenApiError<E>>
+    where
+        F: FnMut(OpenApiConfiguration) -> Fut,
+        Fut: Future<Output = Result<T, OpenApiError<E>>>,
+    {
+        // If the token can't be validated, the request fails with the same error on its own.
+        let _ = self.token_refresher().validated_access_token().await;
+        match request(self.get_openapi_client()).await {
+            Err(error) if is_unauthenticated_response(&error) => {
+                #[cfg(feature = "tracing")]
+                tracing::debug!("QCS rejected the access token, refreshing it");
+                match self.refresh_token().await {
+                    Ok(_) => request(self.get_openapi_client()).await,
+                    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+                    Err(refresh_error) => {
+                        #[cfg(feature = "tracing")]
+                        tracing::warn!(%refresh_error, "failed to refresh the access token");
+                        Err(error)
+                    }
+                }
+            }
+            result => result,
+        }
+    }
+
+    #[cfg(feature = "qcs-api")]
     pub(crate) fn get_openapi_client(&self) -> OpenApiConfiguration {
-        OpenApiConfiguration::with_qcs_config(self.get_config().clone())
+        let mut configuration = OpenApiConfiguration::with_qcs_config(self.get_config().clone());
+        if let Some(application) = &self.client_application {
+            configuration.user_agent =
+                Some(application.user_agent_with_prefix(configuration.user_agent.as_deref()));
+        }
+        if let Some(client) = self.connection.http_client() {
+            configuration.client = client.clone().into();
+        }
+        configuration
     }
 
+    /// The `User-Agent` to use for gRPC requests, and for HTTP requests made by this library
+    /// outside of the QCS API clients, e.g. to a QVM server.
+    #[cfg(any(feature = "qpu", feature = "qvm-http"))]
+    pub(crate) fn user_agent(&self) -> String {
+        let sdk = concat!("qcs-sdk-rust/", env!("CARGO_PKG_VERSION"));
+        match &self.client_application {
+            Some(application) => application.user_agent_with_prefix(Some(sdk)),
+            None => sdk.to_string(),
+        }
+    }
+
+    /// A channel to `uri` whose requests identify this client's [`ClientApplication`], if any, in
+    /// their `User-Agent`, and which uses the certificates of this client's
+    /// [`ConnectionSettings`], if any are configured. It is proxied like any other gRPC channel.
+    #[cfg(feature = "qpu")]
+    pub(crate) fn grpc_channel(
+        &self,
+        uri: Uri,
+        timeout: Option<Duration>,
+    ) -> Result<Channel, ChannelError> {
+        let mut endpoint = get_endpoint(uri);
+        if self.client_application.is_some() {
+            endpoint = endpoint
+                .user_agent(self.user_agent())
+                .expect("client application is validated to be a valid header value");
+        }
+        if let Some(timeout) = timeout {
+            endpoint = endpoint.timeout(timeout);
+        }
+        if let Some(tls_config) = self.connection.grpc_tls_config() {
+            // The configuration was checked when the settings were loaded.
+            endpoint = endpoint.clone().tls_config(tls_config).unwrap_or(endpoint);
+        }
+        get_channel_with_endpoint(&endpoint)
+    }
+
+    #[cfg(feature = "qpu")]
     pub(crate) fn get_translation_client(
         &self,
     ) -> Result<TranslationClient<GrpcConnection>, GrpcError<TokenError>> {
         self.get_translation_client_with_endpoint(self.get_config().grpc_api_url())
     }
 
+    #[cfg(feature = "qpu")]
     pub(crate) fn get_translation_client_with_endpoint(
         &self,
         translation_grpc_endpoint: &str,
     ) -> Result<TranslationClient<GrpcConnection>, GrpcError<TokenError>> {
         let uri = parse_uri(translation_grpc_endpoint)?;
-        let channel = get_channel(uri)?;
-        let service =
-            wrap_channel_with_retry(wrap_channel_with(channel, self.get_config().clone()));
+        let channel = self.grpc_channel(uri, None)?;
+        let service = wrap_channel_with_retry(wrap_channel_with_token_refresher(
+            channel,
+            self.token_refresher(),
+        ));
         #[cfg(feature = "grpc-web")]
         let service = wrap_channel_with_grpc_web(service);
         Ok(TranslationClient::new(service)
@@ -123,7 +375,241 @@ impl Default for Qcs {
     }
 }
 
+/// Whether [`READ_ONLY_VAR`] is set to a value which enables read-only mode.
+fn read_only_from_env() -> bool {
+    std::env::var(READ_ONLY_VAR).map_or(false, |value| parse_flag(&value))
+}
+
+/// Whether `value` is one of `1`, `true`, or `yes`, in any case.
+fn parse_flag(value: &str) -> bool {
+    matches!(
+        value.trim().to_ascii_lowercase().as_str(),
+        "1" | "true" | "yes"
+    )
+}
+
+/// Whether `error` is QCS rejecting a request because it wasn't authenticated.
+#[cfg(feature = "qcs-api")]
+pub(crate) fn is_unauthenticated_response<E>(error: &OpenApiError<E>) -> bool {
+    error
+        .status_code()
+        .map_or(false, |status| status.as_u16() == 401)
+}
+
+/// Refreshes the tokens of a [`Qcs`] client, and of every clone of it, one refresh at a time. See
+/// [`Qcs::refresh_token`].
+///
+/// Every [`GrpcConnection`] refreshes tokens with one of these, so that connections share the
+/// refreshes of the client they were made by, instead of each refreshing on its own.
+#[derive(Clone, Debug)]
+pub struct SharedTokenRefresher {
+    config: ClientConfiguration,
+    single_flight: Arc<SingleFlight>,
+}
+
+impl SharedTokenRefresher {
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    async fn refresh_with_retry(&self) -> Result<String, TokenError> {
+        let mut retries = 0;
+        loop {
+            match self.config.refresh().await {
+                Ok(_) => return self.config.get_bearer_access_token().await,
+                Err(error) if retries < TOKEN_REFRESH_RETRIES => {
+                    let backoff = TOKEN_REFRESH_INITIAL_DELAY * 2u32.pow(retries);
+                    let delay = backoff / 2 + (backoff / 2).mul_f64(random_fraction());
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(
+                        retry = retries + 1,
+                        ?delay,
+                        %error,
+                        "token refresh failed, retrying",
+                    );
+                    tokio::time::sleep(delay).await;
+                    retries += 1;
+                }
+                Err(error) => return Err(error),
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl TokenRefresher for SharedTokenRefresher {
+    type Error = TokenError;
+
+    /// Get the access token, refreshing it first if it has expired. This waits for any refresh
+    /// which is already running, so that an expired token is refreshed only once, however many
+    /// requests find it expired.
+    async fn validated_access_token(&self) -> Result<String, Self::Error> {
+        self.single_flight
+            .after_current(|| self.config.get_bearer_access_token())
+            .await
+    }
+
+    async fn get_access_token(&self) -> Result<Option<String>, Self::Error> {
+        self.config.get_access_token().await
+    }
+
+    async fn refresh_access_token(&self) -> Result<String, Self::Error> {
+        self.single_flight
+            .run(
+                || self.refresh_with_retry(),
+                || self.config.get_bearer_access_token(),
+            )
+            .await
+    }
+
+    #[cfg(feature = "tracing")]
+    fn base_url(&self) -> &str {
+        self.config.base_url()
+    }
+
+    #[cfg(feature = "tracing-config")]
+    fn tracing_configuration(&self) -> Option<&TracingConfiguration> {
+        self.config.tracing_configuration()
+    }
+}
+
+/// Lets only one of many concurrent callers perform an operation, such as refreshing a token,
+/// while the rest wait for it and reuse its result.
+#[derive(Debug, Default)]
+struct SingleFlight {
+    /// The number of times the operation has succeeded.
+    completed: AtomicU64,
+    lock: tokio::sync::Mutex<()>,
+}
+
+impl SingleFlight {
+    /// Call `perform`, unless the operation succeeded while waiting for another caller to finish,
+    /// in which case call `reuse` to get its result instead.
+    async fn run<T, E, P, PF, R, RF>(&self, perform: P, reuse: R) -> Result<T, E>
+    where
+        P: FnOnce() -> PF,
+        PF: Future<Output = Result<T, E>>,
+        R: FnOnce() -> RF,
+        RF: Future<Output = Result<T, E>>,
+    {
+        let completed = self.completed.load(Ordering::Acquire);
+        let _guard = self.lock.lock().await;
+        if self.completed.load(Ordering::Acquire) != completed {
+            return reuse().await;
+        }
+        let result = perform().await;
+        if result.is_ok() {
+            self.completed.fetch_add(1, Ordering::Release);
+        }
+        result
+    }
+
+    /// Call `read` once no operation is running, so that it sees the result of any which was.
+    async fn after_current<T, F, FF>(&self, read: F) -> T
+    where
+        F: FnOnce() -> FF,
+        FF: Future<Output = T>,
+    {
+        let _guard = self.lock.lock().await;
+        read().await
+    }
+}
+
+/// The name and, optionally, the version of the application using this library.
+///
+/// When set on a [`Qcs`] client, it is appended to the `User-Agent` of HTTP and gRPC requests, so
+/// that load on QCS can be attributed to the application that generated it.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ClientApplication {
+    name: String,
+    version: Option<String>,
+}
+
+impl ClientApplication {
+    /// Identify an application by name.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name is empty, or contains anything other than visible ASCII
+    /// characters or a `/`.
+    pub fn new(name: impl Into<String>) -> Result<Self, ClientApplicationError> {
+        let name = name.into();
+        if !is_valid_client_application_token(&name) {
+            return Err(ClientApplicationError::InvalidName(name));
+        }
+        Ok(Self {
+            name,
+            version: None,
+        })
+    }
+
+    /// Set the version of the application.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the version is empty, or contains anything other than visible ASCII
+    /// characters or a `/`.
+    pub fn with_version(
+        mut self,
+        version: impl Into<String>,
+    ) -> Result<Self, ClientApplicationError> {
+        let version = version.into();
+        if !is_valid_client_application_token(&version) {
+            return Err(ClientApplicationError::InvalidVersion(version));
+        }
+        self.version = Some(version);
+        Ok(self)
+    }
+
+    /// The name of the application.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The version of the application, if one was given.
+    #[must_use]
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
+    /// The identifier appended to the `User-Agent` of HTTP requests.
+    #[cfg(any(feature = "qcs-api", feature = "qvm-http"))]
+    fn user_agent_with_prefix(&self, prefix: Option<&str>) -> String {
+        match prefix {
+            Some(prefix) => format!("{prefix} {self}"),
+            None => self.to_string(),
+        }
+    }
+}
+
+/// Formats as a `User-Agent` product token, e.g. `my-app/1.2.3`.
+impl fmt::Display for ClientApplication {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.version {
+            Some(version) => write!(f, "{}/{version}", self.name),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+fn is_valid_client_application_token(token: &str) -> bool {
+    !token.is_empty() && token.chars().all(|c| c.is_ascii_graphic() && c != '/')
+}
+
+/// Errors that may occur when identifying a [`ClientApplication`].
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ClientApplicationError {
+    /// The application name can't be used in a header.
+    #[error("Invalid client application name {0:?}: must be non-empty visible ASCII without '/'")]
+    InvalidName(String),
+    /// The application version can't be used in a header.
+    #[error(
+        "Invalid client application version {0:?}: must be non-empty visible ASCII without '/'"
+    )]
+    InvalidVersion(String),
+}
+
 /// Errors that may occur while trying to use a `gRPC` client
+#[cfg(feature = "qpu")]
 #[derive(Debug, thiserror::Error)]
 pub enum GrpcClientError {
     /// Error due to failure during request
@@ -139,7 +625,18 @@ pub enum GrpcClientError {
     GrpcError(#[from] GrpcError<TokenError>),
 }
 
+#[cfg(feature = "qpu")]
+impl GrpcClientError {
+    /// Whether the request failed because it couldn't be authenticated, even after refreshing the
+    /// access token.
+    #[must_use]
+    pub fn is_unauthenticated(&self) -> bool {
+        matches!(self, Self::RequestFailed(status) if status.code() == Code::Unauthenticated)
+    }
+}
+
 /// Errors that may occur while trying to use an `OpenAPI` client
+#[cfg(feature = "qcs-api")]
 #[derive(Debug, thiserror::Error)]
 pub enum OpenApiClientError<T> {
     /// Error due to request failure
@@ -150,3 +647,223 @@ pub enum OpenApiClientError<T> {
     #[error("Response value was empty: {0}")]
     ResponseEmpty(String),
 }
+
+#[cfg(feature = "qcs-api")]
+impl<T> OpenApiClientError<T> {
+    /// Whether the request failed because it couldn't be authenticated, even after refreshing the
+    /// access token.
+    #[must_use]
+    pub fn is_unauthenticated(&self) -> bool {
+        matches!(self, Self::RequestFailed(error) if is_unauthenticated_response(error))
+    }
+}
+
+#[cfg(test)]
+mod describe_client_application {
+    use super::{ClientApplication, ClientApplicationError, Qcs};
+
+    #[test]
+    fn it_formats_as_a_product_token() {
+        let application = ClientApplication::new("my-app").unwrap();
+        assert_eq!(application.to_string(), "my-app");
+        let application = application.with_version("1.2.3").unwrap();
+        assert_eq!(application.to_string(), "my-app/1.2.3");
+    }
+
+    #[test]
+    fn it_rejects_values_that_are_not_header_safe() {
+        assert_eq!(
+            ClientApplication::new("my app"),
+            Err(ClientApplicationError::InvalidName("my app".to_string()))
+        );
+        assert_eq!(
+            ClientApplication::new("my-app").and_then(|app| app.with_version("1/2")),
+            Err(ClientApplicationError::InvalidVersion("1/2".to_string()))
+        );
+        assert!(ClientApplication::new("").is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "qcs-api")]
+    fn it_appends_to_the_openapi_user_agent() {
+        let default_user_agent = Qcs::default().get_openapi_client().user_agent;
+        let application = ClientApplication::new("my-app")
+            .and_then(|app| app.with_version("1.2.3"))
+            .unwrap();
+        let user_agent = Qcs::default()
+            .with_client_application(application)
+            .get_openapi_client()
+            .user_agent
+            .unwrap();
+        assert!(user_agent.ends_with("my-app/1.2.3"));
+        if let Some(default_user_agent) = default_user_agent {
+            assert!(user_agent.starts_with(&default_user_agent));
+        }
+    }
+
+    #[test]
+    #[cfg(any(feature = "qpu", feature = "qvm-http"))]
+    fn it_appends_to_the_sdk_user_agent() {
+        let sdk = Qcs::default().user_agent();
+        let application = ClientApplication::new("my-app").unwrap();
+        let user_agent = Qcs::default()
+            .with_client_application(application)
+            .user_agent();
+        assert_eq!(user_agent, format!("{sdk} my-app"));
+    }
+}
+
+#[cfg(test)]
+mod describe_read_only {
+    use super::{parse_flag, Qcs};
+
+    #[test]
+    fn it_is_toggled_by_the_builder() {
+        let client = Qcs::default().with_read_only(true);
+        assert!(client.is_read_only());
+        assert!(client.clone().is_read_only());
+        assert!(!client.with_read_only(false).is_read_only());
+    }
+
+    #[test]
+    fn it_parses_the_environment_flag() {
+        for value in ["1", "true", "TRUE", " yes "] {
+            assert!(parse_flag(value), "{value:?} should enable read-only mode");
+        }
+        for value in ["", "0", "false", "no", "on"] {
+            assert!(
+                !parse_flag(value),
+                "{value:?} should not enable read-only mode"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod describe_single_flight {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+    use std::time::Duration;
+
+    use super::SingleFlight;
+
+    #[tokio::test]
+    async fn it_performs_concurrent_operations_once() {
+        let single_flight = Arc::new(SingleFlight::default());
+        let performed = Arc::new(AtomicUsize::new(0));
+
+        let tasks: Vec<_> = (0..8)
+            .map(|_| {
+                let single_flight = single_flight.clone();
+                let performed = performed.clone();
+                tokio::spawn(async move {
+                    single_flight
+                        .run(
+                            || async {
+                                performed.fetch_add(1, Ordering::SeqCst);
+                                tokio::time::sleep(Duration::from_millis(50)).await;
+                                Ok::<_, ()>("refreshed")
+                            },
+                            || async { Ok("reused") },
+                        )
+                        .await
+                })
+            })
+            .collect();
+        let mut results = Vec::new();
+        for task in tasks {
+            results.push(task.await.unwrap().unwrap());
+        }
+
+        assert_eq!(performed.load(Ordering::SeqCst), 1);
+        assert_eq!(results.iter().filter(|r| **r == "refreshed").count(), 1);
+    }
+
+    #[tokio::test]
+    async fn it_tries_again_after_a_failure() {
+        let single_flight = SingleFlight::default();
+        let failed = single_flight
+            .run(|| async { Err::<(), _>("failed") }, || async { Ok(()) })
+            .await;
+        assert_eq!(failed, Err("failed"));
+
+        let retried = single_flight
+            .run(
+                || async { Ok::<_, &str>("performed") },
+                || async { Ok("reused") },
+            )
+            .await;
+        assert_eq!(retried, Ok("performed"));
+    }
+
+    #[tokio::test]
+    async fn it_reads_after_the_current_operation() {
+        let single_flight = Arc::new(SingleFlight::default());
+        let finished = Arc::new(AtomicUsize::new(0));
+
+        let operation = {
+            let single_flight = single_flight.clone();
+            let finished = finished.clone();
+            tokio::spawn(async move {
+                single_flight
+                    .run(
+                        || async {
+                            tokio::time::sleep(Duration::from_millis(50)).await;
+                            finished.store(1, Ordering::SeqCst);
+                            Ok::<_, ()>(())
+                        },
+                        || async { Ok(()) },
+                    )
+                    .await
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let seen = single_flight
+            .after_current(|| async { finished.load(Ordering::SeqCst) })
+            .await;
+
+        assert_eq!(seen, 1);
+        operation.await.unwrap().unwrap();
+    }
+}
+
+#[cfg(test)]
+mod describe_refresh {
+    #[cfg(feature = "qcs-api")]
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[cfg(feature = "qcs-api")]
+    use super::{OpenApiClientError, OpenApiError, Qcs};
+
+    #[cfg(feature = "qcs-api")]
+    #[tokio::test]
+    async fn it_does_not_retry_other_errors() {
+        let attempts = AtomicUsize::new(0);
+        let result = Qcs::default()
+            .with_refresh(|_| async {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err::<(), OpenApiError<()>>(OpenApiError::Io(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    "unreachable",
+                )))
+            })
+            .await;
+
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+        let error = OpenApiClientError::RequestFailed(result.unwrap_err());
+        assert!(!error.is_unauthenticated());
+    }
+
+    #[cfg(feature = "qpu")]
+    #[test]
+    fn it_recognizes_unauthenticated_grpc_requests() {
+        use super::GrpcClientError;
+
+        let expired = GrpcClientError::RequestFailed(tonic::Status::unauthenticated("expired"));
+        let unavailable = GrpcClientError::RequestFailed(tonic::Status::unavailable("down"));
+        assert!(expired.is_unauthenticated());
+        assert!(!unavailable.is_unauthenticated());
+    }
+}
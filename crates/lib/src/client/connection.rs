@@ -0,0 +1,394 @@
+//! Proxy and TLS settings for the connections this library makes, for networks which only allow
+//! traffic through a proxy or which intercept TLS with their own certificate authority.
+
+use std::path::{Path, PathBuf};
+
+use qcs_api_client_common::configuration::{
+    path_from_env_or_home, PROFILE_NAME_VAR, SECRETS_PATH_VAR, SETTINGS_PATH_VAR,
+};
+use serde::{Deserialize, Serialize};
+
+/// Proxy and TLS settings, applied by [`Qcs::with_connection_settings`](super::Qcs::with_connection_settings).
+///
+/// Every setting applies to requests to the QCS REST API, and to HTTP connections to the QVM made
+/// with a [`qvm::http::HttpClient`](crate::qvm::http::HttpClient) built from a [`Qcs`](super::Qcs).
+/// gRPC connections to QCS and QPUs use the certificates, but not the proxy; they are proxied
+/// according to the `HTTPS_PROXY` and `HTTP_PROXY` environment variables instead, whether or not
+/// certificates are configured. Connections to quilc are made over ZeroMQ, which can only use a
+/// `socks5://` proxy and doesn't use TLS.
+///
+/// The settings are read from the `connection` table of the profile in the QCS
+/// `settings.toml` by [`Qcs::load`](super::Qcs::load) and
+/// [`Qcs::with_profile`](super::Qcs::with_profile):
+///
+/// ```toml
+/// [profiles.default.connection]
+/// proxy = "http://proxy.example.com:3128"
+/// ca_bundle = "/etc/ssl/certs/corporate.pem"
+/// client_certificate = "/home/me/.qcs/client.pem"
+/// client_key = "/home/me/.qcs/client.key"
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+#[non_exhaustive]
+pub struct ConnectionSettings {
+    /// The URL of the proxy to connect through, e.g. `http://proxy.example.com:3128` or
+    /// `socks5://proxy.example.com:1080`.
+    pub proxy: Option<String>,
+    /// A PEM file of certificates to trust as roots, in addition to the defaults.
+    pub ca_bundle: Option<PathBuf>,
+    /// A PEM file of the certificate chain to authenticate with, for servers which require
+    /// client authentication. Requires [`ConnectionSettings::client_key`].
+    pub client_certificate: Option<PathBuf>,
+    /// A PEM file of the private key of [`ConnectionSettings::client_certificate`].
+    pub client_key: Option<PathBuf>,
+}
+
+/// Errors that can occur when reading or applying [`ConnectionSettings`].
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum ConnectionSettingsError {
+    /// A settings, certificate, or key file couldn't be read.
+    #[error("failed to read {path}: {source}")]
+    Read {
+        /// The file which couldn't be read.
+        path: PathBuf,
+        /// Why it couldn't be read.
+        #[source]
+        source: std::io::Error,
+    },
+    /// The settings file isn't valid TOML, or its `connection` table isn't valid.
+    #[error("failed to parse the connection settings in {path}: {source}")]
+    Parse {
+        /// The settings file.
+        path: PathBuf,
+        /// Why it couldn't be parsed.
+        #[source]
+        source: toml::de::Error,
+    },
+    /// Only one of a client certificate and its key was given.
+    #[error("a client certificate and its key must be given together")]
+    IncompleteIdentity,
+    /// The proxy, certificates, or key can't be used for HTTP connections.
+    #[cfg(any(feature = "qvm-http", feature = "qcs-api"))]
+    #[error("invalid connection settings: {0}")]
+    Http(#[from] reqwest::Error),
+    /// The certificates or key can't be used for gRPC connections.
+    #[cfg(feature = "qpu")]
+    #[error("invalid TLS settings: {0}")]
+    Grpc(#[from] tonic::transport::Error),
+}
+
+/// The parts of the QCS settings file which hold [`ConnectionSettings`].
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct SettingsFile {
+    default_profile_name: Option<String>,
+    profiles: std::collections::HashMap<String, Profile>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Profile {
+    connection: ConnectionSettings,
+}
+
+impl ConnectionSettings {
+    /// Connect through the proxy at `proxy`, or directly if `None`.
+    #[must_use]
+    pub fn with_proxy(mut self, proxy: Option<String>) -> Self {
+        self.proxy = proxy;
+        self
+    }
+
+    /// Trust the certificates in the PEM file at `ca_bundle` as roots, as well as the defaults.
+    #[must_use]
+    pub fn with_ca_bundle(mut self, ca_bundle: Option<PathBuf>) -> Self {
+        self.ca_bundle = ca_bundle;
+        self
+    }
+
+    /// Authenticate with the certificate chain and private key in the PEM files at `certificate`
+    /// and `key`.
+    #[must_use]
+    pub fn with_client_identity(mut self, certificate: PathBuf, key: PathBuf) -> Self {
+        self.client_certificate = Some(certificate);
+        self.client_key = Some(key);
+        self
+    }
+
+    /// Read the settings of `profile` from the QCS settings file, or of the profile named by the
+    /// `QCS_PROFILE_NAME` environment variable or the file's `default_profile_name` if `None`.
+    ///
+    /// The file is read from `QCS_SETTINGS_FILE_PATH`, or `~/.qcs/settings.toml` by default. If
+    /// it, the profile, or the profile's `connection` table doesn't exist, the settings are empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but can't be read or parsed.
+    pub fn from_settings_file(profile: Option<&str>) -> Result<Self, ConnectionSettingsError> {
+        let Some(path) = settings_path() else {
+            return Ok(Self::default());
+        };
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Self::default())
+            }
+            Err(source) => return Err(ConnectionSettingsError::Read { path, source }),
+        };
+        Self::from_settings_toml(&contents, profile)
+            .map_err(|source| ConnectionSettingsError::Parse { path, source })
+    }
+
+    /// Read the settings of `profile` from the contents of a QCS settings file. See
+    /// [`ConnectionSettings::from_settings_file`].
+    fn from_settings_toml(contents: &str, profile: Option<&str>) -> Result<Self, toml::de::Error> {
+        let mut file: SettingsFile = toml::from_str(contents)?;
+        let profile = profile
+            .map(str::to_string)
+            .or_else(|| std::env::var(PROFILE_NAME_VAR).ok())
+            .or(file.default_profile_name.take())
+            .unwrap_or_else(|| "default".to_string());
+        Ok(file
+            .profiles
+            .remove(&profile)
+            .map(|profile| profile.connection)
+            .unwrap_or_default())
+    }
+
+    /// The `host:port` of the proxy, if it is a SOCKS5 proxy, in the form ZeroMQ expects.
+    #[must_use]
+    pub fn socks_proxy(&self) -> Option<&str> {
+        self.proxy
+            .as_deref()
+            .and_then(|proxy| proxy.strip_prefix("socks5://"))
+            .map(|address| address.trim_end_matches('/'))
+    }
+
+    /// Read the certificate and key files.
+    pub(crate) fn load(&self) -> Result<LoadedConnectionSettings, ConnectionSettingsError> {
+        let identity = match (&self.client_certificate, &self.client_key) {
+            (Some(certificate), Some(key)) => Some(ClientIdentity {
+                certificate: read(certificate)?,
+                key: read(key)?,
+            }),
+            (None, None) => None,
+            _ => return Err(ConnectionSettingsError::IncompleteIdentity),
+        };
+        let loaded = LoadedConnectionSettings {
+            settings: self.clone(),
+            root_certificates: self.ca_bundle.as_deref().map(read).transpose()?,
+            identity,
+            #[cfg(feature = "qcs-api")]
+            http_client: None,
+        };
+        #[cfg(all(feature = "qvm-http", not(feature = "qcs-api")))]
+        loaded.http_client_builder()?.build()?;
+        #[cfg(feature = "qcs-api")]
+        let loaded = loaded.with_http_client()?;
+        #[cfg(feature = "qpu")]
+        if let Some(config) = loaded.grpc_tls_config() {
+            tonic::transport::Endpoint::from_static("https://localhost").tls_config(config)?;
+        }
+        Ok(loaded)
+    }
+}
+
+/// The path of the QCS settings file, resolved as the QCS client resolves it when loading its
+/// configuration, if it can be found.
+pub(crate) fn settings_path() -> Option<PathBuf> {
+    path_from_env_or_home(SETTINGS_PATH_VAR, "settings.toml").ok()
+}
+
+/// The path of the QCS secrets file, resolved as the QCS client resolves it when loading its
+/// configuration, if it can be found.
+pub(crate) fn secrets_path() -> Option<PathBuf> {
+    path_from_env_or_home(SECRETS_PATH_VAR, "secrets.toml").ok()
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, ConnectionSettingsError> {
+    std::fs::read(path).map_err(|source| ConnectionSettingsError::Read {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// A client certificate chain and its private key, in PEM format.
+#[derive(Clone)]
+#[cfg_attr(not(any(feature = "qvm-http", feature = "qcs-api")), allow(dead_code))]
+struct ClientIdentity {
+    certificate: Vec<u8>,
+    key: Vec<u8>,
+}
+
+/// [`ConnectionSettings`] with their certificates and key read into memory, ready to be applied
+/// to each kind of connection.
+#[derive(Clone, Default)]
+#[cfg_attr(not(any(feature = "qvm-http", feature = "qcs-api")), allow(dead_code))]
+pub(crate) struct LoadedConnectionSettings {
+    settings: ConnectionSettings,
+    root_certificates: Option<Vec<u8>>,
+    identity: Option<ClientIdentity>,
+    /// A client built with [`LoadedConnectionSettings::http_client_builder`], or `None` if no
+    /// settings are configured, in which case the defaults should be used.
+    #[cfg(feature = "qcs-api")]
+    http_client: Option<reqwest::Client>,
+}
+
+// The private key is left out, so that it can't end up in logs.
+impl std::fmt::Debug for LoadedConnectionSettings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(&self.settings, f)
+    }
+}
+
+impl LoadedConnectionSettings {
+    pub(crate) fn settings(&self) -> &ConnectionSettings {
+        &self.settings
+    }
+
+    /// A client for requests to the QCS REST API which connects through the proxy and with the
+    /// certificates, or `None` if no settings are configured.
+    #[cfg(feature = "qcs-api")]
+    pub(crate) fn http_client(&self) -> Option<&reqwest::Client> {
+        self.http_client.as_ref()
+    }
+
+    /// Build [`LoadedConnectionSettings::http_client`], if any settings are configured.
+    #[cfg(feature = "qcs-api")]
+    fn with_http_client(mut self) -> Result<Self, ConnectionSettingsError> {
+        if self.settings != ConnectionSettings::default() {
+            self.http_client = Some(self.http_client_builder()?.build()?);
+        }
+        Ok(self)
+    }
+
+    /// A builder for HTTP clients which connect through the proxy and with the certificates.
+    #[cfg(any(feature = "qvm-http", feature = "qcs-api"))]
+    pub(crate) fn http_client_builder(
+        &self,
+    ) -> Result<reqwest::ClientBuilder, ConnectionSettingsError> {
+        let mut builder = reqwest::Client::builder();
+        if let Some(proxy) = &self.settings.proxy {
+            builder = builder.proxy(reqwest::Proxy::all(proxy)?);
+        }
+        if let Some(pem) = &self.root_certificates {
+            for certificate in reqwest::Certificate::from_pem_bundle(pem)? {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+        if let Some(identity) = &self.identity {
+            let pem = [
+                identity.certificate.as_slice(),
+                b"\n",
+                identity.key.as_slice(),
+            ]
+            .concat();
+            builder = builder.identity(reqwest::Identity::from_pem(&pem)?);
+        }
+        Ok(builder)
+    }
+
+    /// The TLS configuration for gRPC connections, or `None` if no certificates are configured,
+    /// in which case the defaults should be used.
+    #[cfg(feature = "qpu")]
+    pub(crate) fn grpc_tls_config(&self) -> Option<tonic::transport::ClientTlsConfig> {
+        use tonic::transport::{Certificate, ClientTlsConfig, Identity};
+
+        if self.root_certificates.is_none() && self.identity.is_none() {
+            return None;
+        }
+        let mut config = ClientTlsConfig::new().with_enabled_roots();
+        if let Some(pem) = &self.root_certificates {
+            config = config.ca_certificate(Certificate::from_pem(pem));
+        }
+        if let Some(identity) = &self.identity {
+            config = config.identity(Identity::from_pem(&identity.certificate, &identity.key));
+        }
+        Some(config)
+    }
+}
+
+#[cfg(test)]
+mod describe_connection_settings {
+    use std::path::PathBuf;
+
+    use super::{ConnectionSettings, ConnectionSettingsError};
+
+    const SETTINGS: &str = r#"
+default_profile_name = "corporate"
+
+[profiles.default]
+api_url = "https://api.qcs.rigetti.com"
+
+[profiles.corporate.connection]
+proxy = "socks5://proxy.example.com:1080"
+ca_bundle = "/etc/ssl/corporate.pem"
+"#;
+
+    #[test]
+    fn it_reads_the_connection_table_of_a_profile() {
+        let settings = ConnectionSettings::from_settings_toml(SETTINGS, Some("corporate")).unwrap();
+        assert_eq!(
+            settings,
+            ConnectionSettings::default()
+                .with_proxy(Some("socks5://proxy.example.com:1080".to_string()))
+                .with_ca_bundle(Some(PathBuf::from("/etc/ssl/corporate.pem")))
+        );
+        assert_eq!(settings.socks_proxy(), Some("proxy.example.com:1080"));
+    }
+
+    #[test]
+    fn it_is_empty_for_profiles_without_a_connection_table() {
+        assert_eq!(
+            ConnectionSettings::from_settings_toml(SETTINGS, Some("default")).unwrap(),
+            ConnectionSettings::default()
+        );
+        assert_eq!(
+            ConnectionSettings::from_settings_toml(SETTINGS, Some("missing")).unwrap(),
+            ConnectionSettings::default()
+        );
+    }
+
+    #[test]
+    fn it_only_uses_socks_proxies_for_zeromq() {
+        let settings =
+            ConnectionSettings::default().with_proxy(Some("http://proxy:3128".to_string()));
+        assert_eq!(settings.socks_proxy(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "qcs-api")]
+    fn it_only_replaces_the_default_http_client_when_configured() {
+        let default = ConnectionSettings::default().load().unwrap();
+        assert!(default.http_client().is_none());
+        let proxied = ConnectionSettings::default()
+            .with_proxy(Some("http://proxy:3128".to_string()))
+            .load()
+            .unwrap();
+        assert!(proxied.http_client().is_some());
+    }
+
+    #[test]
+    fn it_requires_a_key_with_a_client_certificate() {
+        let settings = ConnectionSettings {
+            client_certificate: Some(PathBuf::from("client.pem")),
+            ..ConnectionSettings::default()
+        };
+        assert!(matches!(
+            settings.load(),
+            Err(ConnectionSettingsError::IncompleteIdentity)
+        ));
+    }
+
+    #[test]
+    fn it_reports_missing_certificate_files() {
+        let settings =
+            ConnectionSettings::default().with_ca_bundle(Some(PathBuf::from("/nonexistent.pem")));
+        assert!(matches!(
+            settings.load(),
+            Err(ConnectionSettingsError::Read { .. })
+        ));
+    }
+}
@@ -0,0 +1,577 @@
+//! Creating, listing, and switching between the profiles in the QCS settings file, so that tools
+//! built on this library can configure QCS for their users without asking them to edit TOML.
+//!
+//! A profile saved with [`save_profile`] is used by [`Qcs::with_profile`](super::Qcs::with_profile),
+//! and by [`Qcs::load`](super::Qcs::load) once it is made the default with
+//! [`set_default_profile`]. Each file is replaced as a whole, and atomically, so a reader never
+//! sees it half written, but any comments in it are lost.
+//!
+//! The same functions are available on [`ClientConfiguration`] with [`ClientConfigurationExt`] in
+//! scope, next to [`ClientConfiguration::load_profile`].
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use qcs_api_client_common::configuration::ClientConfiguration;
+use toml::{Table, Value};
+
+use super::connection::{secrets_path, settings_path};
+
+/// The settings of a profile, saved with [`save_profile`].
+///
+/// Settings which are `None` are left as they are in an existing profile, and out of a new one,
+/// so that their defaults are used.
+#[derive(Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Profile {
+    /// The URL of the QCS REST API.
+    pub api_url: Option<String>,
+    /// The URL of the QCS gRPC API.
+    pub grpc_api_url: Option<String>,
+    /// The URL of the QVM.
+    pub qvm_url: Option<String>,
+    /// The URL of quilc.
+    pub quilc_url: Option<String>,
+    /// The OAuth2 server which issues tokens for the profile.
+    pub auth_server: Option<AuthServer>,
+    /// The refresh token to authenticate with, which is saved to the secrets file rather than
+    /// the settings file.
+    pub refresh_token: Option<String>,
+}
+
+impl std::fmt::Debug for Profile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Profile")
+            .field("api_url", &self.api_url)
+            .field("grpc_api_url", &self.grpc_api_url)
+            .field("qvm_url", &self.qvm_url)
+            .field("quilc_url", &self.quilc_url)
+            .field("auth_server", &self.auth_server)
+            .field(
+                "refresh_token",
+                &self.refresh_token.as_ref().map(|_| "<redacted>"),
+            )
+            .finish()
+    }
+}
+
+impl Profile {
+    /// A profile which leaves every setting as it is, or at its default.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use the QCS REST API at `api_url`.
+    #[must_use]
+    pub fn with_api_url(mut self, api_url: String) -> Self {
+        self.api_url = Some(api_url);
+        self
+    }
+
+    /// Use the QCS gRPC API at `grpc_api_url`.
+    #[must_use]
+    pub fn with_grpc_api_url(mut self, grpc_api_url: String) -> Self {
+        self.grpc_api_url = Some(grpc_api_url);
+        self
+    }
+
+    /// Use the QVM at `qvm_url`.
+    #[must_use]
+    pub fn with_qvm_url(mut self, qvm_url: String) -> Self {
+        self.qvm_url = Some(qvm_url);
+        self
+    }
+
+    /// Use quilc at `quilc_url`.
+    #[must_use]
+    pub fn with_quilc_url(mut self, quilc_url: String) -> Self {
+        self.quilc_url = Some(quilc_url);
+        self
+    }
+
+    /// Get tokens from `auth_server`.
+    #[must_use]
+    pub fn with_auth_server(mut self, auth_server: AuthServer) -> Self {
+        self.auth_server = Some(auth_server);
+        self
+    }
+
+    /// Authenticate with `refresh_token`.
+    #[must_use]
+    pub fn with_refresh_token(mut self, refresh_token: String) -> Self {
+        self.refresh_token = Some(refresh_token);
+        self
+    }
+}
+
+/// An OAuth2 server which issues tokens, see [`Profile::with_auth_server`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct AuthServer {
+    /// The ID of the client to request tokens as.
+    pub client_id: String,
+    /// The URL of the server.
+    pub issuer: String,
+}
+
+impl AuthServer {
+    /// The server at `issuer`, from which tokens are requested as `client_id`.
+    #[must_use]
+    pub fn new(client_id: String, issuer: String) -> Self {
+        Self { client_id, issuer }
+    }
+}
+
+/// Errors that can occur when reading or changing the profiles in the QCS settings files.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum ProfileError {
+    /// Neither the path of a settings file nor the home directory is set.
+    #[error("can't find the QCS {0} file: neither its path nor the home directory is set")]
+    NoPath(&'static str),
+    /// A settings file couldn't be read.
+    #[error("failed to read {path}: {source}")]
+    Read {
+        /// The file which couldn't be read.
+        path: PathBuf,
+        /// Why it couldn't be read.
+        #[source]
+        source: std::io::Error,
+    },
+    /// A settings file isn't valid TOML.
+    #[error("failed to parse {path}: {source}")]
+    Parse {
+        /// The file which couldn't be parsed.
+        path: PathBuf,
+        /// Why it couldn't be parsed.
+        #[source]
+        source: toml::de::Error,
+    },
+    /// A settings file has a value where a table of settings belongs.
+    #[error("{key} in {path} should be a table")]
+    NotATable {
+        /// The file.
+        path: PathBuf,
+        /// The key of the value, e.g. `profiles.default`.
+        key: String,
+    },
+    /// A settings file couldn't be written.
+    #[error("failed to write {path}: {source}")]
+    Write {
+        /// The file which couldn't be written.
+        path: PathBuf,
+        /// Why it couldn't be written.
+        #[source]
+        source: std::io::Error,
+    },
+    /// The settings couldn't be serialized.
+    #[error("failed to serialize settings: {0}")]
+    Serialize(#[from] toml::ser::Error),
+    /// The secrets file can be read or written by users other than its owner, so credentials
+    /// aren't saved to it.
+    #[error(
+        "{path} is accessible to other users (mode {mode:o}); restrict it with `chmod 600` before saving credentials to it"
+    )]
+    InsecureSecrets {
+        /// The secrets file.
+        path: PathBuf,
+        /// Its permission bits.
+        mode: u32,
+    },
+    /// There is no profile with the given name.
+    #[error("there is no profile named {0:?}")]
+    UnknownProfile(String),
+}
+
+/// Create the profile `name`, or update it if it exists, with the settings in `profile`.
+///
+/// The authorization server and refresh token, if given, are saved under `name` as well, with
+/// the refresh token in the secrets file. The settings file is read from
+/// `QCS_SETTINGS_FILE_PATH`, or `~/.qcs/settings.toml` by default, and the secrets file from
+/// `QCS_SECRETS_FILE_PATH`, or `~/.qcs/secrets.toml`; either is created if it doesn't exist.
+/// If the settings file has no default profile, `name` becomes the default.
+///
+/// # Errors
+///
+/// Returns an error if a file can't be read, parsed, or written, or if a refresh token is given
+/// and the secrets file is accessible to other users.
+pub fn save_profile(name: &str, profile: &Profile) -> Result<(), ProfileError> {
+    let settings = settings_path().ok_or(ProfileError::NoPath("settings"))?;
+    let secrets = secrets_path().ok_or(ProfileError::NoPath("secrets"))?;
+    save_profile_in(&settings, &secrets, name, profile)
+}
+
+/// The names of the profiles in the QCS settings file, sorted. Empty if the file doesn't exist.
+///
+/// # Errors
+///
+/// Returns an error if the file can't be read or parsed.
+pub fn list_profiles() -> Result<Vec<String>, ProfileError> {
+    let settings = settings_path().ok_or(ProfileError::NoPath("settings"))?;
+    list_profiles_in(&settings)
+}
+
+/// Make `name` the profile used by [`Qcs::load`](super::Qcs::load), unless the
+/// `QCS_PROFILE_NAME` environment variable overrides it.
+///
+/// # Errors
+///
+/// Returns [`ProfileError::UnknownProfile`] if there is no profile named `name`, or another error
+/// if the settings file can't be read, parsed, or written.
+pub fn set_default_profile(name: &str) -> Result<(), ProfileError> {
+    let settings = settings_path().ok_or(ProfileError::NoPath("settings"))?;
+    set_default_profile_in(&settings, name)
+}
+
+/// Profile management as associated functions of [`ClientConfiguration`], which is defined in
+/// another crate, so that profiles are saved and listed alongside where they are loaded.
+pub trait ClientConfigurationExt {
+    /// See [`save_profile`].
+    ///
+    /// # Errors
+    ///
+    /// See [`save_profile`].
+    fn save_profile(name: &str, profile: &Profile) -> Result<(), ProfileError>;
+
+    /// See [`list_profiles`].
+    ///
+    /// # Errors
+    ///
+    /// See [`list_profiles`].
+    fn list_profiles() -> Result<Vec<String>, ProfileError>;
+
+    /// See [`set_default_profile`].
+    ///
+    /// # Errors
+    ///
+    /// See [`set_default_profile`].
+    fn set_default_profile(name: &str) -> Result<(), ProfileError>;
+}
+
+impl ClientConfigurationExt for ClientConfiguration {
+    fn save_profile(name: &str, profile: &Profile) -> Result<(), ProfileError> {
+        save_profile(name, profile)
+    }
+
+    fn list_profiles() -> Result<Vec<String>, ProfileError> {
+        list_profiles()
+    }
+
+    fn set_default_profile(name: &str) -> Result<(), ProfileError> {
+        set_default_profile(name)
+    }
+}
+
+fn save_profile_in(
+    settings_path: &Path,
+    secrets_path: &Path,
+    name: &str,
+    profile: &Profile,
+) -> Result<(), ProfileError> {
+    let mut settings = read(settings_path)?;
+
+    // Save credentials first, so the settings never refer to ones which don't exist.
+    if let Some(refresh_token) = &profile.refresh_token {
+        check_secrets_permissions(secrets_path)?;
+        let mut secrets = read(secrets_path)?;
+        table(
+            &mut secrets,
+            secrets_path,
+            &["credentials", name, "token_payload"],
+        )?
+        .insert("refresh_token".to_string(), refresh_token.clone().into());
+        write(secrets_path, &secrets)?;
+    }
+
+    if let Some(auth_server) = &profile.auth_server {
+        let server = table(&mut settings, settings_path, &["auth_servers", name])?;
+        server.insert(
+            "client_id".to_string(),
+            auth_server.client_id.clone().into(),
+        );
+        server.insert("issuer".to_string(), auth_server.issuer.clone().into());
+    }
+
+    let entry = table(&mut settings, settings_path, &["profiles", name])?;
+    set(
+        entry,
+        [
+            ("api_url", &profile.api_url),
+            ("grpc_api_url", &profile.grpc_api_url),
+        ],
+    );
+    if profile.auth_server.is_some() {
+        entry.insert("auth_server_name".to_string(), name.into());
+    }
+    if profile.refresh_token.is_some() {
+        entry.insert("credentials_name".to_string(), name.into());
+    }
+    if profile.qvm_url.is_some() || profile.quilc_url.is_some() {
+        set(
+            table(entry, settings_path, &["applications", "pyquil"])?,
+            [
+                ("qvm_url", &profile.qvm_url),
+                ("quilc_url", &profile.quilc_url),
+            ],
+        );
+    }
+
+    settings
+        .entry("default_profile_name")
+        .or_insert_with(|| name.into());
+    write(settings_path, &settings)
+}
+
+/// Set each of `values` in `table` which isn't `None`.
+fn set<const N: usize>(table: &mut Table, values: [(&str, &Option<String>); N]) {
+    for (key, value) in values {
+        if let Some(value) = value {
+            table.insert(key.to_string(), value.clone().into());
+        }
+    }
+}
+
+fn list_profiles_in(settings_path: &Path) -> Result<Vec<String>, ProfileError> {
+    let mut settings = read(settings_path)?;
+    let mut names: Vec<String> = table(&mut settings, settings_path, &["profiles"])?
+        .keys()
+        .cloned()
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+fn set_default_profile_in(settings_path: &Path, name: &str) -> Result<(), ProfileError> {
+    let mut settings = read(settings_path)?;
+    if !table(&mut settings, settings_path, &["profiles"])?.contains_key(name) {
+        return Err(ProfileError::UnknownProfile(name.to_string()));
+    }
+    settings.insert("default_profile_name".to_string(), name.into());
+    write(settings_path, &settings)
+}
+
+/// Read the settings in `path`, or none if it doesn't exist.
+fn read(path: &Path) -> Result<Table, ProfileError> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => toml::from_str(&contents).map_err(|source| ProfileError::Parse {
+            path: path.to_path_buf(),
+            source,
+        }),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(Table::new()),
+        Err(source) => Err(ProfileError::Read {
+            path: path.to_path_buf(),
+            source,
+        }),
+    }
+}
+
+/// The table at `keys` in `settings`, read from `path`, creating it and any tables above it
+/// which don't exist.
+fn table<'t>(
+    settings: &'t mut Table,
+    path: &Path,
+    keys: &[&str],
+) -> Result<&'t mut Table, ProfileError> {
+    keys.iter()
+        .enumerate()
+        .try_fold(settings, |table, (depth, key)| {
+            table
+                .entry(*key)
+                .or_insert_with(|| Value::Table(Table::new()))
+                .as_table_mut()
+                .ok_or_else(|| ProfileError::NotATable {
+                    path: path.to_path_buf(),
+                    key: keys[..=depth].join("."),
+                })
+        })
+}
+
+/// Replace `path` with `settings`, by writing them to a temporary file beside it and then
+/// renaming that over it. The temporary file is only readable by its owner; an existing file's
+/// permissions are kept.
+fn write(path: &Path, settings: &Table) -> Result<(), ProfileError> {
+    let contents = toml::to_string(settings)?;
+    let failed = |source| ProfileError::Write {
+        path: path.to_path_buf(),
+        source,
+    };
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    std::fs::create_dir_all(directory).map_err(failed)?;
+    let mut file = tempfile::NamedTempFile::new_in(directory).map_err(failed)?;
+    file.write_all(contents.as_bytes()).map_err(failed)?;
+    if let Ok(metadata) = std::fs::metadata(path) {
+        file.as_file()
+            .set_permissions(metadata.permissions())
+            .map_err(failed)?;
+    }
+    file.as_file().sync_all().map_err(failed)?;
+    file.persist(path).map_err(|error| failed(error.error))?;
+    Ok(())
+}
+
+/// Refuse to save credentials to a secrets file which users other than its owner can access.
+#[cfg(unix)]
+fn check_secrets_permissions(path: &Path) -> Result<(), ProfileError> {
+    use std::os::unix::fs::PermissionsExt;
+
+    match std::fs::metadata(path) {
+        Ok(metadata) if metadata.permissions().mode() & 0o077 != 0 => {
+            Err(ProfileError::InsecureSecrets {
+                path: path.to_path_buf(),
+                mode: metadata.permissions().mode() & 0o777,
+            })
+        }
+        Ok(_) => Ok(()),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(source) => Err(ProfileError::Read {
+            path: path.to_path_buf(),
+            source,
+        }),
+    }
+}
+
+/// Refuse to save credentials to a secrets file which users other than its owner can access.
+#[cfg(not(unix))]
+#[allow(clippy::unnecessary_wraps)]
+fn check_secrets_permissions(_path: &Path) -> Result<(), ProfileError> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod describe_profiles {
+    use std::path::PathBuf;
+
+    use tempfile::TempDir;
+
+    use super::{
+        list_profiles_in, read, save_profile_in, set_default_profile_in, AuthServer, Profile,
+        ProfileError,
+    };
+
+    fn paths(directory: &TempDir) -> (PathBuf, PathBuf) {
+        (
+            directory.path().join("settings.toml"),
+            directory.path().join("secrets.toml"),
+        )
+    }
+
+    #[test]
+    fn it_creates_profiles_and_their_credentials() {
+        let directory = tempfile::tempdir().unwrap();
+        let (settings, secrets) = paths(&directory);
+        let profile = Profile::new()
+            .with_api_url("https://api.example.com".to_string())
+            .with_qvm_url("http://localhost:5000".to_string())
+            .with_auth_server(AuthServer::new(
+                "client".to_string(),
+                "https://auth.example.com".to_string(),
+            ))
+            .with_refresh_token("secret".to_string());
+        save_profile_in(&settings, &secrets, "staging", &profile).unwrap();
+
+        let written = read(&settings).unwrap();
+        assert_eq!(written["default_profile_name"].as_str(), Some("staging"));
+        let staging = &written["profiles"]["staging"];
+        assert_eq!(staging["api_url"].as_str(), Some("https://api.example.com"));
+        assert_eq!(staging["auth_server_name"].as_str(), Some("staging"));
+        assert_eq!(staging["credentials_name"].as_str(), Some("staging"));
+        assert_eq!(
+            staging["applications"]["pyquil"]["qvm_url"].as_str(),
+            Some("http://localhost:5000")
+        );
+        assert_eq!(
+            written["auth_servers"]["staging"]["issuer"].as_str(),
+            Some("https://auth.example.com")
+        );
+        assert_eq!(
+            read(&secrets).unwrap()["credentials"]["staging"]["token_payload"]["refresh_token"]
+                .as_str(),
+            Some("secret")
+        );
+        assert!(!format!("{profile:?}").contains("secret"));
+    }
+
+    #[test]
+    fn it_updates_profiles_without_dropping_other_settings() {
+        let directory = tempfile::tempdir().unwrap();
+        let (settings, secrets) = paths(&directory);
+        std::fs::write(
+            &settings,
+            r#"
+default_profile_name = "default"
+
+[profiles.default]
+api_url = "https://api.qcs.rigetti.com"
+
+[profiles.default.connection]
+proxy = "http://proxy.example.com:3128"
+"#,
+        )
+        .unwrap();
+        let profile = Profile::new().with_quilc_url("tcp://localhost:5555".to_string());
+        save_profile_in(&settings, &secrets, "default", &profile).unwrap();
+
+        let written = read(&settings).unwrap();
+        let default = &written["profiles"]["default"];
+        assert_eq!(
+            default["api_url"].as_str(),
+            Some("https://api.qcs.rigetti.com")
+        );
+        assert_eq!(
+            default["connection"]["proxy"].as_str(),
+            Some("http://proxy.example.com:3128")
+        );
+        assert_eq!(
+            default["applications"]["pyquil"]["quilc_url"].as_str(),
+            Some("tcp://localhost:5555")
+        );
+        assert!(!secrets.exists());
+    }
+
+    #[test]
+    fn it_lists_and_switches_between_profiles() {
+        let directory = tempfile::tempdir().unwrap();
+        let (settings, secrets) = paths(&directory);
+        assert_eq!(list_profiles_in(&settings).unwrap(), Vec::<String>::new());
+
+        for name in ["staging", "default"] {
+            save_profile_in(&settings, &secrets, name, &Profile::new()).unwrap();
+        }
+        assert_eq!(
+            list_profiles_in(&settings).unwrap(),
+            vec!["default".to_string(), "staging".to_string()]
+        );
+
+        set_default_profile_in(&settings, "default").unwrap();
+        assert_eq!(
+            read(&settings).unwrap()["default_profile_name"].as_str(),
+            Some("default")
+        );
+        assert!(matches!(
+            set_default_profile_in(&settings, "missing"),
+            Err(ProfileError::UnknownProfile(name)) if name == "missing"
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn it_refuses_to_save_credentials_to_a_shared_secrets_file() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let directory = tempfile::tempdir().unwrap();
+        let (settings, secrets) = paths(&directory);
+        std::fs::write(&secrets, "").unwrap();
+        std::fs::set_permissions(&secrets, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        let profile = Profile::new().with_refresh_token("secret".to_string());
+        assert!(matches!(
+            save_profile_in(&settings, &secrets, "default", &profile),
+            Err(ProfileError::InsecureSecrets { mode: 0o644, .. })
+        ));
+        assert!(!settings.exists());
+    }
+}
@@ -0,0 +1,160 @@
+//! A compiler which can be used in place of quilc, see [`CompilerBackend`].
+
+use super::quilc::{
+    self, CompilationError, CompilationResult, CompilerOpts, ConjugateByCliffordRequest,
+    ConjugatePauliByCliffordResponse, GenerateRandomizedBenchmarkingSequenceResponse,
+    RandomizedBenchmarkingRequest, TargetDevice,
+};
+
+/// A compiler which turns Quil programs into native Quil for a target device.
+///
+/// Every [`quilc::Client`], including the RPCQ and libquil clients, is a [`CompilerBackend`]. Implement this trait
+/// directly to use another compiler, in process or remote, with
+/// [`Executable::with_compiler_backend`](crate::Executable::with_compiler_backend).
+///
+/// Only [`CompilerBackend::compile_to_native`] and [`CompilerBackend::get_version`] are needed to
+/// run programs. The other methods are quilc services which a backend may not offer; by default
+/// they fail with [`CompilationError::Unsupported`].
+///
+/// Return errors from the compiler itself as [`quilc::Error::QuilcCompilation`] with
+/// [`CompilationError::Backend`], so that they are reported as compilation failures.
+pub trait CompilerBackend {
+    /// Compile the program `quil` to native Quil for the device `target`, with `options`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the program can't be compiled for `target`.
+    fn compile_to_native(
+        &self,
+        quil: &str,
+        target: TargetDevice,
+        options: CompilerOpts,
+    ) -> Result<CompilationResult, quilc::Error>;
+
+    /// Get the version of the compiler.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the compiler can't be reached.
+    fn get_version(&self) -> Result<String, quilc::Error>;
+
+    /// See [`quilc::Client::conjugate_pauli_by_clifford`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CompilationError::Unsupported`] unless the backend implements it.
+    fn conjugate_pauli(
+        &self,
+        request: ConjugateByCliffordRequest,
+    ) -> Result<ConjugatePauliByCliffordResponse, quilc::Error> {
+        let _ = request;
+        Err(unsupported("conjugate_pauli"))
+    }
+
+    /// See [`quilc::Client::generate_randomized_benchmarking_sequence`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CompilationError::Unsupported`] unless the backend implements it.
+    fn randomized_benchmarking_sequence(
+        &self,
+        request: RandomizedBenchmarkingRequest,
+    ) -> Result<GenerateRandomizedBenchmarkingSequenceResponse, quilc::Error> {
+        let _ = request;
+        Err(unsupported("randomized_benchmarking_sequence"))
+    }
+}
+
+impl<C: quilc::Client + ?Sized> CompilerBackend for C {
+    fn compile_to_native(
+        &self,
+        quil: &str,
+        target: TargetDevice,
+        options: CompilerOpts,
+    ) -> Result<CompilationResult, quilc::Error> {
+        self.compile_program(quil, target, options)
+    }
+
+    fn get_version(&self) -> Result<String, quilc::Error> {
+        self.get_version_info()
+    }
+
+    fn conjugate_pauli(
+        &self,
+        request: ConjugateByCliffordRequest,
+    ) -> Result<ConjugatePauliByCliffordResponse, quilc::Error> {
+        self.conjugate_pauli_by_clifford(request)
+    }
+
+    fn randomized_benchmarking_sequence(
+        &self,
+        request: RandomizedBenchmarkingRequest,
+    ) -> Result<GenerateRandomizedBenchmarkingSequenceResponse, quilc::Error> {
+        self.generate_randomized_benchmarking_sequence(request)
+    }
+}
+
+fn unsupported(operation: &'static str) -> quilc::Error {
+    quilc::Error::QuilcCompilation(CompilationError::Unsupported { operation })
+}
+
+#[cfg(test)]
+mod describe_compiler_backend {
+    use std::{convert::TryFrom, str::FromStr};
+
+    use quil_rs::Program;
+
+    use super::CompilerBackend;
+    use crate::compiler::quilc::{
+        self, CompilationError, CompilationResult, CompilerOpts, ConjugateByCliffordRequest,
+        PauliTerm, TargetDevice,
+    };
+    use crate::test_support::qvm_isa;
+
+    /// A "compiler" which returns programs unchanged.
+    struct Identity;
+
+    impl CompilerBackend for Identity {
+        fn compile_to_native(
+            &self,
+            quil: &str,
+            _: TargetDevice,
+            _: CompilerOpts,
+        ) -> Result<CompilationResult, quilc::Error> {
+            Ok(CompilationResult {
+                program: Program::from_str(quil).map_err(quilc::Error::Parse)?,
+                native_quil_metadata: None,
+            })
+        }
+
+        fn get_version(&self) -> Result<String, quilc::Error> {
+            Ok("identity".to_string())
+        }
+    }
+
+    #[test]
+    fn it_only_requires_compilation_and_version() {
+        let target = TargetDevice::try_from(qvm_isa()).unwrap();
+        let result = Identity
+            .compile_to_native("X 0", target, CompilerOpts::default())
+            .unwrap();
+        assert_eq!(result.program, Program::from_str("X 0").unwrap());
+        assert_eq!(Identity.get_version().unwrap(), "identity");
+
+        let request = ConjugateByCliffordRequest {
+            pauli: PauliTerm {
+                indices: vec![0],
+                symbols: vec!["X".to_string()],
+            },
+            clifford: "H 0".to_string(),
+        };
+        assert!(matches!(
+            Identity.conjugate_pauli(request),
+            Err(quilc::Error::QuilcCompilation(
+                CompilationError::Unsupported {
+                    operation: "conjugate_pauli"
+                }
+            ))
+        ));
+    }
+}
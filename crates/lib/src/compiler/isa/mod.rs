@@ -10,6 +10,9 @@ use qubit::{FrbSim1q, Qubit};
 mod edge;
 mod operator;
 mod qubit;
+mod specs;
+
+pub(crate) use specs::Specs;
 
 /// Restructuring of an [`InstructionSetArchitecture`] for sending to quilc
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
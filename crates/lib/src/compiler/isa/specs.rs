@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use qcs_api_client_openapi::models::{Characteristic, InstructionSetArchitecture};
+
+use crate::qpu::isa::sites::IsaSites;
+
+/// The measured characteristics of each qubit and edge of a device, such as gate and readout
+/// fidelities, in the form pyQuil sent them to quilc as the `specs` of a target device.
+///
+/// Each characteristic is named as in the ISA, apart from the 1Q randomized benchmarking fidelity
+/// (`f1QRB`, the simultaneous benchmark's `fRB` or else the individual one) and the active reset
+/// fidelity (`fActiveReset`), and its error, if known, is included as `<name>_std_err`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub(crate) struct Specs {
+    #[serde(rename = "1Q", default, skip_serializing_if = "HashMap::is_empty")]
+    qubits: HashMap<String, HashMap<String, f64>>,
+    #[serde(rename = "2Q", default, skip_serializing_if = "HashMap::is_empty")]
+    edges: HashMap<String, HashMap<String, f64>>,
+}
+
+impl Specs {
+    /// Collect the characteristics of each qubit and edge in `isa`. Characteristics of other
+    /// sites, and of qubits or edges which aren't well formed, are ignored.
+    pub(crate) fn from_isa(isa: &InstructionSetArchitecture) -> Self {
+        let sites = IsaSites::new(isa);
+        let mut specs = Self::default();
+
+        for (&qubit, characteristic) in &sites.rb_fidelities {
+            specs.insert_qubit(qubit, "f1QRB", characteristic);
+        }
+        for (&qubit, characteristics) in &sites.qubit_characteristics {
+            for characteristic in characteristics {
+                specs.insert_qubit(qubit, spec_name(&characteristic.name), characteristic);
+            }
+        }
+        for (&edge, characteristics) in &sites.edge_characteristics {
+            for characteristic in characteristics {
+                specs.insert_edge(edge, spec_name(&characteristic.name), characteristic);
+            }
+        }
+
+        specs
+    }
+
+    fn insert_qubit(&mut self, qubit: u64, name: &str, characteristic: &Characteristic) {
+        let values = self.qubits.entry(qubit.to_string()).or_default();
+        insert(values, name, characteristic);
+    }
+
+    fn insert_edge(&mut self, (a, b): (u64, u64), name: &str, characteristic: &Characteristic) {
+        let values = self.edges.entry(format!("{a}-{b}")).or_default();
+        insert(values, name, characteristic);
+    }
+}
+
+/// The name quilc expects for the ISA characteristic `name`.
+fn spec_name(name: &str) -> &str {
+    match name {
+        "fAR" => "fActiveReset",
+        name => name,
+    }
+}
+
+/// Record `characteristic` as `name`, unless a value was already recorded for it. A
+/// characteristic measured at several parameter values, such as `fXY`, keeps the first.
+fn insert(values: &mut HashMap<String, f64>, name: &str, characteristic: &Characteristic) {
+    values
+        .entry(name.to_string())
+        .or_insert(characteristic.value);
+    if let Some(error) = characteristic.error {
+        values.entry(format!("{name}_std_err")).or_insert(error);
+    }
+}
+
+#[cfg(test)]
+mod describe_specs {
+    use super::Specs;
+    use crate::test_support::aspen_9_isa;
+
+    #[test]
+    fn it_collects_qubit_and_edge_characteristics() {
+        let specs = Specs::from_isa(&aspen_9_isa());
+
+        let qubit = &specs.qubits["0"];
+        assert!((qubit["f1QRB"] - 0.995_691_124_137_083_8).abs() < f64::EPSILON);
+        assert!(qubit.contains_key("f1QRB_std_err"));
+        assert!(qubit.contains_key("fRO"));
+        assert!(qubit.contains_key("fActiveReset"));
+        assert!(!qubit.contains_key("fRO_std_err"));
+
+        let edge = specs.edges.values().find(|edge| edge.contains_key("fCZ"));
+        assert!(edge.unwrap().contains_key("fCZ_std_err"));
+        assert!(specs.edges.keys().all(|id| id.contains('-')));
+    }
+
+    #[test]
+    fn it_serializes_empty_specs_as_an_empty_object() {
+        assert_eq!(
+            serde_json::to_value(Specs::default()).unwrap(),
+            serde_json::json!({})
+        );
+    }
+}
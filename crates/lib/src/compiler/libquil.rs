@@ -5,6 +5,10 @@ use std::ffi::NulError;
 use std::num::TryFromIntError;
 use std::str::FromStr;
 use std::string::String;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
 use std::{convert::TryFrom, ffi::CString};
 
 use super::quilc::{self, NativeQuilMetadata};
@@ -27,6 +31,16 @@ pub enum Error {
     /// Error when creating a [`CString`]
     #[error("error when creating CString: {0}")]
     CString(#[from] NulError),
+    /// The compilation didn't finish within the timeout set with
+    /// [`CompilerOpts::with_timeout`](quilc::CompilerOpts::with_timeout).
+    #[error("compilation timed out after {elapsed:?}")]
+    Timeout {
+        /// How long the compilation ran before it was abandoned.
+        elapsed: Duration,
+    },
+    /// libquil stopped without returning a result, e.g. because it panicked.
+    #[error("libquil stopped without returning a result")]
+    Aborted,
 }
 
 impl From<Error> for quilc::Error {
@@ -54,38 +68,109 @@ impl From<libquil_sys::quilc::CompilationMetadata> for NativeQuilMetadata {
 #[derive(Debug, Clone, Copy)]
 pub struct Client;
 
+/// Compile `quil` for the chip described by `isa`, returning the native Quil and its metadata.
+fn compile(
+    quil: &str,
+    isa: &str,
+    protoquil: bool,
+) -> Result<(String, Option<libquil_sys::quilc::CompilationMetadata>), Error> {
+    let program = libquil_sys::quilc::Program::from_str(quil)?;
+    let chip = libquil_sys::quilc::Chip::from_str(isa)?;
+
+    let compilation_result = if protoquil {
+        libquil_sys::quilc::compile_protoquil(&program, &chip)
+    } else {
+        libquil_sys::quilc::compile_program(&program, &chip)
+    }?;
+
+    Ok((
+        compilation_result.program.to_string()?,
+        compilation_result.metadata,
+    ))
+}
+
+/// Held for every call into libquil, which isn't known to be safe to call from several threads at
+/// once.
+static LIBQUIL: Mutex<()> = Mutex::new(());
+
+/// Run `operation` once no other libquil call is running.
+fn serialized<T>(operation: impl FnOnce() -> T) -> T {
+    let _guard = LIBQUIL.lock().unwrap_or_else(PoisonError::into_inner);
+    operation()
+}
+
+/// Run `operation` with [`serialized`], failing with [`Error::Timeout`] if it doesn't finish
+/// within `timeout` seconds, including any time spent waiting for other libquil calls.
+///
+/// libquil can't be interrupted, so with a timeout the operation runs on its own thread. If it's
+/// abandoned after starting, that thread is left to finish in the background, holding up later
+/// libquil calls until it does; if it's abandoned before starting, it never runs. So at most one
+/// abandoned operation is running at any time.
+fn with_timeout<T, F>(timeout: Option<f64>, operation: F) -> Result<T, Error>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, Error> + Send + 'static,
+{
+    let timeout = match timeout.and_then(|seconds| Duration::try_from_secs_f64(seconds).ok()) {
+        Some(timeout) => timeout,
+        None => return serialized(operation),
+    };
+
+    let started = Instant::now();
+    let abandoned = Arc::new(AtomicBool::new(false));
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn({
+        let abandoned = abandoned.clone();
+        move || {
+            serialized(|| {
+                if abandoned.load(Ordering::SeqCst) {
+                    return;
+                }
+                // The receiver is gone if the operation was abandoned while running, in which
+                // case there is nobody to tell.
+                let _ = sender.send(operation());
+            });
+        }
+    });
+    match receiver.recv_timeout(timeout) {
+        Ok(result) => result,
+        Err(RecvTimeoutError::Timeout) => {
+            abandoned.store(true, Ordering::SeqCst);
+            Err(Error::Timeout {
+                elapsed: started.elapsed(),
+            })
+        }
+        Err(RecvTimeoutError::Disconnected) => Err(Error::Aborted),
+    }
+}
+
 impl quilc::Client for Client {
+    /// Compile `quil` with libquil, honoring the timeout and protoquil setting of `options` as the
+    /// RPCQ client does. The default for protoquil is off, as it is for quilc.
     fn compile_program(
         &self,
         quil: &str,
         isa: quilc::TargetDevice,
         options: quilc::CompilerOpts,
     ) -> Result<quilc::CompilationResult, quilc::Error> {
-        let program = libquil_sys::quilc::Program::from_str(quil).map_err(Error::from)?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(compiler_options=?options, "compiling quil program with quilc (libquil)");
+        let quil = quil.to_string();
         let isa = serde_json::to_string(&isa).map_err(Error::from)?;
-        let chip = libquil_sys::quilc::Chip::from_str(&isa).map_err(Error::from)?;
+        let protoquil = options.protoquil().unwrap_or(false);
 
-        let compilation_result = if options.protoquil.unwrap_or(false) {
-            libquil_sys::quilc::compile_protoquil(&program, &chip)
-        } else {
-            libquil_sys::quilc::compile_program(&program, &chip)
-        }
-        .map_err(Error::from)?;
+        let (native_quil, metadata) =
+            with_timeout(options.timeout(), move || compile(&quil, &isa, protoquil))?;
 
-        let program = compilation_result
-            .program
-            .to_string()
-            .map_err(Error::from)?
-            .parse()
-            .map_err(Error::from)?;
+        let program = native_quil.parse().map_err(Error::from)?;
         Ok(quilc::CompilationResult {
             program,
-            native_quil_metadata: compilation_result.metadata.map(Into::into),
+            native_quil_metadata: metadata.map(Into::into),
         })
     }
 
     fn get_version_info(&self) -> Result<String, quilc::Error> {
-        libquil_sys::quilc::get_version_info()
+        serialized(libquil_sys::quilc::get_version_info)
             .map(|info| info.version)
             .map_err(|e| Error::from(e).into())
     }
@@ -101,17 +186,17 @@ impl quilc::Client for Client {
             .map(CString::new)
             .collect::<Result<_, _>>()
             .map_err(Error::from)?;
-        let result = libquil_sys::quilc::conjugate_pauli_by_clifford(
-            request
-                .pauli
-                .indices
-                .into_iter()
-                .map(u32::try_from)
-                .collect::<Result<_, _>>()
-                .map_err(Error::from)?,
-            pauli_terms,
-            &request.clifford.parse().map_err(Error::from)?,
-        )
+        let indices = request
+            .pauli
+            .indices
+            .into_iter()
+            .map(u32::try_from)
+            .collect::<Result<_, _>>()
+            .map_err(Error::from)?;
+        let clifford = request.clifford.parse().map_err(Error::from)?;
+        let result = serialized(|| {
+            libquil_sys::quilc::conjugate_pauli_by_clifford(indices, pauli_terms, &clifford)
+        })
         .map_err(Error::from)?;
         Ok(quilc::ConjugatePauliByCliffordResponse {
             phase: i64::from(result.phase),
@@ -141,13 +226,17 @@ impl quilc::Client for Client {
             .map(i32::try_from)
             .transpose()
             .map_err(Error::from)?;
-        let result = libquil_sys::quilc::generate_rb_sequence(
-            request.depth.try_into().map_err(Error::from)?,
-            request.qubits.try_into().map_err(Error::from)?,
-            gateset,
-            seed,
-            interleaver.as_ref(),
-        )
+        let depth = request.depth.try_into().map_err(Error::from)?;
+        let qubits = request.qubits.try_into().map_err(Error::from)?;
+        let result = serialized(|| {
+            libquil_sys::quilc::generate_rb_sequence(
+                depth,
+                qubits,
+                gateset,
+                seed,
+                interleaver.as_ref(),
+            )
+        })
         .map_err(Error::from)?;
         Ok(quilc::GenerateRandomizedBenchmarkingSequenceResponse {
             sequence: result
@@ -158,7 +247,7 @@ impl quilc::Client for Client {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "qvm-http"))]
 mod test {
     use crate::{
         compiler::quilc::{
@@ -167,25 +256,17 @@ mod test {
             PauliTerm, RandomizedBenchmarkingRequest, TargetDevice,
         },
         qvm::{self, http::AddressRequest},
+        test_support::{aspen_9_isa, qvm_isa},
     };
 
     use super::*;
 
-    use qcs_api_client_openapi::models::InstructionSetArchitecture;
     use quil_rs::quil::Quil;
     use regex::Regex;
-    use std::{collections::HashMap, fs::File, num::NonZeroU16};
+    use std::{collections::HashMap, num::NonZeroU16};
 
     const EXPECTED_H0_OUTPUT: &str = "MEASURE 0\n";
 
-    fn aspen_9_isa() -> InstructionSetArchitecture {
-        serde_json::from_reader(File::open("tests/aspen_9_isa.json").unwrap()).unwrap()
-    }
-
-    pub(crate) fn qvm_isa() -> InstructionSetArchitecture {
-        serde_json::from_reader(File::open("tests/qvm_isa.json").unwrap()).unwrap()
-    }
-
     fn quilc_client() -> Client {
         Client {}
     }
@@ -345,3 +426,179 @@ MEASURE 1 ro[1]
         );
     }
 }
+
+#[cfg(test)]
+mod describe_with_timeout {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use super::{with_timeout, Error};
+
+    #[test]
+    fn it_returns_results_which_finish_in_time() {
+        assert_eq!(with_timeout(None, || Ok(1)).unwrap(), 1);
+        assert_eq!(with_timeout(Some(10.0), || Ok(2)).unwrap(), 2);
+    }
+
+    #[test]
+    fn it_abandons_operations_which_take_too_long() {
+        let result = with_timeout(Some(0.01), || {
+            std::thread::sleep(Duration::from_millis(500));
+            Ok(())
+        });
+        assert!(matches!(result, Err(Error::Timeout { .. })));
+    }
+
+    #[test]
+    fn it_reports_operations_which_stop_without_a_result() {
+        let result: Result<(), _> = with_timeout(Some(10.0), || panic!("libquil crashed"));
+        assert!(matches!(result, Err(Error::Aborted)));
+    }
+
+    #[test]
+    fn it_never_starts_operations_abandoned_while_waiting_for_another() {
+        let running = std::thread::spawn(|| {
+            with_timeout(None, || {
+                std::thread::sleep(Duration::from_millis(300));
+                Ok(())
+            })
+        });
+        std::thread::sleep(Duration::from_millis(50));
+
+        let started = Arc::new(AtomicBool::new(false));
+        let result = with_timeout(Some(0.01), {
+            let started = started.clone();
+            move || {
+                started.store(true, Ordering::SeqCst);
+                Ok(())
+            }
+        });
+        assert!(matches!(result, Err(Error::Timeout { .. })));
+
+        running.join().unwrap().unwrap();
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(!started.load(Ordering::SeqCst));
+    }
+}
+
+/// Compile the same programs with libquil and with quilc over RPCQ, and check that their native
+/// Quil has the same shape. Requires a running quilc.
+#[cfg(all(test, feature = "quilc-rpcq"))]
+mod describe_rpcq_conformance {
+    use std::collections::HashSet;
+    use std::convert::TryFrom;
+
+    use quil_rs::instruction::Instruction;
+    use quil_rs::quil::Quil;
+    use quil_rs::Program;
+
+    use crate::client::Qcs;
+    use crate::compiler::quilc::{Client as _, CompilationResult, CompilerOpts, TargetDevice};
+    use crate::compiler::rpcq;
+    use crate::test_support::aspen_9_isa;
+
+    const NATIVE_GATES: [&str; 5] = ["RX", "RZ", "CZ", "CPHASE", "XY"];
+
+    const PROGRAMS: [&str; 3] = [
+        "DECLARE ro BIT[1]\nMEASURE 0 ro[0]\n",
+        "DECLARE ro BIT[2]\nH 0\nCNOT 0 1\nMEASURE 0 ro[0]\nMEASURE 1 ro[1]\n",
+        "DECLARE theta REAL[1]\nDECLARE ro BIT[1]\nRX(theta[0]) 0\nMEASURE 0 ro[0]\n",
+    ];
+
+    fn compile_with_both(
+        quil: &str,
+        options: CompilerOpts,
+    ) -> (CompilationResult, CompilationResult) {
+        let target = || TargetDevice::try_from(aspen_9_isa()).unwrap();
+        let libquil = super::Client
+            .compile_program(quil, target(), options)
+            .expect("libquil should compile the program");
+        let endpoint = Qcs::load().get_config().quilc_url().to_string();
+        let rpcq = rpcq::Client::new(&endpoint)
+            .unwrap()
+            .compile_program(quil, target(), options)
+            .expect("quilc should compile the program");
+        (libquil, rpcq)
+    }
+
+    fn gate_names(program: &Program) -> HashSet<String> {
+        program
+            .body_instructions()
+            .filter_map(|instruction| match instruction {
+                Instruction::Gate(gate) => Some(gate.name.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn measurements(program: &Program) -> HashSet<String> {
+        program
+            .body_instructions()
+            .filter_map(|instruction| match instruction {
+                Instruction::Measurement(measurement) => {
+                    measurement.target.as_ref().map(Quil::to_quil_or_debug)
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn it_compiles_to_the_same_shape_as_rpcq() {
+        for protoquil in [None, Some(true)] {
+            let options = CompilerOpts::default().with_protoquil(protoquil);
+            for quil in PROGRAMS {
+                let (libquil, rpcq) = compile_with_both(quil, options);
+
+                assert_eq!(
+                    libquil.program.memory_regions, rpcq.program.memory_regions,
+                    "{quil}"
+                );
+                assert_eq!(
+                    measurements(&libquil.program),
+                    measurements(&rpcq.program),
+                    "{quil}"
+                );
+                for result in [&libquil, &rpcq] {
+                    let unexpected: Vec<_> = gate_names(&result.program)
+                        .into_iter()
+                        .filter(|name| !NATIVE_GATES.contains(&name.as_str()))
+                        .collect();
+                    assert!(unexpected.is_empty(), "{quil}: {unexpected:?}");
+                }
+                if protoquil == Some(true) {
+                    assert!(libquil.native_quil_metadata.is_some(), "{quil}");
+                    assert!(rpcq.native_quil_metadata.is_some(), "{quil}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn it_reports_versions_in_the_same_format() {
+        let libquil = super::Client.get_version_info().unwrap();
+        let endpoint = Qcs::load().get_config().quilc_url().to_string();
+        let rpcq = rpcq::Client::new(&endpoint)
+            .unwrap()
+            .get_version_info()
+            .unwrap();
+        assert_eq!(
+            release_numbers(&libquil),
+            release_numbers(&rpcq),
+            "libquil reports {libquil}, quilc reports {rpcq}"
+        );
+    }
+
+    /// The numbers of a release version, e.g. `[1, 26, 0]` for `1.26.0`.
+    fn release_numbers(version: &str) -> Vec<u64> {
+        version
+            .split('.')
+            .map(|number| {
+                number
+                    .parse()
+                    .unwrap_or_else(|_| panic!("{version} is not a release version"))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,284 @@
+//! An in-process stand-in for a quilc RPCQ server, so that the compile path can be tested without
+//! running quilc. See [`MockQuilc`].
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+use serde::Deserialize;
+use serde_json::json;
+use zmq::{Context, Socket, SocketType};
+
+use super::rpcq::{self, decode_request, encode_error_reply, encode_reply, DecodedRequest};
+
+/// The version a [`MockQuilc`] reports from `get_version_info`.
+pub const MOCK_QUILC_VERSION: &str = "0.0.0-mock";
+
+/// How long the server waits for a request before checking whether it has been stopped, in
+/// milliseconds.
+const POLL_INTERVAL_MS: i32 = 50;
+
+/// The function a [`MockQuilc`] applies to each program it "compiles". Returning an error makes
+/// the server reply with that error message.
+pub type Transform = dyn Fn(&str) -> Result<String, String> + Send + Sync;
+
+/// A quilc RPCQ server which runs on a background thread of this process.
+///
+/// It answers `quil_to_native_quil` by applying a [`Transform`] to the program, by default
+/// returning it unchanged, and `get_version_info` with [`MOCK_QUILC_VERSION`]. Any other method
+/// gets an error reply. No metadata is reported for compiled programs.
+///
+/// The server listens on a random local port until it is dropped.
+///
+/// ```
+/// use qcs::compiler::{mock_quilc::MockQuilc, quilc::Client};
+///
+/// let quilc = MockQuilc::start().unwrap();
+/// let version = quilc.client().get_version_info().unwrap();
+/// assert_eq!(version, qcs::compiler::mock_quilc::MOCK_QUILC_VERSION);
+/// ```
+pub struct MockQuilc {
+    endpoint: String,
+    requests: Arc<AtomicUsize>,
+    stopped: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl std::fmt::Debug for MockQuilc {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "mock quilc at {}", self.endpoint)
+    }
+}
+
+impl MockQuilc {
+    /// Start a server which returns programs unchanged.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the server's socket can't be created or bound.
+    pub fn start() -> Result<Self, rpcq::Error> {
+        Self::start_with_transform(|quil| Ok(quil.to_string()))
+    }
+
+    /// Start a server which "compiles" programs with `transform`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the server's socket can't be created or bound.
+    pub fn start_with_transform<F>(transform: F) -> Result<Self, rpcq::Error>
+    where
+        F: Fn(&str) -> Result<String, String> + Send + Sync + 'static,
+    {
+        let socket = Context::new()
+            .socket(SocketType::ROUTER)
+            .map_err(rpcq::Error::SocketCreation)?;
+        socket
+            .bind("tcp://127.0.0.1:*")
+            .map_err(rpcq::Error::Communication)?;
+        socket
+            .set_rcvtimeo(POLL_INTERVAL_MS)
+            .map_err(rpcq::Error::Communication)?;
+        socket.set_linger(0).map_err(rpcq::Error::Communication)?;
+        let endpoint = socket
+            .get_last_endpoint()
+            .map_err(rpcq::Error::Communication)?
+            .unwrap_or_else(|bytes| String::from_utf8_lossy(&bytes).into_owned());
+
+        let requests = Arc::new(AtomicUsize::new(0));
+        let stopped = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let requests = requests.clone();
+            let stopped = stopped.clone();
+            std::thread::spawn(move || serve(&socket, &transform, &requests, &stopped))
+        };
+
+        Ok(Self {
+            endpoint,
+            requests,
+            stopped,
+            thread: Some(thread),
+        })
+    }
+
+    /// The endpoint the server listens on, such as `tcp://127.0.0.1:49152`.
+    #[must_use]
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// An RPCQ client connected to this server.
+    ///
+    /// # Panics
+    ///
+    /// Never: creating a client doesn't connect to the server.
+    #[must_use]
+    pub fn client(&self) -> rpcq::Client {
+        rpcq::Client::new(&self.endpoint).expect("creating an RPCQ client should not fail")
+    }
+
+    /// The number of requests the server has answered, including those answered with an error.
+    #[must_use]
+    pub fn request_count(&self) -> usize {
+        self.requests.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for MockQuilc {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Answer requests on `socket` until `stopped` is set.
+fn serve(socket: &Socket, transform: &Transform, requests: &AtomicUsize, stopped: &AtomicBool) {
+    while !stopped.load(Ordering::SeqCst) {
+        let frames = match socket.recv_multipart(0) {
+            Ok(frames) => frames,
+            Err(zmq::Error::EAGAIN) => continue,
+            Err(_) => break,
+        };
+        // A DEALER client sends only the request; the ROUTER socket prepends its identity.
+        let [identity, request] = frames.as_slice() else {
+            continue;
+        };
+        let Some(reply) = reply(request, transform) else {
+            continue;
+        };
+        requests.fetch_add(1, Ordering::SeqCst);
+        if socket
+            .send_multipart([identity.as_slice(), reply.as_slice()], 0)
+            .is_err()
+        {
+            break;
+        }
+    }
+}
+
+/// The encoded reply to `request`, or `None` if it isn't an RPCQ request and so can't be answered.
+fn reply(request: &[u8], transform: &Transform) -> Option<Vec<u8>> {
+    let request: DecodedRequest = decode_request(request).ok()?;
+    let result = match request.method.as_str() {
+        "quil_to_native_quil" => serde_json::from_value::<CompileParams>(request.params)
+            .map_err(|error| format!("invalid quil_to_native_quil params: {error}"))
+            .and_then(|params| transform(&params.args.0.quil))
+            .map(|quil| json!({ "quil": quil, "metadata": null })),
+        "get_version_info" => Ok(json!({ "quilc": MOCK_QUILC_VERSION, "githash": "mock" })),
+        method => Err(format!("mock quilc does not implement {method}")),
+    };
+    let reply = match result {
+        Ok(result) => encode_reply(&request.id, &result),
+        Err(error) => encode_error_reply(&request.id, &error),
+    };
+    reply.ok()
+}
+
+/// The params of a `quil_to_native_quil` request, as far as the mock needs them.
+#[derive(Deserialize)]
+struct CompileParams {
+    #[serde(rename = "*args")]
+    args: (NativeQuilRequest,),
+}
+
+#[derive(Deserialize)]
+struct NativeQuilRequest {
+    quil: String,
+}
+
+#[cfg(test)]
+mod describe_mock_quilc {
+    use std::{convert::TryFrom, str::FromStr, time::Duration};
+
+    use quil_rs::Program;
+
+    use super::{MockQuilc, MOCK_QUILC_VERSION};
+    use crate::compiler::quilc::{self, Client, CompilationError, CompilerOpts, TargetDevice};
+    use crate::compiler::rpcq;
+    use crate::test_support::qvm_isa;
+
+    fn target() -> TargetDevice {
+        TargetDevice::try_from(qvm_isa()).unwrap()
+    }
+
+    #[test]
+    fn it_reports_its_version() {
+        let quilc = MockQuilc::start().unwrap();
+        assert_eq!(
+            quilc.client().get_version_info().unwrap(),
+            MOCK_QUILC_VERSION
+        );
+        assert_eq!(quilc.request_count(), 1);
+    }
+
+    #[test]
+    fn it_echoes_programs_by_default() {
+        let quilc = MockQuilc::start().unwrap();
+        let result = quilc
+            .client()
+            .compile_program(
+                "DECLARE ro BIT\nX 0\nMEASURE 0 ro",
+                target(),
+                CompilerOpts::default(),
+            )
+            .unwrap();
+        assert_eq!(
+            result.program,
+            Program::from_str("DECLARE ro BIT\nX 0\nMEASURE 0 ro").unwrap()
+        );
+        assert_eq!(result.native_quil_metadata, None);
+    }
+
+    #[test]
+    fn it_applies_its_transform() {
+        let quilc = MockQuilc::start_with_transform(|quil| {
+            if quil.contains("CNOT") {
+                Err("CNOT is not native".to_string())
+            } else {
+                Ok(quil.replace("X 0", "RX(pi) 0"))
+            }
+        })
+        .unwrap();
+        let client = quilc.client();
+
+        let result = client
+            .compile_program("X 0", target(), CompilerOpts::default())
+            .unwrap();
+        assert_eq!(result.program, Program::from_str("RX(pi) 0").unwrap());
+
+        let error = client
+            .compile_program("CNOT 0 1", target(), CompilerOpts::default())
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            quilc::Error::QuilcCompilation(CompilationError::Rpcq(_))
+        ));
+        assert_eq!(quilc.request_count(), 2);
+    }
+
+    #[test]
+    fn it_stops_waiting_at_the_deadline() {
+        let quilc = MockQuilc::start_with_transform(|quil| {
+            std::thread::sleep(Duration::from_millis(500));
+            Ok(quil.to_string())
+        })
+        .unwrap();
+
+        let error = quilc
+            .client()
+            .compile_program(
+                "X 0",
+                target(),
+                CompilerOpts::default().with_timeout(Some(0.05)),
+            )
+            .unwrap_err();
+        match error {
+            quilc::Error::QuilcConnection(_, rpcq::Error::Timeout { elapsed }) => {
+                assert!(elapsed >= Duration::from_millis(50));
+                assert!(elapsed < Duration::from_millis(500));
+            }
+            error => panic!("expected a timeout, got {error:?}"),
+        }
+    }
+}
@@ -1,8 +1,15 @@
 //! This module contains functionality used to compile Quil programs for
 //! execution on QCS quantum processors.
 
+mod backend;
 mod isa;
 #[cfg(feature = "libquil")]
 pub mod libquil;
+#[cfg(feature = "mock-quilc")]
+pub mod mock_quilc;
 pub mod quilc;
+#[cfg(feature = "quilc-rpcq")]
 pub mod rpcq;
+pub mod topology;
+
+pub use backend::CompilerBackend;
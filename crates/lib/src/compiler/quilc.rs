@@ -3,13 +3,21 @@
 use std::collections::HashMap;
 use std::convert::TryFrom;
 
+use quil_rs::instruction::{Instruction, Qubit};
 use quil_rs::program::{Program, ProgramError};
 use serde::{Deserialize, Deserializer, Serialize};
 
 use qcs_api_client_openapi::models::InstructionSetArchitecture;
 
-use super::isa::{self, Compiler};
+use super::backend::CompilerBackend;
+use super::isa::{self, Compiler, Specs};
+#[cfg(feature = "quilc-rpcq")]
 use super::rpcq;
+use super::topology::{restrict_isa, Topology};
+
+mod cache;
+
+pub use cache::{CachedCompiler, CompilationCache, DEFAULT_COMPILATION_CACHE_SIZE};
 
 /// Number of seconds to wait before timing out.
 pub const DEFAULT_COMPILER_TIMEOUT: f64 = 30.0;
@@ -73,6 +81,10 @@ pub struct CompilerOpts {
     /// If the compiler should produce "protoquil" as output. If `None`, the default
     /// behavior configured in the compiler service is used.
     pub(crate) protoquil: Option<bool>,
+
+    /// If the device's measured characteristics should be sent to the compiler, see
+    /// [`CompilerOpts::with_specs`].
+    pub(crate) specs: bool,
 }
 
 /// Functions for building a [`CompilerOpts`] instance
@@ -84,10 +96,14 @@ impl CompilerOpts {
         Self {
             timeout: None,
             protoquil: None,
+            specs: false,
         }
     }
 
     /// Set the number of seconds to wait before timing out. If set to None, the timeout is disabled.
+    ///
+    /// The timeout is sent to quilc with each request, so the server gives up on compilations which
+    /// take too long, and the RPCQ clients stop waiting for a reply at the same deadline.
     #[must_use]
     pub fn with_timeout(&mut self, seconds: Option<f64>) -> Self {
         self.timeout = seconds;
@@ -101,6 +117,34 @@ impl CompilerOpts {
         self.protoquil = protoquil;
         *self
     }
+
+    /// Set to send the fidelities measured for each qubit and edge of the device to the compiler,
+    /// as the `specs` of the [`TargetDevice`], so that it can prefer the best qubits and gates.
+    /// This is off by default. See [`TargetDevice::from_isa_with_specs`].
+    #[must_use]
+    pub fn with_specs(&mut self, specs: bool) -> Self {
+        self.specs = specs;
+        *self
+    }
+
+    /// The number of seconds to wait before timing out, or `None` if there is no timeout.
+    #[must_use]
+    pub fn timeout(&self) -> Option<f64> {
+        self.timeout
+    }
+
+    /// Whether the compiler should produce "protoquil" as output, or `None` to use the default
+    /// configured in the compiler service.
+    #[must_use]
+    pub fn protoquil(&self) -> Option<bool> {
+        self.protoquil
+    }
+
+    /// Whether the device's measured characteristics are sent to the compiler.
+    #[must_use]
+    pub fn specs(&self) -> bool {
+        self.specs
+    }
 }
 
 impl Default for CompilerOpts {
@@ -110,6 +154,7 @@ impl Default for CompilerOpts {
         Self {
             timeout: Some(DEFAULT_COMPILER_TIMEOUT),
             protoquil: None,
+            specs: false,
         }
     }
 }
@@ -150,7 +195,7 @@ impl From<ConjugateByCliffordRequest> for ConjugatePauliByCliffordRequest {
 }
 
 /// Conjugate Pauli by Clifford response.
-#[derive(Clone, Deserialize, Debug, PartialEq, PartialOrd)]
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, PartialOrd)]
 pub struct ConjugatePauliByCliffordResponse {
     /// Encoded global phase factor on the emitted Pauli.
     pub phase: i64,
@@ -193,7 +238,7 @@ impl From<RandomizedBenchmarkingRequest> for GenerateRandomizedBenchmarkingSeque
 }
 
 /// Randomly generated benchmarking sequence response.
-#[derive(Clone, Deserialize, Debug, PartialEq, PartialOrd)]
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, PartialOrd)]
 pub struct GenerateRandomizedBenchmarkingSequenceResponse {
     /// List of Cliffords, each expressed as a list of generator indices.
     pub sequence: Vec<Vec<i64>>,
@@ -201,6 +246,7 @@ pub struct GenerateRandomizedBenchmarkingSequenceResponse {
 
 /// All of the errors that can occur within this module.
 #[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
 pub enum Error {
     /// An ISA-related error.
     #[error(
@@ -208,6 +254,7 @@ pub enum Error {
     )]
     Isa(#[from] isa::Error),
     /// An error when trying to connect to quilc.
+    #[cfg(feature = "quilc-rpcq")]
     #[error("Problem connecting to quilc at {0}: {1}")]
     QuilcConnection(String, #[source] rpcq::Error),
     /// An error when trying to compile using quilc.
@@ -216,6 +263,9 @@ pub enum Error {
     /// An error when trying to parse the compiled program.
     #[error("Problem when trying to parse the compiled program: {0}")]
     Parse(ProgramError),
+    /// A request was rejected before it was sent to quilc because quilc couldn't fulfil it.
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
 }
 
 /// Errors during compilation with one of the supported clients
@@ -227,8 +277,404 @@ pub enum CompilationError {
     #[error("compilation error from libquil: {0}")]
     Libquil(crate::compiler::libquil::Error),
     /// Errors during compilation when using RPCQ
+    #[cfg(feature = "quilc-rpcq")]
     #[error("compilation error from RPCQ: {0}")]
     Rpcq(rpcq::Error),
+    /// Errors during compilation when using a custom
+    /// [`CompilerBackend`](crate::compiler::CompilerBackend)
+    #[error("compilation error from compiler backend: {0}")]
+    Backend(Box<dyn std::error::Error + Send + Sync>),
+    /// The [`CompilerBackend`](crate::compiler::CompilerBackend) doesn't support an operation
+    #[error("the compiler backend does not support {operation}")]
+    Unsupported {
+        /// The name of the unsupported method
+        operation: &'static str,
+    },
+}
+
+impl Error {
+    /// Classify the failure if this is an [`Error::QuilcCompilation`]. See [`QuilcErrorKind`].
+    #[must_use]
+    pub fn compilation_error_kind(&self) -> Option<QuilcErrorKind> {
+        match self {
+            Self::QuilcCompilation(error) => Some(error.kind()),
+            _ => None,
+        }
+    }
+}
+
+impl CompilationError {
+    /// Classify the failure from the message quilc reported. See [`QuilcErrorKind`].
+    #[must_use]
+    pub fn kind(&self) -> QuilcErrorKind {
+        QuilcErrorKind::from_message(&self.to_string())
+    }
+}
+
+/// The category of a quilc compilation failure, parsed from the message quilc reported.
+///
+/// Classification is best-effort: quilc's messages are meant for people and may change between
+/// versions, so anything unrecognized is [`QuilcErrorKind::Other`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum QuilcErrorKind {
+    /// quilc couldn't make sense of the target device, e.g. because it has no usable qubits.
+    UnresolvableChipSpec,
+    /// The addresser couldn't place the program's qubits onto the device.
+    AddresserFailure {
+        /// The qubits named in the message, if any.
+        qubits: Vec<u64>,
+    },
+    /// There is no path on the device between qubits which the program needs to interact.
+    NoPath {
+        /// The qubits named in the message, if any.
+        qubits: Vec<u64>,
+    },
+    /// A gate in the program can't be compiled to the device's native gates.
+    UnsupportedGate {
+        /// The name of the gate, if the message included it.
+        gate: Option<String>,
+        /// The qubits named in the message, if any.
+        qubits: Vec<u64>,
+    },
+    /// quilc couldn't parse the program.
+    Syntax,
+    /// Any other failure.
+    Other,
+}
+
+impl QuilcErrorKind {
+    /// Classify a quilc error message.
+    #[must_use]
+    pub fn from_message(message: &str) -> Self {
+        let lowercase = message.to_ascii_lowercase();
+        let mentions = |phrases: &[&str]| phrases.iter().any(|phrase| lowercase.contains(phrase));
+
+        if mentions(&["chip spec", "chip-spec"]) {
+            Self::UnresolvableChipSpec
+        } else if mentions(&["no path between", "no route between", "not connected"]) {
+            Self::NoPath {
+                qubits: find_qubits(&lowercase),
+            }
+        } else if mentions(&["addresser", "addressing", "rewiring"]) {
+            Self::AddresserFailure {
+                qubits: find_qubits(&lowercase),
+            }
+        } else if mentions(&[
+            "no compiler for",
+            "no applicable compiler",
+            "unable to compile",
+            "could not compile",
+            "unknown gate",
+        ]) {
+            Self::UnsupportedGate {
+                gate: find_gate(message, &lowercase),
+                qubits: find_qubits(&lowercase),
+            }
+        } else if mentions(&["parse error", "syntax error", "unexpected token"]) {
+            Self::Syntax
+        } else {
+            Self::Other
+        }
+    }
+
+    /// The qubits named in the message, if any.
+    #[must_use]
+    pub fn qubits(&self) -> &[u64] {
+        match self {
+            Self::AddresserFailure { qubits }
+            | Self::NoPath { qubits }
+            | Self::UnsupportedGate { qubits, .. } => qubits,
+            _ => &[],
+        }
+    }
+}
+
+/// Find the qubit indices following each occurrence of "qubit" or "qubits" in a lowercase
+/// message, e.g. `[0, 5]` in "no path between qubits 0 and 5".
+fn find_qubits(lowercase: &str) -> Vec<u64> {
+    let mut qubits = Vec::new();
+    for (start, _) in lowercase.match_indices("qubit") {
+        let words = lowercase[start + "qubit".len()..]
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|word| !word.is_empty());
+        for (position, word) in words.enumerate() {
+            match word.parse::<u64>() {
+                Ok(qubit) if !qubits.contains(&qubit) => qubits.push(qubit),
+                Ok(_) => {}
+                Err(_) if word == "and" || word == "or" || (position == 0 && word == "s") => {}
+                Err(_) => break,
+            }
+        }
+    }
+    qubits
+}
+
+/// Find the gate name following the first occurrence of "gate" in a message, e.g. `FOO` in
+/// "unable to compile gate FOO 0 1". `lowercase` must be `message` in ASCII lowercase.
+fn find_gate(message: &str, lowercase: &str) -> Option<String> {
+    let start = lowercase.find("gate ")? + "gate ".len();
+    message[start..]
+        .split_whitespace()
+        .next()
+        .map(|word| word.trim_matches(|c: char| !c.is_ascii_alphanumeric() && c != '_' && c != '-'))
+        .filter(|word| !word.is_empty())
+        .map(String::from)
+}
+
+/// Where [`compile_with_qubit_reselection`] placed a program on the device.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QubitPlacement {
+    /// The device qubits compilation was restricted to, or `None` if the program compiled for the
+    /// whole device.
+    pub qubit_subset: Option<Vec<u64>>,
+    /// The physical qubit each of the program's qubits was placed on, indexed by the program's
+    /// qubit, as reported by quilc's `EXPECTED_REWIRING` pragma. `None` if quilc didn't report one.
+    pub rewiring: Option<Vec<u64>>,
+    /// The number of alternative qubit subsets tried before compilation succeeded.
+    pub reselections: u32,
+}
+
+/// Where compilation placed each of a program's qubits on the device, and how many `SWAP`s it
+/// needed to bring interacting qubits together, so that placement can be checked against the
+/// device's noise, or pinned by rewriting the program.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct PlacementReport {
+    /// The physical qubit each qubit of the original program starts on in the compiled program,
+    /// as reported by quilc's `EXPECTED_REWIRING` pragma. Qubits quilc didn't report a placement
+    /// for are left out.
+    pub logical_to_physical: HashMap<u64, u64>,
+    /// The number of `SWAP`s quilc inserted, from [`NativeQuilMetadata::topological_swaps`], or
+    /// zero if it didn't report any.
+    pub swaps_inserted: usize,
+}
+
+impl PlacementReport {
+    /// Compare the qubits used by `program` with where quilc placed them in `native`, the program
+    /// it compiled `program` to, using the `metadata` reported with the compilation if known.
+    #[must_use]
+    pub fn new(program: &Program, native: &Program, metadata: Option<&NativeQuilMetadata>) -> Self {
+        let rewiring = rewiring_entries(native).unwrap_or_default();
+        let logical_to_physical = program
+            .get_used_qubits()
+            .iter()
+            .filter_map(|qubit| match qubit {
+                Qubit::Fixed(index) => Some(*index),
+                _ => None,
+            })
+            .filter_map(|logical| {
+                let physical = usize::try_from(logical)
+                    .ok()
+                    .and_then(|index| rewiring.get(index).copied().flatten())?;
+                Some((logical, physical))
+            })
+            .collect();
+        let swaps_inserted = metadata
+            .and_then(|metadata| metadata.topological_swaps)
+            .map_or(0, |swaps| usize::try_from(swaps).unwrap_or(usize::MAX));
+        Self {
+            logical_to_physical,
+            swaps_inserted,
+        }
+    }
+}
+
+/// Compile `quil` for the device described by `isa`, retrying on a different subset of the
+/// device's qubits if compilation fails because the program's qubits couldn't be placed or
+/// connected (see [`QuilcErrorKind::AddresserFailure`] and [`QuilcErrorKind::NoPath`]).
+///
+/// Up to `max_reselections` subsets are tried, in the order chosen by
+/// [`Topology::connected_subsets`], each with one device qubit for every qubit the program uses.
+/// With `max_reselections` of zero this is the same as [`CompilerBackend::compile_to_native`],
+/// plus the reported [`QubitPlacement`].
+///
+/// # Errors
+///
+/// Returns the error from the first attempt if it wasn't a placement failure, otherwise the
+/// error from the last attempt if none succeeded.
+pub fn compile_with_qubit_reselection<C: CompilerBackend + ?Sized>(
+    client: &C,
+    quil: &str,
+    isa: &InstructionSetArchitecture,
+    options: CompilerOpts,
+    max_reselections: u32,
+) -> Result<(CompilationResult, QubitPlacement), Error> {
+    let target_device = TargetDevice::from_isa_with_options(isa.clone(), options)?;
+    let mut error = match client.compile_to_native(quil, target_device, options) {
+        Ok(result) => return Ok(placed(result, None, 0)),
+        Err(error) => error,
+    };
+    if max_reselections == 0 || !is_placement_failure(&error) {
+        return Err(error);
+    }
+    let Ok(program) = quil.parse::<Program>() else {
+        return Err(error);
+    };
+
+    let size = program
+        .get_used_qubits()
+        .iter()
+        .filter(|qubit| matches!(qubit, Qubit::Fixed(_)))
+        .count();
+    let subsets = Topology::from_isa(isa).connected_subsets(size);
+    for (reselections, subset) in (1..=max_reselections).zip(subsets) {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            ?subset,
+            %error,
+            "failed to place the program's qubits, retrying compilation on a different subset",
+        );
+        let target_device =
+            TargetDevice::from_isa_with_options(restrict_isa(isa, &subset), options)?;
+        match client.compile_to_native(quil, target_device, options) {
+            Ok(result) => return Ok(placed(result, Some(subset), reselections)),
+            Err(next) if is_placement_failure(&next) => error = next,
+            Err(next) => return Err(next),
+        }
+    }
+    Err(error)
+}
+
+/// Compile `quil` for the device described by `isa`, returning the native Quil program along with
+/// the [`NativeQuilMetadata`] reported by the compiler, such as the final rewiring of qubits and,
+/// for protoquil (see [`CompilerOpts::with_protoquil`]), the estimated runtime on the QPU.
+///
+/// If the compiler doesn't report any metadata, every field of the returned metadata is empty.
+///
+/// # Errors
+///
+/// Returns an error if `isa` can't be converted for the compiler, or compilation fails.
+pub fn compile_program_with_metadata<C: CompilerBackend + ?Sized>(
+    client: &C,
+    quil: &str,
+    isa: &InstructionSetArchitecture,
+    options: CompilerOpts,
+) -> Result<(Program, NativeQuilMetadata), Error> {
+    let target_device = TargetDevice::from_isa_with_options(isa.clone(), options)?;
+    let result = client.compile_to_native(quil, target_device, options)?;
+    Ok((
+        result.program,
+        result.native_quil_metadata.unwrap_or_default(),
+    ))
+}
+
+/// Conjugate `pauli` by the Clifford element `clifford`, a Quil program made only of Clifford
+/// gates, returning the Pauli term `clifford * pauli * clifford^\dagger` and its phase.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidRequest`] without contacting the compiler if `pauli` doesn't have one
+/// symbol (`I`, `X`, `Y`, or `Z`) for every index, otherwise any error from
+/// [`CompilerBackend::conjugate_pauli`].
+pub fn conjugate_pauli_by_clifford<C: CompilerBackend + ?Sized>(
+    client: &C,
+    pauli: PauliTerm,
+    clifford: &str,
+) -> Result<ConjugatePauliByCliffordResponse, Error> {
+    if pauli.indices.len() != pauli.symbols.len() {
+        return Err(Error::InvalidRequest(format!(
+            "the Pauli term has {} indices but {} symbols",
+            pauli.indices.len(),
+            pauli.symbols.len()
+        )));
+    }
+    if let Some(symbol) = pauli
+        .symbols
+        .iter()
+        .find(|symbol| !matches!(symbol.as_str(), "I" | "X" | "Y" | "Z"))
+    {
+        return Err(Error::InvalidRequest(format!(
+            "{symbol:?} is not a Pauli operator"
+        )));
+    }
+    client.conjugate_pauli(ConjugateByCliffordRequest {
+        pauli,
+        clifford: clifford.to_string(),
+    })
+}
+
+/// Generate a randomized benchmarking sequence, as described in
+/// [`Client::generate_randomized_benchmarking_sequence`].
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidRequest`] without contacting the compiler if the sequence would be
+/// shorter than two Cliffords, act on no qubits, or have no gates to choose from, otherwise any
+/// error from [`CompilerBackend::randomized_benchmarking_sequence`].
+pub fn generate_rb_sequence<C: CompilerBackend + ?Sized>(
+    client: &C,
+    request: RandomizedBenchmarkingRequest,
+) -> Result<GenerateRandomizedBenchmarkingSequenceResponse, Error> {
+    if request.depth < 2 {
+        return Err(Error::InvalidRequest(format!(
+            "the depth of a randomized benchmarking sequence must be at least 2, not {}",
+            request.depth
+        )));
+    }
+    if request.qubits == 0 {
+        return Err(Error::InvalidRequest(
+            "a randomized benchmarking sequence must act on at least one qubit".to_string(),
+        ));
+    }
+    if request.gateset.is_empty() {
+        return Err(Error::InvalidRequest(
+            "the gateset of a randomized benchmarking sequence must not be empty".to_string(),
+        ));
+    }
+    client.randomized_benchmarking_sequence(request)
+}
+
+fn placed(
+    result: CompilationResult,
+    qubit_subset: Option<Vec<u64>>,
+    reselections: u32,
+) -> (CompilationResult, QubitPlacement) {
+    let rewiring = find_rewiring(&result.program);
+    let placement = QubitPlacement {
+        qubit_subset,
+        rewiring,
+        reselections,
+    };
+    (result, placement)
+}
+
+fn is_placement_failure(error: &Error) -> bool {
+    matches!(
+        error.compilation_error_kind(),
+        Some(QuilcErrorKind::AddresserFailure { .. } | QuilcErrorKind::NoPath { .. })
+    )
+}
+
+/// Read the rewiring from quilc's `EXPECTED_REWIRING` pragma in a compiled program, which looks
+/// like `PRAGMA EXPECTED_REWIRING "#(2 0 1)"`.
+fn find_rewiring(program: &Program) -> Option<Vec<u64>> {
+    rewiring_entries(program)?.into_iter().collect()
+}
+
+/// As [`find_rewiring`], but with `None` for the qubits quilc didn't place, which it writes as
+/// `NIL`.
+fn rewiring_entries(program: &Program) -> Option<Vec<Option<u64>>> {
+    program
+        .to_instructions()
+        .iter()
+        .find_map(|instruction| match instruction {
+            Instruction::Pragma(pragma) if pragma.name == "EXPECTED_REWIRING" => {
+                pragma.data.clone()
+            }
+            _ => None,
+        })
+        .and_then(|data| {
+            data.trim()
+                .strip_prefix("#(")?
+                .strip_suffix(')')?
+                .split_whitespace()
+                .map(|qubit| match qubit {
+                    "NIL" => Some(None),
+                    qubit => qubit.parse().ok().map(Some),
+                })
+                .collect()
+        })
 }
 
 /// The response from quilc for a `quil_to_native_quil` request.
@@ -252,7 +698,7 @@ where
 }
 
 /// Metadata about a program compiled to native quil.
-#[derive(Clone, Deserialize, Serialize, Debug, PartialEq, PartialOrd)]
+#[derive(Clone, Default, Deserialize, Serialize, Debug, PartialEq, PartialOrd)]
 pub struct NativeQuilMetadata {
     /// Output qubit index relabeling due to SWAP insertion.
     #[serde(deserialize_with = "deserialize_none_as_default")]
@@ -322,41 +768,64 @@ impl NativeQuilRequest {
 #[serde(tag = "_type")]
 pub struct TargetDevice {
     isa: Compiler,
-    specs: HashMap<String, String>,
+    #[serde(default)]
+    specs: Specs,
+}
+
+impl TargetDevice {
+    /// Describe the device `isa`, including the fidelities measured for each of its qubits and
+    /// edges, such as `f1QRB`, `fRO` and `fCZ`, as pyQuil did. The compiler can use these to
+    /// prefer the best qubits and gates.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `isa` can't be converted for the compiler.
+    pub fn from_isa_with_specs(isa: InstructionSetArchitecture) -> Result<Self, Error> {
+        let specs = Specs::from_isa(&isa);
+        Ok(Self {
+            isa: Compiler::try_from(isa)?,
+            specs,
+        })
+    }
+
+    /// Describe the device `isa`, with specs only if `options` ask for them.
+    fn from_isa_with_options(
+        isa: InstructionSetArchitecture,
+        options: CompilerOpts,
+    ) -> Result<Self, Error> {
+        if options.specs {
+            Self::from_isa_with_specs(isa)
+        } else {
+            Self::try_from(isa)
+        }
+    }
 }
 
 impl TryFrom<InstructionSetArchitecture> for TargetDevice {
     type Error = Error;
 
+    /// Describe the device `isa`, without specs. See [`TargetDevice::from_isa_with_specs`].
     fn try_from(isa: InstructionSetArchitecture) -> Result<Self, Self::Error> {
         Ok(Self {
             isa: Compiler::try_from(isa)?,
-            specs: HashMap::new(),
+            specs: Specs::default(),
         })
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "quilc-rpcq", feature = "qvm-http"))]
 mod tests {
     use crate::qvm::{self, http::AddressRequest};
 
     use super::*;
     use crate::client::Qcs;
-    use qcs_api_client_openapi::models::InstructionSetArchitecture;
+    use crate::test_support::{aspen_9_isa, qvm_isa};
     use quil_rs::quil::Quil;
     use regex::Regex;
-    use std::{fs::File, num::NonZeroU16};
+    use std::num::NonZeroU16;
 
     const EXPECTED_H0_OUTPUT: &str = "MEASURE 0\n";
 
-    fn aspen_9_isa() -> InstructionSetArchitecture {
-        serde_json::from_reader(File::open("tests/aspen_9_isa.json").unwrap()).unwrap()
-    }
-
-    pub(crate) fn qvm_isa() -> InstructionSetArchitecture {
-        serde_json::from_reader(File::open("tests/qvm_isa.json").unwrap()).unwrap()
-    }
-
     fn rpcq_client() -> rpcq::Client {
         let qcs = Qcs::load();
         let endpoint = qcs.get_config().quilc_url();
@@ -435,6 +904,54 @@ MEASURE 1 ro[1]
         assert_ne!(output.native_quil_metadata, None);
     }
 
+    /// The number of two-qubit gates in `program`.
+    fn count_2q_gates(program: &Program) -> usize {
+        program
+            .body_instructions()
+            .filter(|instruction| {
+                matches!(instruction, Instruction::Gate(gate) if gate.qubits.len() == 2)
+            })
+            .count()
+    }
+
+    #[tokio::test]
+    async fn compile_with_and_without_specs() {
+        let program = "DECLARE ro BIT[3]\nH 0\nCNOT 0 1\nCNOT 1 2\nCNOT 0 2\nMEASURE 2 ro[2]\n";
+        let mut counts = Vec::new();
+        let mut fidelities = Vec::new();
+        for target in [
+            TargetDevice::try_from(aspen_9_isa()),
+            TargetDevice::from_isa_with_specs(aspen_9_isa()),
+        ] {
+            let output = rpcq_client()
+                .compile_program(
+                    program,
+                    target.expect("Couldn't build target device from ISA"),
+                    CompilerOpts::default().with_protoquil(Some(true)),
+                )
+                .expect("Could not compile");
+            counts.push(count_2q_gates(&output.program));
+            fidelities.push(
+                output
+                    .native_quil_metadata
+                    .and_then(|metadata| metadata.program_fidelity)
+                    .expect("quilc should estimate the fidelity of protoquil"),
+            );
+        }
+        // The specs steer placement, not the circuit's structure, so both need at least one
+        // two-qubit gate per CNOT, but may route differently.
+        assert!(counts.iter().all(|&count| count >= 3), "{counts:?}");
+        // Without specs quilc assumes near-perfect gates. With them, its estimate reflects the
+        // measured fidelities of the qubits and edges it chose, which Aspen-9's are far from.
+        let [without_specs, with_specs] = fidelities[..] else {
+            unreachable!("two targets were compiled for")
+        };
+        assert!(
+            0.0 < with_specs && with_specs < without_specs && without_specs <= 1.0,
+            "{fidelities:?}"
+        );
+    }
+
     #[tokio::test]
     async fn get_version_info_from_quilc() {
         let rpcq_client = rpcq_client();
@@ -445,6 +962,15 @@ MEASURE 1 ro[1]
         assert!(semver_re.is_match(&version));
     }
 
+    #[tokio::test]
+    async fn call_unwrapped_method_on_quilc() {
+        let response: QuilcVersionResponse = rpcq_client()
+            .call("get_version_info", HashMap::<String, String>::new())
+            .expect("Should call get_version_info on quilc");
+        let semver_re = Regex::new(r"^([0-9]+)\.([0-9]+)\.([0-9]+)$").unwrap();
+        assert!(semver_re.is_match(&response.quilc));
+    }
+
     #[tokio::test]
     async fn test_conjugate_pauli_by_clifford() {
         let rpcq_client = rpcq_client();
@@ -490,3 +1016,448 @@ MEASURE 1 ro[1]
         );
     }
 }
+
+#[cfg(test)]
+mod describe_quilc_error_kind {
+    use super::QuilcErrorKind;
+
+    #[test]
+    fn it_classifies_chip_spec_errors() {
+        assert_eq!(
+            QuilcErrorKind::from_message("Unresolvable chip spec: no live qubits"),
+            QuilcErrorKind::UnresolvableChipSpec
+        );
+    }
+
+    #[test]
+    fn it_extracts_qubits_from_path_errors() {
+        let kind = QuilcErrorKind::from_message("There is no path between qubits 0 and 5.");
+        assert_eq!(kind, QuilcErrorKind::NoPath { qubits: vec![0, 5] });
+        assert_eq!(kind.qubits(), [0, 5]);
+    }
+
+    #[test]
+    fn it_classifies_addresser_failures() {
+        assert_eq!(
+            QuilcErrorKind::from_message("The addresser failed to find a rewiring for qubit 7"),
+            QuilcErrorKind::AddresserFailure { qubits: vec![7] }
+        );
+    }
+
+    #[test]
+    fn it_extracts_unsupported_gates() {
+        assert_eq!(
+            QuilcErrorKind::from_message("Unable to compile gate \"FOO\" on qubits 1, 2"),
+            QuilcErrorKind::UnsupportedGate {
+                gate: Some("FOO".to_string()),
+                qubits: vec![1, 2],
+            }
+        );
+    }
+
+    #[test]
+    fn it_falls_back_to_other() {
+        let kind = QuilcErrorKind::from_message("something unexpected happened");
+        assert_eq!(kind, QuilcErrorKind::Other);
+        assert!(kind.qubits().is_empty());
+    }
+}
+
+#[cfg(all(test, feature = "quilc-rpcq"))]
+mod describe_qubit_reselection {
+    use super::{
+        compile_with_qubit_reselection, find_rewiring, Client, CompilationError, CompilationResult,
+        CompilerOpts, ConjugateByCliffordRequest, ConjugatePauliByCliffordResponse, Error,
+        GenerateRandomizedBenchmarkingSequenceResponse, RandomizedBenchmarkingRequest,
+        TargetDevice,
+    };
+    use crate::compiler::rpcq;
+    use crate::test_support::aspen_9_isa;
+
+    /// Fails to place programs on devices with more than `max_qubits` qubits.
+    struct CrampedClient {
+        max_qubits: usize,
+    }
+
+    impl Client for CrampedClient {
+        fn compile_program(
+            &self,
+            _: &str,
+            isa: TargetDevice,
+            _: CompilerOpts,
+        ) -> Result<CompilationResult, Error> {
+            let qubits = serde_json::to_value(&isa).unwrap()["isa"]["1Q"]
+                .as_object()
+                .unwrap()
+                .len();
+            if qubits > self.max_qubits {
+                return Err(Error::QuilcCompilation(CompilationError::Rpcq(
+                    rpcq::Error::Response("The addresser failed to find a rewiring".to_string()),
+                )));
+            }
+            Ok(CompilationResult {
+                program: "PRAGMA EXPECTED_REWIRING \"#(1 0)\"\nCZ 0 1\n"
+                    .parse()
+                    .unwrap(),
+                native_quil_metadata: None,
+            })
+        }
+
+        fn get_version_info(&self) -> Result<String, Error> {
+            unimplemented!()
+        }
+
+        fn conjugate_pauli_by_clifford(
+            &self,
+            _: ConjugateByCliffordRequest,
+        ) -> Result<ConjugatePauliByCliffordResponse, Error> {
+            unimplemented!()
+        }
+
+        fn generate_randomized_benchmarking_sequence(
+            &self,
+            _: RandomizedBenchmarkingRequest,
+        ) -> Result<GenerateRandomizedBenchmarkingSequenceResponse, Error> {
+            unimplemented!()
+        }
+    }
+
+    #[test]
+    fn it_compiles_for_the_whole_device_first() {
+        let client = CrampedClient { max_qubits: 100 };
+        let (_, placement) = compile_with_qubit_reselection(
+            &client,
+            "CZ 0 1",
+            &aspen_9_isa(),
+            CompilerOpts::default(),
+            3,
+        )
+        .unwrap();
+        assert_eq!(placement.qubit_subset, None);
+        assert_eq!(placement.reselections, 0);
+        assert_eq!(placement.rewiring, Some(vec![1, 0]));
+    }
+
+    #[test]
+    fn it_reselects_qubits_after_a_placement_failure() {
+        let client = CrampedClient { max_qubits: 2 };
+        let (_, placement) = compile_with_qubit_reselection(
+            &client,
+            "CZ 0 1",
+            &aspen_9_isa(),
+            CompilerOpts::default(),
+            3,
+        )
+        .unwrap();
+        assert_eq!(placement.reselections, 1);
+        assert_eq!(placement.qubit_subset.map(|subset| subset.len()), Some(2));
+    }
+
+    #[test]
+    fn it_gives_up_after_max_reselections() {
+        let client = CrampedClient { max_qubits: 1 };
+        for max_reselections in [0, 3] {
+            let result = compile_with_qubit_reselection(
+                &client,
+                "CZ 0 1",
+                &aspen_9_isa(),
+                CompilerOpts::default(),
+                max_reselections,
+            );
+            assert!(result.is_err());
+        }
+    }
+
+    #[test]
+    fn it_reads_the_rewiring_pragma() {
+        let program = "PRAGMA EXPECTED_REWIRING \"#(2 0 1)\"\nH 0\n"
+            .parse()
+            .unwrap();
+        assert_eq!(find_rewiring(&program), Some(vec![2, 0, 1]));
+        assert_eq!(find_rewiring(&"H 0".parse().unwrap()), None);
+    }
+}
+
+#[cfg(test)]
+mod describe_placement_report {
+    use maplit::hashmap;
+    use quil_rs::Program;
+
+    use super::{NativeQuilMetadata, PlacementReport};
+
+    #[test]
+    fn it_maps_program_qubits_to_physical_qubits() {
+        let program: Program = "CZ 0 2".parse().unwrap();
+        let native: Program = "PRAGMA EXPECTED_REWIRING \"#(5 NIL 4)\"\nCZ 5 4\n"
+            .parse()
+            .unwrap();
+        let metadata = NativeQuilMetadata {
+            topological_swaps: Some(1),
+            ..NativeQuilMetadata::default()
+        };
+
+        let report = PlacementReport::new(&program, &native, Some(&metadata));
+
+        assert_eq!(report.logical_to_physical, hashmap! { 0 => 5, 2 => 4 });
+        assert_eq!(report.swaps_inserted, 1);
+    }
+
+    #[test]
+    fn it_leaves_out_qubits_without_a_reported_placement() {
+        let program: Program = "CZ 0 1".parse().unwrap();
+        let native: Program = "CZ 0 1".parse().unwrap();
+
+        let report = PlacementReport::new(&program, &native, None);
+
+        assert!(report.logical_to_physical.is_empty());
+        assert_eq!(report.swaps_inserted, 0);
+    }
+}
+
+#[cfg(test)]
+mod describe_clifford_requests {
+    use std::convert::TryFrom;
+
+    use serde_json::json;
+
+    use super::{
+        conjugate_pauli_by_clifford, generate_rb_sequence, CompilationResult, CompilerOpts,
+        ConjugateByCliffordRequest, ConjugatePauliByCliffordRequest,
+        ConjugatePauliByCliffordResponse, Error, GenerateRandomizedBenchmarkingSequenceRequest,
+        GenerateRandomizedBenchmarkingSequenceResponse, PauliTerm, RandomizedBenchmarkingRequest,
+        TargetDevice,
+    };
+    use crate::compiler::CompilerBackend;
+
+    /// A compiler which answers Clifford requests with fixed responses.
+    struct Fixed;
+
+    impl CompilerBackend for Fixed {
+        fn compile_to_native(
+            &self,
+            _: &str,
+            _: TargetDevice,
+            _: CompilerOpts,
+        ) -> Result<CompilationResult, Error> {
+            unimplemented!("not used by these tests")
+        }
+
+        fn get_version(&self) -> Result<String, Error> {
+            unimplemented!("not used by these tests")
+        }
+
+        fn conjugate_pauli(
+            &self,
+            request: ConjugateByCliffordRequest,
+        ) -> Result<ConjugatePauliByCliffordResponse, Error> {
+            Ok(ConjugatePauliByCliffordResponse {
+                phase: 0,
+                pauli: request.pauli.symbols.concat(),
+            })
+        }
+
+        fn randomized_benchmarking_sequence(
+            &self,
+            request: RandomizedBenchmarkingRequest,
+        ) -> Result<GenerateRandomizedBenchmarkingSequenceResponse, Error> {
+            Ok(GenerateRandomizedBenchmarkingSequenceResponse {
+                sequence: vec![vec![0]; usize::try_from(request.depth).unwrap()],
+            })
+        }
+    }
+
+    fn rb_request(depth: u64, gateset: &[&str]) -> RandomizedBenchmarkingRequest {
+        RandomizedBenchmarkingRequest {
+            depth,
+            qubits: 1,
+            gateset: gateset.iter().map(ToString::to_string).collect(),
+            seed: Some(1),
+            interleaver: None,
+        }
+    }
+
+    fn pauli(symbols: &[&str]) -> PauliTerm {
+        PauliTerm {
+            indices: vec![0],
+            symbols: symbols.iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    #[test]
+    fn it_sends_requests_in_the_rpcq_shape() {
+        let request = ConjugatePauliByCliffordRequest::from(ConjugateByCliffordRequest {
+            pauli: pauli(&["X"]),
+            clifford: "H 0".to_string(),
+        });
+        assert_eq!(
+            serde_json::to_value(request).unwrap(),
+            json!({"*args": [{
+                "_type": "ConjugateByCliffordRequest",
+                "pauli": {"_type": "PauliTerm", "indices": [0], "symbols": ["X"]},
+                "clifford": "H 0",
+            }]})
+        );
+
+        let request =
+            GenerateRandomizedBenchmarkingSequenceRequest::from(rb_request(2, &["X 0", "H 0"]));
+        let value = serde_json::to_value(request).unwrap();
+        assert_eq!(value["*args"][0]["_type"], "RandomizedBenchmarkingRequest");
+        assert_eq!(value["*args"][0]["gateset"], json!(["X 0", "H 0"]));
+    }
+
+    #[test]
+    fn it_forwards_valid_requests() {
+        let response = conjugate_pauli_by_clifford(&Fixed, pauli(&["Z"]), "H 0").unwrap();
+        assert_eq!(response.pauli, "Z");
+        let response = generate_rb_sequence(&Fixed, rb_request(3, &["X 0"])).unwrap();
+        assert_eq!(response.sequence.len(), 3);
+    }
+
+    #[test]
+    fn it_rejects_invalid_requests_without_sending_them() {
+        for symbols in [&["Q"][..], &["X", "Y"][..]] {
+            assert!(matches!(
+                conjugate_pauli_by_clifford(&Fixed, pauli(symbols), "H 0"),
+                Err(Error::InvalidRequest(_))
+            ));
+        }
+        for request in [rb_request(1, &["X 0"]), rb_request(2, &[])] {
+            assert!(matches!(
+                generate_rb_sequence(&Fixed, request),
+                Err(Error::InvalidRequest(_))
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod describe_compile_program_with_metadata {
+    use std::str::FromStr;
+
+    use quil_rs::Program;
+
+    use super::{
+        compile_program_with_metadata, CompilationResult, CompilerOpts, Error, NativeQuilMetadata,
+        TargetDevice,
+    };
+    use crate::compiler::CompilerBackend;
+    use crate::test_support::qvm_isa;
+
+    /// A compiler which returns programs unchanged, reporting `metadata` if it has any.
+    struct Identity {
+        metadata: Option<NativeQuilMetadata>,
+    }
+
+    impl CompilerBackend for Identity {
+        fn compile_to_native(
+            &self,
+            quil: &str,
+            _: TargetDevice,
+            options: CompilerOpts,
+        ) -> Result<CompilationResult, Error> {
+            assert_eq!(options.protoquil(), Some(true));
+            Ok(CompilationResult {
+                program: Program::from_str(quil).map_err(Error::Parse)?,
+                native_quil_metadata: self.metadata.clone(),
+            })
+        }
+
+        fn get_version(&self) -> Result<String, Error> {
+            Ok("identity".to_string())
+        }
+    }
+
+    #[test]
+    fn it_returns_the_reported_metadata() {
+        let metadata = NativeQuilMetadata {
+            final_rewiring: vec![1, 0],
+            qpu_runtime_estimation: Some(0.5),
+            ..NativeQuilMetadata::default()
+        };
+        let client = Identity {
+            metadata: Some(metadata.clone()),
+        };
+        let options = CompilerOpts::default().with_protoquil(Some(true));
+
+        let (program, reported) =
+            compile_program_with_metadata(&client, "X 0", &qvm_isa(), options).unwrap();
+
+        assert_eq!(program, Program::from_str("X 0").unwrap());
+        assert_eq!(reported, metadata);
+    }
+
+    #[test]
+    fn it_returns_empty_metadata_when_none_is_reported() {
+        let client = Identity { metadata: None };
+        let options = CompilerOpts::default().with_protoquil(Some(true));
+
+        let (_, reported) =
+            compile_program_with_metadata(&client, "X 0", &qvm_isa(), options).unwrap();
+
+        assert_eq!(reported, NativeQuilMetadata::default());
+    }
+}
+
+#[cfg(test)]
+mod describe_target_device_specs {
+    use std::{cell::RefCell, convert::TryFrom};
+
+    use super::{
+        compile_program_with_metadata, CompilationResult, CompilerOpts, Error, TargetDevice,
+    };
+    use crate::compiler::CompilerBackend;
+    use crate::test_support::aspen_9_isa;
+
+    /// A compiler which records the `specs` of each target device it is asked to compile for.
+    #[derive(Default)]
+    struct Recording {
+        specs: RefCell<Vec<serde_json::Value>>,
+    }
+
+    impl CompilerBackend for Recording {
+        fn compile_to_native(
+            &self,
+            quil: &str,
+            target: TargetDevice,
+            _: CompilerOpts,
+        ) -> Result<CompilationResult, Error> {
+            let target = serde_json::to_value(target).unwrap();
+            self.specs.borrow_mut().push(target["specs"].clone());
+            Ok(CompilationResult {
+                program: quil.parse().map_err(Error::Parse)?,
+                native_quil_metadata: None,
+            })
+        }
+
+        fn get_version(&self) -> Result<String, Error> {
+            Ok("recording".to_string())
+        }
+    }
+
+    #[test]
+    fn it_omits_specs_by_default() {
+        let target = TargetDevice::try_from(aspen_9_isa()).unwrap();
+        assert_eq!(
+            serde_json::to_value(target).unwrap()["specs"],
+            serde_json::json!({})
+        );
+        assert!(!CompilerOpts::default().specs());
+        assert!(!CompilerOpts::new().specs());
+    }
+
+    #[test]
+    fn it_sends_specs_when_asked() {
+        let client = Recording::default();
+        for specs in [false, true] {
+            let options = CompilerOpts::default().with_specs(specs);
+            compile_program_with_metadata(&client, "CZ 0 1", &aspen_9_isa(), options).unwrap();
+        }
+
+        let sent = client.specs.into_inner();
+        assert_eq!(sent[0], serde_json::json!({}));
+        assert!(sent[1]["1Q"]["0"]["f1QRB"].is_number());
+        assert!(sent[1]["2Q"]
+            .as_object()
+            .is_some_and(|edges| !edges.is_empty()));
+    }
+}
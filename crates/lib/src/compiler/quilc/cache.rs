@@ -0,0 +1,547 @@
+//! Caching compiled programs, so that compiling the same program for the same device with the same
+//! options skips the compiler entirely. See [`CompilationCache`].
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant, SystemTime};
+
+use indexmap::IndexMap;
+use quil_rs::quil::Quil;
+use quil_rs::Program;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    CompilationResult, CompilerOpts, ConjugateByCliffordRequest, ConjugatePauliByCliffordResponse,
+    Error, GenerateRandomizedBenchmarkingSequenceResponse, NativeQuilMetadata,
+    RandomizedBenchmarkingRequest, TargetDevice,
+};
+use crate::compiler::CompilerBackend;
+use crate::metrics::{self, Cache};
+
+/// The number of compiled programs a [`CompilationCache::default`] keeps in memory.
+pub const DEFAULT_COMPILATION_CACHE_SIZE: usize = 128;
+
+/// A cache of compiled programs, keyed by the Quil text, the [`TargetDevice`], and the
+/// [`CompilerOpts`] they were compiled with.
+///
+/// The cache is opt-in: pass it to
+/// [`Executable::with_compilation_cache`](crate::Executable::with_compilation_cache), or wrap a
+/// compiler in a [`CachedCompiler`]. A cache can be shared by any number of executables and
+/// compilers, so that each distinct program is only compiled once.
+///
+/// At most `max_entries` programs are kept in memory, evicting the least recently used first, and
+/// each is kept for at most the cache's time-to-live, if it has one. With
+/// [`CompilationCache::with_directory`], compiled programs are also written to disk, so that they
+/// survive the process; entries on disk expire with the same time-to-live, but aren't limited in
+/// number. Problems reading or writing the directory are ignored, and the program is compiled as
+/// if it weren't cached.
+///
+/// The compiler timeout doesn't change the compiled program, so it isn't part of the key. Failed
+/// compilations aren't cached. Lookups are counted in
+/// [`CacheStats::compilation`](crate::metrics::CacheStats::compilation).
+#[allow(clippy::module_name_repetitions)]
+#[derive(Debug)]
+pub struct CompilationCache {
+    inner: Mutex<Inner>,
+    directory: Option<PathBuf>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    max_entries: usize,
+    ttl: Option<Duration>,
+    entries: IndexMap<Key, (Instant, CompilationResult)>,
+}
+
+/// What a compilation depends on. The target device is identified by a hash of its serialized
+/// form, since it is large and shared by most of the programs compiled for it. The form is
+/// canonical, see [`canonical`], so that equal devices always hash the same.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+struct Key {
+    quil: String,
+    target: u64,
+    protoquil: Option<bool>,
+    specs: bool,
+}
+
+/// A compiled program as it is written to disk, along with the key it was compiled for, so that
+/// the rare file name collision is detected rather than returning the wrong program.
+#[derive(Serialize, Deserialize)]
+struct StoredEntry {
+    key: Key,
+    program: String,
+    native_quil_metadata: Option<NativeQuilMetadata>,
+}
+
+impl Key {
+    /// The key for compiling `quil` for `target` with `options`, or `None` if `target` can't be
+    /// serialized, in which case the compilation isn't cached.
+    fn new(quil: &str, target: &TargetDevice, options: CompilerOpts) -> Option<Self> {
+        let target = serde_json::to_value(target).ok()?;
+        let target = serde_json::to_vec(&canonical(target)).ok()?;
+        Some(Self {
+            quil: quil.to_string(),
+            target: fnv1a(&[target.as_slice()]),
+            protoquil: options.protoquil(),
+            specs: options.specs(),
+        })
+    }
+
+    /// The name of the file this key is stored in.
+    fn file_name(&self) -> String {
+        let options = [
+            u8::from(self.protoquil.is_some()),
+            u8::from(self.protoquil == Some(true)),
+            u8::from(self.specs),
+        ];
+        let hash = fnv1a(&[self.quil.as_bytes(), &self.target.to_le_bytes(), &options]);
+        format!("{hash:016x}.json")
+    }
+}
+
+/// `value` with the entries of every object sorted by key. A [`TargetDevice`] is made of
+/// `HashMap`s, which serialize in a different order each time they're built, even when equal.
+fn canonical(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(object) => {
+            let mut entries: Vec<_> = object
+                .into_iter()
+                .map(|(key, value)| (key, canonical(value)))
+                .collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            serde_json::Value::Object(entries.into_iter().collect())
+        }
+        serde_json::Value::Array(values) => {
+            serde_json::Value::Array(values.into_iter().map(canonical).collect())
+        }
+        value => value,
+    }
+}
+
+/// The 64-bit FNV-1a hash of `parts`, one after the other. Unlike the standard library's hashers,
+/// it is the same in every build, so it can name files which outlive the process.
+fn fnv1a(parts: &[&[u8]]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+    parts
+        .iter()
+        .flat_map(|part| part.iter())
+        .fold(OFFSET_BASIS, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(PRIME)
+        })
+}
+
+impl Default for CompilationCache {
+    /// A cache which keeps up to [`DEFAULT_COMPILATION_CACHE_SIZE`] programs in memory, without
+    /// expiring them.
+    fn default() -> Self {
+        Self::new(DEFAULT_COMPILATION_CACHE_SIZE, None)
+    }
+}
+
+impl CompilationCache {
+    /// Create an empty cache which keeps up to `max_entries` programs in memory, each for at most
+    /// `ttl` if it is set.
+    #[must_use]
+    pub fn new(max_entries: usize, ttl: Option<Duration>) -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                max_entries,
+                ttl,
+                entries: IndexMap::new(),
+            }),
+            directory: None,
+        }
+    }
+
+    /// Also keep compiled programs as files in `directory`, which is created if it doesn't exist.
+    #[must_use]
+    pub fn with_directory<P: Into<PathBuf>>(mut self, directory: P) -> Self {
+        self.directory = Some(directory.into());
+        self
+    }
+
+    /// The directory compiled programs are kept in, if any.
+    #[must_use]
+    pub fn directory(&self) -> Option<&Path> {
+        self.directory.as_deref()
+    }
+
+    /// The number of compiled programs kept in memory, including any which have expired but
+    /// haven't been looked up since.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    /// Whether no compiled programs are kept in memory.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Forget every compiled program kept in memory. Files in the cache's directory are left as
+    /// they are.
+    pub fn clear(&self) {
+        self.lock().entries.clear();
+    }
+
+    /// Compile `quil` for `target` with `options` using `compiler`, unless it has already been
+    /// compiled the same way, in which case the cached result is returned.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error from `compiler` if the program wasn't cached and fails to compile.
+    pub fn compile<C: CompilerBackend + ?Sized>(
+        &self,
+        compiler: &C,
+        quil: &str,
+        target: TargetDevice,
+        options: CompilerOpts,
+    ) -> Result<CompilationResult, Error> {
+        let Some(key) = Key::new(quil, &target, options) else {
+            return compiler.compile_to_native(quil, target, options);
+        };
+
+        let cached = self.get(&key);
+        metrics::record(Cache::Compilation, cached.is_some());
+        if let Some(result) = cached {
+            return Ok(result);
+        }
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!("compilation cache miss, compiling");
+
+        let result = compiler.compile_to_native(quil, target, options)?;
+        self.store(&key, &result);
+        self.insert(key, result.clone());
+        Ok(result)
+    }
+
+    /// The cached result for `key`, from memory or else from disk.
+    fn get(&self, key: &Key) -> Option<CompilationResult> {
+        let ttl = {
+            let mut inner = self.lock();
+            let ttl = inner.ttl;
+            if let Some((compiled_at, result)) = inner.entries.shift_remove(key) {
+                if ttl.map_or(true, |ttl| compiled_at.elapsed() < ttl) {
+                    let cached = result.clone();
+                    inner.entries.insert(key.clone(), (compiled_at, result));
+                    return Some(cached);
+                }
+            }
+            ttl
+        };
+
+        let result = self.load(key, ttl)?;
+        self.insert(key.clone(), result.clone());
+        Some(result)
+    }
+
+    /// Keep `result` in memory, evicting the least recently used programs to make room.
+    fn insert(&self, key: Key, result: CompilationResult) {
+        let mut inner = self.lock();
+        if inner.max_entries == 0 {
+            return;
+        }
+        inner.entries.insert(key, (Instant::now(), result));
+        while inner.entries.len() > inner.max_entries {
+            inner.entries.shift_remove_index(0);
+        }
+    }
+
+    /// Read the result for `key` from the cache's directory, if it's there and younger than `ttl`.
+    fn load(&self, key: &Key, ttl: Option<Duration>) -> Option<CompilationResult> {
+        let path = self.directory.as_ref()?.join(key.file_name());
+        if let Some(ttl) = ttl {
+            let modified = fs::metadata(&path).and_then(|metadata| metadata.modified());
+            let age = modified
+                .ok()
+                .and_then(|modified| SystemTime::now().duration_since(modified).ok())?;
+            if age >= ttl {
+                return None;
+            }
+        }
+        let stored: StoredEntry = serde_json::from_slice(&fs::read(&path).ok()?).ok()?;
+        if &stored.key != key {
+            return None;
+        }
+        Some(CompilationResult {
+            program: Program::from_str(&stored.program).ok()?,
+            native_quil_metadata: stored.native_quil_metadata,
+        })
+    }
+
+    /// Write `result` to the cache's directory, if it has one.
+    fn store(&self, key: &Key, result: &CompilationResult) {
+        let Some(directory) = &self.directory else {
+            return;
+        };
+        let Ok(program) = result.program.to_quil() else {
+            return;
+        };
+        let stored = StoredEntry {
+            key: key.clone(),
+            program,
+            native_quil_metadata: result.native_quil_metadata.clone(),
+        };
+        let written = fs::create_dir_all(directory).and_then(|()| {
+            let contents = serde_json::to_vec(&stored)?;
+            fs::write(directory.join(key.file_name()), contents)
+        });
+        if let Err(error) = written {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(%error, "failed to write a compiled program to the compilation cache");
+            #[cfg(not(feature = "tracing"))]
+            let _ = error;
+        }
+    }
+
+    /// Lock the cache. Every update leaves it consistent, so a poisoned lock is still usable.
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A [`CompilerBackend`] which looks up every compilation in a [`CompilationCache`] before
+/// passing it on to another compiler.
+///
+/// Every other request is passed on to the compiler unchanged.
+#[allow(clippy::module_name_repetitions)]
+#[derive(Clone)]
+pub struct CachedCompiler {
+    compiler: Arc<dyn CompilerBackend + Send + Sync>,
+    cache: Arc<CompilationCache>,
+}
+
+impl std::fmt::Debug for CachedCompiler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CachedCompiler")
+            .field("cache", &self.cache)
+            .finish_non_exhaustive()
+    }
+}
+
+impl CachedCompiler {
+    /// Cache the compilations of `compiler` in `cache`.
+    #[must_use]
+    pub fn new<C: CompilerBackend + Send + Sync + 'static>(
+        compiler: C,
+        cache: Arc<CompilationCache>,
+    ) -> Self {
+        Self::from_shared(Arc::new(compiler), cache)
+    }
+
+    /// Cache the compilations of a compiler which is already shared.
+    pub(crate) fn from_shared(
+        compiler: Arc<dyn CompilerBackend + Send + Sync>,
+        cache: Arc<CompilationCache>,
+    ) -> Self {
+        Self { compiler, cache }
+    }
+
+    /// The cache compilations are kept in.
+    #[must_use]
+    pub fn cache(&self) -> &Arc<CompilationCache> {
+        &self.cache
+    }
+}
+
+impl CompilerBackend for CachedCompiler {
+    fn compile_to_native(
+        &self,
+        quil: &str,
+        target: TargetDevice,
+        options: CompilerOpts,
+    ) -> Result<CompilationResult, Error> {
+        self.cache
+            .compile(self.compiler.as_ref(), quil, target, options)
+    }
+
+    fn get_version(&self) -> Result<String, Error> {
+        self.compiler.get_version()
+    }
+
+    fn conjugate_pauli(
+        &self,
+        request: ConjugateByCliffordRequest,
+    ) -> Result<ConjugatePauliByCliffordResponse, Error> {
+        self.compiler.conjugate_pauli(request)
+    }
+
+    fn randomized_benchmarking_sequence(
+        &self,
+        request: RandomizedBenchmarkingRequest,
+    ) -> Result<GenerateRandomizedBenchmarkingSequenceResponse, Error> {
+        self.compiler.randomized_benchmarking_sequence(request)
+    }
+}
+
+#[cfg(test)]
+mod describe_compilation_cache {
+    use std::convert::TryFrom;
+    use std::str::FromStr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use quil_rs::Program;
+
+    use super::{CompilationCache, Key};
+    use crate::compiler::quilc::{
+        self, CompilationResult, CompilerOpts, NativeQuilMetadata, TargetDevice,
+    };
+    use crate::compiler::CompilerBackend;
+    use crate::test_support::aspen_9_isa;
+
+    /// A compiler which counts its compilations and returns programs unchanged.
+    #[derive(Default)]
+    struct Counting {
+        compilations: AtomicUsize,
+    }
+
+    impl CompilerBackend for Counting {
+        fn compile_to_native(
+            &self,
+            quil: &str,
+            _: TargetDevice,
+            _: CompilerOpts,
+        ) -> Result<CompilationResult, quilc::Error> {
+            self.compilations.fetch_add(1, Ordering::SeqCst);
+            Ok(CompilationResult {
+                program: Program::from_str(quil).map_err(quilc::Error::Parse)?,
+                native_quil_metadata: Some(NativeQuilMetadata {
+                    final_rewiring: vec![0],
+                    ..NativeQuilMetadata::default()
+                }),
+            })
+        }
+
+        fn get_version(&self) -> Result<String, quilc::Error> {
+            Ok("counting".to_string())
+        }
+    }
+
+    impl Counting {
+        fn compilations(&self) -> usize {
+            self.compilations.load(Ordering::SeqCst)
+        }
+    }
+
+    fn target() -> TargetDevice {
+        TargetDevice::try_from(aspen_9_isa()).unwrap()
+    }
+
+    #[test]
+    fn it_compiles_each_program_once() {
+        let cache = CompilationCache::default();
+        let compiler = Counting::default();
+        let options = CompilerOpts::default();
+
+        let first = cache.compile(&compiler, "X 0", target(), options).unwrap();
+        let second = cache.compile(&compiler, "X 0", target(), options).unwrap();
+        cache.compile(&compiler, "Y 0", target(), options).unwrap();
+        cache
+            .compile(
+                &compiler,
+                "X 0",
+                target(),
+                CompilerOpts::default().with_protoquil(Some(true)),
+            )
+            .unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(compiler.compilations(), 3);
+        assert_eq!(cache.len(), 3);
+    }
+
+    #[test]
+    fn it_ignores_the_timeout() {
+        let cache = CompilationCache::default();
+        let compiler = Counting::default();
+
+        cache
+            .compile(
+                &compiler,
+                "X 0",
+                target(),
+                CompilerOpts::default().with_timeout(Some(1.0)),
+            )
+            .unwrap();
+        cache
+            .compile(
+                &compiler,
+                "X 0",
+                target(),
+                CompilerOpts::default().with_timeout(None),
+            )
+            .unwrap();
+
+        assert_eq!(compiler.compilations(), 1);
+    }
+
+    #[test]
+    fn it_evicts_the_least_recently_used_program() {
+        let cache = CompilationCache::new(2, None);
+        let compiler = Counting::default();
+        let options = CompilerOpts::default();
+
+        for quil in ["X 0", "Y 0", "X 0", "Z 0", "X 0", "Y 0"] {
+            cache.compile(&compiler, quil, target(), options).unwrap();
+        }
+
+        // `Y 0` was evicted by `Z 0`, since `X 0` had been used more recently.
+        assert_eq!(compiler.compilations(), 4);
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn it_expires_programs() {
+        let cache = CompilationCache::new(8, Some(Duration::ZERO));
+        let compiler = Counting::default();
+        let options = CompilerOpts::default();
+
+        cache.compile(&compiler, "X 0", target(), options).unwrap();
+        cache.compile(&compiler, "X 0", target(), options).unwrap();
+
+        assert_eq!(compiler.compilations(), 2);
+    }
+
+    #[test]
+    fn it_reads_programs_back_from_disk() {
+        let directory = tempfile::tempdir().unwrap();
+        let compiler = Counting::default();
+        let options = CompilerOpts::default();
+
+        let first = CompilationCache::default().with_directory(directory.path());
+        let compiled = first
+            .compile(&compiler, "DECLARE ro BIT\nX 0", target(), options)
+            .unwrap();
+
+        let second = CompilationCache::default().with_directory(directory.path());
+        let loaded = second
+            .compile(&compiler, "DECLARE ro BIT\nX 0", target(), options)
+            .unwrap();
+
+        assert_eq!(compiler.compilations(), 1);
+        assert_eq!(loaded, compiled);
+        assert_eq!(second.len(), 1);
+    }
+
+    #[test]
+    fn it_names_files_by_every_part_of_the_key() {
+        let key = Key::new("X 0", &target(), CompilerOpts::default()).unwrap();
+        let protoquil = Key::new(
+            "X 0",
+            &target(),
+            CompilerOpts::default().with_protoquil(Some(false)),
+        )
+        .unwrap();
+
+        let same = Key::new("X 0", &target(), CompilerOpts::default()).unwrap();
+
+        assert_eq!(key.file_name(), same.file_name());
+        assert_ne!(key.file_name(), protoquil.file_name());
+    }
+}
@@ -1,7 +1,11 @@
 //! Provides an RPCQ client for Quilc
 
 use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use quil_rs::Program;
 use rmp_serde::Serializer;
@@ -11,8 +15,30 @@ use uuid::Uuid;
 use zmq::{Context, Socket, SocketType};
 
 use super::quilc;
+use crate::client::Qcs;
 
-pub(crate) const DEFAULT_CLIENT_TIMEOUT: f64 = 30.0;
+mod pool;
+
+pub use pool::{AsyncClient, DEFAULT_POOL_SIZE};
+
+/// The number of seconds the server waits on a request before timing out, unless otherwise
+/// specified.
+pub const DEFAULT_CLIENT_TIMEOUT: f64 = 30.0;
+
+/// How a [`Client`] encodes its requests as MessagePack.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Encoding {
+    /// Fields are encoded in declaration order, maps in whatever order they iterate, and each
+    /// request gets a random ID.
+    #[default]
+    Standard,
+    /// Requests are encoded deterministically, so that they can be recorded and replayed: every
+    /// map (including struct fields) is written with its keys sorted, request IDs count up from
+    /// `0` for each [`Client`].
+    ///
+    /// Map keys are always encoded as strings in this mode.
+    Canonical,
+}
 
 /// A minimal RPCQ client that does just enough to talk to `quilc`
 #[derive(Clone)]
@@ -20,6 +46,9 @@ pub struct Client {
     pub(crate) endpoint: String,
     send_timeout: Option<i32>,
     receive_timeout: Option<i32>,
+    encoding: Encoding,
+    next_request_id: Arc<AtomicU64>,
+    socks_proxy: Option<String>,
 }
 
 impl std::fmt::Debug for Client {
@@ -35,9 +64,62 @@ impl Client {
             endpoint: endpoint.to_owned(),
             send_timeout: None,
             receive_timeout: None,
+            encoding: Encoding::Standard,
+            next_request_id: Arc::new(AtomicU64::new(0)),
+            socks_proxy: None,
         })
     }
 
+    /// Construct a new [`Client`] for the quilc server configured for `qcs`, connecting through
+    /// the SOCKS5 proxy of its [`ConnectionSettings`](crate::client::ConnectionSettings), if it
+    /// has one.
+    ///
+    /// # Errors
+    ///
+    /// See [`Client::new`].
+    pub fn from_qcs(qcs: &Qcs) -> Result<Self, Error> {
+        Ok(Self::new(qcs.get_config().quilc_url())?
+            .with_socks_proxy(qcs.connection_settings().socks_proxy().map(str::to_string)))
+    }
+
+    /// Connect through the SOCKS5 proxy at `proxy`, given as `host:port`, or directly if `None`.
+    /// ZeroMQ doesn't support other kinds of proxy.
+    #[must_use]
+    pub fn with_socks_proxy(mut self, proxy: Option<String>) -> Self {
+        self.socks_proxy = proxy;
+        self
+    }
+
+    /// Use `encoding` for all requests made by this client.
+    #[must_use]
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// The [`Encoding`] used for requests made by this client.
+    #[must_use]
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Build an [`RPCRequest`] for `method`, with the ID and envelope metadata required by this
+    /// client's [`Encoding`].
+    fn request<'params, T: Serialize>(
+        &self,
+        method: &'params str,
+        params: &'params T,
+    ) -> RPCRequest<'params, T> {
+        let request = RPCRequest::new(method, params);
+        match self.encoding {
+            Encoding::Standard => request,
+            Encoding::Canonical => {
+                let id = self.next_request_id.fetch_add(1, Ordering::Relaxed);
+                request.with_id(id.to_string())
+            }
+        }
+    }
+
     /// Set the timeout used for both sending and receiving messages
     ///
     /// Value is number of milliseconds. A value of `-1` means no timeout.
@@ -60,8 +142,51 @@ impl Client {
         self.receive_timeout = Some(timeout);
     }
 
+    /// Call an RPC method on the server which this library does not (yet) wrap, such as a newly
+    /// added quilc endpoint.
+    ///
+    /// `params` are sent as the parameters of the request, and the `result` of the reply is
+    /// decoded into `Response`. The request uses [`DEFAULT_CLIENT_TIMEOUT`]; see
+    /// [`Client::call_with_timeout`] to change it.
+    ///
+    /// # Errors
+    ///
+    /// Errors reported by the server are returned as [`quilc::Error::QuilcCompilation`], all
+    /// other failures as [`quilc::Error::QuilcConnection`].
+    pub fn call<Request: Serialize, Response: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Request,
+    ) -> Result<Response, quilc::Error> {
+        self.call_with_timeout(method, params, Some(DEFAULT_CLIENT_TIMEOUT))
+    }
+
+    /// The same as [`Client::call`], but with a timeout of `timeout` seconds, or no timeout if
+    /// `None`. The server is asked to give up after `timeout`, and the client stops waiting for
+    /// the reply at the same deadline.
+    ///
+    /// # Errors
+    ///
+    /// See [`Client::call`].
+    pub fn call_with_timeout<Request: Serialize, Response: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Request,
+        timeout: Option<f64>,
+    ) -> Result<Response, quilc::Error> {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(%method, "calling RPCQ method");
+
+        let request = self.request(method, &params).with_timeout(timeout);
+        self.run_request(&request)
+            .map_err(|source| Error::to_quilc_error(self.endpoint.clone(), source))
+    }
+
     /// Send an RPC request and immediately retrieve and decode the results.
     ///
+    /// If the request has a `client_timeout`, waiting for the reply stops once that long has
+    /// passed since the request was made, with [`Error::Timeout`].
+    ///
     /// # Arguments
     ///
     /// * `request`: An [`RPCRequest`] containing some params.
@@ -69,11 +194,36 @@ impl Client {
         &self,
         request: &RPCRequest<'_, Request>,
     ) -> Result<Response, Error> {
+        let started = Instant::now();
         let socket = self.create_socket()?;
-        Self::send(request, &socket)?;
+        self.send(request, &socket)?;
+        let timeout = request
+            .client_timeout
+            .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok());
+        if let Some(timeout) = timeout {
+            Self::wait_for_reply(&socket, started, timeout)?;
+        }
         Self::receive::<Response>(&request.id, &socket)
     }
 
+    /// Wait until a reply can be read from `socket`, or fail with [`Error::Timeout`] once
+    /// `timeout` has passed since `started`.
+    fn wait_for_reply(socket: &Socket, started: Instant, timeout: Duration) -> Result<(), Error> {
+        let remaining = timeout.saturating_sub(started.elapsed());
+        let wait = i64::try_from(remaining.as_millis()).unwrap_or(i64::MAX);
+        if socket
+            .poll(zmq::POLLIN, wait)
+            .map_err(Error::Communication)?
+            > 0
+        {
+            Ok(())
+        } else {
+            Err(Error::Timeout {
+                elapsed: started.elapsed(),
+            })
+        }
+    }
+
     /// Send an RPC request.
     ///
     /// # Arguments
@@ -81,14 +231,11 @@ impl Client {
     /// * `request`: An [`RPCRequest`] containing some params.
     /// * `socket`: The ZMQ socket to send the request on.
     fn send<Request: Serialize>(
+        &self,
         request: &RPCRequest<'_, Request>,
         socket: &Socket,
     ) -> Result<(), Error> {
-        let mut data = vec![];
-        request
-            .serialize(&mut Serializer::new(&mut data).with_struct_map())
-            .map_err(Error::Serialization)?;
-
+        let data = encode(request, self.encoding)?;
         socket.send(data, 0).map_err(Error::Communication)
     }
 
@@ -116,6 +263,11 @@ impl Client {
                 .set_rcvtimeo(receive_timeout)
                 .map_err(Error::Communication)?;
         }
+        if let Some(proxy) = &self.socks_proxy {
+            socket
+                .set_socks_proxy(Some(proxy.as_str()))
+                .map_err(Error::Communication)?;
+        }
         socket
             .connect(&self.endpoint.clone())
             .map_err(Error::Communication)?;
@@ -132,19 +284,7 @@ impl Client {
         socket: &Socket,
     ) -> Result<Response, Error> {
         let data = Self::receive_raw(socket)?;
-
-        let reply: RPCResponse<Response> =
-            rmp_serde::from_read(data.as_slice()).map_err(Error::Deserialization)?;
-        match reply {
-            RPCResponse::RPCReply { id, result } => {
-                if id == request_id {
-                    Ok(result)
-                } else {
-                    Err(Error::ResponseIdMismatch)
-                }
-            }
-            RPCResponse::RPCError { error, .. } => Err(Error::Response(error)),
-        }
+        decode_reply(&data, request_id)
     }
 
     /// Retrieve the raw bytes of a response
@@ -164,7 +304,9 @@ impl quilc::Client for Client {
         #[cfg(feature = "tracing")]
         tracing::debug!(compiler_options=?options, "compiling quil program with quilc (RPCQ)",);
         let params = quilc::QuilcParams::new(quil, isa).with_protoquil(options.protoquil);
-        let request = RPCRequest::new("quil_to_native_quil", &params).with_timeout(options.timeout);
+        let request = self
+            .request("quil_to_native_quil", &params)
+            .with_timeout(options.timeout);
         match self.run_request::<_, quilc::QuilToNativeQuilResponse>(&request) {
             Ok(response) => Ok(quilc::CompilationResult {
                 program: Program::from_str(&response.quil).map_err(quilc::Error::Parse)?,
@@ -180,7 +322,7 @@ impl quilc::Client for Client {
 
         // todo check this hashmap type
         let bindings: HashMap<String, String> = HashMap::new();
-        let request = RPCRequest::new("get_version_info", &bindings);
+        let request = self.request("get_version_info", &bindings);
         match self.run_request::<_, quilc::QuilcVersionResponse>(&request) {
             Ok(response) => Ok(response.quilc),
             Err(source) => Err(Error::to_quilc_error(self.endpoint.clone(), source)),
@@ -195,7 +337,7 @@ impl quilc::Client for Client {
         tracing::debug!("requesting quilc conjugate_pauli_by_clifford");
 
         let request: quilc::ConjugatePauliByCliffordRequest = request.into();
-        let request = RPCRequest::new("conjugate_pauli_by_clifford", &request);
+        let request = self.request("conjugate_pauli_by_clifford", &request);
         match self.run_request::<_, quilc::ConjugatePauliByCliffordResponse>(&request) {
             Ok(response) => Ok(response),
             Err(source) => Err(Error::to_quilc_error(self.endpoint.clone(), source)),
@@ -210,7 +352,7 @@ impl quilc::Client for Client {
         tracing::debug!("requesting quilc generate_randomized_benchmarking_sequence");
 
         let request: quilc::GenerateRandomizedBenchmarkingSequenceRequest = request.into();
-        let request = RPCRequest::new("generate_rb_sequence", &request);
+        let request = self.request("generate_rb_sequence", &request);
         match self.run_request::<_, quilc::GenerateRandomizedBenchmarkingSequenceResponse>(&request)
         {
             Ok(response) => Ok(response),
@@ -221,6 +363,7 @@ impl quilc::Client for Client {
 
 /// All of the possible errors for this module
 #[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
 pub enum Error {
     /// The ZMQ socket could not be created
     #[error("Could not create a socket: {0}")]
@@ -234,6 +377,9 @@ pub enum Error {
     /// Failed to serialize request
     #[error("Could not serialize request as MessagePack. This is a bug in this library: {0}")]
     Serialization(#[from] rmp_serde::encode::Error),
+    /// Failed to convert a request to its canonical form
+    #[error("Could not build the canonical form of the request: {0}")]
+    CanonicalSerialization(#[source] serde_json::Error),
     /// Failed to deserialize response
     #[error("Could not decode ZMQ server's response. This is likely a bug in this library: {0}")]
     Deserialization(#[from] rmp_serde::decode::Error),
@@ -246,6 +392,17 @@ pub enum Error {
     /// Error occurred when trying to lock the ZMQ socket
     #[error("Could not lock RPCQ client: {0}")]
     ZmqSocketLock(String),
+    /// No reply was received before the request's timeout
+    #[error("Timed out after {elapsed:?} waiting for a reply from the ZMQ server")]
+    Timeout {
+        /// How long the client waited, from making the request until giving up.
+        elapsed: Duration,
+    },
+    /// The workers of an [`AsyncClient`] stopped before replying
+    #[error(
+        "The RPCQ client's worker threads have stopped. This is likely a bug in this library."
+    )]
+    PoolClosed,
 }
 
 impl Error {
@@ -268,7 +425,7 @@ pub(crate) struct RPCRequest<'params, T = HashMap<String, String>>
 where
     T: Serialize,
 {
-    method: &'static str,
+    method: &'params str,
     params: &'params T,
     id: String,
     jsonrpc: &'static str,
@@ -285,7 +442,7 @@ impl<'params, T: Serialize> RPCRequest<'params, T> {
     /// * `params`: The parameters to send. This must implement [`serde::Serialize`].
     ///
     /// returns: `RPCRequest<T>` where `T` is the type you passed in as `params`.
-    pub(crate) fn new(method: &'static str, params: &'params T) -> Self {
+    pub(crate) fn new(method: &'params str, params: &'params T) -> Self {
         Self {
             method,
             params,
@@ -296,6 +453,12 @@ impl<'params, T: Serialize> RPCRequest<'params, T> {
         }
     }
 
+    /// Replaces the randomly generated ID of the [`RPCRequest`].
+    pub(crate) fn with_id(mut self, id: String) -> Self {
+        self.id = id;
+        self
+    }
+
     /// Sets the client timeout for the [`RPCRequest`].
     ///
     /// # Arguments
@@ -315,3 +478,209 @@ pub(crate) enum RPCResponse<T> {
     RPCReply { id: String, result: T },
     RPCError { error: String },
 }
+
+/// The envelope of a reply, as written by [`encode_reply`] and [`encode_error_reply`].
+#[derive(Serialize)]
+#[serde(tag = "_type")]
+enum RPCReplyEnvelope<'a, T: Serialize> {
+    RPCReply {
+        jsonrpc: &'static str,
+        id: &'a str,
+        result: &'a T,
+    },
+    RPCError {
+        jsonrpc: &'static str,
+        id: &'a str,
+        error: &'a str,
+    },
+}
+
+/// Encode `value` as MessagePack using `encoding`.
+fn encode<T: Serialize>(value: &T, encoding: Encoding) -> Result<Vec<u8>, Error> {
+    let mut data = vec![];
+    let mut serializer = Serializer::new(&mut data).with_struct_map();
+    match encoding {
+        Encoding::Standard => value.serialize(&mut serializer),
+        Encoding::Canonical => serde_json::to_value(value)
+            .map(sort_keys)
+            .map_err(Error::CanonicalSerialization)?
+            .serialize(&mut serializer),
+    }
+    .map_err(Error::Serialization)?;
+    Ok(data)
+}
+
+/// Recursively rebuild every map in `value` with its keys in sorted order.
+fn sort_keys(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => {
+            let mut entries: Vec<_> = map.into_iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            serde_json::Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, sort_keys(value)))
+                    .collect(),
+            )
+        }
+        serde_json::Value::Array(values) => {
+            serde_json::Value::Array(values.into_iter().map(sort_keys).collect())
+        }
+        value => value,
+    }
+}
+
+/// An RPCQ request decoded by [`decode_request`].
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct DecodedRequest<T = serde_json::Value> {
+    /// The name of the method being called.
+    pub method: String,
+    /// The parameters of the call.
+    pub params: T,
+    /// The ID of the request, which the reply must echo.
+    pub id: String,
+    /// The number of seconds the server should wait before timing out, if any.
+    pub client_timeout: Option<f64>,
+}
+
+/// Decode an RPCQ request, as sent by a [`Client`], from MessagePack.
+///
+/// This is intended for tools which sit between a [`Client`] and `quilc`, such as a proxy which
+/// records requests and replays their replies.
+///
+/// # Errors
+///
+/// Returns [`Error::Deserialization`] if `data` is not an RPCQ request with params of type `T`.
+pub fn decode_request<T: DeserializeOwned>(data: &[u8]) -> Result<DecodedRequest<T>, Error> {
+    rmp_serde::from_slice(data).map_err(Error::Deserialization)
+}
+
+/// Decode the MessagePack reply to the request with ID `request_id`, returning its result.
+///
+/// # Errors
+///
+/// Returns [`Error::Response`] if the reply is an error, [`Error::ResponseIdMismatch`] if it is
+/// a reply to a different request, and [`Error::Deserialization`] if it can't be decoded.
+pub fn decode_reply<T: DeserializeOwned>(data: &[u8], request_id: &str) -> Result<T, Error> {
+    let reply: RPCResponse<T> = rmp_serde::from_slice(data).map_err(Error::Deserialization)?;
+    match reply {
+        RPCResponse::RPCReply { id, result } => {
+            if id == request_id {
+                Ok(result)
+            } else {
+                Err(Error::ResponseIdMismatch)
+            }
+        }
+        RPCResponse::RPCError { error, .. } => Err(Error::Response(error)),
+    }
+}
+
+/// Encode a successful reply to the request with ID `request_id` as MessagePack, such that a
+/// [`Client`] will decode it. The reply is always encoded with [`Encoding::Canonical`].
+///
+/// # Errors
+///
+/// Returns an error if `result` can't be serialized.
+pub fn encode_reply<T: Serialize>(request_id: &str, result: &T) -> Result<Vec<u8>, Error> {
+    encode(
+        &RPCReplyEnvelope::RPCReply {
+            jsonrpc: "2.0",
+            id: request_id,
+            result,
+        },
+        Encoding::Canonical,
+    )
+}
+
+/// Encode an error reply to the request with ID `request_id` as MessagePack, such that a
+/// [`Client`] will return it as [`Error::Response`].
+///
+/// # Errors
+///
+/// Returns an error if the reply can't be serialized, which should not happen.
+pub fn encode_error_reply(request_id: &str, error: &str) -> Result<Vec<u8>, Error> {
+    encode(
+        &RPCReplyEnvelope::<()>::RPCError {
+            jsonrpc: "2.0",
+            id: request_id,
+            error,
+        },
+        Encoding::Canonical,
+    )
+}
+
+#[cfg(test)]
+mod describe_canonical_encoding {
+    use std::collections::HashMap;
+
+    use super::{
+        decode_reply, decode_request, encode, encode_error_reply, encode_reply, Client,
+        DecodedRequest, Encoding, Error,
+    };
+
+    fn client() -> Client {
+        Client::new("tcp://127.0.0.1:5555")
+            .unwrap()
+            .with_encoding(Encoding::Canonical)
+    }
+
+    fn params<'a>(keys: impl IntoIterator<Item = &'a str>) -> HashMap<String, usize> {
+        keys.into_iter()
+            .map(|key| (key.to_string(), key.len()))
+            .collect()
+    }
+
+    #[test]
+    fn it_encodes_maps_in_sorted_order() {
+        let keys = ["delta", "alpha", "charlie", "bravo", "echo", "foxtrot"];
+        let first = params(keys);
+        let second = params(keys.iter().rev().copied());
+
+        let first = encode(&client().request("method", &first), Encoding::Canonical).unwrap();
+        let second = encode(&client().request("method", &second), Encoding::Canonical).unwrap();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn it_numbers_requests() {
+        let client = client();
+        let params = params(["a"]);
+        let first: DecodedRequest<HashMap<String, usize>> =
+            decode_request(&encode(&client.request("method", &params), client.encoding()).unwrap())
+                .unwrap();
+        let second: DecodedRequest<HashMap<String, usize>> =
+            decode_request(&encode(&client.request("method", &params), client.encoding()).unwrap())
+                .unwrap();
+
+        assert_eq!(first.id, "0");
+        assert_eq!(second.id, "1");
+        assert_eq!(first.method, "method");
+        assert_eq!(first.params, params);
+    }
+
+    #[test]
+    fn it_uses_random_ids_by_default() {
+        let client = Client::new("tcp://127.0.0.1:5555").unwrap();
+        let params = params(["a"]);
+        let request: DecodedRequest =
+            decode_request(&encode(&client.request("method", &params), client.encoding()).unwrap())
+                .unwrap();
+        assert_ne!(request.id, "0");
+    }
+
+    #[test]
+    fn it_round_trips_replies() {
+        let reply = encode_reply("7", &vec![1, 2, 3]).unwrap();
+        assert_eq!(decode_reply::<Vec<u8>>(&reply, "7").unwrap(), vec![1, 2, 3]);
+        assert!(matches!(
+            decode_reply::<Vec<u8>>(&reply, "8"),
+            Err(Error::ResponseIdMismatch)
+        ));
+
+        let reply = encode_error_reply("7", "oops").unwrap();
+        assert!(matches!(
+            decode_reply::<Vec<u8>>(&reply, "7"),
+            Err(Error::Response(message)) if message == "oops"
+        ));
+    }
+}
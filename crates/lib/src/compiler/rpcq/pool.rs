@@ -0,0 +1,584 @@
+//! An asynchronous RPCQ client, see [`AsyncClient`].
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::str::FromStr;
+use std::sync::{mpsc, Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+use futures::channel::oneshot;
+use futures::executor::block_on;
+use lazy_static::lazy_static;
+use quil_rs::Program;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use zmq::Socket;
+
+use super::{decode_reply, encode, Client, Encoding, Error, DEFAULT_CLIENT_TIMEOUT};
+use crate::client::Qcs;
+use crate::compiler::quilc;
+
+/// The number of worker threads, and so of concurrent requests, in the pool shared by every
+/// [`AsyncClient`] for the same endpoint and proxy created with [`AsyncClient::new`],
+/// [`AsyncClient::from_qcs`], or [`AsyncClient::from_client`].
+pub const DEFAULT_POOL_SIZE: usize = 4;
+
+/// How often a worker waiting on a reply checks whether the request was cancelled.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The endpoint and SOCKS proxy which the workers of a shared pool connect through.
+type PoolKey = (String, Option<String>);
+
+lazy_static! {
+    static ref SHARED_POOLS: Mutex<HashMap<PoolKey, Weak<Pool>>> = Mutex::new(HashMap::new());
+}
+
+/// An RPCQ client which doesn't block the async runtime.
+///
+/// Requests are sent by a pool of worker threads, each of which keeps a ZMQ socket connected to
+/// the server and reuses it for every request. Clients created with [`AsyncClient::new`],
+/// [`AsyncClient::from_qcs`], or [`AsyncClient::from_client`] share a pool of
+/// [`DEFAULT_POOL_SIZE`] workers with every other such client for the same endpoint and SOCKS
+/// proxy, so many concurrent compilations use a bounded number of threads and connections.
+///
+/// Each request has its own timeout, measured from when it is made, so time spent waiting for a
+/// free worker counts towards it. A request is cancelled by dropping its future: the worker
+/// stops waiting for the reply and moves on to the next request.
+///
+/// An [`AsyncClient`] is also a [`quilc::Client`], so it can be given to
+/// [`Executable::with_quilc_client`](crate::Executable::with_quilc_client) to bound the number of
+/// connections made by many executables compiling at once.
+#[derive(Clone)]
+pub struct AsyncClient {
+    client: Client,
+    pool: Arc<Pool>,
+    timeout: Option<Duration>,
+}
+
+impl std::fmt::Debug for AsyncClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "async RPCQ client for {}", self.client.endpoint)
+    }
+}
+
+impl AsyncClient {
+    /// Construct a new [`AsyncClient`] for `endpoint`, using the pool shared by all clients for
+    /// that endpoint.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the client can't be configured.
+    pub fn new(endpoint: &str) -> Result<Self, Error> {
+        Self::from_client(Client::new(endpoint)?)
+    }
+
+    /// Construct a new [`AsyncClient`] for the quilc server configured for `qcs`, connecting
+    /// through its SOCKS5 proxy if it has one, as with [`Client::from_qcs`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the client can't be configured.
+    pub fn from_qcs(qcs: &Qcs) -> Result<Self, Error> {
+        Self::from_client(Client::from_qcs(qcs)?)
+    }
+
+    /// Construct a new [`AsyncClient`] which sends its requests as `client` would, using the pool
+    /// shared by all clients for the same endpoint and SOCKS proxy.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the client can't be configured.
+    pub fn from_client(client: Client) -> Result<Self, Error> {
+        let key = (client.endpoint.clone(), client.socks_proxy.clone());
+        let mut pools = SHARED_POOLS
+            .lock()
+            .map_err(|error| Error::ZmqSocketLock(error.to_string()))?;
+        let pool = if let Some(pool) = pools.get(&key).and_then(Weak::upgrade) {
+            pool
+        } else {
+            let pool = Arc::new(Pool::new(client.clone(), DEFAULT_POOL_SIZE));
+            // Forget the pools which no client uses any more.
+            pools.retain(|_, pool| pool.strong_count() > 0);
+            pools.insert(key, Arc::downgrade(&pool));
+            pool
+        };
+        Ok(Self::with_pool(client, pool))
+    }
+
+    /// Construct a new [`AsyncClient`] for `endpoint` with its own pool of `workers` threads,
+    /// shared only with clones of the returned client.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the client can't be configured.
+    pub fn with_dedicated_pool(endpoint: &str, workers: usize) -> Result<Self, Error> {
+        Ok(Self::from_client_with_dedicated_pool(
+            Client::new(endpoint)?,
+            workers,
+        ))
+    }
+
+    /// Construct a new [`AsyncClient`] which sends its requests as `client` would, including
+    /// through its SOCKS proxy, with its own pool of `workers` threads shared only with clones of
+    /// the returned client.
+    #[must_use]
+    pub fn from_client_with_dedicated_pool(client: Client, workers: usize) -> Self {
+        let pool = Arc::new(Pool::new(client.clone(), workers.max(1)));
+        Self::with_pool(client, pool)
+    }
+
+    fn with_pool(client: Client, pool: Arc<Pool>) -> Self {
+        Self {
+            client,
+            pool,
+            timeout: Some(Duration::from_secs_f64(DEFAULT_CLIENT_TIMEOUT)),
+        }
+    }
+
+    /// Use `encoding` for all requests made by this client.
+    #[must_use]
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.client = self.client.with_encoding(encoding);
+        self
+    }
+
+    /// Wait at most `timeout` for replies to requests made with [`AsyncClient::call`], or
+    /// forever if `None`. Defaults to [`DEFAULT_CLIENT_TIMEOUT`] seconds.
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Call an RPC method on the server, as with [`Client::call`], waiting at most the timeout
+    /// set with [`AsyncClient::with_timeout`] for the reply.
+    ///
+    /// # Errors
+    ///
+    /// Errors reported by the server are returned as [`quilc::Error::QuilcCompilation`], all
+    /// other failures, including [`Error::Timeout`], as [`quilc::Error::QuilcConnection`].
+    pub async fn call<Request: Serialize, Response: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Request,
+    ) -> Result<Response, quilc::Error> {
+        self.call_with_timeout(method, params, self.timeout).await
+    }
+
+    /// The same as [`AsyncClient::call`], but waiting at most `timeout` for this request, or
+    /// forever if `None`. The server is asked to give up after the same time.
+    ///
+    /// # Errors
+    ///
+    /// See [`AsyncClient::call`].
+    pub async fn call_with_timeout<Request: Serialize, Response: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Request,
+        timeout: Option<Duration>,
+    ) -> Result<Response, quilc::Error> {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(%method, "calling RPCQ method asynchronously");
+
+        self.run_request(method, &params, timeout)
+            .await
+            .map_err(|source| Error::to_quilc_error(self.client.endpoint.clone(), source))
+    }
+
+    /// Compile `quil` for `isa`, as with [`quilc::Client::compile_program`]. The timeout of
+    /// `options` limits both how long the server compiles for and how long this waits for it.
+    ///
+    /// # Errors
+    ///
+    /// See [`AsyncClient::call`].
+    pub async fn compile_program(
+        &self,
+        quil: &str,
+        isa: quilc::TargetDevice,
+        options: quilc::CompilerOpts,
+    ) -> Result<quilc::CompilationResult, quilc::Error> {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(compiler_options=?options, "compiling quil program with quilc (async RPCQ)");
+
+        let params = quilc::QuilcParams::new(quil, isa).with_protoquil(options.protoquil);
+        let response: quilc::QuilToNativeQuilResponse = self
+            .call_with_timeout(
+                "quil_to_native_quil",
+                params,
+                options.timeout.map(Duration::from_secs_f64),
+            )
+            .await?;
+        Ok(quilc::CompilationResult {
+            program: Program::from_str(&response.quil).map_err(quilc::Error::Parse)?,
+            native_quil_metadata: response.metadata,
+        })
+    }
+
+    /// Get the version of the quilc server, as with [`quilc::Client::get_version_info`].
+    ///
+    /// # Errors
+    ///
+    /// See [`AsyncClient::call`].
+    pub async fn get_version_info(&self) -> Result<String, quilc::Error> {
+        let response: quilc::QuilcVersionResponse = self
+            .call("get_version_info", HashMap::<String, String>::new())
+            .await?;
+        Ok(response.quilc)
+    }
+
+    /// See [`quilc::Client::conjugate_pauli_by_clifford`].
+    ///
+    /// # Errors
+    ///
+    /// See [`AsyncClient::call`].
+    pub async fn conjugate_pauli_by_clifford(
+        &self,
+        request: quilc::ConjugateByCliffordRequest,
+    ) -> Result<quilc::ConjugatePauliByCliffordResponse, quilc::Error> {
+        let request: quilc::ConjugatePauliByCliffordRequest = request.into();
+        self.call("conjugate_pauli_by_clifford", request).await
+    }
+
+    /// See [`quilc::Client::generate_randomized_benchmarking_sequence`].
+    ///
+    /// # Errors
+    ///
+    /// See [`AsyncClient::call`].
+    pub async fn generate_randomized_benchmarking_sequence(
+        &self,
+        request: quilc::RandomizedBenchmarkingRequest,
+    ) -> Result<quilc::GenerateRandomizedBenchmarkingSequenceResponse, quilc::Error> {
+        let request: quilc::GenerateRandomizedBenchmarkingSequenceRequest = request.into();
+        self.call("generate_rb_sequence", request).await
+    }
+
+    /// Encode the request, hand it to a worker, and decode its reply.
+    async fn run_request<Request: Serialize, Response: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: &Request,
+        timeout: Option<Duration>,
+    ) -> Result<Response, Error> {
+        let (id, data) = {
+            let request = self
+                .client
+                .request(method, params)
+                .with_timeout(timeout.map(|timeout| timeout.as_secs_f64()));
+            (
+                request.id.clone(),
+                encode(&request, self.client.encoding())?,
+            )
+        };
+        let reply = self.pool.submit(data, timeout)?;
+        let data = reply.await.map_err(|_| Error::PoolClosed)??;
+        decode_reply(&data, &id)
+    }
+}
+
+/// Each request blocks the calling thread until its reply arrives, as with the other
+/// [`quilc::Client`]s, but is still sent by one of the workers of the client's pool.
+impl quilc::Client for AsyncClient {
+    fn compile_program(
+        &self,
+        quil: &str,
+        isa: quilc::TargetDevice,
+        options: quilc::CompilerOpts,
+    ) -> Result<quilc::CompilationResult, quilc::Error> {
+        block_on(Self::compile_program(self, quil, isa, options))
+    }
+
+    fn get_version_info(&self) -> Result<String, quilc::Error> {
+        block_on(Self::get_version_info(self))
+    }
+
+    fn conjugate_pauli_by_clifford(
+        &self,
+        request: quilc::ConjugateByCliffordRequest,
+    ) -> Result<quilc::ConjugatePauliByCliffordResponse, quilc::Error> {
+        block_on(Self::conjugate_pauli_by_clifford(self, request))
+    }
+
+    fn generate_randomized_benchmarking_sequence(
+        &self,
+        request: quilc::RandomizedBenchmarkingRequest,
+    ) -> Result<quilc::GenerateRandomizedBenchmarkingSequenceResponse, quilc::Error> {
+        block_on(Self::generate_randomized_benchmarking_sequence(
+            self, request,
+        ))
+    }
+}
+
+/// A request waiting for a worker.
+struct Job {
+    data: Vec<u8>,
+    submitted: Instant,
+    deadline: Option<Instant>,
+    reply: oneshot::Sender<Result<Vec<u8>, Error>>,
+}
+
+/// Worker threads which send requests to one endpoint. The workers exit once the pool is dropped.
+struct Pool {
+    jobs: Mutex<mpsc::Sender<Job>>,
+}
+
+impl Pool {
+    fn new(client: Client, workers: usize) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for _ in 0..workers {
+            let client = client.clone();
+            let receiver = receiver.clone();
+            std::thread::spawn(move || work(&client, &receiver));
+        }
+        Self {
+            jobs: Mutex::new(sender),
+        }
+    }
+
+    /// Queue `data` to be sent, returning a receiver for the raw reply.
+    fn submit(
+        &self,
+        data: Vec<u8>,
+        timeout: Option<Duration>,
+    ) -> Result<oneshot::Receiver<Result<Vec<u8>, Error>>, Error> {
+        let (reply, receiver) = oneshot::channel();
+        let submitted = Instant::now();
+        let job = Job {
+            data,
+            submitted,
+            deadline: timeout.map(|timeout| submitted + timeout),
+            reply,
+        };
+        self.jobs
+            .lock()
+            .map_err(|error| Error::ZmqSocketLock(error.to_string()))?
+            .send(job)
+            .map_err(|_| Error::PoolClosed)?;
+        Ok(receiver)
+    }
+}
+
+/// Run jobs from `jobs` until the pool is dropped.
+fn work(client: &Client, jobs: &Mutex<mpsc::Receiver<Job>>) {
+    let mut socket = None;
+    loop {
+        let job = match jobs.lock() {
+            Ok(jobs) => jobs.recv(),
+            Err(_) => return,
+        };
+        let Ok(job) = job else {
+            return;
+        };
+        if job.reply.is_canceled() {
+            continue;
+        }
+        match run(client, &mut socket, &job) {
+            Ok(Some(data)) => {
+                let _ = job.reply.send(Ok(data));
+            }
+            Ok(None) => {
+                // Cancelled: a late reply must not be mistaken for the reply to the next request.
+                socket = None;
+            }
+            Err(error) => {
+                socket = None;
+                let _ = job.reply.send(Err(error));
+            }
+        }
+    }
+}
+
+/// Send `job` on `socket`, connecting it first if needed, and wait for the reply. Returns `None`
+/// if the job was cancelled while waiting.
+fn run(client: &Client, socket: &mut Option<Socket>, job: &Job) -> Result<Option<Vec<u8>>, Error> {
+    let socket = match socket {
+        Some(socket) => socket,
+        None => socket.insert(client.create_socket()?),
+    };
+    socket
+        .send(job.data.as_slice(), 0)
+        .map_err(Error::Communication)?;
+    loop {
+        if job.reply.is_canceled() {
+            return Ok(None);
+        }
+        let wait = match job.deadline {
+            Some(deadline) => {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    return Err(Error::Timeout {
+                        elapsed: job.submitted.elapsed(),
+                    });
+                }
+                remaining.min(POLL_INTERVAL)
+            }
+            None => POLL_INTERVAL,
+        };
+        let wait = i64::try_from(wait.as_millis()).unwrap_or(i64::MAX);
+        if socket
+            .poll(zmq::POLLIN, wait)
+            .map_err(Error::Communication)?
+            > 0
+        {
+            return socket.recv_bytes(0).map(Some).map_err(Error::Communication);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "mock-quilc"))]
+mod describe_async_client {
+    use std::{convert::TryFrom, str::FromStr, sync::Arc, time::Duration};
+
+    use quil_rs::Program;
+
+    use super::{AsyncClient, SHARED_POOLS};
+    use crate::compiler::{
+        mock_quilc::{MockQuilc, MOCK_QUILC_VERSION},
+        quilc::{self, CompilerOpts, TargetDevice},
+        rpcq::{Client, Error},
+    };
+    use crate::test_support::qvm_isa;
+
+    fn target() -> TargetDevice {
+        TargetDevice::try_from(qvm_isa()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn it_shares_a_pool_between_clients_for_an_endpoint() {
+        let quilc = MockQuilc::start().unwrap();
+        let first = AsyncClient::new(quilc.endpoint()).unwrap();
+        let second = AsyncClient::new(quilc.endpoint()).unwrap();
+        let dedicated = AsyncClient::with_dedicated_pool(quilc.endpoint(), 1).unwrap();
+
+        assert!(Arc::ptr_eq(&first.pool, &second.pool));
+        assert!(!Arc::ptr_eq(&first.pool, &dedicated.pool));
+        assert_eq!(first.get_version_info().await.unwrap(), MOCK_QUILC_VERSION);
+        assert_eq!(
+            dedicated.get_version_info().await.unwrap(),
+            MOCK_QUILC_VERSION
+        );
+    }
+
+    #[test]
+    fn it_forgets_pools_once_every_client_is_dropped() {
+        let quilc = MockQuilc::start().unwrap();
+        drop(AsyncClient::new(quilc.endpoint()).unwrap());
+        let other = MockQuilc::start().unwrap();
+        let _client = AsyncClient::new(other.endpoint()).unwrap();
+
+        let pools = SHARED_POOLS.lock().unwrap();
+        assert!(!pools.contains_key(&(quilc.endpoint().to_string(), None)));
+        assert!(pools.contains_key(&(other.endpoint().to_string(), None)));
+    }
+
+    #[test]
+    fn it_shares_a_pool_only_between_clients_with_the_same_proxy() {
+        let quilc = MockQuilc::start().unwrap();
+        let proxied = || {
+            let client = Client::new(quilc.endpoint())
+                .unwrap()
+                .with_socks_proxy(Some("localhost:1080".to_string()));
+            AsyncClient::from_client(client).unwrap()
+        };
+        let direct = AsyncClient::new(quilc.endpoint()).unwrap();
+        let first = proxied();
+        let second = proxied();
+
+        assert!(Arc::ptr_eq(&first.pool, &second.pool));
+        assert!(!Arc::ptr_eq(&first.pool, &direct.pool));
+        assert_eq!(first.client.socks_proxy.as_deref(), Some("localhost:1080"));
+    }
+
+    #[test]
+    fn it_is_a_quilc_client() {
+        use quilc::Client;
+
+        let quilc = MockQuilc::start().unwrap();
+        let client = AsyncClient::with_dedicated_pool(quilc.endpoint(), 1).unwrap();
+
+        let result =
+            Client::compile_program(&client, "X 0", target(), CompilerOpts::default()).unwrap();
+        assert_eq!(result.program, Program::from_str("X 0").unwrap());
+        assert_eq!(
+            Client::get_version_info(&client).unwrap(),
+            MOCK_QUILC_VERSION
+        );
+    }
+
+    #[tokio::test]
+    async fn it_compiles_many_programs_concurrently() {
+        let quilc = MockQuilc::start().unwrap();
+        let client = AsyncClient::with_dedicated_pool(quilc.endpoint(), 2).unwrap();
+
+        let results = futures::future::join_all((0..8).map(|qubit| {
+            let client = client.clone();
+            async move {
+                client
+                    .compile_program(&format!("X {qubit}"), target(), CompilerOpts::default())
+                    .await
+            }
+        }))
+        .await;
+
+        for (qubit, result) in results.into_iter().enumerate() {
+            assert_eq!(
+                result.unwrap().program,
+                Program::from_str(&format!("X {qubit}")).unwrap()
+            );
+        }
+        assert_eq!(quilc.request_count(), 8);
+    }
+
+    #[tokio::test]
+    async fn it_times_out_each_request() {
+        let quilc = MockQuilc::start_with_transform(|quil| {
+            std::thread::sleep(Duration::from_millis(500));
+            Ok(quil.to_string())
+        })
+        .unwrap();
+        let client = AsyncClient::with_dedicated_pool(quilc.endpoint(), 1).unwrap();
+
+        let result = client
+            .compile_program(
+                "X 0",
+                target(),
+                CompilerOpts::default().with_timeout(Some(0.05)),
+            )
+            .await;
+        assert!(matches!(
+            result,
+            Err(quilc::Error::QuilcConnection(_, Error::Timeout { elapsed }))
+                if elapsed >= Duration::from_millis(50)
+        ));
+
+        // The worker reconnects, so the late reply isn't mistaken for this one.
+        let result = client
+            .compile_program("Y 0", target(), CompilerOpts::default())
+            .await
+            .unwrap();
+        assert_eq!(result.program, Program::from_str("Y 0").unwrap());
+    }
+
+    #[tokio::test]
+    async fn it_abandons_cancelled_requests() {
+        let quilc = MockQuilc::start_with_transform(|quil| {
+            std::thread::sleep(Duration::from_millis(200));
+            Ok(quil.to_string())
+        })
+        .unwrap();
+        let client = AsyncClient::with_dedicated_pool(quilc.endpoint(), 1).unwrap();
+
+        let cancelled = tokio::time::timeout(
+            Duration::from_millis(20),
+            client.compile_program("X 0", target(), CompilerOpts::default()),
+        )
+        .await;
+        assert!(cancelled.is_err());
+
+        let result = client
+            .compile_program("Y 0", target(), CompilerOpts::default())
+            .await
+            .unwrap();
+        assert_eq!(result.program, Program::from_str("Y 0").unwrap());
+    }
+}
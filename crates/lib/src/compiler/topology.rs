@@ -0,0 +1,186 @@
+//! Utilities for reasoning about how the qubits of a device are connected, and for choosing
+//! connected subsets of them to compile programs onto.
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::convert::TryFrom;
+
+use qcs_api_client_openapi::models::InstructionSetArchitecture;
+
+use crate::qpu::isa::sites::IsaSites;
+
+/// The connectivity of a device's qubits, as described by its [`InstructionSetArchitecture`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Topology {
+    adjacency: BTreeMap<u64, BTreeSet<u64>>,
+}
+
+impl Topology {
+    /// Build the topology of the qubits and edges in an [`InstructionSetArchitecture`].
+    #[must_use]
+    pub fn from_isa(isa: &InstructionSetArchitecture) -> Self {
+        let sites = IsaSites::new(isa);
+        let mut adjacency: BTreeMap<u64, BTreeSet<u64>> = sites
+            .qubits
+            .into_iter()
+            .map(|qubit| (qubit, BTreeSet::new()))
+            .collect();
+        for (a, b) in sites.edges {
+            adjacency.entry(a).or_default().insert(b);
+            adjacency.entry(b).or_default().insert(a);
+        }
+        Self { adjacency }
+    }
+
+    /// The device's qubits, in ascending order.
+    pub fn qubits(&self) -> impl Iterator<Item = u64> + '_ {
+        self.adjacency.keys().copied()
+    }
+
+    /// The qubits which share an edge with `qubit`, in ascending order.
+    pub fn neighbors(&self, qubit: u64) -> impl Iterator<Item = u64> + '_ {
+        self.adjacency.get(&qubit).into_iter().flatten().copied()
+    }
+
+    /// Whether every qubit in `qubits` can reach every other without leaving `qubits`.
+    #[must_use]
+    pub fn is_connected(&self, qubits: &[u64]) -> bool {
+        let subset: BTreeSet<u64> = qubits.iter().copied().collect();
+        let Some(&start) = subset.iter().next() else {
+            return true;
+        };
+        if !subset
+            .iter()
+            .all(|qubit| self.adjacency.contains_key(qubit))
+        {
+            return false;
+        }
+
+        let mut reached = BTreeSet::from([start]);
+        let mut queue = VecDeque::from([start]);
+        while let Some(qubit) = queue.pop_front() {
+            for neighbor in self.neighbors(qubit) {
+                if subset.contains(&neighbor) && reached.insert(neighbor) {
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+        reached.len() == subset.len()
+    }
+
+    /// Choose connected subsets of `size` qubits, best candidates first.
+    ///
+    /// A subset is grown from each qubit in turn by repeatedly adding the neighbor with the most
+    /// edges into the subset so far. The distinct results are ranked by how many edges they
+    /// contain, since more edges leave the compiler more freedom to route two-qubit gates.
+    #[must_use]
+    pub fn connected_subsets(&self, size: usize) -> Vec<Vec<u64>> {
+        if size == 0 {
+            return Vec::new();
+        }
+
+        let subsets: BTreeSet<Vec<u64>> = self
+            .qubits()
+            .filter_map(|seed| self.grow_subset(seed, size))
+            .collect();
+        let mut subsets: Vec<_> = subsets.into_iter().collect();
+        subsets.sort_by_key(|subset| std::cmp::Reverse(self.count_edges(subset)));
+        subsets
+    }
+
+    /// Grow a connected subset of `size` qubits from `seed`, or `None` if its connected component
+    /// is too small.
+    fn grow_subset(&self, seed: u64, size: usize) -> Option<Vec<u64>> {
+        let mut subset = BTreeSet::from([seed]);
+        while subset.len() < size {
+            let next = subset
+                .iter()
+                .flat_map(|&qubit| self.neighbors(qubit))
+                .filter(|neighbor| !subset.contains(neighbor))
+                .max_by_key(|&candidate| {
+                    let edges = self
+                        .neighbors(candidate)
+                        .filter(|neighbor| subset.contains(neighbor))
+                        .count();
+                    (edges, std::cmp::Reverse(candidate))
+                })?;
+            subset.insert(next);
+        }
+        Some(subset.into_iter().collect())
+    }
+
+    /// The number of edges between qubits in `qubits`.
+    fn count_edges(&self, qubits: &[u64]) -> usize {
+        qubits
+            .iter()
+            .map(|&qubit| {
+                self.neighbors(qubit)
+                    .filter(|neighbor| qubits.contains(neighbor))
+                    .count()
+            })
+            .sum::<usize>()
+            / 2
+    }
+}
+
+/// Remove every qubit not in `qubits` from an [`InstructionSetArchitecture`], along with the edges
+/// and operation sites which involve them.
+#[must_use]
+pub fn restrict_isa(
+    isa: &InstructionSetArchitecture,
+    qubits: &[u64],
+) -> InstructionSetArchitecture {
+    let keep = |node_id: &i64| u64::try_from(*node_id).is_ok_and(|qubit| qubits.contains(&qubit));
+
+    let mut isa = isa.clone();
+    isa.architecture.nodes.retain(|node| keep(&node.node_id));
+    isa.architecture
+        .edges
+        .retain(|edge| edge.node_ids.iter().all(keep));
+    for operation in &mut isa.instructions {
+        operation
+            .sites
+            .retain(|site| site.node_ids.iter().all(keep));
+    }
+    isa
+}
+
+#[cfg(test)]
+mod describe_topology {
+    use super::{restrict_isa, Topology};
+    use crate::test_support::aspen_9_isa;
+
+    #[test]
+    fn it_reads_connectivity_from_the_isa() {
+        let topology = Topology::from_isa(&aspen_9_isa());
+        assert_eq!(topology.qubits().count(), 32);
+        assert!(topology.neighbors(0).any(|neighbor| neighbor == 1));
+        assert!(topology.is_connected(&[0, 1]));
+        assert!(!topology.is_connected(&[0, 2]));
+        assert!(topology.is_connected(&[]));
+        assert!(!topology.is_connected(&[1000]));
+    }
+
+    #[test]
+    fn it_selects_connected_subsets() {
+        let topology = Topology::from_isa(&aspen_9_isa());
+        let subsets = topology.connected_subsets(3);
+        assert!(!subsets.is_empty());
+        for subset in &subsets {
+            assert_eq!(subset.len(), 3);
+            assert!(topology.is_connected(subset), "{subset:?}");
+        }
+        assert!(topology.connected_subsets(0).is_empty());
+        assert!(topology.connected_subsets(1000).is_empty());
+    }
+
+    #[test]
+    fn it_restricts_an_isa_to_a_subset() {
+        let isa = restrict_isa(&aspen_9_isa(), &[0, 1, 7]);
+        let topology = Topology::from_isa(&isa);
+        assert_eq!(topology.qubits().collect::<Vec<_>>(), vec![0, 1, 7]);
+        assert!(isa.instructions.iter().all(|operation| operation
+            .sites
+            .iter()
+            .all(|site| site.node_ids.iter().all(|id| [0, 1, 7].contains(id)))));
+    }
+}
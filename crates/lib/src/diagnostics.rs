@@ -1,83 +1,160 @@
 //! Produce diagnostic information about the crate and its runtime environment in order to aid
 //! in debugging and remote user support.
 
-use std::{borrow::Cow, time::Duration};
+use std::{
+    borrow::Cow,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
 
 use qcs_api_client_openapi::models::User;
+use serde::Serialize;
 
 use crate::{
     build_info,
-    client::Qcs,
+    client::{secrets_path, settings_path, Qcs},
     compiler::{quilc::Client as _, rpcq},
     qvm::{self, Client as _, QvmOptions},
 };
 
+/// The oldest `quilc` version this crate is tested against. Older versions may work, but the
+/// diagnostics report will recommend upgrading.
+pub const MIN_QUILC_VERSION: &str = "1.23.0";
+
+/// The oldest QVM version this crate is tested against. Older versions may work, but the
+/// diagnostics report will recommend upgrading.
+pub const MIN_QVM_VERSION: &str = "1.17.1";
+
 /// Collect package diagnostics in string form
 pub async fn get_report() -> String {
-    Diagnostics::gather().await.to_string()
+    DiagnosticsReport::gather().await.to_string()
 }
 
 /// Diagnostic information representing the environment in which this crate
 /// was built and is executed, for use in diagnosing unexpected and incorrect
 /// behavior.
-#[derive(Debug)]
-struct Diagnostics {
-    /// The version of this crate    
-    version: String,
-
-    rust_version: String,
-
+///
+/// [`get_report`] returns the [`Display`](std::fmt::Display) form of this report, which is meant
+/// for people. Monitoring tools can read the fields directly, or serialize the report as JSON.
+#[derive(Clone, Debug, Serialize)]
+#[non_exhaustive]
+pub struct DiagnosticsReport {
+    /// The version of this crate
+    pub sdk_version: String,
+    /// The version of `rustc` this crate was built with
+    pub rust_version: String,
     /// The features with which this crate was compiled
-    features: Vec<&'static str>,
-
-    qcs: QcsApiDiagnostics,
-    quilc: QuilcDiagnostics,
-    qvm: QvmDiagnostics,
-    libquil: LibquilDiagnostics,
+    pub features: Vec<&'static str>,
+    /// Where QCS settings and secrets are read from
+    pub config_paths: ConfigPaths,
+    /// The health of the QCS API
+    pub qcs: QcsHealth,
+    /// The health of the configured `quilc` server
+    pub quilc: ServiceHealth,
+    /// The health of the configured QVM server
+    pub qvm: ServiceHealth,
+    /// The versions of quilc and the QVM in libquil, if this crate was built with it
+    pub libquil: LibquilHealth,
+    /// Problems found while gathering diagnostics that the user may want to address.
+    pub warnings: Vec<String>,
 }
 
-impl Diagnostics {
-    async fn gather() -> Self {
+impl DiagnosticsReport {
+    /// Gather diagnostics, checking each service in the configuration loaded by [`Qcs::load`].
+    pub async fn gather() -> Self {
         let client = Qcs::load();
 
-        let (qcs, qvm) = futures::future::join(
-            QcsApiDiagnostics::gather(&client),
-            QvmDiagnostics::gather(&client),
-        )
-        .await;
+        let (qcs, qvm) = futures::future::join(check_qcs(&client), check_qvm(&client)).await;
+        let quilc = check_quilc(&client);
+        let libquil = check_libquil().await;
+
+        let warnings = [
+            ("quilc", quilc.version.as_deref(), MIN_QUILC_VERSION),
+            ("qvm", qvm.version.as_deref(), MIN_QVM_VERSION),
+            (
+                "libquil quilc",
+                libquil.quilc_version.as_deref(),
+                MIN_QUILC_VERSION,
+            ),
+            (
+                "libquil qvm",
+                libquil.qvm_version.as_deref(),
+                MIN_QVM_VERSION,
+            ),
+        ]
+        .iter()
+        .filter_map(|(service, version, minimum)| {
+            version.and_then(|version| version_warning(service, version, minimum))
+        })
+        .collect();
+
         Self {
-            version: build_info::PKG_VERSION.to_owned(),
+            sdk_version: build_info::PKG_VERSION.to_owned(),
             rust_version: build_info::RUSTC_VERSION.to_owned(),
             features: build_info::FEATURES.to_vec(),
+            config_paths: ConfigPaths::from_env(),
             qcs,
-            quilc: QuilcDiagnostics::gather(&client),
+            quilc,
             qvm,
-            libquil: LibquilDiagnostics::gather().await,
+            libquil,
+            warnings,
         }
     }
 }
 
-impl std::fmt::Display for Diagnostics {
+/// Returns an upgrade recommendation if `version` of `service` is older than `minimum`.
+fn version_warning(service: &str, version: &str, minimum: &str) -> Option<String> {
+    let warning = match (parse_version(version), parse_version(minimum)) {
+        (Some(parsed), Some(minimum_parsed)) if parsed < minimum_parsed => format!(
+            "{service} version {version} is older than the minimum supported version {minimum}; please upgrade"
+        ),
+        (None, _) => format!(
+            "could not determine whether {service} version {version:?} is supported (minimum {minimum})"
+        ),
+        _ => return None,
+    };
+
+    #[cfg(feature = "tracing")]
+    tracing::warn!("{warning}");
+
+    Some(warning)
+}
+
+/// Parse the leading `major.minor.patch` of a version string such as `1.23.0` or
+/// `1.17.1 [e0b9c1a]`. Missing components are treated as zero.
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let mut components = version
+        .split_whitespace()
+        .next()?
+        .trim_start_matches('v')
+        .split('.')
+        .map(|component| {
+            let digits: String = component.chars().take_while(char::is_ascii_digit).collect();
+            digits.parse::<u64>().ok()
+        });
+    let major = components.next()??;
+    let minor = components.next().flatten().unwrap_or(0);
+    let patch = components.next().flatten().unwrap_or(0);
+    Some((major, minor, patch))
+}
+
+impl std::fmt::Display for DiagnosticsReport {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "qcs-sdk-rust version: {}", self.version)?;
+        writeln!(f, "qcs-sdk-rust version: {}", self.sdk_version)?;
         writeln!(f, "rustc version: {}", self.rust_version)?;
         writeln!(f, "features: {}", self.features.join(", "))?;
+        writeln!(f, "configuration:")?;
+        writeln!(f, "  settings: {}", self.config_paths.settings)?;
+        writeln!(f, "  secrets: {}", self.config_paths.secrets)?;
         writeln!(f, "qcs:")?;
         writeln!(f, "  address: {}", self.qcs.address)?;
-        writeln!(f, "  network reachable: {}", self.qcs.network_reachable)?;
+        writeln!(f, "  network reachable: {}", self.qcs.reachable)?;
+        writeln!(f, "  latency: {}", format_latency(self.qcs.latency_ms))?;
         writeln!(f, "  authentication: {}", self.qcs.authentication)?;
         writeln!(f, "quilc:")?;
-        writeln!(f, "  address: {}", self.quilc.address)?;
-        writeln!(
-            f,
-            "  version: {}",
-            format_option(self.quilc.version.as_ref())
-        )?;
-        writeln!(f, "  available: {}", self.quilc.available)?;
+        write!(f, "{}", self.quilc)?;
         writeln!(f, "qvm:")?;
-        writeln!(f, "  address: {}", self.qvm.address)?;
-        writeln!(f, "  version: {}", format_option(self.qvm.version.as_ref()))?;
-        writeln!(f, "  available: {}", self.qvm.available)?;
+        write!(f, "{}", self.qvm)?;
         writeln!(f, "libquil:")?;
         writeln!(f, "  available: {}", self.libquil.available)?;
         writeln!(
@@ -90,38 +167,132 @@ impl std::fmt::Display for Diagnostics {
             "  qvm version: {}",
             format_option(self.libquil.qvm_version.as_ref())
         )?;
+        if !self.warnings.is_empty() {
+            writeln!(f, "warnings:")?;
+            for warning in &self.warnings {
+                writeln!(f, "  - {warning}")?;
+            }
+        }
         Ok(())
     }
 }
 
-#[derive(Debug)]
-struct QcsApiDiagnostics {
-    address: String,
-    network_reachable: bool,
-    authentication: QcsApiAuthenticationResult,
+/// The files QCS settings and secrets are read from.
+#[derive(Clone, Debug, Serialize)]
+#[non_exhaustive]
+pub struct ConfigPaths {
+    /// The settings file, from `QCS_SETTINGS_FILE_PATH` or `~/.qcs/settings.toml`
+    pub settings: ConfigPath,
+    /// The secrets file, from `QCS_SECRETS_FILE_PATH` or `~/.qcs/secrets.toml`
+    pub secrets: ConfigPath,
+}
+
+impl ConfigPaths {
+    fn from_env() -> Self {
+        Self {
+            settings: ConfigPath::new(settings_path()),
+            secrets: ConfigPath::new(secrets_path()),
+        }
+    }
+}
+
+/// A configuration file, and whether it exists.
+#[derive(Clone, Debug, Serialize)]
+#[non_exhaustive]
+pub struct ConfigPath {
+    /// The path of the file, or `None` if it can't be determined.
+    pub path: Option<PathBuf>,
+    /// Whether a file exists at `path`.
+    pub exists: bool,
+}
+
+impl ConfigPath {
+    fn new(path: Option<PathBuf>) -> Self {
+        let exists = path.as_ref().is_some_and(|path| path.is_file());
+        Self { path, exists }
+    }
+}
+
+impl std::fmt::Display for ConfigPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.path {
+            Some(path) if self.exists => write!(f, "{}", path.display()),
+            Some(path) => write!(f, "{} (not found)", path.display()),
+            None => write!(f, "-"),
+        }
+    }
+}
+
+/// The health of the QCS API, as found by [`check_qcs`].
+#[derive(Clone, Debug, Serialize)]
+#[non_exhaustive]
+pub struct QcsHealth {
+    /// The URL of the API
+    pub address: String,
+    /// Whether the API responded at all
+    pub reachable: bool,
+    /// How long the API took to respond, if it did
+    pub latency_ms: Option<f64>,
+    /// Whether the configured credentials were accepted
+    pub authentication: QcsAuthentication,
 }
 
-#[derive(Debug)]
-enum QcsApiAuthenticationResult {
-    Success(User),
+/// The outcome of authenticating with the QCS API.
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "result", rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum QcsAuthentication {
+    /// The credentials were accepted. These fields identify the user, so should be removed before
+    /// sharing the report publicly.
+    Success {
+        /// The user's ID with the identity provider
+        idp_id: String,
+        /// The user's email address, if known, with all but the first character of its local part
+        /// redacted, such as `j***@example.com`
+        email: Option<String>,
+    },
+    /// The credentials were rejected, or the API couldn't be asked.
     Failure {
-        status_code: Option<reqwest::StatusCode>,
+        /// The HTTP status of the response, if there was one
+        status_code: Option<u16>,
+        /// A description of the failure
         error: String,
     },
 }
 
-impl std::fmt::Display for QcsApiAuthenticationResult {
+impl From<User> for QcsAuthentication {
+    fn from(user: User) -> Self {
+        Self::Success {
+            idp_id: user.idp_id,
+            email: user.profile.map(|profile| redact_email(&profile.email)),
+        }
+    }
+}
+
+/// Redact all but the first character of the local part of `email`, keeping its domain, which is
+/// enough to tell which of their accounts a user is authenticated as.
+fn redact_email(email: &str) -> String {
+    let (local, domain) = email.rsplit_once('@').unwrap_or((email, ""));
+    let first = local.chars().next().map(String::from).unwrap_or_default();
+    if domain.is_empty() {
+        format!("{first}***")
+    } else {
+        format!("{first}***@{domain}")
+    }
+}
+
+impl std::fmt::Display for QcsAuthentication {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            QcsApiAuthenticationResult::Success(user) => {
+            QcsAuthentication::Success { idp_id, email } => {
                 write!(
                     f,
                     "{} / email {}\n  ^^^ for your security, remove this line before posting publicly! ^^^",
-                    user.idp_id,
-                    format_option(user.profile.as_ref().map(|profile| &profile.email))
+                    idp_id,
+                    format_option(email.as_ref())
                 )?;
             }
-            QcsApiAuthenticationResult::Failure { status_code, error } => {
+            QcsAuthentication::Failure { status_code, error } => {
                 write!(f, "failure: ")?;
                 match status_code {
                     Some(status_code) => {
@@ -138,132 +309,146 @@ impl std::fmt::Display for QcsApiAuthenticationResult {
     }
 }
 
-impl QcsApiDiagnostics {
-    async fn gather(client: &Qcs) -> Self {
-        let configuration = client.get_config();
-        let address = configuration.api_url().to_string();
-
-        let network_reachable = reqwest::get(&address).await.is_ok();
-        let client = qcs_api_client_openapi::apis::configuration::Configuration::with_qcs_config(
-            configuration.clone(),
-        );
-
-        let authentication =
-            match qcs_api_client_openapi::apis::authentication_api::auth_get_user(&client).await {
-                Ok(response) => QcsApiAuthenticationResult::Success(response),
-                Err(error) => QcsApiAuthenticationResult::Failure {
-                    status_code: error.status_code(),
-                    error: error.to_string(),
-                },
-            };
+/// Check whether the QCS API configured in `client` is reachable, and whether it accepts the
+/// configured credentials.
+pub async fn check_qcs(client: &Qcs) -> QcsHealth {
+    let configuration = client.get_config();
+    let address = configuration.api_url().to_string();
+
+    let http_client = client
+        .connection()
+        .http_client()
+        .cloned()
+        .unwrap_or_default();
+    let started = Instant::now();
+    let reachable = http_client.get(&address).send().await.is_ok();
+    let latency_ms = reachable.then(|| elapsed_ms(started));
+    let client = client.get_openapi_client();
+
+    let authentication =
+        match qcs_api_client_openapi::apis::authentication_api::auth_get_user(&client).await {
+            Ok(response) => response.into(),
+            Err(error) => QcsAuthentication::Failure {
+                status_code: error.status_code().map(reqwest::StatusCode::as_u16),
+                error: error.to_string(),
+            },
+        };
+
+    QcsHealth {
+        address,
+        reachable,
+        latency_ms,
+        authentication,
+    }
+}
+
+/// The health of a `quilc` or QVM server, as found by [`check_quilc`] or [`check_qvm`].
+#[derive(Clone, Debug, Serialize)]
+#[non_exhaustive]
+pub struct ServiceHealth {
+    /// The address of the server
+    pub address: String,
+    /// The version the server reported, if it responded
+    pub version: Option<String>,
+    /// Whether the server responded
+    pub available: bool,
+    /// How long the server took to report its version, if it responded
+    pub latency_ms: Option<f64>,
+}
 
+impl ServiceHealth {
+    fn new(address: String, started: Instant, version: Option<String>) -> Self {
+        let available = version.is_some();
         Self {
             address,
-            network_reachable,
-            authentication,
+            version,
+            available,
+            latency_ms: available.then(|| elapsed_ms(started)),
         }
     }
 }
 
-#[derive(Debug)]
-struct QuilcDiagnostics {
-    address: String,
-    version: Option<String>,
-    available: bool,
+impl std::fmt::Display for ServiceHealth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "  address: {}", self.address)?;
+        writeln!(f, "  version: {}", format_option(self.version.as_ref()))?;
+        writeln!(f, "  available: {}", self.available)?;
+        writeln!(f, "  latency: {}", format_latency(self.latency_ms))
+    }
 }
 
-impl QuilcDiagnostics {
-    fn gather(client: &Qcs) -> Self {
-        let address = client.get_config().quilc_url().to_string();
-        match rpcq::Client::new(&address) {
-            Ok(mut client) => {
-                // Set timeout in case the Quilc service is not available. Without
-                // this timeout, RPCQ would hang indefinitely when trying to create
-                // the ZMQ context.
-                client.set_timeout(1000);
-                let (version, available) = match client.get_version_info() {
-                    Ok(version) => (Some(version), true),
-                    Err(_) => (None, false),
-                };
-
-                Self {
-                    address,
-                    version,
-                    available,
-                }
-            }
-            Err(_) => Self {
-                address,
-                version: None,
-                available: false,
-            },
-        }
-    }
+/// Check whether the `quilc` server configured in `client` responds, waiting at most one second.
+pub fn check_quilc(client: &Qcs) -> ServiceHealth {
+    let address = client.get_config().quilc_url().to_string();
+    let started = Instant::now();
+    let version = rpcq::Client::new(&address).ok().and_then(|mut client| {
+        // Set timeout in case the Quilc service is not available. Without
+        // this timeout, RPCQ would hang indefinitely when trying to create
+        // the ZMQ context.
+        client.set_timeout(1000);
+        client.get_version_info().ok()
+    });
+    ServiceHealth::new(address, started, version)
 }
 
-#[derive(Debug)]
-struct QvmDiagnostics {
-    address: String,
-    version: Option<String>,
-    available: bool,
+/// Check whether the QVM server configured in `client` responds, waiting at most one second.
+pub async fn check_qvm(client: &Qcs) -> ServiceHealth {
+    let options = QvmOptions::new().with_timeout(Some(Duration::from_secs(1)));
+    let qvm_client = qvm::http::HttpClient::from(client);
+    let started = Instant::now();
+    let version = qvm_client.get_version_info(&options).await.ok();
+    ServiceHealth::new(qvm_client.qvm_url, started, version)
 }
 
-impl QvmDiagnostics {
-    async fn gather(client: &Qcs) -> Self {
-        let options = QvmOptions {
-            timeout: Some(Duration::from_secs(1)),
-        };
-        let qvm_client = qvm::http::HttpClient::from(client);
-        let (version, available) = match qvm_client.get_version_info(&options).await {
-            Ok(version) => (Some(version), true),
-            Err(_) => (None, false),
-        };
+/// The versions of quilc and the QVM in libquil, as found by [`check_libquil`].
+#[derive(Clone, Debug, Serialize)]
+#[non_exhaustive]
+pub struct LibquilHealth {
+    /// Whether this crate was built with the `libquil` feature
+    pub available: bool,
+    /// The version of the QVM in libquil
+    pub qvm_version: Option<String>,
+    /// The version of quilc in libquil
+    pub quilc_version: Option<String>,
+}
 
-        Self {
-            address: qvm_client.qvm_url,
-            version,
-            available,
+/// Get the versions of quilc and the QVM in libquil, if this crate was built with it.
+#[allow(clippy::unused_async)]
+pub async fn check_libquil() -> LibquilHealth {
+    #[cfg(not(feature = "libquil"))]
+    {
+        LibquilHealth {
+            available: false,
+            qvm_version: None,
+            quilc_version: None,
+        }
+    }
+    #[cfg(feature = "libquil")]
+    {
+        let qvm_version = (qvm::libquil::Client {})
+            .get_version_info(&QvmOptions::default())
+            .await
+            .ok();
+        let quilc_version = (crate::compiler::libquil::Client {})
+            .get_version_info()
+            .ok();
+        LibquilHealth {
+            available: true,
+            qvm_version,
+            quilc_version,
         }
     }
 }
 
-#[derive(Debug)]
-struct LibquilDiagnostics {
-    available: bool,
-    qvm_version: Option<String>,
-    quilc_version: Option<String>,
+/// The time since `started`, in milliseconds.
+fn elapsed_ms(started: Instant) -> f64 {
+    started.elapsed().as_secs_f64() * 1000.0
 }
 
-impl LibquilDiagnostics {
-    #[allow(clippy::unused_async)]
-    async fn gather() -> Self {
-        #[cfg(not(feature = "libquil"))]
-        {
-            Self {
-                available: false,
-                qvm_version: None,
-                quilc_version: None,
-            }
-        }
-        #[cfg(feature = "libquil")]
-        {
-            let qvm_version = match (qvm::libquil::Client {})
-                .get_version_info(&QvmOptions::default())
-                .await
-            {
-                Ok(version) => Some(version),
-                Err(_) => None,
-            };
-            let quilc_version = match (crate::compiler::libquil::Client {}).get_version_info() {
-                Ok(version) => Some(version),
-                Err(_) => None,
-            };
-            Self {
-                available: true,
-                qvm_version,
-                quilc_version,
-            }
-        }
+fn format_latency(latency_ms: Option<f64>) -> Cow<'static, str> {
+    match latency_ms {
+        Some(latency_ms) => format!("{latency_ms:.1} ms").into(),
+        None => "-".into(),
     }
 }
 
@@ -276,3 +461,108 @@ where
         None => "-".into(),
     }
 }
+
+#[cfg(test)]
+mod describe_version_checks {
+    use super::{parse_version, version_warning};
+
+    #[test]
+    fn it_parses_service_version_strings() {
+        assert_eq!(parse_version("1.23.0"), Some((1, 23, 0)));
+        assert_eq!(parse_version("1.17.1 [e0b9c1a]"), Some((1, 17, 1)));
+        assert_eq!(parse_version("v2.1"), Some((2, 1, 0)));
+        assert_eq!(parse_version("1.26.0-rc1"), Some((1, 26, 0)));
+        assert_eq!(parse_version("unknown"), None);
+        assert_eq!(parse_version(""), None);
+    }
+
+    #[test]
+    fn it_warns_about_old_or_unparseable_versions() {
+        assert_eq!(version_warning("quilc", "1.23.0", "1.23.0"), None);
+        assert_eq!(version_warning("quilc", "1.24.1", "1.23.0"), None);
+        assert!(version_warning("quilc", "1.22.9", "1.23.0")
+            .unwrap()
+            .contains("please upgrade"));
+        assert!(version_warning("qvm", "dev", "1.17.1").is_some());
+    }
+}
+
+#[cfg(test)]
+mod describe_diagnostics_report {
+    use super::{
+        redact_email, ConfigPath, ConfigPaths, DiagnosticsReport, LibquilHealth, QcsAuthentication,
+        QcsHealth, ServiceHealth,
+    };
+
+    fn report() -> DiagnosticsReport {
+        let service = |address: &str, version: Option<&str>| ServiceHealth {
+            address: address.to_string(),
+            version: version.map(str::to_string),
+            available: version.is_some(),
+            latency_ms: version.map(|_| 2.5),
+        };
+        DiagnosticsReport {
+            sdk_version: "1.0.0".to_string(),
+            rust_version: "1.80.0".to_string(),
+            features: vec!["qpu"],
+            config_paths: ConfigPaths {
+                settings: ConfigPath {
+                    path: Some("/home/user/.qcs/settings.toml".into()),
+                    exists: false,
+                },
+                secrets: ConfigPath {
+                    path: None,
+                    exists: false,
+                },
+            },
+            qcs: QcsHealth {
+                address: "https://api.qcs.rigetti.com".to_string(),
+                reachable: true,
+                latency_ms: Some(12.0),
+                authentication: QcsAuthentication::Failure {
+                    status_code: Some(401),
+                    error: "unauthorized".to_string(),
+                },
+            },
+            quilc: service("tcp://127.0.0.1:5555", Some("1.23.0")),
+            qvm: service("http://127.0.0.1:5000", None),
+            libquil: LibquilHealth {
+                available: false,
+                qvm_version: None,
+                quilc_version: None,
+            },
+            warnings: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn it_serializes_each_service() {
+        let json = serde_json::to_value(report()).unwrap();
+        assert_eq!(json["sdk_version"], "1.0.0");
+        assert_eq!(json["qcs"]["reachable"], true);
+        assert_eq!(json["qcs"]["authentication"]["result"], "failure");
+        assert_eq!(json["qcs"]["authentication"]["status_code"], 401);
+        assert_eq!(json["quilc"]["version"], "1.23.0");
+        assert_eq!(json["quilc"]["latency_ms"], 2.5);
+        assert_eq!(json["qvm"]["available"], false);
+        assert!(json["qvm"]["latency_ms"].is_null());
+        assert_eq!(json["config_paths"]["settings"]["exists"], false);
+    }
+
+    #[test]
+    fn it_displays_a_readable_report() {
+        let report = report().to_string();
+        assert!(report.contains("settings: /home/user/.qcs/settings.toml (not found)"));
+        assert!(report.contains("secrets: -"));
+        assert!(report.contains("quilc:\n  address: tcp://127.0.0.1:5555\n  version: 1.23.0"));
+        assert!(report.contains("  latency: 2.5 ms"));
+        assert!(report.contains("  available: false\n  latency: -"));
+    }
+
+    #[test]
+    fn it_redacts_email_addresses() {
+        assert_eq!(redact_email("jane.doe@example.com"), "j***@example.com");
+        assert_eq!(redact_email("jane"), "j***");
+        assert_eq!(redact_email(""), "***");
+    }
+}
@@ -4,22 +4,68 @@
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::num::NonZeroU16;
+#[cfg(feature = "qpu")]
+use std::num::NonZeroUsize;
+use std::str::FromStr;
+#[cfg(feature = "qpu")]
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
+#[cfg(feature = "qpu")]
+use std::time::{Instant, SystemTime};
 
+use indexmap::IndexMap;
 use qcs_api_client_common::configuration::LoadError;
-use quil_rs::quil::ToQuilError;
+#[cfg(feature = "qpu")]
+use qcs_api_client_openapi::models::InstructionSetArchitecture;
+use quil_rs::instruction::{Instruction, MemoryReference, ScalarType};
+use quil_rs::quil::{Quil, ToQuilError};
+use quil_rs::Program;
+#[cfg(feature = "qpu")]
+use serde::{Deserialize, Serialize};
 
 use crate::client::Qcs;
-use crate::compiler::quilc::{self, CompilerOpts};
-use crate::execution_data::{self, ResultData};
-use crate::qpu::api::{ExecutionOptions, JobId};
-use crate::qpu::translation::TranslationOptions;
-use crate::qpu::ExecutionError;
+#[cfg(feature = "qcs-api")]
+use crate::compiler::quilc;
+#[cfg(feature = "qpu")]
+use crate::compiler::{quilc::CompilerOpts, CompilerBackend};
+use crate::execution_data::{self, report::Report, ResultData};
+use crate::metrics::Stage;
+#[cfg(feature = "qpu")]
+use crate::metrics::StageTimings;
+#[cfg(feature = "qpu")]
+use crate::qpu::{
+    self,
+    api::{until_cancelled, CancellationToken, ExecutionOptions, JobId, JobStatus},
+    reservation::{ReservationExecution, ReservationExecutionStatus, ShotBudget},
+    translation::{SettingsEpoch, TranslationOptions},
+    ExecutionError, ReadoutChunks,
+};
+use crate::qvm;
 use crate::qvm::http::AddressRequest;
-use crate::{qpu, qvm};
+use crate::qvm::noise::NoiseModel;
 use quil_rs::program::ProgramError;
 
+#[cfg(feature = "qpu")]
+mod experiment;
+#[cfg(feature = "qpu")]
+mod many;
+mod memory;
+mod parameters;
+#[cfg(feature = "qpu")]
+mod plan;
+mod pragmas;
+mod shared;
+
+#[cfg(feature = "qpu")]
+pub use experiment::{Experiment, ExperimentResults};
+#[cfg(feature = "qpu")]
+pub use many::{execute_on_many, ExecuteOnManyOptions, ExecuteOnManyOptionsBuilder};
+#[cfg(feature = "qpu")]
+pub use plan::{CompilationPlan, ConnectionPlan, EstimatedSizes, ExecutionPlan, TranslationPlan};
+pub use pragmas::RewiringStrategy;
+pub use shared::SharedExecutable;
+
 /// The builder interface for executing Quil programs on QVMs and QPUs.
 ///
 /// # Example
@@ -86,19 +132,71 @@ use quil_rs::program::ProgramError;
 #[derive(Clone)]
 #[allow(missing_debug_implementations)]
 pub struct Executable<'executable, 'execution> {
-    quil: Arc<str>,
+    source: Source,
     shots: NonZeroU16,
     readout_memory_region_names: Option<Vec<Cow<'executable, str>>>,
+    readout_indices: HashMap<String, Vec<usize>>,
+    narrowed_readouts: Vec<String>,
     params: Parameters,
     qcs_client: Option<Arc<Qcs>>,
-    quilc_client: Option<Arc<dyn quilc::Client + Send + Sync>>,
+    #[cfg(feature = "qpu")]
+    compiler: Option<Arc<dyn CompilerBackend + Send + Sync>>,
+    #[cfg(feature = "qpu")]
     compiler_options: CompilerOpts,
+    #[cfg(feature = "qpu")]
+    max_qubit_reselections: u32,
+    #[cfg(feature = "qpu")]
+    force_isa_refresh: bool,
+    noise_model: Option<NoiseModel>,
+    #[cfg(feature = "qpu")]
     qpu: Option<qpu::Execution<'execution>>,
     qvm: Option<qvm::Execution>,
+    #[cfg(feature = "qvm-http")]
+    qvm_http_client: Option<qvm::http::HttpClient>,
+    skip_quil_t_check: bool,
+    shot_memory: ShotMemory,
+    active_reset: Option<bool>,
+    initial_rewiring: Option<RewiringStrategy>,
+    substitutions_in_errors: bool,
+    #[cfg(feature = "qpu")]
+    parameter_sweeps: Vec<ParameterSweep>,
+    #[cfg(feature = "qpu")]
+    stage_timings: StageTimings,
+    #[cfg(feature = "qpu")]
+    deadline: Option<Duration>,
+    #[cfg(feature = "qpu")]
+    cancellation_token: Option<CancellationToken>,
+    #[cfg(feature = "qpu")]
+    compilation_cache: Option<Arc<quilc::CompilationCache>>,
 }
 
 pub(crate) type Parameters = HashMap<Box<str>, Vec<f64>>;
 
+/// How many times in a row [`Executable::wait_for_results_with`] accepts a job status of
+/// [`JobStatus::Unknown`] before giving up on the job.
+#[cfg(feature = "qpu")]
+const MAX_UNKNOWN_STATUS_POLLS: u32 = 10;
+
+/// The program an [`Executable`] was created from, kept as it was given so that it's only parsed
+/// or serialized when needed.
+#[derive(Clone)]
+enum Source {
+    /// From [`Executable::from_quil`].
+    Quil(Arc<str>),
+    /// From [`Executable::from_program`].
+    Program(Arc<Program>),
+}
+
+/// The values a single memory location takes in each set of parameters of a batch. See
+/// [`Executable::with_parameter_sweep`].
+#[cfg(feature = "qpu")]
+#[derive(Clone, Debug)]
+struct ParameterSweep {
+    name: Box<str>,
+    index: usize,
+    values: Vec<f64>,
+}
+
 impl<'executable> Executable<'executable, '_> {
     /// Create an [`Executable`] from a string containing a  [quil](https://github.com/quil-lang/quil)
     /// program. No additional work is done in this function, so the `quil` may actually be invalid.
@@ -117,16 +215,58 @@ impl<'executable> Executable<'executable, '_> {
     #[must_use]
     #[allow(clippy::missing_panics_doc)]
     pub fn from_quil<Quil: Into<Arc<str>>>(quil: Quil) -> Self {
+        Self::from_source(Source::Quil(quil.into()))
+    }
+
+    /// Create an [`Executable`] from a parsed [`Program`], as [`Executable::from_quil`] does from
+    /// a string.
+    ///
+    /// The program is kept as it is, so running it on a QVM doesn't parse it again. It's only
+    /// serialized, with its calibrations, frames, and waveforms, when it's sent to `quilc` or
+    /// translated for a QPU.
+    #[must_use]
+    pub fn from_program(program: Program) -> Self {
+        Self::from_source(Source::Program(Arc::new(program)))
+    }
+
+    fn from_source(source: Source) -> Self {
         Self {
-            quil: quil.into(),
+            source,
             shots: NonZeroU16::new(1).expect("value is non-zero"),
             readout_memory_region_names: None,
+            readout_indices: HashMap::new(),
+            narrowed_readouts: Vec::new(),
             params: Parameters::new(),
+            #[cfg(feature = "qpu")]
             compiler_options: CompilerOpts::default(),
+            #[cfg(feature = "qpu")]
+            max_qubit_reselections: 0,
+            #[cfg(feature = "qpu")]
+            force_isa_refresh: false,
+            noise_model: None,
+            #[cfg(feature = "qpu")]
             qpu: None,
             qvm: None,
+            #[cfg(feature = "qvm-http")]
+            qvm_http_client: None,
             qcs_client: None,
-            quilc_client: None,
+            #[cfg(feature = "qpu")]
+            compiler: None,
+            skip_quil_t_check: false,
+            shot_memory: ShotMemory::Reset,
+            active_reset: None,
+            initial_rewiring: None,
+            substitutions_in_errors: false,
+            #[cfg(feature = "qpu")]
+            parameter_sweeps: Vec::new(),
+            #[cfg(feature = "qpu")]
+            stage_timings: StageTimings::default(),
+            #[cfg(feature = "qpu")]
+            deadline: None,
+            #[cfg(feature = "qpu")]
+            cancellation_token: None,
+            #[cfg(feature = "qpu")]
+            compilation_cache: None,
         }
     }
 
@@ -141,6 +281,16 @@ impl<'executable> Executable<'executable, '_> {
     ///     of this reference should be the lifetime of the [`Executable`], which is the lifetime of
     ///     the `quil` argument to [`Executable::from_quil`].
     ///
+    ///     A memory reference with an index, such as `"ro[3]"`, reads only that element of the
+    ///     region, as with [`Executable::read_indices`]: the results for the region have a single
+    ///     column, `ro[0]`, holding the values of `ro[3]`. Reading more elements of the same region
+    ///     adds columns in the order they are read, so reading `"ro[3]"` then `"ro[1]"` is the same
+    ///     as `.read_indices("ro", &[3, 1])`.
+    ///
+    ///     Reading an element of a region which is already read whole would drop the rest of it,
+    ///     so executing fails with [`Error::ReadoutNarrowed`] instead, unless the whole region is
+    ///     read again afterwards.
+    ///
     /// # Example
     ///
     /// ```rust
@@ -198,14 +348,57 @@ impl<'executable> Executable<'executable, '_> {
         S: Into<Cow<'executable, str>>,
     {
         let register = register.into();
+        if let Some((name, index)) = split_indexed_reference(&register) {
+            if self.reads_whole_region(&name) {
+                self.narrowed_readouts.push(register.to_string());
+            }
+            let mut indices = self.readout_indices.get(&name).cloned().unwrap_or_default();
+            indices.push(index);
+            return self.read_indices(name, &indices);
+        }
         #[cfg(feature = "tracing")]
         tracing::trace!("reading from register {:?}", register);
+        self.readout_indices.remove(register.as_ref());
+        self.narrowed_readouts.retain(|reference| {
+            split_indexed_reference(reference).map_or(true, |(name, _)| name != register)
+        });
         let mut readouts = self.readout_memory_region_names.take().unwrap_or_default();
         readouts.push(register);
         self.readout_memory_region_names = Some(readouts);
         self
     }
 
+    /// Read only the values at `indices` of the memory region `register`, rather than the whole
+    /// region as [`Executable::read_from`] does. For programs which declare large regions but only
+    /// use a few of their values, this makes the results much smaller.
+    ///
+    /// The results have one column of the region for each index, in the order given, so after
+    /// `.read_indices("ro", &[0, 2, 5])` the results for `ro[2]` are the values of `ro[5]`. The QVM
+    /// is only asked for the values at `indices`. Results from a QPU are filtered once they have
+    /// been retrieved, both the values read out to the region and its final contents, leaving
+    /// other regions as they are (see
+    /// [`QpuResultData::select_indices`](crate::qpu::QpuResultData::select_indices)); results
+    /// which were [spilled](crate::spill) to disk aren't filtered.
+    ///
+    /// A later call to [`Executable::read_from`] for the same region reads the whole region again.
+    #[must_use]
+    pub fn read_indices<S>(mut self, register: S, indices: &[usize]) -> Self
+    where
+        S: Into<Cow<'executable, str>>,
+    {
+        let register = register.into();
+        #[cfg(feature = "tracing")]
+        tracing::trace!("reading indices {:?} of register {:?}", indices, register);
+        self.readout_indices
+            .insert(register.to_string(), indices.to_vec());
+        let mut readouts = self.readout_memory_region_names.take().unwrap_or_default();
+        if !readouts.contains(&register) {
+            readouts.push(register);
+        }
+        self.readout_memory_region_names = Some(readouts);
+        self
+    }
+
     /// Sets a concrete value for [parametric compilation].
     /// The validity of parameters is not checked until execution.
     ///
@@ -215,7 +408,9 @@ impl<'executable> Executable<'executable, '_> {
     ///     `DECLARE` statement in the Quil program. The lifetime of the reference should be the
     ///     same as the [`Executable`]: that is the same as the `quil` param to [`Executable::from_quil`].
     /// 2. `index`: The index into the memory vector that you're setting.
-    /// 3. `value`: The value to set for the specified memory.
+    /// 3. `value`: The value to set for the specified memory. For a `BIT`, `OCTET`, or `INTEGER`
+    ///     region this must be a whole number which the declared type can hold; on a QPU it is
+    ///     sent with that type (see [`crate::qpu::patch_values::type_parameters`]).
     ///
     /// # Example
     ///
@@ -295,6 +490,10 @@ impl<'executable> Executable<'executable, '_> {
     #[must_use]
     pub fn with_qcs_client(mut self, client: Qcs) -> Self {
         self.qcs_client = Some(Arc::from(client));
+        #[cfg(feature = "qvm-http")]
+        {
+            self.qvm_http_client = None;
+        }
         self
     }
 
@@ -312,6 +511,64 @@ impl<'executable> Executable<'executable, '_> {
     }
 }
 
+/// Split a memory reference with an index, such as `ro[3]`, into the name of its region and the
+/// index. Returns `None` for anything else, including a bare region name, which Quil would read as
+/// the first index of the region.
+fn split_indexed_reference(reference: &str) -> Option<(String, usize)> {
+    if !reference.trim_end().ends_with(']') {
+        return None;
+    }
+    let reference = MemoryReference::from_str(reference).ok()?;
+    let index = usize::try_from(reference.index).ok()?;
+    Some((reference.name, index))
+}
+
+/// Run `future` to completion, or fail with `stage` if `deadline` passes first.
+#[cfg(feature = "qpu")]
+pub(crate) async fn within_deadline<F: std::future::Future>(
+    deadline: Option<Instant>,
+    stage: Stage,
+    future: F,
+) -> Result<F::Output, Stage> {
+    match deadline {
+        Some(deadline) => tokio::time::timeout_at(deadline.into(), future)
+            .await
+            .map_err(|_| stage),
+        None => Ok(future.await),
+    }
+}
+
+/// Combine the results of jobs which each ran some of the shots of the same program, in the order
+/// the jobs are given. The combined duration is the total time spent executing the jobs.
+#[cfg(feature = "qpu")]
+fn concatenate_shots(results: Vec<execution_data::ExecutionData>) -> ExecutionResult {
+    let mut results = results.into_iter();
+    let mut combined = results
+        .next()
+        .ok_or_else(|| Error::ShotSplitting("no jobs were run".to_string()))?;
+    for data in results {
+        match (&mut combined.result_data, &data.result_data) {
+            (ResultData::Qpu(combined_data), ResultData::Qpu(data)) => combined_data
+                .append_shots(data)
+                .map_err(|error| Error::ShotSplitting(error.to_string()))?,
+            _ => {
+                return Err(Error::ShotSplitting(
+                    "only results decoded into memory can be combined".to_string(),
+                ))
+            }
+        }
+        combined.duration = combined
+            .duration
+            .zip(data.duration)
+            .map(|(first, second)| first + second);
+        combined.timings = combined
+            .timings
+            .zip(data.timings)
+            .map(|(first, second)| first.then(second));
+    }
+    Ok(combined)
+}
+
 /// The [`Result`] from executing on a QPU or QVM.
 pub type ExecutionResult = Result<execution_data::ExecutionData, Error>;
 
@@ -326,29 +583,391 @@ impl Executable<'_, '_> {
     /// Set the client used for compilation.
     ///
     /// To disable compilation, set this to `None`.
+    #[cfg(feature = "qpu")]
     #[must_use]
-    #[allow(trivial_casts)]
     pub fn with_quilc_client<C: quilc::Client + Send + Sync + 'static>(
-        mut self,
+        self,
         client: Option<C>,
     ) -> Self {
-        self.quilc_client = client.map(|c| Arc::new(c) as _);
+        self.with_compiler_backend(client)
+    }
+
+    /// Set the compiler used to compile the program to native Quil before it is run on a QPU. Any
+    /// [`quilc::Client`] can be used, as can any other [`CompilerBackend`]. This replaces the
+    /// client set with [`Executable::with_quilc_client`].
+    ///
+    /// To disable compilation, set this to `None`.
+    #[cfg(feature = "qpu")]
+    #[must_use]
+    #[allow(trivial_casts)]
+    pub fn with_compiler_backend<C: CompilerBackend + Send + Sync + 'static>(
+        mut self,
+        backend: Option<C>,
+    ) -> Self {
+        self.compiler = backend.map(|backend| Arc::new(backend) as _);
         self
     }
 
     /// If set, the value will override the default compiler options
+    #[cfg(feature = "qpu")]
     #[must_use]
     pub fn compiler_options(mut self, options: CompilerOpts) -> Self {
         self.compiler_options = options;
         self
     }
 
+    /// If quilc can't place the program's qubits on the QPU, for instance because there is no path
+    /// between two qubits which interact, retry compilation on up to `max_reselections`
+    /// alternative subsets of the QPU's qubits. Defaults to 0, which never retries.
+    ///
+    /// The placement that was used is available from [`Executable::qubit_placement`] after
+    /// compiling. See [`quilc::compile_with_qubit_reselection`] for how subsets are chosen.
+    #[cfg(feature = "qpu")]
+    #[must_use]
+    pub fn with_qubit_reselection(mut self, max_reselections: u32) -> Self {
+        self.max_qubit_reselections = max_reselections;
+        self
+    }
+
+    /// ISAs are cached by [`IsaCache::global`](crate::qpu::IsaCache::global) so that compiling for the
+    /// same QPU again doesn't fetch its ISA again. Set this to `true` to always fetch the current
+    /// ISA when compiling, e.g. right after the QPU has been recalibrated.
+    #[cfg(feature = "qpu")]
+    #[must_use]
+    pub fn with_force_isa_refresh(mut self, force_refresh: bool) -> Self {
+        self.force_isa_refresh = force_refresh;
+        self
+    }
+
+    /// Simulate `noise_model` when running on a QVM with [`Executable::execute_on_qvm`],
+    /// [`Executable::execute_and_measure_on_qvm`], or [`Executable::wavefunction_on_qvm`]. It has
+    /// no effect on a QPU, or on [`Executable::expectation_on_qvm`].
+    #[must_use]
+    pub fn with_noise_model(mut self, noise_model: NoiseModel) -> Self {
+        self.noise_model = Some(noise_model);
+        self
+    }
+
+    /// By default, programs containing Quil-T (pulse-level) instructions are rejected with
+    /// [`Error::QuilTNotSupportedByTarget`] before being sent to `quilc` or a QVM, neither of which
+    /// support them. Set this to `true` to skip that check and send the program as-is.
+    #[must_use]
+    pub fn skip_quil_t_check(mut self, skip: bool) -> Self {
+        self.skip_quil_t_check = skip;
+        self
+    }
+
+    /// Returns an error if the program contains Quil-T and the check hasn't been disabled with
+    /// [`Executable::skip_quil_t_check`].
+    fn check_quil_t(&self, program: &Program, target: Service) -> Result<(), Error> {
+        if self.skip_quil_t_check {
+            return Ok(());
+        }
+        match find_quil_t_instruction(program) {
+            Some(instruction) => Err(Error::QuilTNotSupportedByTarget {
+                instruction: instruction.to_quil_or_debug(),
+                target,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Set whether the program's classical memory is reset at the start of every shot
+    /// ([`ShotMemory::Reset`]) or carries over from one shot to the next
+    /// ([`ShotMemory::Persist`]). This is [`ShotMemory::Reset`] by default.
+    ///
+    /// Each service supports only what [`Service::shot_memory`] reports, which for both the QVM
+    /// and QPUs is [`ShotMemory::Reset`]: every shot starts from the same memory, zeroed apart from
+    /// the values set with [`Executable::with_parameter`], so a counter incremented with
+    /// `ADD counter[0] 1` is `1` at the end of every shot rather than counting the shots.
+    ///
+    /// Executions fail with [`Error::ShotMemoryNotSupported`], before the program is sent
+    /// anywhere, if the target doesn't support the requested behavior. When memory is reset, a
+    /// warning is logged (with the `tracing` feature) for programs which update memory before
+    /// writing it, e.g. with `ADD`, since they may have been written expecting it to persist.
+    #[must_use]
+    pub fn with_shot_memory(mut self, shot_memory: ShotMemory) -> Self {
+        self.shot_memory = shot_memory;
+        self
+    }
+
+    /// As [`Executable::with_shot_memory`], with [`ShotMemory::Reset`] for `true` and
+    /// [`ShotMemory::Persist`] for `false`.
+    #[must_use]
+    pub fn with_shot_memory_reset(self, reset: bool) -> Self {
+        self.with_shot_memory(if reset {
+            ShotMemory::Reset
+        } else {
+            ShotMemory::Persist
+        })
+    }
+
+    /// What happens to the program's classical memory between shots, see
+    /// [`Executable::with_shot_memory`].
+    #[must_use]
+    pub fn shot_memory(&self) -> ShotMemory {
+        self.shot_memory
+    }
+
+    /// Whether the program's classical memory is reset at the start of every shot, see
+    /// [`Executable::with_shot_memory`].
+    #[must_use]
+    pub fn shot_memory_reset(&self) -> bool {
+        self.shot_memory == ShotMemory::Reset
+    }
+
+    /// Returns an error if `target` doesn't treat memory between shots as requested, and
+    /// otherwise warns about parts of `program` which may expect memory to persist when it is
+    /// reset.
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    fn check_shot_memory(&self, program: &Program, target: Service) -> Result<(), Error> {
+        if target.shot_memory() != Some(self.shot_memory) {
+            return Err(Error::ShotMemoryNotSupported {
+                target,
+                requested: self.shot_memory,
+            });
+        }
+        #[cfg(feature = "tracing")]
+        {
+            let regions = memory::accumulating_regions(program);
+            if self.shot_memory == ShotMemory::Reset && !regions.is_empty() {
+                tracing::warn!(
+                    ?regions,
+                    ?target,
+                    "the program updates memory before writing it, but memory is reset every shot",
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Set whether every qubit is actively reset at the start of each shot, by starting the
+    /// program with a `RESET` instruction. With `false`, any `RESET` of every qubit at the start of
+    /// the program is removed, so that qubits are reset passively, by waiting for them to relax.
+    ///
+    /// The program is rewritten the same way whether it is run on a QVM or a QPU. `RESET`s later
+    /// in the program, or of single qubits, are left as they are. By default, the program is run as
+    /// it is written.
+    #[must_use]
+    pub fn with_active_reset(mut self, active_reset: bool) -> Self {
+        self.active_reset = Some(active_reset);
+        self.clear_executions();
+        self
+    }
+
+    /// Set how quilc chooses the physical qubits to place the program's qubits on, by starting the
+    /// program with a `PRAGMA INITIAL_REWIRING`, which replaces any already in the program.
+    ///
+    /// The pragma only affects compilation for a QPU, but is included in the program whether it
+    /// is run on a QVM or a QPU. By default, the program is run as it is written, and quilc uses its
+    /// own default strategy.
+    #[must_use]
+    pub fn with_initial_rewiring(mut self, strategy: RewiringStrategy) -> Self {
+        self.initial_rewiring = Some(strategy);
+        self.clear_executions();
+        self
+    }
+
+    /// Forget any program prepared for the QVM or a QPU, so that the next execution prepares it
+    /// again with the current settings.
+    fn clear_executions(&mut self) {
+        self.qvm = None;
+        #[cfg(feature = "qpu")]
+        {
+            self.qpu = None;
+        }
+    }
+
+    /// The program to run, with the pragmas and resets set with [`Executable::with_active_reset`]
+    /// and [`Executable::with_initial_rewiring`] applied.
+    fn program_quil(&self) -> Result<Arc<str>, Error> {
+        match &self.source {
+            Source::Quil(quil) if !self.applies_pragmas() => Ok(quil.clone()),
+            _ => Ok(self.program()?.to_quil()?.into()),
+        }
+    }
+
+    /// The parsed program to run, as [`Executable::program_quil`], without parsing it if it was
+    /// given to [`Executable::from_program`].
+    fn program(&self) -> Result<Cow<'_, Program>, Error> {
+        let program = match &self.source {
+            Source::Quil(quil) => Cow::Owned(Program::from_str(quil)?),
+            Source::Program(program) => Cow::Borrowed(program.as_ref()),
+        };
+        if !self.applies_pragmas() {
+            return Ok(program);
+        }
+        Ok(Cow::Owned(pragmas::apply(
+            &program,
+            self.active_reset,
+            self.initial_rewiring,
+        )))
+    }
+
+    /// Whether the program is changed by [`Executable::with_active_reset`] or
+    /// [`Executable::with_initial_rewiring`].
+    fn applies_pragmas(&self) -> bool {
+        self.active_reset.is_some() || self.initial_rewiring.is_some()
+    }
+
+    /// The program as it is run on the QVM, with each parameter set by
+    /// [`Executable::with_parameter`] applied as `MOVE` instructions at its start. Use this to
+    /// see what is actually run when a parametric program misbehaves.
+    ///
+    /// QPUs are sent the parameters separately, as patch values; see
+    /// [`Executable::patch_values`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::ParameterMismatch`] if a parameter doesn't fit a memory region the
+    /// program declares (see [`Executable::validate_parameters`]), [`Error::Substitution`] if it
+    /// otherwise can't be applied, or an error if the program isn't valid Quil.
+    pub fn to_quil_with_substitutions(&self) -> Result<String, Error> {
+        let program = self.program()?;
+        self.check_parameters(&program, Service::Qvm)?;
+        let program = qvm::apply_parameters_to_program(&program, &self.params)?;
+        Ok(program.to_quil()?)
+    }
+
+    /// Set whether an [`Error::ParameterMismatch`] includes the parameters as they were to be
+    /// applied: the program with every parameter's `MOVE` instructions for the QVM, or the table
+    /// of patch values for a QPU. This is `false` by default, since the program may be large.
+    #[must_use]
+    pub fn with_substitutions_in_errors(mut self, include: bool) -> Self {
+        self.substitutions_in_errors = include;
+        self
+    }
+
+    /// Check every parameter set with [`Executable::with_parameter`] against the memory regions
+    /// the program declares, without compiling or running it or contacting any service.
+    ///
+    /// This is done before the program is run on a QVM or submitted to a QPU, so that a parameter
+    /// which can't be applied is reported before anything is sent over the network. Call it
+    /// directly to check parameters as soon as they're set.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::ParameterMismatch`] describing the first parameter, by name, whose region
+    /// isn't declared, which is set at an index past the region's declared length, or whose value
+    /// the region's declared type can't hold, e.g. `0.5` for a `BIT`. The error gives the region,
+    /// the offending index, and the region's declared type and length, and its message includes
+    /// them too. Returns an error if the program isn't valid Quil.
+    pub fn validate_parameters(&self) -> Result<(), Error> {
+        parameters::check(&self.program()?, &self.params).map_err(parameter_mismatch)
+    }
+
+    /// As [`Executable::validate_parameters`] for `program`, the program as it is sent to
+    /// `target`, attaching the substituted parameters to the error if requested.
+    fn check_parameters(&self, program: &Program, target: Service) -> Result<(), Error> {
+        parameters::check(program, &self.params)
+            .map_err(|mismatch| self.with_substitutions(parameter_mismatch(mismatch), target))
+    }
+
+    /// Check the parameters before submitting to a QPU, against the memory regions of the program
+    /// already compiled for it if there is one, so that repeated submissions don't parse the
+    /// program each time.
+    fn check_qpu_parameters(&self) -> Result<(), Error> {
+        match &self.qpu {
+            Some(qpu) => self.check_parameters(qpu.program(), Service::Qpu),
+            None => self.check_parameters(&self.program()?, Service::Qpu),
+        }
+    }
+
+    /// Attach the substituted parameters to `error` if it is an [`Error::ParameterMismatch`] and
+    /// [`Executable::with_substitutions_in_errors`] is set. Parameters which don't fit the program
+    /// are applied without being checked, so that the mismatch can be seen.
+    fn with_substitutions(&self, error: Error, target: Service) -> Error {
+        match error {
+            Error::ParameterMismatch {
+                message,
+                region,
+                index,
+                declared_type,
+                declared_length,
+                substitutions: None,
+            } if self.substitutions_in_errors => Error::ParameterMismatch {
+                message,
+                region,
+                index,
+                declared_type,
+                declared_length,
+                substitutions: self.render_substitutions(target),
+            },
+            error => error,
+        }
+    }
+
+    /// The parameters as they are given to `target`, see [`Executable::with_substitutions_in_errors`].
+    fn render_substitutions(&self, target: Service) -> Option<String> {
+        if target == Service::Qpu {
+            let mut params: Vec<_> = self.params.iter().collect();
+            params.sort_by_key(|(name, _)| name.as_ref());
+            return Some(
+                params
+                    .into_iter()
+                    .map(|(name, values)| format!("{name}: {values:?}\n"))
+                    .collect(),
+            );
+        }
+        let program = self.program().ok()?;
+        let mut substituted = program.clone_without_body_instructions();
+        substituted.add_instructions(
+            self.params
+                .iter()
+                .flat_map(|(name, values)| qvm::parameter_moves(name, values))
+                .chain(program.body_instructions().cloned())
+                .collect::<Vec<_>>(),
+        );
+        substituted.to_quil().ok()
+    }
+
+    /// Whether the whole memory region `name` was read with [`Executable::read_from`].
+    fn reads_whole_region(&self, name: &str) -> bool {
+        !self.readout_indices.contains_key(name)
+            && self
+                .readout_memory_region_names
+                .as_ref()
+                .is_some_and(|names| names.iter().any(|region| region == name))
+    }
+
+    /// Returns an error if an indexed memory reference given to [`Executable::read_from`] would
+    /// have narrowed a memory region which was already being read whole.
+    fn check_readouts(&self) -> Result<(), Error> {
+        match self.narrowed_readouts.first() {
+            Some(reference) => Err(Error::ReadoutNarrowed {
+                reference: reference.clone(),
+            }),
+            None => Ok(()),
+        }
+    }
+
     fn get_readouts(&self) -> &[Cow<'_, str>] {
         self.readout_memory_region_names
             .as_ref()
             .map_or(&[Cow::Borrowed("ro")], Vec::as_slice)
     }
 
+    /// The memory regions to read from a QVM run of `program`. The default `ro` region is only
+    /// read if `program` declares it, so that programs without any readout can still be run.
+    fn readout_addresses(&self, program: &Program) -> HashMap<String, AddressRequest> {
+        self.get_readouts()
+            .iter()
+            .filter(|address| {
+                self.readout_memory_region_names.is_some()
+                    || program.memory_regions.contains_key(address.as_ref())
+            })
+            .map(|address| {
+                let request = self
+                    .readout_indices
+                    .get(address.as_ref())
+                    .map_or(AddressRequest::IncludeAll, |indices| {
+                        AddressRequest::Indices(indices.clone())
+                    });
+                (address.to_string(), request)
+            })
+            .collect()
+    }
+
     /// Execute on a QVM which must be available at the configured URL (default <http://localhost:5000>).
     ///
     /// # Warning
@@ -370,19 +989,14 @@ impl Executable<'_, '_> {
             "running Executable on QVM",
         );
 
-        let qvm = if let Some(qvm) = self.qvm.take() {
-            qvm
-        } else {
-            qvm::Execution::new(&self.quil)?
-        };
+        let qvm = self.take_qvm_execution()?;
+        let addresses = self.readout_addresses(qvm.program());
         let result = qvm
             .run(
                 self.shots,
-                self.get_readouts()
-                    .iter()
-                    .map(|address| (address.to_string(), AddressRequest::IncludeAll))
-                    .collect(),
+                addresses,
                 &self.params,
+                self.noise_model.as_ref(),
                 client,
             )
             .await;
@@ -392,100 +1006,834 @@ impl Executable<'_, '_> {
             .map(|registers| execution_data::ExecutionData {
                 result_data: ResultData::Qvm(registers),
                 duration: None,
+                timings: None,
             })
     }
-}
-
-impl<'execution> Executable<'_, 'execution> {
-    /// Remove and return `self.qpu` if it's set and still valid. Otherwise, create a new one.
-    async fn qpu_for_id<S>(&mut self, id: S) -> Result<qpu::Execution<'execution>, Error>
-    where
-        S: Into<Cow<'execution, str>>,
-    {
-        let id = id.into();
-        if let Some(qpu) = self.qpu.take() {
-            if qpu.quantum_processor_id == id.as_ref() && qpu.shots == self.shots {
-                return Ok(qpu);
-            }
-        }
-        qpu::Execution::new(
-            self.quil.clone(),
-            self.shots,
-            id,
-            self.qcs_client(),
-            self.quilc_client.clone(),
-            self.compiler_options,
-        )
-        .await
-        .map_err(Error::from)
-    }
 
-    /// Compile the program and execute it on a QPU, waiting for results.
-    ///
-    /// # Arguments
-    /// 1. `quantum_processor_id`: The name of the QPU to run on. This parameter affects the
-    ///     lifetime of the [`Executable`]. The [`Executable`] will only live as long as the last
-    ///     parameter passed into this function.
-    ///
-    /// # Warning
-    ///
-    /// This function uses [`tokio::task::spawn_blocking`] internally. See the docs for that function
-    /// to avoid blocking shutdown of the runtime.
+    /// Execute on a QVM like [`Executable::execute_on_qvm`], but keep the state needed to run the
+    /// program again between runs. This is meant for iterative algorithms such as VQE, which run
+    /// the same program many times with new parameters.
     ///
-    /// # Returns
+    /// The program is serialized for the QVM on the first run. Later runs only rewrite the `MOVE`
+    /// instructions of parameters whose values changed since the previous run, rather than applying
+    /// every parameter to a copy of the program and serializing it again. The program is
+    /// serialized again if the noise model changes.
     ///
-    /// An [`ExecutionResult`].
+    /// Runs are sent to the QVM configured by [`Executable::qcs_client`], through an
+    /// [`HttpClient`](qvm::http::HttpClient) which this [`Executable`] keeps, so that its
+    /// connection to the QVM is reused between runs. Setting a new client with
+    /// [`Executable::with_qcs_client`] replaces it.
     ///
     /// # Errors
-    /// All errors are human readable by way of [`mod@thiserror`]. Some common errors are:
-    ///
-    /// 1. You are not authenticated for QCS
-    /// 1. Your credentials don't have an active reservation for the QPU you requested
-    /// 1. [quilc] was not running.
-    /// 1. The `quil` that this [`Executable`] was constructed with was invalid.
-    /// 1. Missing parameters that should be filled with [`Executable::with_parameter`]
     ///
-    /// [quilc]: https://github.com/quil-lang/quilc
-    pub async fn execute_on_qpu_with_endpoint<S>(
+    /// See [`Error`].
+    #[cfg(feature = "qvm-http")]
+    pub async fn execute_on_qvm_with_state_reuse(&mut self) -> ExecutionResult {
+        let client = match &self.qvm_http_client {
+            Some(client) => client.clone(),
+            None => {
+                let client = qvm::http::HttpClient::from(self.qcs_client().as_ref());
+                self.qvm_http_client = Some(client.clone());
+                client
+            }
+        };
+        self.run_on_qvm_with_state_reuse(&client).await
+    }
+
+    /// Run on `client` as described in [`Executable::execute_on_qvm_with_state_reuse`].
+    async fn run_on_qvm_with_state_reuse<V: qvm::Client + ?Sized>(
         &mut self,
-        quantum_processor_id: S,
-        endpoint_id: S,
-        translation_options: Option<TranslationOptions>,
-    ) -> ExecutionResult
-    where
-        S: Into<Cow<'execution, str>>,
-    {
-        let job_handle = self
-            .submit_to_qpu_with_endpoint(quantum_processor_id, endpoint_id, translation_options)
-            .await?;
-        self.retrieve_results(job_handle).await
+        client: &V,
+    ) -> ExecutionResult {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            num_shots = %self.shots,
+            "running Executable on QVM, reusing its state",
+        );
+
+        let mut qvm = self.take_qvm_execution()?;
+        let addresses = self.readout_addresses(qvm.program());
+        let result = qvm
+            .run_with_state_reuse(
+                self.shots,
+                addresses,
+                &self.params,
+                self.noise_model.as_ref(),
+                client,
+            )
+            .await;
+        self.qvm = Some(qvm);
+        result
+            .map_err(Error::from)
+            .map(|registers| execution_data::ExecutionData {
+                result_data: ResultData::Qvm(registers),
+                duration: None,
+                timings: None,
+            })
     }
 
-    /// Compile the program and execute it on a QCS endpoint, waiting for results.
-    ///
-    /// # Arguments
-    /// 1. `quantum_processor_id`: The ID of the QPU for which to translate the program.
-    ///     This parameter affects the lifetime of the [`Executable`].
-    ///     The [`Executable`] will only live as long as the last parameter passed into this function.
-    /// 2. `translation_options`: An optional [`TranslationOptions`] that is used to configure how
-    ///    the program in translated.
-    /// 3. `execution_options`: The [`ExecutionOptions`] to use. If the connection strategy used
-    ///       is [`crate::qpu::api::ConnectionStrategy::EndpointId`] then direct access to that endpoint
-    ///       overrides the `quantum_processor_id` parameter.
-    ///
-    /// # Warning
+    /// Execute on a QVM using run-and-measure semantics: the program is simulated once, and the
+    /// resulting wavefunction is sampled once per shot. This avoids re-simulating expensive
+    /// programs for every shot, but is only correct for programs whose outcome doesn't depend on
+    /// mid-circuit measurement or classical control flow.
     ///
-    /// This function uses [`tokio::task::spawn_blocking`] internally. See the docs for that function
-    /// to avoid blocking shutdown of the runtime.
-    ///
-    /// # Returns
-    ///
-    /// An [`ExecutionResult`].
+    /// `qubits` are the qubits to measure. If empty, every qubit used by the program is measured,
+    /// in ascending order. The results are returned in the
+    /// [`RUN_AND_MEASURE_REGISTER`](qvm::RUN_AND_MEASURE_REGISTER) register, with one column per
+    /// measured qubit; the registers set with [`Executable::read_from`] are not used.
     ///
     /// # Errors
-    /// All errors are human readable by way of [`mod@thiserror`]. Some common errors are:
     ///
-    /// 1. You are not authenticated for QCS
+    /// See [`Error`].
+    pub async fn execute_and_measure_on_qvm<V: qvm::Client + ?Sized>(
+        &mut self,
+        qubits: &[u64],
+        client: &V,
+    ) -> ExecutionResult {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            num_shots = %self.shots,
+            ?qubits,
+            "running Executable on QVM with run-and-measure",
+        );
+
+        let qvm = self.take_qvm_execution()?;
+        let result = qvm
+            .run_and_measure(
+                self.shots,
+                qubits,
+                &self.params,
+                self.noise_model.as_ref(),
+                client,
+            )
+            .await;
+        self.qvm = Some(qvm);
+        result
+            .map_err(Error::from)
+            .map(|registers| execution_data::ExecutionData {
+                result_data: ResultData::Qvm(registers),
+                duration: None,
+                timings: None,
+            })
+    }
+
+    /// Simulate the program once on a QVM and return its wavefunction as
+    /// [`ResultData::Wavefunction`], which can be read with
+    /// [`ResultData::as_wavefunction`]. The number of shots and the registers set with
+    /// [`Executable::read_from`] are not used.
+    ///
+    /// # Errors
+    ///
+    /// See [`Error`].
+    pub async fn wavefunction_on_qvm<V: qvm::Client + ?Sized>(
+        &mut self,
+        client: &V,
+    ) -> ExecutionResult {
+        #[cfg(feature = "tracing")]
+        tracing::debug!("simulating Executable wavefunction on QVM");
+
+        let qvm = self.take_qvm_execution()?;
+        let result = qvm
+            .wavefunction(&self.params, self.noise_model.as_ref(), client)
+            .await;
+        self.qvm = Some(qvm);
+        result
+            .map_err(Error::from)
+            .map(|wavefunction| execution_data::ExecutionData {
+                result_data: ResultData::Wavefunction(wavefunction),
+                duration: None,
+                timings: None,
+            })
+    }
+
+    /// Simulate the program on a QVM and measure the expectation value of each of
+    /// `pauli_operators` in the state it prepares, like pyQuil's
+    /// `WavefunctionSimulator.expectation`. See [`qvm::measure_expectation`] for how operators
+    /// are written. The number of shots and the registers set with [`Executable::read_from`] are
+    /// not used.
+    ///
+    /// # Errors
+    ///
+    /// See [`Error`].
+    pub async fn expectation_on_qvm<V, S>(
+        &mut self,
+        pauli_operators: &[S],
+        client: &V,
+    ) -> Result<IndexMap<String, f64>, Error>
+    where
+        V: qvm::Client + ?Sized,
+        S: AsRef<str>,
+    {
+        #[cfg(feature = "tracing")]
+        tracing::debug!("measuring Executable expectation values on QVM");
+
+        let qvm = self.take_qvm_execution()?;
+        let result = qvm.expectation(pauli_operators, &self.params, client).await;
+        self.qvm = Some(qvm);
+        result.map_err(Error::from)
+    }
+
+    /// A [`Report`] of `results`, which were produced by this [`Executable`], including the
+    /// program, its parameters, and, after a QPU execution, the compiled program, readout mapping,
+    /// and [`StageTimings`]. Write it to an archive with [`Report::export`].
+    #[must_use]
+    pub fn report(&self, results: execution_data::ExecutionData) -> Report {
+        #[cfg_attr(not(feature = "qpu"), allow(unused_mut))]
+        let mut report = Report::new(results)
+            .with_program(match &self.source {
+                Source::Quil(quil) => quil.to_string(),
+                Source::Program(program) => program.to_quil_or_debug(),
+            })
+            .with_patch_values(&self.params);
+        #[cfg(feature = "qpu")]
+        {
+            report = report.with_timings(self.stage_timings);
+            if let Some(qpu) = &self.qpu {
+                report = report.with_native_quil(qpu.program().to_quil_or_debug());
+                if let Some(readout_map) = qpu.readout_map() {
+                    report = report.with_ro_sources(readout_map);
+                }
+            }
+        }
+        report
+    }
+
+    /// Remove and return `self.qvm` if it's set. Otherwise, parse and check the program.
+    fn take_qvm_execution(&mut self) -> Result<qvm::Execution, Error> {
+        if let Some(qvm) = self.qvm.take() {
+            if let Err(error) = self.check_parameters(qvm.program(), Service::Qvm) {
+                self.qvm = Some(qvm);
+                return Err(error);
+            }
+            return Ok(qvm);
+        }
+        let qvm = match &self.source {
+            Source::Quil(_) => qvm::Execution::new(&self.program_quil()?)?,
+            Source::Program(_) => qvm::Execution::from_program(self.program()?.into_owned()),
+        };
+        self.check_quil_t(qvm.program(), Service::Qvm)?;
+        self.check_shot_memory(qvm.program(), Service::Qvm)?;
+        self.check_readouts()?;
+        self.check_parameters(qvm.program(), Service::Qvm)?;
+        Ok(qvm)
+    }
+}
+
+#[cfg(feature = "qpu")]
+impl<'execution> Executable<'_, 'execution> {
+    /// Remove and return `self.qpu` if it's set and still valid. Otherwise, create a new one.
+    ///
+    /// Callers put the execution back in `self.qpu` when they're done with it, whether or not their
+    /// request succeeded, so that later submissions, cancellations and retrievals for the same QPU
+    /// reuse its compilation.
+    async fn qpu_for_id<S>(&mut self, id: S) -> Result<qpu::Execution<'execution>, Error>
+    where
+        S: Into<Cow<'execution, str>>,
+    {
+        self.qpu_for_id_before(id, None).await
+    }
+
+    /// As [`Executable::qpu_for_id`], failing with [`Error::DeadlineExceeded`] if compilation
+    /// doesn't finish before `deadline`. The returned execution is bound by `deadline` too.
+    async fn qpu_for_id_before<S>(
+        &mut self,
+        id: S,
+        deadline: Option<Instant>,
+    ) -> Result<qpu::Execution<'execution>, Error>
+    where
+        S: Into<Cow<'execution, str>>,
+    {
+        let id = id.into();
+        if let Some(mut qpu) = self.qpu.take() {
+            if qpu.quantum_processor_id == id.as_ref() && qpu.shots == self.shots {
+                self.stage_timings.compilation = None;
+                qpu.deadline = deadline;
+                qpu.cancellation_token = self.cancellation_token.clone();
+                return Ok(qpu);
+            }
+        }
+        self.check_qpu_program()?;
+        let started = Instant::now();
+        let compilation = within_deadline(
+            deadline,
+            Stage::Compilation,
+            qpu::Execution::new(
+                self.program_quil()?,
+                self.shots,
+                id,
+                self.qcs_client(),
+                self.compiler_backend(),
+                self.compiler_options,
+                self.max_qubit_reselections,
+                self.force_isa_refresh,
+            ),
+        );
+        let mut qpu = until_cancelled(self.cancellation_token.as_ref(), compilation)
+            .await
+            .ok_or(Error::Cancelled {
+                phase: Stage::Compilation,
+            })?
+            .map_err(|phase| Error::DeadlineExceeded { phase })?
+            .map_err(Error::from)?;
+        qpu.deadline = deadline;
+        qpu.cancellation_token = self.cancellation_token.clone();
+        self.stage_timings.compilation = Some(started.elapsed());
+        Ok(qpu)
+    }
+
+    /// The values each memory region is patched with when the program is submitted to a QPU,
+    /// typed to match the region's declaration. This is the QPU's counterpart to
+    /// [`Executable::to_quil_with_substitutions`]: rather than being written into the program,
+    /// parameters are sent alongside the translated program.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::ParameterMismatch`] if a parameter isn't declared by the program, or
+    /// doesn't fit its declaration (see [`Executable::validate_parameters`]), and
+    /// [`Error::Substitution`] if it otherwise can't be typed; see
+    /// [`type_parameters`](qpu::patch_values::type_parameters).
+    pub fn patch_values(&self) -> Result<qpu::patch_values::TypedParameters, Error> {
+        let program = self.program()?;
+        self.check_parameters(&program, Service::Qpu)?;
+        qpu::patch_values::type_parameters(&program, &self.params)
+            .map_err(|error| ExecutionError::PatchValues(error).into())
+    }
+
+    /// Look up every compilation for a QPU in `cache` before compiling, and keep the result there,
+    /// so that a program which has already been compiled for the same device with the same
+    /// [`CompilerOpts`] isn't compiled again, even by a different [`Executable`]. See
+    /// [`quilc::CompilationCache`].
+    ///
+    /// This applies to whichever compiler is set, before or after this call.
+    #[must_use]
+    pub fn with_compilation_cache(mut self, cache: Arc<quilc::CompilationCache>) -> Self {
+        self.compilation_cache = Some(cache);
+        self
+    }
+
+    /// The compiler to compile for a QPU with, looking up its compilations in the compilation
+    /// cache if there is one.
+    #[allow(trivial_casts)]
+    fn compiler_backend(&self) -> Option<Arc<dyn CompilerBackend + Send + Sync>> {
+        let compiler = self.compiler.clone()?;
+        match &self.compilation_cache {
+            Some(cache) => {
+                Some(Arc::new(quilc::CachedCompiler::from_shared(compiler, cache.clone())) as _)
+            }
+            None => Some(compiler),
+        }
+    }
+
+    /// Check the program before compiling it for a QPU.
+    fn check_qpu_program(&self) -> Result<(), Error> {
+        self.check_readouts()?;
+        let program = self.program()?;
+        if self.compiler.is_some() {
+            self.check_quil_t(&program, Service::Quilc)?;
+        }
+        self.check_shot_memory(&program, Service::Qpu)
+    }
+
+    /// Compile the program for `quantum_processor_id` as described by `isa`, rather than its
+    /// current ISA, and keep the compilation for the next submission. Its translations are made
+    /// as of `settings_epoch`, if known, without checking the QPU's settings again. A compilation
+    /// which is already kept for the same QPU, shots, and settings is reused.
+    pub(crate) fn compile_for_isa(
+        &mut self,
+        quantum_processor_id: Cow<'execution, str>,
+        isa: &InstructionSetArchitecture,
+        settings_epoch: Option<SettingsEpoch>,
+    ) -> Result<(), Error> {
+        if let Some(qpu) = &self.qpu {
+            if qpu.quantum_processor_id == quantum_processor_id
+                && qpu.shots == self.shots
+                && qpu.settings_epoch == settings_epoch
+            {
+                self.stage_timings.compilation = None;
+                return Ok(());
+            }
+        }
+        self.check_qpu_program()?;
+        let started = Instant::now();
+        let mut qpu = qpu::Execution::with_isa(
+            self.program_quil()?,
+            self.shots,
+            quantum_processor_id,
+            self.qcs_client(),
+            self.compiler_backend(),
+            self.compiler_options,
+            self.max_qubit_reselections,
+            isa,
+        )?;
+        qpu.settings_epoch = settings_epoch;
+        self.stage_timings.compilation = Some(started.elapsed());
+        self.qpu = Some(qpu);
+        Ok(())
+    }
+
+    /// Give each QPU execution at most `deadline` to finish, across all of its [`Stage`]s. If the
+    /// deadline passes, the execution fails with [`Error::DeadlineExceeded`], naming the stage it
+    /// was in, rather than waiting on whichever stage stalled.
+    ///
+    /// The deadline starts again with each call to [`Executable::execute_on_qpu`],
+    /// [`Executable::execute_on_qpu_with_endpoint`], [`Executable::submit_to_qpu`],
+    /// [`Executable::submit_to_qpu_with_endpoint`], [`Executable::retrieve_results`], and
+    /// [`Executable::execute_parameter_batch_on_qpu`], which are the methods that honor it. Each stage has whatever is left of the deadline when it starts,
+    /// on top of its own timeout, such as [`CompilerOpts::with_timeout`]. A job which was
+    /// submitted before the deadline passed keeps running on the QPU, and its results can still be
+    /// retrieved. A submission request which is already in flight when the deadline passes is
+    /// allowed to finish, so that the jobs it queued can be cancelled rather than orphaned.
+    #[must_use]
+    pub fn with_deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Abandon QPU executions as soon as `cancellation_token` is cancelled, failing with
+    /// [`Error::Cancelled`], naming the stage the execution was in. This allows a signal handler,
+    /// for instance, to stop an execution cleanly without waiting for a stalled request.
+    ///
+    /// The token is honored by the same methods as [`Executable::with_deadline`]. A job which was
+    /// submitted before the token was cancelled is cancelled too, on a best effort basis: a job
+    /// which has already started executing keeps running (see [`Executable::cancel_qpu_job`]).
+    /// A submission request which is already in flight is allowed to finish, so that the job it
+    /// queued can be cancelled rather than orphaned.
+    /// Once cancelled, a token stays cancelled, so pass a new token to run the executable again.
+    #[must_use]
+    pub fn with_cancellation_token(mut self, cancellation_token: CancellationToken) -> Self {
+        self.cancellation_token = Some(cancellation_token);
+        self
+    }
+
+    /// When an execution starting now must finish, see [`Executable::with_deadline`].
+    fn deadline_from_now(&self) -> Option<Instant> {
+        self.deadline
+            .and_then(|deadline| Instant::now().checked_add(deadline))
+    }
+
+    /// How long each stage of the most recent QPU execution took. Use this along with
+    /// [`Qcs::cache_stats`] to check whether cached compilations and translations are being
+    /// reused.
+    #[must_use]
+    pub fn stage_timings(&self) -> StageTimings {
+        self.stage_timings
+    }
+
+    /// Fail early if the client is read-only, before compiling anything for the QPU.
+    fn ensure_submissions_enabled(&mut self) -> Result<(), Error> {
+        if self.qcs_client().is_read_only() {
+            Err(Error::SubmissionsDisabled)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Fail if a single job of this executable's shots would be larger than
+    /// [`ExecutionOptions::max_shots_per_job`] allows.
+    fn check_shots_per_job(&self, execution_options: &ExecutionOptions) -> Result<(), Error> {
+        match execution_options.max_shots_per_job() {
+            Some(max_shots_per_job) if self.shots > max_shots_per_job => {
+                Err(Error::TooManyShotsPerJob {
+                    shots: self.shots,
+                    max_shots_per_job,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Record the time taken by a submission to `qpu` which took `elapsed` in total.
+    fn record_submission(&mut self, qpu: &qpu::Execution<'_>, elapsed: Duration) {
+        self.stage_timings.translation = qpu.translation_duration;
+        self.stage_timings.submission =
+            Some(elapsed.saturating_sub(qpu.translation_duration.unwrap_or_default()));
+    }
+
+    /// Keep only the readout values selected with [`Executable::read_indices`] in `data`.
+    fn select_readout_indices(
+        &self,
+        mut data: execution_data::ExecutionData,
+    ) -> execution_data::ExecutionData {
+        if let ResultData::Qpu(result_data) = &mut data.result_data {
+            for (register, indices) in &self.readout_indices {
+                result_data.select_indices(register, indices);
+            }
+        }
+        data
+    }
+
+    /// Record the time taken to retrieve `results`, which took `elapsed` in total.
+    fn record_retrieval<'a>(
+        &mut self,
+        results: impl IntoIterator<Item = &'a execution_data::ExecutionData>,
+        elapsed: Duration,
+    ) {
+        self.stage_timings.retrieval = Some(elapsed);
+        self.stage_timings.execution = results
+            .into_iter()
+            .map(|data| data.duration)
+            .sum::<Option<Duration>>();
+    }
+
+    /// Where quilc placed the program on the QPU the last time it was compiled for one, including
+    /// any qubit subset chosen because of [`Executable::with_qubit_reselection`]. `None` if the
+    /// program hasn't been compiled for a QPU, or was run without compilation.
+    #[must_use]
+    pub fn qubit_placement(&self) -> Option<&quilc::QubitPlacement> {
+        self.qpu
+            .as_ref()
+            .and_then(|execution| execution.placement.as_ref())
+    }
+
+    /// Which physical qubit each of the program's qubits was placed on the last time it was
+    /// compiled for a QPU, and how many `SWAP`s quilc inserted. `None` if the program hasn't been
+    /// compiled for a QPU, or was run without compilation.
+    #[must_use]
+    pub fn placement_report(&self) -> Option<quilc::PlacementReport> {
+        self.placement_report_for(self.qpu.as_ref()?)
+    }
+
+    fn placement_report_for(&self, qpu: &qpu::Execution<'_>) -> Option<quilc::PlacementReport> {
+        qpu.placement.as_ref()?;
+        let program = self.program().ok()?;
+        Some(quilc::PlacementReport::new(
+            &program,
+            qpu.program(),
+            qpu.native_quil_metadata.as_ref(),
+        ))
+    }
+
+    /// The metadata quilc reported the last time the program was compiled for a QPU, such as the
+    /// final rewiring of qubits and, when compiling protoquil, the estimated runtime. `None` if the
+    /// program hasn't been compiled for a QPU, was run without compilation, or the compiler didn't
+    /// report any metadata.
+    #[must_use]
+    pub fn native_quil_metadata(&self) -> Option<&quilc::NativeQuilMetadata> {
+        self.qpu
+            .as_ref()
+            .and_then(|execution| execution.native_quil_metadata.as_ref())
+    }
+
+    /// Compile the program for a QPU without translating or submitting it, so that the compiled
+    /// program can be audited before spending QPU time. The compilation is kept and reused by a
+    /// later submission to the same QPU, exactly as if the program had been submitted.
+    ///
+    /// [`CompilationArtifacts::ro_sources`] is only known after translation, so it is `None`. Use
+    /// [`Executable::compile_and_translate_only`] to get it.
+    ///
+    /// # Errors
+    ///
+    /// See [`Executable::execute_on_qpu`].
+    pub async fn compile_only<S>(
+        &mut self,
+        quantum_processor_id: S,
+    ) -> Result<CompilationArtifacts, Error>
+    where
+        S: Into<Cow<'execution, str>>,
+    {
+        let qpu = self.qpu_for_id(quantum_processor_id).await?;
+        let artifacts = CompilationArtifacts::new(
+            qpu.program(),
+            &self.params,
+            None,
+            qpu.native_quil_metadata.clone(),
+            self.placement_report_for(&qpu),
+        );
+        self.qpu = Some(qpu);
+        artifacts
+    }
+
+    /// Like [`Executable::compile_only`], but also translate the program, so that
+    /// [`CompilationArtifacts::ro_sources`] is known. The translation is kept and reused by a
+    /// later submission with the same `translation_options`, as long as the QPU hasn't been
+    /// recalibrated since.
+    ///
+    /// # Errors
+    ///
+    /// See [`Executable::execute_on_qpu`].
+    pub async fn compile_and_translate_only<S>(
+        &mut self,
+        quantum_processor_id: S,
+        translation_options: Option<TranslationOptions>,
+    ) -> Result<CompilationArtifacts, Error>
+    where
+        S: Into<Cow<'execution, str>>,
+    {
+        let mut qpu = self.qpu_for_id(quantum_processor_id).await?;
+        let translation = qpu.translate_or_reuse(translation_options).await;
+        self.stage_timings.translation = qpu.translation_duration;
+        let artifacts = translation.map_err(Error::from).and_then(|translation| {
+            CompilationArtifacts::new(
+                qpu.program(),
+                &self.params,
+                Some(translation.readout_map),
+                qpu.native_quil_metadata.clone(),
+                self.placement_report_for(&qpu),
+            )
+        });
+        self.qpu = Some(qpu);
+        artifacts
+    }
+
+    /// Describe what [`Executable::execute_on_qpu`] would do with the same arguments, stage by
+    /// stage, without compiling, translating, or submitting anything. See [`ExecutionPlan`].
+    ///
+    /// The compiler's version and the address of the QPU are looked up along the way. Failures to
+    /// look them up are described in the plan, rather than returned.
+    ///
+    /// # Warning
+    ///
+    /// This function uses [`tokio::task::spawn_blocking`] internally. See the docs for that function
+    /// to avoid blocking shutdown of the runtime.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the program can't be run on a QPU at all, for instance because it
+    /// isn't valid Quil or its parameter sweeps have different lengths.
+    pub async fn plan(
+        &mut self,
+        quantum_processor_id: &str,
+        translation_options: Option<TranslationOptions>,
+        execution_options: &ExecutionOptions,
+    ) -> Result<ExecutionPlan, Error> {
+        use crate::qpu::api::ExecutionTarget;
+        use quil_rs::instruction::Measurement;
+
+        let kept = self.qpu.as_ref().filter(|qpu| {
+            qpu.quantum_processor_id == quantum_processor_id && qpu.shots == self.shots
+        });
+        let reuses_compilation = kept.is_some();
+        let parsed;
+        let program = if let Some(qpu) = kept {
+            qpu.program()
+        } else {
+            self.check_qpu_program()?;
+            parsed = self.program()?;
+            &*parsed
+        };
+
+        let parameter_sets = self.parameter_sets()?;
+        let patch_values_per_job = parameter_sets
+            .first()
+            .map_or(0, |params| params.values().map(Vec::len).sum());
+        // The QPU reads out a value every time a `MEASURE` runs, however many indices the region
+        // being measured into has.
+        let readouts = self.get_readouts();
+        let readout_values_per_shot = program
+            .body_instructions()
+            .filter(|instruction| match instruction {
+                Instruction::Measurement(Measurement {
+                    target: Some(target),
+                    ..
+                }) => {
+                    readouts.iter().any(|name| name == &target.name)
+                        && self
+                            .readout_indices
+                            .get(&target.name)
+                            .map_or(true, |indices| {
+                                usize::try_from(target.index)
+                                    .is_ok_and(|index| indices.contains(&index))
+                            })
+                }
+                _ => false,
+            })
+            .count();
+        let sizes = EstimatedSizes::new(
+            program,
+            self.shots,
+            parameter_sets.len(),
+            patch_values_per_job,
+            readout_values_per_shot,
+        );
+
+        let compiler_version = match self.compiler.clone() {
+            Some(compiler) => Some(
+                tokio::task::spawn_blocking(move || compiler.get_version())
+                    .await
+                    .map_err(|error| format!("version lookup stopped unexpectedly: {error}"))
+                    .and_then(|version| version.map_err(|error| error.to_string())),
+            ),
+            None => None,
+        };
+        let client = self.qcs_client();
+        let address = execution_options
+            .get_qpu_grpc_address(&client, Some(quantum_processor_id))
+            .await
+            .map_err(|error| error.to_string());
+
+        Ok(ExecutionPlan {
+            quantum_processor_id: quantum_processor_id.to_string(),
+            compilation: CompilationPlan {
+                compiler_version,
+                options: self.compiler_options,
+                max_qubit_reselections: self.max_qubit_reselections,
+                reuses_compilation,
+            },
+            translation: TranslationPlan {
+                options: translation_options,
+            },
+            connection: ConnectionPlan {
+                strategy: execution_options.connection_strategy().clone(),
+                address,
+                timeout: execution_options.timeout(),
+                max_request_bytes: execution_options.max_request_bytes(),
+            },
+            sizes,
+        })
+    }
+
+    /// Sweep a memory location over several values, for use with
+    /// [`Executable::execute_parameter_batch_on_qpu`].
+    ///
+    /// Each value in `values` produces one set of parameters, which is the parameters set with
+    /// [`Executable::with_parameter`] plus `param_name[index]` set to that value. If more than one
+    /// sweep is set, they are combined element-wise, not as a product: the `i`th set of parameters
+    /// takes the `i`th value of every sweep, so every sweep must have the same number of values.
+    ///
+    /// Setting a sweep for the same memory location again replaces the previous one.
+    pub fn with_parameter_sweep<Param, Values>(
+        &mut self,
+        param_name: Param,
+        index: usize,
+        values: Values,
+    ) -> &mut Self
+    where
+        Param: Into<Box<str>>,
+        Values: IntoIterator<Item = f64>,
+    {
+        let name = param_name.into();
+        let values: Vec<f64> = values.into_iter().collect();
+
+        #[cfg(feature = "tracing")]
+        tracing::trace!(
+            "sweeping parameter {}[{}] over {} values",
+            name,
+            index,
+            values.len()
+        );
+
+        self.parameter_sweeps
+            .retain(|sweep| sweep.name != name || sweep.index != index);
+        self.parameter_sweeps.push(ParameterSweep {
+            name,
+            index,
+            values,
+        });
+        self
+    }
+
+    /// Remove every sweep set with [`Executable::with_parameter_sweep`].
+    pub fn clear_parameter_sweeps(&mut self) -> &mut Self {
+        self.parameter_sweeps.clear();
+        self
+    }
+
+    /// Build one set of parameters for each value of the parameter sweeps, or just the fixed
+    /// parameters if there are no sweeps.
+    fn parameter_sets(&self) -> Result<Vec<Parameters>, Error> {
+        let Some(first) = self.parameter_sweeps.first() else {
+            return Ok(vec![self.params.clone()]);
+        };
+        let expected = first.values.len();
+        if let Some(sweep) = self
+            .parameter_sweeps
+            .iter()
+            .find(|sweep| sweep.values.len() != expected)
+        {
+            return Err(Error::ParameterSweepLengthMismatch {
+                parameter: format!("{}[{}]", sweep.name, sweep.index),
+                expected,
+                found: sweep.values.len(),
+            });
+        }
+
+        Ok((0..expected)
+            .map(|set| {
+                let mut params = self.params.clone();
+                for sweep in &self.parameter_sweeps {
+                    let values = params.entry(sweep.name.clone()).or_default();
+                    if sweep.index >= values.len() {
+                        values.resize(sweep.index + 1, 0.0);
+                    }
+                    values[sweep.index] = sweep.values[set];
+                }
+                params
+            })
+            .collect())
+    }
+
+    /// Compile the program and execute it on a QPU, waiting for results.
+    ///
+    /// # Arguments
+    /// 1. `quantum_processor_id`: The name of the QPU to run on. This parameter affects the
+    ///     lifetime of the [`Executable`]. The [`Executable`] will only live as long as the last
+    ///     parameter passed into this function.
+    ///
+    /// # Warning
+    ///
+    /// This function uses [`tokio::task::spawn_blocking`] internally. See the docs for that function
+    /// to avoid blocking shutdown of the runtime.
+    ///
+    /// # Returns
+    ///
+    /// An [`ExecutionResult`].
+    ///
+    /// # Errors
+    /// All errors are human readable by way of [`mod@thiserror`]. Some common errors are:
+    ///
+    /// 1. You are not authenticated for QCS
+    /// 1. Your credentials don't have an active reservation for the QPU you requested
+    /// 1. [quilc] was not running.
+    /// 1. The `quil` that this [`Executable`] was constructed with was invalid.
+    /// 1. Missing parameters that should be filled with [`Executable::with_parameter`]
+    ///
+    /// [quilc]: https://github.com/quil-lang/quilc
+    pub async fn execute_on_qpu_with_endpoint<S>(
+        &mut self,
+        quantum_processor_id: S,
+        endpoint_id: S,
+        translation_options: Option<TranslationOptions>,
+    ) -> ExecutionResult
+    where
+        S: Into<Cow<'execution, str>>,
+    {
+        let deadline = self.deadline_from_now();
+        let job_handle = self
+            .submit_to_qpu_with_endpoint_before(
+                quantum_processor_id,
+                endpoint_id,
+                translation_options,
+                deadline,
+            )
+            .await?;
+        self.retrieve_results_before(job_handle, deadline).await
+    }
+
+    /// Compile the program and execute it on a QCS endpoint, waiting for results.
+    ///
+    /// # Arguments
+    /// 1. `quantum_processor_id`: The ID of the QPU for which to translate the program.
+    ///     This parameter affects the lifetime of the [`Executable`].
+    ///     The [`Executable`] will only live as long as the last parameter passed into this function.
+    /// 2. `translation_options`: An optional [`TranslationOptions`] that is used to configure how
+    ///    the program in translated.
+    /// 3. `execution_options`: The [`ExecutionOptions`] to use. If the connection strategy used
+    ///       is [`crate::qpu::api::ConnectionStrategy::EndpointId`] then direct access to that endpoint
+    ///       overrides the `quantum_processor_id` parameter.
+    ///
+    /// # Warning
+    ///
+    /// This function uses [`tokio::task::spawn_blocking`] internally. See the docs for that function
+    /// to avoid blocking shutdown of the runtime.
+    ///
+    /// # Returns
+    ///
+    /// An [`ExecutionResult`].
+    ///
+    /// # Errors
+    /// All errors are human readable by way of [`mod@thiserror`]. Some common errors are:
+    ///
+    /// 1. You are not authenticated for QCS
     /// 1. Your credentials don't have an active reservation for the QPU you requested
     /// 1. [quilc] was not running.
     /// 1. The `quil` that this [`Executable`] was constructed with was invalid.
@@ -510,10 +1858,216 @@ impl<'execution> Executable<'_, 'execution> {
             "running Executable on QPU",
         );
 
+        if let Some(max_shots) = execution_options.max_shots_per_job() {
+            if self.shots > max_shots {
+                return self
+                    .execute_split_on_qpu(
+                        max_shots,
+                        quantum_processor_id,
+                        translation_options,
+                        execution_options,
+                    )
+                    .await;
+            }
+        }
+
+        let deadline = self.deadline_from_now();
         let job_handle = self
-            .submit_to_qpu(quantum_processor_id, translation_options, execution_options)
+            .submit_to_qpu_before(
+                quantum_processor_id,
+                translation_options,
+                execution_options,
+                deadline,
+            )
             .await?;
-        self.retrieve_results(job_handle).await
+        self.retrieve_results_before(job_handle, deadline).await
+    }
+
+    /// Run the program's shots in as many jobs of at most `max_shots` shots as needed, waiting on
+    /// them concurrently and combining their results in shot order. See
+    /// [`ExecutionOptions::max_shots_per_job`].
+    ///
+    /// Every job but the last runs `max_shots` shots, so the program is translated at most twice.
+    async fn execute_split_on_qpu(
+        &mut self,
+        max_shots: NonZeroU16,
+        quantum_processor_id: Cow<'execution, str>,
+        translation_options: Option<TranslationOptions>,
+        execution_options: &ExecutionOptions,
+    ) -> ExecutionResult {
+        let total_shots = self.shots;
+        let full_jobs = usize::from(total_shots.get() / max_shots.get());
+        let remainder = NonZeroU16::new(total_shots.get() % max_shots.get());
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            num_shots = %total_shots,
+            max_shots_per_job = %max_shots,
+            num_jobs = full_jobs + usize::from(remainder.is_some()),
+            "splitting shots across jobs",
+        );
+
+        let deadline = self.deadline_from_now();
+        self.ensure_submissions_enabled()?;
+        self.check_qpu_parameters()?;
+        self.shots = max_shots;
+        let qpu = self.qpu_for_id_before(quantum_processor_id, deadline).await;
+        self.shots = total_shots;
+        let mut qpu = qpu?;
+        let mut remainder_qpu = remainder.map(|shots| qpu.with_shots(shots));
+
+        let started = Instant::now();
+        let params = vec![self.params.clone(); full_jobs];
+        let submission = async {
+            let mut job_handles = qpu
+                .submit_batch(&params, translation_options.clone(), execution_options)
+                .await?;
+            if let Some(remainder_qpu) = &mut remainder_qpu {
+                match remainder_qpu
+                    .submit(&self.params, translation_options, execution_options)
+                    .await
+                {
+                    Ok(job_handle) => job_handles.push(job_handle),
+                    Err(error) => {
+                        for job_handle in &job_handles {
+                            qpu.cancel_abandoned_job(job_handle).await;
+                        }
+                        return Err(error);
+                    }
+                }
+            }
+            Ok::<_, ExecutionError>(job_handles)
+        };
+        let submission = submission.await;
+        self.record_submission(&qpu, started.elapsed());
+        let job_handles = match submission {
+            Ok(job_handles) => job_handles,
+            Err(error) => {
+                self.qpu = Some(qpu);
+                return Err(error.into());
+            }
+        };
+
+        let started = Instant::now();
+        let retrievals = job_handles.iter().enumerate().map(|(index, job_handle)| {
+            let execution = match &remainder_qpu {
+                Some(remainder_qpu) if index == full_jobs => remainder_qpu,
+                _ => &qpu,
+            };
+            execution.retrieve_results(job_handle.clone())
+        });
+        let retrieval = within_deadline(
+            deadline,
+            Stage::Retrieval,
+            futures::future::try_join_all(retrievals),
+        );
+        let result = match until_cancelled(self.cancellation_token.as_ref(), retrieval).await {
+            Some(result) => result
+                .map_err(|phase| Error::DeadlineExceeded { phase })
+                .and_then(|result| result.map_err(Error::from))
+                .and_then(concatenate_shots)
+                .map(|data| self.select_readout_indices(data)),
+            None => {
+                for job_handle in &job_handles {
+                    qpu.cancel_abandoned_job(job_handle).await;
+                }
+                Err(Error::Cancelled {
+                    phase: Stage::Retrieval,
+                })
+            }
+        };
+        if let Ok(data) = &result {
+            self.record_retrieval([data], started.elapsed());
+        }
+        self.qpu = Some(qpu);
+        result
+    }
+
+    /// Run `total_shots` shots of the program on a QPU, split across as many jobs as needed so
+    /// that each job is expected to finish before the reservation described by `budget` ends.
+    ///
+    /// Before each job, the number of shots is sized to the time left in the reservation. If not
+    /// even one more shot would fit, execution stops with
+    /// [`ReservationExecutionStatus::ReservationExpiring`] and the results gathered so far,
+    /// rather than submitting a job that would be cut off. The per-shot estimate in `budget` is
+    /// raised if a job takes longer than expected.
+    ///
+    /// The shot count set with [`Executable::with_shots`] is replaced by the size of each job,
+    /// and is left at the size of the last job.
+    ///
+    /// # Errors
+    ///
+    /// See [`Executable::execute_on_qpu`]. An error from the first job is returned directly. If a
+    /// later job fails, the results of the earlier jobs are returned with
+    /// [`ReservationExecutionStatus::Interrupted`].
+    pub async fn execute_on_qpu_within_reservation<S>(
+        &mut self,
+        quantum_processor_id: S,
+        total_shots: u32,
+        mut budget: ShotBudget,
+        translation_options: Option<TranslationOptions>,
+        execution_options: &ExecutionOptions,
+    ) -> Result<ReservationExecution, Error>
+    where
+        S: Into<Cow<'execution, str>>,
+    {
+        let quantum_processor_id = quantum_processor_id.into();
+        let mut execution = ReservationExecution {
+            batches: Vec::new(),
+            shots_requested: total_shots,
+            shots_completed: 0,
+            status: ReservationExecutionStatus::Completed,
+        };
+
+        while execution.shots_completed < total_shots {
+            let shots_remaining = total_shots - execution.shots_completed;
+            let Some(shots) = budget.shots_for_next_job(SystemTime::now(), shots_remaining) else {
+                #[cfg(feature = "tracing")]
+                tracing::info!(
+                    shots_remaining,
+                    %quantum_processor_id,
+                    "stopping before the end of the reservation",
+                );
+                execution.status =
+                    ReservationExecutionStatus::ReservationExpiring { shots_remaining };
+                break;
+            };
+
+            #[cfg(feature = "tracing")]
+            tracing::debug!(
+                num_shots = %shots,
+                shots_remaining,
+                %quantum_processor_id,
+                "submitting a job sized to the remaining reservation time",
+            );
+
+            self.shots = shots;
+            let started = Instant::now();
+            let result = self
+                .execute_on_qpu(
+                    quantum_processor_id.clone(),
+                    translation_options.clone(),
+                    execution_options,
+                )
+                .await;
+            match result {
+                Ok(data) => {
+                    budget.observe(shots, started.elapsed());
+                    execution.batches.push(data);
+                    execution.shots_completed += u32::from(shots.get());
+                }
+                Err(error) if execution.batches.is_empty() => return Err(error),
+                Err(error) => {
+                    execution.status = ReservationExecutionStatus::Interrupted {
+                        shots_remaining,
+                        error: Box::new(error),
+                    };
+                    break;
+                }
+            }
+        }
+
+        Ok(execution)
     }
 
     /// Compile and submit the program to a QPU, but do not wait for execution to complete.
@@ -540,6 +2094,28 @@ impl<'execution> Executable<'_, 'execution> {
         translation_options: Option<TranslationOptions>,
         execution_options: &ExecutionOptions,
     ) -> Result<JobHandle<'execution>, Error>
+    where
+        S: Into<Cow<'execution, str>>,
+    {
+        let deadline = self.deadline_from_now();
+        self.submit_to_qpu_before(
+            quantum_processor_id,
+            translation_options,
+            execution_options,
+            deadline,
+        )
+        .await
+    }
+
+    /// As [`Executable::submit_to_qpu`], failing with [`Error::DeadlineExceeded`] if the job
+    /// isn't submitted before `deadline`.
+    async fn submit_to_qpu_before<S>(
+        &mut self,
+        quantum_processor_id: S,
+        translation_options: Option<TranslationOptions>,
+        execution_options: &ExecutionOptions,
+        deadline: Option<Instant>,
+    ) -> Result<JobHandle<'execution>, Error>
     where
         S: Into<Cow<'execution, str>>,
     {
@@ -552,12 +2128,263 @@ impl<'execution> Executable<'_, 'execution> {
             "submitting Executable to QPU",
         );
 
-        let job_handle = self
-            .qpu_for_id(quantum_processor_id)
-            .await?
+        self.ensure_submissions_enabled()?;
+        self.check_shots_per_job(execution_options)?;
+        self.check_qpu_parameters()?;
+        let mut qpu = self
+            .qpu_for_id_before(quantum_processor_id, deadline)
+            .await?;
+        let started = Instant::now();
+        let result = qpu
             .submit(&self.params, translation_options, execution_options)
+            .await
+            .map_err(Error::from);
+        self.record_submission(&qpu, started.elapsed());
+        self.qpu = Some(qpu);
+        result
+    }
+
+    /// Compile the program once and execute it on a QPU with every set of parameters described by
+    /// [`Executable::with_parameter_sweep`], waiting for all of the results.
+    ///
+    /// All sets of parameters are submitted together (see
+    /// [`submit_with_parameter_batch`](qpu::api::submit_with_parameter_batch)), which avoids a
+    /// round trip per set. The results are returned in the same order as the sweep values. If no
+    /// sweeps are set, the program is run once with the fixed parameters.
+    ///
+    /// # Errors
+    ///
+    /// See [`Executable::execute_on_qpu`]. Also returns
+    /// [`Error::ParameterSweepLengthMismatch`] if the sweeps don't all have the same number of
+    /// values. If the deadline passes or the [`CancellationToken`] is cancelled while waiting for
+    /// results, the jobs still outstanding are cancelled, since their handles aren't returned.
+    pub async fn execute_parameter_batch_on_qpu<S>(
+        &mut self,
+        quantum_processor_id: S,
+        translation_options: Option<TranslationOptions>,
+        execution_options: &ExecutionOptions,
+    ) -> Result<Vec<execution_data::ExecutionData>, Error>
+    where
+        S: Into<Cow<'execution, str>>,
+    {
+        let quantum_processor_id = quantum_processor_id.into();
+        if let Some(max_shots) = execution_options.max_shots_per_job() {
+            if self.shots > max_shots {
+                return self
+                    .execute_split_parameter_batch_on_qpu(
+                        max_shots,
+                        quantum_processor_id,
+                        translation_options,
+                        execution_options,
+                    )
+                    .await;
+            }
+        }
+
+        let deadline = self.deadline_from_now();
+        let job_handles = self
+            .submit_parameter_batch_to_qpu(
+                quantum_processor_id.clone(),
+                translation_options,
+                execution_options,
+            )
             .await?;
-        Ok(job_handle)
+
+        let qpu = self.qpu_for_id(quantum_processor_id).await?;
+        let started = Instant::now();
+        let jobs = job_handles
+            .into_iter()
+            .map(|job_handle| (&qpu, job_handle))
+            .collect();
+        let results = self
+            .retrieve_batch_before(jobs, deadline)
+            .await
+            .map(|results| {
+                results
+                    .into_iter()
+                    .map(|data| self.select_readout_indices(data))
+                    .collect::<Vec<_>>()
+            });
+        if let Ok(results) = &results {
+            self.record_retrieval(results, started.elapsed());
+        }
+        self.qpu = Some(qpu);
+        results
+    }
+
+    /// As [`Executable::execute_split_on_qpu`], for every set of parameters described by
+    /// [`Executable::with_parameter_sweep`]. The jobs of every set are submitted together, and
+    /// the results of each set are combined in shot order.
+    async fn execute_split_parameter_batch_on_qpu(
+        &mut self,
+        max_shots: NonZeroU16,
+        quantum_processor_id: Cow<'execution, str>,
+        translation_options: Option<TranslationOptions>,
+        execution_options: &ExecutionOptions,
+    ) -> Result<Vec<execution_data::ExecutionData>, Error> {
+        let total_shots = self.shots;
+        let full_jobs = usize::from(total_shots.get() / max_shots.get());
+        let remainder = NonZeroU16::new(total_shots.get() % max_shots.get());
+        let parameter_sets = self.parameter_sets()?;
+
+        let deadline = self.deadline_from_now();
+        self.ensure_submissions_enabled()?;
+        let program = self.program()?;
+        for params in &parameter_sets {
+            parameters::check(&program, params).map_err(parameter_mismatch)?;
+        }
+        drop(program);
+        self.shots = max_shots;
+        let qpu = self.qpu_for_id(quantum_processor_id).await;
+        self.shots = total_shots;
+        let mut qpu = qpu?;
+        let mut remainder_qpu = remainder.map(|shots| qpu.with_shots(shots));
+
+        // The full jobs of every set come first, followed by the remainder job of every set.
+        let full_params: Vec<Parameters> = parameter_sets
+            .iter()
+            .flat_map(|params| std::iter::repeat(params).take(full_jobs).cloned())
+            .collect();
+        let started = Instant::now();
+        let submission = async {
+            let mut job_handles = qpu
+                .submit_batch(&full_params, translation_options.clone(), execution_options)
+                .await?;
+            if let Some(remainder_qpu) = &mut remainder_qpu {
+                match remainder_qpu
+                    .submit_batch(&parameter_sets, translation_options, execution_options)
+                    .await
+                {
+                    Ok(remainder_handles) => job_handles.extend(remainder_handles),
+                    Err(error) => {
+                        for job_handle in &job_handles {
+                            qpu.cancel_abandoned_job(job_handle).await;
+                        }
+                        return Err(error);
+                    }
+                }
+            }
+            Ok::<_, ExecutionError>(job_handles)
+        }
+        .await;
+        self.record_submission(&qpu, started.elapsed());
+        let job_handles = match submission {
+            Ok(job_handles) => job_handles,
+            Err(error) => {
+                self.qpu = Some(qpu);
+                return Err(error.into());
+            }
+        };
+
+        let started = Instant::now();
+        let jobs = job_handles
+            .into_iter()
+            .enumerate()
+            .map(|(index, job_handle)| {
+                let execution = match &remainder_qpu {
+                    Some(remainder_qpu) if index >= full_params.len() => remainder_qpu,
+                    _ => &qpu,
+                };
+                (execution, job_handle)
+            })
+            .collect();
+        let results = self
+            .retrieve_batch_before(jobs, deadline)
+            .await
+            .and_then(|mut jobs| {
+                let mut remainders = jobs.split_off(full_params.len()).into_iter();
+                let mut jobs = jobs.into_iter();
+                let mut results = Vec::with_capacity(parameter_sets.len());
+                for _ in &parameter_sets {
+                    let mut set_jobs: Vec<_> = jobs.by_ref().take(full_jobs).collect();
+                    set_jobs.extend(remainders.next());
+                    results.push(self.select_readout_indices(concatenate_shots(set_jobs)?));
+                }
+                Ok(results)
+            });
+        if let Ok(results) = &results {
+            self.record_retrieval(results, started.elapsed());
+        }
+        self.qpu = Some(qpu);
+        results
+    }
+
+    /// Wait for the results of each of `jobs` in turn, failing with [`Error::DeadlineExceeded`]
+    /// if they aren't all retrieved before `deadline`, or with [`Error::Cancelled`] if the
+    /// [`CancellationToken`] is cancelled first. The jobs whose results weren't retrieved are then
+    /// cancelled, since no handle to them is returned.
+    async fn retrieve_batch_before(
+        &self,
+        jobs: Vec<(&qpu::Execution<'execution>, JobHandle<'execution>)>,
+        deadline: Option<Instant>,
+    ) -> Result<Vec<execution_data::ExecutionData>, Error> {
+        let retrieved = AtomicUsize::new(0);
+        let retrieval = within_deadline(deadline, Stage::Retrieval, async {
+            let mut results = Vec::with_capacity(jobs.len());
+            for (execution, job_handle) in &jobs {
+                results.push(execution.retrieve_results(job_handle.clone()).await?);
+                retrieved.fetch_add(1, Ordering::Relaxed);
+            }
+            Ok::<_, ExecutionError>(results)
+        });
+        let error = match until_cancelled(self.cancellation_token.as_ref(), retrieval).await {
+            Some(Ok(results)) => return results.map_err(Error::from),
+            Some(Err(phase)) => Error::DeadlineExceeded { phase },
+            None => Error::Cancelled {
+                phase: Stage::Retrieval,
+            },
+        };
+        for (execution, job_handle) in jobs.iter().skip(retrieved.load(Ordering::Relaxed)) {
+            execution.cancel_abandoned_job(job_handle).await;
+        }
+        Err(error)
+    }
+
+    /// Compile the program once and submit it to a QPU with every set of parameters described by
+    /// [`Executable::with_parameter_sweep`], but do not wait for execution to complete.
+    ///
+    /// One [`JobHandle`] is returned for each set of parameters, in the same order as the sweep
+    /// values. Call [`Executable::retrieve_results`] with each of them to retrieve the results.
+    ///
+    /// # Errors
+    ///
+    /// See [`Executable::execute_parameter_batch_on_qpu`].
+    pub async fn submit_parameter_batch_to_qpu<S>(
+        &mut self,
+        quantum_processor_id: S,
+        translation_options: Option<TranslationOptions>,
+        execution_options: &ExecutionOptions,
+    ) -> Result<Vec<JobHandle<'execution>>, Error>
+    where
+        S: Into<Cow<'execution, str>>,
+    {
+        let quantum_processor_id = quantum_processor_id.into();
+        let parameter_sets = self.parameter_sets()?;
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            num_shots = %self.shots,
+            num_parameter_sets = parameter_sets.len(),
+            %quantum_processor_id,
+            "submitting Executable parameter batch to QPU",
+        );
+
+        self.ensure_submissions_enabled()?;
+        self.check_shots_per_job(execution_options)?;
+        let program = self.program()?;
+        for params in &parameter_sets {
+            parameters::check(&program, params).map_err(parameter_mismatch)?;
+        }
+        drop(program);
+        // Keep the execution so that retrieving each job's results doesn't have to recreate it.
+        let mut qpu = self.qpu_for_id(quantum_processor_id).await?;
+        let started = Instant::now();
+        let job_handles = qpu
+            .submit_batch(&parameter_sets, translation_options, execution_options)
+            .await;
+        self.record_submission(&qpu, started.elapsed());
+        self.qpu = Some(qpu);
+        Ok(job_handles?)
     }
 
     /// Compile and submit the program to a QCS endpoint, but do not wait for execution to complete.
@@ -577,12 +2404,41 @@ impl<'execution> Executable<'_, 'execution> {
     where
         S: Into<Cow<'execution, str>>,
     {
-        let job_handle = self
-            .qpu_for_id(quantum_processor_id)
-            .await?
-            .submit_to_endpoint_id(&self.params, endpoint_id.into(), translation_options)
+        let deadline = self.deadline_from_now();
+        self.submit_to_qpu_with_endpoint_before(
+            quantum_processor_id,
+            endpoint_id,
+            translation_options,
+            deadline,
+        )
+        .await
+    }
+
+    /// As [`Executable::submit_to_qpu_with_endpoint`], failing with [`Error::DeadlineExceeded`]
+    /// if the job isn't submitted before `deadline`.
+    async fn submit_to_qpu_with_endpoint_before<S>(
+        &mut self,
+        quantum_processor_id: S,
+        endpoint_id: S,
+        translation_options: Option<TranslationOptions>,
+        deadline: Option<Instant>,
+    ) -> Result<JobHandle<'execution>, Error>
+    where
+        S: Into<Cow<'execution, str>>,
+    {
+        self.ensure_submissions_enabled()?;
+        self.check_qpu_parameters()?;
+        let mut qpu = self
+            .qpu_for_id_before(quantum_processor_id, deadline)
             .await?;
-        Ok(job_handle)
+        let started = Instant::now();
+        let result = qpu
+            .submit_to_endpoint_id(&self.params, endpoint_id.into(), translation_options)
+            .await
+            .map_err(Error::from);
+        self.record_submission(&qpu, started.elapsed());
+        self.qpu = Some(qpu);
+        result
     }
 
     /// Cancel a job that has yet to begin executing.
@@ -596,7 +2452,9 @@ impl<'execution> Executable<'_, 'execution> {
     pub async fn cancel_qpu_job(&mut self, job_handle: JobHandle<'execution>) -> Result<(), Error> {
         let quantum_processor_id = job_handle.quantum_processor_id.to_string();
         let qpu = self.qpu_for_id(quantum_processor_id).await?;
-        Ok(qpu.cancel_job(job_handle).await?)
+        let result = qpu.cancel_job(job_handle).await;
+        self.qpu = Some(qpu);
+        Ok(result?)
     }
 
     /// Wait for the results of a job submitted via [`Executable::submit_to_qpu`] to complete.
@@ -605,15 +2463,148 @@ impl<'execution> Executable<'_, 'execution> {
     ///
     /// See [`Executable::execute_on_qpu`].
     pub async fn retrieve_results(&mut self, job_handle: JobHandle<'execution>) -> ExecutionResult {
+        let deadline = self.deadline_from_now();
+        self.retrieve_results_before(job_handle, deadline).await
+    }
+
+    /// Wait for the results of a job submitted via [`Executable::submit_to_qpu`], as with
+    /// [`Executable::retrieve_results`], and take each readout node's values out in chunks of
+    /// `values_per_chunk` values. See
+    /// [`QpuResultData::into_readout_chunks`](crate::qpu::QpuResultData::into_readout_chunks).
+    ///
+    /// The controller returns all of a job's values in a single response, so they are held as
+    /// received, but no [`RegisterMap`](crate::RegisterMap) is built from them and each readout
+    /// node's values are freed once its last chunk has been taken. To hold fewer values at once,
+    /// run the shots as several smaller jobs with [`Executable::with_shots`] and retrieve them one
+    /// at a time.
+    ///
+    /// # Errors
+    ///
+    /// See [`Executable::execute_on_qpu`].
+    pub async fn retrieve_results_in_chunks(
+        &mut self,
+        job_handle: JobHandle<'execution>,
+        values_per_chunk: NonZeroUsize,
+    ) -> Result<ReadoutChunks, Error> {
+        self.retrieve_results(job_handle)
+            .await?
+            .result_data
+            .into_qpu()
+            .map(|result_data| result_data.into_readout_chunks(values_per_chunk))
+            .map_err(|_| {
+                Error::Unexpected("the QPU returned results which aren't readout data".into())
+            })
+    }
+
+    /// As [`Executable::retrieve_results`], failing with [`Error::DeadlineExceeded`] if the
+    /// results aren't retrieved before `deadline`.
+    async fn retrieve_results_before(
+        &mut self,
+        job_handle: JobHandle<'execution>,
+        deadline: Option<Instant>,
+    ) -> ExecutionResult {
         let quantum_processor_id = job_handle.quantum_processor_id.to_string();
-        let qpu = self.qpu_for_id(quantum_processor_id).await?;
-        qpu.retrieve_results(job_handle).await.map_err(Error::from)
+        let qpu = self
+            .qpu_for_id_before(quantum_processor_id, deadline)
+            .await?;
+        let started = Instant::now();
+        let retrieval = within_deadline(
+            deadline,
+            Stage::Retrieval,
+            qpu.retrieve_results(job_handle.clone()),
+        );
+        let result = match until_cancelled(self.cancellation_token.as_ref(), retrieval).await {
+            Some(result) => result
+                .map_err(|phase| Error::DeadlineExceeded { phase })
+                .and_then(|result| result.map_err(Error::from))
+                .map(|data| self.select_readout_indices(data)),
+            None => {
+                qpu.cancel_abandoned_job(&job_handle).await;
+                Err(Error::Cancelled {
+                    phase: Stage::Retrieval,
+                })
+            }
+        };
+        if let Ok(data) = &result {
+            self.record_retrieval([data], started.elapsed());
+        }
+        self.qpu = Some(qpu);
+        result
+    }
+
+    /// Wait for the results of a job submitted via [`Executable::submit_to_qpu`], as with
+    /// [`Executable::retrieve_results`], but check on the job while waiting.
+    ///
+    /// The job's [`JobStatus`] is polled every `poll_interval` until it has finished, and
+    /// `on_status` is called with the first status reported and then each time it changes, so
+    /// that progress can be shown while the job is queued. Every poll is made on the same
+    /// connection to the controller.
+    ///
+    /// # Errors
+    ///
+    /// See [`Executable::execute_on_qpu`]. A job which fails or is cancelled is reported once
+    /// `on_status` has been called with its final status. Polling counts towards the deadline set
+    /// with [`Executable::with_deadline`], and fails with [`Error::JobStatusUnknown`] if the
+    /// controller reports the status as [`JobStatus::Unknown`] ten times in a row.
+    pub async fn wait_for_results_with<F>(
+        &mut self,
+        job_handle: JobHandle<'execution>,
+        poll_interval: Duration,
+        mut on_status: F,
+    ) -> ExecutionResult
+    where
+        F: FnMut(JobStatus),
+    {
+        let client = self.qcs_client();
+        let deadline = self.deadline_from_now();
+        let quantum_processor_id = Some(job_handle.quantum_processor_id());
+        let options = job_handle.execution_options();
+        let polling = async {
+            let connection = options
+                .get_controller_client(&client, quantum_processor_id)
+                .await?;
+            let mut last_status = None;
+            let mut unknown_polls = 0;
+            loop {
+                let status = qpu::api::get_job_status_on_connection(
+                    &connection,
+                    job_handle.job_id(),
+                    quantum_processor_id,
+                    options,
+                )
+                .await?;
+                if last_status != Some(status) {
+                    on_status(status);
+                    last_status = Some(status);
+                }
+                if status.is_finished() {
+                    return Ok::<_, Error>(());
+                }
+                if status == JobStatus::Unknown {
+                    unknown_polls += 1;
+                    if unknown_polls >= MAX_UNKNOWN_STATUS_POLLS {
+                        return Err(Error::JobStatusUnknown {
+                            job_id: job_handle.job_id().to_string(),
+                            polls: unknown_polls,
+                        });
+                    }
+                } else {
+                    unknown_polls = 0;
+                }
+                tokio::time::sleep(poll_interval).await;
+            }
+        };
+        within_deadline(deadline, Stage::Retrieval, polling)
+            .await
+            .map_err(|phase| Error::DeadlineExceeded { phase })??;
+        self.retrieve_results_before(job_handle, deadline).await
     }
 }
 
 /// The possible errors which can be returned by [`Executable::execute_on_qpu`] and
 /// [`Executable::execute_on_qvm`]..
 #[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
 pub enum Error {
     /// Communicating with QCS requires appropriate settings and secrets files. By default, these
     /// should be `$HOME/.qcs/settings.toml` and `$HOME/.qcs/secrets.toml`, though those files can
@@ -627,9 +2618,13 @@ pub enum Error {
     /// This error occurs when the SDK was unable to authenticate a request to QCS. This could mean
     /// that your credentials are invalid or expired, or that you do not have access to the requested
     /// QPU.
+    ///
+    /// Expired access tokens are refreshed automatically, so this is only returned once refreshing
+    /// has failed too.
     #[error("Could not authenticate a request to QCS for the requested QPU.")]
     Authentication,
     /// An API error occurred while connecting to the QPU.
+    #[cfg(feature = "qpu")]
     #[error("An API error occurred while connecting to the QPU: {0}")]
     QpuApiError(#[from] qpu::api::QpuApiError),
     /// This happens when the QPU is down for maintenance and not accepting new jobs. If you receive
@@ -656,9 +2651,31 @@ pub enum Error {
     /// There was a problem when translating the Quil program.
     #[error("There was a problem translating the Quil program: {0}")]
     Translation(String),
-    /// There was a problem when substituting parameters in the Quil program.
+    /// There was a problem when substituting parameters in the Quil program. Parameters which
+    /// are found not to fit the program before it is run are reported as
+    /// [`Error::ParameterMismatch`] instead.
     #[error("There was a problem substituting parameters in the Quil program: {0}")]
     Substitution(String),
+    /// The parameters set with [`Executable::with_parameter`] don't fit the memory regions the
+    /// program declares: a region isn't declared, is given the wrong number of values, or can't
+    /// hold one of them.
+    #[error("The parameters don't fit the Quil program: {message}")]
+    ParameterMismatch {
+        /// What doesn't fit, including the region's declaration and the offending index.
+        message: String,
+        /// The name of the memory region the parameter is set in.
+        region: String,
+        /// The offending index into the region, or `None` if the region isn't declared.
+        index: Option<usize>,
+        /// The type the region is declared with, or `None` if it isn't declared.
+        declared_type: Option<ScalarType>,
+        /// The length the region is declared with, or `None` if it isn't declared.
+        declared_length: Option<u64>,
+        /// The parameters as they were to be applied, if requested with
+        /// [`Executable::with_substitutions_in_errors`]. See
+        /// [`Executable::to_quil_with_substitutions`].
+        substitutions: Option<String>,
+    },
     /// The Quil program is missing readout sources.
     #[error("The Quil program is missing readout sources")]
     MissingRoSources,
@@ -675,129 +2692,1032 @@ pub enum Error {
     /// Occurs when failing to construct a [`Qcs`] client.
     #[error("The QCS client configuration failed to load")]
     QcsConfigLoadFailure(#[from] LoadError),
+    /// The sweeps set with [`Executable::with_parameter_sweep`] have different numbers of values.
+    #[error("Every parameter sweep must have the same number of values, but {parameter} has {found} instead of {expected}")]
+    ParameterSweepLengthMismatch {
+        /// The memory location whose sweep has a different number of values.
+        parameter: String,
+        /// The number of values in the first sweep.
+        expected: usize,
+        /// The number of values in this sweep.
+        found: usize,
+    },
+    /// The program contains a Quil-T (pulse-level) instruction, which the target service does not
+    /// support. This check can be disabled with [`Executable::skip_quil_t_check`].
+    #[error("{target:?} does not support Quil-T, but the program contains: {instruction}")]
+    QuilTNotSupportedByTarget {
+        /// The first Quil-T instruction found in the program.
+        instruction: String,
+        /// The service the program was about to be sent to.
+        target: Service,
+    },
+    /// The [`Qcs`] client is in read-only mode, so programs can't be submitted to a QPU. Results of
+    /// jobs which have already been submitted can still be retrieved. See
+    /// [`Qcs::with_read_only`].
+    #[error("Submissions are disabled because the QCS client is in read-only mode")]
+    SubmissionsDisabled,
+    /// The program's classical memory was requested to be treated between shots, with
+    /// [`Executable::with_shot_memory`], in a way the target service doesn't support. See
+    /// [`Service::shot_memory`].
+    #[error("{target:?} doesn't support {requested:?} shot memory")]
+    ShotMemoryNotSupported {
+        /// The service the program was about to be sent to.
+        target: Service,
+        /// What was requested to happen to memory between shots.
+        requested: ShotMemory,
+    },
+    /// The settings of the QPU changed after an [`Experiment`](crate::Experiment) took its
+    /// snapshot of the QPU's ISA, so its programs would no longer all run against the same device
+    /// state.
+    #[error("The settings of {quantum_processor_id} changed after the experiment's snapshot")]
+    SnapshotOutdated {
+        /// The QPU whose settings changed.
+        quantum_processor_id: String,
+    },
+    /// The deadline set with [`Executable::with_deadline`] passed before the execution finished.
+    #[error("The deadline was exceeded during {phase}")]
+    DeadlineExceeded {
+        /// The stage the execution was in when the deadline passed.
+        phase: Stage,
+    },
+    /// The token set with [`Executable::with_cancellation_token`] was cancelled before the
+    /// execution finished.
+    #[error("The execution was cancelled during {phase}")]
+    Cancelled {
+        /// The stage the execution was in when it was cancelled.
+        phase: Stage,
+    },
+    /// The results of the jobs which an execution was split across, as configured with
+    /// [`ExecutionOptions::max_shots_per_job`](crate::qpu::api::ExecutionOptions::max_shots_per_job),
+    /// couldn't be combined.
+    #[error("The results of the jobs the shots were split across couldn't be combined: {0}")]
+    ShotSplitting(String),
+    /// A job was submitted with more shots than
+    /// [`ExecutionOptions::max_shots_per_job`](crate::qpu::api::ExecutionOptions::max_shots_per_job)
+    /// allows. A [`JobHandle`] describes a single job, so only the methods which wait for the
+    /// results, such as [`Executable::execute_on_qpu`], can split shots across several jobs.
+    #[error("{shots} shots can't be run in a single job of at most {max_shots_per_job} shots")]
+    TooManyShotsPerJob {
+        /// The number of shots the job was submitted with.
+        shots: NonZeroU16,
+        /// The most shots allowed in a single job.
+        max_shots_per_job: NonZeroU16,
+    },
+    /// The controller kept reporting the status of a job as [`JobStatus::Unknown`] while
+    /// [`Executable::wait_for_results_with`] waited for it to finish.
+    #[error("The status of job {job_id} was still unknown after {polls} checks")]
+    JobStatusUnknown {
+        /// The job being waited for.
+        job_id: String,
+        /// How many times in a row the status was unknown.
+        polls: u32,
+    },
+    /// A memory reference such as `ro[3]` was given to [`Executable::read_from`] after its whole
+    /// memory region was, which would read only that index of the region instead. Use
+    /// [`Executable::read_indices`] to read some indices of a region.
+    #[error("{reference} would narrow a memory region which is already read whole")]
+    ReadoutNarrowed {
+        /// The indexed memory reference.
+        reference: String,
+    },
+    /// Results were too large to decode into memory, as configured with
+    /// [`ExecutionOptions::spill_threshold_bytes`](crate::qpu::api::ExecutionOptions::spill_threshold_bytes),
+    /// but couldn't be written to temporary files.
+    #[error("There was a problem writing results to temporary files: {0}")]
+    Spill(String),
+}
+
+impl Error {
+    /// Classify the failure if this is an [`Error::Compilation`], so that callers can react to
+    /// specific problems, e.g. by retrying on a different set of qubits.
+    #[cfg(feature = "qcs-api")]
+    #[must_use]
+    pub fn compilation_error_kind(&self) -> Option<quilc::QuilcErrorKind> {
+        match self {
+            Self::Compilation(message) => Some(quilc::QuilcErrorKind::from_message(message)),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the first Quil-T (pulse-level) instruction in the program, if any, including those in
+/// calibration, frame, and waveform definitions.
+fn find_quil_t_instruction(program: &Program) -> Option<Instruction> {
+    program.to_instructions().into_iter().find(|instruction| {
+        matches!(
+            instruction,
+            Instruction::CalibrationDefinition(_)
+                | Instruction::Capture(_)
+                | Instruction::Delay(_)
+                | Instruction::Fence(_)
+                | Instruction::FrameDefinition(_)
+                | Instruction::MeasureCalibrationDefinition(_)
+                | Instruction::Pulse(_)
+                | Instruction::RawCapture(_)
+                | Instruction::SetFrequency(_)
+                | Instruction::SetPhase(_)
+                | Instruction::SetScale(_)
+                | Instruction::ShiftFrequency(_)
+                | Instruction::ShiftPhase(_)
+                | Instruction::SwapPhases(_)
+                | Instruction::WaveformDefinition(_)
+        )
+    })
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+/// The external services that this SDK may connect to. Used to differentiate between networking
+/// issues in [`Error::Connection`].
+pub enum Service {
+    /// The open source [`quilc`](https://github.com/quil-lang/quilc) compiler.
+    ///
+    /// This compiler must be running before calling [`Executable::execute_on_qpu`] unless the
+    /// [`Executable::compile_with_quilc`] option is set to `false`. By default, it's assumed that
+    /// this is running on `tcp://localhost:5555`, but this can be overridden via
+    /// `[profiles.<profile_name>.applications.pyquil.quilc_url]` in your `.qcs/settings.toml` file.
+    Quilc,
+    /// The open source [`qvm`](https://github.com/quil-lang/qvm) simulator.
+    ///
+    /// This simulator must be running before calling [`Executable::execute_on_qvm`]. By default,
+    /// it's assumed that this is running on `http://localhost:5000`, but this can be overridden via
+    /// `[profiles.<profile_name>.applications.pyquil.qvm_url]` in your `.qcs/settings.toml` file.
+    Qvm,
+    /// The connection to [`QCS`](https://docs.rigetti.com/qcs/), the API for authentication,
+    /// QPU lookup, and translation.
+    ///
+    /// You should be able to reach this service as long as you have a connection to the internet.
+    Qcs,
+    /// The connection to the QPU itself. You can only connect to the QPU from an authorized network
+    /// (like QCS JupyterLab).
+    Qpu,
+}
+
+impl Service {
+    /// What the service does with a program's classical memory between shots, or `None` if it
+    /// doesn't run programs. See [`Executable::with_shot_memory`].
+    ///
+    /// - [`Service::Qvm`] resets it: each shot runs on freshly allocated memory, into which the
+    ///   program's parameters are loaded.
+    /// - [`Service::Qpu`] resets it: the controller loads the job's patch values, and zeroes the
+    ///   rest of memory, before every shot.
+    #[must_use]
+    pub fn shot_memory(self) -> Option<ShotMemory> {
+        match self {
+            Self::Qvm | Self::Qpu => Some(ShotMemory::Reset),
+            Self::Quilc | Self::Qcs => None,
+        }
+    }
+}
+
+/// What happens to a program's classical memory between shots, see
+/// [`Executable::with_shot_memory`] and [`Service::shot_memory`].
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub enum ShotMemory {
+    /// Every shot starts from the same memory, zeroed apart from the program's parameters.
+    #[default]
+    Reset,
+    /// Every shot starts from the memory the previous shot left, so that, for example, a counter
+    /// incremented once per shot counts the shots.
+    Persist,
+}
+
+#[cfg(feature = "qpu")]
+impl From<ExecutionError> for Error {
+    fn from(err: ExecutionError) -> Self {
+        if is_unauthenticated(&err) {
+            return Self::Authentication;
+        }
+        match err {
+            ExecutionError::Unexpected(inner) => Self::Unexpected(format!("{inner:?}")),
+            #[cfg(feature = "quilc-rpcq")]
+            ExecutionError::Quilc { .. } => Self::Connection(Service::Quilc),
+            ExecutionError::QcsClient(v) => Self::Unexpected(format!("{v:?}")),
+            ExecutionError::Translation(v) => Self::Translation(v.to_string()),
+            ExecutionError::Isa(v) => Self::Unexpected(format!("{v:?}")),
+            ExecutionError::ReadoutParse(v) => Self::Unexpected(format!("{v:?}")),
+            ExecutionError::Quil(e) => Self::Quil(e),
+            ExecutionError::ToQuil(e) => Self::ToQuil(e),
+            ExecutionError::Compilation { details } => Self::Compilation(details),
+            #[cfg(feature = "quilc-rpcq")]
+            ExecutionError::RpcqClient(e) => Self::Unexpected(format!("{e:?}")),
+            ExecutionError::QpuApi(qpu::api::QpuApiError::SubmissionsDisabled) => {
+                Self::SubmissionsDisabled
+            }
+            ExecutionError::QpuApi(e) => Self::QpuApiError(e),
+            ExecutionError::PatchValues(e) => Self::Substitution(e.to_string()),
+            ExecutionError::Spill(e) => Self::Spill(e.to_string()),
+            ExecutionError::DeadlineExceeded(phase) => Self::DeadlineExceeded { phase },
+            ExecutionError::Cancelled(phase) => Self::Cancelled { phase },
+        }
+    }
+}
+
+/// Whether `err` is QCS rejecting a request as unauthenticated, even after refreshing the access
+/// token.
+#[cfg(feature = "qpu")]
+fn is_unauthenticated(err: &ExecutionError) -> bool {
+    match err {
+        ExecutionError::QcsClient(error) => error.is_unauthenticated(),
+        ExecutionError::Translation(error) => error.is_unauthenticated(),
+        ExecutionError::Isa(error) => error.is_unauthenticated(),
+        ExecutionError::QpuApi(error) => error.is_unauthenticated(),
+        _ => false,
+    }
+}
+
+impl From<qvm::Error> for Error {
+    fn from(err: qvm::Error) -> Self {
+        match err {
+            #[cfg(feature = "qvm-http")]
+            qvm::Error::QvmCommunication { .. } | qvm::Error::Client { .. } => {
+                Self::Connection(Service::Qvm)
+            }
+            qvm::Error::ToQuil(q) => Self::ToQuil(q),
+            qvm::Error::RegionSizeMismatch { .. } | qvm::Error::RegionNotFound { .. } => {
+                Self::Substitution(err.to_string())
+            }
+            qvm::Error::Parsing(_)
+            | qvm::Error::ShotsMustBePositive
+            | qvm::Error::RegisterTypeMismatch { .. }
+            | qvm::Error::InvalidPauliOperator { .. }
+            | qvm::Error::Qvm { .. } => Self::Compilation(format!("{err}")),
+            qvm::Error::InvalidWavefunction { .. } => Self::Unexpected(format!("{err}")),
+        }
+    }
+}
+
+/// The compiled program and its metadata, as returned by [`Executable::compile_only`] and
+/// [`Executable::compile_and_translate_only`].
+#[cfg(feature = "qpu")]
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct CompilationArtifacts {
+    /// The program as compiled by quilc, or as given if it wasn't compiled.
+    pub native_quil: String,
+    /// The values each memory region will be patched with when the program is submitted, typed
+    /// to match the region's declaration. See [`Executable::with_parameter`].
+    pub patch_table: qpu::patch_values::TypedParameters,
+    /// A mapping of the names of the values read out by the translated program back to the
+    /// memory references they are stored in, or `None` if the program wasn't translated. See
+    /// [`JobHandle::readout_map`].
+    pub ro_sources: Option<HashMap<String, String>>,
+    /// The memory regions declared by the compiled program, by name.
+    pub memory_descriptors: HashMap<String, qpu::translation::MemoryDescriptor>,
+    /// The metadata reported by the compiler, such as the final rewiring and estimated runtime,
+    /// or `None` if the program wasn't compiled or the compiler didn't report any.
+    pub native_quil_metadata: Option<quilc::NativeQuilMetadata>,
+    /// Where quilc placed the program's qubits, or `None` if the program wasn't compiled. See
+    /// [`Executable::placement_report`].
+    pub placement_report: Option<quilc::PlacementReport>,
+}
+
+#[cfg(feature = "qpu")]
+impl CompilationArtifacts {
+    fn new(
+        program: &Program,
+        params: &Parameters,
+        ro_sources: Option<HashMap<String, String>>,
+        native_quil_metadata: Option<quilc::NativeQuilMetadata>,
+        placement_report: Option<quilc::PlacementReport>,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            native_quil: program.to_quil()?,
+            patch_table: qpu::patch_values::type_parameters(program, params)
+                .map_err(|error| Error::Substitution(error.to_string()))?,
+            ro_sources,
+            memory_descriptors: qpu::translation::memory_descriptors(program),
+            native_quil_metadata,
+            placement_report,
+        })
+    }
+}
+
+/// The [`Error::ParameterMismatch`] for `mismatch`.
+fn parameter_mismatch(mismatch: parameters::Mismatch) -> Error {
+    let parameters::Mismatch {
+        message,
+        region,
+        index,
+        declared_type,
+        declared_length,
+    } = mismatch;
+    Error::ParameterMismatch {
+        message,
+        region,
+        index,
+        declared_type,
+        declared_length,
+        substitutions: None,
+    }
+}
+
+/// The result of calling [`Executable::submit_to_qpu`]. Represents a quantum program running on
+/// a QPU. Can be passed to [`Executable::retrieve_results`] to retrieve the results of the job.
+///
+/// A [`JobHandle`] can be serialized, so that a job can be submitted by one process and its
+/// results retrieved by another with [`JobHandle::retrieve_results`]. Pass the whole handle
+/// rather than just its [`JobId`]: the QPU doesn't record which memory references a job's
+/// readout values belong in, so results can only be arranged into registers with the
+/// [`JobHandle::readout_map`] saved when the program was translated.
+///
+/// ```no_run
+/// # async fn example(job_handle: qcs::JobHandle<'_>) -> Result<(), Box<dyn std::error::Error>> {
+/// use qcs::{client::Qcs, JobHandle};
+///
+/// std::fs::write("job.json", serde_json::to_string(&job_handle)?)?;
+///
+/// // Later, possibly in another process:
+/// let job_handle: JobHandle = serde_json::from_str(&std::fs::read_to_string("job.json")?)?;
+/// let data = job_handle.retrieve_results(&Qcs::load()).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "qpu")]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobHandle<'executable> {
+    job_id: JobId,
+    quantum_processor_id: Cow<'executable, str>,
+    endpoint_id: Option<Cow<'executable, str>>,
+    readout_map: HashMap<String, String>,
+    execution_options: ExecutionOptions,
+    /// When the job was submitted, for handles saved before this was recorded.
+    #[serde(default)]
+    submitted_at: Option<SystemTime>,
+}
+
+#[cfg(feature = "qpu")]
+impl<'a> JobHandle<'a> {
+    #[must_use]
+    pub(crate) fn new<S>(
+        job_id: JobId,
+        quantum_processor_id: S,
+        endpoint_id: Option<S>,
+        readout_map: HashMap<String, String>,
+        execution_options: ExecutionOptions,
+    ) -> Self
+    where
+        S: Into<Cow<'a, str>>,
+    {
+        Self {
+            job_id,
+            quantum_processor_id: quantum_processor_id.into(),
+            endpoint_id: endpoint_id.map(Into::into),
+            readout_map,
+            execution_options,
+            submitted_at: Some(SystemTime::now()),
+        }
+    }
+
+    /// The string representation of the QCS Job ID. Useful for debugging.
+    #[must_use]
+    pub fn job_id(&self) -> JobId {
+        self.job_id.clone()
+    }
+
+    /// The ID of the quantum processor to which the job was submitted.
+    #[must_use]
+    pub fn quantum_processor_id(&self) -> &str {
+        &self.quantum_processor_id
+    }
+
+    /// The readout map from source readout memory locations to the
+    /// filter pipeline node which publishes the data.
+    #[must_use]
+    pub fn readout_map(&self) -> &HashMap<String, String> {
+        &self.readout_map
+    }
+
+    /// The [`ExecutionOptions`] used to submit the job to the QPU.
+    #[must_use]
+    pub fn execution_options(&self) -> &ExecutionOptions {
+        &self.execution_options
+    }
+
+    /// When the job was submitted, or `None` if this handle was deserialized from one which
+    /// didn't record it.
+    #[must_use]
+    pub fn submitted_at(&self) -> Option<SystemTime> {
+        self.submitted_at
+    }
+
+    /// Ask the controller for the current [`JobStatus`] of the job, connecting with `client`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the controller can't be reached or reports an unknown status.
+    pub async fn status(&self, client: &Qcs) -> Result<JobStatus, Error> {
+        qpu::api::get_job_status(
+            self.job_id.clone(),
+            Some(self.quantum_processor_id.as_ref()),
+            client,
+            &self.execution_options,
+        )
+        .await
+        .map_err(Error::from)
+    }
+
+    /// Wait for the job to complete and retrieve its results, connecting to the QPU with `client`.
+    ///
+    /// Unlike [`Executable::retrieve_results`], this doesn't need the [`Executable`] which
+    /// submitted the job, so it works with a handle that was deserialized by another process.
+    ///
+    /// # Errors
+    ///
+    /// See [`Executable::execute_on_qpu`].
+    pub async fn retrieve_results(&self, client: &Qcs) -> ExecutionResult {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            job_id = %self.job_id,
+            quantum_processor_id = %self.quantum_processor_id,
+            "retrieving execution results for job handle",
+        );
+
+        qpu::retrieve_job_results(self, client)
+            .await
+            .map_err(Error::from)
+    }
+}
+
+#[cfg(all(test, feature = "qvm-http"))]
+mod describe_quil_t_check {
+    use std::str::FromStr;
+
+    use assert2::let_assert;
+    use quil_rs::Program;
+
+    use super::{find_quil_t_instruction, Error, Service};
+    use crate::{client::Qcs, qvm, Executable};
+
+    const QUIL_T_PROGRAM: &str = r#"DECLARE ro BIT
+DEFFRAME 0 "rf":
+    SAMPLE-RATE: 1.0
+X 0
+PULSE 0 "rf" gaussian(duration: 1.0, fwhm: 0.5, t0: 0.5)
+MEASURE 0 ro
+"#;
+
+    #[test]
+    fn it_finds_quil_t_instructions() {
+        let program = Program::from_str(QUIL_T_PROGRAM).unwrap();
+        assert!(find_quil_t_instruction(&program).is_some());
+
+        let program = Program::from_str("DECLARE ro BIT\nX 0\nMEASURE 0 ro").unwrap();
+        assert!(find_quil_t_instruction(&program).is_none());
+    }
+
+    #[tokio::test]
+    async fn it_rejects_quil_t_before_contacting_qvm() {
+        // The QVM at this address is never contacted.
+        let qvm_client = qvm::http::HttpClient::new("http://127.0.0.1:1".to_string());
+        let mut exe = Executable::from_quil(QUIL_T_PROGRAM).with_qcs_client(Qcs::default());
+        let result = exe.execute_on_qvm(&qvm_client).await;
+        let_assert!(
+            Err(Error::QuilTNotSupportedByTarget {
+                target: Service::Qvm,
+                ..
+            }) = result
+        );
+
+        let mut exe = exe.skip_quil_t_check(true);
+        let result = exe.execute_on_qvm(&qvm_client).await;
+        let_assert!(Err(Error::Connection(Service::Qvm)) = result);
+    }
+}
+
+#[cfg(test)]
+mod describe_program_rewriting {
+    use super::{Executable, RewiringStrategy};
+
+    #[test]
+    fn it_runs_the_program_as_written_by_default() {
+        let exe = Executable::from_quil("RESET\nX 0");
+        assert_eq!(exe.program_quil().unwrap().as_ref(), "RESET\nX 0");
+    }
+
+    #[test]
+    fn it_applies_active_reset_and_initial_rewiring() {
+        let exe = Executable::from_quil("DECLARE ro BIT\nX 0\nMEASURE 0 ro")
+            .with_active_reset(true)
+            .with_initial_rewiring(RewiringStrategy::Naive);
+        assert_eq!(
+            exe.program_quil().unwrap().as_ref(),
+            "DECLARE ro BIT[1]\nPRAGMA INITIAL_REWIRING \"NAIVE\"\nRESET\nX 0\nMEASURE 0 ro[0]\n"
+        );
+
+        let exe = exe.with_active_reset(false);
+        assert!(!exe.program_quil().unwrap().contains("RESET"));
+    }
+}
+
+#[cfg(test)]
+mod describe_from_program {
+    use std::str::FromStr;
+
+    use quil_rs::Program;
+
+    use super::Executable;
+
+    const QUIL_T: &str = r#"DEFFRAME 0 "rf":
+    SAMPLE-RATE: 1.0
+DEFCAL X 0:
+    PULSE 0 "rf" gaussian(duration: 1.0, fwhm: 0.5, t0: 0.5)
+DECLARE ro BIT[1]
+X 0
+MEASURE 0 ro[0]
+"#;
+
+    #[test]
+    fn it_keeps_calibrations_and_frames() {
+        let program = Program::from_str(QUIL_T).unwrap();
+        let exe = Executable::from_program(program.clone());
+
+        let quil = exe.program_quil().unwrap();
+        assert!(quil.contains("DEFFRAME 0 \"rf\""));
+        assert!(quil.contains("DEFCAL X 0"));
+        assert_eq!(Program::from_str(&quil).unwrap(), program);
+    }
+
+    #[test]
+    fn it_does_not_copy_the_program_without_rewriting() {
+        let program = Program::from_str("DECLARE ro BIT\nX 0\nMEASURE 0 ro").unwrap();
+        let exe = Executable::from_program(program.clone());
+        assert!(matches!(
+            exe.program().unwrap(),
+            std::borrow::Cow::Borrowed(_)
+        ));
+
+        let exe = exe.with_active_reset(true);
+        let rewritten = exe.program().unwrap();
+        assert_eq!(
+            rewritten.body_instructions().count(),
+            program.body_instructions().count() + 1
+        );
+    }
+}
+
+#[cfg(test)]
+mod describe_substitutions {
+    use assert2::let_assert;
+
+    use super::{Error, Executable, Service};
+
+    const PROGRAM: &str = "DECLARE theta REAL[2]\nRX(theta[0]) 0";
+
+    #[test]
+    fn it_applies_parameters_as_moves() {
+        let mut exe = Executable::from_quil(PROGRAM);
+        exe.with_parameter("theta", 0, 0.5)
+            .with_parameter("theta", 1, 1.5);
+        assert_eq!(
+            exe.to_quil_with_substitutions().unwrap(),
+            "DECLARE theta REAL[2]\nMOVE theta[0] 0.5\nMOVE theta[1] 1.5\nRX(theta[0]) 0\n"
+        );
+    }
+
+    #[test]
+    fn it_leaves_substitutions_out_of_errors_by_default() {
+        let mut exe = Executable::from_quil(PROGRAM);
+        exe.with_parameter("theta", 2, 0.5);
+        let_assert!(
+            Err(Error::ParameterMismatch {
+                substitutions: None,
+                ..
+            }) = exe.to_quil_with_substitutions()
+        );
+    }
+
+    #[test]
+    fn it_includes_substitutions_in_errors_when_asked() {
+        let mut exe = Executable::from_quil(PROGRAM).with_substitutions_in_errors(true);
+        exe.with_parameter("theta", 2, 0.5);
+        let_assert!(
+            Err(Error::ParameterMismatch {
+                message,
+                substitutions: Some(substitutions),
+                ..
+            }) = exe.to_quil_with_substitutions()
+        );
+        assert!(message.contains("theta"));
+        assert!(substitutions.contains("MOVE theta[2] 0.5"));
+
+        assert_eq!(
+            exe.render_substitutions(Service::Qpu).unwrap(),
+            "theta: [0.0, 0.0, 0.5]\n"
+        );
+    }
+}
+
+#[cfg(all(test, feature = "qvm-http"))]
+mod describe_qvm_state_reuse {
+    use crate::{client::Qcs, Executable};
+
+    #[tokio::test]
+    async fn it_keeps_its_qvm_client_between_runs() {
+        let mut exe = Executable::from_quil("DECLARE ro BIT\nX 0\nMEASURE 0 ro")
+            .with_qcs_client(Qcs::default());
+        // Whether or not a QVM is running, the client is kept for the next run.
+        let _ = exe.execute_on_qvm_with_state_reuse().await;
+        assert_eq!(
+            exe.qvm_http_client
+                .as_ref()
+                .map(|client| client.qvm_url.as_str()),
+            Some(Qcs::default().get_config().qvm_url())
+        );
+        assert!(exe.qvm.is_some());
+
+        let exe = exe.with_qcs_client(Qcs::default());
+        assert!(exe.qvm_http_client.is_none());
+    }
+}
+
+#[cfg(test)]
+mod describe_validate_parameters {
+    use assert2::let_assert;
+    use quil_rs::instruction::ScalarType;
+
+    use super::{Error, Executable};
+    use crate::{client::Qcs, qvm};
+
+    const PROGRAM: &str = "DECLARE theta REAL[2]\nDECLARE flag BIT\nRX(theta[0]) 0";
+
+    #[test]
+    fn it_accepts_parameters_which_fit_the_declarations() {
+        let mut exe = Executable::from_quil(PROGRAM);
+        exe.with_parameter("theta", 1, 0.5)
+            .with_parameter("flag", 0, 1.0);
+        assert!(exe.validate_parameters().is_ok());
+    }
+
+    #[test]
+    fn it_reports_the_declared_size_and_offending_index() {
+        let mut exe = Executable::from_quil(PROGRAM);
+        exe.with_parameter("theta", 3, 0.5);
+        let_assert!(
+            Err(Error::ParameterMismatch {
+                message,
+                region,
+                index,
+                declared_type,
+                declared_length,
+                ..
+            }) = exe.validate_parameters()
+        );
+        assert!(message.contains("theta[3]"), "{message}");
+        assert!(message.contains("DECLARE theta REAL[2]"), "{message}");
+        assert_eq!(region, "theta");
+        assert_eq!(index, Some(3));
+        assert_eq!(declared_type, Some(ScalarType::Real));
+        assert_eq!(declared_length, Some(2));
+    }
+
+    #[tokio::test]
+    async fn it_rejects_parameters_before_contacting_qvm() {
+        // The QVM at this address is never contacted.
+        let qvm_client = qvm::http::HttpClient::new("http://127.0.0.1:1".to_string());
+        let mut exe = Executable::from_quil(PROGRAM).with_qcs_client(Qcs::default());
+        exe.with_parameter("flag", 0, 0.5);
+        let_assert!(
+            Err(Error::ParameterMismatch { message, .. }) = exe.execute_on_qvm(&qvm_client).await
+        );
+        assert!(message.contains("flag[0]"), "{message}");
+    }
+
+    #[cfg(feature = "qpu")]
+    #[tokio::test]
+    async fn it_rejects_parameters_before_compiling_for_qpu() {
+        let mut exe = Executable::from_quil(PROGRAM).with_qcs_client(Qcs::default());
+        exe.with_parameter("phi", 0, 0.5);
+        let result = exe
+            .submit_to_qpu(
+                "Aspen-M-3",
+                None,
+                &crate::qpu::api::ExecutionOptions::default(),
+            )
+            .await;
+        let_assert!(Err(Error::ParameterMismatch { message, .. }) = result);
+        assert!(message.contains("phi isn't declared"), "{message}");
+        assert!(exe.qpu.is_none());
+    }
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
-/// The external services that this SDK may connect to. Used to differentiate between networking
-/// issues in [`Error::Connection`].
-pub enum Service {
-    /// The open source [`quilc`](https://github.com/quil-lang/quilc) compiler.
-    ///
-    /// This compiler must be running before calling [`Executable::execute_on_qpu`] unless the
-    /// [`Executable::compile_with_quilc`] option is set to `false`. By default, it's assumed that
-    /// this is running on `tcp://localhost:5555`, but this can be overridden via
-    /// `[profiles.<profile_name>.applications.pyquil.quilc_url]` in your `.qcs/settings.toml` file.
-    Quilc,
-    /// The open source [`qvm`](https://github.com/quil-lang/qvm) simulator.
-    ///
-    /// This simulator must be running before calling [`Executable::execute_on_qvm`]. By default,
-    /// it's assumed that this is running on `http://localhost:5000`, but this can be overridden via
-    /// `[profiles.<profile_name>.applications.pyquil.qvm_url]` in your `.qcs/settings.toml` file.
-    Qvm,
-    /// The connection to [`QCS`](https://docs.rigetti.com/qcs/), the API for authentication,
-    /// QPU lookup, and translation.
-    ///
-    /// You should be able to reach this service as long as you have a connection to the internet.
-    Qcs,
-    /// The connection to the QPU itself. You can only connect to the QPU from an authorized network
-    /// (like QCS JupyterLab).
-    Qpu,
+#[cfg(test)]
+mod describe_shot_memory_reset {
+    use assert2::let_assert;
+
+    use super::{Error, Service, ShotMemory};
+    use crate::{client::Qcs, qvm, Executable};
+
+    const COUNTER: &str = "DECLARE count INTEGER\nADD count[0] 1";
+
+    #[tokio::test]
+    async fn it_rejects_persistent_memory_before_contacting_qvm() {
+        // The QVM at this address is never contacted.
+        let qvm_client = qvm::http::HttpClient::new("http://127.0.0.1:1".to_string());
+        let exe = Executable::from_quil(COUNTER).with_qcs_client(Qcs::default());
+        assert_eq!(exe.shot_memory(), ShotMemory::Reset);
+        assert_eq!(Service::Qvm.shot_memory(), Some(ShotMemory::Reset));
+
+        let mut exe = exe.with_shot_memory_reset(false);
+        assert!(!exe.shot_memory_reset());
+        let result = exe.execute_on_qvm(&qvm_client).await;
+        let_assert!(
+            Err(Error::ShotMemoryNotSupported {
+                target: Service::Qvm,
+                requested: ShotMemory::Persist,
+            }) = result
+        );
+
+        let mut exe = exe.with_shot_memory_reset(true);
+        let result = exe.execute_on_qvm(&qvm_client).await;
+        let_assert!(Err(Error::Connection(Service::Qvm)) = result);
+    }
 }
 
-impl From<ExecutionError> for Error {
-    fn from(err: ExecutionError) -> Self {
-        match err {
-            ExecutionError::Unexpected(inner) => Self::Unexpected(format!("{inner:?}")),
-            ExecutionError::Quilc { .. } => Self::Connection(Service::Quilc),
-            ExecutionError::QcsClient(v) => Self::Unexpected(format!("{v:?}")),
-            ExecutionError::Translation(v) => Self::Translation(v.to_string()),
-            ExecutionError::Isa(v) => Self::Unexpected(format!("{v:?}")),
-            ExecutionError::ReadoutParse(v) => Self::Unexpected(format!("{v:?}")),
-            ExecutionError::Quil(e) => Self::Quil(e),
-            ExecutionError::ToQuil(e) => Self::ToQuil(e),
-            ExecutionError::Compilation { details } => Self::Compilation(details),
-            ExecutionError::RpcqClient(e) => Self::Unexpected(format!("{e:?}")),
-            ExecutionError::QpuApi(e) => Self::QpuApiError(e),
-        }
+#[cfg(all(test, feature = "qpu"))]
+mod describe_deadline {
+    use std::time::{Duration, Instant};
+
+    use super::{within_deadline, Error, Stage};
+    use crate::Executable;
+
+    #[tokio::test]
+    async fn it_stops_waiting_when_the_deadline_passes() {
+        let deadline = Instant::now() + Duration::from_millis(10);
+        let result = within_deadline(
+            Some(deadline),
+            Stage::Retrieval,
+            std::future::pending::<()>(),
+        );
+        assert_eq!(result.await, Err(Stage::Retrieval));
+    }
+
+    #[tokio::test]
+    async fn it_waits_without_a_deadline() {
+        let result = within_deadline(None, Stage::Compilation, async { 42 });
+        assert_eq!(result.await, Ok(42));
+    }
+
+    #[test]
+    fn it_starts_the_deadline_with_each_execution() {
+        let exe = Executable::from_quil("X 0");
+        assert!(exe.deadline_from_now().is_none());
+
+        let exe = exe.with_deadline(Duration::from_secs(60));
+        let deadline = exe.deadline_from_now().unwrap();
+        assert!(deadline > Instant::now() + Duration::from_secs(59));
+        assert!(exe.deadline_from_now().unwrap() >= deadline);
+    }
+
+    #[test]
+    fn it_names_the_stage_that_ran_out_of_time() {
+        let error = Error::DeadlineExceeded {
+            phase: Stage::Translation,
+        };
+        assert_eq!(
+            error.to_string(),
+            "The deadline was exceeded during translation"
+        );
     }
 }
 
-impl From<qvm::Error> for Error {
-    fn from(err: qvm::Error) -> Self {
-        match err {
-            qvm::Error::QvmCommunication { .. } | qvm::Error::Client { .. } => {
-                Self::Connection(Service::Qvm)
-            }
-            qvm::Error::ToQuil(q) => Self::ToQuil(q),
-            qvm::Error::Parsing(_)
-            | qvm::Error::ShotsMustBePositive
-            | qvm::Error::RegionSizeMismatch { .. }
-            | qvm::Error::RegionNotFound { .. }
-            | qvm::Error::Qvm { .. } => Self::Compilation(format!("{err}")),
+#[cfg(all(test, feature = "qpu"))]
+mod describe_shot_splitting {
+    use std::num::NonZeroU16;
+    use std::time::Duration;
+
+    use maplit::hashmap;
+
+    use super::{concatenate_shots, Error};
+    use crate::client::Qcs;
+    use crate::execution_data::{ExecutionData, ExecutionTimings};
+    use crate::qpu::api::ExecutionOptionsBuilder;
+    use crate::qpu::{QpuResultData, ReadoutValues};
+    use crate::qvm::QvmResultData;
+    use crate::{Executable, ResultData};
+
+    fn job(values: Vec<i64>, duration: u64) -> ExecutionData {
+        ExecutionData {
+            result_data: ResultData::Qpu(QpuResultData::from_mappings_and_values(
+                hashmap! {"ro[0]".to_string() => "q0".to_string()},
+                hashmap! {"q0".to_string() => ReadoutValues::Integer(values)},
+                hashmap! {},
+            )),
+            duration: Some(Duration::from_micros(duration)),
+            timings: None,
         }
     }
+
+    #[test]
+    fn it_combines_jobs_in_shot_order() {
+        let combined = concatenate_shots(vec![job(vec![0, 1], 10), job(vec![1], 5)]).unwrap();
+
+        assert_eq!(combined, job(vec![0, 1, 1], 15));
+    }
+
+    #[test]
+    fn it_totals_execution_and_decoding_and_keeps_the_longest_time_to_results() {
+        let timed = |execution, time_to_results, post_processing| {
+            let mut data = job(vec![0], execution);
+            data.timings = Some(ExecutionTimings {
+                execution: Some(Duration::from_micros(execution)),
+                time_to_results: Some(Duration::from_micros(time_to_results)),
+                post_processing: Some(Duration::from_micros(post_processing)),
+            });
+            data
+        };
+
+        let combined = concatenate_shots(vec![timed(10, 300, 2), timed(5, 100, 1)]).unwrap();
+
+        assert_eq!(
+            combined.timings,
+            Some(ExecutionTimings {
+                execution: Some(Duration::from_micros(15)),
+                time_to_results: Some(Duration::from_micros(300)),
+                post_processing: Some(Duration::from_micros(3)),
+            })
+        );
+    }
+
+    #[test]
+    fn it_refuses_results_which_are_not_from_a_qpu() {
+        let qvm = ExecutionData {
+            result_data: ResultData::Qvm(QvmResultData::from_memory_map(hashmap! {})),
+            duration: None,
+            timings: None,
+        };
+
+        let result = concatenate_shots(vec![job(vec![0], 10), qvm]);
+
+        assert!(matches!(result, Err(Error::ShotSplitting(_))));
+    }
+
+    #[tokio::test]
+    async fn it_refuses_to_submit_more_shots_than_a_job_allows() {
+        let mut exe = Executable::from_quil("DECLARE ro BIT\nX 0\nMEASURE 0 ro")
+            .with_qcs_client(Qcs::default())
+            .with_shots(NonZeroU16::new(10).unwrap());
+        let execution_options = ExecutionOptionsBuilder::default()
+            .max_shots_per_job(NonZeroU16::new(4))
+            .build()
+            .unwrap();
+
+        let result = exe
+            .submit_to_qpu("Aspen-M-3", None, &execution_options)
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(Error::TooManyShotsPerJob { shots, max_shots_per_job })
+                if shots.get() == 10 && max_shots_per_job.get() == 4
+        ));
+        assert!(exe.qpu.is_none());
+    }
 }
 
-/// The result of calling [`Executable::submit_to_qpu`]. Represents a quantum program running on
-/// a QPU. Can be passed to [`Executable::retrieve_results`] to retrieve the results of the job.
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct JobHandle<'executable> {
-    job_id: JobId,
-    quantum_processor_id: Cow<'executable, str>,
-    endpoint_id: Option<Cow<'executable, str>>,
-    readout_map: HashMap<String, String>,
-    execution_options: ExecutionOptions,
+#[cfg(all(test, feature = "qpu"))]
+mod describe_cancellation {
+    use crate::client::Qcs;
+    use crate::executable::Error;
+    use crate::metrics::Stage;
+    use crate::qpu::api::{CancellationToken, ExecutionOptions};
+    use crate::Executable;
+
+    #[tokio::test]
+    async fn it_stops_before_compiling_with_a_cancelled_token() {
+        let token = CancellationToken::new();
+        token.cancel();
+        let mut exe = Executable::from_quil("DECLARE ro BIT\nX 0\nMEASURE 0 ro")
+            .with_qcs_client(Qcs::default())
+            .with_cancellation_token(token);
+
+        let result = exe
+            .execute_on_qpu("Aspen-M-3", None, &ExecutionOptions::default())
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(Error::Cancelled {
+                phase: Stage::Compilation
+            })
+        ));
+        assert!(exe.qpu.is_none());
+    }
+
+    #[test]
+    fn it_names_the_stage_that_was_cancelled() {
+        let error = Error::Cancelled {
+            phase: Stage::Retrieval,
+        };
+        assert_eq!(
+            error.to_string(),
+            "The execution was cancelled during retrieval"
+        );
+    }
 }
 
-impl<'a> JobHandle<'a> {
-    #[must_use]
-    pub(crate) fn new<S>(
-        job_id: JobId,
-        quantum_processor_id: S,
-        endpoint_id: Option<S>,
-        readout_map: HashMap<String, String>,
-        execution_options: ExecutionOptions,
-    ) -> Self
-    where
-        S: Into<Cow<'a, str>>,
-    {
-        Self {
-            job_id,
-            quantum_processor_id: quantum_processor_id.into(),
-            endpoint_id: endpoint_id.map(Into::into),
-            readout_map,
+#[cfg(all(test, feature = "qpu"))]
+mod describe_job_handle {
+    use std::collections::HashMap;
+
+    use super::JobHandle;
+    use crate::qpu::api::{ConnectionStrategy, ExecutionOptionsBuilder, JobId};
+
+    #[test]
+    fn it_round_trips_through_json() {
+        let execution_options = ExecutionOptionsBuilder::default()
+            .connection_strategy(ConnectionStrategy::EndpointId("endpoint".to_string()))
+            .api_options(Some(Default::default()))
+            .timeout(Some(std::time::Duration::from_secs(5)))
+            .build()
+            .unwrap();
+        let job_handle = JobHandle::new(
+            JobId("job".to_string()),
+            "Ankaa-3",
+            Some("endpoint"),
+            HashMap::from([("ro[0]".to_string(), "q0".to_string())]),
             execution_options,
-        }
+        );
+
+        let json = serde_json::to_string(&job_handle).unwrap();
+        let deserialized: JobHandle = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, job_handle);
     }
 
-    /// The string representation of the QCS Job ID. Useful for debugging.
-    #[must_use]
-    pub fn job_id(&self) -> JobId {
-        self.job_id.clone()
+    #[test]
+    fn it_defaults_missing_execution_options() {
+        let json = r#"{
+            "job_id": "job",
+            "quantum_processor_id": "Ankaa-3",
+            "endpoint_id": null,
+            "readout_map": {},
+            "execution_options": {}
+        }"#;
+        let job_handle: JobHandle = serde_json::from_str(json).unwrap();
+        assert_eq!(job_handle.execution_options(), &Default::default());
+        assert_eq!(job_handle.submitted_at(), None);
     }
+}
 
-    /// The ID of the quantum processor to which the job was submitted.
-    #[must_use]
-    pub fn quantum_processor_id(&self) -> &str {
-        &self.quantum_processor_id
+#[cfg(all(test, feature = "qpu"))]
+mod describe_parameter_sweep {
+    use assert2::let_assert;
+
+    use super::Error;
+    use crate::Executable;
+
+    #[test]
+    fn it_uses_fixed_parameters_without_sweeps() {
+        let mut exe = Executable::from_quil("");
+        exe.with_parameter("theta", 0, 1.0);
+
+        let sets = exe.parameter_sets().unwrap();
+        assert_eq!(sets.len(), 1);
+        assert_eq!(sets[0]["theta"], vec![1.0]);
     }
 
-    /// The readout map from source readout memory locations to the
-    /// filter pipeline node which publishes the data.
-    #[must_use]
-    pub fn readout_map(&self) -> &HashMap<String, String> {
-        &self.readout_map
+    #[test]
+    fn it_combines_sweeps_element_wise() {
+        let mut exe = Executable::from_quil("");
+        exe.with_parameter("theta", 0, 1.0)
+            .with_parameter_sweep("theta", 1, [0.1, 0.2, 0.3])
+            .with_parameter_sweep("phi", 0, vec![1.0, 2.0, 3.0]);
+
+        let sets = exe.parameter_sets().unwrap();
+        assert_eq!(sets.len(), 3);
+        assert_eq!(sets[1]["theta"], vec![1.0, 0.2]);
+        assert_eq!(sets[2]["phi"], vec![3.0]);
     }
 
-    /// The [`ExecutionOptions`] used to submit the job to the QPU.
-    #[must_use]
-    pub fn execution_options(&self) -> &ExecutionOptions {
-        &self.execution_options
+    #[test]
+    fn it_replaces_sweeps_of_the_same_location() {
+        let mut exe = Executable::from_quil("");
+        exe.with_parameter_sweep("theta", 0, vec![0.1, 0.2])
+            .with_parameter_sweep("theta", 0, vec![0.3]);
+
+        let sets = exe.parameter_sets().unwrap();
+        assert_eq!(sets.len(), 1);
+        assert_eq!(sets[0]["theta"], vec![0.3]);
+    }
+
+    #[test]
+    fn it_rejects_sweeps_of_different_lengths() {
+        let mut exe = Executable::from_quil("");
+        exe.with_parameter_sweep("theta", 0, vec![0.1, 0.2])
+            .with_parameter_sweep("phi", 2, vec![1.0]);
+
+        let_assert!(
+            Err(Error::ParameterSweepLengthMismatch {
+                parameter,
+                expected: 2,
+                found: 1,
+            }) = exe.parameter_sets()
+        );
+        assert_eq!(parameter, "phi[2]");
     }
 }
 
 #[cfg(test)]
-#[cfg(feature = "manual-tests")]
+#[cfg(all(feature = "manual-tests", feature = "quilc-rpcq"))]
 mod describe_get_config {
     use crate::client::Qcs;
     use crate::{compiler::rpcq, Executable};
@@ -823,14 +3743,19 @@ mod describe_get_config {
 }
 
 #[cfg(test)]
-#[cfg(feature = "manual-tests")]
+#[cfg(all(feature = "manual-tests", feature = "qpu", feature = "quilc-rpcq"))]
 mod describe_qpu_for_id {
     use assert2::let_assert;
+    use std::collections::HashMap;
     use std::num::NonZeroU16;
 
     use crate::compiler::quilc::CompilerOpts;
     use crate::compiler::rpcq;
-    use crate::qpu;
+    use crate::executable::JobHandle;
+    use crate::qpu::{
+        self,
+        api::{ExecutionOptions, JobId},
+    };
     use crate::{client::Qcs, Executable};
 
     fn quilc_client() -> rpcq::Client {
@@ -864,8 +3789,10 @@ mod describe_qpu_for_id {
                 shots,
                 "Aspen-M-3".into(),
                 exe.qcs_client(),
-                exe.quilc_client.clone(),
+                exe.compiler.clone(),
                 CompilerOpts::default(),
+                0,
+                false,
             )
             .await
             .unwrap(),
@@ -874,6 +3801,7 @@ mod describe_qpu_for_id {
         let mut exe = exe.with_qcs_client(Qcs::default());
 
         assert!(exe.qpu_for_id("Aspen-M-3").await.is_ok());
+        assert_eq!(exe.stage_timings().compilation, None);
     }
 
     #[tokio::test]
@@ -885,6 +3813,7 @@ mod describe_qpu_for_id {
         let qpu = exe.qpu_for_id("Aspen-9").await.unwrap();
 
         assert_eq!(qpu.shots, original_shots);
+        assert!(exe.stage_timings().compilation.is_some());
 
         // Cache so we can verify cache is not used.
         exe.qpu = Some(qpu);
@@ -912,4 +3841,254 @@ mod describe_qpu_for_id {
         assert!(err.contains("NoRefreshToken"));
         assert!(exe.qpu.is_none());
     }
+    #[tokio::test]
+    async fn it_compiles_without_submitting() {
+        const PROGRAM: &str = "DECLARE theta REAL\nDECLARE ro BIT\nRX(theta) 0\nMEASURE 0 ro";
+        let mut exe = Executable::from_quil(PROGRAM).with_quilc_client(Some(quilc_client()));
+        exe.with_parameter("theta", 0, 1.5);
+
+        let artifacts = exe.compile_only("Aspen-9").await.unwrap();
+
+        assert!(artifacts.native_quil.contains("MEASURE"));
+        assert_eq!(
+            artifacts.patch_table["theta"],
+            qpu::result_data::MemoryValues::Real(vec![1.5])
+        );
+        assert_eq!(artifacts.memory_descriptors["ro"].length, 1);
+        assert!(artifacts.ro_sources.is_none());
+        // The compilation is kept for the next submission.
+        assert!(exe.qpu.is_some());
+    }
+
+    #[tokio::test]
+    async fn it_keeps_the_compilation_after_cancelling_and_retrieving() {
+        let mut exe = Executable::from_quil("").with_quilc_client(Some(quilc_client()));
+        let qpu = exe.qpu_for_id("Aspen-9").await.unwrap();
+        exe.qpu = Some(qpu);
+        // Load config with no credentials, so that requests fail and a new Execution can't be made.
+        let mut exe = exe.with_qcs_client(Qcs::default());
+        let job_handle = JobHandle::new(
+            JobId("job".to_string()),
+            "Aspen-9",
+            None,
+            HashMap::new(),
+            ExecutionOptions::default(),
+        );
+
+        assert!(exe.cancel_qpu_job(job_handle.clone()).await.is_err());
+        assert!(exe.qpu.is_some());
+        assert!(exe.retrieve_results(job_handle).await.is_err());
+        assert!(exe.qpu.is_some());
+        assert!(exe.qpu_for_id("Aspen-9").await.is_ok());
+        assert_eq!(exe.stage_timings().compilation, None);
+    }
+}
+
+#[cfg(test)]
+mod describe_read_indices {
+    use std::str::FromStr;
+
+    use quil_rs::Program;
+
+    use super::{split_indexed_reference, Error};
+    use crate::qvm::http::AddressRequest;
+    use crate::Executable;
+
+    #[test]
+    fn it_requests_only_the_selected_indices() {
+        let program = Program::from_str("DECLARE ro BIT[6]\nDECLARE theta REAL").unwrap();
+        let exe = Executable::from_quil("")
+            .read_indices("ro", &[0, 2, 5])
+            .read_from("theta");
+        let addresses = exe.readout_addresses(&program);
+
+        assert_eq!(addresses["ro"], AddressRequest::Indices(vec![0, 2, 5]));
+        assert_eq!(addresses["theta"], AddressRequest::IncludeAll);
+    }
+
+    #[test]
+    fn it_reads_indexed_memory_references() {
+        let program = Program::from_str("DECLARE ro BIT[6]\nDECLARE theta REAL[2]").unwrap();
+        let exe = Executable::from_quil("")
+            .read_from("ro[3]")
+            .read_from("theta")
+            .read_from("ro[1]");
+        let addresses = exe.readout_addresses(&program);
+
+        assert_eq!(addresses.len(), 2);
+        assert_eq!(addresses["ro"], AddressRequest::Indices(vec![3, 1]));
+        assert_eq!(addresses["theta"], AddressRequest::IncludeAll);
+    }
+
+    #[test]
+    fn it_refuses_to_narrow_a_region_read_whole() {
+        let exe = Executable::from_quil("").read_from("ro").read_from("ro[3]");
+        assert!(matches!(
+            exe.check_readouts(),
+            Err(Error::ReadoutNarrowed { reference }) if reference == "ro[3]"
+        ));
+
+        let exe = exe.read_from("ro");
+        assert!(exe.check_readouts().is_ok());
+        let exe = Executable::from_quil("")
+            .read_from("ro[3]")
+            .read_from("ro[1]");
+        assert!(exe.check_readouts().is_ok());
+    }
+
+    #[test]
+    fn it_splits_indexed_memory_references() {
+        assert_eq!(
+            split_indexed_reference("ro[3]"),
+            Some(("ro".to_string(), 3))
+        );
+        assert_eq!(split_indexed_reference("ro"), None);
+        assert_eq!(split_indexed_reference("ro[-1]"), None);
+        assert_eq!(split_indexed_reference("ro[]"), None);
+    }
+
+    #[test]
+    fn it_reads_the_whole_region_after_read_from() {
+        let program = Program::from_str("DECLARE ro BIT[6]").unwrap();
+        let exe = Executable::from_quil("")
+            .read_indices("ro", &[1])
+            .read_from("ro");
+
+        assert_eq!(
+            exe.readout_addresses(&program)["ro"],
+            AddressRequest::IncludeAll
+        );
+    }
+}
+
+#[cfg(all(test, feature = "qpu"))]
+mod describe_read_only {
+    use crate::client::Qcs;
+    use crate::executable::Error;
+    use crate::qpu::api::ExecutionOptions;
+    use crate::Executable;
+
+    #[tokio::test]
+    async fn it_refuses_to_submit_before_compiling() {
+        let mut exe = Executable::from_quil("DECLARE ro BIT\nX 0\nMEASURE 0 ro")
+            .with_qcs_client(Qcs::default().with_read_only(true));
+        let result = exe
+            .submit_to_qpu("Aspen-M-3", None, &ExecutionOptions::default())
+            .await;
+
+        assert!(matches!(result, Err(Error::SubmissionsDisabled)));
+        assert!(exe.qpu.is_none());
+    }
+}
+
+#[cfg(all(test, feature = "qpu"))]
+mod describe_compiler_backend {
+    use std::{num::NonZeroU16, str::FromStr, sync::Arc};
+
+    use quil_rs::Program;
+
+    use crate::{
+        client::Qcs,
+        compiler::{
+            quilc::{
+                self, CompilationCache, CompilationResult, CompilerOpts, NativeQuilMetadata,
+                TargetDevice,
+            },
+            CompilerBackend,
+        },
+        qpu::{api::ExecutionOptions, IsaCache},
+        test_support::aspen_9_isa,
+        Executable,
+    };
+
+    /// A compiler which marks the programs it compiles, but otherwise leaves them unchanged.
+    struct Marking;
+
+    impl CompilerBackend for Marking {
+        fn compile_to_native(
+            &self,
+            quil: &str,
+            _: TargetDevice,
+            _: CompilerOpts,
+        ) -> Result<CompilationResult, quilc::Error> {
+            Ok(CompilationResult {
+                program: Program::from_str(&format!("PRAGMA COMPILED_BY_MARKING\n{quil}"))
+                    .map_err(quilc::Error::Parse)?,
+                native_quil_metadata: Some(NativeQuilMetadata {
+                    final_rewiring: vec![0],
+                    ..NativeQuilMetadata::default()
+                }),
+            })
+        }
+
+        fn get_version(&self) -> Result<String, quilc::Error> {
+            Ok("1.0.0".to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn it_compiles_with_a_custom_backend() {
+        // Cache the ISA so that compiling doesn't need to reach QCS.
+        IsaCache::global().insert("Custom-Backend", aspen_9_isa());
+        let mut exe = Executable::from_quil("DECLARE ro BIT\nX 0\nMEASURE 0 ro")
+            .with_qcs_client(Qcs::default())
+            .with_compiler_backend(Some(Marking));
+
+        let artifacts = exe.compile_only("Custom-Backend").await.unwrap();
+
+        assert!(artifacts.native_quil.contains("PRAGMA COMPILED_BY_MARKING"));
+        assert_eq!(
+            artifacts.native_quil_metadata.unwrap().final_rewiring,
+            vec![0]
+        );
+        assert_eq!(exe.native_quil_metadata().unwrap().final_rewiring, vec![0]);
+    }
+
+    #[tokio::test]
+    async fn it_shares_compilations_through_the_cache() {
+        IsaCache::global().insert("Cached-Backend", aspen_9_isa());
+        let cache = Arc::new(CompilationCache::default());
+        let executable = || {
+            Executable::from_quil("DECLARE ro BIT\nX 0\nMEASURE 0 ro")
+                .with_qcs_client(Qcs::default())
+                .with_compilation_cache(cache.clone())
+                .with_compiler_backend(Some(Marking))
+        };
+
+        let first = executable().compile_only("Cached-Backend").await.unwrap();
+        let second = executable().compile_only("Cached-Backend").await.unwrap();
+
+        assert_eq!(cache.len(), 1);
+        assert_eq!(first.native_quil, second.native_quil);
+        assert!(second.native_quil.contains("PRAGMA COMPILED_BY_MARKING"));
+    }
+
+    #[tokio::test]
+    async fn it_plans_without_compiling() {
+        // Only the two `MEASURE`s read out values, not every index of `ro`.
+        const PROGRAM: &str =
+            "DECLARE ro BIT[4]\nDECLARE theta REAL\nRX(theta) 0\nMEASURE 0 ro[0]\nMEASURE 0 ro[1]";
+        let mut exe = Executable::from_quil(PROGRAM)
+            .with_qcs_client(Qcs::default())
+            .with_compiler_backend(Some(Marking))
+            .with_shots(NonZeroU16::new(10).unwrap());
+        exe.with_parameter("theta", 0, 0.5);
+
+        let plan = exe
+            .plan("Aspen-M-3", None, &ExecutionOptions::default())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            plan.compilation.compiler_version,
+            Some(Ok("1.0.0".to_string()))
+        );
+        assert!(!plan.compilation.reuses_compilation);
+        assert_eq!(plan.sizes.instructions, 3);
+        assert_eq!(plan.sizes.patch_values_per_job, 1);
+        assert_eq!(plan.sizes.readout_values_per_shot, 2);
+        assert_eq!(plan.sizes.readout_values(), 20);
+        assert!(plan.to_string().starts_with("Plan for Aspen-M-3\n"));
+        assert!(exe.qpu.is_none());
+    }
 }
@@ -0,0 +1,340 @@
+//! Running a group of [`Executable`]s together on one QPU, see [`Experiment`].
+
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use futures::future::join_all;
+use indexmap::IndexMap;
+use qcs_api_client_openapi::models::InstructionSetArchitecture;
+
+use crate::client::Qcs;
+use crate::qpu::{
+    api::ExecutionOptions,
+    translation::{SettingsEpoch, TranslationOptions},
+    ExecutionError, IsaCache,
+};
+
+use super::{Error, Executable, ExecutionResult};
+
+/// The result of each [`Executable`] in an [`Experiment`], keyed by name, in the order the
+/// executables were added.
+pub type ExperimentResults = IndexMap<String, ExecutionResult>;
+
+/// A group of named [`Executable`]s which are run together on one QPU, all against the same
+/// snapshot of the QPU's ISA.
+///
+/// The ISA is fetched once, when the experiment is created, and every program is compiled for
+/// it. Before submitting, the QPU's settings are checked once against the snapshot, and if the QPU
+/// has been recalibrated since, nothing is submitted and [`Error::SnapshotOutdated`] is returned,
+/// so that every program in the experiment sees the same device state. The programs are then
+/// translated and submitted together, without each checking the QPU's settings on its own.
+///
+/// Each [`Executable`] keeps its own program, parameters, shots, and compiler settings. The
+/// experiment's [`Qcs`] client, translation options, and execution options are used for all of
+/// them.
+///
+/// ```no_run
+/// use qcs::{client::Qcs, Executable, Experiment};
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), qcs::Error> {
+/// let mut experiment = Experiment::new("Aspen-M-3", Qcs::load())
+///     .await?
+///     .with_executable("x", Executable::from_quil("DECLARE ro BIT\nX 0\nMEASURE 0 ro"))
+///     .with_executable("i", Executable::from_quil("DECLARE ro BIT\nI 0\nMEASURE 0 ro"));
+///
+/// let results = experiment.execute().await?;
+/// let x = results["x"].as_ref().unwrap();
+/// # Ok(())
+/// # }
+/// ```
+#[allow(missing_debug_implementations)]
+pub struct Experiment<'executable> {
+    quantum_processor_id: String,
+    client: Arc<Qcs>,
+    isa: InstructionSetArchitecture,
+    settings_epoch: Option<SettingsEpoch>,
+    executables: IndexMap<String, Executable<'executable, 'static>>,
+    translation_options: Option<TranslationOptions>,
+    execution_options: ExecutionOptions,
+}
+
+impl<'executable> Experiment<'executable> {
+    /// Start an experiment on `quantum_processor_id`, taking a snapshot of its current ISA. The
+    /// ISA is always fetched, rather than read from [`IsaCache::global`], so that the experiment
+    /// doesn't start from calibrations which are already out of date; the fetched ISA replaces
+    /// the cached one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the ISA can't be fetched.
+    pub async fn new<S: Into<String>>(quantum_processor_id: S, client: Qcs) -> Result<Self, Error> {
+        let quantum_processor_id = quantum_processor_id.into();
+        let isa = IsaCache::global()
+            .get(&quantum_processor_id, &client, true)
+            .await
+            .map_err(|error| Error::from(ExecutionError::from(error)))?;
+        Ok(Self::from_isa(quantum_processor_id, isa, client))
+    }
+
+    /// Start an experiment on `quantum_processor_id`, using `isa` as the snapshot of its ISA.
+    #[must_use]
+    pub fn from_isa<S: Into<String>>(
+        quantum_processor_id: S,
+        isa: InstructionSetArchitecture,
+        client: Qcs,
+    ) -> Self {
+        Self {
+            quantum_processor_id: quantum_processor_id.into(),
+            client: Arc::new(client),
+            settings_epoch: SettingsEpoch::from_isa(&isa),
+            isa,
+            executables: IndexMap::new(),
+            translation_options: None,
+            execution_options: ExecutionOptions::default(),
+        }
+    }
+
+    /// Add `executable` to the experiment as `name`, replacing any executable already added with
+    /// that name. It will use the experiment's [`Qcs`] client.
+    #[must_use]
+    pub fn with_executable<S: Into<String>>(
+        mut self,
+        name: S,
+        executable: Executable<'executable, 'static>,
+    ) -> Self {
+        let executable = executable.with_qcs_client((*self.client).clone());
+        self.executables.insert(name.into(), executable);
+        self
+    }
+
+    /// Translate every program with `options`.
+    #[must_use]
+    pub fn with_translation_options(mut self, options: TranslationOptions) -> Self {
+        self.translation_options = Some(options);
+        self
+    }
+
+    /// Submit every program with `options`.
+    #[must_use]
+    pub fn with_execution_options(mut self, options: ExecutionOptions) -> Self {
+        self.execution_options = options;
+        self
+    }
+
+    /// The QPU the experiment runs on.
+    #[must_use]
+    pub fn quantum_processor_id(&self) -> &str {
+        &self.quantum_processor_id
+    }
+
+    /// The snapshot of the QPU's ISA which every program is compiled for.
+    #[must_use]
+    pub fn isa(&self) -> &InstructionSetArchitecture {
+        &self.isa
+    }
+
+    /// The settings of the QPU when the snapshot was taken, or `None` if they can't be determined
+    /// from the ISA, in which case they aren't checked before submitting.
+    #[must_use]
+    pub fn settings_epoch(&self) -> Option<&SettingsEpoch> {
+        self.settings_epoch.as_ref()
+    }
+
+    /// The executable added as `name`, for example to read its
+    /// [`Executable::stage_timings`] or [`Executable::report`] after running the experiment.
+    #[must_use]
+    pub fn executable(&self, name: &str) -> Option<&Executable<'executable, 'static>> {
+        self.executables.get(name)
+    }
+
+    /// The names of the executables, in the order they were added.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.executables.keys().map(String::as_str)
+    }
+
+    /// Compile every program for the snapshot of the ISA, without submitting anything, and return
+    /// the result for each, keyed by name. The compilations are kept for
+    /// [`Experiment::execute`], unless an executable's shots are changed in the meantime.
+    pub fn compile(&mut self) -> IndexMap<String, Result<(), Error>> {
+        let Self {
+            quantum_processor_id,
+            isa,
+            settings_epoch,
+            executables,
+            ..
+        } = self;
+        executables
+            .iter_mut()
+            .map(|(name, executable)| {
+                let result = executable.compile_for_isa(
+                    Cow::Owned(quantum_processor_id.clone()),
+                    isa,
+                    settings_epoch.clone(),
+                );
+                (name.clone(), result)
+            })
+            .collect()
+    }
+
+    /// Compile every program for the snapshot of the ISA, check that the QPU's settings haven't
+    /// changed since it was taken, then submit all of the programs and wait for their results.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SnapshotOutdated`] if the QPU's settings have changed since the snapshot
+    /// was taken, or an error if they can't be checked. Nothing is submitted in either case.
+    /// Otherwise, each executable's result, including any failure to compile, submit, or
+    /// retrieve it, is returned under its name.
+    pub async fn execute(&mut self) -> Result<ExperimentResults, Error> {
+        self.check_settings_epoch().await?;
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            quantum_processor_id = %self.quantum_processor_id,
+            num_executables = self.executables.len(),
+            "running experiment",
+        );
+
+        let mut results = IndexMap::with_capacity(self.executables.len());
+        let mut compiled = Vec::with_capacity(self.executables.len());
+        for ((name, result), executable) in self
+            .compile()
+            .into_iter()
+            .zip(self.executables.values_mut())
+        {
+            match result {
+                Ok(()) => compiled.push((name, executable)),
+                Err(error) => {
+                    results.insert(name, Err(error));
+                }
+            }
+        }
+
+        let quantum_processor_id = &self.quantum_processor_id;
+        let translation_options = &self.translation_options;
+        let execution_options = &self.execution_options;
+        let submissions = join_all(compiled.into_iter().map(|(name, executable)| async move {
+            let job_handle = executable
+                .submit_to_qpu(
+                    quantum_processor_id.clone(),
+                    translation_options.clone(),
+                    execution_options,
+                )
+                .await;
+            (name, executable, job_handle)
+        }))
+        .await;
+
+        let retrievals = join_all(submissions.into_iter().map(
+            |(name, executable, job_handle)| async move {
+                let result = match job_handle {
+                    Ok(job_handle) => executable.retrieve_results(job_handle).await,
+                    Err(error) => Err(error),
+                };
+                (name, result)
+            },
+        ))
+        .await;
+        results.extend(retrievals);
+
+        results.sort_by_cached_key(|name, _| self.executables.get_index_of(name));
+        Ok(results)
+    }
+
+    /// Fail if the QPU's settings have changed since the snapshot was taken.
+    async fn check_settings_epoch(&self) -> Result<(), Error> {
+        let Some(settings_epoch) = &self.settings_epoch else {
+            return Ok(());
+        };
+        let current = IsaCache::global()
+            .get(&self.quantum_processor_id, &self.client, true)
+            .await
+            .map_err(|error| Error::from(ExecutionError::from(error)))?;
+        if SettingsEpoch::from_isa(&current).as_ref() == Some(settings_epoch) {
+            Ok(())
+        } else {
+            Err(Error::SnapshotOutdated {
+                quantum_processor_id: self.quantum_processor_id.clone(),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod describe_experiment {
+    use std::str::FromStr;
+
+    use quil_rs::Program;
+
+    use super::Experiment;
+    use crate::{
+        client::Qcs,
+        compiler::{
+            quilc::{self, CompilationResult, CompilerOpts, TargetDevice},
+            CompilerBackend,
+        },
+        test_support::aspen_9_isa,
+        Executable,
+    };
+
+    /// A compiler which returns programs unchanged, and fails on programs using `CNOT`.
+    struct Identity;
+
+    impl CompilerBackend for Identity {
+        fn compile_to_native(
+            &self,
+            quil: &str,
+            _: TargetDevice,
+            _: CompilerOpts,
+        ) -> Result<CompilationResult, quilc::Error> {
+            if quil.contains("CNOT") {
+                return Err(quilc::Error::QuilcCompilation(
+                    quilc::CompilationError::Unsupported { operation: "CNOT" },
+                ));
+            }
+            Ok(CompilationResult {
+                program: Program::from_str(quil).map_err(quilc::Error::Parse)?,
+                native_quil_metadata: None,
+            })
+        }
+
+        fn get_version(&self) -> Result<String, quilc::Error> {
+            Ok("identity".to_string())
+        }
+    }
+
+    fn executable(quil: &str) -> Executable<'static, 'static> {
+        Executable::from_quil(quil.to_string()).with_compiler_backend(Some(Identity))
+    }
+
+    #[test]
+    fn it_keeps_executables_in_the_order_they_were_added() {
+        let experiment = Experiment::from_isa("Aspen-9", aspen_9_isa(), Qcs::default())
+            .with_executable("b", executable("X 0"))
+            .with_executable("a", executable("X 1"))
+            .with_executable("b", executable("X 2"));
+
+        assert_eq!(experiment.names().collect::<Vec<_>>(), vec!["b", "a"]);
+        assert_eq!(experiment.quantum_processor_id(), "Aspen-9");
+        assert!(experiment.settings_epoch().is_some());
+    }
+
+    #[test]
+    fn it_compiles_every_program_for_the_snapshot() {
+        let mut experiment = Experiment::from_isa("Aspen-9", aspen_9_isa(), Qcs::default())
+            .with_executable("x", executable("DECLARE ro BIT\nX 0\nMEASURE 0 ro"))
+            .with_executable("bell", executable("DECLARE ro BIT[2]\nH 0\nCNOT 0 1"));
+
+        let results = experiment.compile();
+
+        assert!(results["x"].is_ok());
+        assert!(matches!(results["bell"], Err(crate::Error::Compilation(_))));
+        assert!(experiment
+            .executable("x")
+            .unwrap()
+            .stage_timings()
+            .compilation
+            .is_some());
+    }
+}
@@ -0,0 +1,138 @@
+//! Running one [`Executable`] on several QPUs at once.
+
+use std::collections::HashMap;
+
+use derive_builder::Builder;
+use futures::{stream, StreamExt};
+
+use crate::qpu::{api::ExecutionOptions, translation::TranslationOptions};
+
+use super::{Executable, ExecutionResult};
+
+/// Options for [`execute_on_many`].
+#[derive(Builder, Clone, Debug)]
+pub struct ExecuteOnManyOptions {
+    #[doc = "The greatest number of QPUs to compile for, submit to, or wait for at once. Must not be zero."]
+    #[builder(default = "4")]
+    max_concurrency: usize,
+    #[doc = "The options used to translate the program for each QPU."]
+    #[builder(default)]
+    translation_options: Option<TranslationOptions>,
+    #[doc = "The options used to submit the program to each QPU."]
+    #[builder(default)]
+    execution_options: ExecutionOptions,
+}
+
+impl Default for ExecuteOnManyOptions {
+    fn default() -> Self {
+        ExecuteOnManyOptionsBuilder::default()
+            .build()
+            .expect("Should be able to derive default ExecuteOnManyOptions from the builder.")
+    }
+}
+
+impl ExecuteOnManyOptions {
+    /// Get a [`ExecuteOnManyOptionsBuilder`] that can be used to build custom options.
+    #[must_use]
+    pub fn builder() -> ExecuteOnManyOptionsBuilder {
+        ExecuteOnManyOptionsBuilder::default()
+    }
+
+    /// Get the greatest number of QPUs to work with at once.
+    #[must_use]
+    pub fn max_concurrency(&self) -> usize {
+        self.max_concurrency
+    }
+
+    /// Get the options used to translate the program for each QPU.
+    #[must_use]
+    pub fn translation_options(&self) -> Option<&TranslationOptions> {
+        self.translation_options.as_ref()
+    }
+
+    /// Get the options used to submit the program to each QPU.
+    #[must_use]
+    pub fn execution_options(&self) -> &ExecutionOptions {
+        &self.execution_options
+    }
+}
+
+/// Run `executable` on each of `quantum_processor_ids`, and return the result from each QPU keyed
+/// by its ID.
+///
+/// The program is compiled once for each QPU, with the settings of `executable`, and the QPUs are
+/// worked on in parallel, up to [`ExecuteOnManyOptions::max_concurrency`] at a time. A failure on
+/// one QPU doesn't stop the others, so each QPU has its own [`ExecutionResult`]. A QPU listed more
+/// than once is only run on once.
+///
+/// `executable` itself is left untouched: each QPU is run with its own copy of it.
+pub async fn execute_on_many<S>(
+    executable: &Executable<'_, '_>,
+    quantum_processor_ids: &[S],
+    options: &ExecuteOnManyOptions,
+) -> HashMap<String, ExecutionResult>
+where
+    S: AsRef<str>,
+{
+    let mut ids: Vec<String> = quantum_processor_ids
+        .iter()
+        .map(|id| id.as_ref().to_string())
+        .collect();
+    ids.sort_unstable();
+    ids.dedup();
+
+    #[cfg(feature = "tracing")]
+    tracing::debug!(
+        ?ids,
+        max_concurrency = options.max_concurrency,
+        "running Executable on many QPUs",
+    );
+
+    stream::iter(ids)
+        .map(|id| {
+            let mut executable = executable.clone();
+            async move {
+                let result = executable
+                    .execute_on_qpu(
+                        id.clone(),
+                        options.translation_options.clone(),
+                        &options.execution_options,
+                    )
+                    .await;
+                (id, result)
+            }
+        })
+        .buffer_unordered(options.max_concurrency.max(1))
+        .collect()
+        .await
+}
+
+#[cfg(test)]
+mod describe_execute_on_many {
+    use super::{execute_on_many, ExecuteOnManyOptions};
+    use crate::{client::Qcs, Executable};
+
+    #[test]
+    fn it_has_bounded_concurrency_by_default() {
+        let options = ExecuteOnManyOptions::default();
+        assert_eq!(options.max_concurrency(), 4);
+        assert!(options.translation_options().is_none());
+    }
+
+    #[tokio::test]
+    async fn it_keys_results_by_qpu() {
+        // The default client has no credentials, so every QPU fails, but each gets its own result.
+        let executable =
+            Executable::from_quil("DECLARE ro BIT\nMEASURE 0 ro").with_qcs_client(Qcs::default());
+        let results = execute_on_many(
+            &executable,
+            &["Ankaa-2", "Ankaa-9Q-1", "Ankaa-2"],
+            &ExecuteOnManyOptions::default(),
+        )
+        .await;
+
+        assert_eq!(results.len(), 2);
+        assert!(results["Ankaa-2"].is_err());
+        assert!(results["Ankaa-9Q-1"].is_err());
+    }
+}
@@ -0,0 +1,81 @@
+//! Finding programs which may expect classical memory to carry over from one shot to the next,
+//! see [`Executable::with_shot_memory`](crate::Executable::with_shot_memory).
+
+use quil_rs::instruction::{
+    Arithmetic, BinaryLogic, Convert, Exchange, Instruction, Load, Measurement, Move, Store,
+    UnaryLogic,
+};
+use quil_rs::Program;
+
+/// The memory regions which `program` updates in place before writing them, e.g. with
+/// `ADD counter[0] 1`, in the order they are first updated.
+///
+/// Since memory is reset at the start of every shot, such updates always start from the region's
+/// initial value, so a program which uses them to count or sum across shots won't work as
+/// intended. Instructions are considered in the order they appear, without following jumps.
+pub(crate) fn accumulating_regions(program: &Program) -> Vec<String> {
+    let mut written: Vec<&str> = Vec::new();
+    let mut accumulating = Vec::new();
+    for instruction in program.body_instructions() {
+        let (updated, overwritten) = memory_access(instruction);
+        if let Some(region) = updated {
+            if !written.contains(&region) && !accumulating.iter().any(|name| name == region) {
+                accumulating.push(region.to_string());
+            }
+        }
+        written.extend(overwritten);
+    }
+    accumulating
+}
+
+/// The region `instruction` updates in place, if any, and the regions it overwrites.
+fn memory_access(instruction: &Instruction) -> (Option<&str>, Vec<&str>) {
+    match instruction {
+        Instruction::Arithmetic(Arithmetic { destination, .. })
+        | Instruction::BinaryLogic(BinaryLogic { destination, .. }) => {
+            (Some(destination.name.as_str()), Vec::new())
+        }
+        Instruction::UnaryLogic(UnaryLogic { operand, .. }) => {
+            (Some(operand.name.as_str()), Vec::new())
+        }
+        Instruction::Move(Move { destination, .. })
+        | Instruction::Load(Load { destination, .. })
+        | Instruction::Convert(Convert { destination, .. })
+        | Instruction::Measurement(Measurement {
+            target: Some(destination),
+            ..
+        }) => (None, vec![destination.name.as_str()]),
+        Instruction::Store(Store { destination, .. }) => (None, vec![destination.as_str()]),
+        Instruction::Exchange(Exchange { left, right }) => {
+            (None, vec![left.name.as_str(), right.name.as_str()])
+        }
+        _ => (None, Vec::new()),
+    }
+}
+
+#[cfg(test)]
+mod describe_accumulating_regions {
+    use std::str::FromStr;
+
+    use quil_rs::Program;
+
+    use super::accumulating_regions;
+
+    fn regions(quil: &str) -> Vec<String> {
+        accumulating_regions(&Program::from_str(quil).unwrap())
+    }
+
+    #[test]
+    fn it_finds_regions_updated_before_being_written() {
+        let quil = "DECLARE count INTEGER\nDECLARE flags BIT[2]\nDECLARE ro BIT\n\
+                    MEASURE 0 ro\nADD count[0] 1\nADD count[0] 1\nNOT flags[1]";
+        assert_eq!(regions(quil), vec!["count", "flags"]);
+    }
+
+    #[test]
+    fn it_ignores_regions_written_first() {
+        let quil = "DECLARE count INTEGER\nDECLARE ro BIT\n\
+                    MOVE count[0] 0\nADD count[0] 1\nMEASURE 0 ro\nAND ro[0] 1";
+        assert!(regions(quil).is_empty());
+    }
+}
@@ -0,0 +1,185 @@
+//! Checking the parameters set with [`Executable::with_parameter`](crate::Executable::with_parameter)
+//! against the memory regions a program declares, see
+//! [`Executable::validate_parameters`](crate::Executable::validate_parameters).
+
+use quil_rs::instruction::ScalarType;
+use quil_rs::quil::Quil;
+use quil_rs::Program;
+
+use super::Parameters;
+
+/// A parameter which doesn't fit the memory regions a program declares.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Mismatch {
+    /// What doesn't fit, including the region's declaration and the offending index.
+    pub(crate) message: String,
+    /// The name of the parameter's region.
+    pub(crate) region: String,
+    /// The offending index, unless the region isn't declared.
+    pub(crate) index: Option<usize>,
+    /// The region's declared type, if it's declared.
+    pub(crate) declared_type: Option<ScalarType>,
+    /// The region's declared length, if it's declared.
+    pub(crate) declared_length: Option<u64>,
+}
+
+/// Describe the first parameter in `params`, by name, which doesn't fit the memory regions
+/// `program` declares: one whose region isn't declared, which is set at an index past the end of
+/// its region, or whose value the region's type can't hold.
+///
+/// A region may be given fewer values than its declared length; whether that's allowed depends on
+/// where the program is run.
+pub(crate) fn check(program: &Program, params: &Parameters) -> Result<(), Mismatch> {
+    let mut names: Vec<&Box<str>> = params.keys().collect();
+    names.sort();
+    for name in names {
+        let values = &params[name];
+        let region = program
+            .memory_regions
+            .get(name.as_ref())
+            .ok_or_else(|| undeclared(program, name))?;
+        let data_type = region.size.data_type;
+        let declaration = format!(
+            "DECLARE {name} {}[{}]",
+            data_type.to_quil_or_debug(),
+            region.size.length
+        );
+        let mismatch = |index: usize, message: String| Mismatch {
+            message,
+            region: name.to_string(),
+            index: Some(index),
+            declared_type: Some(data_type),
+            declared_length: Some(region.size.length),
+        };
+        if values.len() as u64 > region.size.length {
+            let index = values.len() - 1;
+            return Err(mismatch(
+                index,
+                format!(
+                    "{name}[{index}] is out of bounds of `{declaration}`, which has indices 0 to {}",
+                    region.size.length.saturating_sub(1)
+                ),
+            ));
+        }
+        if let Some((index, value)) = values
+            .iter()
+            .enumerate()
+            .find(|(_, value)| !holds(data_type, **value))
+        {
+            return Err(mismatch(
+                index,
+                format!("{name}[{index}] is set to {value}, which `{declaration}` can't hold"),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Describe the parameter `name` which `program` doesn't declare, along with the regions it does.
+fn undeclared(program: &Program, name: &str) -> Mismatch {
+    let mut declared: Vec<&String> = program.memory_regions.keys().collect();
+    declared.sort();
+    let message = if declared.is_empty() {
+        format!("{name} isn't declared, and the program declares no memory regions")
+    } else {
+        format!(
+            "{name} isn't declared; the program declares {}",
+            declared
+                .iter()
+                .map(|name| name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    };
+    Mismatch {
+        message,
+        region: name.to_string(),
+        index: None,
+        declared_type: None,
+        declared_length: None,
+    }
+}
+
+/// Whether a memory region of `data_type` can hold `value` exactly.
+fn holds(data_type: ScalarType, value: f64) -> bool {
+    let whole_within = |min: f64, max: f64| value.fract() == 0.0 && (min..=max).contains(&value);
+    match data_type {
+        ScalarType::Real => true,
+        ScalarType::Bit => whole_within(0.0, 1.0),
+        ScalarType::Octet => whole_within(f64::from(u8::MIN), f64::from(u8::MAX)),
+        // `i64::MAX` isn't representable as an `f64`, so the upper bound is the largest `f64`
+        // below 2^63.
+        ScalarType::Integer => whole_within(-(2f64.powi(63)), 2f64.powi(63) - 1024.0),
+    }
+}
+
+#[cfg(test)]
+mod describe_check {
+    use std::collections::HashMap;
+    use std::str::FromStr;
+
+    use quil_rs::instruction::ScalarType;
+    use quil_rs::Program;
+
+    use super::{check, Mismatch};
+
+    const PROGRAM: &str = "DECLARE theta REAL[2]\nDECLARE flags BIT[1]\nDECLARE count INTEGER";
+
+    fn check_one(name: &str, values: Vec<f64>) -> Result<(), String> {
+        check_one_structured(name, values).map_err(|mismatch| mismatch.message)
+    }
+
+    fn check_one_structured(name: &str, values: Vec<f64>) -> Result<(), Mismatch> {
+        let params = HashMap::from([(name.into(), values)]);
+        check(&Program::from_str(PROGRAM).unwrap(), &params)
+    }
+
+    #[test]
+    fn it_accepts_parameters_which_fit() {
+        assert_eq!(check_one("theta", vec![0.5]), Ok(()));
+        assert_eq!(check_one("theta", vec![0.5, -1.5]), Ok(()));
+        assert_eq!(check_one("flags", vec![1.0]), Ok(()));
+        assert_eq!(check_one("count", vec![-3.0]), Ok(()));
+    }
+
+    #[test]
+    fn it_reports_undeclared_regions() {
+        assert_eq!(
+            check_one("phi", vec![0.5]),
+            Err("phi isn't declared; the program declares count, flags, theta".to_string())
+        );
+        let mismatch = check_one_structured("phi", vec![0.5]).unwrap_err();
+        assert_eq!(mismatch.region, "phi");
+        assert_eq!(mismatch.index, None);
+        assert_eq!(mismatch.declared_type, None);
+        assert_eq!(mismatch.declared_length, None);
+    }
+
+    #[test]
+    fn it_reports_the_declared_size_and_the_offending_index() {
+        assert_eq!(
+            check_one("theta", vec![0.0, 0.0, 0.5]),
+            Err(
+                "theta[2] is out of bounds of `DECLARE theta REAL[2]`, which has indices 0 to 1"
+                    .to_string()
+            )
+        );
+        let mismatch = check_one_structured("theta", vec![0.0, 0.0, 0.5]).unwrap_err();
+        assert_eq!(mismatch.region, "theta");
+        assert_eq!(mismatch.index, Some(2));
+        assert_eq!(mismatch.declared_type, Some(ScalarType::Real));
+        assert_eq!(mismatch.declared_length, Some(2));
+    }
+
+    #[test]
+    fn it_reports_values_the_declared_type_cant_hold() {
+        assert_eq!(
+            check_one("flags", vec![0.5]),
+            Err("flags[0] is set to 0.5, which `DECLARE flags BIT[1]` can't hold".to_string())
+        );
+        assert_eq!(
+            check_one("count", vec![1.5]),
+            Err("count[0] is set to 1.5, which `DECLARE count INTEGER[1]` can't hold".to_string())
+        );
+    }
+}
@@ -0,0 +1,295 @@
+//! Describing what a QPU execution would do without running it, see [`ExecutionPlan`].
+
+use std::fmt;
+use std::num::NonZeroU16;
+use std::time::Duration;
+
+use quil_rs::quil::Quil;
+use quil_rs::Program;
+
+use crate::compiler::quilc::CompilerOpts;
+use crate::qpu::api::ConnectionStrategy;
+use crate::qpu::translation::{TranslationBackend, TranslationOptions};
+
+/// What running an [`Executable`](crate::Executable) on a QPU would do, stage by stage, as
+/// described by [`Executable::plan`](crate::Executable::plan).
+///
+/// Nothing is compiled, translated, or submitted to make a plan. The compiler's version and the
+/// address of the QPU are looked up, and if either lookup fails, the failure is described in the
+/// plan rather than returned, so that every problem with the configuration is shown at once.
+///
+/// The [`Display`](fmt::Display) implementation renders the plan for people to read:
+///
+/// ```text
+/// Plan for Aspen-M-3
+///   compilation: compiler 1.26.0 (timeout 30s, protoquil default, 0 qubit reselections)
+///   translation: default backend
+///   connection:  Gateway, at https://gateway.example:443 (timeout 30s)
+///   sizes:       412 bytes of Quil in 9 instructions, 1 job of 100 shots,
+///                0 patch values per job, 200 readout values in total
+/// ```
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ExecutionPlan {
+    /// The QPU the program would run on.
+    pub quantum_processor_id: String,
+    /// How the program would be compiled.
+    pub compilation: CompilationPlan,
+    /// How the program would be translated.
+    pub translation: TranslationPlan,
+    /// How jobs would be submitted and their results retrieved.
+    pub connection: ConnectionPlan,
+    /// How much would be sent to and received from the QPU.
+    pub sizes: EstimatedSizes,
+}
+
+/// How the program would be compiled, see [`ExecutionPlan`].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct CompilationPlan {
+    /// The version reported by the compiler backend, or why it couldn't be reached. `None` if the
+    /// program would be run without compiling it.
+    pub compiler_version: Option<Result<String, String>>,
+    /// The options the program would be compiled with.
+    pub options: CompilerOpts,
+    /// How many alternative qubit subsets would be tried, see
+    /// [`Executable::with_qubit_reselection`](crate::Executable::with_qubit_reselection).
+    pub max_qubit_reselections: u32,
+    /// Whether a compilation kept from an earlier run for the same QPU and shots would be reused,
+    /// rather than compiling again.
+    pub reuses_compilation: bool,
+}
+
+/// How the program would be translated, see [`ExecutionPlan`].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct TranslationPlan {
+    /// The options the program would be translated with, if any.
+    pub options: Option<TranslationOptions>,
+}
+
+impl TranslationPlan {
+    /// The name of the translation backend, or `None` if the service's default would be used.
+    #[must_use]
+    pub fn backend(&self) -> Option<&'static str> {
+        match self.options.as_ref()?.backend()? {
+            TranslationBackend::V1(_) => Some("V1"),
+            TranslationBackend::V2(_) => Some("V2"),
+        }
+    }
+}
+
+/// How jobs would be submitted and their results retrieved, see [`ExecutionPlan`].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ConnectionPlan {
+    /// The strategy used to choose the service to connect to.
+    pub strategy: ConnectionStrategy,
+    /// The address of the gRPC service the strategy resolved to, or why it couldn't be resolved.
+    pub address: Result<String, String>,
+    /// The timeout for each request, if any.
+    pub timeout: Option<Duration>,
+    /// The largest request a batch of patch values would be split into, if they are split.
+    pub max_request_bytes: Option<usize>,
+}
+
+/// How much would be sent to and received from the QPU, see [`ExecutionPlan`].
+///
+/// Sizes are estimated from the compiled program if a compilation would be reused, and from the
+/// program as given otherwise, so they may change once the program is compiled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct EstimatedSizes {
+    /// The length of the program sent for translation, in bytes.
+    pub program_bytes: usize,
+    /// The number of instructions in the body of the program.
+    pub instructions: usize,
+    /// The number of shots of each job.
+    pub shots: NonZeroU16,
+    /// The number of jobs, one per set of parameters, see
+    /// [`Executable::with_parameter_sweep`](crate::Executable::with_parameter_sweep).
+    pub jobs: usize,
+    /// The number of values patched into memory for each job.
+    pub patch_values_per_job: usize,
+    /// The number of values read out for each shot: one for each `MEASURE` into a region which is
+    /// read out, since the QPU reads out a value every time one runs.
+    pub readout_values_per_shot: usize,
+}
+
+impl EstimatedSizes {
+    /// Estimate the sizes for running `program` `jobs` times, each with `patch_values_per_job`
+    /// values and `shots` shots, reading `readout_values_per_shot` values per shot.
+    pub(crate) fn new(
+        program: &Program,
+        shots: NonZeroU16,
+        jobs: usize,
+        patch_values_per_job: usize,
+        readout_values_per_shot: usize,
+    ) -> Self {
+        Self {
+            program_bytes: program.to_quil_or_debug().len(),
+            instructions: program.body_instructions().count(),
+            shots,
+            jobs,
+            patch_values_per_job,
+            readout_values_per_shot,
+        }
+    }
+
+    /// The number of values read out across every shot of every job.
+    #[must_use]
+    pub fn readout_values(&self) -> usize {
+        self.readout_values_per_shot
+            .saturating_mul(usize::from(self.shots.get()))
+            .saturating_mul(self.jobs)
+    }
+}
+
+/// Write an optional timeout, given in seconds.
+fn write_timeout(f: &mut fmt::Formatter<'_>, seconds: Option<f64>) -> fmt::Result {
+    match seconds {
+        Some(seconds) => write!(f, "timeout {seconds}s"),
+        None => write!(f, "no timeout"),
+    }
+}
+
+impl fmt::Display for ExecutionPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Plan for {}", self.quantum_processor_id)?;
+
+        let compilation = &self.compilation;
+        write!(f, "  compilation: ")?;
+        match &compilation.compiler_version {
+            None => write!(f, "skipped")?,
+            Some(version) => {
+                match version {
+                    Ok(version) => write!(f, "compiler {version}")?,
+                    Err(error) => write!(f, "compiler unavailable: {error}")?,
+                }
+                write!(f, " (")?;
+                write_timeout(f, compilation.options.timeout())?;
+                match compilation.options.protoquil() {
+                    Some(protoquil) => write!(f, ", protoquil {protoquil}")?,
+                    None => write!(f, ", protoquil default")?,
+                }
+                write!(
+                    f,
+                    ", {} qubit reselections)",
+                    compilation.max_qubit_reselections
+                )?;
+                if compilation.reuses_compilation {
+                    write!(f, ", reusing the kept compilation")?;
+                }
+            }
+        }
+        writeln!(f)?;
+
+        match self.translation.backend() {
+            Some(backend) => writeln!(f, "  translation: {backend} backend")?,
+            None => writeln!(f, "  translation: default backend")?,
+        }
+
+        let connection = &self.connection;
+        write!(f, "  connection:  {:?}, ", connection.strategy)?;
+        match &connection.address {
+            Ok(address) => write!(f, "at {address} (")?,
+            Err(error) => write!(f, "unresolved: {error} (")?,
+        }
+        write_timeout(f, connection.timeout.map(|timeout| timeout.as_secs_f64()))?;
+        writeln!(f, ")")?;
+
+        let sizes = &self.sizes;
+        writeln!(
+            f,
+            "  sizes:       {} bytes of Quil in {} instructions, {} {} of {} shots,",
+            sizes.program_bytes,
+            sizes.instructions,
+            sizes.jobs,
+            if sizes.jobs == 1 { "job" } else { "jobs" },
+            sizes.shots,
+        )?;
+        write!(
+            f,
+            "               {} patch values per job, {} readout values in total",
+            sizes.patch_values_per_job,
+            sizes.readout_values(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod describe_execution_plan {
+    use std::num::NonZeroU16;
+    use std::str::FromStr;
+    use std::time::Duration;
+
+    use quil_rs::Program;
+
+    use super::{CompilationPlan, ConnectionPlan, EstimatedSizes, ExecutionPlan, TranslationPlan};
+    use crate::compiler::quilc::CompilerOpts;
+    use crate::qpu::api::ConnectionStrategy;
+    use crate::qpu::translation::TranslationOptions;
+
+    fn plan() -> ExecutionPlan {
+        let program =
+            Program::from_str("DECLARE ro BIT[2]\nH 0\nCNOT 0 1\nMEASURE 0 ro[0]").unwrap();
+        ExecutionPlan {
+            quantum_processor_id: "Aspen-M-3".to_string(),
+            compilation: CompilationPlan {
+                compiler_version: Some(Ok("1.26.0".to_string())),
+                options: CompilerOpts::default(),
+                max_qubit_reselections: 2,
+                reuses_compilation: false,
+            },
+            translation: TranslationPlan { options: None },
+            connection: ConnectionPlan {
+                strategy: ConnectionStrategy::Gateway,
+                address: Err("QPU not found".to_string()),
+                timeout: Some(Duration::from_secs(30)),
+                max_request_bytes: None,
+            },
+            sizes: EstimatedSizes::new(&program, NonZeroU16::new(100).unwrap(), 3, 0, 2),
+        }
+    }
+
+    #[test]
+    fn it_estimates_sizes_from_the_program() {
+        let sizes = plan().sizes;
+
+        assert_eq!(sizes.instructions, 3);
+        assert!(sizes.program_bytes > 0);
+        assert_eq!(sizes.readout_values(), 600);
+    }
+
+    #[test]
+    fn it_names_the_translation_backend() {
+        let mut options = TranslationOptions::default();
+        options.with_backend_v2();
+
+        assert_eq!(TranslationPlan { options: None }.backend(), None);
+        assert_eq!(
+            TranslationPlan {
+                options: Some(options)
+            }
+            .backend(),
+            Some("V2")
+        );
+    }
+
+    #[test]
+    fn it_renders_every_stage() {
+        let rendered = plan().to_string();
+        let lines: Vec<&str> = rendered.lines().collect();
+
+        assert_eq!(lines[0], "Plan for Aspen-M-3");
+        assert!(lines[1].starts_with("  compilation: compiler 1.26.0 ("));
+        assert!(lines[1].ends_with(", 2 qubit reselections)"));
+        assert_eq!(lines[2], "  translation: default backend");
+        assert_eq!(
+            lines[3],
+            "  connection:  Gateway, unresolved: QPU not found (timeout 30s)"
+        );
+        assert!(lines[4].ends_with("3 jobs of 100 shots,"));
+        assert!(lines[5].ends_with("0 patch values per job, 600 readout values in total"));
+    }
+}
@@ -0,0 +1,176 @@
+//! Rewriting programs to apply [`Executable::with_active_reset`](crate::Executable::with_active_reset)
+//! and [`Executable::with_initial_rewiring`](crate::Executable::with_initial_rewiring), so that
+//! the QVM and QPUs run the same program.
+
+use std::fmt;
+
+use quil_rs::instruction::{Instruction, Pragma, Reset};
+use quil_rs::Program;
+
+/// The name of the `PRAGMA` which sets quilc's [`RewiringStrategy`].
+pub(crate) const INITIAL_REWIRING: &str = "INITIAL_REWIRING";
+
+/// How quilc chooses the physical qubits to place a program's qubits on before compiling it, set
+/// with `PRAGMA INITIAL_REWIRING`. See [`Executable::with_initial_rewiring`](crate::Executable::with_initial_rewiring).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum RewiringStrategy {
+    /// Use the qubits the program names, without moving any of them.
+    Naive,
+    /// Move qubits to where they can be connected, starting from the qubits the program names.
+    Partial,
+    /// Place qubits which interact closely together, without regard to the qubits the program
+    /// names.
+    Greedy,
+    /// Place qubits at random.
+    Random,
+}
+
+impl RewiringStrategy {
+    /// The name of the strategy, as given to `PRAGMA INITIAL_REWIRING`.
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Naive => "NAIVE",
+            Self::Partial => "PARTIAL",
+            Self::Greedy => "GREEDY",
+            Self::Random => "RANDOM",
+        }
+    }
+}
+
+impl fmt::Display for RewiringStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Whether `instruction` resets every qubit, as opposed to a single one.
+fn is_global_reset(instruction: &Instruction) -> bool {
+    matches!(instruction, Instruction::Reset(Reset { qubit: None }))
+}
+
+/// Whether `instruction` is a `PRAGMA INITIAL_REWIRING`.
+fn is_initial_rewiring(instruction: &Instruction) -> bool {
+    matches!(instruction, Instruction::Pragma(pragma) if pragma.name == INITIAL_REWIRING)
+}
+
+/// Return a copy of `program` which starts with a `RESET` if `active_reset` is `Some(true)`, or
+/// without the `RESET`s it starts with if `Some(false)`, and with its `PRAGMA INITIAL_REWIRING`
+/// replaced by one for `initial_rewiring`, if given.
+///
+/// The start of a program is every `PRAGMA` and `RESET` of all qubits before its first other
+/// instruction. A `RESET` later in the program, or of a single qubit, is left as it is.
+pub(crate) fn apply(
+    program: &Program,
+    active_reset: Option<bool>,
+    initial_rewiring: Option<RewiringStrategy>,
+) -> Program {
+    let mut body: Vec<Instruction> = program
+        .body_instructions()
+        .filter(|instruction| initial_rewiring.is_none() || !is_initial_rewiring(instruction))
+        .cloned()
+        .collect();
+    let preamble = body
+        .iter()
+        .take_while(|instruction| {
+            matches!(instruction, Instruction::Pragma(_)) || is_global_reset(instruction)
+        })
+        .count();
+
+    match active_reset {
+        Some(true) => {
+            if !body[..preamble].iter().any(is_global_reset) {
+                body.insert(preamble, Instruction::Reset(Reset::new(None)));
+            }
+        }
+        Some(false) => {
+            let mut index = 0;
+            body.retain(|instruction| {
+                index += 1;
+                index > preamble || !is_global_reset(instruction)
+            });
+        }
+        None => {}
+    }
+
+    if let Some(strategy) = initial_rewiring {
+        body.insert(
+            0,
+            Instruction::Pragma(Pragma::new(
+                INITIAL_REWIRING.to_string(),
+                Vec::new(),
+                Some(strategy.as_str().to_string()),
+            )),
+        );
+    }
+
+    let mut new_program = program.clone_without_body_instructions();
+    new_program.add_instructions(body);
+    new_program
+}
+
+#[cfg(test)]
+mod describe_apply {
+    use std::str::FromStr;
+
+    use quil_rs::quil::Quil;
+    use quil_rs::Program;
+
+    use super::{apply, RewiringStrategy};
+
+    fn rewrite(
+        quil: &str,
+        active_reset: Option<bool>,
+        rewiring: Option<RewiringStrategy>,
+    ) -> String {
+        apply(&Program::from_str(quil).unwrap(), active_reset, rewiring)
+            .to_quil()
+            .unwrap()
+    }
+
+    #[test]
+    fn it_adds_a_reset_after_leading_pragmas() {
+        assert_eq!(
+            rewrite("PRAGMA PRESERVE_BLOCK\nX 0\n", Some(true), None),
+            "PRAGMA PRESERVE_BLOCK\nRESET\nX 0\n"
+        );
+    }
+
+    #[test]
+    fn it_keeps_an_existing_reset() {
+        assert_eq!(rewrite("RESET\nX 0\n", Some(true), None), "RESET\nX 0\n");
+    }
+
+    #[test]
+    fn it_removes_only_leading_resets_of_all_qubits() {
+        assert_eq!(
+            rewrite("RESET\nRESET 1\nX 0\nRESET\n", Some(false), None),
+            "RESET 1\nX 0\nRESET\n"
+        );
+    }
+
+    #[test]
+    fn it_replaces_the_initial_rewiring() {
+        assert_eq!(
+            rewrite(
+                "PRAGMA INITIAL_REWIRING \"NAIVE\"\nX 0\n",
+                Some(true),
+                Some(RewiringStrategy::Greedy)
+            ),
+            "PRAGMA INITIAL_REWIRING \"GREEDY\"\nRESET\nX 0\n"
+        );
+    }
+
+    #[test]
+    fn it_leaves_the_program_alone_by_default() {
+        assert_eq!(
+            rewrite(
+                "PRAGMA INITIAL_REWIRING \"PARTIAL\"\nRESET\nX 0\n",
+                None,
+                None
+            ),
+            "PRAGMA INITIAL_REWIRING \"PARTIAL\"\nRESET\nX 0\n"
+        );
+    }
+}
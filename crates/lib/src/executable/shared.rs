@@ -0,0 +1,182 @@
+//! An [`Executable`] which can be used by many tasks at once, sharing its cached compilations.
+
+#[cfg(feature = "qpu")]
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use tokio::sync::Mutex;
+
+#[cfg(feature = "qpu")]
+use crate::qpu::{self, api::ExecutionOptions, translation::TranslationOptions};
+use crate::qvm;
+
+#[cfg(feature = "qpu")]
+use super::{Error, JobHandle};
+use super::{Executable, ExecutionResult};
+
+/// An [`Executable`] which can be cloned and used from many tasks at once, while sharing the
+/// compiled and translated program between all of them.
+///
+/// Cloning an [`Executable`] copies its cached compilation, and any work done by one clone (for
+/// instance compiling for a different QPU) is invisible to the others. Every clone of a
+/// [`SharedExecutable`] shares the same caches instead.
+///
+/// # Contention
+///
+/// Compiling, translating, and submitting a program for a QPU all read or update the shared QPU
+/// cache, so they hold its lock and run one task at a time. Waiting for and retrieving results
+/// doesn't need the lock, so any number of tasks can wait for their jobs at once. Runs on a QVM
+/// only hold a lock while the program is parsed for the first time.
+///
+/// Settings such as shots and parameters are fixed when the [`SharedExecutable`] is created. Create
+/// another one to use different settings.
+#[derive(Clone)]
+#[allow(missing_debug_implementations)]
+pub struct SharedExecutable {
+    executable: Arc<Executable<'static, 'static>>,
+    #[cfg(feature = "qpu")]
+    qpu: Arc<Mutex<Option<qpu::Execution<'static>>>>,
+    qvm: Arc<Mutex<Option<qvm::Execution>>>,
+}
+
+impl From<Executable<'static, 'static>> for SharedExecutable {
+    fn from(mut executable: Executable<'static, 'static>) -> Self {
+        #[cfg(feature = "qpu")]
+        let qpu = executable.qpu.take();
+        let qvm = executable.qvm.take();
+        Self {
+            executable: Arc::new(executable),
+            #[cfg(feature = "qpu")]
+            qpu: Arc::new(Mutex::new(qpu)),
+            qvm: Arc::new(Mutex::new(qvm)),
+        }
+    }
+}
+
+impl Executable<'static, 'static> {
+    /// Convert into a [`SharedExecutable`], which can be used from many tasks at once.
+    #[must_use]
+    pub fn into_shared(self) -> SharedExecutable {
+        self.into()
+    }
+}
+
+impl SharedExecutable {
+    /// A copy of the underlying [`Executable`], without any of the shared caches.
+    fn executable(&self) -> Executable<'static, 'static> {
+        Executable::clone(&self.executable)
+    }
+
+    /// Execute on a QVM. See [`Executable::execute_on_qvm`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Executable::execute_on_qvm`].
+    pub async fn execute_on_qvm<V: qvm::Client + ?Sized>(&self, client: &V) -> ExecutionResult {
+        let mut executable = self.executable();
+        {
+            let mut qvm = self.qvm.lock().await;
+            if qvm.is_none() {
+                *qvm = Some(executable.take_qvm_execution()?);
+            }
+            executable.qvm.clone_from(&*qvm);
+        }
+        executable.execute_on_qvm(client).await
+    }
+
+    /// Compile the program and execute it on a QPU, waiting for results. See
+    /// [`Executable::execute_on_qpu`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Executable::execute_on_qpu`].
+    #[cfg(feature = "qpu")]
+    pub async fn execute_on_qpu<S>(
+        &self,
+        quantum_processor_id: S,
+        translation_options: Option<TranslationOptions>,
+        execution_options: &ExecutionOptions,
+    ) -> ExecutionResult
+    where
+        S: Into<Cow<'static, str>>,
+    {
+        let job_handle = self
+            .submit_to_qpu(quantum_processor_id, translation_options, execution_options)
+            .await?;
+        self.retrieve_results(job_handle).await
+    }
+
+    /// Compile and submit the program to a QPU, but do not wait for execution to complete. See
+    /// [`Executable::submit_to_qpu`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Executable::execute_on_qpu`].
+    #[cfg(feature = "qpu")]
+    pub async fn submit_to_qpu<S>(
+        &self,
+        quantum_processor_id: S,
+        translation_options: Option<TranslationOptions>,
+        execution_options: &ExecutionOptions,
+    ) -> Result<JobHandle<'static>, Error>
+    where
+        S: Into<Cow<'static, str>>,
+    {
+        let mut executable = self.executable();
+        let mut qpu = self.qpu.lock().await;
+        executable.qpu = qpu.take();
+        let result = executable
+            .submit_to_qpu(quantum_processor_id, translation_options, execution_options)
+            .await;
+        // Keep whatever the submission compiled or translated, even if submitting failed.
+        *qpu = executable.qpu.take();
+        result
+    }
+
+    /// Wait for the results of a job submitted via [`SharedExecutable::submit_to_qpu`] to
+    /// complete. See [`Executable::retrieve_results`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Executable::execute_on_qpu`].
+    #[cfg(feature = "qpu")]
+    pub async fn retrieve_results(&self, job_handle: JobHandle<'static>) -> ExecutionResult {
+        let mut executable = self.executable();
+        executable.qpu.clone_from(&*self.qpu.lock().await);
+        executable.retrieve_results(job_handle).await
+    }
+}
+
+#[cfg(test)]
+mod describe_shared_executable {
+    use super::SharedExecutable;
+    use crate::{test_support::RecordingQvm, Executable};
+
+    #[test]
+    fn it_can_be_sent_between_tasks() {
+        fn assert_send_sync<T: Send + Sync + 'static>() {}
+        assert_send_sync::<SharedExecutable>();
+    }
+
+    #[tokio::test]
+    async fn it_runs_concurrently_on_the_qvm() {
+        let qvm = std::sync::Arc::new(RecordingQvm::default());
+        let shared = Executable::from_quil("DECLARE ro BIT\nX 0\nMEASURE 0 ro").into_shared();
+
+        let tasks: Vec<_> = (0..8)
+            .map(|_| {
+                let shared = shared.clone();
+                let qvm = qvm.clone();
+                tokio::spawn(async move { shared.execute_on_qvm(qvm.as_ref()).await })
+            })
+            .collect();
+        for task in tasks {
+            let data = task.await.unwrap().expect("should run on the QVM");
+            let ro = data.result_data.to_register_map().unwrap();
+            assert!(ro.get_register_matrix("ro").is_some());
+        }
+
+        assert_eq!(qvm.programs.lock().unwrap().len(), 8);
+        assert!(shared.qvm.lock().await.is_some());
+    }
+}
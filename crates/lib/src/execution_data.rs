@@ -1,6 +1,8 @@
 use enum_as_inner::EnumAsInner;
 use num::complex::Complex64;
+use quil_rs::instruction::ScalarType;
 use quil_rs::program::SyntaxError;
+use quil_rs::quil::Quil;
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, HashMap};
 use std::convert::TryFrom;
@@ -11,13 +13,25 @@ use std::time::Duration;
 use itertools::Itertools;
 use ndarray::prelude::*;
 
+pub mod export;
+pub mod interop;
+pub mod report;
+pub mod schema;
+pub mod spill;
+
 use crate::{
-    qpu::{QpuResultData, ReadoutValues},
-    qvm::QvmResultData,
+    bit_order::BitOrder,
+    qpu::{
+        mitigation::{self, ConfusionMatrix},
+        result_data::{whole_number, MemoryValues},
+        QpuResultData, RawQpuResult, ReadoutValues,
+    },
+    qvm::{QvmResultData, Wavefunction},
     RegisterData,
 };
+use spill::SpilledResultData;
 
-/// Represents the two possible types of data returned from either the QVM or a real QPU.
+/// Represents the possible types of data returned from either the QVM or a real QPU.
 /// Each variant contains the original data returned from its respective executor.
 ///
 /// # Usage
@@ -54,6 +68,30 @@ pub enum ResultData {
     Qvm(QvmResultData),
     /// Readout data returned from the QPU, stored as [`QpuResultData`]
     Qpu(QpuResultData),
+    /// The wavefunction of a program simulated by the QVM, stored as a [`Wavefunction`]
+    Wavefunction(Wavefunction),
+    /// Readout data returned from the QPU which was too large to keep in memory, decoded into
+    /// temporary files as [`SpilledResultData`]. See [`spill`].
+    ///
+    /// This variant can't be serialized with `serde`; use [`ResultData::to_json`] instead.
+    #[serde(skip)]
+    Spilled(SpilledResultData),
+}
+
+/// A borrowed view of the data in a [`ResultData`], exactly as it was returned by the executor.
+///
+/// See [`ResultData::as_raw`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RawResultData<'a> {
+    /// The final contents of each memory region on every shot, keyed on region name.
+    Qvm(&'a HashMap<String, RegisterData>),
+    /// The readout and memory values returned by the QPU.
+    Qpu(RawQpuResult<'a>),
+    /// The amplitudes of a wavefunction, as described in [`Wavefunction`].
+    Wavefunction(&'a [Complex64]),
+    /// Readout values which were decoded into temporary files, as described in
+    /// [`SpilledResultData`].
+    Spilled(&'a SpilledResultData),
 }
 
 /// The result of executing an [`Executable`](crate::Executable)
@@ -66,6 +104,52 @@ pub struct ExecutionData {
     ///
     /// This will always be `None` for QVM execution.
     pub duration: Option<Duration>,
+    /// How long a QPU job took to return its results, to execute, and to decode, or `None` if
+    /// the program was run on the QVM. See [`ExecutionTimings`].
+    ///
+    /// These are kept when the results are pickled or serialized with `serde`, but not by
+    /// [`ExecutionData::to_json`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timings: Option<ExecutionTimings>,
+}
+
+/// How long a QPU job spent in each stage after it was submitted, for tracking a QPU's
+/// utilization. See [`ExecutionData::timings`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct ExecutionTimings {
+    /// How long the program ran on the QPU, as reported by the controller. This is the same as
+    /// [`ExecutionData::duration`], and is what on-demand execution is billed for.
+    pub execution: Option<Duration>,
+    /// The time from the job's submission until its results were received, as measured by this
+    /// client. Besides the job's execution and any time it spent in the QPU's queue, this includes
+    /// network latency and however long the caller waited before retrieving the results, so it
+    /// isn't a measure of the queue. It is `None` if the time the job was submitted isn't known,
+    /// such as for a [`JobHandle`](crate::JobHandle) deserialized from an older version of this
+    /// crate.
+    pub time_to_results: Option<Duration>,
+    /// How long it took to decode the results once they were returned.
+    pub post_processing: Option<Duration>,
+}
+
+impl ExecutionTimings {
+    /// The timings of the jobs which together ran all of a program's shots, `self` and then
+    /// `later`: the total time spent executing and decoding, and the longest time to results.
+    #[must_use]
+    pub(crate) fn then(self, later: Self) -> Self {
+        fn combine(
+            first: Option<Duration>,
+            second: Option<Duration>,
+            f: fn(Duration, Duration) -> Duration,
+        ) -> Option<Duration> {
+            first.zip(second).map(|(first, second)| f(first, second))
+        }
+        Self {
+            execution: combine(self.execution, later.execution, |a, b| a + b),
+            time_to_results: combine(self.time_to_results, later.time_to_results, Duration::max),
+            post_processing: combine(self.post_processing, later.post_processing, |a, b| a + b),
+        }
+    }
 }
 
 /// An enum representing every possible register type as a 2 dimensional matrix.
@@ -79,12 +163,220 @@ pub enum RegisterMatrix {
     Complex(Array2<Complex64>),
 }
 
+impl RegisterMatrix {
+    /// The [`Shape`] of the matrix.
+    #[must_use]
+    pub fn shape(&self) -> Shape {
+        let ((rows, cols), dtype) = match self {
+            Self::Integer(matrix) => (matrix.dim(), DType::Int64),
+            Self::Real(matrix) => (matrix.dim(), DType::Float64),
+            Self::Complex(matrix) => (matrix.dim(), DType::Complex128),
+        };
+        Shape { rows, cols, dtype }
+    }
+
+    /// Borrow the matrix as a [`RegisterMatrixView`], without copying its values.
+    #[must_use]
+    pub fn as_array_view(&self) -> RegisterMatrixView<'_> {
+        match self {
+            Self::Integer(matrix) => RegisterMatrixView::Integer(matrix.view()),
+            Self::Real(matrix) => RegisterMatrixView::Real(matrix.view()),
+            Self::Complex(matrix) => RegisterMatrixView::Complex(matrix.view()),
+        }
+    }
+}
+
+/// A borrowed [`RegisterMatrix`], as returned by [`RegisterMatrix::as_array_view`].
+#[derive(Clone, Copy, Debug, EnumAsInner, PartialEq)]
+pub enum RegisterMatrixView<'a> {
+    /// Integer register
+    Integer(ArrayView2<'a, i64>),
+    /// Real numbered register
+    Real(ArrayView2<'a, f64>),
+    /// Complex numbered register
+    Complex(ArrayView2<'a, Complex64>),
+}
+
+impl RegisterMatrixView<'_> {
+    /// The [`Shape`] of the matrix.
+    #[must_use]
+    pub fn shape(&self) -> Shape {
+        let ((rows, cols), dtype) = match self {
+            Self::Integer(matrix) => (matrix.dim(), DType::Int64),
+            Self::Real(matrix) => (matrix.dim(), DType::Float64),
+            Self::Complex(matrix) => (matrix.dim(), DType::Complex128),
+        };
+        Shape { rows, cols, dtype }
+    }
+
+    /// Copy the viewed values into a [`RegisterMatrix`].
+    #[must_use]
+    pub fn to_register_matrix(&self) -> RegisterMatrix {
+        match self {
+            Self::Integer(matrix) => RegisterMatrix::Integer(matrix.to_owned()),
+            Self::Real(matrix) => RegisterMatrix::Real(matrix.to_owned()),
+            Self::Complex(matrix) => RegisterMatrix::Complex(matrix.to_owned()),
+        }
+    }
+}
+
+/// The type of each value in a [`RegisterMatrix`] or [`ReadoutValues`](crate::qpu::result_data::ReadoutValues),
+/// named as in NumPy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum DType {
+    /// 64 bit signed integers
+    Int64,
+    /// 64 bit floating point numbers
+    Float64,
+    /// Complex numbers made of two 64 bit floating point numbers
+    Complex128,
+}
+
+impl DType {
+    /// The NumPy name of the type, such as `"int64"`.
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Int64 => "int64",
+            Self::Float64 => "float64",
+            Self::Complex128 => "complex128",
+        }
+    }
+}
+
+impl std::fmt::Display for DType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The dimensions and type of a block of result data, known without converting or copying it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Shape {
+    /// The number of rows. A [`RegisterMatrix`] has a row for each shot, while
+    /// [`ReadoutValues`] have a row for each value read out, which is one per shot only if the
+    /// readout node was measured exactly once per shot.
+    pub rows: usize,
+    /// The number of columns, such as the number of values in a register for each shot.
+    pub cols: usize,
+    /// The type of each value.
+    pub dtype: DType,
+}
+
 /// A mapping of a register name (ie. "ro") to a [`RegisterMatrix`] containing the values for the
 /// register.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[repr(transparent)]
 pub struct RegisterMap(pub HashMap<String, RegisterMatrix>);
 
+/// The values read out to each index of a register, keyed on index, as built by
+/// [`ResultData::to_sparse_register_map`].
+///
+/// Unlike a [`RegisterMatrix`], indices which weren't read out are simply absent, so a register
+/// can be represented even if a program only measures a few of the indices it `DECLARE`s. Each
+/// index has every value read out to it, in order, which is one value per shot only if it was
+/// measured exactly once per shot.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[repr(transparent)]
+pub struct SparseRegister(pub BTreeMap<usize, ReadoutValues>);
+
+impl SparseRegister {
+    /// The values read out to `index`, if it was read out.
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<&ReadoutValues> {
+        self.0.get(&index)
+    }
+
+    /// The indices which were read out, in ascending order.
+    pub fn indices(&self) -> impl Iterator<Item = usize> + '_ {
+        self.0.keys().copied()
+    }
+
+    /// Whether every index from 0 up to the highest index read out was read out, so that the
+    /// register could also be represented as a [`RegisterMatrix`].
+    #[must_use]
+    pub fn is_contiguous(&self) -> bool {
+        self.indices().eq(0..self.0.len())
+    }
+}
+
+/// A mapping of a register name (ie. "ro") to a [`SparseRegister`] containing the values read out
+/// to each of its indices. See [`ResultData::to_sparse_register_map`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[repr(transparent)]
+pub struct SparseRegisterMap(pub HashMap<String, SparseRegister>);
+
+impl SparseRegisterMap {
+    /// Returns the [`SparseRegister`] for the given register, if it exists.
+    #[must_use]
+    pub fn get_register(&self, register_name: &str) -> Option<&SparseRegister> {
+        self.0.get(register_name)
+    }
+
+    /// Builds a [`SparseRegisterMap`] from [`QpuResultData`], with an entry for each memory
+    /// reference which has a readout mapping.
+    pub(crate) fn from_qpu_result_data(
+        qpu_result_data: &QpuResultData,
+    ) -> Result<Self, RegisterMatrixConversionError> {
+        #[cfg(feature = "tracing")]
+        tracing::trace!("converting QPU result data to SparseRegisterMap");
+
+        let references =
+            paired_readout_references(&qpu_result_data.mappings, &qpu_result_data.readout_values)?;
+        let mut registers: HashMap<String, SparseRegister> = HashMap::new();
+        for (reference, values) in references {
+            let values = match qpu_result_data.declared_type(&reference.name) {
+                Some(data_type) => {
+                    readout_values_with_declared_type(&reference.name, values.clone(), data_type)?
+                }
+                None => values.clone(),
+            };
+            registers
+                .entry(reference.name)
+                .or_default()
+                .0
+                .insert(reference.index, values);
+        }
+        Ok(Self(registers))
+    }
+}
+
+impl From<&RegisterMap> for SparseRegisterMap {
+    /// Split each [`RegisterMatrix`] into its columns, one for each index of the register.
+    fn from(register_map: &RegisterMap) -> Self {
+        Self(
+            register_map
+                .0
+                .iter()
+                .map(|(name, matrix)| {
+                    let columns = match matrix {
+                        RegisterMatrix::Integer(matrix) => matrix
+                            .columns()
+                            .into_iter()
+                            .map(|column| ReadoutValues::Integer(column.to_vec()))
+                            .enumerate()
+                            .collect(),
+                        RegisterMatrix::Real(matrix) => matrix
+                            .columns()
+                            .into_iter()
+                            .map(|column| ReadoutValues::Real(column.to_vec()))
+                            .enumerate()
+                            .collect(),
+                        RegisterMatrix::Complex(matrix) => matrix
+                            .columns()
+                            .into_iter()
+                            .map(|column| ReadoutValues::Complex(column.to_vec()))
+                            .enumerate()
+                            .collect(),
+                    };
+                    (name.clone(), SparseRegister(columns))
+                })
+                .collect(),
+        )
+    }
+}
+
 /// Errors that may occur when trying to build a [`RegisterMatrix`] from execution data
 #[allow(missing_docs)]
 #[derive(Debug, thiserror::Error)]
@@ -100,13 +392,26 @@ pub enum RegisterMatrixConversionError {
         alias: String,
     },
 
-    /// A row of readout values for a register were missing
+    /// A row of readout values for a register were missing. See
+    /// [`ResultData::to_sparse_register_map`] for registers which are only partly read out.
     #[error("Missing readout values for {register}[{index}]")]
     MissingRow { register: String, index: usize },
 
     /// The memory reference could not be parsed
     #[error("{0}")]
     MemoryReferenceParseError(MemoryReferenceParseError),
+
+    /// The data is a wavefunction, which has no registers
+    #[error("A wavefunction has no registers to convert into a register map")]
+    Wavefunction,
+
+    /// The values read out to a register can't be represented as its declared type
+    #[error("The values read out to {register} can't be represented as {data_type}")]
+    TypeMismatch { register: String, data_type: String },
+
+    /// The values of a register which was [spilled](spill) to disk couldn't be read back
+    #[error("The values of a spilled register couldn't be read back: {0}")]
+    SpillRead(String),
 }
 
 impl ResultData {
@@ -125,10 +430,130 @@ impl ResultData {
     /// necessitate making assumptions about the data that could skew the data in undesirable ways.
     /// Instead, it's recommended to manually build a matrix from [`QpuResultData`] that accurately
     /// selects the last value per-shot based on the program that was run.
+    ///
+    /// If the declared type of a register is known (see [`QpuResultData::with_declarations`]), its
+    /// [`RegisterMatrix`] matches that type: `REAL` registers are [`RegisterMatrix::Real`] even if
+    /// integers were read out to them, and other registers are [`RegisterMatrix::Integer`].
+    /// Complex values, such as those of raw captures, are kept as they are.
+    ///
+    /// Always returns [`RegisterMatrixConversionError::Wavefunction`] for
+    /// [`ResultData::Wavefunction`]. For [`ResultData::Spilled`], every register is read back into
+    /// memory, failing with [`RegisterMatrixConversionError::SpillRead`] if one can't be. Prefer
+    /// reading blocks of rows from [`SpilledResultData::register_map`] for those.
     pub fn to_register_map(&self) -> Result<RegisterMap, RegisterMatrixConversionError> {
         match self {
             ResultData::Qvm(data) => RegisterMap::from_qvm_result_data(data),
             ResultData::Qpu(data) => RegisterMap::from_qpu_result_data(data),
+            ResultData::Wavefunction(_) => Err(RegisterMatrixConversionError::Wavefunction),
+            ResultData::Spilled(data) => data
+                .register_map()
+                .to_register_map()
+                .map_err(|error| RegisterMatrixConversionError::SpillRead(error.to_string())),
+        }
+    }
+
+    /// As [`ResultData::to_register_map`], but consumes the data so that, for
+    /// [`ResultData::Qpu`], each [`RegisterMatrix`] can take ownership of the values read out to
+    /// it instead of copying them. See [`QpuResultData::into_register_map`].
+    ///
+    /// # Errors
+    ///
+    /// Fails as [`ResultData::to_register_map`] does.
+    pub fn into_register_map(self) -> Result<RegisterMap, RegisterMatrixConversionError> {
+        match self {
+            ResultData::Qpu(data) => data.into_register_map(),
+            data => data.to_register_map(),
+        }
+    }
+
+    /// Convert [`ResultData`] into a [`SparseRegisterMap`], with the values read out to each index
+    /// of each register.
+    ///
+    /// Unlike [`ResultData::to_register_map`], this doesn't fail if some indices of a register
+    /// weren't read out, as when a program measures only a few qubits into a large `DECLARE`d
+    /// region. Those indices are left out of its [`SparseRegister`] instead. Nor does it require
+    /// every index to have the same number of values. As with [`ResultData::to_register_map`],
+    /// the values of a register are converted to its declared type, if known.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`RegisterMatrixConversionError`] if a memory reference can't be parsed, or if
+    /// its readout has no values. Always returns [`RegisterMatrixConversionError::Wavefunction`]
+    /// for [`ResultData::Wavefunction`].
+    pub fn to_sparse_register_map(
+        &self,
+    ) -> Result<SparseRegisterMap, RegisterMatrixConversionError> {
+        match self {
+            ResultData::Qvm(data) => {
+                RegisterMap::from_qvm_result_data(data).map(|map| SparseRegisterMap::from(&map))
+            }
+            ResultData::Qpu(data) => SparseRegisterMap::from_qpu_result_data(data),
+            ResultData::Wavefunction(_) => Err(RegisterMatrixConversionError::Wavefunction),
+            ResultData::Spilled(data) => data
+                .register_map()
+                .to_register_map()
+                .map(|map| SparseRegisterMap::from(&map))
+                .map_err(|error| RegisterMatrixConversionError::SpillRead(error.to_string())),
+        }
+    }
+
+    /// Borrow the data as it was returned by the executor, without building a [`RegisterMap`]
+    /// or any other intermediate structure.
+    ///
+    /// This is the low-level escape hatch for custom processing of results, such as when the
+    /// program's readout doesn't fit a rectangular [`RegisterMatrix`].
+    #[must_use]
+    pub fn as_raw(&self) -> RawResultData<'_> {
+        match self {
+            ResultData::Qvm(data) => RawResultData::Qvm(data.memory()),
+            ResultData::Qpu(data) => RawResultData::Qpu(data.as_raw()),
+            ResultData::Wavefunction(wavefunction) => {
+                RawResultData::Wavefunction(wavefunction.amplitudes())
+            }
+            ResultData::Spilled(data) => RawResultData::Spilled(data),
+        }
+    }
+}
+
+impl ExecutionData {
+    /// Borrow the readout and memory values returned by the QPU without any conversion, or `None`
+    /// if the program was run on the QVM.
+    ///
+    /// See [`ResultData::as_raw`].
+    #[must_use]
+    pub fn raw_qpu_result(&self) -> Option<RawQpuResult<'_>> {
+        match self.result_data.as_raw() {
+            RawResultData::Qpu(raw) => Some(raw),
+            RawResultData::Qvm(_) | RawResultData::Wavefunction(_) | RawResultData::Spilled(_) => {
+                None
+            }
+        }
+    }
+
+    /// The readout node (ie. "q0") of the QPU's filter pipeline which produced the values read
+    /// out to each memory reference (ie. "ro\[0\]"), or `None` if the program was run on the QVM.
+    ///
+    /// Use these to apply custom readout mitigation to the values of each node. See
+    /// [`QpuResultData::mappings`].
+    #[must_use]
+    pub fn readout_mappings(&self) -> Option<&HashMap<String, String>> {
+        match &self.result_data {
+            ResultData::Qpu(data) => Some(data.mappings()),
+            ResultData::Spilled(data) => Some(data.mappings()),
+            ResultData::Qvm(_) | ResultData::Wavefunction(_) => None,
+        }
+    }
+
+    /// The final contents of the memory region `name` returned by the QPU, or `None` if the
+    /// program was run on the QVM or the QPU didn't return the region.
+    ///
+    /// See [`QpuResultData::memory_region`].
+    #[must_use]
+    pub fn memory_region(&self, name: &str) -> Option<&MemoryValues> {
+        match &self.result_data {
+            ResultData::Qpu(data) => data.memory_region(name),
+            ResultData::Spilled(data) => data.memory_region(name),
+            ResultData::Qvm(_) | ResultData::Wavefunction(_) => None,
         }
     }
 }
@@ -140,6 +565,103 @@ impl RegisterMap {
         self.0.get(register_name)
     }
 
+    /// The [`Shape`] of each register, by name.
+    #[must_use]
+    pub fn shapes(&self) -> HashMap<String, Shape> {
+        self.0
+            .iter()
+            .map(|(name, matrix)| (name.clone(), matrix.shape()))
+            .collect()
+    }
+
+    /// The bitstrings read out to `register`, written in `bit_order`, with the number of shots
+    /// which produced each of them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no such register, or if it isn't made up of bits. See
+    /// [`RegisterCounts::from_register_matrix`](interop::RegisterCounts::from_register_matrix).
+    pub fn histogram(
+        &self,
+        register: &str,
+        bit_order: BitOrder,
+    ) -> Result<BTreeMap<String, u64>, interop::Error> {
+        self.register_counts(register, bit_order)
+            .map(|counts| counts.counts)
+    }
+
+    /// The fraction of shots which produced each bitstring read out to `register`, written in
+    /// `bit_order`. The fractions sum to 1, unless there were no shots.
+    ///
+    /// # Errors
+    ///
+    /// See [`RegisterMap::histogram`].
+    // Shot counts are far too small to lose precision as floats.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn probabilities(
+        &self,
+        register: &str,
+        bit_order: BitOrder,
+    ) -> Result<BTreeMap<String, f64>, interop::Error> {
+        let counts = self.register_counts(register, bit_order)?;
+        let shots = counts.memory.len() as f64;
+        Ok(counts
+            .counts
+            .into_iter()
+            .map(|(bitstring, count)| (bitstring, count as f64 / shots))
+            .collect())
+    }
+
+    /// The probabilities of the bitstrings read out to `register`, as
+    /// [`RegisterMap::probabilities`], corrected for readout errors with `confusion_matrix`.
+    ///
+    /// The `i`th bit of `register` must have been read out from the `i`th qubit of the confusion
+    /// matrix. See [`mitigation`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the register can't be counted, as for [`RegisterMap::histogram`], or
+    /// can't be corrected. See [`ConfusionMatrix::correct`].
+    pub fn apply_readout_mitigation(
+        &self,
+        register: &str,
+        confusion_matrix: &ConfusionMatrix,
+        bit_order: BitOrder,
+    ) -> Result<BTreeMap<String, f64>, mitigation::Error> {
+        let probabilities = self.probabilities(register, bit_order)?;
+        confusion_matrix.correct(&probabilities, bit_order)
+    }
+
+    /// The parity of the bits read out to `register` on each shot: `true` if an odd number of
+    /// them were 1.
+    ///
+    /// # Errors
+    ///
+    /// See [`RegisterMap::histogram`].
+    pub fn parity(&self, register: &str) -> Result<Vec<bool>, interop::Error> {
+        self.register_counts(register, BitOrder::default())
+            .map(|counts| {
+                counts
+                    .memory
+                    .iter()
+                    .map(|bitstring| bitstring.matches('1').count() % 2 == 1)
+                    .collect()
+            })
+    }
+
+    fn register_counts(
+        &self,
+        register: &str,
+        bit_order: BitOrder,
+    ) -> Result<interop::RegisterCounts, interop::Error> {
+        let matrix =
+            self.get_register_matrix(register)
+                .ok_or_else(|| interop::Error::MissingRegister {
+                    register: register.to_string(),
+                })?;
+        interop::RegisterCounts::from_register_matrix(register, matrix, bit_order)
+    }
+
     /// Returns a [`RegisterMap`] with the underlying [`RegisterMatrix`] data
     #[must_use]
     pub fn from_hashmap(map: HashMap<String, RegisterMatrix>) -> Self {
@@ -183,6 +705,26 @@ impl RegisterMap {
                                 .collect(),
                         )
                         .map(RegisterMatrix::Complex),
+                        RegisterData::I32(data) => Array::from_shape_vec(
+                            (data.len(), data.first().map_or(0, Vec::len)),
+                            data.iter().flatten().copied().map(i64::from).collect(),
+                        )
+                        .map(RegisterMatrix::Integer),
+                        RegisterData::F32(data) => Array::from_shape_vec(
+                            (data.len(), data.first().map_or(0, Vec::len)),
+                            data.iter().flatten().copied().map(f64::from).collect(),
+                        )
+                        .map(RegisterMatrix::Real),
+                        RegisterData::Complex64(data) => Array::from_shape_vec(
+                            (data.len(), data.first().map_or(0, Vec::len)),
+                            data.iter().flatten().copied().collect(),
+                        )
+                        .map(RegisterMatrix::Complex),
+                        RegisterData::I64(data) => Array::from_shape_vec(
+                            (data.len(), data.first().map_or(0, Vec::len)),
+                            data.iter().flatten().copied().collect(),
+                        )
+                        .map(RegisterMatrix::Integer),
                     }
                     .map_err(|_| {
                         RegisterMatrixConversionError::InvalidShape {
@@ -202,65 +744,16 @@ impl RegisterMap {
     ///
     /// This fails if the underlying [`QpuResultData`] data is jagged. See [`RegisterMap`] for more
     /// detailed explanations of why and when this occurs.
-    fn from_qpu_result_data(
+    pub(crate) fn from_qpu_result_data(
         qpu_result_data: &QpuResultData,
     ) -> Result<Self, RegisterMatrixConversionError> {
         #[cfg(feature = "tracing")]
         tracing::trace!("converting QPU result data to RegisterMap");
 
-        let register_map = qpu_result_data
-                    .mappings
-                    .iter()
-                    // Pair all the memory references with their readout values
-                    .map(|(memory_reference, alias)| {
-                        Ok((
-                            parse_readout_register(memory_reference).map_err(|e| {
-                                RegisterMatrixConversionError::MemoryReferenceParseError(e)
-                            })?,
-                            qpu_result_data.readout_values.get(alias).ok_or_else(||
-                                RegisterMatrixConversionError::UnmappedAlias {
-                                    memory_reference: memory_reference.to_string(),
-                                    alias: alias.to_string(),
-                                },
-                            )?,
-                        ))
-                    })
-                    // Collect into a type that will sort them by memory reference, this allows us
-                    // to make sure indices are sequential.
-                    .collect::<Result<
-                        BTreeMap<MemoryReference, &ReadoutValues>,
-                        RegisterMatrixConversionError,
-                    >>()?;
-
-        // Return an error if any group of memory references don't form a continuous sequence, indicating
-        // that a row is missing
-        let mut reference_windows = register_map.keys().tuple_windows().peekable();
-        // Ensure the first window starts with a zero index
-        if let Some((reference_a, _)) = reference_windows.peek() {
-            if reference_a.index != 0 {
-                return Err(RegisterMatrixConversionError::MissingRow {
-                    register: reference_a.name.clone(),
-                    index: 0,
-                });
-            }
-        }
-        for (reference_a, reference_b) in register_map.keys().tuple_windows() {
-            if reference_a.name == reference_b.name {
-                if reference_a.index + 1 != reference_b.index {
-                    return Err(RegisterMatrixConversionError::MissingRow {
-                        register: reference_a.name.clone(),
-                        index: reference_a.index + 1,
-                    });
-                }
-            } else if reference_b.index != 0 {
-                return Err(RegisterMatrixConversionError::MissingRow {
-                    register: reference_b.name.clone(),
-                    index: 0,
-                });
-            }
-        }
+        let register_map =
+            sorted_readout_references(&qpu_result_data.mappings, &qpu_result_data.readout_values)?;
 
-        Ok(Self(
+        let registers = Self(
             // Iterate over them in reverse so we can initialize each RegisterMatrix with the
             // correct number of rows
             register_map.into_iter().try_rfold(
@@ -290,19 +783,19 @@ impl RegisterMap {
                             if m.nrows() == v.len() =>
                         {
                             m.column_mut(reference.index)
-                                .assign(&Array::from_vec(v.clone()));
+                                .assign(&ArrayView1::from(v.as_slice()));
                         }
                         (RegisterMatrix::Real(m), ReadoutValues::Real(v))
                             if m.nrows() == v.len() =>
                         {
                             m.column_mut(reference.index)
-                                .assign(&Array::from_vec(v.clone()));
+                                .assign(&ArrayView1::from(v.as_slice()));
                         }
                         (RegisterMatrix::Complex(m), ReadoutValues::Complex(v))
                             if m.nrows() == v.len() =>
                         {
                             m.column_mut(reference.index)
-                                .assign(&Array::from_vec(v.clone()));
+                                .assign(&ArrayView1::from(v.as_slice()));
                         }
                         _ => {
                             return Err(RegisterMatrixConversionError::InvalidShape {
@@ -313,8 +806,175 @@ impl RegisterMap {
                     Ok(register_map)
                 },
             )?,
-        ))
+        );
+        registers.with_declared_types(qpu_result_data)
     }
+
+    /// As [`RegisterMap::from_qpu_result_data`], but moves the readout values out of
+    /// `qpu_result_data` instead of copying them.
+    ///
+    /// Each matrix is laid out column-major, with the values of each memory reference contiguous,
+    /// so a register read out to a single memory reference keeps the buffer its values were
+    /// decoded into. Values read out to more than one memory reference are copied once.
+    ///
+    /// # Errors
+    ///
+    /// Fails as [`RegisterMap::from_qpu_result_data`] does.
+    pub(crate) fn from_owned_qpu_result_data(
+        mut qpu_result_data: QpuResultData,
+    ) -> Result<Self, RegisterMatrixConversionError> {
+        #[cfg(feature = "tracing")]
+        tracing::trace!("converting owned QPU result data to RegisterMap");
+
+        // Pair each memory reference with the alias of its readout values, so they can be taken
+        // out of `qpu_result_data` once every memory reference mapped to them has been seen.
+        let aliases: HashMap<String, String> = qpu_result_data
+            .readout_values
+            .keys()
+            .map(|alias| (alias.clone(), alias.clone()))
+            .collect();
+        let references = sorted_readout_references(&qpu_result_data.mappings, &aliases)?;
+        let mut uses: HashMap<&str, usize> = HashMap::new();
+        for alias in references.values() {
+            *uses.entry(alias.as_str()).or_default() += 1;
+        }
+
+        let mut columns: BTreeMap<String, Vec<ReadoutValues>> = BTreeMap::new();
+        for (reference, alias) in &references {
+            let values = match uses.get_mut(alias.as_str()) {
+                Some(remaining) if *remaining > 1 => {
+                    *remaining -= 1;
+                    qpu_result_data.readout_values.get(alias.as_str()).cloned()
+                }
+                _ => qpu_result_data.readout_values.remove(alias.as_str()),
+            }
+            .ok_or_else(|| RegisterMatrixConversionError::UnmappedAlias {
+                memory_reference: format!("{}[{}]", reference.name, reference.index),
+                alias: alias.to_string(),
+            })?;
+            columns
+                .entry(reference.name.clone())
+                .or_default()
+                .push(values);
+        }
+
+        let registers = columns
+            .into_iter()
+            .map(|(name, columns)| match matrix_from_columns(columns) {
+                Some(matrix) => Ok((name, matrix)),
+                None => Err(RegisterMatrixConversionError::InvalidShape { register: name }),
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)?;
+        registers.with_declared_types(&qpu_result_data)
+    }
+
+    /// Convert each register into the [`RegisterMatrix`] variant matching its declared type in
+    /// `qpu_result_data`, if known. See [`with_declared_type`].
+    fn with_declared_types(
+        self,
+        qpu_result_data: &QpuResultData,
+    ) -> Result<Self, RegisterMatrixConversionError> {
+        self.0
+            .into_iter()
+            .map(|(name, matrix)| {
+                let matrix = match qpu_result_data.declared_type(&name) {
+                    Some(data_type) => with_declared_type(&name, matrix, data_type)?,
+                    None => matrix,
+                };
+                Ok((name, matrix))
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+/// Lay out `columns`, the values read out to each index of a register in order, side by side in a
+/// column-major matrix, moving them rather than copying them where possible. Returns `None` if
+/// there are no columns, or if they differ in type or length.
+fn matrix_from_columns(columns: Vec<ReadoutValues>) -> Option<RegisterMatrix> {
+    fn stack<T>(
+        rows: usize,
+        columns: impl ExactSizeIterator<Item = Option<Vec<T>>>,
+    ) -> Option<Array2<T>> {
+        let cols = columns.len();
+        let mut values = Vec::new();
+        for column in columns {
+            let mut column = column.filter(|column| column.len() == rows)?;
+            if values.is_empty() {
+                column.reserve_exact(rows * (cols - 1));
+                values = column;
+            } else {
+                values.append(&mut column);
+            }
+        }
+        Array2::from_shape_vec((rows, cols).f(), values).ok()
+    }
+
+    let shape = columns.first()?.shape();
+    let columns = columns.into_iter();
+    match shape.dtype {
+        DType::Int64 => stack(shape.rows, columns.map(|column| column.into_integer().ok()))
+            .map(RegisterMatrix::Integer),
+        DType::Float64 => stack(shape.rows, columns.map(|column| column.into_real().ok()))
+            .map(RegisterMatrix::Real),
+        DType::Complex128 => stack(shape.rows, columns.map(|column| column.into_complex().ok()))
+            .map(RegisterMatrix::Complex),
+    }
+}
+
+/// Convert `matrix` into the variant matching the declared type of `register`:
+/// [`RegisterMatrix::Real`] for `REAL` registers and [`RegisterMatrix::Integer`] otherwise.
+/// [`RegisterMatrix::Complex`] matrices are returned unchanged.
+// Readout values are far too small to lose precision as floats.
+#[allow(clippy::cast_precision_loss)]
+fn with_declared_type(
+    register: &str,
+    matrix: RegisterMatrix,
+    data_type: ScalarType,
+) -> Result<RegisterMatrix, RegisterMatrixConversionError> {
+    match (data_type, matrix) {
+        (ScalarType::Real, RegisterMatrix::Integer(matrix)) => {
+            Ok(RegisterMatrix::Real(matrix.mapv(|value| value as f64)))
+        }
+        (
+            ScalarType::Bit | ScalarType::Octet | ScalarType::Integer,
+            RegisterMatrix::Real(matrix),
+        ) => {
+            let values: Option<Vec<i64>> = matrix.iter().copied().map(whole_number).collect();
+            values
+                .and_then(|values| Array2::from_shape_vec(matrix.dim(), values).ok())
+                .map(RegisterMatrix::Integer)
+                .ok_or_else(|| RegisterMatrixConversionError::TypeMismatch {
+                    register: register.to_string(),
+                    data_type: data_type.to_quil_or_debug(),
+                })
+        }
+        (_, matrix) => Ok(matrix),
+    }
+}
+
+/// Convert `values` into the variant matching the declared type of `register`, by converting
+/// them as a single column with [`with_declared_type`].
+fn readout_values_with_declared_type(
+    register: &str,
+    values: ReadoutValues,
+    data_type: ScalarType,
+) -> Result<ReadoutValues, RegisterMatrixConversionError> {
+    fn column<T>(values: Vec<T>) -> Array2<T> {
+        Array1::from(values).insert_axis(Axis(1))
+    }
+
+    let matrix = match values {
+        ReadoutValues::Integer(values) => RegisterMatrix::Integer(column(values)),
+        ReadoutValues::Real(values) => RegisterMatrix::Real(column(values)),
+        ReadoutValues::Complex(values) => RegisterMatrix::Complex(column(values)),
+    };
+    Ok(match with_declared_type(register, matrix, data_type)? {
+        RegisterMatrix::Integer(matrix) => ReadoutValues::Integer(matrix.into_raw_vec()),
+        RegisterMatrix::Real(matrix) => ReadoutValues::Real(matrix.into_raw_vec()),
+        RegisterMatrix::Complex(matrix) => ReadoutValues::Complex(matrix.into_raw_vec()),
+    })
 }
 
 // This is a copy of [`quil_rs::instruction::MemoryReference`] that uses `usize` for the index
@@ -337,6 +997,80 @@ pub enum MemoryReferenceParseError {
     OversizedIndex(#[from] TryFromIntError),
 }
 
+/// Pair each memory reference in `mappings` with the values its readout is mapped to in
+/// `values`, sorted by memory reference so that the references to each register are in order.
+///
+/// # Errors
+///
+/// Fails if a memory reference can't be parsed, or a readout has no values.
+fn paired_readout_references<'a, V>(
+    mappings: &HashMap<String, String>,
+    values: &'a HashMap<String, V>,
+) -> Result<BTreeMap<MemoryReference, &'a V>, RegisterMatrixConversionError> {
+    mappings
+        .iter()
+        // Pair all the memory references with their readout values
+        .map(|(memory_reference, alias)| {
+            Ok((
+                parse_readout_register(memory_reference)
+                    .map_err(RegisterMatrixConversionError::MemoryReferenceParseError)?,
+                values
+                    .get(alias)
+                    .ok_or_else(|| RegisterMatrixConversionError::UnmappedAlias {
+                        memory_reference: memory_reference.to_string(),
+                        alias: alias.to_string(),
+                    })?,
+            ))
+        })
+        // Collect into a type that will sort them by memory reference, this allows us
+        // to make sure indices are sequential.
+        .collect::<Result<BTreeMap<MemoryReference, &V>, RegisterMatrixConversionError>>()
+}
+
+/// As [`paired_readout_references`], but every register must have a memory reference for each
+/// index from 0 up to its highest.
+///
+/// # Errors
+///
+/// Fails as [`paired_readout_references`] does, or if a register is missing a memory reference,
+/// as that would leave a gap in its matrix.
+fn sorted_readout_references<'a, V>(
+    mappings: &HashMap<String, String>,
+    values: &'a HashMap<String, V>,
+) -> Result<BTreeMap<MemoryReference, &'a V>, RegisterMatrixConversionError> {
+    let register_map = paired_readout_references(mappings, values)?;
+
+    // Return an error if any group of memory references don't form a continuous sequence, indicating
+    // that a row is missing
+    let mut reference_windows = register_map.keys().tuple_windows().peekable();
+    // Ensure the first window starts with a zero index
+    if let Some((reference_a, _)) = reference_windows.peek() {
+        if reference_a.index != 0 {
+            return Err(RegisterMatrixConversionError::MissingRow {
+                register: reference_a.name.clone(),
+                index: 0,
+            });
+        }
+    }
+    for (reference_a, reference_b) in register_map.keys().tuple_windows() {
+        if reference_a.name == reference_b.name {
+            if reference_a.index + 1 != reference_b.index {
+                return Err(RegisterMatrixConversionError::MissingRow {
+                    register: reference_a.name.clone(),
+                    index: reference_a.index + 1,
+                });
+            }
+        } else if reference_b.index != 0 {
+            return Err(RegisterMatrixConversionError::MissingRow {
+                register: reference_b.name.clone(),
+                index: 0,
+            });
+        }
+    }
+
+    Ok(register_map)
+}
+
 fn parse_readout_register(
     register_name: &str,
 ) -> Result<MemoryReference, MemoryReferenceParseError> {
@@ -348,15 +1082,91 @@ fn parse_readout_register(
 }
 
 #[cfg(test)]
+mod describe_histogram {
+    use maplit::hashmap;
+    use ndarray::prelude::*;
+
+    use super::{interop, BitOrder, RegisterMap, RegisterMatrix};
+
+    fn register_map() -> RegisterMap {
+        RegisterMap(hashmap! {
+            "ro".to_string() => RegisterMatrix::Integer(arr2(&[[1, 0], [1, 0], [0, 0], [1, 1]])),
+            "theta".to_string() => RegisterMatrix::Real(arr2(&[[0.5]])),
+        })
+    }
+
+    #[test]
+    fn it_counts_bitstrings() {
+        let histogram = register_map()
+            .histogram("ro", BitOrder::AscendingIndex)
+            .unwrap();
+        assert_eq!(
+            histogram.into_iter().collect::<Vec<_>>(),
+            [
+                ("00".to_string(), 1),
+                ("10".to_string(), 2),
+                ("11".to_string(), 1)
+            ]
+        );
+
+        let histogram = register_map()
+            .histogram("ro", BitOrder::DescendingIndex)
+            .unwrap();
+        assert_eq!(histogram["01"], 2);
+    }
+
+    #[test]
+    fn it_normalizes_counts_into_probabilities() {
+        let probabilities = register_map()
+            .probabilities("ro", BitOrder::AscendingIndex)
+            .unwrap();
+        assert_eq!(
+            probabilities.into_iter().collect::<Vec<_>>(),
+            [
+                ("00".to_string(), 0.25),
+                ("10".to_string(), 0.5),
+                ("11".to_string(), 0.25)
+            ]
+        );
+    }
+
+    #[test]
+    fn it_computes_the_parity_of_each_shot() {
+        assert_eq!(
+            register_map().parity("ro").unwrap(),
+            [true, true, false, false]
+        );
+    }
+
+    #[test]
+    fn it_rejects_missing_and_non_integer_registers() {
+        assert!(matches!(
+            register_map().histogram("missing", BitOrder::default()),
+            Err(interop::Error::MissingRegister { register }) if register == "missing"
+        ));
+        assert!(matches!(
+            register_map().parity("theta"),
+            Err(interop::Error::NonIntegerRegister { .. })
+        ));
+    }
+}
+
+#[cfg(all(test, feature = "qpu"))]
 mod describe_register_map {
+    use std::str::FromStr;
+
     use maplit::hashmap;
     use ndarray::prelude::*;
+    use quil_rs::Program;
 
     use crate::qpu::result_data::MemoryValues;
     use crate::qpu::QpuResultData;
     use crate::qvm::QvmResultData;
 
-    use super::{RegisterData, RegisterMap};
+    use super::{
+        DType, RegisterData, RegisterMap, RegisterMatrix, RegisterMatrixConversionError,
+        RegisterMatrixView, ResultData, Shape,
+    };
     use qcs_api_client_grpc::models::controller::readout_values::Values;
     use qcs_api_client_grpc::models::controller::{
         self, BinaryDataValue, DataValue as ControllerMemoryValue, IntegerDataValue,
@@ -517,6 +1327,161 @@ mod describe_register_map {
 
         RegisterMap::from_qpu_result_data(&qpu_result_data)
             .expect_err("Should not be able to create RegisterMap from QPU readout with jagged data for a register");
+        qpu_result_data
+            .into_register_map()
+            .expect_err("Should not be able to take jagged QPU readout into a RegisterMap");
+    }
+
+    #[test]
+    fn it_takes_ownership_of_readout_values_when_converting_to_register_map() {
+        let readout_mappings = hashmap! {
+            String::from("ro[1]") => String::from("qB"),
+            String::from("ro[0]") => String::from("qA"),
+            String::from("bar[0]") => String::from("qB"),
+            String::from("baz[0]") => String::from("qC"),
+        };
+        let readout_values = hashmap! {
+            String::from("qA") => dummy_readout_values(vec![1, 2, 3]),
+            String::from("qB") => dummy_readout_values(vec![4, 5, 6]),
+            String::from("qC") => dummy_readout_values(vec![7, 8, 9]),
+        };
+        let qpu_result_data = QpuResultData::from_controller_mappings_and_values(
+            &readout_mappings,
+            &readout_values,
+            &hashmap! {},
+        );
+        let baz_buffer = qpu_result_data.readout_values()["qC"]
+            .as_integer()
+            .unwrap()
+            .as_ptr();
+
+        let borrowed = qpu_result_data.to_register_map().unwrap();
+        let owned = qpu_result_data.into_register_map().unwrap();
+        assert_eq!(owned, borrowed);
+        assert_eq!(
+            owned.get_register_matrix("ro").unwrap().as_array_view(),
+            RegisterMatrixView::Integer(arr2(&[[1, 4], [2, 5], [3, 6]]).view())
+        );
+
+        let baz = owned
+            .get_register_matrix("baz")
+            .unwrap()
+            .as_integer()
+            .unwrap();
+        assert_eq!(baz.as_ptr(), baz_buffer);
+    }
+
+    #[test]
+    fn it_uses_the_declared_type_of_each_register() {
+        let readout_mappings = hashmap! {
+            String::from("ro[0]") => String::from("qA"),
+            String::from("theta[0]") => String::from("qB"),
+        };
+        let readout_values = hashmap! {
+            String::from("qA") => dummy_readout_values(vec![0, 1]),
+            String::from("qB") => dummy_readout_values(vec![2, 3]),
+        };
+        let program = Program::from_str("DECLARE ro BIT\nDECLARE theta REAL").unwrap();
+
+        let qpu_result_data = QpuResultData::from_controller_mappings_and_values(
+            &readout_mappings,
+            &readout_values,
+            &hashmap! {},
+        );
+        let register_map = RegisterMap::from_qpu_result_data(&qpu_result_data).unwrap();
+        assert!(register_map
+            .get_register_matrix("theta")
+            .unwrap()
+            .is_integer());
+
+        let register_map =
+            RegisterMap::from_qpu_result_data(&qpu_result_data.with_declarations(&program))
+                .unwrap();
+        assert_eq!(
+            register_map.get_register_matrix("ro"),
+            Some(&RegisterMatrix::Integer(arr2(&[[0], [1]])))
+        );
+        assert_eq!(
+            register_map.get_register_matrix("theta"),
+            Some(&RegisterMatrix::Real(arr2(&[[2.0], [3.0]])))
+        );
+    }
+
+    #[test]
+    fn it_fails_to_convert_values_unrepresentable_as_the_declared_type() {
+        let program = Program::from_str("DECLARE count INTEGER").unwrap();
+        let qpu_result_data = QpuResultData::from_mappings_and_values(
+            hashmap! { String::from("count[0]") => String::from("qA") },
+            hashmap! { String::from("qA") => crate::qpu::ReadoutValues::Real(vec![1.0, 2.5]) },
+            hashmap! {},
+        )
+        .with_declarations(&program);
+
+        assert!(matches!(
+            RegisterMap::from_qpu_result_data(&qpu_result_data),
+            Err(RegisterMatrixConversionError::TypeMismatch { register, .. }) if register == "count"
+        ));
+    }
+
+    #[test]
+    fn it_converts_missing_readout_indices_to_a_sparse_register_map() {
+        let program = Program::from_str("DECLARE ro BIT[8]\nDECLARE theta REAL[2]").unwrap();
+        let qpu_result_data = QpuResultData::from_controller_mappings_and_values(
+            &hashmap! {
+                String::from("ro[2]") => String::from("qA"),
+                String::from("ro[6]") => String::from("qB"),
+                String::from("theta[1]") => String::from("qC"),
+            },
+            &hashmap! {
+                String::from("qA") => dummy_readout_values(vec![0, 1]),
+                String::from("qB") => dummy_readout_values(vec![1, 1, 0]),
+                String::from("qC") => dummy_readout_values(vec![3, 4]),
+            },
+            &hashmap! {},
+        )
+        .with_declarations(&program);
+
+        assert!(matches!(
+            qpu_result_data.to_register_map(),
+            Err(RegisterMatrixConversionError::MissingRow { .. })
+        ));
+
+        let sparse = qpu_result_data.to_sparse_register_map().unwrap();
+        let ro = sparse.get_register("ro").unwrap();
+        assert_eq!(ro.indices().collect::<Vec<_>>(), vec![2, 6]);
+        assert!(!ro.is_contiguous());
+        assert_eq!(
+            ro.get(2),
+            Some(&crate::qpu::ReadoutValues::Integer(vec![0, 1]))
+        );
+        assert_eq!(
+            ro.get(6),
+            Some(&crate::qpu::ReadoutValues::Integer(vec![1, 1, 0]))
+        );
+        assert_eq!(ro.get(0), None);
+        assert_eq!(
+            sparse.get_register("theta").unwrap().get(1),
+            Some(&crate::qpu::ReadoutValues::Real(vec![3.0, 4.0]))
+        );
+    }
+
+    #[test]
+    fn it_splits_qvm_registers_into_sparse_columns() {
+        let result_data = ResultData::Qvm(QvmResultData::from_memory_map(hashmap! {
+            String::from("ro") => RegisterData::I8(vec![vec![1, 0], vec![0, 0]]),
+        }));
+
+        let sparse = result_data.to_sparse_register_map().unwrap();
+        let ro = sparse.get_register("ro").unwrap();
+        assert!(ro.is_contiguous());
+        assert_eq!(
+            ro.get(0),
+            Some(&crate::qpu::ReadoutValues::Integer(vec![1, 0]))
+        );
+        assert_eq!(
+            ro.get(1),
+            Some(&crate::qpu::ReadoutValues::Integer(vec![0, 0]))
+        );
     }
 
     #[test]
@@ -537,4 +1502,95 @@ mod describe_register_map {
         let expected = arr2(&[[1, 0, 1]]);
         assert_eq!(ro, expected);
     }
+
+    #[test]
+    fn it_reports_the_shape_of_each_register() {
+        let register_map = RegisterMap::from_hashmap(hashmap! {
+            String::from("ro") => RegisterMatrix::Integer(arr2(&[[1, 0, 1], [0, 0, 1]])),
+            String::from("theta") => RegisterMatrix::Real(arr2(&[[0.5], [1.5]])),
+        });
+
+        assert_eq!(
+            register_map.shapes(),
+            hashmap! {
+                String::from("ro") => Shape { rows: 2, cols: 3, dtype: DType::Int64 },
+                String::from("theta") => Shape { rows: 2, cols: 1, dtype: DType::Float64 },
+            }
+        );
+    }
+}
+
+#[cfg(test)]
+mod describe_raw_result_data {
+    use maplit::hashmap;
+
+    use num::complex::Complex64;
+
+    use crate::qpu::{result_data::MemoryValues, QpuResultData, ReadoutValues};
+    use crate::qvm::{QvmResultData, Wavefunction};
+
+    use super::{
+        ExecutionData, RawResultData, RegisterData, RegisterMatrixConversionError, ResultData,
+    };
+
+    #[test]
+    fn it_exposes_qpu_values_without_conversion() {
+        let qpu_result_data = QpuResultData::from_mappings_and_values(
+            hashmap! { String::from("ro[0]") => String::from("q0") },
+            hashmap! { String::from("q0") => ReadoutValues::Integer(vec![0, 1, 1]) },
+            hashmap! { String::from("ro") => MemoryValues::Binary(vec![1]) },
+        );
+        let data = ExecutionData {
+            result_data: ResultData::Qpu(qpu_result_data.clone()),
+            duration: None,
+            timings: None,
+        };
+
+        let raw = data
+            .raw_qpu_result()
+            .expect("QPU results should have a raw QPU result");
+        assert_eq!(raw.mappings, qpu_result_data.mappings());
+        assert_eq!(data.readout_mappings(), Some(qpu_result_data.mappings()));
+        assert_eq!(raw.readout_values, qpu_result_data.readout_values());
+        assert_eq!(raw.memory_values, qpu_result_data.memory_values());
+        assert_eq!(data.result_data.as_raw(), RawResultData::Qpu(raw));
+    }
+
+    #[test]
+    fn it_exposes_qvm_memory_without_conversion() {
+        let memory = hashmap! { String::from("ro") => RegisterData::I8(vec![vec![1, 0]]) };
+        let data = ExecutionData {
+            result_data: ResultData::Qvm(QvmResultData::from_memory_map(memory.clone())),
+            duration: None,
+            timings: None,
+        };
+
+        assert_eq!(data.raw_qpu_result(), None);
+        assert_eq!(data.readout_mappings(), None);
+        assert_eq!(data.result_data.as_raw(), RawResultData::Qvm(&memory));
+    }
+
+    #[test]
+    fn it_exposes_wavefunction_amplitudes() {
+        let bytes: Vec<u8> = [1.0_f64, 0.0, 0.0, 0.0]
+            .iter()
+            .flat_map(|value| value.to_be_bytes())
+            .collect();
+        let wavefunction = Wavefunction::from_qvm_bytes(&bytes).expect("should decode");
+        let data = ExecutionData {
+            result_data: ResultData::Wavefunction(wavefunction),
+            duration: None,
+            timings: None,
+        };
+
+        assert_eq!(data.raw_qpu_result(), None);
+        assert_eq!(
+            data.result_data.as_raw(),
+            RawResultData::Wavefunction(&[Complex64::new(1.0, 0.0), Complex64::new(0.0, 0.0)])
+        );
+        assert!(matches!(
+            data.result_data.to_register_map(),
+            Err(RegisterMatrixConversionError::Wavefunction)
+        ));
+    }
 }
@@ -0,0 +1,339 @@
+//! Export execution results as tidy tables, with one row per value read out, so that they can be
+//! loaded by dataframe libraries such as pandas or Polars without any custom code.
+//!
+//! Every table has the same columns:
+//!
+//! | Column        | Contents                                                                    |
+//! |---------------|-----------------------------------------------------------------------------|
+//! | `shot`        | The shot the value was read out in, counting from 0.                        |
+//! | `register`    | The name of the register the value was read out to.                         |
+//! | `index`       | The index of the value within the register.                                 |
+//! | `value`       | The value, or the real part of a complex value.                             |
+//! | `imaginary`   | The imaginary part of a complex value, empty for other values.              |
+//! | `duration_us` | How long the program ran on the QPU, in microseconds, empty for the QVM.    |
+//!
+//! Rows are ordered by register name, then shot, then index.
+//!
+//! Write a table as CSV with [`ExecutionData::to_csv`], or as Parquet with
+//! `ExecutionData::to_parquet`, which requires the `parquet` feature.
+
+use std::convert::TryFrom;
+use std::io::{self, Write};
+#[cfg(feature = "parquet")]
+use std::path::Path;
+
+use num::complex::Complex64;
+
+use crate::{ExecutionData, RegisterMap, RegisterMatrix, RegisterMatrixConversionError};
+
+/// The name of each column, in order.
+pub const COLUMNS: [&str; 6] = [
+    "shot",
+    "register",
+    "index",
+    "value",
+    "imaginary",
+    "duration_us",
+];
+
+/// Errors that can occur when exporting results.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// The results couldn't be arranged into a [`RegisterMap`].
+    #[error(transparent)]
+    RegisterMatrixConversion(#[from] RegisterMatrixConversionError),
+    /// The table couldn't be written.
+    #[error("failed to write results: {0}")]
+    Io(#[from] io::Error),
+    /// The Parquet file couldn't be written.
+    #[cfg(feature = "parquet")]
+    #[error("failed to write results as Parquet: {0}")]
+    Parquet(#[from] parquet::errors::ParquetError),
+}
+
+/// A single value read out, as one row of the table.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Row<'a> {
+    shot: usize,
+    register: &'a str,
+    index: usize,
+    value: Value,
+}
+
+/// A value of any register type.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Value {
+    Integer(i64),
+    Real(f64),
+    Complex(Complex64),
+}
+
+/// Every value in `register_map`, as rows ordered by register name, then shot, then index.
+fn rows(register_map: &RegisterMap) -> Vec<Row<'_>> {
+    let mut registers: Vec<_> = register_map.0.iter().collect();
+    registers.sort_by_key(|(name, _)| name.as_str());
+
+    let mut rows = Vec::new();
+    for (register, matrix) in registers {
+        let mut push = |(shot, index), value| {
+            rows.push(Row {
+                shot,
+                register,
+                index,
+                value,
+            });
+        };
+        match matrix {
+            RegisterMatrix::Integer(matrix) => matrix
+                .indexed_iter()
+                .for_each(|(position, value)| push(position, Value::Integer(*value))),
+            RegisterMatrix::Real(matrix) => matrix
+                .indexed_iter()
+                .for_each(|(position, value)| push(position, Value::Real(*value))),
+            RegisterMatrix::Complex(matrix) => matrix
+                .indexed_iter()
+                .for_each(|(position, value)| push(position, Value::Complex(*value))),
+        }
+    }
+    rows
+}
+
+impl ExecutionData {
+    /// The duration of the execution in whole microseconds, if known.
+    fn duration_us(&self) -> Option<u64> {
+        self.duration
+            .map(|duration| u64::try_from(duration.as_micros()).unwrap_or(u64::MAX))
+    }
+
+    /// Write the results as CSV to `writer`, with a header row naming the [`COLUMNS`]. See the
+    /// [module documentation](self) for the layout of the table.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the results can't be arranged into a [`RegisterMap`], or if writing to
+    /// `writer` fails.
+    pub fn to_csv<W: Write>(&self, writer: W) -> Result<(), Error> {
+        let register_map = self.result_data.to_register_map()?;
+        let duration = self
+            .duration_us()
+            .map_or_else(String::new, |duration| duration.to_string());
+
+        let mut writer = io::BufWriter::new(writer);
+        writeln!(writer, "{}", COLUMNS.join(","))?;
+        for row in rows(&register_map) {
+            write!(
+                writer,
+                "{},{},{},",
+                row.shot,
+                csv_field(row.register),
+                row.index
+            )?;
+            match row.value {
+                Value::Integer(value) => write!(writer, "{value},")?,
+                Value::Real(value) => write!(writer, "{value},")?,
+                Value::Complex(value) => write!(writer, "{},{}", value.re, value.im)?,
+            }
+            writeln!(writer, ",{duration}")?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Write the results as a Parquet file at `path`, replacing it if it exists. See the
+    /// [module documentation](self) for the layout of the table.
+    ///
+    /// The `value` column holds 64-bit integers if every register holds integers, and 64-bit
+    /// floats otherwise. The `imaginary` and `duration_us` columns are null where the CSV would be
+    /// empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the results can't be arranged into a [`RegisterMap`], or if the file
+    /// can't be written.
+    #[cfg(feature = "parquet")]
+    pub fn to_parquet<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let register_map = self.result_data.to_register_map()?;
+        let file = std::fs::File::create(path)?;
+        parquet_table::write(file, &rows(&register_map), self.duration_us())?;
+        Ok(())
+    }
+}
+
+/// Quote `field` if it contains a character which is special in CSV.
+fn csv_field(field: &str) -> std::borrow::Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\"")).into()
+    } else {
+        field.into()
+    }
+}
+
+#[cfg(feature = "parquet")]
+mod parquet_table {
+    use std::convert::TryFrom;
+    use std::sync::Arc;
+
+    use arrow_array::{ArrayRef, Float64Array, Int64Array, RecordBatch, StringArray, UInt64Array};
+    use parquet::arrow::ArrowWriter;
+    use parquet::errors::ParquetError;
+
+    use super::{Row, Value, COLUMNS};
+
+    /// Write `rows` as a Parquet table, with `duration_us` in every row.
+    #[allow(clippy::cast_precision_loss)] // Integers are only mixed with floats to match other registers.
+    pub(super) fn write<W: std::io::Write + Send>(
+        writer: W,
+        rows: &[Row<'_>],
+        duration_us: Option<u64>,
+    ) -> Result<(), ParquetError> {
+        let index = |value: usize| u64::try_from(value).unwrap_or(u64::MAX);
+        let all_integers = rows
+            .iter()
+            .all(|row| matches!(row.value, Value::Integer(_)));
+        let value: ArrayRef = if all_integers {
+            Arc::new(Int64Array::from_iter_values(rows.iter().filter_map(
+                |row| match row.value {
+                    Value::Integer(value) => Some(value),
+                    Value::Real(_) | Value::Complex(_) => None,
+                },
+            )))
+        } else {
+            Arc::new(Float64Array::from_iter_values(rows.iter().map(
+                |row| match row.value {
+                    Value::Integer(value) => value as f64,
+                    Value::Real(value) => value,
+                    Value::Complex(value) => value.re,
+                },
+            )))
+        };
+        let imaginary = Float64Array::from_iter(rows.iter().map(|row| match row.value {
+            Value::Complex(value) => Some(value.im),
+            Value::Integer(_) | Value::Real(_) => None,
+        }));
+
+        let columns: [(&str, ArrayRef, bool); 6] = [
+            (
+                COLUMNS[0],
+                Arc::new(UInt64Array::from_iter_values(
+                    rows.iter().map(|row| index(row.shot)),
+                )),
+                false,
+            ),
+            (
+                COLUMNS[1],
+                Arc::new(StringArray::from_iter_values(
+                    rows.iter().map(|row| row.register),
+                )),
+                false,
+            ),
+            (
+                COLUMNS[2],
+                Arc::new(UInt64Array::from_iter_values(
+                    rows.iter().map(|row| index(row.index)),
+                )),
+                false,
+            ),
+            (COLUMNS[3], value, false),
+            (COLUMNS[4], Arc::new(imaginary), true),
+            (
+                COLUMNS[5],
+                Arc::new(UInt64Array::from(vec![duration_us; rows.len()])),
+                true,
+            ),
+        ];
+        let batch = RecordBatch::try_from_iter_with_nullable(columns)?;
+
+        let mut writer = ArrowWriter::try_new(writer, batch.schema(), None)?;
+        writer.write(&batch)?;
+        writer.close()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod describe_csv_export {
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    use maplit::hashmap;
+    use num::complex::Complex32;
+
+    use crate::{qvm::QvmResultData, ExecutionData, RegisterData, ResultData};
+
+    fn csv(memory: HashMap<String, RegisterData>, duration: Option<Duration>) -> String {
+        let execution_data = ExecutionData {
+            result_data: ResultData::Qvm(QvmResultData::from_memory_map(memory)),
+            duration,
+            timings: None,
+        };
+        let mut csv = Vec::new();
+        execution_data.to_csv(&mut csv).unwrap();
+        String::from_utf8(csv).unwrap()
+    }
+
+    #[test]
+    fn it_writes_one_row_per_value_in_order() {
+        let memory = hashmap! {
+            String::from("theta") => RegisterData::F64(vec![vec![0.5], vec![1.5]]),
+            String::from("ro") => RegisterData::I8(vec![vec![0, 1], vec![1, 1]]),
+        };
+
+        assert_eq!(
+            csv(memory, Some(Duration::from_micros(1234))),
+            "shot,register,index,value,imaginary,duration_us\n\
+             0,ro,0,0,,1234\n\
+             0,ro,1,1,,1234\n\
+             1,ro,0,1,,1234\n\
+             1,ro,1,1,,1234\n\
+             0,theta,0,0.5,,1234\n\
+             1,theta,0,1.5,,1234\n"
+        );
+    }
+
+    #[test]
+    fn it_splits_complex_values_without_a_duration() {
+        let memory = hashmap! {
+            String::from("iq") => RegisterData::Complex32(vec![vec![Complex32::new(1.0, -2.5)]]),
+        };
+
+        assert_eq!(
+            csv(memory, None),
+            "shot,register,index,value,imaginary,duration_us\n0,iq,0,1,-2.5,\n"
+        );
+    }
+
+    #[test]
+    fn it_quotes_special_characters() {
+        assert_eq!(super::csv_field("ro"), "ro");
+        assert_eq!(super::csv_field("a,\"b\""), "\"a,\"\"b\"\"\"");
+    }
+}
+
+#[cfg(all(test, feature = "parquet"))]
+mod describe_parquet_export {
+    use maplit::hashmap;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+
+    use crate::{qvm::QvmResultData, ExecutionData, RegisterData, ResultData};
+
+    #[test]
+    fn it_writes_one_row_per_value() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("results.parquet");
+        let execution_data = ExecutionData {
+            result_data: ResultData::Qvm(QvmResultData::from_memory_map(hashmap! {
+                String::from("ro") => RegisterData::I8(vec![vec![0, 1], vec![1, 1], vec![0, 0]]),
+            })),
+            duration: None,
+            timings: None,
+        };
+
+        execution_data.to_parquet(&path).unwrap();
+
+        let reader = SerializedFileReader::new(std::fs::File::open(path).unwrap()).unwrap();
+        let metadata = reader.metadata().file_metadata();
+        assert_eq!(metadata.num_rows(), 6);
+        assert_eq!(metadata.schema_descr().num_columns(), super::COLUMNS.len());
+    }
+}
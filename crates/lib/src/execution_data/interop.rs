@@ -0,0 +1,217 @@
+//! Convert execution results into the counts/memory format used by other quantum toolchains,
+//! such as Qiskit, so they can be fed into existing analysis code.
+//!
+//! Each readout register is described by its bitstrings: `memory` holds the bitstring of every
+//! shot in order, and `counts` holds how many times each bitstring occurred. For example:
+//!
+//! ```json
+//! {"ro": {"counts": {"00": 498, "11": 502}, "memory": ["11", "00", "11", ...]}}
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+pub use crate::BitOrder;
+use crate::{ExecutionData, RegisterMap, RegisterMatrix, RegisterMatrixConversionError};
+
+/// The bitstrings read out to one register.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegisterCounts {
+    /// The number of shots which produced each bitstring.
+    pub counts: BTreeMap<String, u64>,
+    /// The bitstring produced by each shot, in order.
+    pub memory: Vec<String>,
+}
+
+impl RegisterCounts {
+    /// Build the bitstrings for a register, where each row of `matrix` is one shot.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the register isn't an integer register or contains a value other than
+    /// 0 or 1.
+    pub fn from_register_matrix(
+        register: &str,
+        matrix: &RegisterMatrix,
+        bit_order: BitOrder,
+    ) -> Result<Self, Error> {
+        let matrix = matrix
+            .as_integer()
+            .ok_or_else(|| Error::NonIntegerRegister {
+                register: register.to_string(),
+            })?;
+
+        let mut counts = Self::default();
+        for row in matrix.rows() {
+            let bits = row
+                .iter()
+                .map(|value| match value {
+                    0 => Ok(false),
+                    1 => Ok(true),
+                    value => Err(Error::NonBinaryValue {
+                        register: register.to_string(),
+                        value: *value,
+                    }),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let bitstring = bit_order.to_bitstring(&bits);
+            *counts.counts.entry(bitstring.clone()).or_default() += 1;
+            counts.memory.push(bitstring);
+        }
+        Ok(counts)
+    }
+
+    /// Rewrite every bitstring, written in the `from` order, into the `to` order.
+    #[must_use]
+    pub fn convert(self, from: BitOrder, to: BitOrder) -> Self {
+        Self {
+            counts: self
+                .counts
+                .into_iter()
+                .map(|(bitstring, count)| (from.convert(&bitstring, to), count))
+                .collect(),
+            memory: self
+                .memory
+                .iter()
+                .map(|bitstring| from.convert(bitstring, to))
+                .collect(),
+        }
+    }
+}
+
+/// Build the bitstrings for every register in `register_map`.
+///
+/// # Errors
+///
+/// See [`RegisterCounts::from_register_matrix`].
+pub fn to_counts(
+    register_map: &RegisterMap,
+    bit_order: BitOrder,
+) -> Result<HashMap<String, RegisterCounts>, Error> {
+    register_map
+        .0
+        .iter()
+        .map(|(register, matrix)| {
+            RegisterCounts::from_register_matrix(register, matrix, bit_order)
+                .map(|counts| (register.clone(), counts))
+        })
+        .collect()
+}
+
+/// Convert the results of an execution into a JSON object mapping each register name to its
+/// `counts` and `memory`.
+///
+/// # Errors
+///
+/// Returns an error if the results can't be converted to a [`RegisterMap`], or if any register
+/// isn't made up of bits. See [`RegisterCounts::from_register_matrix`].
+pub fn to_counts_json(
+    execution_data: &ExecutionData,
+    bit_order: BitOrder,
+) -> Result<serde_json::Value, Error> {
+    let register_map = execution_data.result_data.to_register_map()?;
+    let counts = to_counts(&register_map, bit_order)?;
+    Ok(serde_json::to_value(counts).expect("counts always serialize to JSON"))
+}
+
+/// Errors that can occur when converting results to counts.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// The results couldn't be arranged into a [`RegisterMap`].
+    #[error(transparent)]
+    RegisterMatrixConversion(#[from] RegisterMatrixConversionError),
+    /// There is no register with this name.
+    #[error("There is no register named {register}")]
+    MissingRegister {
+        /// The name of the register.
+        register: String,
+    },
+    /// Only integer registers can be written as bitstrings.
+    #[error("Register {register} does not contain integers")]
+    NonIntegerRegister {
+        /// The name of the register.
+        register: String,
+    },
+    /// A register contained a value that isn't a bit.
+    #[error("Register {register} contains {value}, which is not 0 or 1")]
+    NonBinaryValue {
+        /// The name of the register.
+        register: String,
+        /// The offending value.
+        value: i64,
+    },
+}
+
+#[cfg(test)]
+mod describe_counts {
+    use maplit::hashmap;
+    use ndarray::arr2;
+    use serde_json::json;
+
+    use super::{to_counts_json, BitOrder, Error, RegisterCounts};
+    use crate::{qvm::QvmResultData, ExecutionData, RegisterData, RegisterMatrix, ResultData};
+
+    fn execution_data(ro: Vec<Vec<i8>>) -> ExecutionData {
+        ExecutionData {
+            result_data: ResultData::Qvm(QvmResultData::from_memory_map(hashmap! {
+                String::from("ro") => RegisterData::I8(ro),
+            })),
+            duration: None,
+            timings: None,
+        }
+    }
+
+    #[test]
+    fn it_counts_bitstrings_in_ascending_order() {
+        let data = execution_data(vec![vec![1, 0], vec![1, 0], vec![0, 0]]);
+        assert_eq!(
+            to_counts_json(&data, BitOrder::AscendingIndex).unwrap(),
+            json!({"ro": {"counts": {"00": 1, "10": 2}, "memory": ["10", "10", "00"]}})
+        );
+    }
+
+    #[test]
+    fn it_counts_bitstrings_in_descending_order() {
+        let data = execution_data(vec![vec![1, 0], vec![1, 0], vec![0, 0]]);
+        assert_eq!(
+            to_counts_json(&data, BitOrder::DescendingIndex).unwrap(),
+            json!({"ro": {"counts": {"00": 1, "01": 2}, "memory": ["01", "01", "00"]}})
+        );
+    }
+
+    #[test]
+    fn it_converts_counts_between_orders() {
+        let matrix = RegisterMatrix::Integer(arr2(&[[1, 1, 0], [1, 0, 0]]));
+        let ascending =
+            RegisterCounts::from_register_matrix("ro", &matrix, BitOrder::AscendingIndex).unwrap();
+        let descending =
+            RegisterCounts::from_register_matrix("ro", &matrix, BitOrder::DescendingIndex).unwrap();
+        assert_eq!(
+            ascending
+                .clone()
+                .convert(BitOrder::AscendingIndex, BitOrder::DescendingIndex),
+            descending
+        );
+        assert_eq!(
+            descending.convert(BitOrder::DescendingIndex, BitOrder::AscendingIndex),
+            ascending
+        );
+    }
+
+    #[test]
+    fn it_rejects_registers_that_are_not_bits() {
+        let matrix = RegisterMatrix::Integer(arr2(&[[2, 0]]));
+        assert!(matches!(
+            RegisterCounts::from_register_matrix("ro", &matrix, BitOrder::default()),
+            Err(Error::NonBinaryValue { value: 2, .. })
+        ));
+
+        let matrix = RegisterMatrix::Real(arr2(&[[0.0]]));
+        assert!(matches!(
+            RegisterCounts::from_register_matrix("theta", &matrix, BitOrder::default()),
+            Err(Error::NonIntegerRegister { .. })
+        ));
+    }
+}
@@ -0,0 +1,324 @@
+//! Bundle everything needed to reproduce or investigate an experiment into a single archive.
+//!
+//! A [`Report`] is written as an uncompressed tarball with one file per part of the experiment:
+//!
+//! | File                       | Contents                                                      |
+//! |----------------------------|---------------------------------------------------------------|
+//! | `report/results.json`      | The [`ExecutionData`], as JSON.                               |
+//! | `report/environment.json`  | The SDK version, Rust version, target, and enabled features.  |
+//! | `report/program.quil`      | The program as it was given.                                  |
+//! | `report/native.quil`       | The program as it was compiled for the QPU.                   |
+//! | `report/patch_values.json` | The values patched into each memory region.                   |
+//! | `report/translation.json`  | The readout mapping produced by translation.                  |
+//! | `report/timings.json`      | The [`StageTimings`] of the execution.                        |
+//! | `report/diagnostics.txt`   | The [diagnostics report](crate::diagnostics::get_report).     |
+//!
+//! Only `results.json` and `environment.json` are always present; the others are written only if
+//! they are known.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::{build_info, metrics::StageTimings, ExecutionData};
+
+/// The directory every file in the archive is written to.
+const ROOT: &str = "report";
+
+/// Errors that can occur when exporting a [`Report`].
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// Part of the report couldn't be serialized.
+    #[error("failed to serialize {file}: {source}")]
+    Serialization {
+        /// The file which couldn't be serialized.
+        file: &'static str,
+        /// The underlying error.
+        #[source]
+        source: serde_json::Error,
+    },
+    /// The archive couldn't be written.
+    #[error("failed to write report: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// Everything known about one experiment: its program, how it was run, and its results.
+///
+/// Build one with [`Executable::report`](crate::Executable::report), which fills in everything the
+/// [`Executable`](crate::Executable) knows, or with [`Report::new`] and the `with_*` methods.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Report {
+    results: ExecutionData,
+    program: Option<String>,
+    native_quil: Option<String>,
+    patch_values: Option<BTreeMap<String, Vec<f64>>>,
+    ro_sources: Option<BTreeMap<String, String>>,
+    timings: Option<StageTimings>,
+    diagnostics: Option<String>,
+}
+
+/// The build of the SDK which produced a report.
+#[derive(Serialize)]
+struct Environment {
+    version: &'static str,
+    rust_version: &'static str,
+    target: &'static str,
+    profile: &'static str,
+    features: &'static [&'static str],
+}
+
+impl Environment {
+    const CURRENT: Self = Self {
+        version: build_info::PKG_VERSION,
+        rust_version: build_info::RUSTC_VERSION,
+        target: build_info::TARGET,
+        profile: build_info::PROFILE,
+        features: &build_info::FEATURES,
+    };
+}
+
+impl Report {
+    /// A report containing only `results` and the build of the SDK.
+    #[must_use]
+    pub fn new(results: ExecutionData) -> Self {
+        Self {
+            results,
+            program: None,
+            native_quil: None,
+            patch_values: None,
+            ro_sources: None,
+            timings: None,
+            diagnostics: None,
+        }
+    }
+
+    /// Include the program as it was given.
+    #[must_use]
+    pub fn with_program(mut self, program: impl Into<String>) -> Self {
+        self.program = Some(program.into());
+        self
+    }
+
+    /// Include the program as it was compiled.
+    #[must_use]
+    pub fn with_native_quil(mut self, native_quil: impl Into<String>) -> Self {
+        self.native_quil = Some(native_quil.into());
+        self
+    }
+
+    /// Include the values patched into each memory region.
+    #[must_use]
+    pub fn with_patch_values<K: AsRef<str>>(mut self, patch_values: &HashMap<K, Vec<f64>>) -> Self {
+        self.patch_values = Some(
+            patch_values
+                .iter()
+                .map(|(name, values)| (name.as_ref().to_string(), values.clone()))
+                .collect(),
+        );
+        self
+    }
+
+    /// Include the readout mapping produced by translation. See
+    /// [`JobHandle::readout_map`](crate::JobHandle::readout_map).
+    #[must_use]
+    pub fn with_ro_sources(mut self, ro_sources: &HashMap<String, String>) -> Self {
+        self.ro_sources = Some(ro_sources.clone().into_iter().collect());
+        self
+    }
+
+    /// Include how long each stage of the execution took.
+    #[must_use]
+    pub fn with_timings(mut self, timings: StageTimings) -> Self {
+        self.timings = Some(timings);
+        self
+    }
+
+    /// Include a diagnostics report, such as the one from
+    /// [`diagnostics::get_report`](crate::diagnostics::get_report).
+    #[must_use]
+    pub fn with_diagnostics(mut self, diagnostics: impl Into<String>) -> Self {
+        self.diagnostics = Some(diagnostics.into());
+        self
+    }
+
+    /// Write the report as a tarball to the file at `path`, replacing it if it exists.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if part of the report can't be serialized or the file can't be
+    /// written.
+    pub fn export(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let mut file = io::BufWriter::new(std::fs::File::create(path)?);
+        self.write_to(&mut file)?;
+        file.flush()?;
+        Ok(())
+    }
+
+    /// Write the report as a tarball to `writer`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if part of the report can't be serialized or can't be written.
+    pub fn write_to<W: Write>(&self, writer: W) -> Result<(), Error> {
+        let modified = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        let mut archive = tar::Builder::new(writer);
+        for (file, contents) in self.files()? {
+            let mut header = tar::Header::new_ustar();
+            header.set_entry_type(tar::EntryType::Regular);
+            header.set_mode(0o644);
+            header.set_mtime(modified);
+            header.set_size(contents.len() as u64);
+            archive.append_data(&mut header, format!("{ROOT}/{file}"), contents.as_slice())?;
+        }
+        archive.into_inner()?;
+        Ok(())
+    }
+
+    /// The name and contents of every file in the report.
+    fn files(&self) -> Result<Vec<(&'static str, Vec<u8>)>, Error> {
+        let mut files = vec![
+            ("results.json", to_json("results.json", &self.results)?),
+            (
+                "environment.json",
+                to_json("environment.json", &Environment::CURRENT)?,
+            ),
+        ];
+        if let Some(program) = &self.program {
+            files.push(("program.quil", program.clone().into_bytes()));
+        }
+        if let Some(native_quil) = &self.native_quil {
+            files.push(("native.quil", native_quil.clone().into_bytes()));
+        }
+        if let Some(patch_values) = &self.patch_values {
+            files.push((
+                "patch_values.json",
+                to_json("patch_values.json", patch_values)?,
+            ));
+        }
+        if let Some(ro_sources) = &self.ro_sources {
+            files.push((
+                "translation.json",
+                to_json(
+                    "translation.json",
+                    &serde_json::json!({ "ro_sources": ro_sources }),
+                )?,
+            ));
+        }
+        if let Some(timings) = &self.timings {
+            files.push(("timings.json", to_json("timings.json", timings)?));
+        }
+        if let Some(diagnostics) = &self.diagnostics {
+            files.push(("diagnostics.txt", diagnostics.clone().into_bytes()));
+        }
+        Ok(files)
+    }
+}
+
+impl ExecutionData {
+    /// Write these results, along with the build of the SDK, as a report archive at `path`.
+    ///
+    /// Use [`Executable::report`](crate::Executable::report) instead to include the program and
+    /// how it was run.
+    ///
+    /// # Errors
+    ///
+    /// See [`Report::export`].
+    pub fn export_report(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        Report::new(self.clone()).export(path)
+    }
+}
+
+fn to_json<T: Serialize + ?Sized>(file: &'static str, value: &T) -> Result<Vec<u8>, Error> {
+    serde_json::to_vec_pretty(value).map_err(|source| Error::Serialization { file, source })
+}
+
+#[cfg(test)]
+mod describe_report {
+    use std::collections::HashMap;
+    use std::io::Read;
+    use std::time::Duration;
+
+    use super::Report;
+    use crate::{metrics::StageTimings, qvm::QvmResultData, ExecutionData, RegisterData};
+
+    fn results() -> ExecutionData {
+        ExecutionData {
+            result_data: crate::ResultData::Qvm(QvmResultData::from_memory_map(HashMap::from([(
+                "ro".to_string(),
+                RegisterData::I8(vec![vec![0], vec![1]]),
+            )]))),
+            duration: None,
+            timings: None,
+        }
+    }
+
+    /// The name and contents of every file in a tarball.
+    fn read_entries(archive: &[u8]) -> Vec<(String, String)> {
+        tar::Archive::new(archive)
+            .entries()
+            .unwrap()
+            .map(|entry| {
+                let mut entry = entry.unwrap();
+                assert_eq!(entry.header().entry_type(), tar::EntryType::Regular);
+                let name = entry.path().unwrap().to_string_lossy().into_owned();
+                let mut contents = String::new();
+                entry.read_to_string(&mut contents).unwrap();
+                (name, contents)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn it_writes_only_what_is_known() {
+        let mut archive = Vec::new();
+        Report::new(results()).write_to(&mut archive).unwrap();
+        let names: Vec<_> = read_entries(&archive)
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(names, ["report/results.json", "report/environment.json"]);
+    }
+
+    #[test]
+    fn it_writes_every_part_of_the_experiment() {
+        let report = Report::new(results())
+            .with_program("DECLARE ro BIT\nMEASURE 0 ro")
+            .with_native_quil("DECLARE ro BIT[1]\nMEASURE 0 ro[0]\n")
+            .with_patch_values(&HashMap::from([("theta", vec![0.5])]))
+            .with_ro_sources(&HashMap::from([("q0".to_string(), "ro[0]".to_string())]))
+            .with_timings(StageTimings {
+                compilation: Some(Duration::from_millis(5)),
+                ..StageTimings::default()
+            })
+            .with_diagnostics("qcs-sdk-rust version: test");
+        let mut archive = Vec::new();
+        report.write_to(&mut archive).unwrap();
+        let entries: HashMap<_, _> = read_entries(&archive).into_iter().collect();
+
+        assert_eq!(entries.len(), 8);
+        assert_eq!(
+            entries["report/program.quil"],
+            "DECLARE ro BIT\nMEASURE 0 ro"
+        );
+        assert!(entries["report/translation.json"].contains("ro[0]"));
+        assert!(entries["report/patch_values.json"].contains("theta"));
+        assert!(entries["report/environment.json"].contains(crate::build_info::PKG_VERSION));
+        let results: ExecutionData = serde_json::from_str(&entries["report/results.json"]).unwrap();
+        assert_eq!(results, report.results);
+    }
+
+    #[test]
+    fn it_exports_to_a_file() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("report.tar");
+        results().export_report(&path).unwrap();
+        let archive = std::fs::read(path).unwrap();
+        assert_eq!(read_entries(&archive).len(), 2);
+    }
+}
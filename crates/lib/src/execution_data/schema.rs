@@ -0,0 +1,590 @@
+//! A canonical, versioned JSON representation of [`ExecutionData`] and [`ResultData`], for
+//! exchanging results with other languages (such as pyQuil's `QAMExecutionResult`) without
+//! depending on how the Rust types happen to serialize.
+//!
+//! Every document carries a `schema_version`, and every array of values is tagged with its
+//! `dtype`, so that a reader never has to guess whether `[[0, 1]]` holds bits or integers. The
+//! schema itself is published as [`JSON_SCHEMA`]. For example, the results of a QVM run are:
+//!
+//! ```json
+//! {
+//!   "schema_version": 2,
+//!   "duration_microseconds": null,
+//!   "result_data": {
+//!     "kind": "qvm",
+//!     "memory": { "ro": { "dtype": "int8", "data": [[0, 1], [1, 1]] } }
+//!   }
+//! }
+//! ```
+//!
+//! Complex numbers are written as `[real, imaginary]` pairs. [`ResultData::Spilled`] results are
+//! written as QPU results, and so are read back into memory.
+//!
+//! # Versioning
+//!
+//! [`ExecutionData::from_json`] and [`ResultData::from_json`] read any version up to
+//! [`SCHEMA_VERSION`], migrating older documents as they go. Documents without a `schema_version`
+//! are read as the unversioned output of `serde_json` for the Rust types (for instance, an
+//! [`ExecutionData`] pickled by an older version of the Python package). Documents from a newer
+//! version are rejected rather than misread.
+//!
+//! Version 2 added the `int32`, `int64`, `float32` and `complex128` dtypes for registers. Every
+//! version 1 document is also a valid version 2 document.
+
+use std::collections::{BTreeMap, HashMap};
+use std::convert::{TryFrom, TryInto};
+use std::time::Duration;
+
+use num::complex::{Complex32, Complex64};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    qpu::{result_data::MemoryValues, QpuResultData, ReadoutValues},
+    qvm::{QvmResultData, Wavefunction},
+    ExecutionData, RegisterData, ResultData,
+};
+
+/// The version of the schema written by [`ExecutionData::to_json`] and [`ResultData::to_json`].
+pub const SCHEMA_VERSION: u64 = 2;
+
+/// The [JSON Schema](https://json-schema.org/) of the documents written by
+/// [`ExecutionData::to_json`].
+pub const JSON_SCHEMA: &str = include_str!("execution_data.schema.json");
+
+/// Errors that can occur when reading or writing the JSON representation.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// The document was written by a newer version of the schema than this version of the SDK
+    /// supports.
+    #[error("schema version {found} is newer than the latest supported version {supported}")]
+    UnsupportedVersion {
+        /// The version of the document.
+        found: u64,
+        /// The latest version this SDK can read, [`SCHEMA_VERSION`].
+        supported: u64,
+    },
+    /// The document isn't valid for its schema version.
+    #[error("invalid document: {0}")]
+    Json(#[from] serde_json::Error),
+    /// A wavefunction doesn't have exactly one amplitude for every basis state.
+    #[error("a wavefunction must have a power of two amplitudes, found {0}")]
+    InvalidWavefunction(usize),
+    /// The values of [spilled](crate::spill) results couldn't be read back to be written.
+    #[error("spilled results couldn't be read back: {0}")]
+    SpillRead(#[from] std::io::Error),
+}
+
+impl ExecutionData {
+    /// Serialize to the canonical JSON representation, see [`schema`](crate::schema).
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the data can't be serialized.
+    pub fn to_json(&self) -> Result<String, Error> {
+        let document = Versioned {
+            schema_version: SCHEMA_VERSION,
+            document: ExecutionDataV2::try_from(self)?,
+        };
+        Ok(serde_json::to_string(&document)?)
+    }
+
+    /// Deserialize from the canonical JSON representation of any supported version, see
+    /// [`schema`](crate::schema).
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the document is from an unsupported version or isn't valid.
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        match read_version(json)? {
+            (None, value) => Ok(serde_json::from_value(value)?),
+            (Some(_), value) => serde_json::from_value::<ExecutionDataV2>(value)?.try_into(),
+        }
+    }
+}
+
+impl ResultData {
+    /// Serialize to the canonical JSON representation, see [`schema`](crate::schema).
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the data can't be serialized.
+    pub fn to_json(&self) -> Result<String, Error> {
+        let document = Versioned {
+            schema_version: SCHEMA_VERSION,
+            document: ResultDataV2::try_from(self)?,
+        };
+        Ok(serde_json::to_string(&document)?)
+    }
+
+    /// Deserialize from the canonical JSON representation of any supported version, see
+    /// [`schema`](crate::schema).
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the document is from an unsupported version or isn't valid.
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        match read_version(json)? {
+            (None, value) => Ok(serde_json::from_value(value)?),
+            (Some(_), value) => serde_json::from_value::<ResultDataV2>(value)?.try_into(),
+        }
+    }
+}
+
+/// Parse `json`, returning its schema version, if it has one, along with the document migrated to
+/// [`SCHEMA_VERSION`].
+fn read_version(json: &str) -> Result<(Option<u64>, Value), Error> {
+    let mut value: Value = serde_json::from_str(json)?;
+    let version = match value.as_object_mut() {
+        Some(object) => match object.remove("schema_version") {
+            Some(version) => Some(serde_json::from_value::<u64>(version)?),
+            None => None,
+        },
+        None => None,
+    };
+    match version {
+        Some(found) if found > SCHEMA_VERSION => Err(Error::UnsupportedVersion {
+            found,
+            supported: SCHEMA_VERSION,
+        }),
+        // Version 1 is a subset of version 2, so it needs no migration. Migrations from older
+        // versions go here as the schema evolves.
+        _ => Ok((version, value)),
+    }
+}
+
+/// A document tagged with the version of its schema.
+#[derive(Serialize)]
+struct Versioned<T> {
+    schema_version: u64,
+    #[serde(flatten)]
+    document: T,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ExecutionDataV2 {
+    result_data: ResultDataV2,
+    duration_microseconds: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum ResultDataV2 {
+    Qvm {
+        memory: BTreeMap<String, RegisterV2>,
+    },
+    Qpu {
+        mappings: BTreeMap<String, String>,
+        readout_values: BTreeMap<String, ValuesV2>,
+        memory_values: BTreeMap<String, ValuesV2>,
+    },
+    Wavefunction {
+        amplitudes: Vec<[f64; 2]>,
+    },
+}
+
+/// The values of a register on each shot.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "dtype", content = "data", rename_all = "snake_case")]
+enum RegisterV2 {
+    Int8(Vec<Vec<i8>>),
+    Int16(Vec<Vec<i16>>),
+    Float64(Vec<Vec<f64>>),
+    Complex64(Vec<Vec<[f32; 2]>>),
+    Int32(Vec<Vec<i32>>),
+    Float32(Vec<Vec<f32>>),
+    Complex128(Vec<Vec<[f64; 2]>>),
+    Int64(Vec<Vec<i64>>),
+}
+
+/// A flat array of values, as read out or left in memory by the QPU.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "dtype", content = "data", rename_all = "snake_case")]
+enum ValuesV2 {
+    Uint8(Vec<u8>),
+    Int64(Vec<i64>),
+    Float64(Vec<f64>),
+    Complex128(Vec<[f64; 2]>),
+}
+
+impl TryFrom<&ExecutionData> for ExecutionDataV2 {
+    type Error = Error;
+
+    fn try_from(data: &ExecutionData) -> Result<Self, Error> {
+        Ok(Self {
+            result_data: ResultDataV2::try_from(&data.result_data)?,
+            duration_microseconds: data
+                .duration
+                .map(|duration| u64::try_from(duration.as_micros()).unwrap_or(u64::MAX)),
+        })
+    }
+}
+
+impl TryFrom<ExecutionDataV2> for ExecutionData {
+    type Error = Error;
+
+    fn try_from(data: ExecutionDataV2) -> Result<Self, Error> {
+        Ok(Self {
+            result_data: data.result_data.try_into()?,
+            duration: data.duration_microseconds.map(Duration::from_micros),
+            timings: None,
+        })
+    }
+}
+
+impl TryFrom<&ResultData> for ResultDataV2 {
+    type Error = Error;
+
+    fn try_from(data: &ResultData) -> Result<Self, Error> {
+        Ok(match data {
+            ResultData::Qvm(data) => Self::Qvm {
+                memory: data
+                    .memory()
+                    .iter()
+                    .map(|(name, register)| (name.clone(), RegisterV2::from(register)))
+                    .collect(),
+            },
+            ResultData::Qpu(data) => Self::Qpu {
+                mappings: data.mappings().clone().into_iter().collect(),
+                readout_values: data
+                    .readout_values()
+                    .iter()
+                    .map(|(name, values)| (name.clone(), ValuesV2::from(values)))
+                    .collect(),
+                memory_values: data
+                    .memory_values()
+                    .iter()
+                    .map(|(name, values)| (name.clone(), ValuesV2::from(values)))
+                    .collect(),
+            },
+            ResultData::Wavefunction(wavefunction) => Self::Wavefunction {
+                amplitudes: wavefunction
+                    .amplitudes()
+                    .iter()
+                    .map(|amplitude| [amplitude.re, amplitude.im])
+                    .collect(),
+            },
+            // Spilled registers are written as QPU results in which each memory reference is
+            // mapped to a readout of the same name.
+            ResultData::Spilled(data) => {
+                let mut references = Vec::new();
+                for (name, matrix) in &data.register_map().0 {
+                    for index in 0..matrix.shape().cols {
+                        if let Some(values) = matrix.column(index)? {
+                            references.push((format!("{name}[{index}]"), values));
+                        }
+                    }
+                }
+                Self::Qpu {
+                    mappings: references
+                        .iter()
+                        .map(|(reference, _)| (reference.clone(), reference.clone()))
+                        .collect(),
+                    readout_values: references
+                        .iter()
+                        .map(|(reference, values)| (reference.clone(), ValuesV2::from(values)))
+                        .collect(),
+                    memory_values: data
+                        .memory_values()
+                        .iter()
+                        .map(|(name, values)| (name.clone(), ValuesV2::from(values)))
+                        .collect(),
+                }
+            }
+        })
+    }
+}
+
+impl TryFrom<ResultDataV2> for ResultData {
+    type Error = Error;
+
+    fn try_from(data: ResultDataV2) -> Result<Self, Error> {
+        Ok(match data {
+            ResultDataV2::Qvm { memory } => Self::Qvm(QvmResultData::from_memory_map(
+                memory
+                    .into_iter()
+                    .map(|(name, register)| (name, register.into()))
+                    .collect(),
+            )),
+            ResultDataV2::Qpu {
+                mappings,
+                readout_values,
+                memory_values,
+            } => Self::Qpu(QpuResultData::from_mappings_and_values(
+                mappings.into_iter().collect(),
+                readout_values
+                    .into_iter()
+                    .map(|(name, values)| Ok((name, values.try_into()?)))
+                    .collect::<Result<HashMap<_, _>, Error>>()?,
+                memory_values
+                    .into_iter()
+                    .map(|(name, values)| Ok((name, values.try_into()?)))
+                    .collect::<Result<HashMap<_, _>, Error>>()?,
+            )),
+            ResultDataV2::Wavefunction { amplitudes } => {
+                let count = amplitudes.len();
+                Self::Wavefunction(
+                    Wavefunction::from_amplitudes(
+                        amplitudes
+                            .into_iter()
+                            .map(|[re, im]| Complex64::new(re, im))
+                            .collect(),
+                    )
+                    .map_err(|_| Error::InvalidWavefunction(count))?,
+                )
+            }
+        })
+    }
+}
+
+impl From<&RegisterData> for RegisterV2 {
+    fn from(register: &RegisterData) -> Self {
+        match register {
+            RegisterData::I8(data) => Self::Int8(data.clone()),
+            RegisterData::I16(data) => Self::Int16(data.clone()),
+            RegisterData::F64(data) => Self::Float64(data.clone()),
+            RegisterData::Complex32(data) => Self::Complex64(
+                data.iter()
+                    .map(|row| row.iter().map(|value| [value.re, value.im]).collect())
+                    .collect(),
+            ),
+            RegisterData::I32(data) => Self::Int32(data.clone()),
+            RegisterData::F32(data) => Self::Float32(data.clone()),
+            RegisterData::Complex64(data) => Self::Complex128(
+                data.iter()
+                    .map(|row| row.iter().map(|value| [value.re, value.im]).collect())
+                    .collect(),
+            ),
+            RegisterData::I64(data) => Self::Int64(data.clone()),
+        }
+    }
+}
+
+impl From<RegisterV2> for RegisterData {
+    fn from(register: RegisterV2) -> Self {
+        match register {
+            RegisterV2::Int8(data) => Self::I8(data),
+            RegisterV2::Int16(data) => Self::I16(data),
+            RegisterV2::Float64(data) => Self::F64(data),
+            RegisterV2::Complex64(data) => Self::Complex32(
+                data.into_iter()
+                    .map(|row| {
+                        row.into_iter()
+                            .map(|[re, im]| Complex32::new(re, im))
+                            .collect()
+                    })
+                    .collect(),
+            ),
+            RegisterV2::Int32(data) => Self::I32(data),
+            RegisterV2::Float32(data) => Self::F32(data),
+            RegisterV2::Complex128(data) => Self::Complex64(
+                data.into_iter()
+                    .map(|row| {
+                        row.into_iter()
+                            .map(|[re, im]| Complex64::new(re, im))
+                            .collect()
+                    })
+                    .collect(),
+            ),
+            RegisterV2::Int64(data) => Self::I64(data),
+        }
+    }
+}
+
+impl From<&ReadoutValues> for ValuesV2 {
+    fn from(values: &ReadoutValues) -> Self {
+        match values {
+            ReadoutValues::Integer(data) => Self::Int64(data.clone()),
+            ReadoutValues::Real(data) => Self::Float64(data.clone()),
+            ReadoutValues::Complex(data) => {
+                Self::Complex128(data.iter().map(|value| [value.re, value.im]).collect())
+            }
+        }
+    }
+}
+
+impl TryFrom<ValuesV2> for ReadoutValues {
+    type Error = Error;
+
+    fn try_from(values: ValuesV2) -> Result<Self, Error> {
+        Ok(match values {
+            ValuesV2::Int64(data) => Self::Integer(data),
+            ValuesV2::Float64(data) => Self::Real(data),
+            ValuesV2::Complex128(data) => Self::Complex(
+                data.into_iter()
+                    .map(|[re, im]| Complex64::new(re, im))
+                    .collect(),
+            ),
+            ValuesV2::Uint8(_) => return Err(invalid_dtype("readout_values", "uint8")),
+        })
+    }
+}
+
+impl From<&MemoryValues> for ValuesV2 {
+    fn from(values: &MemoryValues) -> Self {
+        match values {
+            MemoryValues::Binary(data) => Self::Uint8(data.clone()),
+            MemoryValues::Integer(data) => Self::Int64(data.clone()),
+            MemoryValues::Real(data) => Self::Float64(data.clone()),
+        }
+    }
+}
+
+impl TryFrom<ValuesV2> for MemoryValues {
+    type Error = Error;
+
+    fn try_from(values: ValuesV2) -> Result<Self, Error> {
+        Ok(match values {
+            ValuesV2::Uint8(data) => Self::Binary(data),
+            ValuesV2::Int64(data) => Self::Integer(data),
+            ValuesV2::Float64(data) => Self::Real(data),
+            ValuesV2::Complex128(_) => return Err(invalid_dtype("memory_values", "complex128")),
+        })
+    }
+}
+
+fn invalid_dtype(field: &str, dtype: &str) -> Error {
+    Error::Json(serde::de::Error::custom(format!(
+        "{field} may not have dtype {dtype}"
+    )))
+}
+
+#[cfg(test)]
+mod describe_schema {
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    use num::complex::Complex64;
+    use serde_json::{json, Value};
+
+    use super::{Error, JSON_SCHEMA, SCHEMA_VERSION};
+    use crate::{
+        qpu::{result_data::MemoryValues, QpuResultData, ReadoutValues},
+        qvm::{QvmResultData, Wavefunction},
+        ExecutionData, RegisterData, ResultData,
+    };
+
+    fn qpu_data() -> ExecutionData {
+        ExecutionData {
+            result_data: ResultData::Qpu(QpuResultData::from_mappings_and_values(
+                HashMap::from([("ro[0]".to_string(), "q0".to_string())]),
+                HashMap::from([
+                    ("q0".to_string(), ReadoutValues::Integer(vec![0, 1])),
+                    (
+                        "q0_iq".to_string(),
+                        ReadoutValues::Complex(vec![Complex64::new(0.5, -0.5)]),
+                    ),
+                ]),
+                HashMap::from([("theta".to_string(), MemoryValues::Real(vec![1.5]))]),
+            )),
+            duration: Some(Duration::from_micros(1234)),
+            timings: None,
+        }
+    }
+
+    #[test]
+    fn it_round_trips_every_kind_of_result() {
+        let qvm = ExecutionData {
+            result_data: ResultData::Qvm(QvmResultData::from_memory_map(HashMap::from([
+                ("bits".to_string(), RegisterData::I8(vec![vec![0, 1]])),
+                ("ints".to_string(), RegisterData::I16(vec![vec![0, 1]])),
+                ("reals".to_string(), RegisterData::F64(vec![vec![0.5]])),
+                ("longs".to_string(), RegisterData::I32(vec![vec![1 << 20]])),
+                ("counts".to_string(), RegisterData::I64(vec![vec![1 << 40]])),
+                ("singles".to_string(), RegisterData::F32(vec![vec![0.25]])),
+                (
+                    "iq".to_string(),
+                    RegisterData::Complex64(vec![vec![Complex64::new(0.5, -0.5)]]),
+                ),
+            ]))),
+            duration: None,
+            timings: None,
+        };
+        let wavefunction = ExecutionData {
+            result_data: ResultData::Wavefunction(
+                Wavefunction::from_amplitudes(vec![Complex64::new(1.0, 0.0); 2]).unwrap(),
+            ),
+            duration: None,
+            timings: None,
+        };
+        for data in [qvm, qpu_data(), wavefunction] {
+            let json = data.to_json().unwrap();
+            assert_eq!(ExecutionData::from_json(&json).unwrap(), data);
+            let json = data.result_data.to_json().unwrap();
+            assert_eq!(ResultData::from_json(&json).unwrap(), data.result_data);
+        }
+    }
+
+    #[test]
+    fn it_tags_values_with_their_dtype() {
+        let json: Value = serde_json::from_str(&qpu_data().to_json().unwrap()).unwrap();
+        assert_eq!(json["schema_version"], SCHEMA_VERSION);
+        assert_eq!(json["duration_microseconds"], 1234);
+        assert_eq!(json["result_data"]["kind"], "qpu");
+        assert_eq!(
+            json["result_data"]["readout_values"]["q0_iq"],
+            json!({"dtype": "complex128", "data": [[0.5, -0.5]]})
+        );
+        assert_eq!(
+            json["result_data"]["memory_values"]["theta"],
+            json!({"dtype": "float64", "data": [1.5]})
+        );
+    }
+
+    #[test]
+    fn it_reads_unversioned_documents() {
+        let data = qpu_data();
+        let legacy = serde_json::to_string(&data).unwrap();
+        assert_eq!(ExecutionData::from_json(&legacy).unwrap(), data);
+    }
+
+    #[test]
+    fn it_reads_unversioned_complex_registers_at_full_precision() {
+        let data = ResultData::Qvm(QvmResultData::from_memory_map(HashMap::from([(
+            "iq".to_string(),
+            RegisterData::Complex64(vec![vec![Complex64::new(0.1, -0.2)]]),
+        )])));
+        let legacy = serde_json::to_string(&data).unwrap();
+        assert_eq!(ResultData::from_json(&legacy).unwrap(), data);
+    }
+
+    #[test]
+    fn it_reads_version_1_documents() {
+        let json = json!({
+            "schema_version": 1,
+            "duration_microseconds": null,
+            "result_data": {
+                "kind": "qvm",
+                "memory": { "ro": { "dtype": "int8", "data": [[0, 1]] } }
+            }
+        })
+        .to_string();
+        let data = ExecutionData::from_json(&json).unwrap();
+        assert_eq!(
+            data.result_data,
+            ResultData::Qvm(QvmResultData::from_memory_map(HashMap::from([(
+                "ro".to_string(),
+                RegisterData::I8(vec![vec![0, 1]])
+            )])))
+        );
+    }
+
+    #[test]
+    fn it_rejects_newer_versions() {
+        let json = json!({"schema_version": SCHEMA_VERSION + 1}).to_string();
+        assert!(matches!(
+            ExecutionData::from_json(&json),
+            Err(Error::UnsupportedVersion { found, supported: SCHEMA_VERSION }) if found == SCHEMA_VERSION + 1
+        ));
+    }
+
+    #[test]
+    fn it_publishes_its_schema() {
+        let schema: Value = serde_json::from_str(JSON_SCHEMA).unwrap();
+        assert_eq!(
+            schema["properties"]["schema_version"]["const"],
+            SCHEMA_VERSION
+        );
+    }
+}
@@ -0,0 +1,664 @@
+//! Decoding QPU results into temporary files instead of the heap, so that jobs whose results
+//! don't fit in memory can still be processed.
+//!
+//! Results are spilled when [`ExecutionOptions::spill_threshold_bytes`](crate::qpu::api::ExecutionOptions::spill_threshold_bytes)
+//! is set and the decoded readout values would be larger than it. They are then returned as
+//! [`ResultData::Spilled`](crate::ResultData::Spilled), with each register decoded straight into
+//! a [`FileMatrix`] rather than into [`ReadoutValues`] first. A [`FileMatrix`] is read back through
+//! ordinary file I/O a block of rows at a time, see [`FileMatrix::read_rows`], so only the rows in
+//! use are ever held in memory. The temporary files are deleted when the last copy of the
+//! [`SpilledResultData`] is dropped.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Seek, SeekFrom, Write};
+use std::marker::PhantomData;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use enum_as_inner::EnumAsInner;
+use ndarray::{Array1, Array2, ShapeBuilder};
+use num::complex::Complex64;
+
+use super::{DType, RegisterMap, RegisterMatrix, RegisterMatrixConversionError, Shape};
+use crate::qpu::result_data::{MemoryValues, ReadoutValues};
+
+#[cfg(feature = "qpu")]
+use std::collections::hash_map::Entry;
+
+#[cfg(feature = "qpu")]
+use qcs_api_client_grpc::models::controller::{
+    readout_values::Values as ControllerValues, DataValue as ControllerMemoryValues,
+    ReadoutValues as ControllerReadoutValues,
+};
+
+/// Errors that may occur when spilling results to disk.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// A temporary file couldn't be created or written.
+    #[error("could not write a temporary file for results in {}: {source}", .directory.display())]
+    Io {
+        /// The directory the file was to be created in.
+        directory: PathBuf,
+        /// The underlying error.
+        source: io::Error,
+    },
+    /// The readout values don't form a rectangular matrix, so they can't be decoded into one.
+    #[error(transparent)]
+    Conversion(#[from] RegisterMatrixConversionError),
+}
+
+/// A type of value which can be stored in a [`FileMatrix`].
+pub trait SpilledElement: Copy + private::Sealed {}
+
+impl SpilledElement for i64 {}
+impl SpilledElement for f64 {}
+impl SpilledElement for Complex64 {}
+
+mod private {
+    use std::io::{self, Write};
+
+    use num::complex::Complex64;
+
+    /// How a [`SpilledElement`](super::SpilledElement) is laid out in a file.
+    pub trait Sealed: Sized {
+        /// The number of bytes each value takes up.
+        const SIZE: usize;
+
+        /// Write the value as [`Self::SIZE`] little-endian bytes.
+        fn write_to(self, writer: &mut impl Write) -> io::Result<()>;
+
+        /// Read a value from the [`Self::SIZE`] bytes written by [`Sealed::write_to`].
+        fn read_from(bytes: &[u8]) -> Self;
+    }
+
+    /// The eight bytes at the start of `bytes`.
+    fn word(bytes: &[u8]) -> [u8; 8] {
+        let mut word = [0; 8];
+        word.copy_from_slice(&bytes[..8]);
+        word
+    }
+
+    impl Sealed for i64 {
+        const SIZE: usize = 8;
+
+        fn write_to(self, writer: &mut impl Write) -> io::Result<()> {
+            writer.write_all(&self.to_le_bytes())
+        }
+
+        fn read_from(bytes: &[u8]) -> Self {
+            Self::from_le_bytes(word(bytes))
+        }
+    }
+
+    impl Sealed for f64 {
+        const SIZE: usize = 8;
+
+        fn write_to(self, writer: &mut impl Write) -> io::Result<()> {
+            writer.write_all(&self.to_le_bytes())
+        }
+
+        fn read_from(bytes: &[u8]) -> Self {
+            Self::from_le_bytes(word(bytes))
+        }
+    }
+
+    impl Sealed for Complex64 {
+        const SIZE: usize = 16;
+
+        fn write_to(self, writer: &mut impl Write) -> io::Result<()> {
+            self.re.write_to(writer)?;
+            self.im.write_to(writer)
+        }
+
+        fn read_from(bytes: &[u8]) -> Self {
+            Self::new(f64::read_from(bytes), f64::read_from(&bytes[8..]))
+        }
+    }
+}
+
+/// A two dimensional matrix stored in a temporary file, one column after another.
+///
+/// The matrix is read back with ordinary file I/O, either whole with [`FileMatrix::read_all`] or a
+/// block of rows at a time with [`FileMatrix::read_rows`].
+pub struct FileMatrix<T> {
+    // Reads seek before reading, so they take turns.
+    file: Mutex<File>,
+    rows: usize,
+    cols: usize,
+    element: PhantomData<T>,
+}
+
+impl<T: SpilledElement> FileMatrix<T> {
+    /// Create a matrix of zeros with `rows` rows and `cols` columns, in a new temporary file in
+    /// `directory`.
+    #[cfg_attr(not(feature = "qpu"), allow(dead_code))]
+    pub(crate) fn zeros(rows: usize, cols: usize, directory: &Path) -> io::Result<Self> {
+        let len = rows
+            .checked_mul(cols)
+            .and_then(|elements| elements.checked_mul(T::SIZE))
+            .ok_or_else(too_large)?;
+        let file = tempfile::tempfile_in(directory)?;
+        // The file is extended with zeros, which decode as zero for every `SpilledElement`.
+        file.set_len(u64::try_from(len).map_err(|_| too_large())?)?;
+        Ok(Self {
+            file: Mutex::new(file),
+            rows,
+            cols,
+            element: PhantomData,
+        })
+    }
+
+    /// The number of rows and columns in the matrix.
+    #[must_use]
+    pub fn dim(&self) -> (usize, usize) {
+        (self.rows, self.cols)
+    }
+
+    /// Read the values in `rows` of column `column` into memory.
+    ///
+    /// # Errors
+    ///
+    /// Fails if there is no such column or rows, or the file can't be read.
+    pub fn read_column(&self, column: usize, rows: Range<usize>) -> io::Result<Array1<T>> {
+        self.read_values(column, rows).map(Array1::from)
+    }
+
+    /// Read the rows in `rows`, such as a block of shots, into memory.
+    ///
+    /// # Errors
+    ///
+    /// Fails if there are no such rows, or the file can't be read.
+    pub fn read_rows(&self, rows: Range<usize>) -> io::Result<Array2<T>> {
+        self.check_rows(&rows)?;
+        let mut values = Vec::with_capacity(rows.len() * self.cols);
+        for column in 0..self.cols {
+            values.extend(self.read_values(column, rows.clone())?);
+        }
+        // The columns were read one after another.
+        Array2::from_shape_vec((rows.len(), self.cols).f(), values)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+
+    /// Read the whole matrix into memory.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file can't be read.
+    pub fn read_all(&self) -> io::Result<Array2<T>> {
+        self.read_rows(0..self.rows)
+    }
+
+    /// Set column `column` to `values`, if it has the same number of values as the matrix has rows.
+    /// Returns whether it did.
+    #[cfg_attr(not(feature = "qpu"), allow(dead_code))]
+    fn write_column(
+        &mut self,
+        column: usize,
+        values: impl ExactSizeIterator<Item = T>,
+    ) -> io::Result<bool> {
+        if values.len() != self.rows || column >= self.cols {
+            return Ok(false);
+        }
+        let offset = self.offset(column, 0)?;
+        let file = self.file.get_mut().unwrap_or_else(PoisonError::into_inner);
+        file.seek(SeekFrom::Start(offset))?;
+        let mut writer = BufWriter::new(file);
+        for value in values {
+            value.write_to(&mut writer)?;
+        }
+        writer.flush()?;
+        Ok(true)
+    }
+
+    /// Read the values in `rows` of column `column`, which are next to each other in the file.
+    fn read_values(&self, column: usize, rows: Range<usize>) -> io::Result<Vec<T>> {
+        if column >= self.cols {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "there is no column {column} in a matrix of {} columns",
+                    self.cols
+                ),
+            ));
+        }
+        self.check_rows(&rows)?;
+        let mut bytes = vec![0; rows.len() * T::SIZE];
+        let mut file = self.lock();
+        file.seek(SeekFrom::Start(self.offset(column, rows.start)?))?;
+        file.read_exact(&mut bytes)?;
+        Ok(bytes.chunks_exact(T::SIZE).map(T::read_from).collect())
+    }
+
+    /// Fail unless `rows` are within the matrix.
+    fn check_rows(&self, rows: &Range<usize>) -> io::Result<()> {
+        if rows.start <= rows.end && rows.end <= self.rows {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("rows {rows:?} are outside a matrix of {} rows", self.rows),
+            ))
+        }
+    }
+
+    /// The position in the file of the value at `row` of column `column`.
+    fn offset(&self, column: usize, row: usize) -> io::Result<u64> {
+        column
+            .checked_mul(self.rows)
+            .and_then(|offset| offset.checked_add(row))
+            .and_then(|offset| offset.checked_mul(T::SIZE))
+            .and_then(|offset| u64::try_from(offset).ok())
+            .ok_or_else(too_large)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, File> {
+        self.file.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// The error for a matrix too large to address in a file.
+fn too_large() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, "the matrix is too large")
+}
+
+impl<T> fmt::Debug for FileMatrix<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FileMatrix")
+            .field("rows", &self.rows)
+            .field("cols", &self.cols)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T: SpilledElement + PartialEq> PartialEq for FileMatrix<T> {
+    /// Matrices are equal if they have the same values, and both can be read.
+    fn eq(&self, other: &Self) -> bool {
+        self.dim() == other.dim()
+            && matches!((self.read_all(), other.read_all()), (Ok(this), Ok(other)) if this == other)
+    }
+}
+
+/// The spilled counterpart of a [`RegisterMatrix`].
+#[derive(Debug, EnumAsInner, PartialEq)]
+pub enum SpilledRegisterMatrix {
+    /// Integer register
+    Integer(FileMatrix<i64>),
+    /// Real numbered register
+    Real(FileMatrix<f64>),
+    /// Complex numbered register
+    Complex(FileMatrix<Complex64>),
+}
+
+impl SpilledRegisterMatrix {
+    /// The [`Shape`] of the matrix.
+    #[must_use]
+    pub fn shape(&self) -> Shape {
+        let ((rows, cols), dtype) = match self {
+            Self::Integer(matrix) => (matrix.dim(), DType::Int64),
+            Self::Real(matrix) => (matrix.dim(), DType::Float64),
+            Self::Complex(matrix) => (matrix.dim(), DType::Complex128),
+        };
+        Shape { rows, cols, dtype }
+    }
+
+    /// The values in column `index`, that is, of one memory reference on every shot, read into
+    /// memory. Returns `None` if there is no such column.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file can't be read.
+    pub fn column(&self, index: usize) -> io::Result<Option<ReadoutValues>> {
+        let (rows, cols) = (self.shape().rows, self.shape().cols);
+        if index >= cols {
+            return Ok(None);
+        }
+        Ok(Some(match self {
+            Self::Integer(matrix) => {
+                ReadoutValues::Integer(matrix.read_column(index, 0..rows)?.to_vec())
+            }
+            Self::Real(matrix) => ReadoutValues::Real(matrix.read_column(index, 0..rows)?.to_vec()),
+            Self::Complex(matrix) => {
+                ReadoutValues::Complex(matrix.read_column(index, 0..rows)?.to_vec())
+            }
+        }))
+    }
+
+    /// Read the rows in `rows`, such as a block of shots, into memory as a [`RegisterMatrix`].
+    ///
+    /// # Errors
+    ///
+    /// Fails if there are no such rows, or the file can't be read.
+    pub fn read_rows(&self, rows: Range<usize>) -> io::Result<RegisterMatrix> {
+        Ok(match self {
+            Self::Integer(matrix) => RegisterMatrix::Integer(matrix.read_rows(rows)?),
+            Self::Real(matrix) => RegisterMatrix::Real(matrix.read_rows(rows)?),
+            Self::Complex(matrix) => RegisterMatrix::Complex(matrix.read_rows(rows)?),
+        })
+    }
+
+    /// Read the whole matrix into memory as a [`RegisterMatrix`].
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file can't be read.
+    pub fn to_register_matrix(&self) -> io::Result<RegisterMatrix> {
+        self.read_rows(0..self.shape().rows)
+    }
+}
+
+/// The spilled counterpart of a [`RegisterMap`]: a [`SpilledRegisterMatrix`] for each register.
+#[derive(Debug, Default, PartialEq)]
+pub struct SpilledRegisterMap(pub HashMap<String, SpilledRegisterMatrix>);
+
+impl SpilledRegisterMap {
+    /// Returns the [`SpilledRegisterMatrix`] for the given register, if it exists.
+    #[must_use]
+    pub fn get_register_matrix(&self, register_name: &str) -> Option<&SpilledRegisterMatrix> {
+        self.0.get(register_name)
+    }
+
+    /// The [`Shape`] of each register.
+    #[must_use]
+    pub fn shapes(&self) -> HashMap<String, Shape> {
+        self.0
+            .iter()
+            .map(|(name, matrix)| (name.clone(), matrix.shape()))
+            .collect()
+    }
+
+    /// Read every register into memory as a [`RegisterMap`].
+    ///
+    /// # Errors
+    ///
+    /// Fails if a file can't be read.
+    pub fn to_register_map(&self) -> io::Result<RegisterMap> {
+        self.0
+            .iter()
+            .map(|(name, matrix)| Ok((name.clone(), matrix.to_register_matrix()?)))
+            .collect::<io::Result<_>>()
+            .map(RegisterMap)
+    }
+}
+
+/// Results from the QPU whose readout values were decoded into temporary files.
+///
+/// Cloning is cheap: clones share the same files.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpilledResultData {
+    registers: Arc<SpilledRegisterMap>,
+    mappings: HashMap<String, String>,
+    memory_values: HashMap<String, MemoryValues>,
+}
+
+impl SpilledResultData {
+    /// The readout values of each register, with a row per shot and a column per memory reference.
+    #[must_use]
+    pub fn register_map(&self) -> &SpilledRegisterMap {
+        &self.registers
+    }
+
+    /// The readout node (ie. "q0") which produced the values read out to each memory reference
+    /// (ie. "ro\[0\]"), as returned by the QPU. See
+    /// [`QpuResultData::mappings`](crate::qpu::QpuResultData::mappings).
+    #[must_use]
+    pub fn mappings(&self) -> &HashMap<String, String> {
+        &self.mappings
+    }
+
+    /// The final contents of each memory region, keyed on region name. These are small, and so
+    /// are kept in memory.
+    #[must_use]
+    pub fn memory_values(&self) -> &HashMap<String, MemoryValues> {
+        &self.memory_values
+    }
+
+    /// The final contents of the memory region `name`, if the QPU returned it.
+    #[must_use]
+    pub fn memory_region(&self, name: &str) -> Option<&MemoryValues> {
+        self.memory_values.get(name)
+    }
+
+    /// Decode results from the controller service into temporary files in `directory`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a file can't be created or written, or if the readout values don't form a
+    /// rectangular matrix for every register, see [`RegisterMap`].
+    #[cfg(feature = "qpu")]
+    pub(crate) fn from_controller_mappings_and_values(
+        mappings: &HashMap<String, String>,
+        readout_values: &HashMap<String, ControllerReadoutValues>,
+        memory_values: &HashMap<String, ControllerMemoryValues>,
+        directory: &Path,
+    ) -> Result<Self, Error> {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(directory = %directory.display(), "spilling QPU results to disk");
+
+        let io_error = |source| Error::Io {
+            directory: directory.to_path_buf(),
+            source,
+        };
+
+        let references = super::sorted_readout_references(mappings, readout_values)?;
+        let mut registers = HashMap::new();
+        // Iterate in reverse so that the first reference to each register has the highest index,
+        // and so gives the number of columns.
+        for (reference, values) in references.into_iter().rev() {
+            let matrix = match registers.entry(reference.name.clone()) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    let cols = reference.index + 1;
+                    entry.insert(match &values.values {
+                        Some(ControllerValues::ComplexValues(v)) => SpilledRegisterMatrix::Complex(
+                            FileMatrix::zeros(v.values.len(), cols, directory).map_err(io_error)?,
+                        ),
+                        Some(ControllerValues::IntegerValues(v)) => SpilledRegisterMatrix::Integer(
+                            FileMatrix::zeros(v.values.len(), cols, directory).map_err(io_error)?,
+                        ),
+                        None => SpilledRegisterMatrix::Integer(
+                            FileMatrix::zeros(0, cols, directory).map_err(io_error)?,
+                        ),
+                    })
+                }
+            };
+
+            let written = match (matrix, &values.values) {
+                (SpilledRegisterMatrix::Integer(m), Some(ControllerValues::IntegerValues(v))) => {
+                    m.write_column(reference.index, v.values.iter().copied().map(i64::from))
+                }
+                (SpilledRegisterMatrix::Complex(m), Some(ControllerValues::ComplexValues(v))) => m
+                    .write_column(
+                        reference.index,
+                        v.values
+                            .iter()
+                            .map(|c| Complex64::new(c.real.into(), c.imaginary.into())),
+                    ),
+                (SpilledRegisterMatrix::Integer(m), None) => {
+                    m.write_column(reference.index, std::iter::empty())
+                }
+                _ => Ok(false),
+            };
+            if !written.map_err(io_error)? {
+                return Err(RegisterMatrixConversionError::InvalidShape {
+                    register: reference.name,
+                }
+                .into());
+            }
+        }
+
+        Ok(Self {
+            registers: Arc::new(SpilledRegisterMap(registers)),
+            mappings: mappings.clone(),
+            memory_values: crate::qpu::result_data::memory_values_from_controller(memory_values),
+        })
+    }
+}
+
+/// The number of bytes the readout values in `readout_values` take up once decoded.
+#[cfg(feature = "qpu")]
+pub(crate) fn decoded_len(readout_values: &HashMap<String, ControllerReadoutValues>) -> usize {
+    readout_values
+        .values()
+        .map(|values| match &values.values {
+            Some(ControllerValues::IntegerValues(v)) => v.values.len() * std::mem::size_of::<i64>(),
+            Some(ControllerValues::ComplexValues(v)) => {
+                v.values.len() * std::mem::size_of::<Complex64>()
+            }
+            None => 0,
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod describe_file_matrix {
+    use ndarray::arr2;
+    use num::complex::Complex64;
+
+    use super::FileMatrix;
+
+    #[test]
+    fn it_reads_back_blocks_of_rows() {
+        let directory = tempfile::tempdir().unwrap();
+        let mut matrix = FileMatrix::<i64>::zeros(3, 2, directory.path()).unwrap();
+        assert!(matrix.write_column(1, vec![4, 5, 6].into_iter()).unwrap());
+        assert!(!matrix.write_column(0, vec![1, 2].into_iter()).unwrap());
+        assert!(!matrix.write_column(2, vec![1, 2, 3].into_iter()).unwrap());
+
+        assert_eq!(matrix.read_all().unwrap(), arr2(&[[0, 4], [0, 5], [0, 6]]));
+        assert_eq!(matrix.read_rows(1..3).unwrap(), arr2(&[[0, 5], [0, 6]]));
+        assert!(matrix.read_rows(2..4).is_err());
+    }
+
+    #[test]
+    fn it_round_trips_complex_values() {
+        let directory = tempfile::tempdir().unwrap();
+        let mut matrix = FileMatrix::<Complex64>::zeros(2, 1, directory.path()).unwrap();
+        let values = [Complex64::new(1.5, -2.0), Complex64::new(-0.25, 8.0)];
+        assert!(matrix.write_column(0, values.iter().copied()).unwrap());
+        assert_eq!(matrix.read_column(0, 0..2).unwrap().to_vec(), values);
+    }
+}
+
+#[cfg(all(test, feature = "qpu"))]
+mod describe_spilled_result_data {
+    use std::collections::HashMap;
+
+    use maplit::hashmap;
+    use ndarray::arr2;
+    use num::complex::Complex64;
+    use qcs_api_client_grpc::models::controller::{
+        readout_values::Values, Complex64 as ControllerComplex64, Complex64ReadoutValues,
+        IntegerReadoutValues, ReadoutValues,
+    };
+
+    use super::{decoded_len, Error, SpilledResultData};
+    use crate::qpu::QpuResultData;
+    use crate::{RegisterMatrix, RegisterMatrixConversionError, ResultData};
+
+    fn integers(values: Vec<i32>) -> ReadoutValues {
+        ReadoutValues {
+            values: Some(Values::IntegerValues(IntegerReadoutValues { values })),
+        }
+    }
+
+    fn mappings() -> HashMap<String, String> {
+        hashmap! {
+            "ro[0]".to_string() => "q0".to_string(),
+            "ro[1]".to_string() => "q1".to_string(),
+            "iq[0]".to_string() => "q0_iq".to_string(),
+        }
+    }
+
+    fn readout_values() -> HashMap<String, ReadoutValues> {
+        hashmap! {
+            "q0".to_string() => integers(vec![0, 1, 1]),
+            "q1".to_string() => integers(vec![1, 1, 0]),
+            "q0_iq".to_string() => ReadoutValues {
+                values: Some(Values::ComplexValues(Complex64ReadoutValues {
+                    values: vec![
+                        ControllerComplex64 { real: 1.0, imaginary: -1.0 },
+                        ControllerComplex64 { real: 0.5, imaginary: 0.5 },
+                        ControllerComplex64 { real: 0.0, imaginary: 2.0 },
+                    ],
+                })),
+            },
+        }
+    }
+
+    #[test]
+    fn it_decodes_registers_into_files() {
+        let directory = tempfile::tempdir().unwrap();
+        let data = SpilledResultData::from_controller_mappings_and_values(
+            &mappings(),
+            &readout_values(),
+            &HashMap::new(),
+            directory.path(),
+        )
+        .unwrap();
+
+        assert_eq!(data.mappings(), &mappings());
+        let ro = data.register_map().get_register_matrix("ro").unwrap();
+        assert_eq!(
+            ro.as_integer().unwrap().read_all().unwrap(),
+            arr2(&[[0, 1], [1, 1], [1, 0]])
+        );
+        assert_eq!(
+            ro.read_rows(2..3).unwrap(),
+            RegisterMatrix::Integer(arr2(&[[1, 0]]))
+        );
+        let iq = data.register_map().get_register_matrix("iq").unwrap();
+        assert_eq!(iq.shape().rows, 3);
+        assert_eq!(
+            iq.as_complex().unwrap().read_all().unwrap()[[2, 0]],
+            Complex64::new(0.0, 2.0)
+        );
+
+        // Spilling doesn't change what the results convert to.
+        let in_memory = ResultData::Qpu(QpuResultData::from_controller_mappings_and_values(
+            &mappings(),
+            &readout_values(),
+            &HashMap::new(),
+        ));
+        let spilled = ResultData::Spilled(data);
+        assert_eq!(
+            spilled.to_register_map().unwrap(),
+            in_memory.to_register_map().unwrap()
+        );
+        // Spilled results are written as QPU results, which are read back into memory.
+        let json = spilled.to_json().unwrap();
+        assert_eq!(
+            ResultData::from_json(&json)
+                .unwrap()
+                .to_register_map()
+                .unwrap(),
+            in_memory.to_register_map().unwrap()
+        );
+    }
+
+    #[test]
+    fn it_rejects_jagged_readout_values() {
+        let directory = tempfile::tempdir().unwrap();
+        let mut readout_values = readout_values();
+        readout_values.insert("q1".to_string(), integers(vec![1]));
+        let error = SpilledResultData::from_controller_mappings_and_values(
+            &mappings(),
+            &readout_values,
+            &HashMap::new(),
+            directory.path(),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error,
+            Error::Conversion(RegisterMatrixConversionError::InvalidShape { register }) if register == "ro"
+        ));
+    }
+
+    #[test]
+    fn it_measures_the_decoded_size() {
+        assert_eq!(decoded_len(&readout_values()), 6 * 8 + 3 * 16);
+    }
+}
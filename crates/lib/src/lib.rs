@@ -48,25 +48,79 @@
 //! This crate is the primary Rust API for interacting with Rigetti products. Specifically, this
 //! crate allows you to run Quil programs against real QPUs or a QVM
 //! using [`Executable`].
+//!
+//! # Features
+//!
+//! All of the following are enabled by default. Users who only need a subset, such as running on
+//! a QVM, can disable default features and pick the ones they need to trim the dependency tree.
+//!
+//! - `qpu`: translating, submitting, and retrieving results for programs run on a QPU (gRPC).
+//! - `quilc-rpcq`: compiling programs with a `quilc` server over RPCQ (ZeroMQ).
+//! - `qvm-http`: running programs on a QVM server over HTTP.
+//!
+//! `qpu`, `quilc-rpcq`, and `libquil` each enable `qcs-api`, the QCS REST API client, which
+//! fetches QPU ISAs and is needed to compile programs for one. A build with only `qvm-http` doesn't
+//! depend on it.
+//!
+//! `libquil` (off by default) enables compilation and simulation using the `libquil` shared
+//! libraries instead of servers.
+//!
+//! `blocking` (off by default) enables [`blocking`], a synchronous API for programs which don't
+//! otherwise need an async runtime.
+//!
+//! `local-services` (off by default) enables [`local_services`], which launches local `quilc`
+//! and QVM servers for tests and examples.
+//!
+//! `testing` (off by default) enables [`testing`], with stand-ins for QCS, QPUs, and the QVM for
+//! testing code which uses this crate.
 
 // Re-export quil_rs so that all downstream crates can ensure that they are
 // using the same version.
 pub use quil_rs;
 
-pub use executable::{Error, Executable, ExecutionResult, JobHandle, Service};
+pub use bit_order::BitOrder;
+#[cfg(feature = "qpu")]
+pub use executable::{
+    execute_on_many, CompilationArtifacts, CompilationPlan, ConnectionPlan, EstimatedSizes,
+    ExecuteOnManyOptions, ExecuteOnManyOptionsBuilder, ExecutionPlan, Experiment,
+    ExperimentResults, JobHandle, TranslationPlan,
+};
+pub use executable::{
+    Error, Executable, ExecutionResult, RewiringStrategy, Service, SharedExecutable, ShotMemory,
+};
+pub use execution_data::export;
+pub use execution_data::interop;
+pub use execution_data::report;
+pub use execution_data::schema;
+pub use execution_data::spill;
 pub use execution_data::{
-    ExecutionData, RegisterMap, RegisterMatrix, RegisterMatrixConversionError, ResultData,
+    DType, ExecutionData, ExecutionTimings, RawResultData, RegisterMap, RegisterMatrix,
+    RegisterMatrixConversionError, RegisterMatrixView, ResultData, Shape, SparseRegister,
+    SparseRegisterMap,
 };
 pub use register_data::RegisterData;
 
+pub mod bit_order;
+#[cfg(feature = "blocking")]
+pub mod blocking;
 pub mod client;
+#[cfg(feature = "qcs-api")]
 pub mod compiler;
+#[cfg(all(feature = "qpu", feature = "quilc-rpcq", feature = "qvm-http"))]
 pub mod diagnostics;
 mod executable;
 mod execution_data;
+#[cfg(feature = "local-services")]
+pub mod local_services;
+pub mod metrics;
 pub mod qpu;
 pub mod qvm;
+mod random;
 mod register_data;
+#[cfg(test)]
+mod test_support;
+#[cfg(feature = "testing")]
+pub mod testing;
 
 /// Build information about the crate and environment in which it was built.
 pub mod build_info {
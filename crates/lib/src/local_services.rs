@@ -0,0 +1,472 @@
+//! Launch local `quilc` and QVM servers, for tests and examples which would otherwise fail with
+//! [`Error::Connection`](crate::Error::Connection) because nothing is running.
+//!
+//! [`LocalServices::start`] runs `quilc -S` and `qvm -S`, either as processes or as Docker
+//! containers, waits until each answers a request for its version, and stops them when dropped.
+//!
+//! ```no_run
+//! use qcs::local_services::{LocalServices, LocalServicesOptions};
+//! use qcs::{qvm, Executable};
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let services = LocalServices::start(LocalServicesOptions::default())
+//!     .await
+//!     .expect("quilc and qvm should be installed");
+//! let qvm_client = qvm::http::HttpClient::from(&services.client());
+//! let data = Executable::from_quil("DECLARE ro BIT\nX 0\nMEASURE 0 ro")
+//!     .execute_on_qvm(&qvm_client)
+//!     .await
+//!     .unwrap();
+//! // Both servers are stopped here, when `services` is dropped.
+//! # }
+//! ```
+
+use std::path::PathBuf;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::time::{Duration, Instant};
+
+use derive_builder::Builder;
+use qcs_api_client_common::configuration::ClientConfiguration;
+
+use crate::client::Qcs;
+use crate::compiler::{quilc::Client as _, rpcq};
+use crate::qvm::{self, Client as _, QvmOptions};
+use crate::Service;
+
+/// The port the QVM listens on inside its Docker image.
+const QVM_CONTAINER_PORT: u16 = 5000;
+
+/// The port quilc listens on inside its Docker image.
+const QUILC_CONTAINER_PORT: u16 = 5555;
+
+/// How long to wait between checks of whether a service is ready.
+const READINESS_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How a local service is run.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Launcher {
+    /// Run an executable, found on the `PATH` unless `program` is a path.
+    Process {
+        /// The executable to run, e.g. `qvm`.
+        program: PathBuf,
+    },
+    /// Run a Docker container with the `docker` command.
+    Docker {
+        /// The image to run, e.g. `rigetti/qvm`.
+        image: String,
+    },
+}
+
+impl Launcher {
+    /// Run the executable `program`, found on the `PATH` unless it's a path.
+    #[must_use]
+    pub fn process(program: impl Into<PathBuf>) -> Self {
+        Self::Process {
+            program: program.into(),
+        }
+    }
+
+    /// Run a Docker container from `image`.
+    #[must_use]
+    pub fn docker(image: impl Into<String>) -> Self {
+        Self::Docker {
+            image: image.into(),
+        }
+    }
+
+    /// The command which starts `service` listening on `port` of the host.
+    fn command(&self, service: Service, port: u16) -> Command {
+        let port = port.to_string();
+        match self {
+            Self::Process { program } => {
+                let mut command = Command::new(program);
+                command.args(["-S", "-p", &port]);
+                command
+            }
+            Self::Docker { image } => {
+                let container_port = match service {
+                    Service::Qvm => QVM_CONTAINER_PORT,
+                    _ => QUILC_CONTAINER_PORT,
+                };
+                let mut command = Command::new("docker");
+                command
+                    .args(["run", "--rm", "--detach", "--publish"])
+                    .arg(format!("{port}:{container_port}"))
+                    .arg(image)
+                    .arg("-S");
+                command
+            }
+        }
+    }
+}
+
+/// Options for [`LocalServices::start`].
+#[derive(Builder, Clone, Debug)]
+pub struct LocalServicesOptions {
+    #[doc = "How to run the QVM, or `None` to not run it. Defaults to the `qvm` executable."]
+    #[builder(default = "Some(Launcher::process(\"qvm\"))")]
+    qvm: Option<Launcher>,
+    #[doc = "How to run quilc, or `None` to not run it. Defaults to the `quilc` executable."]
+    #[builder(default = "Some(Launcher::process(\"quilc\"))")]
+    quilc: Option<Launcher>,
+    #[doc = "The port the QVM listens on. Defaults to 5000."]
+    #[builder(default = "5000")]
+    qvm_port: u16,
+    #[doc = "The port quilc listens on. Defaults to 5555."]
+    #[builder(default = "5555")]
+    quilc_port: u16,
+    #[doc = "How long to wait for each service to be ready. Defaults to 30 seconds."]
+    #[builder(default = "Duration::from_secs(30)")]
+    startup_timeout: Duration,
+}
+
+impl Default for LocalServicesOptions {
+    fn default() -> Self {
+        LocalServicesOptionsBuilder::default()
+            .build()
+            .expect("Should be able to derive default LocalServicesOptions from the builder.")
+    }
+}
+
+impl LocalServicesOptions {
+    /// Get a [`LocalServicesOptionsBuilder`] that can be used to build custom options.
+    #[must_use]
+    pub fn builder() -> LocalServicesOptionsBuilder {
+        LocalServicesOptionsBuilder::default()
+    }
+
+    /// Run both services as Docker containers of the `rigetti/qvm` and `rigetti/quilc` images,
+    /// on the default ports.
+    #[must_use]
+    pub fn docker() -> Self {
+        Self {
+            qvm: Some(Launcher::docker("rigetti/qvm")),
+            quilc: Some(Launcher::docker("rigetti/quilc")),
+            ..Self::default()
+        }
+    }
+}
+
+/// Errors that can occur when starting local services.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// The service's process couldn't be started, e.g. because it isn't installed.
+    #[error("Failed to launch {service:?}: {source}")]
+    Launch {
+        /// The service which couldn't be started.
+        service: Service,
+        /// Why it couldn't be started.
+        #[source]
+        source: std::io::Error,
+    },
+    /// `docker run` failed.
+    #[error("Docker failed to start {service:?}: {message}")]
+    Docker {
+        /// The service which couldn't be started.
+        service: Service,
+        /// What `docker` reported.
+        message: String,
+    },
+    /// The service's process exited before it was ready.
+    #[error("{service:?} exited before it was ready: {status}")]
+    Exited {
+        /// The service which exited.
+        service: Service,
+        /// How it exited.
+        status: ExitStatus,
+    },
+    /// The service didn't answer requests in time.
+    #[error("{service:?} was not ready at {url} within {timeout:?}")]
+    NotReady {
+        /// The service which wasn't ready.
+        service: Service,
+        /// Where it was expected to listen.
+        url: String,
+        /// How long it was given.
+        timeout: Duration,
+    },
+}
+
+/// A running service, stopped when dropped.
+#[derive(Debug)]
+enum Running {
+    Process(Child),
+    Container(String),
+}
+
+impl Running {
+    /// Launch `service` on `port` with `launcher`.
+    fn launch(launcher: &Launcher, service: Service, port: u16) -> Result<Self, Error> {
+        let mut command = launcher.command(service, port);
+        let launch_error = |source| Error::Launch { service, source };
+        match launcher {
+            Launcher::Process { .. } => command
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()
+                .map(Self::Process)
+                .map_err(launch_error),
+            Launcher::Docker { .. } => {
+                let output = command.output().map_err(launch_error)?;
+                if output.status.success() {
+                    let id = String::from_utf8_lossy(&output.stdout).trim().to_string();
+                    Ok(Self::Container(id))
+                } else {
+                    Err(Error::Docker {
+                        service,
+                        message: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+                    })
+                }
+            }
+        }
+    }
+
+    /// Fail if the service has already stopped.
+    fn check_alive(&mut self, service: Service) -> Result<(), Error> {
+        match self {
+            Self::Process(child) => match child.try_wait() {
+                Ok(Some(status)) => Err(Error::Exited { service, status }),
+                Ok(None) | Err(_) => Ok(()),
+            },
+            Self::Container(_) => Ok(()),
+        }
+    }
+}
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        match self {
+            Self::Process(child) => {
+                // The process may have exited already, in which case there is nothing to stop.
+                let _ = child.kill();
+                let _ = child.wait();
+            }
+            Self::Container(id) => {
+                let _ = Command::new("docker")
+                    .args(["rm", "--force"])
+                    .arg(id.as_str())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .status();
+            }
+        }
+    }
+}
+
+/// Local `quilc` and QVM servers, started with [`LocalServices::start`] and stopped when this is
+/// dropped.
+#[derive(Debug)]
+pub struct LocalServices {
+    qvm: Option<Running>,
+    quilc: Option<Running>,
+    qvm_url: String,
+    quilc_url: String,
+}
+
+impl LocalServices {
+    /// Start the services requested by `options`, and wait until each is ready.
+    ///
+    /// If any service fails to start, those already started are stopped.
+    ///
+    /// # Errors
+    ///
+    /// See [`Error`].
+    pub async fn start(options: LocalServicesOptions) -> Result<Self, Error> {
+        let mut services = Self {
+            qvm: None,
+            quilc: None,
+            qvm_url: format!("http://127.0.0.1:{}", options.qvm_port),
+            quilc_url: format!("tcp://127.0.0.1:{}", options.quilc_port),
+        };
+
+        if let Some(launcher) = &options.qvm {
+            #[cfg(feature = "tracing")]
+            tracing::info!(?launcher, url = %services.qvm_url, "starting a local QVM");
+            services.qvm = Some(Running::launch(launcher, Service::Qvm, options.qvm_port)?);
+        }
+        if let Some(launcher) = &options.quilc {
+            #[cfg(feature = "tracing")]
+            tracing::info!(?launcher, url = %services.quilc_url, "starting a local quilc");
+            services.quilc = Some(Running::launch(
+                launcher,
+                Service::Quilc,
+                options.quilc_port,
+            )?);
+        }
+
+        services.wait_until_ready(options.startup_timeout).await?;
+        Ok(services)
+    }
+
+    /// Wait until every service answers a request for its version, or fail once `timeout` has
+    /// passed.
+    async fn wait_until_ready(&mut self, timeout: Duration) -> Result<(), Error> {
+        let started = Instant::now();
+        let mut qvm_ready = self.qvm.is_none();
+        let mut quilc_ready = self.quilc.is_none();
+        loop {
+            if !qvm_ready {
+                if let Some(qvm) = &mut self.qvm {
+                    qvm.check_alive(Service::Qvm)?;
+                }
+                qvm_ready = qvm_version(&self.qvm_url).await.is_some();
+            }
+            if !quilc_ready {
+                if let Some(quilc) = &mut self.quilc {
+                    quilc.check_alive(Service::Quilc)?;
+                }
+                quilc_ready = quilc_version(&self.quilc_url).await.is_some();
+            }
+            if qvm_ready && quilc_ready {
+                return Ok(());
+            }
+            if started.elapsed() >= timeout {
+                let (service, url) = if qvm_ready {
+                    (Service::Quilc, self.quilc_url.clone())
+                } else {
+                    (Service::Qvm, self.qvm_url.clone())
+                };
+                return Err(Error::NotReady {
+                    service,
+                    url,
+                    timeout,
+                });
+            }
+            tokio::time::sleep(READINESS_POLL_INTERVAL).await;
+        }
+    }
+
+    /// The URL of the QVM, whether or not it was started.
+    #[must_use]
+    pub fn qvm_url(&self) -> &str {
+        &self.qvm_url
+    }
+
+    /// The URL of quilc, whether or not it was started.
+    #[must_use]
+    pub fn quilc_url(&self) -> &str {
+        &self.quilc_url
+    }
+
+    /// A [`Qcs`] client configured to use these services, and the default settings otherwise.
+    ///
+    /// # Panics
+    ///
+    /// Never: the client's configuration is always valid.
+    #[must_use]
+    pub fn client(&self) -> Qcs {
+        let config = ClientConfiguration::builder()
+            .qvm_url(self.qvm_url.clone())
+            .quilc_url(self.quilc_url.clone())
+            .build()
+            .expect("every field of the configuration is valid");
+        Qcs::with_config(config)
+    }
+}
+
+/// The version of the QVM at `url`, or `None` if it doesn't answer within a second.
+async fn qvm_version(url: &str) -> Option<String> {
+    let options = QvmOptions::new().with_timeout(Some(Duration::from_secs(1)));
+    qvm::http::HttpClient::new(url.to_string())
+        .get_version_info(&options)
+        .await
+        .ok()
+}
+
+/// The version of quilc at `url`, or `None` if it doesn't answer within a second.
+async fn quilc_version(url: &str) -> Option<String> {
+    let url = url.to_string();
+    tokio::task::spawn_blocking(move || {
+        let mut client = rpcq::Client::new(&url).ok()?;
+        client.set_timeout(1000);
+        client.get_version_info().ok()
+    })
+    .await
+    .ok()
+    .flatten()
+}
+
+#[cfg(test)]
+mod describe_local_services {
+    use std::time::Duration;
+
+    use super::{Error, Launcher, LocalServices, LocalServicesOptions};
+    use crate::Service;
+
+    fn arguments(launcher: &Launcher, service: Service, port: u16) -> (String, Vec<String>) {
+        let command = launcher.command(service, port);
+        (
+            command.get_program().to_string_lossy().into_owned(),
+            command
+                .get_args()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn it_runs_executables_in_server_mode() {
+        assert_eq!(
+            arguments(&Launcher::process("qvm"), Service::Qvm, 5001),
+            (
+                "qvm".to_string(),
+                vec!["-S".into(), "-p".into(), "5001".into()]
+            )
+        );
+    }
+
+    #[test]
+    fn it_publishes_container_ports() {
+        let (program, args) = arguments(&Launcher::docker("rigetti/quilc"), Service::Quilc, 6000);
+        assert_eq!(program, "docker");
+        assert_eq!(
+            args,
+            [
+                "run",
+                "--rm",
+                "--detach",
+                "--publish",
+                "6000:5555",
+                "rigetti/quilc",
+                "-S"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn it_fails_to_launch_missing_executables() {
+        let options = LocalServicesOptions::builder()
+            .qvm(Some(Launcher::process("/nonexistent/qvm")))
+            .quilc(None)
+            .startup_timeout(Duration::from_secs(1))
+            .build()
+            .unwrap();
+
+        let result = LocalServices::start(options).await;
+        assert!(matches!(
+            result,
+            Err(Error::Launch {
+                service: Service::Qvm,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn it_configures_a_client_for_the_services() {
+        let services = LocalServices {
+            qvm: None,
+            quilc: None,
+            qvm_url: "http://127.0.0.1:5010".to_string(),
+            quilc_url: "tcp://127.0.0.1:5565".to_string(),
+        };
+
+        let client = services.client();
+        assert_eq!(client.get_config().qvm_url(), services.qvm_url());
+        assert_eq!(client.get_config().quilc_url(), services.quilc_url());
+    }
+}
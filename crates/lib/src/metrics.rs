@@ -0,0 +1,222 @@
+//! Counters and timings which show whether the SDK's caches are helping a workload, and where the
+//! time of each execution goes.
+//!
+//! Cache counters are process-wide, since the caches they describe are shared by every client in
+//! the process. Read them with [`cache_stats`] (or [`Qcs::cache_stats`](crate::client::Qcs::cache_stats))
+//! and start over with [`reset_cache_stats`]. Stage timings belong to a single
+//! [`Executable`](crate::Executable); see [`Executable::stage_timings`](crate::Executable::stage_timings).
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use serde::Serialize;
+
+/// The number of lookups a cache answered itself, and the number it had to pass on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheCounter {
+    /// The number of lookups answered from the cache.
+    pub hits: u64,
+    /// The number of lookups which had to fetch or compute their value.
+    pub misses: u64,
+}
+
+impl CacheCounter {
+    /// The total number of lookups.
+    #[must_use]
+    pub fn lookups(&self) -> u64 {
+        self.hits + self.misses
+    }
+
+    /// The fraction of lookups answered from the cache, or `None` if there have been none.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn hit_ratio(&self) -> Option<f64> {
+        match self.lookups() {
+            0 => None,
+            lookups => Some(self.hits as f64 / lookups as f64),
+        }
+    }
+}
+
+/// Counters for each of the SDK's caches.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CacheStats {
+    /// Translations reused by a QPU execution because the program, options, and QPU settings were
+    /// unchanged.
+    pub translation: CacheCounter,
+    /// Quil-T calibration programs, see
+    /// [`get_calibration_program`](crate::qpu::translation::get_calibration_program).
+    pub calibrations: CacheCounter,
+    /// The gateway addresses used to reach each QPU.
+    pub gateway_address: CacheCounter,
+    /// The ISAs of each QPU, see [`IsaCache`](crate::qpu::IsaCache).
+    pub isa: CacheCounter,
+    /// The default endpoint addresses used to reach each QPU.
+    pub default_endpoint: CacheCounter,
+    /// Compiled programs, see
+    /// [`CompilationCache`](crate::compiler::quilc::CompilationCache).
+    pub compilation: CacheCounter,
+}
+
+/// How long each stage of the most recent QPU execution of an
+/// [`Executable`](crate::Executable) took.
+///
+/// A stage is `None` if it hasn't run yet, or if its last run was skipped because a cached result
+/// could be reused.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[non_exhaustive]
+pub struct StageTimings {
+    /// Fetching the ISA and compiling the program with quilc.
+    pub compilation: Option<Duration>,
+    /// Translating the program for the QPU.
+    pub translation: Option<Duration>,
+    /// Submitting the translated program, not including translation.
+    pub submission: Option<Duration>,
+    /// Waiting for and retrieving results.
+    pub retrieval: Option<Duration>,
+    /// Running the program on the QPU, as reported by the QPU.
+    pub execution: Option<Duration>,
+}
+
+/// A stage of a QPU execution, as timed by [`StageTimings`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum Stage {
+    /// Fetching the ISA and compiling the program with quilc.
+    Compilation,
+    /// Translating the program for the QPU.
+    Translation,
+    /// Submitting the translated program.
+    Submission,
+    /// Waiting for and retrieving results.
+    Retrieval,
+}
+
+impl std::fmt::Display for Stage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Compilation => "compilation",
+            Self::Translation => "translation",
+            Self::Submission => "submission",
+            Self::Retrieval => "retrieval",
+        };
+        f.write_str(name)
+    }
+}
+
+/// The caches which keep counters.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(not(feature = "qpu"), allow(dead_code))]
+pub(crate) enum Cache {
+    Translation = 0,
+    Calibrations = 1,
+    GatewayAddress = 2,
+    DefaultEndpoint = 3,
+    Isa = 4,
+    Compilation = 5,
+}
+
+struct Counter {
+    lookups: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl Counter {
+    const fn new() -> Self {
+        Self {
+            lookups: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    fn get(&self) -> CacheCounter {
+        let lookups = self.lookups.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+        CacheCounter {
+            hits: lookups.saturating_sub(misses),
+            misses,
+        }
+    }
+
+    fn reset(&self) {
+        self.lookups.store(0, Ordering::Relaxed);
+        self.misses.store(0, Ordering::Relaxed);
+    }
+}
+
+#[allow(clippy::declare_interior_mutable_const)]
+const NEW_COUNTER: Counter = Counter::new();
+static COUNTERS: [Counter; 6] = [NEW_COUNTER; 6];
+
+/// Count a lookup in `cache`. Lookups which miss must also be counted with [`record_miss`].
+#[cfg_attr(not(feature = "qpu"), allow(dead_code))]
+pub(crate) fn record_lookup(cache: Cache) {
+    COUNTERS[cache as usize]
+        .lookups
+        .fetch_add(1, Ordering::Relaxed);
+}
+
+/// Count a lookup in `cache` which had to fetch or compute its value.
+#[cfg_attr(not(feature = "qpu"), allow(dead_code))]
+pub(crate) fn record_miss(cache: Cache) {
+    COUNTERS[cache as usize]
+        .misses
+        .fetch_add(1, Ordering::Relaxed);
+}
+
+/// Count a lookup in `cache`, and whether it was a hit.
+#[cfg_attr(not(feature = "qpu"), allow(dead_code))]
+pub(crate) fn record(cache: Cache, hit: bool) {
+    record_lookup(cache);
+    if !hit {
+        record_miss(cache);
+    }
+}
+
+/// The counters of every cache since the process started, or since [`reset_cache_stats`].
+#[must_use]
+pub fn cache_stats() -> CacheStats {
+    CacheStats {
+        translation: COUNTERS[Cache::Translation as usize].get(),
+        calibrations: COUNTERS[Cache::Calibrations as usize].get(),
+        gateway_address: COUNTERS[Cache::GatewayAddress as usize].get(),
+        default_endpoint: COUNTERS[Cache::DefaultEndpoint as usize].get(),
+        isa: COUNTERS[Cache::Isa as usize].get(),
+        compilation: COUNTERS[Cache::Compilation as usize].get(),
+    }
+}
+
+/// Set every cache counter back to zero.
+pub fn reset_cache_stats() {
+    for counter in &COUNTERS {
+        counter.reset();
+    }
+}
+
+#[cfg(test)]
+mod describe_cache_stats {
+    use super::{cache_stats, record, record_lookup, record_miss, Cache, CacheCounter};
+
+    #[test]
+    fn it_computes_hit_ratios() {
+        assert_eq!(CacheCounter::default().hit_ratio(), None);
+        let counter = CacheCounter { hits: 3, misses: 1 };
+        assert_eq!(counter.lookups(), 4);
+        assert_eq!(counter.hit_ratio(), Some(0.75));
+    }
+
+    // The counters are shared with every other test in the process, so only check that they grow.
+    #[test]
+    fn it_counts_hits_and_misses() {
+        let before = cache_stats().calibrations;
+        record(Cache::Calibrations, true);
+        record(Cache::Calibrations, false);
+        record_lookup(Cache::Calibrations);
+        record_miss(Cache::Calibrations);
+        let after = cache_stats().calibrations;
+        assert!(after.hits > before.hits);
+        assert!(after.misses >= before.misses + 2);
+    }
+}
@@ -1,30 +1,42 @@
 //! This module provides bindings to for submitting jobs to and retrieving them from
 //! Rigetti QPUs using the QCS API.
 
-use std::{convert::TryFrom, fmt, time::Duration};
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    fmt,
+    future::Future,
+    num::NonZeroU16,
+    ops::Range,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 #[deny(clippy::module_name_repetitions)]
 pub use ::pbjson_types::Duration as QpuApiDuration;
 use async_trait::async_trait;
 use cached::proc_macro::cached;
 use derive_builder::Builder;
+use futures::future::{select, Either};
+use prost::Message;
 use qcs_api_client_common::configuration::TokenError;
 #[cfg(feature = "grpc-web")]
 use qcs_api_client_grpc::tonic::wrap_channel_with_grpc_web;
 pub use qcs_api_client_grpc::tonic::Error as GrpcError;
 use qcs_api_client_grpc::{
-    get_channel_with_timeout,
     models::controller::{
-        controller_job_execution_result, data_value::Value, ControllerJobExecutionResult,
-        DataValue, EncryptedControllerJob, JobExecutionConfiguration, RealDataValue,
+        controller_job_execution_result, ControllerJobExecutionResult, EncryptedControllerJob,
+        JobExecutionConfiguration,
     },
     services::controller::{
         cancel_controller_jobs_request, controller_client::ControllerClient,
         execute_controller_job_request, get_controller_job_results_request,
+        get_controller_job_status_request, get_controller_job_status_response,
         CancelControllerJobsRequest, ExecuteControllerJobRequest,
         ExecutionOptions as InnerApiExecutionOptions, GetControllerJobResultsRequest,
+        GetControllerJobStatusRequest,
     },
-    tonic::{parse_uri, wrap_channel_with, wrap_channel_with_retry},
+    tonic::{parse_uri, wrap_channel_with_retry, wrap_channel_with_token_refresher},
 };
 pub use qcs_api_client_openapi::apis::Error as OpenApiError;
 use qcs_api_client_openapi::apis::{
@@ -37,36 +49,34 @@ use qcs_api_client_openapi::apis::{
     },
 };
 use qcs_api_client_openapi::models::QuantumProcessorAccessorType;
+use serde::{Deserialize, Serialize};
+pub use tokio_util::sync::CancellationToken;
+use tonic::metadata::MetadataMap;
 
-use crate::executable::Parameters;
+use crate::client::{is_unauthenticated_response, GrpcClientError, GrpcConnection, Qcs};
+use crate::metrics::{self, Cache};
 
-use crate::client::{GrpcClientError, GrpcConnection, Qcs};
+use super::patch_values::PatchValues;
+use super::reservation::{self, DEFAULT_MAX_RESERVATION_WAIT};
+use super::result_data::QpuResultData;
+use super::retry::{QpuApiCall, RetryPolicy};
 
 /// The maximum size of a gRPC response, in bytes.
 const MAX_DECODING_MESSAGE_SIZE_BYTES: usize = 250 * 1024 * 1024;
 
-pub(crate) fn params_into_job_execution_configuration(
-    params: &Parameters,
-) -> JobExecutionConfiguration {
-    let memory_values = params
-        .iter()
-        .map(|(str, value)| {
-            (
-                str.as_ref().into(),
-                DataValue {
-                    value: Some(Value::Real(RealDataValue {
-                        data: value.clone(),
-                    })),
-                },
-            )
-        })
-        .collect();
+/// The default maximum size of a message accepted by a gRPC server, in bytes. This is a reasonable
+/// value for [`ExecutionOptions::max_request_bytes`] when splitting large batches of patch values.
+pub const GRPC_MAX_MESSAGE_BYTES: usize = 4 * 1024 * 1024;
 
-    JobExecutionConfiguration { memory_values }
+pub(crate) fn params_into_job_execution_configuration<P: PatchValues + ?Sized>(
+    params: &P,
+) -> JobExecutionConfiguration {
+    params.to_job_execution_configuration()
 }
 
 /// The QCS Job ID. Useful for debugging or retrieving results later.
-#[derive(Debug, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+#[derive(Debug, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
 pub struct JobId(pub(crate) String);
 
 impl fmt::Display for JobId {
@@ -91,18 +101,23 @@ impl From<String> for JobId {
 ///      to target a specific endpoint ID.
 /// * `program` - The compiled program as an [`EncryptedControllerJob`]
 /// * `patch_values` - The parameters to use for the execution. See [`submit_with_parameter_batch`]
-///      if you need to execute with multiple sets of parameters.
+///      if you need to execute with multiple sets of parameters. Plain `f64` parameters are sent
+///      as `REAL` data; use [`TypedParameters`](super::patch_values::TypedParameters) to patch
+///      `BIT`, `OCTET`, or `INTEGER` regions.
 /// * `client` - The [`Qcs`] client to use.
 /// * `execution_options` - The [`ExecutionOptions`] to use. If the connection strategy used
 ///       is [`ConnectionStrategy::EndpointId`] then direct access to that endpoint
 ///       overrides the `quantum_processor_id` parameter.
-pub async fn submit(
+pub async fn submit<P>(
     quantum_processor_id: Option<&str>,
     program: EncryptedControllerJob,
-    patch_values: &Parameters,
+    patch_values: &P,
     client: &Qcs,
     execution_options: &ExecutionOptions,
-) -> Result<JobId, QpuApiError> {
+) -> Result<JobId, QpuApiError>
+where
+    P: PatchValues + ?Sized,
+{
     submit_with_parameter_batch(
         quantum_processor_id,
         program,
@@ -116,9 +131,59 @@ pub async fn submit(
     .map_err(QpuApiError::from)
 }
 
+/// As [`submit`], but fail with [`QpuApiError::Cancelled`] once `cancellation_token` is cancelled.
+///
+/// No request is made if the token is already cancelled. A request which is still in flight when
+/// the token is cancelled is allowed to finish, since abandoning it could leave a queued job with
+/// nothing to cancel it. The job it queued is then cancelled (see [`cancel_job`]), which succeeds
+/// only if it hasn't started executing.
+///
+/// # Errors
+///
+/// See [`submit`].
+pub async fn submit_with_cancellation<P>(
+    quantum_processor_id: Option<&str>,
+    program: EncryptedControllerJob,
+    patch_values: &P,
+    client: &Qcs,
+    execution_options: &ExecutionOptions,
+    cancellation_token: &CancellationToken,
+) -> Result<JobId, QpuApiError>
+where
+    P: PatchValues + ?Sized,
+{
+    if cancellation_token.is_cancelled() {
+        return Err(QpuApiError::Cancelled { job_id: None });
+    }
+    let job_id = submit(
+        quantum_processor_id,
+        program,
+        patch_values,
+        client,
+        execution_options,
+    )
+    .await?;
+
+    if cancellation_token.is_cancelled() {
+        cancel_abandoned_job(
+            job_id.clone(),
+            quantum_processor_id,
+            client,
+            execution_options,
+        )
+        .await;
+        return Err(QpuApiError::Cancelled {
+            job_id: Some(job_id),
+        });
+    }
+    Ok(job_id)
+}
+
 /// Execute a compiled program on a QPU with multiple sets of `patch_values`.
 ///
-/// See [`ExecuteControllerJobRequest`] for more details.
+/// See [`ExecuteControllerJobRequest`] for more details. The patch values are sent in a single
+/// request unless [`ExecutionOptions::max_request_bytes`] is set, in which case they may be split
+/// as described in [`submit_with_chunked_parameter_batch`].
 ///
 /// # Arguments
 /// * `quantum_processor_id` - The quantum processor to execute the job on. This parameter
@@ -126,7 +191,7 @@ pub async fn submit(
 ///      to target a specific endpoint ID.
 /// * `program` - The compiled program as an [`EncryptedControllerJob`]
 /// * `patch_values` - The parameters to use for the execution. The job will be run once for each
-///     given set of [`Parameters`].
+///     given set of patch values.
 /// * `client` - The [`Qcs`] client to use.
 /// * `execution_options` - The [`ExecutionOptions`] to use. If the connection strategy used
 ///       is [`ConnectionStrategy::EndpointId`] then direct access to that endpoint
@@ -137,7 +202,117 @@ pub async fn submit(
 /// * Returns a [`QpuApiError`] if:
 ///     * Any of the jobs fail to be queued.
 ///     * The provided `patch_values` iterator is empty.
-pub async fn submit_with_parameter_batch<'a, I>(
+pub async fn submit_with_parameter_batch<'a, I, P>(
+    quantum_processor_id: Option<&str>,
+    program: EncryptedControllerJob,
+    patch_values: I,
+    client: &Qcs,
+    execution_options: &ExecutionOptions,
+) -> Result<Vec<JobId>, QpuApiError>
+where
+    I: IntoIterator<Item = &'a P>,
+    P: PatchValues + ?Sized + 'a,
+{
+    submit_with_chunked_parameter_batch(
+        quantum_processor_id,
+        program,
+        patch_values,
+        client,
+        execution_options,
+    )
+    .await
+    .map(|submission| submission.job_ids)
+}
+
+/// The jobs queued by [`submit_with_chunked_parameter_batch`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChunkedSubmission {
+    /// One [`JobId`] for each set of patch values, in the order the patch values were given.
+    pub job_ids: Vec<JobId>,
+    /// The indices of the patch values sent in each request, in the order the requests were made.
+    pub chunks: Vec<Range<usize>>,
+}
+
+/// Execute a compiled program on a QPU with multiple sets of `patch_values`, splitting them
+/// across as many requests as needed to keep each request within
+/// [`ExecutionOptions::max_request_bytes`].
+///
+/// Requests are made one after another, so the returned [`JobId`]s are in the same order as
+/// `patch_values`. A set of patch values which is too large to fit within the limit by itself is
+/// still sent, alone, in its own request.
+///
+/// See [`submit_with_parameter_batch`] for a description of the arguments.
+///
+/// # Errors
+///
+/// * Returns a [`QpuApiError`] if:
+///     * The first request fails.
+///     * The provided `patch_values` iterator is empty.
+/// * Returns [`QpuApiError::PartiallySubmitted`] with the [`JobId`]s that were already queued
+///     if any later request fails.
+/// * Returns [`QpuApiError::SubmissionsDisabled`] without making any request if `client` is
+///     read-only (see [`Qcs::with_read_only`]).
+pub async fn submit_with_chunked_parameter_batch<'a, I, P>(
+    quantum_processor_id: Option<&str>,
+    program: EncryptedControllerJob,
+    patch_values: I,
+    client: &Qcs,
+    execution_options: &ExecutionOptions,
+) -> Result<ChunkedSubmission, QpuApiError>
+where
+    I: IntoIterator<Item = &'a P>,
+    P: PatchValues + ?Sized + 'a,
+{
+    #[cfg(feature = "tracing")]
+    let span = tracing::info_span!(
+        "qpu.submit",
+        quantum_processor_id = quantum_processor_id,
+        num_patch_values = tracing::field::Empty,
+        job_id = tracing::field::Empty,
+    );
+    let submission = submit_chunks(
+        quantum_processor_id,
+        program,
+        patch_values,
+        client,
+        execution_options,
+        None,
+    );
+    #[cfg(feature = "tracing")]
+    let submission = tracing::Instrument::instrument(submission, span.clone());
+    let submission = submission.await?;
+
+    #[cfg(feature = "tracing")]
+    if let [job_id] = submission.job_ids.as_slice() {
+        span.record("job_id", tracing::field::display(job_id));
+    } else {
+        span.record("job_id", tracing::field::debug(&submission.job_ids));
+    }
+
+    Ok(submission)
+}
+
+/// A connection to the controller service of a QPU, which can submit many jobs without
+/// connecting again for each. See [`submit_on_connection`].
+pub(crate) type ControllerConnection = ControllerClient<GrpcConnection>;
+
+/// Connect to the controller service of `quantum_processor_id`, after waiting for a reservation
+/// if `execution_options` ask for it.
+pub(crate) async fn connect_to_controller(
+    quantum_processor_id: Option<&str>,
+    client: &Qcs,
+    execution_options: &ExecutionOptions,
+) -> Result<ControllerConnection, QpuApiError> {
+    reservation::wait_if_requested(client, quantum_processor_id, execution_options).await?;
+    execution_options
+        .get_controller_client(client, quantum_processor_id)
+        .await
+}
+
+/// As [`submit_with_parameter_batch`], but make the requests on `connection`, made with
+/// [`connect_to_controller`], rather than connecting again.
+pub(crate) async fn submit_on_connection<'a, I, P>(
+    connection: &ControllerConnection,
     quantum_processor_id: Option<&str>,
     program: EncryptedControllerJob,
     patch_values: I,
@@ -145,7 +320,35 @@ pub async fn submit_with_parameter_batch<'a, I>(
     execution_options: &ExecutionOptions,
 ) -> Result<Vec<JobId>, QpuApiError>
 where
-    I: IntoIterator<Item = &'a Parameters>,
+    I: IntoIterator<Item = &'a P>,
+    P: PatchValues + ?Sized + 'a,
+{
+    submit_chunks(
+        quantum_processor_id,
+        program,
+        patch_values,
+        client,
+        execution_options,
+        Some(connection),
+    )
+    .await
+    .map(|submission| submission.job_ids)
+}
+
+/// Submit `patch_values` in as many requests as needed, see
+/// [`submit_with_chunked_parameter_batch`]. The requests are made on `connection` if given, or
+/// on a new connection otherwise.
+async fn submit_chunks<'a, I, P>(
+    quantum_processor_id: Option<&str>,
+    program: EncryptedControllerJob,
+    patch_values: I,
+    client: &Qcs,
+    execution_options: &ExecutionOptions,
+    connection: Option<&ControllerConnection>,
+) -> Result<ChunkedSubmission, QpuApiError>
+where
+    I: IntoIterator<Item = &'a P>,
+    P: PatchValues + ?Sized + 'a,
 {
     #[cfg(feature = "tracing")]
     tracing::debug!(
@@ -154,33 +357,151 @@ where
         execution_options
     );
 
-    let mut patch_values = patch_values.into_iter().peekable();
-    if patch_values.peek().is_none() {
+    if client.is_read_only() {
+        return Err(QpuApiError::SubmissionsDisabled);
+    }
+
+    let configurations: Vec<_> = patch_values
+        .into_iter()
+        .map(params_into_job_execution_configuration::<P>)
+        .collect();
+    if configurations.is_empty() {
         return Err(QpuApiError::EmptyPatchValues);
     }
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record("num_patch_values", configurations.len());
 
-    let request = ExecuteControllerJobRequest {
-        execution_configurations: patch_values
-            .map(params_into_job_execution_configuration)
-            .collect(),
+    let base_request = ExecuteControllerJobRequest {
+        execution_configurations: Vec::new(),
         job: Some(execute_controller_job_request::Job::Encrypted(program)),
         target: execution_options.get_job_target(quantum_processor_id),
         options: execution_options.api_options().copied(),
     };
+    let chunks = chunk_execution_configurations(
+        base_request.encoded_len(),
+        &configurations,
+        execution_options.max_request_bytes(),
+    );
+    let mut metadata = MetadataMap::new();
+    propagate_trace_context(execution_options, &mut metadata);
 
-    let mut controller_client = execution_options
-        .get_controller_client(client, quantum_processor_id)
-        .await?;
+    #[cfg(feature = "tracing")]
+    tracing::debug!(
+        num_patch_values = configurations.len(),
+        num_requests = chunks.len(),
+        "splitting patch values into requests"
+    );
 
-    Ok(controller_client
-        .execute_controller_job(request)
-        .await
-        .map_err(GrpcClientError::RequestFailed)?
-        .into_inner()
-        .job_execution_ids
-        .into_iter()
-        .map(JobId)
-        .collect())
+    let controller_client = match connection {
+        Some(connection) => connection.clone(),
+        None => connect_to_controller(quantum_processor_id, client, execution_options).await?,
+    };
+
+    let retry_policy = execution_options.retry_policy_for(QpuApiCall::Submit);
+    let mut job_ids = Vec::with_capacity(configurations.len());
+    let mut configurations = configurations.into_iter();
+    for chunk in &chunks {
+        let chunk_request = ExecuteControllerJobRequest {
+            execution_configurations: configurations.by_ref().take(chunk.len()).collect(),
+            ..base_request.clone()
+        };
+        let result = retry_policy
+            .retry(|| {
+                let mut controller_client = controller_client.clone();
+                let mut request = tonic::Request::new(chunk_request.clone());
+                *request.metadata_mut() = metadata.clone();
+                async move { controller_client.execute_controller_job(request).await }
+            })
+            .await;
+        match result {
+            Ok(response) => job_ids.extend(
+                response
+                    .into_inner()
+                    .job_execution_ids
+                    .into_iter()
+                    .map(JobId),
+            ),
+            Err(status) => {
+                let error = QpuApiError::from(GrpcClientError::RequestFailed(status));
+                return Err(if job_ids.is_empty() {
+                    error
+                } else {
+                    QpuApiError::PartiallySubmitted {
+                        job_ids,
+                        source: Box::new(error),
+                    }
+                });
+            }
+        }
+    }
+
+    Ok(ChunkedSubmission { job_ids, chunks })
+}
+
+/// Add the OpenTelemetry context of the current span to `metadata`, if `execution_options` ask
+/// for it. See [`ExecutionOptions::propagate_trace_context`].
+#[cfg_attr(not(feature = "tracing-opentelemetry"), allow(unused_variables))]
+fn propagate_trace_context(execution_options: &ExecutionOptions, metadata: &mut MetadataMap) {
+    #[cfg(feature = "tracing-opentelemetry")]
+    if execution_options.propagate_trace_context() {
+        use opentelemetry::propagation::TextMapPropagator;
+        use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+        let context = tracing::Span::current().context();
+        opentelemetry::global::get_text_map_propagator(|propagator| {
+            propagator.inject_context(&context, &mut MetadataInjector(metadata));
+        });
+    }
+}
+
+/// Writes OpenTelemetry propagation headers into gRPC request metadata.
+#[cfg(feature = "tracing-opentelemetry")]
+struct MetadataInjector<'a>(&'a mut MetadataMap);
+
+#[cfg(feature = "tracing-opentelemetry")]
+impl opentelemetry::propagation::Injector for MetadataInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        let key = tonic::metadata::MetadataKey::from_bytes(key.as_bytes());
+        if let (Ok(key), Ok(value)) = (key, value.parse()) {
+            self.0.insert(key, value);
+        }
+    }
+}
+
+/// Split `configurations` into consecutive ranges such that each range, added to a request which
+/// is `base_len` bytes without them, encodes to at most `max_request_bytes`. Every range has at
+/// least one element, so a configuration which doesn't fit by itself gets a range of its own.
+fn chunk_execution_configurations(
+    base_len: usize,
+    configurations: &[JobExecutionConfiguration],
+    max_request_bytes: Option<usize>,
+) -> Vec<Range<usize>> {
+    let Some(max_request_bytes) = max_request_bytes else {
+        return vec![0..configurations.len()];
+    };
+
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    let mut request_len = base_len;
+    for (index, configuration) in configurations.iter().enumerate() {
+        let len = encoded_len_in_repeated_field(configuration);
+        if index > start && request_len + len > max_request_bytes {
+            chunks.push(start..index);
+            start = index;
+            request_len = base_len;
+        }
+        request_len += len;
+    }
+    chunks.push(start..configurations.len());
+    chunks
+}
+
+/// The number of bytes `message` adds to a request when it is an element of a repeated field.
+fn encoded_len_in_repeated_field(message: &impl Message) -> usize {
+    // The field key takes at most 2 bytes for field numbers below 2048.
+    const MAX_KEY_LEN: usize = 2;
+    let len = message.encoded_len();
+    MAX_KEY_LEN + prost::length_delimiter_len(len) + len
 }
 
 /// Cancel all given jobs that have yet to begin executing.
@@ -210,7 +531,7 @@ pub async fn cancel_jobs(
     client: &Qcs,
     execution_options: &ExecutionOptions,
 ) -> Result<(), QpuApiError> {
-    let mut controller_client = execution_options
+    let controller_client = execution_options
         .get_controller_client(client, quantum_processor_id)
         .await?;
 
@@ -219,8 +540,13 @@ pub async fn cancel_jobs(
         target: execution_options.get_cancel_target(quantum_processor_id),
     };
 
-    controller_client
-        .cancel_controller_jobs(request)
+    execution_options
+        .retry_policy_for(QpuApiCall::Cancel)
+        .retry(|| {
+            let mut controller_client = controller_client.clone();
+            let request = request.clone();
+            async move { controller_client.cancel_controller_jobs(request).await }
+        })
         .await
         .map_err(GrpcClientError::RequestFailed)?;
 
@@ -263,8 +589,147 @@ pub async fn cancel_job(
     .await
 }
 
+/// The state of a job on a QPU, as reported by [`get_job_status`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum JobStatus {
+    /// The job is waiting to be executed.
+    Queued,
+    /// The job is executing.
+    Running,
+    /// The job has finished, and its results can be retrieved.
+    Succeeded,
+    /// The job has finished without results.
+    Failed,
+    /// The job was cancelled before it could be executed.
+    Canceled,
+    /// The controller doesn't know the state of the job.
+    Unknown,
+}
+
+impl JobStatus {
+    /// Whether the job has stopped, so that its status won't change again.
+    #[must_use]
+    pub fn is_finished(self) -> bool {
+        matches!(self, Self::Succeeded | Self::Failed | Self::Canceled)
+    }
+}
+
+impl From<get_controller_job_status_response::Status> for JobStatus {
+    fn from(status: get_controller_job_status_response::Status) -> Self {
+        use get_controller_job_status_response::Status;
+        match status {
+            Status::Queued => Self::Queued,
+            Status::Running => Self::Running,
+            Status::Succeeded => Self::Succeeded,
+            Status::Failed => Self::Failed,
+            Status::Canceled => Self::Canceled,
+            Status::Unknown => Self::Unknown,
+        }
+    }
+}
+
+impl fmt::Display for JobStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = match self {
+            Self::Queued => "queued",
+            Self::Running => "running",
+            Self::Succeeded => "succeeded",
+            Self::Failed => "failed",
+            Self::Canceled => "canceled",
+            Self::Unknown => "unknown",
+        };
+        f.write_str(status)
+    }
+}
+
+/// Ask the controller for the [`JobStatus`] of a job, without waiting for it to finish.
+///
+/// If the service rejects the request with a status that may be temporary, such as when it is
+/// briefly unavailable, it is retried according to the [`RetryPolicy`] for
+/// [`QpuApiCall::GetJobStatus`] in `execution_options`. Failures to reach the service are retried
+/// by the connection itself.
+///
+/// # Arguments
+/// * `job_id` - The [`JobId`] of the job.
+/// * `quantum_processor_id` - The quantum processor the job was submitted to. This parameter
+///      is required unless using [`ConnectionStrategy::EndpointId`] in `execution_options`
+///      to target a specific endpoint ID.
+/// * `client` - The [`Qcs`] client to use.
+/// * `execution_options` - The [`ExecutionOptions`] to use. If the connection strategy used
+///       is [`ConnectionStrategy::EndpointId`] then direct access to that endpoint
+///       overrides the `quantum_processor_id` parameter.
+///
+/// # Errors
+/// * Returns [`QpuApiError::GrpcClientError`] with [`GrpcClientError::RequestFailed`] if the
+///     request fails.
+/// * Returns [`QpuApiError::InvalidJobStatus`] if the controller reports a status this version
+///     of the SDK doesn't know.
+pub async fn get_job_status(
+    job_id: JobId,
+    quantum_processor_id: Option<&str>,
+    client: &Qcs,
+    execution_options: &ExecutionOptions,
+) -> Result<JobStatus, QpuApiError> {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(
+        "getting the status of job {} on {:?}",
+        job_id,
+        quantum_processor_id,
+    );
+
+    let controller_client = execution_options
+        .get_controller_client(client, quantum_processor_id)
+        .await?;
+    get_job_status_on_connection(
+        &controller_client,
+        job_id,
+        quantum_processor_id,
+        execution_options,
+    )
+    .await
+}
+
+/// As [`get_job_status`], but make the request on `connection`, made with
+/// [`ExecutionOptions::get_controller_client`], rather than connecting again. For polling a job's
+/// status.
+pub(crate) async fn get_job_status_on_connection(
+    connection: &ControllerConnection,
+    job_id: JobId,
+    quantum_processor_id: Option<&str>,
+    execution_options: &ExecutionOptions,
+) -> Result<JobStatus, QpuApiError> {
+    let request = GetControllerJobStatusRequest {
+        job_id: job_id.0,
+        target: execution_options.get_job_status_target(quantum_processor_id),
+    };
+
+    let response = execution_options
+        .retry_policy_for(QpuApiCall::GetJobStatus)
+        .retry(|| {
+            let mut controller_client = connection.clone();
+            let request = request.clone();
+            async move { controller_client.get_controller_job_status(request).await }
+        })
+        .await
+        .map_err(GrpcClientError::RequestFailed)?
+        .into_inner();
+
+    get_controller_job_status_response::Status::try_from(response.status)
+        .map(JobStatus::from)
+        .map_err(|error| QpuApiError::InvalidJobStatus {
+            status: response.status,
+            message: error.to_string(),
+        })
+}
+
 /// Fetch results from QPU job execution.
 ///
+/// If the service rejects the request with a status that may be temporary, such as when it is
+/// briefly unavailable, it is retried according to the [`RetryPolicy`] for
+/// [`QpuApiCall::RetrieveResults`] in `execution_options`. Failures to reach the service are
+/// retried by the connection itself.
+///
 /// # Arguments
 /// * `job_id` - The [`JobId`] to retrieve results for.
 /// * `quantum_processor_id` - The quantum processor the job was run on. This parameter
@@ -274,6 +739,14 @@ pub async fn cancel_job(
 /// * `execution_options` - The [`ExecutionOptions`] to use. If the connection strategy used
 ///       is [`ConnectionStrategy::EndpointId`] then direct access to that endpoint
 ///       overrides the `quantum_processor_id` parameter.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        name = "qpu.retrieve",
+        skip_all,
+        fields(job_id = %job_id, quantum_processor_id = quantum_processor_id)
+    )
+)]
 pub async fn retrieve_results(
     job_id: JobId,
     quantum_processor_id: Option<&str>,
@@ -293,14 +766,26 @@ pub async fn retrieve_results(
         target: execution_options.get_results_target(quantum_processor_id),
     };
 
-    let mut controller_client = execution_options
+    let controller_client = execution_options
         .get_controller_client(client, quantum_processor_id)
         .await?;
 
-    controller_client
-        .get_controller_job_results(request)
+    // The controller returns all of a job's results in a single response, so a retrieval that is
+    // interrupted part way through can't be resumed. Instead, retry the whole request, which is
+    // safe to repeat because results are looked up by the job's ID and are not consumed by
+    // reading them.
+    let response = execution_options
+        .retry_policy_for(QpuApiCall::RetrieveResults)
+        .retry(|| {
+            let mut controller_client = controller_client.clone();
+            let mut request = tonic::Request::new(request.clone());
+            propagate_trace_context(execution_options, request.metadata_mut());
+            async move { controller_client.get_controller_job_results(request).await }
+        })
         .await
-        .map_err(GrpcClientError::RequestFailed)?
+        .map_err(GrpcClientError::RequestFailed)?;
+
+    response
         .into_inner()
         .result
         .ok_or_else(|| GrpcClientError::ResponseEmpty("Job Execution Results".into()))
@@ -322,6 +807,124 @@ pub async fn retrieve_results(
         )
 }
 
+/// Retrieve the results of a job knowing only its ID, as returned by [`submit`] or
+/// [`JobId`]'s [`Display`](fmt::Display) implementation, so that one process can submit jobs and
+/// another, which never saw their [`JobHandle`](crate::JobHandle)s, can collect their results.
+///
+/// The QPU doesn't record which memory references (ie. "ro\[0\]") a job's readout nodes
+/// (ie. "q0") were read out to; that readout map is part of the translation metadata returned
+/// when the program was translated, see
+/// [`EncryptedTranslationResult`](super::translation::EncryptedTranslationResult) and
+/// [`JobHandle::readout_map`](crate::JobHandle::readout_map). The submitter passes it along
+/// with the job ID, e.g. as JSON, and it is given here as `readout_map` to rebuild the mappings
+/// of the returned [`QpuResultData`].
+///
+/// # Errors
+///
+/// See [`retrieve_results`].
+pub async fn retrieve_results_by_id(
+    job_id: &str,
+    quantum_processor_id: Option<&str>,
+    client: &Qcs,
+    execution_options: &ExecutionOptions,
+    readout_map: &HashMap<String, String>,
+) -> Result<QpuResultData, QpuApiError> {
+    let result = retrieve_results(
+        JobId::from(job_id.to_string()),
+        quantum_processor_id,
+        client,
+        execution_options,
+    )
+    .await?;
+    Ok(QpuResultData::from_controller_mappings_and_values(
+        readout_map,
+        &result.readout_values,
+        &result.memory_values,
+    ))
+}
+
+/// As [`retrieve_results`], but stop waiting as soon as `cancellation_token` is cancelled,
+/// failing with [`QpuApiError::Cancelled`].
+///
+/// When the token is cancelled, an attempt is made to cancel the job (see [`cancel_job`]), which
+/// succeeds only if it hasn't started executing. A job which has started keeps running, and its
+/// results can still be retrieved later.
+///
+/// # Errors
+///
+/// See [`retrieve_results`].
+pub async fn retrieve_results_with_cancellation(
+    job_id: JobId,
+    quantum_processor_id: Option<&str>,
+    client: &Qcs,
+    execution_options: &ExecutionOptions,
+    cancellation_token: &CancellationToken,
+) -> Result<ControllerJobExecutionResult, QpuApiError> {
+    let retrieval = retrieve_results(
+        job_id.clone(),
+        quantum_processor_id,
+        client,
+        execution_options,
+    );
+    match until_cancelled(Some(cancellation_token), retrieval).await {
+        Some(result) => result,
+        None => {
+            cancel_abandoned_job(
+                job_id.clone(),
+                quantum_processor_id,
+                client,
+                execution_options,
+            )
+            .await;
+            Err(QpuApiError::Cancelled {
+                job_id: Some(job_id),
+            })
+        }
+    }
+}
+
+/// Run `future` to completion, or return `None` as soon as `cancellation_token` is cancelled,
+/// without polling `future` at all if it already is.
+pub(crate) async fn until_cancelled<F: Future>(
+    cancellation_token: Option<&CancellationToken>,
+    future: F,
+) -> Option<F::Output> {
+    let Some(cancellation_token) = cancellation_token else {
+        return Some(future.await);
+    };
+    if cancellation_token.is_cancelled() {
+        return None;
+    }
+
+    let cancelled = cancellation_token.cancelled();
+    futures::pin_mut!(future, cancelled);
+    match select(future, cancelled).await {
+        Either::Left((output, _)) => Some(output),
+        Either::Right(((), _)) => None,
+    }
+}
+
+/// Try to cancel a job whose submission or retrieval was cancelled. A job which has already
+/// started executing can't be cancelled, so failures are only logged.
+pub(crate) async fn cancel_abandoned_job(
+    job_id: JobId,
+    quantum_processor_id: Option<&str>,
+    client: &Qcs,
+    execution_options: &ExecutionOptions,
+) {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(%job_id, "cancelling job after its operation was cancelled");
+
+    let result = cancel_job(job_id, quantum_processor_id, client, execution_options).await;
+
+    #[cfg(feature = "tracing")]
+    if let Err(error) = &result {
+        tracing::warn!(%error, "failed to cancel job after its operation was cancelled");
+    }
+    #[cfg(not(feature = "tracing"))]
+    let _ = result;
+}
+
 /// Options available when connecting to a QPU.
 ///
 /// Use [`Default`] to get a reasonable set of defaults, or start with [`QpuConnectionOptionsBuilder`]
@@ -335,7 +938,11 @@ pub type QpuConnectionOptionsBuilder = ExecutionOptionsBuilder;
 ///
 /// Use [`Default`] to get a reasonable set of defaults, or start with [`ExecutionOptionsBuilder`]
 /// to build a custom set of options.
-#[derive(Builder, Clone, Debug, PartialEq)]
+///
+/// [`ExecutionOptions`] can be serialized, so that they can be persisted along with a
+/// [`JobHandle`](crate::JobHandle). Options missing when deserializing take their default values.
+#[derive(Builder, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ExecutionOptions {
     #[doc = "The [`ConnectionStrategy`] to use to establish a connection to the QPU."]
     #[builder(default)]
@@ -345,7 +952,41 @@ pub struct ExecutionOptions {
     timeout: Option<Duration>,
     #[doc = "Options available when executing a job on a QPU, particular to the execution service's API."]
     #[builder(default = "None")]
+    #[serde(with = "serde_api_options")]
     api_options: Option<InnerApiExecutionOptions>,
+    #[doc = "The maximum size of a single request, in bytes, when submitting a batch of patch values. If set, larger batches are split across multiple requests; see [`GRPC_MAX_MESSAGE_BYTES`]. Defaults to `None`, in which case batches are never split."]
+    #[builder(default = "None")]
+    max_request_bytes: Option<usize>,
+    #[doc = "How to retry requests which the service rejects for a reason that may be temporary, such as being briefly unavailable, unless overridden for a particular request. Failures to reach the service are retried by the connection itself."]
+    #[builder(default)]
+    retry_policy: RetryPolicy,
+    #[doc = "Overrides the `retry_policy` when submitting jobs. Defaults to [`RetryPolicy::for_submission`]."]
+    #[builder(default = "Some(RetryPolicy::for_submission())")]
+    submit_retry_policy: Option<RetryPolicy>,
+    #[doc = "Overrides the `retry_policy` when cancelling jobs."]
+    #[builder(default = "None")]
+    cancel_retry_policy: Option<RetryPolicy>,
+    #[doc = "Overrides the `retry_policy` when retrieving job results."]
+    #[builder(default = "None")]
+    retrieve_results_retry_policy: Option<RetryPolicy>,
+    #[doc = "Results whose readout values would take more than this many bytes once decoded are decoded into temporary files instead, and returned as [`ResultData::Spilled`](crate::ResultData::Spilled), whose registers can be read back a block of rows at a time. Results which don't fit a [`RegisterMatrix`](crate::RegisterMatrix) are always decoded into memory. Defaults to `None`, which never spills results."]
+    #[builder(default = "None")]
+    spill_threshold_bytes: Option<usize>,
+    #[doc = "The directory to create the temporary files for spilled results in. Defaults to `None`, which uses [`std::env::temp_dir`]."]
+    #[builder(default = "None")]
+    spill_directory: Option<PathBuf>,
+    #[doc = "Whether submitting a job first waits for one of your reservations of the quantum processor to begin, checking with increasing delays. Submitting fails if there is no current or upcoming reservation. Defaults to `false`, which submits right away. See [`wait_for_reservation`](super::reservation::wait_for_reservation)."]
+    #[builder(default = "false")]
+    wait_for_reservation: bool,
+    #[doc = "The longest time to wait for a reservation to begin when [`ExecutionOptions::wait_for_reservation`] is set. Submitting fails right away if the next reservation begins later than this. Defaults to [`DEFAULT_MAX_RESERVATION_WAIT`]; if set to `None`, there is no limit."]
+    #[builder(default = "Some(DEFAULT_MAX_RESERVATION_WAIT)")]
+    max_reservation_wait: Option<Duration>,
+    #[doc = "Whether to send the OpenTelemetry context of the current [`tracing`] span as gRPC metadata when submitting jobs and retrieving their results, so that the service's spans join the same trace. Requires the `tracing-opentelemetry` feature, and is ignored without it. Defaults to `false`."]
+    #[builder(default = "false")]
+    propagate_trace_context: bool,
+    #[doc = "The most shots to run in a single job. [`Executable::execute_on_qpu`](crate::Executable::execute_on_qpu) and [`Executable::execute_parameter_batch_on_qpu`](crate::Executable::execute_parameter_batch_on_qpu) split a request for more shots than this across several jobs, which they submit together, and return their results combined in shot order. Methods which return a [`JobHandle`](crate::JobHandle) per run, such as [`Executable::submit_to_qpu`](crate::Executable::submit_to_qpu), fail with [`Error::TooManyShotsPerJob`](crate::Error::TooManyShotsPerJob) instead. Defaults to `None`, which runs every shot in a single job."]
+    #[builder(default = "None")]
+    max_shots_per_job: Option<NonZeroU16>,
 }
 
 impl Default for ExecutionOptions {
@@ -358,6 +999,34 @@ impl Default for ExecutionOptions {
 
 impl Eq for ExecutionOptions {}
 
+/// (De)serializes [`InnerApiExecutionOptions`] as their protobuf encoding.
+mod serde_api_options {
+    use prost::Message;
+    use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::InnerApiExecutionOptions;
+
+    pub(super) fn serialize<S: Serializer>(
+        options: &Option<InnerApiExecutionOptions>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        options
+            .as_ref()
+            .map(Message::encode_to_vec)
+            .serialize(serializer)
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<InnerApiExecutionOptions>, D::Error> {
+        Option::<Vec<u8>>::deserialize(deserializer)?
+            .map(|bytes| {
+                InnerApiExecutionOptions::decode(bytes.as_slice()).map_err(D::Error::custom)
+            })
+            .transpose()
+    }
+}
+
 /// Options available when executing a job on a QPU, particular to the execution service's API.
 /// This is a conventent alias for [`InnerApiExecutionOptions`] which provides a builder.
 ///
@@ -431,6 +1100,23 @@ impl ApiExecutionOptionsBuilder {
     }
 }
 
+impl ExecutionOptionsBuilder {
+    /// Set the number of times to retry retrieving a job's results after a temporary failure.
+    ///
+    /// This is shorthand for overriding `retrieve_results_retry_policy` with the policy which would
+    /// otherwise apply, but with `retries` as its `max_retries`.
+    pub fn result_retrieval_retries(&mut self, retries: u32) -> &mut Self {
+        let policy = self
+            .retrieve_results_retry_policy
+            .clone()
+            .flatten()
+            .or_else(|| self.retry_policy.clone())
+            .unwrap_or_default()
+            .with_max_retries(retries);
+        self.retrieve_results_retry_policy(Some(policy))
+    }
+}
+
 impl ExecutionOptions {
     /// Get an [`ExecutionOptionsBuilder`] that can be used to build a custom [`ExecutionOptions`].
     #[must_use]
@@ -455,10 +1141,97 @@ impl ExecutionOptions {
     pub fn api_options(&self) -> Option<&InnerApiExecutionOptions> {
         self.api_options.as_ref()
     }
+
+    /// Get the maximum size of a single request when submitting a batch of patch values.
+    #[must_use]
+    pub fn max_request_bytes(&self) -> Option<usize> {
+        self.max_request_bytes
+    }
+
+    /// Get the [`RetryPolicy`] used for requests without their own override.
+    #[must_use]
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+
+    /// Get the [`RetryPolicy`] override for submitting jobs.
+    #[must_use]
+    pub fn submit_retry_policy(&self) -> Option<&RetryPolicy> {
+        self.submit_retry_policy.as_ref()
+    }
+
+    /// Get the [`RetryPolicy`] override for cancelling jobs.
+    #[must_use]
+    pub fn cancel_retry_policy(&self) -> Option<&RetryPolicy> {
+        self.cancel_retry_policy.as_ref()
+    }
+
+    /// Get the [`RetryPolicy`] override for retrieving job results.
+    #[must_use]
+    pub fn retrieve_results_retry_policy(&self) -> Option<&RetryPolicy> {
+        self.retrieve_results_retry_policy.as_ref()
+    }
+
+    /// Get the [`RetryPolicy`] which applies to `call`: its override if it has one, otherwise
+    /// [`ExecutionOptions::retry_policy`].
+    #[must_use]
+    pub fn retry_policy_for(&self, call: QpuApiCall) -> &RetryPolicy {
+        match call {
+            QpuApiCall::Submit => self.submit_retry_policy.as_ref(),
+            QpuApiCall::Cancel => self.cancel_retry_policy.as_ref(),
+            QpuApiCall::RetrieveResults => self.retrieve_results_retry_policy.as_ref(),
+            QpuApiCall::GetJobStatus => None,
+        }
+        .unwrap_or(&self.retry_policy)
+    }
+
+    /// Get the number of times to retry retrieving a job's results after a temporary failure.
+    /// Shorthand for the `max_retries` of the [`RetryPolicy`] for [`QpuApiCall::RetrieveResults`].
+    #[must_use]
+    pub fn result_retrieval_retries(&self) -> u32 {
+        self.retry_policy_for(QpuApiCall::RetrieveResults)
+            .max_retries()
+    }
+
+    /// Get the size above which results are decoded into temporary files instead of memory.
+    #[must_use]
+    pub fn spill_threshold_bytes(&self) -> Option<usize> {
+        self.spill_threshold_bytes
+    }
+
+    /// Get the directory in which temporary files for spilled results are created.
+    #[must_use]
+    pub fn spill_directory(&self) -> Option<&Path> {
+        self.spill_directory.as_deref()
+    }
+
+    /// Get whether submitting a job waits for a reservation to begin.
+    #[must_use]
+    pub fn wait_for_reservation(&self) -> bool {
+        self.wait_for_reservation
+    }
+
+    /// Get the longest time to wait for a reservation to begin.
+    #[must_use]
+    pub fn max_reservation_wait(&self) -> Option<Duration> {
+        self.max_reservation_wait
+    }
+
+    /// Get whether the trace context of the current span is sent along with requests.
+    #[must_use]
+    pub fn propagate_trace_context(&self) -> bool {
+        self.propagate_trace_context
+    }
+
+    /// Get the most shots to run in a single job.
+    #[must_use]
+    pub fn max_shots_per_job(&self) -> Option<NonZeroU16> {
+        self.max_shots_per_job
+    }
 }
 
 /// The connection strategy to use when submitting and retrieving jobs from a QPU.
-#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ConnectionStrategy {
     /// Connect through the publicly accessible gateway.
     #[default]
@@ -512,6 +1285,21 @@ pub trait ExecutionTarget<'a> {
         }
     }
 
+    /// Get the [`get_controller_job_status_request::Target`] for the given quantum processor ID.
+    fn get_job_status_target(
+        &'a self,
+        quantum_processor_id: Option<&str>,
+    ) -> Option<get_controller_job_status_request::Target> {
+        match self.connection_strategy() {
+            ConnectionStrategy::EndpointId(endpoint_id) => Some(
+                get_controller_job_status_request::Target::EndpointId(endpoint_id.to_string()),
+            ),
+            ConnectionStrategy::Gateway | ConnectionStrategy::DirectAccess => quantum_processor_id
+                .map(String::from)
+                .map(get_controller_job_status_request::Target::QuantumProcessorId),
+        }
+    }
+
     /// Get the [`cancel_controller_jobs_request::Target`] for the given quantum processor ID.
     fn get_cancel_target(
         &'a self,
@@ -528,7 +1316,7 @@ pub trait ExecutionTarget<'a> {
     }
 
     /// Get a controller client for the given quantum processor ID.
-    async fn get_controller_client(
+    pub(crate) async fn get_controller_client(
         &'a self,
         client: &Qcs,
         quantum_processor_id: Option<&str>,
@@ -546,9 +1334,26 @@ pub trait ExecutionTarget<'a> {
         client: &Qcs,
         quantum_processor_id: Option<&str>,
     ) -> Result<GrpcConnection, QpuApiError> {
+        let address = self
+            .get_qpu_grpc_address(client, quantum_processor_id)
+            .await?;
+        self.grpc_address_to_channel(&address, client)
+    }
+
+    /// Get the address of the gRPC service which the [`ConnectionStrategy`] connects to for the
+    /// given quantum processor ID, without connecting to it.
+    async fn get_qpu_grpc_address(
+        &'a self,
+        client: &Qcs,
+        quantum_processor_id: Option<&str>,
+    ) -> Result<String, QpuApiError> {
         let address = match self.connection_strategy() {
             ConnectionStrategy::EndpointId(endpoint_id) => {
-                let endpoint = get_endpoint(&client.get_openapi_client(), endpoint_id).await?;
+                let endpoint = client
+                    .with_refresh(|configuration| async move {
+                        get_endpoint(&configuration, endpoint_id).await
+                    })
+                    .await?;
                 endpoint
                     .addresses
                     .grpc
@@ -569,7 +1374,7 @@ pub trait ExecutionTarget<'a> {
                 .await?
             }
         };
-        self.grpc_address_to_channel(&address, client)
+        Ok(address)
     }
 
     /// Get a channel from the given gRPC address.
@@ -579,10 +1384,13 @@ pub trait ExecutionTarget<'a> {
         client: &Qcs,
     ) -> Result<GrpcConnection, QpuApiError> {
         let uri = parse_uri(address).map_err(QpuApiError::GrpcError)?;
-        let channel = get_channel_with_timeout(uri, self.timeout())
+        let channel = client
+            .grpc_channel(uri, self.timeout())
             .map_err(|err| QpuApiError::GrpcError(err.into()))?;
-        let channel =
-            wrap_channel_with_retry(wrap_channel_with(channel, client.get_config().clone()));
+        let channel = wrap_channel_with_retry(wrap_channel_with_token_refresher(
+            channel,
+            client.token_refresher(),
+        ));
         #[cfg(feature = "grpc-web")]
         let channel = wrap_channel_with_grpc_web(channel);
         Ok(channel)
@@ -594,6 +1402,7 @@ pub trait ExecutionTarget<'a> {
         quantum_processor_id: &str,
         client: &Qcs,
     ) -> Result<String, QpuApiError> {
+        metrics::record_lookup(Cache::GatewayAddress);
         get_accessor_with_cache(quantum_processor_id, client).await
     }
 
@@ -603,6 +1412,7 @@ pub trait ExecutionTarget<'a> {
         quantum_processor_id: &str,
         client: &Qcs,
     ) -> Result<String, QpuApiError> {
+        metrics::record_lookup(Cache::DefaultEndpoint);
         get_default_endpoint_with_cache(quantum_processor_id, client).await
     }
 }
@@ -634,6 +1444,7 @@ async fn get_accessor_with_cache(
 ) -> Result<String, QpuApiError> {
     #[cfg(feature = "tracing")]
     tracing::info!(quantum_processor_id=%quantum_processor_id, "get_accessor cache miss");
+    metrics::record_miss(Cache::GatewayAddress);
     get_accessor(quantum_processor_id, client).await
 }
 
@@ -641,13 +1452,18 @@ async fn get_accessor(quantum_processor_id: &str, client: &Qcs) -> Result<String
     let mut min = None;
     let mut next_page_token = None;
     loop {
-        let accessors = list_quantum_processor_accessors(
-            &client.get_openapi_client(),
-            quantum_processor_id,
-            Some(100),
-            next_page_token.as_deref(),
-        )
-        .await?;
+        let page = next_page_token.as_deref();
+        let accessors = client
+            .with_refresh(|configuration| async move {
+                list_quantum_processor_accessors(
+                    &configuration,
+                    quantum_processor_id,
+                    Some(100),
+                    page,
+                )
+                .await
+            })
+            .await?;
 
         let accessor = accessors
             .accessors
@@ -686,6 +1502,7 @@ async fn get_default_endpoint_with_cache(
 ) -> Result<String, QpuApiError> {
     #[cfg(feature = "tracing")]
     tracing::info!(quantum_processor_id=%quantum_processor_id, "get_default_endpoint cache miss");
+    metrics::record_miss(Cache::DefaultEndpoint);
     get_default_endpoint(quantum_processor_id, client).await
 }
 
@@ -693,8 +1510,11 @@ async fn get_default_endpoint(
     quantum_processor_id: &str,
     client: &Qcs,
 ) -> Result<String, QpuApiError> {
-    let default_endpoint =
-        api_get_default_endpoint(&client.get_openapi_client(), quantum_processor_id).await?;
+    let default_endpoint = client
+        .with_refresh(|configuration| async move {
+            api_get_default_endpoint(&configuration, quantum_processor_id).await
+        })
+        .await?;
     let addresses = default_endpoint.addresses.as_ref();
     let grpc_address = addresses.grpc.as_ref();
     grpc_address
@@ -704,6 +1524,7 @@ async fn get_default_endpoint(
 
 /// Errors that can occur while attempting to establish a connection to the QPU.
 #[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
 pub enum QpuApiError {
     /// Error due to a bad gRPC configuration
     #[error("Error configuring gRPC request: {0}")]
@@ -745,6 +1566,33 @@ pub enum QpuApiError {
     #[error("Submitting a job requires at least one set of patch values")]
     EmptyPatchValues,
 
+    /// The client is in read-only mode, so jobs can't be submitted. See [`Qcs::with_read_only`].
+    #[error("Submitting jobs is disabled because the QCS client is read-only (see the {} environment variable)", crate::client::READ_ONLY_VAR)]
+    SubmissionsDisabled,
+
+    /// Waiting for a reservation to begin failed, see [`ExecutionOptions::wait_for_reservation`].
+    #[error(transparent)]
+    Reservation(#[from] reservation::Error),
+
+    /// The operation was abandoned because its [`CancellationToken`] was cancelled, see
+    /// [`submit_with_cancellation`] and [`retrieve_results_with_cancellation`].
+    #[error("The operation was cancelled")]
+    Cancelled {
+        /// The job which was submitted, if known. Cancelling it was attempted, but may not have
+        /// succeeded if it had already started executing.
+        job_id: Option<JobId>,
+    },
+
+    /// Some, but not all, of the requests needed to submit a batch of patch values succeeded.
+    #[error("{} jobs were queued before a request failed: {source}", .job_ids.len())]
+    PartiallySubmitted {
+        /// The jobs which were queued, in the order of their patch values.
+        job_ids: Vec<JobId>,
+        /// The error from the request which failed.
+        #[source]
+        source: Box<QpuApiError>,
+    },
+
     /// Error that can occur when controller service fails to execute a job
     #[error("The submitted job failed with status: {status}. {message}")]
     JobExecutionFailed {
@@ -769,11 +1617,38 @@ pub enum QpuApiError {
     },
 }
 
+impl QpuApiError {
+    /// Whether a request failed because it couldn't be authenticated, even after refreshing the
+    /// access token.
+    #[must_use]
+    pub fn is_unauthenticated(&self) -> bool {
+        match self {
+            Self::QpuEndpointRequestFailed(error) => is_unauthenticated_response(error),
+            Self::AccessorRequestFailed(error) => is_unauthenticated_response(error),
+            Self::EndpointRequestFailed(error) => is_unauthenticated_response(error),
+            Self::GrpcClientError(error) => error.is_unauthenticated(),
+            Self::PartiallySubmitted { source, .. } => source.is_unauthenticated(),
+            _ => false,
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
+    use std::{collections::HashMap, time::Duration};
+
+    use crate::client::Qcs;
     use crate::qpu::api::ExecutionOptions;
 
-    use super::ExecutionOptionsBuilder;
+    use qcs_api_client_grpc::models::controller::EncryptedControllerJob;
+    use tonic::metadata::MetadataMap;
+
+    use super::{
+        chunk_execution_configurations, encoded_len_in_repeated_field,
+        params_into_job_execution_configuration, propagate_trace_context, submit,
+        submit_with_cancellation, until_cancelled, CancellationToken, ExecutionOptionsBuilder,
+        QpuApiCall, QpuApiError, RetryPolicy,
+    };
 
     #[test]
     fn test_default_execution_options() {
@@ -782,4 +1657,166 @@ mod test {
             ExecutionOptionsBuilder::default().build().unwrap(),
         );
     }
+
+    #[test]
+    fn test_chunk_execution_configurations() {
+        let configurations: Vec<_> = (0..5)
+            .map(|index| {
+                params_into_job_execution_configuration(&HashMap::from([(
+                    Box::from("theta"),
+                    vec![f64::from(index); 10],
+                )]))
+            })
+            .collect();
+        let len = encoded_len_in_repeated_field(&configurations[0]);
+        let base_len = 100;
+
+        assert_eq!(
+            chunk_execution_configurations(base_len, &configurations, None),
+            vec![0..5]
+        );
+        assert_eq!(
+            chunk_execution_configurations(base_len, &configurations, Some(base_len + 2 * len)),
+            vec![0..2, 2..4, 4..5]
+        );
+        assert_eq!(
+            chunk_execution_configurations(base_len, &configurations, Some(base_len + 2 * len - 1)),
+            vec![0..1, 1..2, 2..3, 3..4, 4..5]
+        );
+        // Configurations that can't fit on their own are still sent, one per request.
+        assert_eq!(
+            chunk_execution_configurations(base_len, &configurations[..2], Some(base_len)),
+            vec![0..1, 1..2]
+        );
+    }
+
+    #[test]
+    fn test_retry_policy_overrides() {
+        let options = ExecutionOptions::default();
+        assert_eq!(
+            options.retry_policy_for(QpuApiCall::Submit),
+            &RetryPolicy::for_submission()
+        );
+        assert_eq!(
+            options.retry_policy_for(QpuApiCall::RetrieveResults),
+            &RetryPolicy::default()
+        );
+
+        let retrieval = RetryPolicy::builder()
+            .max_retries(10)
+            .max_delay(Duration::from_secs(60))
+            .build()
+            .unwrap();
+        let options = ExecutionOptionsBuilder::default()
+            .retry_policy(RetryPolicy::never())
+            .submit_retry_policy(None)
+            .retrieve_results_retry_policy(Some(retrieval.clone()))
+            .build()
+            .unwrap();
+        assert_eq!(
+            options.retry_policy_for(QpuApiCall::Submit),
+            &RetryPolicy::never()
+        );
+        assert_eq!(
+            options.retry_policy_for(QpuApiCall::Cancel),
+            &RetryPolicy::never()
+        );
+        assert_eq!(
+            options.retry_policy_for(QpuApiCall::RetrieveResults),
+            &retrieval
+        );
+    }
+
+    #[test]
+    fn test_result_retrieval_retries() {
+        assert_eq!(ExecutionOptions::default().result_retrieval_retries(), 3);
+
+        let options = ExecutionOptionsBuilder::default()
+            .retry_policy(RetryPolicy::builder().jitter(false).build().unwrap())
+            .result_retrieval_retries(0)
+            .build()
+            .unwrap();
+        assert_eq!(options.result_retrieval_retries(), 0);
+        assert_eq!(
+            options.retry_policy_for(QpuApiCall::RetrieveResults),
+            &RetryPolicy::builder()
+                .max_retries(0)
+                .jitter(false)
+                .build()
+                .unwrap()
+        );
+        assert_eq!(
+            options
+                .retry_policy_for(QpuApiCall::GetJobStatus)
+                .max_retries(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_trace_context_is_not_propagated_by_default() {
+        let mut metadata = MetadataMap::new();
+        propagate_trace_context(&ExecutionOptions::default(), &mut metadata);
+        assert!(metadata.is_empty());
+    }
+
+    #[cfg(feature = "tracing-opentelemetry")]
+    #[test]
+    fn test_metadata_injector() {
+        use opentelemetry::propagation::Injector;
+
+        let traceparent = "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01";
+        let mut metadata = MetadataMap::new();
+        super::MetadataInjector(&mut metadata).set("traceparent", traceparent.to_string());
+        super::MetadataInjector(&mut metadata).set("not a key", String::from("ignored"));
+        assert_eq!(metadata.get("traceparent").unwrap(), traceparent);
+        assert_eq!(metadata.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_read_only_client_refuses_submissions() {
+        let client = Qcs::default().with_read_only(true);
+        let patch_values: HashMap<Box<str>, Vec<f64>> = HashMap::new();
+        let result = submit(
+            Some("Aspen-M-3"),
+            EncryptedControllerJob::default(),
+            &patch_values,
+            &client,
+            &ExecutionOptions::default(),
+        )
+        .await;
+        assert!(matches!(result, Err(QpuApiError::SubmissionsDisabled)));
+    }
+
+    #[tokio::test]
+    async fn test_until_cancelled() {
+        let token = CancellationToken::new();
+        assert_eq!(until_cancelled(Some(&token), async { 42 }).await, Some(42));
+        assert_eq!(until_cancelled(None, async { 42 }).await, Some(42));
+
+        let waiting = until_cancelled(Some(&token), std::future::pending::<()>());
+        token.cancel();
+        assert_eq!(waiting.await, None);
+        assert_eq!(until_cancelled(Some(&token), async { 42 }).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_submission_is_not_sent() {
+        let token = CancellationToken::new();
+        token.cancel();
+        let patch_values: HashMap<Box<str>, Vec<f64>> = HashMap::new();
+        let result = submit_with_cancellation(
+            Some("Aspen-M-3"),
+            EncryptedControllerJob::default(),
+            &patch_values,
+            &Qcs::default(),
+            &ExecutionOptions::default(),
+            &token,
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(QpuApiError::Cancelled { job_id: None })
+        ));
+    }
 }
@@ -0,0 +1,419 @@
+//! Running many related programs on one QPU as a group, see [`Batch`].
+
+use std::borrow::Cow;
+use std::num::NonZeroU16;
+use std::sync::Arc;
+
+use futures::{stream, StreamExt};
+use qcs_api_client_openapi::models::InstructionSetArchitecture;
+use quil_rs::quil::Quil;
+use quil_rs::Program;
+
+use crate::client::Qcs;
+use crate::compiler::quilc::CompilerOpts;
+use crate::compiler::CompilerBackend;
+use crate::{Error, ExecutionResult, JobHandle};
+
+use super::api::{connect_to_controller, ControllerConnection, ExecutionOptions};
+use super::translation::{EncryptedTranslationResult, TranslationOptions};
+use super::{Execution, ExecutionError, IsaCache};
+
+/// The number of programs compiled, translated, or retrieved at once by default.
+const DEFAULT_MAX_CONCURRENCY: usize = 8;
+
+/// A group of programs, such as the fragments of a tomography experiment, which are compiled,
+/// translated, and run together on one QPU.
+///
+/// The QPU's ISA is fetched once and every program is compiled for it. Programs are compiled and
+/// translated concurrently, then submitted in order over a single connection to the QPU, and their
+/// results are retrieved concurrently. Every program has its own [`ExecutionResult`], in the order
+/// the programs were given, so that a program which fails to compile, translate, submit, or run
+/// doesn't stop the rest.
+///
+/// ```no_run
+/// use std::str::FromStr;
+///
+/// use qcs::{client::Qcs, qpu::batch::Batch, quil_rs::Program};
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), qcs::Error> {
+/// let programs = ["X 0", "H 0", "RX(pi/2) 0"]
+///     .iter()
+///     .map(|gate| Program::from_str(&format!("DECLARE ro BIT\n{gate}\nMEASURE 0 ro")).unwrap())
+///     .collect();
+/// let results = Batch::new("Ankaa-3", programs, Qcs::load())
+///     .with_shots(std::num::NonZeroU16::new(100).unwrap())
+///     .execute()
+///     .await?;
+/// for result in results {
+///     println!("{:?}", result.map(|data| data.result_data));
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[allow(missing_debug_implementations)]
+pub struct Batch {
+    quantum_processor_id: String,
+    programs: Vec<Program>,
+    client: Arc<Qcs>,
+    shots: NonZeroU16,
+    compiler: Option<Arc<dyn CompilerBackend + Send + Sync>>,
+    compiler_options: CompilerOpts,
+    max_concurrency: usize,
+    translation_options: Option<TranslationOptions>,
+    execution_options: ExecutionOptions,
+}
+
+/// The programs of a [`Batch`] which were submitted, see [`Batch::submit`].
+#[allow(missing_debug_implementations)]
+pub struct BatchSubmission {
+    jobs: Vec<Result<(Execution<'static>, JobHandle<'static>), Error>>,
+}
+
+impl BatchSubmission {
+    /// The handle of each program's job, in the order the programs were given, or the error which
+    /// stopped it from being submitted.
+    pub fn job_handles(&self) -> impl Iterator<Item = Result<&JobHandle<'static>, &Error>> {
+        self.jobs
+            .iter()
+            .map(|job| job.as_ref().map(|(_, job_handle)| job_handle))
+    }
+}
+
+impl Batch {
+    /// A batch of `programs` to run on `quantum_processor_id` with `client`, with one shot each
+    /// and without compilation. See [`Batch::with_compiler_backend`].
+    #[must_use]
+    #[allow(clippy::missing_panics_doc)]
+    pub fn new<S: Into<String>>(
+        quantum_processor_id: S,
+        programs: Vec<Program>,
+        client: Qcs,
+    ) -> Self {
+        Self {
+            quantum_processor_id: quantum_processor_id.into(),
+            programs,
+            client: Arc::new(client),
+            shots: NonZeroU16::new(1).expect("value is non-zero"),
+            compiler: None,
+            compiler_options: CompilerOpts::default(),
+            max_concurrency: DEFAULT_MAX_CONCURRENCY,
+            translation_options: None,
+            execution_options: ExecutionOptions::default(),
+        }
+    }
+
+    /// Run every program `shots` times.
+    #[must_use]
+    pub fn with_shots(mut self, shots: NonZeroU16) -> Self {
+        self.shots = shots;
+        self
+    }
+
+    /// Compile every program with `backend` before translating it, or translate the programs as
+    /// they are if `None`. See [`Executable::with_compiler_backend`](crate::Executable::with_compiler_backend).
+    #[must_use]
+    pub fn with_compiler_backend<C: CompilerBackend + Send + Sync + 'static>(
+        mut self,
+        backend: Option<C>,
+    ) -> Self {
+        self.compiler = backend.map(|backend| Arc::new(backend) as _);
+        self
+    }
+
+    /// The options to compile every program with.
+    #[must_use]
+    pub fn compiler_options(mut self, options: CompilerOpts) -> Self {
+        self.compiler_options = options;
+        self
+    }
+
+    /// The greatest number of programs to compile, translate, or retrieve results for at once.
+    /// Defaults to 8; zero is treated as one.
+    #[must_use]
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency.max(1);
+        self
+    }
+
+    /// The options to translate every program with.
+    #[must_use]
+    pub fn with_translation_options(mut self, options: Option<TranslationOptions>) -> Self {
+        self.translation_options = options;
+        self
+    }
+
+    /// The options to submit every program with.
+    #[must_use]
+    pub fn with_execution_options(mut self, options: ExecutionOptions) -> Self {
+        self.execution_options = options;
+        self
+    }
+
+    /// Compile, translate, and submit every program, then wait for their results. See
+    /// [`Batch::submit`] and [`Batch::retrieve_results`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Batch::submit`]. Errors with individual programs are returned in their results.
+    pub async fn execute(&self) -> Result<Vec<ExecutionResult>, Error> {
+        let submission = self.submit().await?;
+        Ok(self.retrieve_results(submission).await)
+    }
+
+    /// Compile, translate, and submit every program, without waiting for the results.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error, without submitting anything, if the QPU's ISA can't be fetched or the
+    /// QPU can't be connected to. Errors with individual programs are kept in the
+    /// [`BatchSubmission`], and returned by [`Batch::retrieve_results`].
+    pub async fn submit(&self) -> Result<BatchSubmission, Error> {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            quantum_processor_id = %self.quantum_processor_id,
+            num_programs = self.programs.len(),
+            "submitting batch of programs to QPU",
+        );
+
+        let isa = IsaCache::global()
+            .get(&self.quantum_processor_id, &self.client, false)
+            .await
+            .map_err(|error| Error::from(ExecutionError::from(error)))?;
+        let isa = Arc::new(isa);
+
+        let translated: Vec<_> = stream::iter(&self.programs)
+            .map(|program| {
+                let isa = isa.clone();
+                async move {
+                    let mut execution = self.compile(program, isa).await?;
+                    // Each execution is submitted once, so its translation is never reused and
+                    // there's no need to check the QPU's settings as `translate_or_reuse` would.
+                    let translation = execution
+                        .translate(self.translation_options.clone())
+                        .await?;
+                    Ok::<_, Error>((execution, translation))
+                }
+            })
+            .buffered(self.max_concurrency)
+            .collect()
+            .await;
+
+        let connection = connect_to_controller(
+            Some(&self.quantum_processor_id),
+            &self.client,
+            &self.execution_options,
+        )
+        .await
+        .map_err(|error| Error::from(ExecutionError::from(error)))?;
+
+        let mut jobs = Vec::with_capacity(translated.len());
+        for result in translated {
+            jobs.push(match result {
+                Ok((execution, translation)) => {
+                    self.submit_one(&connection, execution, translation).await
+                }
+                Err(error) => Err(error),
+            });
+        }
+        Ok(BatchSubmission { jobs })
+    }
+
+    /// Compile `program` for `isa` on a blocking thread, so that several programs can be compiled
+    /// at once without holding up the runtime.
+    async fn compile(
+        &self,
+        program: &Program,
+        isa: Arc<InstructionSetArchitecture>,
+    ) -> Result<Execution<'static>, Error> {
+        let quil = program.to_quil()?.into();
+        let shots = self.shots;
+        let quantum_processor_id = Cow::Owned(self.quantum_processor_id.clone());
+        let client = self.client.clone();
+        let compiler = self.compiler.clone();
+        let compiler_options = self.compiler_options;
+        tokio::task::spawn_blocking(move || {
+            Execution::with_isa(
+                quil,
+                shots,
+                quantum_processor_id,
+                client,
+                compiler,
+                compiler_options,
+                0,
+                &isa,
+            )
+        })
+        .await
+        .map_err(|error| Error::Unexpected(format!("compilation stopped unexpectedly: {error}")))?
+        .map_err(Error::from)
+    }
+
+    /// Submit one program's `translation` on `connection`.
+    async fn submit_one(
+        &self,
+        connection: &ControllerConnection,
+        execution: Execution<'static>,
+        translation: EncryptedTranslationResult,
+    ) -> Result<(Execution<'static>, JobHandle<'static>), Error> {
+        let job_handle = execution
+            .submit_translation_on(connection, translation, &self.execution_options)
+            .await?;
+        Ok((execution, job_handle))
+    }
+
+    /// Wait for the results of every program in `submission`, in the order the programs were
+    /// given. A program which wasn't submitted has the error which stopped it.
+    pub async fn retrieve_results(&self, submission: BatchSubmission) -> Vec<ExecutionResult> {
+        stream::iter(submission.jobs)
+            .map(|job| async move {
+                let (execution, job_handle) = job?;
+                execution
+                    .retrieve_results(job_handle)
+                    .await
+                    .map_err(Error::from)
+            })
+            .buffered(self.max_concurrency)
+            .collect()
+            .await
+    }
+}
+
+#[cfg(test)]
+mod describe_batch {
+    use super::{Batch, DEFAULT_MAX_CONCURRENCY};
+    use crate::client::Qcs;
+
+    #[test]
+    fn it_runs_one_shot_without_compilation_by_default() {
+        let batch = Batch::new("Ankaa-3", Vec::new(), Qcs::default());
+        assert_eq!(batch.shots.get(), 1);
+        assert!(batch.compiler.is_none());
+        assert_eq!(batch.max_concurrency, DEFAULT_MAX_CONCURRENCY);
+    }
+
+    #[test]
+    fn it_treats_zero_concurrency_as_one() {
+        let batch = Batch::new("Ankaa-3", Vec::new(), Qcs::default()).with_max_concurrency(0);
+        assert_eq!(batch.max_concurrency, 1);
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod describe_batch_on_a_mock_qpu {
+    use std::str::FromStr;
+    use std::time::Duration;
+
+    use quil_rs::Program;
+
+    use super::Batch;
+    use crate::compiler::quilc::{self, CompilationResult, CompilerOpts, TargetDevice};
+    use crate::compiler::CompilerBackend;
+    use crate::test_support::aspen_9_isa;
+    use crate::testing::{integer_result, MockQpu};
+    use crate::{Error, ExecutionResult};
+
+    /// A compiler which returns programs unchanged, and fails on programs using `CNOT`.
+    struct RefusesCnot;
+
+    impl CompilerBackend for RefusesCnot {
+        fn compile_to_native(
+            &self,
+            quil: &str,
+            _: TargetDevice,
+            _: CompilerOpts,
+        ) -> Result<CompilationResult, quilc::Error> {
+            if quil.contains("CNOT") {
+                return Err(quilc::Error::QuilcCompilation(
+                    quilc::CompilationError::Unsupported { operation: "CNOT" },
+                ));
+            }
+            Ok(CompilationResult {
+                program: Program::from_str(quil).map_err(quilc::Error::Parse)?,
+                native_quil_metadata: None,
+            })
+        }
+
+        fn get_version(&self) -> Result<String, quilc::Error> {
+            Ok("refuses-cnot".to_string())
+        }
+    }
+
+    /// A QPU which returns `values[i]` as the readout of the `i`th job submitted to it.
+    async fn qpu(quantum_processor_id: &str, values: &[i32]) -> MockQpu {
+        values.iter().fold(
+            MockQpu::start(quantum_processor_id, aspen_9_isa())
+                .await
+                .unwrap()
+                .with_readout_mapping("ro[0]", "q0"),
+            |qpu, &value| {
+                qpu.with_result(integer_result(
+                    [("q0", vec![value])],
+                    Duration::from_micros(100),
+                ))
+            },
+        )
+    }
+
+    fn programs(gates: &[&str]) -> Vec<Program> {
+        gates
+            .iter()
+            .map(|gate| {
+                Program::from_str(&format!("DECLARE ro INTEGER\n{gate}\nMEASURE 0 ro[0]")).unwrap()
+            })
+            .collect()
+    }
+
+    fn readout(result: &ExecutionResult) -> i64 {
+        let data = result.as_ref().expect("program should run");
+        let registers = data.result_data.to_register_map().unwrap();
+        registers
+            .get_register_matrix("ro")
+            .unwrap()
+            .as_integer()
+            .unwrap()[[0, 0]]
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn it_returns_results_in_the_order_the_programs_were_given() {
+        let qpu = qpu("mock-batch-order", &[10, 20, 30, 40]).await;
+
+        let results = Batch::new(
+            qpu.quantum_processor_id(),
+            programs(&["X 0", "Y 0", "Z 0", "H 0"]),
+            qpu.client(),
+        )
+        .with_compiler_backend(Some(RefusesCnot))
+        .with_execution_options(qpu.execution_options())
+        .execute()
+        .await
+        .unwrap();
+
+        let readouts: Vec<_> = results.iter().map(readout).collect();
+        assert_eq!(readouts, vec![10, 20, 30, 40]);
+        assert_eq!(qpu.submitted_jobs().len(), 4);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn it_runs_the_other_programs_when_one_fails() {
+        let qpu = qpu("mock-batch-errors", &[10, 30]).await;
+
+        let results = Batch::new(
+            qpu.quantum_processor_id(),
+            programs(&["X 0", "CNOT 0 1", "Z 0"]),
+            qpu.client(),
+        )
+        .with_compiler_backend(Some(RefusesCnot))
+        .with_execution_options(qpu.execution_options())
+        .execute()
+        .await
+        .unwrap();
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(readout(&results[0]), 10);
+        assert!(matches!(results[1], Err(Error::Compilation(_))));
+        assert_eq!(readout(&results[2]), 30);
+        assert_eq!(qpu.translated_programs().len(), 2);
+        assert_eq!(qpu.submitted_jobs().len(), 2);
+    }
+}
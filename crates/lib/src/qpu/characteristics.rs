@@ -0,0 +1,409 @@
+//! Track how the characteristics of a QPU, such as T1, T2, and gate fidelities, change over time.
+//!
+//! QCS serves the most recent characteristics of a QPU as part of its
+//! [`InstructionSetArchitecture`]. Each time [`history`] or [`latest`] fetches an ISA, the
+//! characteristics it contains are recorded in a process-wide cache, keyed by the time each was
+//! measured. Over the life of a process this builds up a time series suitable for drift
+//! dashboards. To keep history across processes, save a [`CharacteristicsHistory`] from
+//! [`cached_history`] (it implements [`Serialize`]) and restore it with [`load_history`].
+
+use std::collections::{BTreeMap, HashMap};
+use std::ops::RangeBounds;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use qcs_api_client_openapi::models::{Characteristic, InstructionSetArchitecture, Operation};
+use serde::{Deserialize, Serialize};
+
+use super::{get_isa, GetIsaError};
+use crate::client::Qcs;
+
+lazy_static! {
+    static ref HISTORY_CACHE: Mutex<HashMap<String, CharacteristicsHistory>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Identifies a single characteristic that is measured repeatedly over time.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct CharacteristicKey {
+    /// The operation or benchmark the characteristic belongs to, e.g. `RX` or `MEASURE`.
+    pub operation: String,
+    /// The qubits the characteristic was measured on. Empty for characteristics of the whole QPU.
+    pub node_ids: Vec<i64>,
+    /// The name of the characteristic, e.g. `T1` or `fRB`.
+    pub name: String,
+}
+
+/// A single measurement of a characteristic.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CharacteristicSample {
+    /// When the characteristic was measured.
+    pub timestamp: DateTime<Utc>,
+    /// The measured value.
+    pub value: f64,
+    /// The uncertainty of the measurement, if known.
+    pub error: Option<f64>,
+}
+
+/// The measurements of one characteristic, ordered by time.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TimeSeries {
+    /// The characteristic that was measured.
+    pub key: CharacteristicKey,
+    /// The measurements, oldest first.
+    pub samples: Vec<CharacteristicSample>,
+}
+
+/// Every recorded measurement of every characteristic of a QPU.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(from = "Vec<TimeSeries>", into = "Vec<TimeSeries>")]
+pub struct CharacteristicsHistory {
+    series: BTreeMap<CharacteristicKey, Vec<CharacteristicSample>>,
+}
+
+impl CharacteristicsHistory {
+    /// Record every characteristic in `isa`. Measurements which were already recorded (the same
+    /// characteristic with the same timestamp) are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a characteristic has a timestamp which isn't RFC 3339.
+    pub fn record(&mut self, isa: &InstructionSetArchitecture) -> Result<(), Error> {
+        for operation in isa.instructions.iter().chain(&isa.benchmarks) {
+            for (node_ids, characteristic) in operation_characteristics(operation) {
+                let key = CharacteristicKey {
+                    operation: operation.name.clone(),
+                    node_ids: node_ids.to_vec(),
+                    name: characteristic.name.clone(),
+                };
+                let sample = CharacteristicSample {
+                    timestamp: DateTime::parse_from_rfc3339(&characteristic.timestamp)
+                        .map_err(|source| Error::Timestamp {
+                            timestamp: characteristic.timestamp.clone(),
+                            source,
+                        })?
+                        .with_timezone(&Utc),
+                    value: characteristic.value,
+                    error: characteristic.error,
+                };
+                self.insert(key, sample);
+            }
+        }
+        Ok(())
+    }
+
+    fn insert(&mut self, key: CharacteristicKey, sample: CharacteristicSample) {
+        let samples = self.series.entry(key).or_default();
+        match samples.binary_search_by_key(&sample.timestamp, |existing| existing.timestamp) {
+            Ok(_) => {}
+            Err(index) => samples.insert(index, sample),
+        }
+    }
+
+    /// The measurements of the characteristics named in `names` (or all characteristics, if
+    /// `names` is empty) that were taken within `range`.
+    #[must_use]
+    pub fn select<R>(&self, names: &[&str], range: &R) -> Vec<TimeSeries>
+    where
+        R: RangeBounds<DateTime<Utc>>,
+    {
+        self.series
+            .iter()
+            .filter(|(key, _)| names.is_empty() || names.contains(&key.name.as_str()))
+            .map(|(key, samples)| TimeSeries {
+                key: key.clone(),
+                samples: samples
+                    .iter()
+                    .filter(|sample| range.contains(&sample.timestamp))
+                    .copied()
+                    .collect(),
+            })
+            .filter(|series| !series.samples.is_empty())
+            .collect()
+    }
+
+    /// The most recent measurement of every characteristic.
+    #[must_use]
+    pub fn latest_samples(&self) -> Vec<(CharacteristicKey, CharacteristicSample)> {
+        self.series
+            .iter()
+            .filter_map(|(key, samples)| samples.last().map(|sample| (key.clone(), *sample)))
+            .collect()
+    }
+
+    /// Whether no measurements have been recorded.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.series.is_empty()
+    }
+}
+
+impl From<Vec<TimeSeries>> for CharacteristicsHistory {
+    fn from(series: Vec<TimeSeries>) -> Self {
+        let mut history = Self::default();
+        for TimeSeries { key, samples } in series {
+            for sample in samples {
+                history.insert(key.clone(), sample);
+            }
+        }
+        history
+    }
+}
+
+impl From<CharacteristicsHistory> for Vec<TimeSeries> {
+    fn from(history: CharacteristicsHistory) -> Self {
+        history
+            .series
+            .into_iter()
+            .map(|(key, samples)| TimeSeries { key, samples })
+            .collect()
+    }
+}
+
+fn operation_characteristics(
+    operation: &Operation,
+) -> impl Iterator<Item = (&[i64], &Characteristic)> + '_ {
+    let qpu_wide = operation
+        .characteristics
+        .iter()
+        .map(|characteristic| (&[][..], characteristic));
+    let per_site = operation.sites.iter().flat_map(|site| {
+        site.characteristics
+            .iter()
+            .map(move |characteristic| (site.node_ids.as_slice(), characteristic))
+    });
+    qpu_wide.chain(per_site)
+}
+
+/// A summary of the most recent measurements of one characteristic across every site of a QPU.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CharacteristicSummary {
+    /// The operation or benchmark the characteristic belongs to.
+    pub operation: String,
+    /// The name of the characteristic.
+    pub name: String,
+    /// The number of sites the characteristic was measured on.
+    pub count: usize,
+    /// The mean of the most recent values.
+    pub mean: f64,
+    /// The smallest of the most recent values.
+    pub min: f64,
+    /// The largest of the most recent values.
+    pub max: f64,
+    /// The time of the most recent measurement.
+    pub latest_timestamp: DateTime<Utc>,
+}
+
+/// Fetch the current characteristics of a QPU, record them, and return the recorded
+/// measurements of the characteristics named in `names` (or all, if empty) taken within `range`.
+///
+/// See the [module documentation](self) for how history is accumulated.
+///
+/// # Errors
+///
+/// Returns an error if the ISA can't be fetched or contains an invalid timestamp.
+pub async fn history<R>(
+    quantum_processor_id: &str,
+    names: &[&str],
+    range: R,
+    client: &Qcs,
+) -> Result<Vec<TimeSeries>, Error>
+where
+    R: RangeBounds<DateTime<Utc>>,
+{
+    let history = refresh(quantum_processor_id, client).await?;
+    Ok(history.select(names, &range))
+}
+
+/// Fetch the current characteristics of a QPU, record them, and summarize the most recent value
+/// of each characteristic across all the sites it was measured on.
+///
+/// # Errors
+///
+/// Returns an error if the ISA can't be fetched or contains an invalid timestamp.
+pub async fn latest(
+    quantum_processor_id: &str,
+    client: &Qcs,
+) -> Result<Vec<CharacteristicSummary>, Error> {
+    let history = refresh(quantum_processor_id, client).await?;
+    Ok(summarize(&history))
+}
+
+fn summarize(history: &CharacteristicsHistory) -> Vec<CharacteristicSummary> {
+    let mut summaries: BTreeMap<(String, String), CharacteristicSummary> = BTreeMap::new();
+    for (key, sample) in history.latest_samples() {
+        summaries
+            .entry((key.operation.clone(), key.name.clone()))
+            .and_modify(|summary| {
+                summary.count += 1;
+                summary.mean += sample.value;
+                summary.min = summary.min.min(sample.value);
+                summary.max = summary.max.max(sample.value);
+                summary.latest_timestamp = summary.latest_timestamp.max(sample.timestamp);
+            })
+            .or_insert_with(|| CharacteristicSummary {
+                operation: key.operation,
+                name: key.name,
+                count: 1,
+                mean: sample.value,
+                min: sample.value,
+                max: sample.value,
+                latest_timestamp: sample.timestamp,
+            });
+    }
+    summaries
+        .into_values()
+        .map(|mut summary| {
+            #[allow(clippy::cast_precision_loss)]
+            let count = summary.count as f64;
+            summary.mean /= count;
+            summary
+        })
+        .collect()
+}
+
+async fn refresh(
+    quantum_processor_id: &str,
+    client: &Qcs,
+) -> Result<CharacteristicsHistory, Error> {
+    let isa = get_isa(quantum_processor_id, client).await?;
+    let mut cache = HISTORY_CACHE.lock().map_err(|_| Error::CachePoisoned)?;
+    let history = cache.entry(quantum_processor_id.to_string()).or_default();
+    history.record(&isa)?;
+    Ok(history.clone())
+}
+
+/// The history recorded so far for a QPU, if any, without contacting QCS.
+#[must_use]
+pub fn cached_history(quantum_processor_id: &str) -> Option<CharacteristicsHistory> {
+    HISTORY_CACHE
+        .lock()
+        .ok()?
+        .get(quantum_processor_id)
+        .cloned()
+}
+
+/// Merge previously saved history for a QPU into the cache.
+pub fn load_history(quantum_processor_id: &str, history: CharacteristicsHistory) {
+    if let Ok(mut cache) = HISTORY_CACHE.lock() {
+        let cached = cache.entry(quantum_processor_id.to_string()).or_default();
+        for (key, samples) in history.series {
+            for sample in samples {
+                cached.insert(key.clone(), sample);
+            }
+        }
+    }
+}
+
+/// Forget all recorded history.
+pub fn clear_cache() {
+    if let Ok(mut cache) = HISTORY_CACHE.lock() {
+        cache.clear();
+    }
+}
+
+/// Errors that can occur when fetching characteristics history.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// The ISA couldn't be fetched.
+    #[error("Failed to get the ISA: {0}")]
+    Isa(#[from] GetIsaError),
+    /// A characteristic had a timestamp which isn't RFC 3339.
+    #[error("Invalid characteristic timestamp {timestamp:?}: {source}")]
+    Timestamp {
+        /// The invalid timestamp.
+        timestamp: String,
+        /// Why it couldn't be parsed.
+        source: chrono::ParseError,
+    },
+    /// A thread panicked while holding the cache lock.
+    #[error("The characteristics cache is unusable because a thread panicked while using it")]
+    CachePoisoned,
+}
+
+#[cfg(test)]
+mod describe_characteristics_history {
+    use chrono::{DateTime, Utc};
+    use qcs_api_client_openapi::models::InstructionSetArchitecture;
+
+    use super::{summarize, CharacteristicsHistory};
+
+    fn timestamp(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn isa_at(timestamp: &str, fidelity: f64) -> InstructionSetArchitecture {
+        let mut isa: InstructionSetArchitecture =
+            serde_json::from_str(include_str!("../../tests/qcs-isa-Aspen-8.json")).unwrap();
+        for operation in isa.instructions.iter_mut().chain(isa.benchmarks.iter_mut()) {
+            for site in &mut operation.sites {
+                for characteristic in &mut site.characteristics {
+                    characteristic.timestamp = timestamp.to_string();
+                    characteristic.value = fidelity;
+                }
+            }
+        }
+        isa
+    }
+
+    #[test]
+    fn it_accumulates_samples_over_time() {
+        let mut history = CharacteristicsHistory::default();
+        history
+            .record(&isa_at("2024-01-01T00:00:00+00:00", 0.9))
+            .unwrap();
+        history
+            .record(&isa_at("2024-01-02T00:00:00+00:00", 0.8))
+            .unwrap();
+        // Recording the same measurements again has no effect.
+        history
+            .record(&isa_at("2024-01-02T00:00:00+00:00", 0.8))
+            .unwrap();
+
+        let series = history.select(&["fAR"], &(..));
+        assert!(!series.is_empty());
+        for series in &series {
+            assert_eq!(series.key.name, "fAR");
+            let values: Vec<_> = series.samples.iter().map(|sample| sample.value).collect();
+            assert_eq!(values, vec![0.9, 0.8]);
+        }
+
+        let recent = history.select(&["fAR"], &(timestamp("2024-01-01T12:00:00Z")..));
+        assert!(recent.iter().all(|series| series.samples.len() == 1));
+    }
+
+    #[test]
+    fn it_summarizes_the_latest_values() {
+        let mut history = CharacteristicsHistory::default();
+        history
+            .record(&isa_at("2024-01-01T00:00:00+00:00", 0.9))
+            .unwrap();
+        history
+            .record(&isa_at("2024-01-02T00:00:00+00:00", 0.8))
+            .unwrap();
+
+        let summaries = summarize(&history);
+        let far = summaries
+            .iter()
+            .find(|summary| summary.name == "fAR")
+            .expect("fAR should be summarized");
+        assert!(far.count > 1);
+        assert!((far.mean - 0.8).abs() < 1e-9);
+        assert_eq!(far.latest_timestamp, timestamp("2024-01-02T00:00:00Z"));
+    }
+
+    #[test]
+    fn it_round_trips_through_serde() {
+        let mut history = CharacteristicsHistory::default();
+        history
+            .record(&isa_at("2024-01-01T00:00:00+00:00", 0.9))
+            .unwrap();
+        let json = serde_json::to_string(&history).unwrap();
+        let restored: CharacteristicsHistory = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, history);
+    }
+}
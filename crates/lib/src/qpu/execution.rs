@@ -1,11 +1,14 @@
 //! Contains QPU-specific executable stuff.
 
 use std::borrow::Cow;
-use std::convert::TryFrom;
+use std::collections::HashMap;
 use std::num::NonZeroU16;
+use std::path::Path;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime};
 
+use qcs_api_client_grpc::services::translation::TranslationOptions as ApiTranslationOptions;
+use qcs_api_client_openapi::models::InstructionSetArchitecture;
 use quil_rs::program::ProgramError;
 use quil_rs::quil::{Quil, ToQuilError};
 
@@ -13,20 +16,27 @@ use quil_rs::Program;
 #[cfg(feature = "tracing")]
 use tracing::trace;
 
+#[cfg(feature = "quilc-rpcq")]
 use crate::compiler::rpcq;
-use crate::executable::Parameters;
-use crate::execution_data::{MemoryReferenceParseError, ResultData};
+use crate::executable::{within_deadline, Parameters};
+use crate::execution_data::spill::{self, SpilledResultData};
+use crate::execution_data::{ExecutionTimings, MemoryReferenceParseError, ResultData};
+use crate::metrics::{self, Cache, Stage};
 use crate::qpu::translation::translate;
 use crate::{ExecutionData, JobHandle};
 
 use super::api::{
-    retrieve_results, submit, ConnectionStrategy, ExecutionOptions, ExecutionOptionsBuilder,
+    retrieve_results, submit_on_connection, submit_with_parameter_batch, until_cancelled,
+    CancellationToken, ConnectionStrategy, ControllerConnection, ExecutionOptions,
+    ExecutionOptionsBuilder,
 };
-use super::translation::{EncryptedTranslationResult, TranslationOptions};
+use super::patch_values::type_parameters;
+use super::translation::{EncryptedTranslationResult, SettingsEpoch, TranslationOptions};
 use super::QpuResultData;
-use super::{get_isa, GetIsaError};
+use super::{GetIsaError, IsaCache};
 use crate::client::{GrpcClientError, Qcs};
-use crate::compiler::quilc::{self, CompilerOpts, TargetDevice};
+use crate::compiler::quilc::{self, CompilerOpts, NativeQuilMetadata, QubitPlacement};
+use crate::compiler::CompilerBackend;
 
 /// Contains all the info needed for a single run of an [`crate::Executable`] against a QPU. Can be
 /// updated with fresh parameters in order to re-run the same program against the same QPU with the
@@ -37,6 +47,32 @@ pub(crate) struct Execution<'a> {
     pub(crate) quantum_processor_id: Cow<'a, str>,
     pub(crate) shots: NonZeroU16,
     client: Arc<Qcs>,
+    translation: Option<CachedTranslation>,
+    /// Where quilc placed the program, if it was compiled.
+    pub(crate) placement: Option<QubitPlacement>,
+    /// The metadata reported by the compiler, if the program was compiled and it reported any.
+    pub(crate) native_quil_metadata: Option<NativeQuilMetadata>,
+    /// How long the last translation took, or `None` if a cached translation was reused.
+    pub(crate) translation_duration: Option<Duration>,
+    /// When the next submission must be translated and submitted by, if ever. See
+    /// [`Executable::with_deadline`](crate::Executable::with_deadline).
+    pub(crate) deadline: Option<Instant>,
+    /// Cancelled to abandon the next submission, if ever. See
+    /// [`Executable::with_cancellation_token`](crate::Executable::with_cancellation_token).
+    pub(crate) cancellation_token: Option<CancellationToken>,
+    /// The settings of the QPU which translations are known to be made with, if they have
+    /// already been checked. Otherwise, they are checked against the cached ISA before every
+    /// submission.
+    pub(crate) settings_epoch: Option<SettingsEpoch>,
+}
+
+/// The result of a previous translation, which may be reused as long as the translation options
+/// are the same and the QPU's settings have not changed since.
+#[derive(Debug, Clone)]
+struct CachedTranslation {
+    result: EncryptedTranslationResult,
+    options: Option<ApiTranslationOptions>,
+    settings_epoch: SettingsEpoch,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -47,6 +83,7 @@ pub(crate) enum Error {
     ToQuil(#[from] ToQuilError),
     #[error("An error that is not expected to occur. If this shows up it may be a bug in this SDK or QCS")]
     Unexpected(#[from] Unexpected),
+    #[cfg(feature = "quilc-rpcq")]
     #[error("Problem communicating with quilc at {uri}: {details}")]
     Quilc { uri: String, details: String },
     #[error("Problem using QCS API: {0}")]
@@ -59,16 +96,26 @@ pub(crate) enum Error {
     ReadoutParse(#[from] MemoryReferenceParseError),
     #[error("Problem when compiling program: {details}")]
     Compilation { details: String },
+    #[cfg(feature = "quilc-rpcq")]
     #[error("Problem when getting RPCQ client: {0}")]
     RpcqClient(#[from] rpcq::Error),
     #[error("Problem making a request to the QPU: {0}")]
     QpuApi(#[from] super::api::QpuApiError),
+    #[error("Problem with the parameters: {0}")]
+    PatchValues(#[from] super::patch_values::Error),
+    #[error("Problem spilling results to disk: {0}")]
+    Spill(#[from] spill::Error),
+    #[error("The deadline was exceeded during {0}")]
+    DeadlineExceeded(Stage),
+    #[error("The execution was cancelled during {0}")]
+    Cancelled(Stage),
 }
 
 impl From<quilc::Error> for Error {
     fn from(source: quilc::Error) -> Self {
         match source {
             quilc::Error::Isa(source) => Self::Unexpected(Unexpected::Isa(format!("{source:?}"))),
+            #[cfg(feature = "quilc-rpcq")]
             quilc::Error::QuilcConnection(uri, details) => Self::Quilc {
                 uri,
                 details: format!("{details:?}"),
@@ -79,6 +126,7 @@ impl From<quilc::Error> for Error {
             quilc::Error::Parse(details) => Self::Compilation {
                 details: format!("{details:?}"),
             },
+            quilc::Error::InvalidRequest(details) => Self::Compilation { details },
         }
     }
 }
@@ -104,6 +152,8 @@ impl<'a> Execution<'a> {
     /// * `compile_with_quilc`: A boolean that if set, will compile the given `quil` using `quilc`
     /// * `compiler_options`: A [`qcs::qpu::quilc::CompilerOpts`] instance with configuration
     ///     options for quilc. Has no effect if `compile_with_quilc` is false
+    /// * `max_qubit_reselections`: How many alternative qubit subsets to compile onto if quilc
+    ///     can't place the program's qubits. See [`quilc::compile_with_qubit_reselection`].
     ///
     /// returns: Result<Execution, Report>
     ///
@@ -122,8 +172,10 @@ impl<'a> Execution<'a> {
         shots: NonZeroU16,
         quantum_processor_id: Cow<'a, str>,
         client: Arc<Qcs>,
-        quilc_client: Option<Arc<dyn quilc::Client + Send + Sync>>,
+        compiler: Option<Arc<dyn CompilerBackend + Send + Sync>>,
         compiler_options: CompilerOpts,
+        max_qubit_reselections: u32,
+        force_isa_refresh: bool,
     ) -> Result<Execution<'a>, Error> {
         #[cfg(feature = "tracing")]
         tracing::debug!(
@@ -133,22 +185,62 @@ impl<'a> Execution<'a> {
             "creating new QPU Execution",
         );
 
-        let isa = get_isa(quantum_processor_id.as_ref(), &client).await?;
-        let target_device = TargetDevice::try_from(isa)?;
+        let isa = IsaCache::global()
+            .get(quantum_processor_id.as_ref(), &client, force_isa_refresh)
+            .await?;
 
-        let program = if let Some(client) = quilc_client {
+        Self::with_isa(
+            quil,
+            shots,
+            quantum_processor_id,
+            client,
+            compiler,
+            compiler_options,
+            max_qubit_reselections,
+            &isa,
+        )
+    }
+
+    /// As [`Execution::new`], but compile for `isa` rather than fetching the current ISA of the
+    /// quantum processor.
+    pub(crate) fn with_isa(
+        quil: Arc<str>,
+        shots: NonZeroU16,
+        quantum_processor_id: Cow<'a, str>,
+        client: Arc<Qcs>,
+        compiler: Option<Arc<dyn CompilerBackend + Send + Sync>>,
+        compiler_options: CompilerOpts,
+        max_qubit_reselections: u32,
+        isa: &InstructionSetArchitecture,
+    ) -> Result<Execution<'a>, Error> {
+        let (program, placement, native_quil_metadata) = if let Some(compiler) = compiler {
+            #[cfg(feature = "tracing")]
+            let _span = tracing::info_span!(
+                "quilc.compile",
+                %quantum_processor_id,
+                num_shots = shots.get(),
+            )
+            .entered();
             #[cfg(feature = "tracing")]
             trace!("Converting to Native Quil");
-            client
-                .compile_program(&quil, target_device, compiler_options)
-                .map_err(|e| Error::Compilation {
+            let (result, placement) = quilc::compile_with_qubit_reselection(
+                compiler.as_ref(),
+                &quil,
+                isa,
+                compiler_options,
+                max_qubit_reselections,
+            )
+            .map_err(|e| match e {
+                quilc::Error::Isa(_) => Error::from(e),
+                e => Error::Compilation {
                     details: e.to_string(),
-                })?
-                .program
+                },
+            })?;
+            (result.program, Some(placement), result.native_quil_metadata)
         } else {
             #[cfg(feature = "tracing")]
             trace!("Skipping conversion to Native Quil");
-            quil.parse().map_err(Error::Quil)?
+            (quil.parse().map_err(Error::Quil)?, None, None)
         };
 
         Ok(Self {
@@ -156,9 +248,39 @@ impl<'a> Execution<'a> {
             quantum_processor_id,
             shots,
             client,
+            translation: None,
+            placement,
+            native_quil_metadata,
+            translation_duration: None,
+            deadline: None,
+            cancellation_token: None,
+            settings_epoch: None,
         })
     }
 
+    /// The program which is translated and run, after compilation if it was compiled.
+    pub(crate) fn program(&self) -> &Program {
+        &self.program
+    }
+
+    /// The readout mapping of the most recent translation, if the program has been translated.
+    pub(crate) fn readout_map(&self) -> Option<&HashMap<String, String>> {
+        self.translation
+            .as_ref()
+            .map(|translation| &translation.result.readout_map)
+    }
+
+    /// A copy of this execution which runs `shots` shots instead, reusing the compiled program but
+    /// not the translation, which is made for a particular number of shots.
+    pub(crate) fn with_shots(&self, shots: NonZeroU16) -> Self {
+        Self {
+            shots,
+            translation: None,
+            translation_duration: None,
+            ..self.clone()
+        }
+    }
+
     /// Translate the execution's quil program for it's given quantum processor.
     pub(crate) async fn translate(
         &mut self,
@@ -175,6 +297,62 @@ impl<'a> Execution<'a> {
         Ok(encrpyted_translation_result)
     }
 
+    /// Translate the execution's quil program, reusing the previous translation if it was made
+    /// with the same options and the QPU has not been recalibrated since.
+    ///
+    /// The QPU's current [`SettingsEpoch`] is taken from the ISA in [`IsaCache::global`], so a
+    /// recalibration is noticed once the cached ISA expires. If the epoch can't be determined,
+    /// the program is re-translated.
+    pub(crate) async fn translate_or_reuse(
+        &mut self,
+        options: Option<TranslationOptions>,
+    ) -> Result<EncryptedTranslationResult, Error> {
+        let settings_epoch = match &self.settings_epoch {
+            Some(settings_epoch) => Some(settings_epoch.clone()),
+            None => self.current_settings_epoch().await,
+        };
+        let api_options = options.clone().map(ApiTranslationOptions::from);
+
+        if let (Some(cached), Some(settings_epoch)) = (&self.translation, &settings_epoch) {
+            if &cached.settings_epoch == settings_epoch && cached.options == api_options {
+                #[cfg(feature = "tracing")]
+                trace!(settings_epoch = %settings_epoch.as_str(), "reusing cached translation");
+                metrics::record(Cache::Translation, true);
+                self.translation_duration = None;
+                return Ok(cached.result.clone());
+            }
+        }
+
+        metrics::record(Cache::Translation, false);
+        let started = Instant::now();
+        let result = self.translate(options).await?;
+        self.translation_duration = Some(started.elapsed());
+        self.translation = settings_epoch.map(|settings_epoch| CachedTranslation {
+            result: result.clone(),
+            options: api_options,
+            settings_epoch,
+        });
+        Ok(result)
+    }
+
+    /// The QPU's current [`SettingsEpoch`], from its cached ISA. Returns `None` if the ISA can't
+    /// be fetched, so that a failure to check the QPU's settings doesn't fail the submission.
+    async fn current_settings_epoch(&self) -> Option<SettingsEpoch> {
+        match IsaCache::global()
+            .get(self.quantum_processor_id.as_ref(), &self.client, false)
+            .await
+        {
+            Ok(isa) => SettingsEpoch::from_isa(&isa),
+            Err(error) => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(%error, "failed to check the QPU's settings, translating again");
+                #[cfg(not(feature = "tracing"))]
+                let _ = error;
+                None
+            }
+        }
+    }
+
     /// Run on a real QPU and wait for the results.
     pub(crate) async fn submit(
         &mut self,
@@ -218,6 +396,30 @@ impl<'a> Execution<'a> {
         .await
     }
 
+    /// Run on a real QPU once for each set of parameters, without waiting for the results. The
+    /// returned handles are in the same order as `params`.
+    pub(crate) async fn submit_batch(
+        &mut self,
+        params: &[Parameters],
+        translation_options: Option<TranslationOptions>,
+        execution_options: &ExecutionOptions,
+    ) -> Result<Vec<JobHandle<'a>>, Error> {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            quantum_processor_id=%self.quantum_processor_id,
+            num_parameter_sets=params.len(),
+            "submitting parameter batch to QPU",
+        );
+
+        self.submit_batch_to_target(
+            params,
+            Some(&self.quantum_processor_id.clone()),
+            translation_options,
+            execution_options,
+        )
+        .await
+    }
+
     async fn submit_to_target(
         &mut self,
         params: &Parameters,
@@ -225,13 +427,63 @@ impl<'a> Execution<'a> {
         translation_options: Option<TranslationOptions>,
         execution_options: &ExecutionOptions,
     ) -> Result<JobHandle<'a>, Error> {
+        self.submit_batch_to_target(
+            std::slice::from_ref(params),
+            quantum_processor_id,
+            translation_options,
+            execution_options,
+        )
+        .await?
+        .pop()
+        .ok_or_else(|| GrpcClientError::ResponseEmpty("Job Execution ID".into()).into())
+    }
+
+    async fn submit_batch_to_target(
+        &mut self,
+        params: &[Parameters],
+        quantum_processor_id: Option<&str>,
+        translation_options: Option<TranslationOptions>,
+        execution_options: &ExecutionOptions,
+    ) -> Result<Vec<JobHandle<'a>>, Error> {
+        let params = params
+            .iter()
+            .map(|params| type_parameters(&self.program, params))
+            .collect::<Result<Vec<_>, _>>()?;
+        let deadline = self.deadline;
+        let cancellation_token = self.cancellation_token.clone();
+        let translation = within_deadline(
+            deadline,
+            Stage::Translation,
+            self.translate_or_reuse(translation_options),
+        );
         let EncryptedTranslationResult { job, readout_map } =
-            self.translate(translation_options).await?;
+            until_cancelled(cancellation_token.as_ref(), translation)
+                .await
+                .ok_or(Error::Cancelled(Stage::Translation))?
+                .map_err(Error::DeadlineExceeded)??;
 
-        let job_id = submit(
+        // A submission dropped part way might still queue its jobs, with nothing left to cancel
+        // them, so the request is only started before the deadline or cancellation, and is always
+        // allowed to finish.
+        let abandoned = || {
+            if is_past(deadline) {
+                Some(Error::DeadlineExceeded(Stage::Submission))
+            } else if cancellation_token
+                .as_ref()
+                .map_or(false, CancellationToken::is_cancelled)
+            {
+                Some(Error::Cancelled(Stage::Submission))
+            } else {
+                None
+            }
+        };
+        if let Some(error) = abandoned() {
+            return Err(error);
+        }
+        let job_ids = submit_with_parameter_batch(
             quantum_processor_id,
             job,
-            params,
+            &params,
             self.client.as_ref(),
             execution_options,
         )
@@ -242,11 +494,53 @@ impl<'a> Execution<'a> {
             _ => None,
         };
 
+        let job_handles: Vec<_> = job_ids
+            .into_iter()
+            .map(|job_id| {
+                JobHandle::new(
+                    job_id,
+                    self.quantum_processor_id.to_string(),
+                    endpoint_id.cloned(),
+                    readout_map.clone(),
+                    execution_options.clone(),
+                )
+            })
+            .collect();
+
+        if let Some(error) = abandoned() {
+            for job_handle in &job_handles {
+                self.cancel_abandoned_job(job_handle).await;
+            }
+            return Err(error);
+        }
+        Ok(job_handles)
+    }
+
+    /// Submit `translation`, a translation of this execution's program, on `connection` without
+    /// any parameters, and without waiting for the results.
+    pub(crate) async fn submit_translation_on(
+        &self,
+        connection: &ControllerConnection,
+        translation: EncryptedTranslationResult,
+        execution_options: &ExecutionOptions,
+    ) -> Result<JobHandle<'a>, Error> {
+        let params = type_parameters(&self.program, &Parameters::new())?;
+        let job_id = submit_on_connection(
+            connection,
+            Some(self.quantum_processor_id.as_ref()),
+            translation.job,
+            std::iter::once(&params),
+            self.client.as_ref(),
+            execution_options,
+        )
+        .await?
+        .pop()
+        .ok_or_else(|| GrpcClientError::ResponseEmpty("Job Execution ID".into()))?;
         Ok(JobHandle::new(
             job_id,
             self.quantum_processor_id.to_string(),
-            endpoint_id.cloned(),
-            readout_map,
+            None,
+            translation.readout_map,
             execution_options.clone(),
         ))
     }
@@ -262,6 +556,18 @@ impl<'a> Execution<'a> {
         .map_err(Error::from)
     }
 
+    /// Try to cancel a job whose submission or retrieval was cancelled, see
+    /// [`Executable::with_cancellation_token`](crate::Executable::with_cancellation_token).
+    pub(crate) async fn cancel_abandoned_job(&self, job_handle: &JobHandle<'a>) {
+        crate::qpu::api::cancel_abandoned_job(
+            job_handle.job_id(),
+            Some(job_handle.quantum_processor_id()),
+            self.client.as_ref(),
+            job_handle.execution_options(),
+        )
+        .await;
+    }
+
     pub(crate) async fn retrieve_results(
         &self,
         job_handle: JobHandle<'a>,
@@ -274,23 +580,78 @@ impl<'a> Execution<'a> {
             "retrieving execution results for job",
         );
 
-        let response = retrieve_results(
-            job_handle.job_id(),
-            Some(job_handle.quantum_processor_id()),
-            self.client.as_ref(),
-            job_handle.execution_options(),
-        )
-        .await?;
+        let mut data = retrieve_job_results(&job_handle, self.client.as_ref()).await?;
+        if let ResultData::Qpu(result_data) = data.result_data {
+            data.result_data = ResultData::Qpu(result_data.with_declarations(&self.program));
+        }
+        Ok(data)
+    }
+}
+
+/// Whether `deadline` has already passed.
+fn is_past(deadline: Option<Instant>) -> bool {
+    deadline.map_or(false, |deadline| Instant::now() >= deadline)
+}
 
-        Ok(ExecutionData {
-            result_data: ResultData::Qpu(QpuResultData::from_controller_mappings_and_values(
+/// Wait for the job described by `job_handle` to complete and retrieve its results.
+pub(crate) async fn retrieve_job_results(
+    job_handle: &JobHandle<'_>,
+    client: &Qcs,
+) -> Result<ExecutionData, Error> {
+    let response = retrieve_results(
+        job_handle.job_id(),
+        Some(job_handle.quantum_processor_id()),
+        client,
+        job_handle.execution_options(),
+    )
+    .await?;
+    let received = SystemTime::now();
+    let decoding = Instant::now();
+
+    let in_memory = || {
+        ResultData::Qpu(QpuResultData::from_controller_mappings_and_values(
+            job_handle.readout_map(),
+            &response.readout_values,
+            &response.memory_values,
+        ))
+    };
+    let options = job_handle.execution_options();
+    let result_data = match options.spill_threshold_bytes() {
+        Some(threshold) if spill::decoded_len(&response.readout_values) > threshold => {
+            let directory = options
+                .spill_directory()
+                .map_or_else(std::env::temp_dir, Path::to_path_buf);
+            match SpilledResultData::from_controller_mappings_and_values(
                 job_handle.readout_map(),
                 &response.readout_values,
                 &response.memory_values,
-            )),
-            duration: Some(Duration::from_micros(
-                response.execution_duration_microseconds,
-            )),
-        })
-    }
+                &directory,
+            ) {
+                Ok(data) => ResultData::Spilled(data),
+                // Values which don't fit a matrix can't be spilled into one.
+                #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+                Err(spill::Error::Conversion(error)) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(%error, "results can't be spilled, decoding them into memory");
+                    in_memory()
+                }
+                Err(error) => return Err(error.into()),
+            }
+        }
+        _ => in_memory(),
+    };
+
+    let execution = Duration::from_micros(response.execution_duration_microseconds);
+    let time_to_results = job_handle
+        .submitted_at()
+        .and_then(|submitted_at| received.duration_since(submitted_at).ok());
+    Ok(ExecutionData {
+        result_data,
+        duration: Some(execution),
+        timings: Some(ExecutionTimings {
+            execution: Some(execution),
+            time_to_results,
+            post_processing: Some(decoding.elapsed()),
+        }),
+    })
 }
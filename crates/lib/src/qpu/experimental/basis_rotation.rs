@@ -0,0 +1,580 @@
+//! Measure the expectation values of Pauli observables by rotating each qubit into the basis of
+//! the Pauli measured on it, then reading it out in the computational basis.
+//!
+//! [`plan_measurements`] groups observables which agree on the Pauli of every qubit they share
+//! (i.e. which commute qubit-wise) so that they can be measured by the same program, then appends
+//! the rotations and measurements for each group to a state preparation program. Each group is a
+//! [`MeasurementSetting`]; once every setting's program has been run,
+//! [`MeasurementPlan::expectation_values`] reconstructs the expectation value of every observable
+//! from the results.
+//!
+//! ```no_run
+//! use std::str::FromStr;
+//!
+//! use qcs::compiler::quilc::PauliTerm;
+//! use qcs::qpu::experimental::basis_rotation::plan_measurements;
+//! use qcs::{client::Qcs, qvm, quil_rs::Program, Executable};
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let observable = |symbols: &[&str]| PauliTerm {
+//!     indices: (0..symbols.len() as u64).collect(),
+//!     symbols: symbols.iter().map(|symbol| symbol.to_string()).collect(),
+//! };
+//! let bell_state = Program::from_str("H 0\nCNOT 0 1")?;
+//! let plan = plan_measurements(
+//!     &bell_state,
+//!     &[observable(&["X", "X"]), observable(&["Z", "Z"]), observable(&["Z", "I"])],
+//! )?;
+//!
+//! let qvm_client = qvm::http::HttpClient::from(&Qcs::load());
+//! let mut results = Vec::new();
+//! for setting in plan.settings() {
+//!     let data = Executable::from_program(setting.program().clone())
+//!         .with_shots(std::num::NonZeroU16::new(1000).unwrap())
+//!         .execute_on_qvm(&qvm_client)
+//!         .await?;
+//!     results.push(data);
+//! }
+//! let expectation_values = plan.expectation_values(&results)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::str::FromStr;
+
+use quil_rs::instruction::Instruction;
+use quil_rs::Program;
+
+use crate::compiler::quilc::{self, ConjugateByCliffordRequest, PauliTerm};
+use crate::{ExecutionData, RegisterMatrix, RegisterMatrixConversionError};
+
+/// The register each setting's program reads its qubits out to.
+pub const MEASUREMENT_REGISTER: &str = "ro";
+
+/// A Pauli operator other than the identity, measured on a single qubit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Pauli {
+    /// Pauli X
+    X,
+    /// Pauli Y
+    Y,
+    /// Pauli Z
+    Z,
+}
+
+impl Pauli {
+    /// The Pauli named by `symbol`, `None` for the identity, or an error for anything else.
+    fn parse(symbol: &str) -> Result<Option<Self>, ()> {
+        match symbol {
+            "I" => Ok(None),
+            "X" => Ok(Some(Self::X)),
+            "Y" => Ok(Some(Self::Y)),
+            "Z" => Ok(Some(Self::Z)),
+            _ => Err(()),
+        }
+    }
+
+    /// The symbol used for this Pauli in a [`PauliTerm`].
+    #[must_use]
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Self::X => "X",
+            Self::Y => "Y",
+            Self::Z => "Z",
+        }
+    }
+
+    /// The Clifford gate `C` which rotates this Pauli onto Z, i.e. for which `CPC^{\dagger} = Z`,
+    /// so that measuring Z after `C` measures this Pauli. `None` for Z, which needs no rotation.
+    #[must_use]
+    pub fn rotation(self) -> Option<&'static str> {
+        match self {
+            Self::X => Some("RY(-pi/2)"),
+            Self::Y => Some("RX(pi/2)"),
+            Self::Z => None,
+        }
+    }
+}
+
+/// Errors that can occur when planning measurements or reconstructing expectation values.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// An observable isn't a valid Pauli term.
+    #[error("observable {index} is not a valid Pauli term: {reason}")]
+    InvalidObservable {
+        /// The index of the observable.
+        index: usize,
+        /// What is wrong with it.
+        reason: String,
+    },
+    /// The program already measures qubits, which would happen before the basis rotations.
+    #[error("the program already contains a MEASURE instruction; it should only prepare a state")]
+    AlreadyMeasured,
+    /// The program already declares the register the settings' programs measure into.
+    #[error("the program already declares the `ro` register")]
+    RegisterInUse,
+    /// A different number of results was given than there are settings.
+    #[error("expected the results of {expected} measurement settings, got {found}")]
+    ResultCount {
+        /// The number of settings.
+        expected: usize,
+        /// The number of results.
+        found: usize,
+    },
+    /// A setting's results couldn't be arranged into registers.
+    #[error("measurement results could not be converted: {0}")]
+    Conversion(#[from] RegisterMatrixConversionError),
+    /// A setting's results didn't include a bit for every qubit it measures.
+    #[error("the results of setting {setting} have no bit register `ro` with {qubits} columns")]
+    MissingRegister {
+        /// The index of the setting.
+        setting: usize,
+        /// The number of qubits the setting measures.
+        qubits: usize,
+    },
+    /// quilc couldn't conjugate a setting's basis by its rotations.
+    #[error("failed to check basis rotations with quilc: {0}")]
+    Quilc(#[from] quilc::Error),
+    /// quilc found that a setting's rotations don't take its basis onto Z.
+    #[error("the rotations of setting {setting} take its basis to {phase} * {pauli}, not Z")]
+    IncorrectRotation {
+        /// The index of the setting.
+        setting: usize,
+        /// The encoded phase of the Pauli the basis was taken to.
+        phase: i64,
+        /// The Pauli the basis was taken to.
+        pauli: String,
+    },
+}
+
+/// A product of Paulis on distinct qubits, without its identity factors.
+type Observable = BTreeMap<u64, Pauli>;
+
+/// One program of a [`MeasurementPlan`], which measures every qubit in the basis of a group of
+/// observables which commute qubit-wise.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MeasurementSetting {
+    basis: Observable,
+    program: Program,
+    /// The index of each observable measured by this setting, and the index into
+    /// [`MEASUREMENT_REGISTER`] of each of its qubits.
+    observables: Vec<(usize, Vec<usize>)>,
+}
+
+impl MeasurementSetting {
+    /// The Pauli each qubit is measured in, in the order the qubits are read out into
+    /// [`MEASUREMENT_REGISTER`].
+    pub fn basis(&self) -> impl Iterator<Item = (u64, Pauli)> + '_ {
+        self.basis.iter().map(|(qubit, pauli)| (*qubit, *pauli))
+    }
+
+    /// The state preparation program, followed by the basis rotations and a measurement of the
+    /// `i`th qubit of [`MeasurementSetting::basis`] into index `i` of [`MEASUREMENT_REGISTER`].
+    #[must_use]
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    /// The indices of the observables measured by this setting, in the order they were given to
+    /// [`plan_measurements`].
+    pub fn observables(&self) -> impl Iterator<Item = usize> + '_ {
+        self.observables.iter().map(|(index, _)| *index)
+    }
+
+    /// The Clifford program which rotates [`MeasurementSetting::basis`] onto Z.
+    fn rotations(&self) -> String {
+        let mut quil = String::new();
+        for (qubit, pauli) in &self.basis {
+            if let Some(rotation) = pauli.rotation() {
+                let _ = writeln!(quil, "{rotation} {qubit}");
+            }
+        }
+        quil
+    }
+}
+
+/// The programs which together measure a set of Pauli observables, built by
+/// [`plan_measurements`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct MeasurementPlan {
+    settings: Vec<MeasurementSetting>,
+    observables: usize,
+}
+
+impl MeasurementPlan {
+    /// The settings to run, each of which measures one or more of the observables.
+    #[must_use]
+    pub fn settings(&self) -> &[MeasurementSetting] {
+        &self.settings
+    }
+
+    /// The expectation value of every observable, in the order they were given to
+    /// [`plan_measurements`], from the `results` of running each setting's program, in the
+    /// order of [`MeasurementPlan::settings`].
+    ///
+    /// Observables made only of identities always have an expectation value of `1`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there isn't one result per setting, or a result doesn't have a bit
+    /// register named [`MEASUREMENT_REGISTER`] with a column for every qubit its setting measures.
+    pub fn expectation_values(&self, results: &[ExecutionData]) -> Result<Vec<f64>, Error> {
+        if results.len() != self.settings.len() {
+            return Err(Error::ResultCount {
+                expected: self.settings.len(),
+                found: results.len(),
+            });
+        }
+
+        let mut values = vec![1.0; self.observables];
+        for (index, (setting, data)) in self.settings.iter().zip(results).enumerate() {
+            let register_map = data.result_data.to_register_map()?;
+            let register = match register_map.get_register_matrix(MEASUREMENT_REGISTER) {
+                Some(RegisterMatrix::Integer(matrix)) if matrix.ncols() >= setting.basis.len() => {
+                    matrix
+                }
+                _ => {
+                    return Err(Error::MissingRegister {
+                        setting: index,
+                        qubits: setting.basis.len(),
+                    })
+                }
+            };
+            for (observable, columns) in &setting.observables {
+                values[*observable] = expectation_value(register, columns);
+            }
+        }
+        Ok(values)
+    }
+
+    /// Check with quilc's `conjugate_pauli_by_clifford` that every setting's rotations take its
+    /// basis onto Z, with no change of sign.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if quilc fails, or finds a setting whose rotations are incorrect.
+    pub fn verify_rotations<C: quilc::Client + ?Sized>(&self, client: &C) -> Result<(), Error> {
+        for (index, setting) in self.settings.iter().enumerate() {
+            let rotated: Vec<_> = setting
+                .basis
+                .iter()
+                .filter(|(_, pauli)| pauli.rotation().is_some())
+                .collect();
+            if rotated.is_empty() {
+                continue;
+            }
+            let response = client.conjugate_pauli_by_clifford(ConjugateByCliffordRequest {
+                pauli: PauliTerm {
+                    indices: rotated.iter().map(|(qubit, _)| **qubit).collect(),
+                    symbols: rotated
+                        .iter()
+                        .map(|(_, pauli)| pauli.symbol().to_string())
+                        .collect(),
+                },
+                clifford: setting.rotations(),
+            })?;
+            if response.phase != 0 || response.pauli.chars().any(|symbol| symbol != 'Z') {
+                return Err(Error::IncorrectRotation {
+                    setting: index,
+                    phase: response.phase,
+                    pauli: response.pauli,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Plan the programs which measure every one of `observables` on the state prepared by
+/// `program`.
+///
+/// Observables are grouped greedily, heaviest first, into as few settings as possible such that
+/// the observables of a setting agree on the Pauli of every qubit they share. Each setting's
+/// program is `program` followed by a rotation of each qubit into its basis (see
+/// [`Pauli::rotation`]), then a measurement of each qubit into [`MEASUREMENT_REGISTER`]. The
+/// rotations aren't native gates, so the programs should be compiled before they are run.
+///
+/// # Errors
+///
+/// Returns an error if an observable has a symbol other than `I`, `X`, `Y`, or `Z`, has a
+/// different number of symbols than indices, or repeats a qubit; or if `program` measures any
+/// qubits or declares [`MEASUREMENT_REGISTER`] itself.
+///
+/// # Panics
+///
+/// Never: the rotations and measurements are always valid Quil.
+pub fn plan_measurements(
+    program: &Program,
+    observables: &[PauliTerm],
+) -> Result<MeasurementPlan, Error> {
+    if program
+        .body_instructions()
+        .any(|instruction| matches!(instruction, Instruction::Measurement(_)))
+    {
+        return Err(Error::AlreadyMeasured);
+    }
+    if program.memory_regions.contains_key(MEASUREMENT_REGISTER) {
+        return Err(Error::RegisterInUse);
+    }
+
+    let parsed = observables
+        .iter()
+        .enumerate()
+        .map(|(index, term)| {
+            parse_observable(term).map_err(|reason| Error::InvalidObservable { index, reason })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut order: Vec<usize> = (0..parsed.len())
+        .filter(|index| !parsed[*index].is_empty())
+        .collect();
+    order.sort_by_key(|index| std::cmp::Reverse(parsed[*index].len()));
+
+    let mut groups: Vec<(Observable, Vec<usize>)> = Vec::new();
+    for index in order {
+        let observable = &parsed[index];
+        let group = groups.iter_mut().find(|(basis, _)| {
+            observable
+                .iter()
+                .all(|(qubit, pauli)| !matches!(basis.get(qubit), Some(other) if other != pauli))
+        });
+        match group {
+            Some((basis, members)) => {
+                basis.extend(observable);
+                members.push(index);
+            }
+            None => groups.push((observable.clone(), vec![index])),
+        }
+    }
+
+    let settings = groups
+        .into_iter()
+        .map(|(basis, mut members)| {
+            members.sort_unstable();
+            let columns: BTreeMap<u64, usize> = basis
+                .keys()
+                .enumerate()
+                .map(|(column, qubit)| (*qubit, column))
+                .collect();
+            let observables = members
+                .into_iter()
+                .map(|index| {
+                    (
+                        index,
+                        parsed[index].keys().map(|qubit| columns[qubit]).collect(),
+                    )
+                })
+                .collect();
+            let mut setting = MeasurementSetting {
+                basis,
+                program: program.clone(),
+                observables,
+            };
+            let mut quil = format!(
+                "DECLARE {MEASUREMENT_REGISTER} BIT[{}]\n",
+                setting.basis.len()
+            );
+            quil.push_str(&setting.rotations());
+            for (column, qubit) in setting.basis.keys().enumerate() {
+                let _ = writeln!(quil, "MEASURE {qubit} {MEASUREMENT_REGISTER}[{column}]");
+            }
+            let measurements =
+                Program::from_str(&quil).expect("rotations and measurements are valid Quil");
+            setting
+                .program
+                .add_instructions(measurements.to_instructions());
+            setting
+        })
+        .collect();
+
+    Ok(MeasurementPlan {
+        settings,
+        observables: observables.len(),
+    })
+}
+
+/// The mean over every shot in `register` of the product of the eigenvalues (`1` for a `0`,
+/// `-1` for a `1`) read out at `columns`.
+// Shot counts are far too small to lose precision as floats.
+#[allow(clippy::cast_precision_loss)]
+fn expectation_value(register: &ndarray::Array2<i64>, columns: &[usize]) -> f64 {
+    let shots = register.nrows().max(1) as f64;
+    let total: i64 = register
+        .rows()
+        .into_iter()
+        .map(|shot| {
+            let ones = columns.iter().filter(|column| shot[**column] == 1).count();
+            if ones % 2 == 0 {
+                1
+            } else {
+                -1
+            }
+        })
+        .sum();
+    total as f64 / shots
+}
+
+/// The non-identity factors of `term`, or why it isn't a valid Pauli term.
+fn parse_observable(term: &PauliTerm) -> Result<Observable, String> {
+    if term.indices.len() != term.symbols.len() {
+        return Err(format!(
+            "it has {} indices but {} symbols",
+            term.indices.len(),
+            term.symbols.len()
+        ));
+    }
+    let mut observable = Observable::new();
+    let mut seen = Vec::with_capacity(term.indices.len());
+    for (qubit, symbol) in term.indices.iter().zip(&term.symbols) {
+        if seen.contains(qubit) {
+            return Err(format!("qubit {qubit} appears more than once"));
+        }
+        seen.push(*qubit);
+        match Pauli::parse(symbol) {
+            Ok(Some(pauli)) => {
+                observable.insert(*qubit, pauli);
+            }
+            Ok(None) => {}
+            Err(()) => return Err(format!("{symbol:?} is not one of I, X, Y, or Z")),
+        }
+    }
+    Ok(observable)
+}
+
+#[cfg(test)]
+mod describe_basis_rotation {
+    use std::str::FromStr;
+
+    use maplit::hashmap;
+    use quil_rs::quil::Quil;
+    use quil_rs::Program;
+
+    use super::{plan_measurements, Error, Pauli};
+    use crate::compiler::quilc::PauliTerm;
+    use crate::qvm::QvmResultData;
+    use crate::{ExecutionData, RegisterData, ResultData};
+
+    fn term(factors: &[(u64, &str)]) -> PauliTerm {
+        PauliTerm {
+            indices: factors.iter().map(|(qubit, _)| *qubit).collect(),
+            symbols: factors
+                .iter()
+                .map(|(_, symbol)| symbol.to_string())
+                .collect(),
+        }
+    }
+
+    fn qvm_data(rows: Vec<Vec<i8>>) -> ExecutionData {
+        ExecutionData {
+            result_data: ResultData::Qvm(QvmResultData::from_memory_map(
+                hashmap! { "ro".to_string() => RegisterData::I8(rows) },
+            )),
+            duration: None,
+            timings: None,
+        }
+    }
+
+    #[test]
+    fn it_groups_observables_which_commute_qubit_wise() {
+        let program = Program::from_str("H 0").unwrap();
+        let plan = plan_measurements(
+            &program,
+            &[
+                term(&[(0, "X")]),
+                term(&[(0, "Z"), (1, "Z")]),
+                term(&[(0, "X"), (1, "Z")]),
+                term(&[(1, "Z")]),
+            ],
+        )
+        .unwrap();
+
+        let settings: Vec<(Vec<(u64, Pauli)>, Vec<usize>)> = plan
+            .settings()
+            .iter()
+            .map(|setting| (setting.basis().collect(), setting.observables().collect()))
+            .collect();
+        assert_eq!(
+            settings,
+            vec![
+                (vec![(0, Pauli::Z), (1, Pauli::Z)], vec![1, 3]),
+                (vec![(0, Pauli::X), (1, Pauli::Z)], vec![0, 2]),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_rotates_each_qubit_before_measuring_it() {
+        let program = Program::from_str("H 0").unwrap();
+        let plan = plan_measurements(&program, &[term(&[(0, "X"), (1, "I"), (2, "Y")])]).unwrap();
+
+        let expected = Program::from_str(
+            "DECLARE ro BIT[2]\nH 0\nRY(-pi/2) 0\nRX(pi/2) 2\nMEASURE 0 ro[0]\nMEASURE 2 ro[1]",
+        )
+        .unwrap();
+        assert_eq!(
+            plan.settings()[0].program().to_quil_or_debug(),
+            expected.to_quil_or_debug()
+        );
+    }
+
+    #[test]
+    fn it_reconstructs_expectation_values_in_the_order_given() {
+        let program = Program::from_str("H 0").unwrap();
+        let plan = plan_measurements(
+            &program,
+            &[
+                term(&[(0, "X")]),
+                term(&[(0, "Z"), (1, "Z")]),
+                term(&[]),
+                term(&[(1, "Z")]),
+            ],
+        )
+        .unwrap();
+
+        let zz = qvm_data(vec![vec![0, 0], vec![1, 1], vec![1, 0], vec![0, 0]]);
+        let x = qvm_data(vec![vec![0], vec![0], vec![0], vec![1]]);
+        let values = plan.expectation_values(&[zz, x]).unwrap();
+
+        assert_eq!(values, vec![0.5, 0.5, 1.0, 0.5]);
+    }
+
+    #[test]
+    fn it_requires_a_result_for_every_setting() {
+        let plan = plan_measurements(&Program::new(), &[term(&[(0, "X")])]).unwrap();
+        assert!(matches!(
+            plan.expectation_values(&[]),
+            Err(Error::ResultCount {
+                expected: 1,
+                found: 0
+            })
+        ));
+    }
+
+    #[test]
+    fn it_rejects_invalid_observables_and_programs() {
+        let program = Program::new();
+        assert!(matches!(
+            plan_measurements(&program, &[term(&[(0, "X")]), term(&[(0, "X"), (0, "Z")])]),
+            Err(Error::InvalidObservable { index: 1, .. })
+        ));
+        assert!(matches!(
+            plan_measurements(&program, &[term(&[(0, "W")])]),
+            Err(Error::InvalidObservable { index: 0, .. })
+        ));
+
+        let measured = Program::from_str("DECLARE out BIT\nMEASURE 0 out").unwrap();
+        assert!(matches!(
+            plan_measurements(&measured, &[]),
+            Err(Error::AlreadyMeasured)
+        ));
+        let declared = Program::from_str("DECLARE ro BIT").unwrap();
+        assert!(matches!(
+            plan_measurements(&declared, &[]),
+            Err(Error::RegisterInUse)
+        ));
+    }
+}
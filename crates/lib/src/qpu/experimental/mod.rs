@@ -0,0 +1,7 @@
+//! Experimental utilities for making better use of a QPU.
+//!
+//! ⚠️ The APIs in this module are not yet stable and may change in a minor release.
+
+#[cfg(feature = "qcs-api")]
+pub mod basis_rotation;
+pub mod parallel;
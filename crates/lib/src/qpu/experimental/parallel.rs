@@ -0,0 +1,272 @@
+//! Merge several small, independent programs acting on disjoint qubits into a single program so
+//! that they can be run side by side in one job, then split the results back apart.
+//!
+//! Each program's memory regions are renamed with a per-program prefix (`p0_`, `p1_`, ...) so
+//! that they cannot collide in the merged program.
+
+use std::collections::{HashMap, HashSet};
+
+use quil_rs::{
+    expression::Expression,
+    instruction::{Declaration, Gate, Instruction, Measurement, MemoryReference, Qubit, Sharing},
+    Program,
+};
+
+use crate::RegisterMap;
+
+/// A program built by [`merge_programs`], along with what is needed to split its results.
+#[derive(Clone, Debug)]
+pub struct MergedProgram {
+    program: Program,
+    prefixes: Vec<String>,
+}
+
+impl MergedProgram {
+    /// The merged program.
+    #[must_use]
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    /// The prefix given to the memory regions of each of the original programs, in the order
+    /// the programs were given to [`merge_programs`].
+    #[must_use]
+    pub fn prefixes(&self) -> &[String] {
+        &self.prefixes
+    }
+
+    /// The names of every memory region in the merged program, suitable for passing to
+    /// [`Executable::read_from`](crate::Executable::read_from).
+    #[must_use]
+    pub fn memory_region_names(&self) -> Vec<String> {
+        self.program.memory_regions.keys().cloned().collect()
+    }
+
+    /// Split a [`RegisterMap`] produced by running the merged program into one [`RegisterMap`]
+    /// per original program, with the original register names restored.
+    ///
+    /// Registers that don't belong to any of the original programs are dropped.
+    #[must_use]
+    pub fn split_register_map(&self, register_map: RegisterMap) -> Vec<RegisterMap> {
+        let mut maps = vec![HashMap::new(); self.prefixes.len()];
+        for (name, matrix) in register_map.0 {
+            let owner = self
+                .prefixes
+                .iter()
+                .enumerate()
+                .find_map(|(index, prefix)| {
+                    name.strip_prefix(prefix.as_str())
+                        .map(|original| (index, original.to_string()))
+                });
+            if let Some((index, original)) = owner {
+                maps[index].insert(original, matrix);
+            }
+        }
+        maps.into_iter().map(RegisterMap).collect()
+    }
+}
+
+/// Merge `programs` into a single program.
+///
+/// Only instructions whose effects are confined to their own qubits and memory are supported:
+/// gates, measurements, qubit-specific `RESET`s, and `PRAGMA`s. Calibrations and other
+/// non-body instructions of the original programs are not carried over.
+///
+/// # Errors
+///
+/// Returns an error if the programs share any qubits, use a qubit variable, or contain an
+/// unsupported instruction.
+pub fn merge_programs<'a>(
+    programs: impl IntoIterator<Item = &'a Program>,
+) -> Result<MergedProgram, Error> {
+    let mut merged = Program::new();
+    let mut prefixes = Vec::new();
+    let mut used_qubits = HashSet::new();
+
+    for (index, program) in programs.into_iter().enumerate() {
+        for qubit in program.get_used_qubits().iter() {
+            match qubit {
+                Qubit::Fixed(q) => {
+                    if !used_qubits.insert(*q) {
+                        return Err(Error::OverlappingQubit(*q));
+                    }
+                }
+                _ => return Err(Error::NonFixedQubit { program: index }),
+            }
+        }
+
+        let prefix = format!("p{index}_");
+        for (name, region) in &program.memory_regions {
+            merged.add_instruction(Instruction::Declaration(Declaration::new(
+                format!("{prefix}{name}"),
+                region.size.clone(),
+                region.sharing.as_ref().map(|sharing| Sharing {
+                    name: format!("{prefix}{}", sharing.name),
+                    offsets: sharing.offsets.clone(),
+                }),
+            )));
+        }
+
+        for instruction in program.body_instructions() {
+            merged.add_instruction(prefix_instruction(instruction, &prefix, index)?);
+        }
+        prefixes.push(prefix);
+    }
+
+    Ok(MergedProgram {
+        program: merged,
+        prefixes,
+    })
+}
+
+fn prefix_instruction(
+    instruction: &Instruction,
+    prefix: &str,
+    program: usize,
+) -> Result<Instruction, Error> {
+    match instruction {
+        Instruction::Gate(gate) => Ok(Instruction::Gate(Gate {
+            parameters: gate
+                .parameters
+                .iter()
+                .map(|parameter| prefix_expression(parameter, prefix))
+                .collect(),
+            ..gate.clone()
+        })),
+        Instruction::Measurement(measurement) => Ok(Instruction::Measurement(Measurement {
+            qubit: measurement.qubit.clone(),
+            target: measurement
+                .target
+                .as_ref()
+                .map(|target| prefix_memory_reference(target, prefix)),
+        })),
+        Instruction::Reset(reset) if reset.qubit.is_some() => Ok(instruction.clone()),
+        Instruction::Pragma(_) => Ok(instruction.clone()),
+        _ => Err(Error::UnsupportedInstruction {
+            program,
+            instruction: Box::new(instruction.clone()),
+        }),
+    }
+}
+
+fn prefix_memory_reference(reference: &MemoryReference, prefix: &str) -> MemoryReference {
+    MemoryReference {
+        name: format!("{prefix}{}", reference.name),
+        index: reference.index,
+    }
+}
+
+fn prefix_expression(expression: &Expression, prefix: &str) -> Expression {
+    let mut expression = expression.clone();
+    prefix_expression_in_place(&mut expression, prefix);
+    expression
+}
+
+fn prefix_expression_in_place(expression: &mut Expression, prefix: &str) {
+    match expression {
+        Expression::Address(reference) => {
+            *reference = prefix_memory_reference(reference, prefix);
+        }
+        Expression::FunctionCall(call) => prefix_expression_in_place(&mut call.expression, prefix),
+        Expression::Infix(infix) => {
+            prefix_expression_in_place(&mut infix.left, prefix);
+            prefix_expression_in_place(&mut infix.right, prefix);
+        }
+        Expression::Prefix(prefix_expression) => {
+            prefix_expression_in_place(&mut prefix_expression.expression, prefix);
+        }
+        _ => {}
+    }
+}
+
+/// Errors that can occur when merging programs.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// More than one program uses the same qubit.
+    #[error("Qubit {0} is used by more than one program")]
+    OverlappingQubit(u64),
+    /// A program uses a qubit variable or placeholder, which can't be checked for overlap.
+    #[error("Program {program} uses a qubit that is not a fixed index")]
+    NonFixedQubit {
+        /// The index of the offending program.
+        program: usize,
+    },
+    /// A program contains an instruction which can't safely be merged.
+    #[error("Program {program} contains an instruction that cannot be merged: {instruction:?}")]
+    UnsupportedInstruction {
+        /// The index of the offending program.
+        program: usize,
+        /// The offending instruction.
+        instruction: Box<Instruction>,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, str::FromStr};
+
+    use ndarray::arr2;
+    use quil_rs::{quil::Quil, Program};
+
+    use super::{merge_programs, Error};
+    use crate::{RegisterMap, RegisterMatrix};
+
+    #[test]
+    fn test_merge_and_split() {
+        let first =
+            Program::from_str("DECLARE ro BIT[2]\nH 0\nCNOT 0 1\nMEASURE 0 ro[0]\nMEASURE 1 ro[1]")
+                .unwrap();
+        let second =
+            Program::from_str("DECLARE theta REAL\nDECLARE ro BIT\nRX(theta) 2\nMEASURE 2 ro")
+                .unwrap();
+
+        let merged = merge_programs([&first, &second]).expect("programs should merge");
+        let quil = merged.program().to_quil_or_debug();
+        assert!(quil.contains("DECLARE p0_ro BIT[2]"));
+        assert!(quil.contains("DECLARE p1_theta REAL[1]"));
+        assert!(quil.contains("RX(p1_theta[0]) 2"));
+        assert!(quil.contains("MEASURE 1 p0_ro[1]"));
+        assert!(quil.contains("MEASURE 2 p1_ro[0]"));
+
+        let results = RegisterMap(HashMap::from([
+            (
+                "p0_ro".to_string(),
+                RegisterMatrix::Integer(arr2(&[[0, 0], [1, 1]])),
+            ),
+            (
+                "p1_ro".to_string(),
+                RegisterMatrix::Integer(arr2(&[[1], [0]])),
+            ),
+        ]));
+        let split = merged.split_register_map(results);
+        assert_eq!(split.len(), 2);
+        assert_eq!(
+            split[0].get_register_matrix("ro"),
+            Some(&RegisterMatrix::Integer(arr2(&[[0, 0], [1, 1]])))
+        );
+        assert_eq!(
+            split[1].get_register_matrix("ro"),
+            Some(&RegisterMatrix::Integer(arr2(&[[1], [0]])))
+        );
+    }
+
+    #[test]
+    fn test_merge_rejects_overlapping_qubits() {
+        let first = Program::from_str("H 0").unwrap();
+        let second = Program::from_str("X 0").unwrap();
+        assert!(matches!(
+            merge_programs([&first, &second]),
+            Err(Error::OverlappingQubit(0))
+        ));
+    }
+
+    #[test]
+    fn test_merge_rejects_unsupported_instructions() {
+        let program = Program::from_str("DECLARE ro BIT\nRESET\nMEASURE 0 ro").unwrap();
+        assert!(matches!(
+            merge_programs([&program]),
+            Err(Error::UnsupportedInstruction { program: 0, .. })
+        ));
+    }
+}
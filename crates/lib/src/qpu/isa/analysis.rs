@@ -0,0 +1,267 @@
+//! Query the fidelities a QPU's [`InstructionSetArchitecture`] reports for its qubits and edges,
+//! and choose the qubits most likely to run a program well.
+//!
+//! The fidelity of a qubit is its simultaneous single-qubit randomized benchmarking fidelity
+//! (`fRB` of `randomized_benchmark_simultaneous_1q`), which quilc also uses, or its individual
+//! `fRB` if that isn't reported. The fidelity of an edge is the best fidelity reported for any of
+//! its two-qubit gates, e.g. `fCZ` for `CZ`. Qubits and edges without a reported fidelity are
+//! never selected.
+//!
+//! Selections are ranked by the product of the fidelities of their qubits and of the edges
+//! connecting them, found with a heuristic search rather than by comparing every candidate.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use qcs_api_client_openapi::models::InstructionSetArchitecture;
+
+use super::sites::IsaSites;
+
+/// The names of the characteristics holding the fidelities of the two-qubit gates considered for
+/// an edge: `CZ`, `ISWAP`, `CPHASE` and `XY`.
+const TWO_QUBIT_GATE_FIDELITIES: [&str; 4] = ["fCZ", "fISWAP", "fCPHASE", "fXY"];
+
+/// How many of the best partial chains [`Fidelities::select_best_linear_chain`] keeps at each
+/// step.
+const BEAM_WIDTH: usize = 64;
+
+/// The fidelities of a QPU's qubits and edges, read from its [`InstructionSetArchitecture`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Fidelities {
+    qubits: BTreeMap<u64, f64>,
+    edges: BTreeMap<(u64, u64), f64>,
+    adjacency: BTreeMap<u64, BTreeSet<u64>>,
+}
+
+impl Fidelities {
+    /// Read the fidelities reported in `isa`, see the [module documentation](self).
+    #[must_use]
+    pub fn from_isa(isa: &InstructionSetArchitecture) -> Self {
+        let sites = IsaSites::new(isa);
+        let qubits = sites
+            .rb_fidelities
+            .iter()
+            .map(|(&qubit, characteristic)| (qubit, characteristic.value))
+            .collect();
+
+        let mut edges: BTreeMap<(u64, u64), f64> = BTreeMap::new();
+        for (&edge, characteristics) in &sites.edge_characteristics {
+            let best = characteristics
+                .iter()
+                .filter(|characteristic| {
+                    TWO_QUBIT_GATE_FIDELITIES.contains(&characteristic.name.as_str())
+                })
+                .map(|characteristic| characteristic.value)
+                .reduce(f64::max);
+            if let Some(best) = best {
+                edges.insert(edge, best);
+            }
+        }
+
+        Self::from_parts(qubits, edges)
+    }
+
+    /// Keep only the edges between qubits with known fidelities, and index them by qubit.
+    fn from_parts(qubits: BTreeMap<u64, f64>, mut edges: BTreeMap<(u64, u64), f64>) -> Self {
+        edges.retain(|(a, b), _| qubits.contains_key(a) && qubits.contains_key(b));
+        let mut adjacency: BTreeMap<u64, BTreeSet<u64>> = BTreeMap::new();
+        for &(a, b) in edges.keys() {
+            adjacency.entry(a).or_default().insert(b);
+            adjacency.entry(b).or_default().insert(a);
+        }
+        Self {
+            qubits,
+            edges,
+            adjacency,
+        }
+    }
+
+    /// The fidelity of `qubit`, if reported.
+    #[must_use]
+    pub fn qubit_fidelity(&self, qubit: u64) -> Option<f64> {
+        self.qubits.get(&qubit).copied()
+    }
+
+    /// The fidelity of the edge between `a` and `b`, in either order, if reported.
+    #[must_use]
+    pub fn edge_fidelity(&self, a: u64, b: u64) -> Option<f64> {
+        self.edges.get(&(a.min(b), a.max(b))).copied()
+    }
+
+    /// Every qubit with a reported fidelity, in ascending order.
+    pub fn qubit_fidelities(&self) -> impl Iterator<Item = (u64, f64)> + '_ {
+        self.qubits
+            .iter()
+            .map(|(&qubit, &fidelity)| (qubit, fidelity))
+    }
+
+    /// Every edge with a reported fidelity, as its qubits in ascending order.
+    pub fn edge_fidelities(&self) -> impl Iterator<Item = ((u64, u64), f64)> + '_ {
+        self.edges.iter().map(|(&edge, &fidelity)| (edge, fidelity))
+    }
+
+    /// The `n` qubits which form the best chain, each connected to the next by an edge, in order
+    /// along the chain. `None` if `n` is zero or no chain of `n` qubits exists.
+    #[must_use]
+    pub fn select_best_linear_chain(&self, n: usize) -> Option<Vec<u64>> {
+        if n == 0 {
+            return None;
+        }
+
+        let mut chains: Vec<(f64, Vec<u64>)> = self
+            .qubit_fidelities()
+            .map(|(qubit, fidelity)| (score(fidelity), vec![qubit]))
+            .collect();
+        for _ in 1..n {
+            let mut extended = Vec::new();
+            for (chain_score, chain) in &chains {
+                let Some(&last) = chain.last() else {
+                    continue;
+                };
+                for (neighbor, gain) in self.extensions(last) {
+                    if !chain.contains(&neighbor) {
+                        let mut chain = chain.clone();
+                        chain.push(neighbor);
+                        extended.push((chain_score + gain, chain));
+                    }
+                }
+            }
+            rank(&mut extended);
+            extended.truncate(BEAM_WIDTH);
+            chains = extended;
+        }
+        rank(&mut chains);
+        chains.into_iter().next().map(|(_, chain)| chain)
+    }
+
+    /// The `n` connected qubits which form the best subset, in ascending order. `None` if `n` is
+    /// zero or no connected subset of `n` qubits exists.
+    ///
+    /// A subset is grown from each qubit in turn by repeatedly adding the neighboring qubit which
+    /// best combines its own fidelity with that of its best edge into the subset.
+    #[must_use]
+    pub fn select_best_qubit_subset(&self, n: usize) -> Option<Vec<u64>> {
+        if n == 0 {
+            return None;
+        }
+
+        let mut subsets: Vec<_> = self
+            .qubits
+            .keys()
+            .filter_map(|&seed| self.grow_subset(seed, n))
+            .collect();
+        rank(&mut subsets);
+        subsets.into_iter().next().map(|(_, subset)| subset)
+    }
+
+    /// Grow a connected subset of `n` qubits from `seed`, with its score.
+    fn grow_subset(&self, seed: u64, n: usize) -> Option<(f64, Vec<u64>)> {
+        let mut subset = BTreeSet::from([seed]);
+        let mut subset_score = score(self.qubit_fidelity(seed)?);
+        while subset.len() < n {
+            let (next, gain) = subset
+                .iter()
+                .flat_map(|&qubit| self.extensions(qubit))
+                .filter(|(neighbor, _)| !subset.contains(neighbor))
+                .max_by(|(a, a_gain), (b, b_gain)| a_gain.total_cmp(b_gain).then(b.cmp(a)))?;
+            subset.insert(next);
+            subset_score += gain;
+        }
+        Some((subset_score, subset.into_iter().collect()))
+    }
+
+    /// The neighbors of `qubit`, each with the score of adding it and its edge to `qubit`.
+    fn extensions(&self, qubit: u64) -> impl Iterator<Item = (u64, f64)> + '_ {
+        self.adjacency
+            .get(&qubit)
+            .into_iter()
+            .flatten()
+            .filter_map(move |&neighbor| {
+                let gain = score(self.edge_fidelity(qubit, neighbor)?)
+                    + score(self.qubit_fidelity(neighbor)?);
+                Some((neighbor, gain))
+            })
+    }
+}
+
+/// The contribution of a fidelity to the score of a selection, which is the logarithm of the
+/// product of every fidelity in it.
+fn score(fidelity: f64) -> f64 {
+    fidelity.max(f64::MIN_POSITIVE).ln()
+}
+
+/// Sort selections best first, breaking ties by their qubits.
+fn rank(selections: &mut [(f64, Vec<u64>)]) {
+    selections.sort_by(|(a_score, a), (b_score, b)| b_score.total_cmp(a_score).then(a.cmp(b)));
+}
+
+#[cfg(test)]
+mod describe_fidelities {
+    use std::collections::BTreeMap;
+
+    use super::Fidelities;
+    use crate::compiler::topology::Topology;
+    use crate::test_support::aspen_9_isa;
+
+    /// A line of qubits 0-1-2-3, whose first edge is much worse than the others.
+    fn line() -> Fidelities {
+        Fidelities::from_parts(
+            (0..4).map(|qubit| (qubit, 0.99)).collect(),
+            BTreeMap::from([((0, 1), 0.5), ((1, 2), 0.9), ((2, 3), 0.95)]),
+        )
+    }
+
+    #[test]
+    fn it_reads_fidelities_from_the_isa() {
+        let isa = aspen_9_isa();
+        let fidelities = Fidelities::from_isa(&isa);
+        let topology = Topology::from_isa(&isa);
+
+        assert_eq!(fidelities.qubit_fidelity(0), Some(0.995_691_124_137_083_8));
+        assert_eq!(
+            fidelities.edge_fidelity(10, 11),
+            fidelities.edge_fidelity(11, 10)
+        );
+        assert!(fidelities.edge_fidelity(10, 11).is_some());
+        assert_eq!(fidelities.qubit_fidelity(1000), None);
+        assert!(fidelities
+            .edge_fidelities()
+            .all(|((a, b), _)| topology.neighbors(a).any(|qubit| qubit == b)));
+    }
+
+    #[test]
+    fn it_selects_the_best_linear_chain() {
+        let fidelities = line();
+        assert_eq!(fidelities.select_best_linear_chain(2), Some(vec![2, 3]));
+        assert_eq!(fidelities.select_best_linear_chain(3), Some(vec![1, 2, 3]));
+        assert_eq!(fidelities.select_best_linear_chain(5), None);
+        assert_eq!(fidelities.select_best_linear_chain(0), None);
+    }
+
+    #[test]
+    fn it_selects_the_best_qubit_subset() {
+        let fidelities = line();
+        assert_eq!(fidelities.select_best_qubit_subset(3), Some(vec![1, 2, 3]));
+        assert_eq!(
+            fidelities.select_best_qubit_subset(4),
+            Some(vec![0, 1, 2, 3])
+        );
+        assert_eq!(fidelities.select_best_qubit_subset(5), None);
+    }
+
+    #[test]
+    fn it_selects_connected_qubits_on_a_device() {
+        let isa = aspen_9_isa();
+        let fidelities = Fidelities::from_isa(&isa);
+        let topology = Topology::from_isa(&isa);
+
+        let chain = fidelities.select_best_linear_chain(4).unwrap();
+        assert_eq!(chain.len(), 4);
+        assert!(chain
+            .windows(2)
+            .all(|pair| topology.neighbors(pair[0]).any(|qubit| qubit == pair[1])));
+
+        let subset = fidelities.select_best_qubit_subset(6).unwrap();
+        assert_eq!(subset.len(), 6);
+        assert!(topology.is_connected(&subset));
+    }
+}
@@ -0,0 +1,5 @@
+//! Working with the [`InstructionSetArchitecture`](qcs_api_client_openapi::models::InstructionSetArchitecture)
+//! of a QPU, as fetched with [`get_isa`](super::get_isa).
+
+pub mod analysis;
+pub(crate) mod sites;
@@ -0,0 +1,122 @@
+//! The qubits, edges and measured characteristics of an [`InstructionSetArchitecture`], read once
+//! for everything in this crate which reasons about a device: its [`Topology`], the
+//! [`Fidelities`] of its qubits and edges, and the specs sent to quilc.
+//!
+//! [`Topology`]: crate::compiler::topology::Topology
+//! [`Fidelities`]: super::analysis::Fidelities
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryFrom;
+
+use qcs_api_client_openapi::models::{Characteristic, InstructionSetArchitecture, Operation};
+
+/// The benchmark whose `fRB` is preferred as the fidelity of a qubit, and which quilc expects as
+/// `f1QRB`.
+const SIMULTANEOUS_1Q_BENCHMARK: &str = "randomized_benchmark_simultaneous_1q";
+
+/// The benchmark whose `fRB` is used for qubits without a simultaneous benchmark.
+const INDIVIDUAL_1Q_BENCHMARK: &str = "randomized_benchmark_1q";
+
+/// The name of single-qubit randomized benchmarking fidelities.
+const RB_FIDELITY: &str = "fRB";
+
+/// The sites of an [`InstructionSetArchitecture`], keyed by qubit or by edge. Qubits are keyed by
+/// their node ID and edges by the node IDs of their two distinct qubits, in ascending order; sites
+/// with negative node IDs, or which aren't a qubit or an edge, are ignored.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct IsaSites<'isa> {
+    /// The qubits of the architecture.
+    pub(crate) qubits: BTreeSet<u64>,
+    /// The edges of the architecture.
+    pub(crate) edges: BTreeSet<(u64, u64)>,
+    /// The single-qubit randomized benchmarking fidelity (`fRB`) of each qubit, from the
+    /// simultaneous benchmark or, for qubits it doesn't cover, the individual one.
+    pub(crate) rb_fidelities: BTreeMap<u64, &'isa Characteristic>,
+    /// The characteristics of every instruction site on a single qubit, in ISA order.
+    pub(crate) qubit_characteristics: BTreeMap<u64, Vec<&'isa Characteristic>>,
+    /// The characteristics of every instruction site on an edge, in ISA order.
+    pub(crate) edge_characteristics: BTreeMap<(u64, u64), Vec<&'isa Characteristic>>,
+}
+
+impl<'isa> IsaSites<'isa> {
+    /// Read the sites of `isa`.
+    pub(crate) fn new(isa: &'isa InstructionSetArchitecture) -> Self {
+        let mut sites = Self {
+            qubits: isa
+                .architecture
+                .nodes
+                .iter()
+                .filter_map(|node| u64::try_from(node.node_id).ok())
+                .collect(),
+            edges: isa
+                .architecture
+                .edges
+                .iter()
+                .filter_map(|edge| edge_key(&edge.node_ids))
+                .collect(),
+            ..Self::default()
+        };
+
+        let benchmark = |name: &str| isa.benchmarks.iter().find(|op| op.name == name);
+        for name in [INDIVIDUAL_1Q_BENCHMARK, SIMULTANEOUS_1Q_BENCHMARK] {
+            if let Some(benchmark) = benchmark(name) {
+                sites.rb_fidelities.extend(rb_fidelities(benchmark));
+            }
+        }
+
+        let instruction_sites = isa
+            .instructions
+            .iter()
+            .flat_map(|instruction| &instruction.sites);
+        for site in instruction_sites {
+            let characteristics = site.characteristics.iter();
+            if let Some(qubit) = qubit_key(&site.node_ids) {
+                sites
+                    .qubit_characteristics
+                    .entry(qubit)
+                    .or_default()
+                    .extend(characteristics);
+            } else if let Some(edge) = edge_key(&site.node_ids) {
+                sites
+                    .edge_characteristics
+                    .entry(edge)
+                    .or_default()
+                    .extend(characteristics);
+            }
+        }
+
+        sites
+    }
+}
+
+/// The `fRB` of each qubit measured by a single-qubit randomized benchmark.
+fn rb_fidelities(benchmark: &Operation) -> impl Iterator<Item = (u64, &Characteristic)> + '_ {
+    benchmark.sites.iter().flat_map(|site| {
+        site.characteristics
+            .iter()
+            .filter(|characteristic| characteristic.name == RB_FIDELITY)
+            .filter_map(move |characteristic| {
+                let node_ids = characteristic.node_ids.as_deref().unwrap_or(&site.node_ids);
+                Some((qubit_key(node_ids)?, characteristic))
+            })
+    })
+}
+
+/// The key of a single qubit.
+fn qubit_key(node_ids: &[i64]) -> Option<u64> {
+    match node_ids {
+        [qubit] => u64::try_from(*qubit).ok(),
+        _ => None,
+    }
+}
+
+/// The key of the edge between two distinct qubits, in ascending order.
+fn edge_key(node_ids: &[i64]) -> Option<(u64, u64)> {
+    match node_ids {
+        [a, b] if a != b => {
+            let (a, b) = (u64::try_from(*a).ok()?, u64::try_from(*b).ok()?);
+            Some((a.min(b), a.max(b)))
+        }
+        _ => None,
+    }
+}
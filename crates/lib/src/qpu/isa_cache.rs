@@ -0,0 +1,172 @@
+//! Caching the [`InstructionSetArchitecture`] of each QPU, so that repeated executions against the
+//! same QPU don't have to fetch it every time.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use lazy_static::lazy_static;
+use qcs_api_client_openapi::models::InstructionSetArchitecture;
+
+use super::{get_isa, GetIsaError};
+use crate::client::Qcs;
+use crate::metrics::{self, Cache};
+
+/// How long [`IsaCache::global`] keeps an ISA before fetching it again, unless changed with
+/// [`IsaCache::set_ttl`].
+pub const DEFAULT_ISA_CACHE_TTL: Duration = Duration::from_secs(60);
+
+lazy_static! {
+    static ref GLOBAL_ISA_CACHE: IsaCache = IsaCache::new(DEFAULT_ISA_CACHE_TTL);
+}
+
+/// A cache of [`InstructionSetArchitecture`]s, keyed by quantum processor ID, which fetches an ISA
+/// again once it is older than the cache's time-to-live.
+///
+/// [`Executable`](crate::Executable) uses [`IsaCache::global`]. Pass `force_refresh` to
+/// [`IsaCache::get`], or call [`IsaCache::invalidate`], to fetch an ISA again before it expires,
+/// e.g. after a QPU has been recalibrated.
+#[allow(clippy::module_name_repetitions)]
+#[derive(Debug)]
+pub struct IsaCache {
+    inner: Mutex<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    ttl: Duration,
+    entries: HashMap<String, (Instant, InstructionSetArchitecture)>,
+}
+
+impl IsaCache {
+    /// Create an empty cache which keeps each ISA for `ttl`.
+    #[must_use]
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                ttl,
+                entries: HashMap::new(),
+            }),
+        }
+    }
+
+    /// The cache shared by the whole process, which keeps each ISA for
+    /// [`DEFAULT_ISA_CACHE_TTL`] unless changed with [`IsaCache::set_ttl`].
+    #[must_use]
+    pub fn global() -> &'static Self {
+        &GLOBAL_ISA_CACHE
+    }
+
+    /// How long an ISA is kept before it is fetched again.
+    #[must_use]
+    pub fn ttl(&self) -> Duration {
+        self.lock().ttl
+    }
+
+    /// Change how long an ISA is kept before it is fetched again. This applies to ISAs which are
+    /// already cached, too.
+    pub fn set_ttl(&self, ttl: Duration) {
+        self.lock().ttl = ttl;
+    }
+
+    /// Get the ISA of `quantum_processor_id`, fetching it from QCS if it isn't cached, has
+    /// expired, or `force_refresh` is set.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the ISA has to be fetched and that fails, leaving the cache as it was.
+    pub async fn get(
+        &self,
+        quantum_processor_id: &str,
+        client: &Qcs,
+        force_refresh: bool,
+    ) -> Result<InstructionSetArchitecture, GetIsaError> {
+        let cached = if force_refresh {
+            None
+        } else {
+            self.get_cached(quantum_processor_id)
+        };
+        metrics::record(Cache::Isa, cached.is_some());
+        if let Some(isa) = cached {
+            return Ok(isa);
+        }
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(force_refresh, "ISA cache miss for {}", quantum_processor_id);
+
+        let isa = get_isa(quantum_processor_id, client).await?;
+        self.insert(quantum_processor_id, isa.clone());
+        Ok(isa)
+    }
+
+    /// The cached ISA of `quantum_processor_id`, if there is one which hasn't expired.
+    #[must_use]
+    pub fn get_cached(&self, quantum_processor_id: &str) -> Option<InstructionSetArchitecture> {
+        let inner = self.lock();
+        let (fetched_at, isa) = inner.entries.get(quantum_processor_id)?;
+        (fetched_at.elapsed() < inner.ttl).then(|| isa.clone())
+    }
+
+    /// Cache `isa` as the ISA of `quantum_processor_id`, as though it had just been fetched.
+    pub fn insert(&self, quantum_processor_id: &str, isa: InstructionSetArchitecture) {
+        self.lock()
+            .entries
+            .insert(quantum_processor_id.to_string(), (Instant::now(), isa));
+    }
+
+    /// Forget the cached ISA of `quantum_processor_id`, so that the next [`IsaCache::get`] fetches
+    /// it again.
+    pub fn invalidate(&self, quantum_processor_id: &str) {
+        self.lock().entries.remove(quantum_processor_id);
+    }
+
+    /// Forget every cached ISA.
+    pub fn clear(&self) {
+        self.lock().entries.clear();
+    }
+
+    /// Lock the cache. Every update leaves it consistent, so a poisoned lock is still usable.
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod describe_isa_cache {
+    use std::time::Duration;
+
+    use super::IsaCache;
+    use crate::client::Qcs;
+    use crate::test_support::aspen_9_isa;
+
+    #[tokio::test]
+    async fn it_serves_cached_isas_without_fetching() {
+        let cache = IsaCache::new(Duration::from_secs(60));
+        cache.insert("Aspen-9", aspen_9_isa());
+
+        // The default client has no credentials, so any fetch would fail.
+        let isa = cache
+            .get("Aspen-9", &Qcs::default(), false)
+            .await
+            .expect("the ISA should come from the cache");
+        assert_eq!(isa, aspen_9_isa());
+    }
+
+    #[tokio::test]
+    async fn it_fetches_again_when_expired_forced_or_invalidated() {
+        let cache = IsaCache::new(Duration::from_secs(60));
+        cache.insert("Aspen-9", aspen_9_isa());
+        assert!(cache.get("Aspen-9", &Qcs::default(), true).await.is_err());
+
+        cache.invalidate("Aspen-9");
+        assert!(cache.get_cached("Aspen-9").is_none());
+
+        cache.insert("Aspen-9", aspen_9_isa());
+        cache.set_ttl(Duration::ZERO);
+        assert_eq!(cache.ttl(), Duration::ZERO);
+        assert!(cache.get_cached("Aspen-9").is_none());
+        assert!(cache.get("Aspen-9", &Qcs::default(), false).await.is_err());
+    }
+}
@@ -0,0 +1,549 @@
+//! Readout error mitigation: measure how often each qubit is read out wrongly, then correct the
+//! probabilities of a program's results for those errors.
+//!
+//! [`ReadoutCalibration`] runs two programs on a set of qubits, one which leaves every qubit in
+//! `|0>` and one which flips every qubit to `|1>`, and builds a [`ConfusionMatrix`] from how
+//! often each qubit was read out as the state it was prepared in. The model is tensored: each
+//! qubit's readout errors are assumed to be independent of the others', so only two programs are
+//! needed however many qubits there are.
+//!
+//! The corrected probabilities are returned by
+//! [`RegisterMap::apply_readout_mitigation`](crate::RegisterMap::apply_readout_mitigation). The
+//! program whose results are corrected must measure the calibrated qubits into its register in
+//! the same order, i.e. the `i`th calibrated qubit into index `i`.
+//!
+//! ```no_run
+//! use std::num::NonZeroU16;
+//!
+//! use qcs::qpu::mitigation::ReadoutCalibration;
+//! use qcs::{client::Qcs, qvm, BitOrder, Executable};
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let qvm_client = qvm::http::HttpClient::from(&Qcs::load());
+//! let shots = NonZeroU16::new(1000).unwrap();
+//! let confusion_matrix = ReadoutCalibration::new([0, 1])
+//!     .run_on_qvm(shots, &qvm_client)
+//!     .await?;
+//!
+//! let quil = "DECLARE ro BIT[2]\nH 0\nCNOT 0 1\nMEASURE 0 ro[0]\nMEASURE 1 ro[1]";
+//! let data = Executable::from_quil(quil)
+//!     .with_shots(shots)
+//!     .execute_on_qvm(&qvm_client)
+//!     .await?;
+//! let probabilities = data
+//!     .result_data
+//!     .to_register_map()?
+//!     .apply_readout_mitigation("ro", &confusion_matrix, BitOrder::AscendingIndex)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::BTreeMap;
+use std::num::NonZeroU16;
+use std::str::FromStr;
+
+use quil_rs::Program;
+
+use crate::{
+    interop, qvm, BitOrder, Executable, ExecutionData, RegisterMatrix,
+    RegisterMatrixConversionError,
+};
+
+#[cfg(feature = "qpu")]
+use crate::{client::Qcs, compiler::quilc::CachedCompiler, qpu::api::ExecutionOptions};
+
+/// The register calibration programs read out to.
+pub const CALIBRATION_REGISTER: &str = "ro";
+
+/// The most qubits whose readout can be corrected together. Correction works on every possible
+/// bitstring, of which there are `2^n` for `n` qubits.
+pub const MAX_MITIGATED_QUBITS: usize = 24;
+
+/// The probability of reading out each state given the state a single qubit was prepared in,
+/// indexed as `[measured][prepared]`.
+pub type QubitConfusionMatrix = [[f64; 2]; 2];
+
+/// Errors that can occur when calibrating or correcting readout.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// A calibration program couldn't be run.
+    #[error("failed to run a readout calibration program: {0}")]
+    Execution(#[from] crate::Error),
+    /// The results of a calibration program couldn't be arranged into a register.
+    #[error("readout calibration results could not be converted: {0}")]
+    Conversion(#[from] RegisterMatrixConversionError),
+    /// The results to correct couldn't be counted, e.g. because the register isn't made of bits.
+    #[error(transparent)]
+    Counts(#[from] interop::Error),
+    /// The results of a calibration program didn't include the calibration register, or it
+    /// wasn't made of bits.
+    #[error("readout calibration results have no bit register `ro` with {qubits} columns")]
+    MissingCalibration {
+        /// The number of qubits calibrated.
+        qubits: usize,
+    },
+    /// A bitstring didn't have a bit for every calibrated qubit.
+    #[error(
+        "expected bitstrings of {expected} bits to match the confusion matrix, found {bitstring:?}"
+    )]
+    Width {
+        /// The number of calibrated qubits.
+        expected: usize,
+        /// The bitstring which didn't match.
+        bitstring: String,
+    },
+    /// The readout of a qubit can't be corrected because its errors can't be undone, e.g. because
+    /// it reads out the same state whatever it was prepared in.
+    #[error("the readout of qubit {qubit} can't be corrected: its confusion matrix is singular")]
+    Singular {
+        /// The qubit whose confusion matrix is singular.
+        qubit: u64,
+    },
+    /// Too many qubits were calibrated to correct their readout together.
+    #[error("can't correct the readout of {qubits} qubits together, the most is 24")]
+    TooManyQubits {
+        /// The number of calibrated qubits.
+        qubits: usize,
+    },
+}
+
+/// The calibration of the readout of a set of qubits, run with [`ReadoutCalibration::run_on_qvm`]
+/// or [`ReadoutCalibration::run_on_qpu`]. See the [module documentation](self).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReadoutCalibration {
+    qubits: Vec<u64>,
+}
+
+impl ReadoutCalibration {
+    /// Calibrate the readout of `qubits`, in the order their results will be read out.
+    #[must_use]
+    pub fn new(qubits: impl IntoIterator<Item = u64>) -> Self {
+        Self {
+            qubits: qubits.into_iter().collect(),
+        }
+    }
+
+    /// The qubits being calibrated.
+    #[must_use]
+    pub fn qubits(&self) -> &[u64] {
+        &self.qubits
+    }
+
+    /// The program which prepares every qubit in `prepared` (`|1>` if `true`) and measures the
+    /// `i`th qubit into index `i` of [`CALIBRATION_REGISTER`].
+    ///
+    /// The programs are native Quil, so they can be run without compilation.
+    ///
+    /// # Panics
+    ///
+    /// Never: the program is always valid Quil.
+    #[must_use]
+    pub fn program(&self, prepared: bool) -> Program {
+        let mut quil = format!(
+            "DECLARE {CALIBRATION_REGISTER} BIT[{}]\n",
+            self.qubits.len().max(1)
+        );
+        if prepared {
+            for qubit in &self.qubits {
+                quil.push_str(&format!("RX(pi) {qubit}\n"));
+            }
+        }
+        for (index, qubit) in self.qubits.iter().enumerate() {
+            quil.push_str(&format!(
+                "MEASURE {qubit} {CALIBRATION_REGISTER}[{index}]\n"
+            ));
+        }
+        Program::from_str(&quil).expect("calibration programs are valid Quil")
+    }
+
+    /// Build the [`ConfusionMatrix`] from the results of [`ReadoutCalibration::program`] run with
+    /// every qubit in `|0>` and in `|1>`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either result doesn't have a column of bits in
+    /// [`CALIBRATION_REGISTER`] for every qubit.
+    pub fn confusion_matrix(
+        &self,
+        prepared_zero: &ExecutionData,
+        prepared_one: &ExecutionData,
+    ) -> Result<ConfusionMatrix, Error> {
+        let flipped_from_zero = self.fractions_read_as_one(prepared_zero)?;
+        let read_from_one = self.fractions_read_as_one(prepared_one)?;
+        Ok(ConfusionMatrix::from_qubit_matrices(
+            self.qubits
+                .iter()
+                .zip(flipped_from_zero.into_iter().zip(read_from_one))
+                .map(|(qubit, (one_given_zero, one_given_one))| {
+                    (
+                        *qubit,
+                        [
+                            [1.0 - one_given_zero, 1.0 - one_given_one],
+                            [one_given_zero, one_given_one],
+                        ],
+                    )
+                }),
+        ))
+    }
+
+    /// The fraction of shots in which each qubit was read out as `1`.
+    // Shot counts are far too small to lose precision as floats.
+    #[allow(clippy::cast_precision_loss)]
+    fn fractions_read_as_one(&self, data: &ExecutionData) -> Result<Vec<f64>, Error> {
+        let missing = || Error::MissingCalibration {
+            qubits: self.qubits.len(),
+        };
+        let register_map = data.result_data.to_register_map()?;
+        let matrix = match register_map.get_register_matrix(CALIBRATION_REGISTER) {
+            Some(RegisterMatrix::Integer(matrix)) if matrix.ncols() >= self.qubits.len() => matrix,
+            _ => return Err(missing()),
+        };
+        let shots = matrix.nrows().max(1) as f64;
+        Ok(matrix
+            .columns()
+            .into_iter()
+            .take(self.qubits.len())
+            .map(|column| column.iter().filter(|value| **value == 1).count() as f64 / shots)
+            .collect())
+    }
+
+    /// Run both calibration programs `shots` times on the QVM, and build the [`ConfusionMatrix`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either program fails to run. See
+    /// [`ReadoutCalibration::confusion_matrix`].
+    pub async fn run_on_qvm<V: qvm::Client + ?Sized>(
+        &self,
+        shots: NonZeroU16,
+        client: &V,
+    ) -> Result<ConfusionMatrix, Error> {
+        let prepared_zero = Executable::from_program(self.program(false))
+            .with_shots(shots)
+            .execute_on_qvm(client)
+            .await?;
+        let prepared_one = Executable::from_program(self.program(true))
+            .with_shots(shots)
+            .execute_on_qvm(client)
+            .await?;
+        self.confusion_matrix(&prepared_zero, &prepared_one)
+    }
+
+    /// Run both calibration programs `shots` times on `quantum_processor_id`, without compiling
+    /// them, and build the [`ConfusionMatrix`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either program fails to run. See
+    /// [`ReadoutCalibration::confusion_matrix`].
+    #[cfg(feature = "qpu")]
+    pub async fn run_on_qpu(
+        &self,
+        quantum_processor_id: &str,
+        shots: NonZeroU16,
+        client: Qcs,
+        execution_options: &ExecutionOptions,
+    ) -> Result<ConfusionMatrix, Error> {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(%quantum_processor_id, qubits = ?self.qubits, "calibrating readout");
+
+        let mut results = Vec::with_capacity(2);
+        for prepared in [false, true] {
+            let data = Executable::from_program(self.program(prepared))
+                .with_shots(shots)
+                .with_qcs_client(client.clone())
+                .with_compiler_backend(None::<CachedCompiler>)
+                .execute_on_qpu(quantum_processor_id, None, execution_options)
+                .await?;
+            results.push(data);
+        }
+        self.confusion_matrix(&results[0], &results[1])
+    }
+}
+
+/// The readout errors of a set of qubits, built by [`ReadoutCalibration`], used to correct the
+/// probabilities of results with
+/// [`RegisterMap::apply_readout_mitigation`](crate::RegisterMap::apply_readout_mitigation).
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfusionMatrix {
+    qubits: Vec<u64>,
+    matrices: Vec<QubitConfusionMatrix>,
+}
+
+impl ConfusionMatrix {
+    /// Build a confusion matrix from the [`QubitConfusionMatrix`] of each qubit, in the order
+    /// their results are read out.
+    #[must_use]
+    pub fn from_qubit_matrices(
+        matrices: impl IntoIterator<Item = (u64, QubitConfusionMatrix)>,
+    ) -> Self {
+        let (qubits, matrices) = matrices.into_iter().unzip();
+        Self { qubits, matrices }
+    }
+
+    /// The calibrated qubits, in the order their results are read out.
+    #[must_use]
+    pub fn qubits(&self) -> &[u64] {
+        &self.qubits
+    }
+
+    /// The [`QubitConfusionMatrix`] of `qubit`, if it was calibrated.
+    #[must_use]
+    pub fn qubit_matrix(&self, qubit: u64) -> Option<&QubitConfusionMatrix> {
+        self.qubits
+            .iter()
+            .position(|calibrated| *calibrated == qubit)
+            .map(|index| &self.matrices[index])
+    }
+
+    /// Correct `probabilities`, keyed on bitstrings written in `bit_order` with a bit for each
+    /// calibrated qubit, for readout errors.
+    ///
+    /// Each qubit's errors are undone by applying the inverse of its [`QubitConfusionMatrix`].
+    /// This can give small negative probabilities, which are clipped to 0 before the rest are
+    /// normalized to sum to 1. Bitstrings with a corrected probability of 0 are left out.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a bitstring has the wrong number of bits, a qubit's confusion matrix is
+    /// singular, or there are more than [`MAX_MITIGATED_QUBITS`] qubits.
+    pub fn correct(
+        &self,
+        probabilities: &BTreeMap<String, f64>,
+        bit_order: BitOrder,
+    ) -> Result<BTreeMap<String, f64>, Error> {
+        let width = self.qubits.len();
+        if width > MAX_MITIGATED_QUBITS {
+            return Err(Error::TooManyQubits { qubits: width });
+        }
+
+        let mut distribution = vec![0.0; 1 << width];
+        for (bitstring, probability) in probabilities {
+            let bits = bit_order
+                .parse_bitstring(bitstring)
+                .ok()
+                .filter(|bits| bits.len() == width)
+                .ok_or_else(|| Error::Width {
+                    expected: width,
+                    bitstring: bitstring.clone(),
+                })?;
+            let index = bits
+                .iter()
+                .enumerate()
+                .filter(|(_, bit)| **bit)
+                .fold(0, |index, (position, _)| index | (1 << position));
+            distribution[index] += probability;
+        }
+
+        for (position, (qubit, matrix)) in self.qubits.iter().zip(&self.matrices).enumerate() {
+            let inverse = invert(matrix).ok_or(Error::Singular { qubit: *qubit })?;
+            let bit = 1 << position;
+            for index in (0..distribution.len()).filter(|index| index & bit == 0) {
+                let (zero, one) = (distribution[index], distribution[index | bit]);
+                distribution[index] = inverse[0][0] * zero + inverse[0][1] * one;
+                distribution[index | bit] = inverse[1][0] * zero + inverse[1][1] * one;
+            }
+        }
+
+        for probability in &mut distribution {
+            *probability = probability.max(0.0);
+        }
+        let total: f64 = distribution.iter().sum();
+        Ok(distribution
+            .into_iter()
+            .enumerate()
+            .filter(|(_, probability)| *probability > 0.0)
+            .map(|(index, probability)| {
+                let bits: Vec<bool> = (0..width)
+                    .map(|position| (index >> position) & 1 == 1)
+                    .collect();
+                (bit_order.to_bitstring(&bits), probability / total)
+            })
+            .collect())
+    }
+}
+
+/// The inverse of `matrix`, or `None` if it's singular.
+fn invert(matrix: &QubitConfusionMatrix) -> Option<QubitConfusionMatrix> {
+    let [[a, b], [c, d]] = *matrix;
+    let determinant = a * d - b * c;
+    if determinant.abs() < f64::EPSILON {
+        return None;
+    }
+    Some([
+        [d / determinant, -b / determinant],
+        [-c / determinant, a / determinant],
+    ])
+}
+
+#[cfg(test)]
+mod describe_readout_mitigation {
+    use std::collections::BTreeMap;
+
+    use maplit::{btreemap, hashmap};
+    use ndarray::arr2;
+    use quil_rs::quil::Quil;
+
+    use super::{ConfusionMatrix, Error, ReadoutCalibration};
+    use crate::qvm::QvmResultData;
+    use crate::{BitOrder, ExecutionData, RegisterData, RegisterMap, RegisterMatrix, ResultData};
+
+    fn qvm_data(rows: Vec<Vec<i8>>) -> ExecutionData {
+        ExecutionData {
+            result_data: ResultData::Qvm(QvmResultData::from_memory_map(
+                hashmap! { "ro".to_string() => RegisterData::I8(rows) },
+            )),
+            duration: None,
+            timings: None,
+        }
+    }
+
+    /// Assert that `actual` and `expected` give each bitstring the same probability, where a
+    /// missing bitstring has a probability of 0.
+    fn assert_close(actual: &BTreeMap<String, f64>, expected: &BTreeMap<String, f64>) {
+        for bitstring in actual.keys().chain(expected.keys()) {
+            let actual = actual.get(bitstring).copied().unwrap_or_default();
+            let expected = expected.get(bitstring).copied().unwrap_or_default();
+            assert!(
+                (actual - expected).abs() < 1e-9,
+                "{bitstring}: {actual} != {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn it_builds_calibration_programs() {
+        let calibration = ReadoutCalibration::new([3, 1]);
+        assert_eq!(
+            calibration.program(false).to_quil().unwrap(),
+            "DECLARE ro BIT[2]\nMEASURE 3 ro[0]\nMEASURE 1 ro[1]\n"
+        );
+        assert_eq!(
+            calibration.program(true).to_quil().unwrap(),
+            "DECLARE ro BIT[2]\nRX(pi) 3\nRX(pi) 1\nMEASURE 3 ro[0]\nMEASURE 1 ro[1]\n"
+        );
+    }
+
+    #[test]
+    fn it_builds_a_confusion_matrix_from_calibration_results() {
+        let calibration = ReadoutCalibration::new([0, 1]);
+        let prepared_zero = qvm_data(vec![vec![0, 0], vec![1, 0], vec![0, 0], vec![0, 1]]);
+        let prepared_one = qvm_data(vec![vec![1, 1], vec![1, 1], vec![0, 1], vec![1, 1]]);
+
+        let confusion_matrix = calibration
+            .confusion_matrix(&prepared_zero, &prepared_one)
+            .unwrap();
+        assert_eq!(confusion_matrix.qubits(), &[0, 1]);
+        assert_eq!(
+            confusion_matrix.qubit_matrix(0),
+            Some(&[[0.75, 0.25], [0.25, 0.75]])
+        );
+        assert_eq!(
+            confusion_matrix.qubit_matrix(1),
+            Some(&[[0.75, 0.0], [0.25, 1.0]])
+        );
+        assert_eq!(confusion_matrix.qubit_matrix(2), None);
+    }
+
+    #[test]
+    fn it_rejects_calibration_results_without_the_register() {
+        let calibration = ReadoutCalibration::new([0, 1, 2]);
+        let data = qvm_data(vec![vec![0, 0]]);
+        assert!(matches!(
+            calibration.confusion_matrix(&data, &data),
+            Err(Error::MissingCalibration { qubits: 3 })
+        ));
+    }
+
+    #[test]
+    fn it_undoes_readout_errors() {
+        // Qubit 0 is read out wrongly 10% of the time, whatever its state.
+        let confusion_matrix =
+            ConfusionMatrix::from_qubit_matrices([(0, [[0.9, 0.1], [0.1, 0.9]])]);
+        let corrected = confusion_matrix
+            .correct(
+                &btreemap! { "0".to_string() => 0.9, "1".to_string() => 0.1 },
+                BitOrder::AscendingIndex,
+            )
+            .unwrap();
+        assert_close(&corrected, &btreemap! { "0".to_string() => 1.0 });
+    }
+
+    #[test]
+    fn it_corrects_each_qubit_independently() {
+        // Qubit 1 decays from 1 to 0 a fifth of the time, but never flips from 0 to 1.
+        let confusion_matrix = ConfusionMatrix::from_qubit_matrices([
+            (0, [[1.0, 0.0], [0.0, 1.0]]),
+            (1, [[1.0, 0.2], [0.0, 0.8]]),
+        ]);
+        let measured = btreemap! {
+            "00".to_string() => 0.125,
+            "01".to_string() => 0.5,
+            "10".to_string() => 0.375,
+        };
+        let corrected = confusion_matrix
+            .correct(&measured, BitOrder::AscendingIndex)
+            .unwrap();
+        assert_close(
+            &corrected,
+            &btreemap! {
+                "01".to_string() => 0.625,
+                "10".to_string() => 0.375,
+            },
+        );
+        // The same results written in the other order are corrected the same way.
+        let corrected = confusion_matrix
+            .correct(
+                &measured
+                    .iter()
+                    .map(|(bitstring, probability)| {
+                        (bitstring.chars().rev().collect(), *probability)
+                    })
+                    .collect(),
+                BitOrder::DescendingIndex,
+            )
+            .unwrap();
+        assert_close(
+            &corrected,
+            &btreemap! {
+                "10".to_string() => 0.625,
+                "01".to_string() => 0.375,
+            },
+        );
+    }
+
+    #[test]
+    fn it_rejects_singular_confusion_matrices_and_mismatched_bitstrings() {
+        let confusion_matrix =
+            ConfusionMatrix::from_qubit_matrices([(7, [[0.5, 0.5], [0.5, 0.5]])]);
+        assert!(matches!(
+            confusion_matrix.correct(
+                &btreemap! { "0".to_string() => 1.0 },
+                BitOrder::AscendingIndex
+            ),
+            Err(Error::Singular { qubit: 7 })
+        ));
+        assert!(matches!(
+            confusion_matrix.correct(
+                &btreemap! { "01".to_string() => 1.0 },
+                BitOrder::AscendingIndex
+            ),
+            Err(Error::Width { expected: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn it_applies_mitigation_to_a_register_map() {
+        let register_map = RegisterMap::from_hashmap(hashmap! {
+            "ro".to_string() => RegisterMatrix::Integer(arr2(&[[0], [0], [0], [0], [0], [0], [0], [0], [0], [1]])),
+        });
+        let confusion_matrix =
+            ConfusionMatrix::from_qubit_matrices([(0, [[0.9, 0.1], [0.1, 0.9]])]);
+        let corrected = register_map
+            .apply_readout_mitigation("ro", &confusion_matrix, BitOrder::AscendingIndex)
+            .unwrap();
+        assert_close(&corrected, &btreemap! { "0".to_string() => 1.0 });
+    }
+}
@@ -1,8 +1,11 @@
 //! This module contains all the functionality for running Quil programs on a real QPU. Specifically,
 //! the [`Execution`] struct in this module.
+#[cfg(feature = "qcs-api")]
 use std::time::Duration;
 
+#[cfg(feature = "qcs-api")]
 use crate::client::{OpenApiClientError, Qcs, DEFAULT_HTTP_API_TIMEOUT};
+#[cfg(feature = "qcs-api")]
 use qcs_api_client_openapi::{
     apis::{
         quantum_processors_api::{
@@ -12,16 +15,43 @@ use qcs_api_client_openapi::{
     },
     models::InstructionSetArchitecture,
 };
+#[cfg(feature = "qcs-api")]
 use tokio::time::error::Elapsed;
 
+#[cfg(feature = "qpu")]
 pub mod api;
+#[cfg(feature = "qpu")]
+pub mod batch;
+#[cfg(feature = "qcs-api")]
+pub mod characteristics;
+#[cfg(feature = "qpu")]
 mod execution;
+pub mod experimental;
+#[cfg(feature = "qcs-api")]
+pub mod isa;
+#[cfg(feature = "qcs-api")]
+mod isa_cache;
+pub mod mitigation;
+#[cfg(feature = "qpu")]
+pub mod patch_values;
+#[cfg(feature = "qpu")]
+pub mod reservation;
 pub mod result_data;
+#[cfg(feature = "qpu")]
+pub mod retry;
+#[cfg(feature = "qpu")]
 pub mod translation;
 
-pub(crate) use execution::{Error as ExecutionError, Execution};
+#[cfg(feature = "qpu")]
+pub(crate) use execution::{retrieve_job_results, Error as ExecutionError, Execution};
+#[cfg(feature = "qcs-api")]
+pub use isa_cache::{IsaCache, DEFAULT_ISA_CACHE_TTL};
 #[allow(clippy::module_name_repetitions)]
-pub use result_data::{QpuResultData, ReadoutValues};
+pub use result_data::{
+    QpuResultData, RawQpuResult, ReadoutChunk, ReadoutChunks, ReadoutValues, ReadoutValuesView,
+};
+#[cfg(feature = "qpu")]
+pub use translation::get_calibration_program;
 
 /// Query QCS for the ISA of the provided `quantum_processor_id`.
 ///
@@ -30,6 +60,7 @@ pub use result_data::{QpuResultData, ReadoutValues};
 /// 1. Problem communicating with QCS
 /// 2. Unauthenticated
 /// 3. Expired token
+#[cfg(feature = "qcs-api")]
 pub async fn get_isa(
     quantum_processor_id: &str,
     client: &Qcs,
@@ -40,15 +71,20 @@ pub async fn get_isa(
         quantum_processor_id
     );
 
-    get_instruction_set_architecture(&client.get_openapi_client(), quantum_processor_id)
+    client
+        .with_refresh(|configuration| async move {
+            get_instruction_set_architecture(&configuration, quantum_processor_id).await
+        })
         .await
         .map_err(OpenApiClientError::RequestFailed)
 }
 
 /// Error raised due to failure to get an ISA
+#[cfg(feature = "qcs-api")]
 pub type GetIsaError = OpenApiClientError<GetInstructionSetArchitectureError>;
 
 /// API Errors encountered when trying to list available quantum processors.
+#[cfg(feature = "qcs-api")]
 #[derive(Debug, thiserror::Error)]
 pub enum ListQuantumProcessorsError {
     /// Failed the http call
@@ -62,6 +98,7 @@ pub enum ListQuantumProcessorsError {
 
 /// Query the QCS API for the names of all available quantum processors.
 /// If `None`, the default `timeout` used is 10 seconds.
+#[cfg(feature = "qcs-api")]
 pub async fn list_quantum_processors(
     client: &Qcs,
     timeout: Option<Duration>,
@@ -76,12 +113,13 @@ pub async fn list_quantum_processors(
         let mut page_token = None;
 
         loop {
-            let result = quantum_processors_api::list_quantum_processors(
-                &client.get_openapi_client(),
-                Some(100),
-                page_token.as_deref(),
-            )
-            .await?;
+            let page = page_token.as_deref();
+            let result = client
+                .with_refresh(|configuration| async move {
+                    quantum_processors_api::list_quantum_processors(&configuration, Some(100), page)
+                        .await
+                })
+                .await?;
 
             let mut data = result
                 .quantum_processors
@@ -0,0 +1,266 @@
+//! Typed values to patch into the memory regions of a translated program when it is submitted to
+//! a QPU.
+//!
+//! [`Executable`](crate::Executable) and the `submit*` functions in [`super::api`] accept
+//! parameters as `f64`s, which are only correct for `REAL` regions. Use [`type_parameters`] to
+//! convert them into the [`MemoryValues`] variant matching each region's `DECLARE`d type, or
+//! build [`TypedParameters`] directly.
+
+use std::{collections::HashMap, convert::TryFrom};
+
+use qcs_api_client_grpc::models::controller::{
+    data_value::Value, BinaryDataValue, DataValue, IntegerDataValue, JobExecutionConfiguration,
+    RealDataValue,
+};
+use quil_rs::instruction::ScalarType;
+use quil_rs::quil::Quil;
+use quil_rs::Program;
+
+use super::result_data::MemoryValues;
+use crate::executable::Parameters;
+
+/// Patch values for each memory region, typed to match the region's declaration.
+pub type TypedParameters = HashMap<Box<str>, MemoryValues>;
+
+/// A set of values which can be patched into a program submitted to a QPU.
+pub trait PatchValues {
+    /// Serialize the values into the configuration of a single job execution.
+    fn to_job_execution_configuration(&self) -> JobExecutionConfiguration;
+}
+
+impl PatchValues for Parameters {
+    /// Every region is sent as `REAL` data.
+    fn to_job_execution_configuration(&self) -> JobExecutionConfiguration {
+        let memory_values = self
+            .iter()
+            .map(|(name, values)| {
+                (
+                    name.as_ref().into(),
+                    DataValue {
+                        value: Some(Value::Real(RealDataValue {
+                            data: values.clone(),
+                        })),
+                    },
+                )
+            })
+            .collect();
+        JobExecutionConfiguration { memory_values }
+    }
+}
+
+impl PatchValues for TypedParameters {
+    fn to_job_execution_configuration(&self) -> JobExecutionConfiguration {
+        let memory_values = self
+            .iter()
+            .map(|(name, values)| (name.as_ref().into(), data_value(values)))
+            .collect();
+        JobExecutionConfiguration { memory_values }
+    }
+}
+
+/// The gRPC representation of `values`.
+fn data_value(values: &MemoryValues) -> DataValue {
+    let value = match values {
+        MemoryValues::Binary(data) => Value::Binary(BinaryDataValue { data: data.clone() }),
+        MemoryValues::Integer(data) => Value::Integer(IntegerDataValue { data: data.clone() }),
+        MemoryValues::Real(data) => Value::Real(RealDataValue { data: data.clone() }),
+    };
+    DataValue { value: Some(value) }
+}
+
+/// Errors that can occur when typing patch values against a program's declarations.
+#[derive(Debug, thiserror::Error, PartialEq)]
+#[non_exhaustive]
+pub enum Error {
+    /// A value was given for a region that the program doesn't declare.
+    #[error("The program does not declare a memory region named {name}")]
+    RegionNotFound {
+        /// The name of the region.
+        name: Box<str>,
+    },
+    /// More values were given than the region can hold.
+    #[error("{found} values were given for {name}, which is declared with length {declared}")]
+    TooManyValues {
+        /// The name of the region.
+        name: Box<str>,
+        /// The declared length of the region.
+        declared: u64,
+        /// The number of values given.
+        found: usize,
+    },
+    /// A value can't be represented by the region's declared type.
+    #[error("{name}[{index}] is declared as {data_type}, which can't hold {value}")]
+    InvalidValue {
+        /// The name of the region.
+        name: Box<str>,
+        /// The index of the value within the region.
+        index: usize,
+        /// The value given.
+        value: f64,
+        /// The declared type of the region, as Quil.
+        data_type: String,
+    },
+}
+
+/// Convert `params` into [`TypedParameters`], using the type each region is declared with in
+/// `program`.
+///
+/// `BIT` and `OCTET` regions become [`MemoryValues::Binary`], `INTEGER` regions become
+/// [`MemoryValues::Integer`], and `REAL` regions are left as [`MemoryValues::Real`].
+///
+/// # Errors
+///
+/// Returns an [`Error`] if a region isn't declared, is given more values than its declared length,
+/// or is given a value which its type can't hold exactly (e.g. `2.0` for a `BIT`, or `0.5` for an
+/// `INTEGER`).
+pub fn type_parameters(program: &Program, params: &Parameters) -> Result<TypedParameters, Error> {
+    params
+        .iter()
+        .map(|(name, values)| {
+            let region = program
+                .memory_regions
+                .get(name.as_ref())
+                .ok_or_else(|| Error::RegionNotFound { name: name.clone() })?;
+            if values.len() as u64 > region.size.length {
+                return Err(Error::TooManyValues {
+                    name: name.clone(),
+                    declared: region.size.length,
+                    found: values.len(),
+                });
+            }
+            let data_type = region.size.data_type;
+            let invalid = |index: usize, value: f64| Error::InvalidValue {
+                name: name.clone(),
+                index,
+                value,
+                data_type: data_type.to_quil_or_debug(),
+            };
+            let typed = match data_type {
+                ScalarType::Real => MemoryValues::Real(values.clone()),
+                ScalarType::Bit => MemoryValues::Binary(convert(values, 0.0, 1.0, invalid)?),
+                ScalarType::Octet => MemoryValues::Binary(convert(
+                    values,
+                    f64::from(u8::MIN),
+                    f64::from(u8::MAX),
+                    invalid,
+                )?),
+                // `i64::MAX` isn't representable as an `f64`, so the upper bound is the largest
+                // `f64` below 2^63.
+                ScalarType::Integer => MemoryValues::Integer(convert(
+                    values,
+                    -(2f64.powi(63)),
+                    2f64.powi(63) - 1024.0,
+                    invalid,
+                )?),
+            };
+            Ok((name.clone(), typed))
+        })
+        .collect()
+}
+
+/// Convert each value into a whole number within `min..=max`.
+#[allow(clippy::cast_possible_truncation)]
+fn convert<T, F>(values: &[f64], min: f64, max: f64, invalid: F) -> Result<Vec<T>, Error>
+where
+    T: TryFrom<i64>,
+    F: Fn(usize, f64) -> Error,
+{
+    values
+        .iter()
+        .enumerate()
+        .map(|(index, &value)| {
+            if value.fract() != 0.0 || value < min || value > max {
+                return Err(invalid(index, value));
+            }
+            T::try_from(value as i64).map_err(|_| invalid(index, value))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod describe_type_parameters {
+    use std::str::FromStr;
+
+    use qcs_api_client_grpc::models::controller::data_value::Value;
+    use quil_rs::Program;
+
+    use super::{type_parameters, Error, PatchValues};
+    use crate::executable::Parameters;
+    use crate::qpu::result_data::MemoryValues;
+
+    fn program() -> Program {
+        Program::from_str(
+            "DECLARE flag BIT\nDECLARE bytes OCTET[2]\nDECLARE count INTEGER\nDECLARE theta REAL",
+        )
+        .unwrap()
+    }
+
+    fn params(name: &str, values: Vec<f64>) -> Parameters {
+        Parameters::from([(Box::from(name), values)])
+    }
+
+    #[test]
+    fn it_types_values_by_declaration() {
+        let params = Parameters::from([
+            (Box::from("flag"), vec![1.0]),
+            (Box::from("bytes"), vec![0.0, 255.0]),
+            (Box::from("count"), vec![-42.0]),
+            (Box::from("theta"), vec![0.5]),
+        ]);
+        let typed = type_parameters(&program(), &params).unwrap();
+        assert_eq!(typed["flag"], MemoryValues::Binary(vec![1]));
+        assert_eq!(typed["bytes"], MemoryValues::Binary(vec![0, 255]));
+        assert_eq!(typed["count"], MemoryValues::Integer(vec![-42]));
+        assert_eq!(typed["theta"], MemoryValues::Real(vec![0.5]));
+
+        let configuration = typed.to_job_execution_configuration();
+        assert!(matches!(
+            configuration.memory_values["bytes"].value,
+            Some(Value::Binary(_))
+        ));
+        assert!(matches!(
+            configuration.memory_values["count"].value,
+            Some(Value::Integer(_))
+        ));
+        assert!(matches!(
+            configuration.memory_values["theta"].value,
+            Some(Value::Real(_))
+        ));
+    }
+
+    #[test]
+    fn it_rejects_values_the_declared_type_cannot_hold() {
+        for (name, value) in [
+            ("flag", 2.0),
+            ("bytes", 256.0),
+            ("bytes", -1.0),
+            ("count", 0.5),
+        ] {
+            assert!(
+                matches!(
+                    type_parameters(&program(), &params(name, vec![value])),
+                    Err(Error::InvalidValue { .. })
+                ),
+                "{name} should not accept {value}"
+            );
+        }
+    }
+
+    #[test]
+    fn it_rejects_undeclared_and_oversized_regions() {
+        assert_eq!(
+            type_parameters(&program(), &params("missing", vec![1.0])),
+            Err(Error::RegionNotFound {
+                name: Box::from("missing")
+            })
+        );
+        assert_eq!(
+            type_parameters(&program(), &params("flag", vec![0.0, 1.0])),
+            Err(Error::TooManyValues {
+                name: Box::from("flag"),
+                declared: 1,
+                found: 2,
+            })
+        );
+    }
+}
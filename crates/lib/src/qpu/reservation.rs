@@ -0,0 +1,600 @@
+//! Work with QPU reservations: list upcoming reservations, wait for one to begin before
+//! submitting jobs, and size job submissions to fit within the remaining time of a reservation.
+//!
+//! See [`list_reservations`], [`ExecutionOptions::wait_for_reservation`], and
+//! [`Executable::execute_on_qpu_within_reservation`](crate::Executable::execute_on_qpu_within_reservation).
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::num::NonZeroU16;
+use std::time::{Duration, SystemTime};
+
+use chrono::{DateTime, Utc};
+use ndarray::{concatenate, Axis};
+use qcs_api_client_openapi::apis::reservations_api::{
+    list_reservations as api_list_reservations, ListReservationsError,
+};
+use qcs_api_client_openapi::apis::Error as OpenApiError;
+use qcs_api_client_openapi::models;
+
+use super::api::ExecutionOptions;
+use crate::client::Qcs;
+use crate::{ExecutionData, RegisterMap, RegisterMatrix, RegisterMatrixConversionError};
+
+/// The default for [`ExecutionOptions::max_reservation_wait`].
+pub const DEFAULT_MAX_RESERVATION_WAIT: Duration = Duration::from_secs(60 * 60);
+
+/// The delay before checking again whether a reservation has begun, which doubles with each check
+/// up to [`MAX_RESERVATION_POLL_INTERVAL`].
+const MIN_RESERVATION_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The longest delay between two checks of whether a reservation has begun.
+const MAX_RESERVATION_POLL_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Errors that can occur while listing reservations or waiting for one to begin.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// The reservations couldn't be listed.
+    #[error("Failed to list reservations: {0}")]
+    ListFailed(#[from] OpenApiError<ListReservationsError>),
+
+    /// A reservation had a start or end time which isn't a valid RFC 3339 timestamp.
+    #[error("Reservation {id} has an invalid time {time:?}: {source}")]
+    InvalidTime {
+        /// The ID of the reservation.
+        id: i64,
+        /// The time as given by QCS.
+        time: String,
+        /// Why the time couldn't be parsed.
+        #[source]
+        source: chrono::ParseError,
+    },
+
+    /// Waiting for a reservation was requested, but there is no reservation to wait for.
+    #[error("There is no current or upcoming reservation for quantum processor {0}")]
+    NoUpcomingReservation(String),
+
+    /// The next reservation begins too late to wait for it.
+    #[error("The next reservation for quantum processor {quantum_processor_id} begins at {start_time}, later than the maximum wait of {max_wait:?}")]
+    WaitExceeded {
+        /// The quantum processor the reservation is for.
+        quantum_processor_id: String,
+        /// When the next reservation begins.
+        start_time: DateTime<Utc>,
+        /// The longest time to wait for a reservation, see
+        /// [`ExecutionOptions::max_reservation_wait`].
+        max_wait: Duration,
+    },
+}
+
+/// A reservation of a quantum processor, as listed by [`list_reservations`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Reservation {
+    /// The ID of the reservation.
+    pub id: i64,
+    /// The quantum processor which is reserved.
+    pub quantum_processor_id: String,
+    /// When the reservation begins.
+    pub start_time: DateTime<Utc>,
+    /// When the reservation ends.
+    pub end_time: DateTime<Utc>,
+}
+
+impl Reservation {
+    /// Whether the reservation has begun, but not ended, at `now`.
+    #[must_use]
+    pub fn is_active_at(&self, now: DateTime<Utc>) -> bool {
+        self.start_time <= now && now < self.end_time
+    }
+
+    /// A [`ShotBudget`] for running shots until the end of this reservation, where each shot is
+    /// expected to take `estimated_shot_duration`.
+    #[must_use]
+    pub fn shot_budget(&self, estimated_shot_duration: Duration) -> ShotBudget {
+        ShotBudget::new(self.end_time.into(), estimated_shot_duration)
+    }
+}
+
+impl TryFrom<models::Reservation> for Reservation {
+    type Error = Error;
+
+    fn try_from(reservation: models::Reservation) -> Result<Self, Self::Error> {
+        let id = reservation.id;
+        let parse = |time: String| {
+            DateTime::parse_from_rfc3339(&time)
+                .map(|time| time.with_timezone(&Utc))
+                .map_err(|source| Error::InvalidTime { id, time, source })
+        };
+        Ok(Self {
+            id,
+            quantum_processor_id: reservation.quantum_processor_id,
+            start_time: parse(reservation.start_time)?,
+            end_time: parse(reservation.end_time)?,
+        })
+    }
+}
+
+/// List the reservations you hold which haven't ended yet, including any which are active now,
+/// ordered by when they begin. If `quantum_processor_id` is given, only reservations of that
+/// quantum processor are listed. Cancelled reservations are never listed.
+///
+/// # Errors
+///
+/// Returns an [`Error`] if the reservations can't be listed, or if QCS returns a reservation with
+/// an invalid start or end time.
+pub async fn list_reservations(
+    client: &Qcs,
+    quantum_processor_id: Option<&str>,
+) -> Result<Vec<Reservation>, Error> {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(?quantum_processor_id, "listing reservations");
+
+    let now = Utc::now();
+    let mut filter = format!("endTime > \"{}\"", now.to_rfc3339());
+    if let Some(quantum_processor_id) = quantum_processor_id {
+        filter.push_str(&format!(
+            " AND quantumProcessorId == \"{quantum_processor_id}\""
+        ));
+    }
+
+    let mut reservations = Vec::new();
+    let mut page_token = None;
+    loop {
+        let (filter, page) = (filter.as_str(), page_token.as_deref());
+        let response = client
+            .with_refresh(|configuration| async move {
+                api_list_reservations(
+                    &configuration,
+                    Some(filter),
+                    None,
+                    Some(100),
+                    page,
+                    None,
+                    None,
+                    None,
+                )
+                .await
+            })
+            .await?;
+        for reservation in response.reservations {
+            if reservation.cancelled == Some(true) {
+                continue;
+            }
+            reservations.push(Reservation::try_from(reservation)?);
+        }
+        page_token = response.next_page_token;
+        if page_token.is_none() {
+            break;
+        }
+    }
+
+    // The filter is applied again in case QCS ignored any of it.
+    reservations.retain(|reservation| {
+        reservation.end_time > now
+            && quantum_processor_id.map_or(true, |id| reservation.quantum_processor_id == id)
+    });
+    reservations.sort_by_key(|reservation| reservation.start_time);
+    Ok(reservations)
+}
+
+/// What to do next while waiting for a reservation to begin.
+#[derive(Debug, PartialEq, Eq)]
+enum Wait {
+    /// A reservation is active.
+    Ready(Reservation),
+    /// Check again after the given delay.
+    Sleep(Duration),
+}
+
+/// Decide what to do next, given the `reservations` listed at `now`, the deadline after which a
+/// reservation is too late to wait for along with the maximum wait it was computed from, and the
+/// delay to use if there's no reason to check sooner.
+fn next_wait(
+    quantum_processor_id: &str,
+    reservations: &[Reservation],
+    now: DateTime<Utc>,
+    limit: Option<(DateTime<Utc>, Duration)>,
+    poll_interval: Duration,
+) -> Result<Wait, Error> {
+    if let Some(active) = reservations
+        .iter()
+        .find(|reservation| reservation.is_active_at(now))
+    {
+        return Ok(Wait::Ready(active.clone()));
+    }
+    let next = reservations
+        .iter()
+        .filter(|reservation| reservation.end_time > now)
+        .min_by_key(|reservation| reservation.start_time)
+        .ok_or_else(|| Error::NoUpcomingReservation(quantum_processor_id.to_string()))?;
+    if let Some((deadline, max_wait)) = limit {
+        if next.start_time > deadline {
+            return Err(Error::WaitExceeded {
+                quantum_processor_id: quantum_processor_id.to_string(),
+                start_time: next.start_time,
+                max_wait,
+            });
+        }
+    }
+    let until_start = (next.start_time - now).to_std().unwrap_or_default();
+    Ok(Wait::Sleep(
+        poll_interval.min(until_start).max(Duration::from_secs(1)),
+    ))
+}
+
+/// Wait until one of your reservations of `quantum_processor_id` is active, and return it.
+///
+/// Reservations are listed again before each check, so that a reservation which is made or
+/// cancelled while waiting is taken into account. The delay between checks starts short and
+/// doubles with each check, but never goes past the beginning of the next reservation.
+///
+/// # Errors
+///
+/// Returns an [`Error`] if the reservations can't be listed, if there is no current or upcoming
+/// reservation, or if the next reservation begins more than `max_wait` from now, if given.
+pub async fn wait_for_reservation(
+    client: &Qcs,
+    quantum_processor_id: &str,
+    max_wait: Option<Duration>,
+) -> Result<Reservation, Error> {
+    let started = Utc::now();
+    let limit = max_wait.and_then(|max_wait| {
+        let deadline = started.checked_add_signed(chrono::Duration::from_std(max_wait).ok()?)?;
+        Some((deadline, max_wait))
+    });
+    let mut poll_interval = MIN_RESERVATION_POLL_INTERVAL;
+    loop {
+        let reservations = list_reservations(client, Some(quantum_processor_id)).await?;
+        let wait = next_wait(
+            quantum_processor_id,
+            &reservations,
+            Utc::now(),
+            limit,
+            poll_interval,
+        )?;
+        match wait {
+            Wait::Ready(reservation) => {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(
+                    reservation_id = reservation.id,
+                    "reservation is active, submitting"
+                );
+                return Ok(reservation);
+            }
+            Wait::Sleep(delay) => {
+                #[cfg(feature = "tracing")]
+                tracing::info!(
+                    quantum_processor_id,
+                    ?delay,
+                    "waiting for a reservation to begin"
+                );
+                tokio::time::sleep(delay).await;
+                poll_interval = (poll_interval * 2).min(MAX_RESERVATION_POLL_INTERVAL);
+            }
+        }
+    }
+}
+
+/// Wait for a reservation if `execution_options` ask for it, see
+/// [`ExecutionOptions::wait_for_reservation`].
+pub(crate) async fn wait_if_requested(
+    client: &Qcs,
+    quantum_processor_id: Option<&str>,
+    execution_options: &ExecutionOptions,
+) -> Result<(), super::api::QpuApiError> {
+    if !execution_options.wait_for_reservation() {
+        return Ok(());
+    }
+    let quantum_processor_id = quantum_processor_id.ok_or(super::api::QpuApiError::MissingQpuId)?;
+    wait_for_reservation(
+        client,
+        quantum_processor_id,
+        execution_options.max_reservation_wait(),
+    )
+    .await?;
+    Ok(())
+}
+
+/// The default allowance for the time a job spends outside of execution, such as translation,
+/// queueing, and network round trips.
+pub const DEFAULT_JOB_OVERHEAD: Duration = Duration::from_secs(5);
+
+/// Describes how much time is left in a reservation and how long shots are expected to take, used
+/// to decide how many shots to submit in each job.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ShotBudget {
+    reservation_end: SystemTime,
+    estimated_shot_duration: Duration,
+    job_overhead: Duration,
+    max_shots_per_job: NonZeroU16,
+}
+
+impl ShotBudget {
+    /// Create a budget for a reservation ending at `reservation_end`, where each shot is expected
+    /// to take `estimated_shot_duration`.
+    #[must_use]
+    pub fn new(reservation_end: SystemTime, estimated_shot_duration: Duration) -> Self {
+        Self {
+            reservation_end,
+            estimated_shot_duration,
+            job_overhead: DEFAULT_JOB_OVERHEAD,
+            max_shots_per_job: NonZeroU16::MAX,
+        }
+    }
+
+    /// Set the time allowed per job for everything other than executing shots. Defaults to
+    /// [`DEFAULT_JOB_OVERHEAD`].
+    #[must_use]
+    pub fn with_job_overhead(mut self, job_overhead: Duration) -> Self {
+        self.job_overhead = job_overhead;
+        self
+    }
+
+    /// Limit the number of shots submitted in a single job.
+    #[must_use]
+    pub fn with_max_shots_per_job(mut self, max_shots_per_job: NonZeroU16) -> Self {
+        self.max_shots_per_job = max_shots_per_job;
+        self
+    }
+
+    /// The time the reservation ends.
+    #[must_use]
+    pub fn reservation_end(&self) -> SystemTime {
+        self.reservation_end
+    }
+
+    /// The expected duration of a single shot.
+    #[must_use]
+    pub fn estimated_shot_duration(&self) -> Duration {
+        self.estimated_shot_duration
+    }
+
+    /// The time allowed per job for everything other than executing shots.
+    #[must_use]
+    pub fn job_overhead(&self) -> Duration {
+        self.job_overhead
+    }
+
+    /// The maximum number of shots submitted in a single job.
+    #[must_use]
+    pub fn max_shots_per_job(&self) -> NonZeroU16 {
+        self.max_shots_per_job
+    }
+
+    /// Raise the per-shot estimate if a job took longer per shot than expected. The estimate is
+    /// never lowered, so that a fast job doesn't cause the next one to overrun the reservation.
+    pub(crate) fn observe(&mut self, shots: NonZeroU16, elapsed: Duration) {
+        let per_shot = elapsed.saturating_sub(self.job_overhead) / u32::from(shots.get());
+        self.estimated_shot_duration = self.estimated_shot_duration.max(per_shot);
+    }
+
+    /// The number of shots to submit in the next job, given the current time and the number of
+    /// shots still to run, or `None` if there isn't enough time left for even one shot.
+    #[must_use]
+    pub fn shots_for_next_job(&self, now: SystemTime, shots_remaining: u32) -> Option<NonZeroU16> {
+        let available = self
+            .reservation_end
+            .duration_since(now)
+            .ok()?
+            .checked_sub(self.job_overhead)?;
+        let affordable = if self.estimated_shot_duration.is_zero() {
+            u128::MAX
+        } else {
+            available.as_nanos() / self.estimated_shot_duration.as_nanos()
+        };
+        let shots = affordable
+            .min(u128::from(shots_remaining))
+            .min(u128::from(self.max_shots_per_job.get()));
+        NonZeroU16::new(u16::try_from(shots).ok()?)
+    }
+}
+
+/// Why an execution with a [`ShotBudget`] stopped.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ReservationExecutionStatus {
+    /// All requested shots were run.
+    Completed,
+    /// The remaining shots were not submitted because they would not finish before the
+    /// reservation ends.
+    ReservationExpiring {
+        /// The number of requested shots that were not run.
+        shots_remaining: u32,
+    },
+    /// A job failed after at least one earlier job had completed. The results of the earlier jobs
+    /// are kept.
+    Interrupted {
+        /// The number of requested shots that were not run.
+        shots_remaining: u32,
+        /// The error which stopped execution.
+        error: Box<crate::Error>,
+    },
+}
+
+/// The results of an execution with a [`ShotBudget`], which may have stopped before running
+/// every requested shot.
+#[derive(Debug)]
+pub struct ReservationExecution {
+    /// The results of each job, in the order they were run.
+    pub batches: Vec<ExecutionData>,
+    /// The number of shots that were requested.
+    pub shots_requested: u32,
+    /// The number of shots that were run.
+    pub shots_completed: u32,
+    /// Why execution stopped.
+    pub status: ReservationExecutionStatus,
+}
+
+impl ReservationExecution {
+    /// Whether every requested shot was run.
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        matches!(self.status, ReservationExecutionStatus::Completed)
+    }
+
+    /// Combine the results of every job into a single [`RegisterMap`], with the rows of each
+    /// register in the order the shots were run.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the results of any job can't be converted to a [`RegisterMap`], or if
+    /// a register has a different type or width in different jobs.
+    pub fn to_register_map(&self) -> Result<RegisterMap, RegisterMatrixConversionError> {
+        let mut combined = RegisterMap::from_hashmap(HashMap::new());
+        for batch in &self.batches {
+            let register_map = batch.result_data.to_register_map()?;
+            for (register, matrix) in register_map.0 {
+                let merged = match combined.0.remove(&register) {
+                    None => Some(matrix),
+                    Some(existing) => concatenate_rows(&existing, &matrix),
+                };
+                let merged = merged.ok_or_else(|| RegisterMatrixConversionError::InvalidShape {
+                    register: register.clone(),
+                })?;
+                combined.0.insert(register, merged);
+            }
+        }
+        Ok(combined)
+    }
+}
+
+fn concatenate_rows(first: &RegisterMatrix, second: &RegisterMatrix) -> Option<RegisterMatrix> {
+    match (first, second) {
+        (RegisterMatrix::Integer(a), RegisterMatrix::Integer(b)) => {
+            concatenate(Axis(0), &[a.view(), b.view()])
+                .ok()
+                .map(RegisterMatrix::Integer)
+        }
+        (RegisterMatrix::Real(a), RegisterMatrix::Real(b)) => {
+            concatenate(Axis(0), &[a.view(), b.view()])
+                .ok()
+                .map(RegisterMatrix::Real)
+        }
+        (RegisterMatrix::Complex(a), RegisterMatrix::Complex(b)) => {
+            concatenate(Axis(0), &[a.view(), b.view()])
+                .ok()
+                .map(RegisterMatrix::Complex)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod describe_waiting_for_a_reservation {
+    use std::time::Duration;
+
+    use chrono::{DateTime, TimeZone, Utc};
+
+    use super::{next_wait, Error, Reservation, Wait};
+
+    const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+    fn at(seconds: i64) -> DateTime<Utc> {
+        Utc.timestamp_opt(1_700_000_000 + seconds, 0).unwrap()
+    }
+
+    fn reservation(id: i64, start: i64, end: i64) -> Reservation {
+        Reservation {
+            id,
+            quantum_processor_id: String::from("Aspen-M-3"),
+            start_time: at(start),
+            end_time: at(end),
+        }
+    }
+
+    #[test]
+    fn it_is_ready_during_a_reservation() {
+        let reservations = [reservation(1, 100, 200), reservation(2, -100, 50)];
+        let wait = next_wait("Aspen-M-3", &reservations, at(0), None, POLL_INTERVAL).unwrap();
+        assert_eq!(wait, Wait::Ready(reservations[1].clone()));
+    }
+
+    #[test]
+    fn it_polls_until_the_next_reservation_begins() {
+        let reservations = [reservation(1, 600, 900), reservation(2, 30, 60)];
+        let wait = next_wait("Aspen-M-3", &reservations, at(0), None, POLL_INTERVAL).unwrap();
+        assert_eq!(wait, Wait::Sleep(Duration::from_secs(30)));
+
+        let wait = next_wait("Aspen-M-3", &reservations[..1], at(0), None, POLL_INTERVAL).unwrap();
+        assert_eq!(wait, Wait::Sleep(POLL_INTERVAL));
+    }
+
+    #[test]
+    fn it_gives_up_on_reservations_beyond_the_maximum_wait() {
+        let reservations = [reservation(1, 600, 900)];
+        let limit = Some((at(300), Duration::from_secs(300)));
+        let error = next_wait("Aspen-M-3", &reservations, at(0), limit, POLL_INTERVAL).unwrap_err();
+        assert!(matches!(error, Error::WaitExceeded { start_time, .. } if start_time == at(600)));
+    }
+
+    #[test]
+    fn it_fails_without_an_upcoming_reservation() {
+        let reservations = [reservation(1, -600, -300)];
+        let error = next_wait("Aspen-M-3", &reservations, at(0), None, POLL_INTERVAL).unwrap_err();
+        assert!(matches!(error, Error::NoUpcomingReservation(id) if id == "Aspen-M-3"));
+    }
+}
+
+#[cfg(test)]
+mod describe_shot_budget {
+    use std::num::NonZeroU16;
+    use std::time::{Duration, SystemTime};
+
+    use super::ShotBudget;
+
+    fn make_budget(seconds_left: u64, shot_millis: u64) -> (SystemTime, ShotBudget) {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        let budget = ShotBudget::new(
+            now + Duration::from_secs(seconds_left),
+            Duration::from_millis(shot_millis),
+        )
+        .with_job_overhead(Duration::from_secs(10));
+        (now, budget)
+    }
+
+    #[test]
+    fn it_fits_shots_in_the_remaining_time() {
+        let (now, budget) = make_budget(20, 10);
+        // 10 seconds left after overhead at 10ms per shot.
+        assert_eq!(
+            budget.shots_for_next_job(now, 5_000),
+            NonZeroU16::new(1_000)
+        );
+        assert_eq!(budget.shots_for_next_job(now, 300), NonZeroU16::new(300));
+    }
+
+    #[test]
+    fn it_respects_the_maximum_shots_per_job() {
+        let (now, budget) = make_budget(3_600, 1);
+        assert_eq!(
+            budget.shots_for_next_job(now, 1_000_000),
+            Some(NonZeroU16::MAX)
+        );
+        let budget = budget.with_max_shots_per_job(NonZeroU16::new(100).unwrap());
+        assert_eq!(budget.shots_for_next_job(now, 1_000), NonZeroU16::new(100));
+    }
+
+    #[test]
+    fn it_stops_when_time_runs_out() {
+        let (now, budget) = make_budget(5, 10);
+        assert_eq!(budget.shots_for_next_job(now, 100), None);
+        let (now, budget) = make_budget(20, 10);
+        assert_eq!(
+            budget.shots_for_next_job(now + Duration::from_secs(30), 100),
+            None
+        );
+        assert_eq!(budget.shots_for_next_job(now, 0), None);
+    }
+
+    #[test]
+    fn it_only_raises_the_estimate() {
+        let (_, mut budget) = make_budget(20, 10);
+        let shots = NonZeroU16::new(100).unwrap();
+        budget.observe(shots, Duration::from_secs(11));
+        assert_eq!(budget.estimated_shot_duration(), Duration::from_millis(10));
+        budget.observe(shots, Duration::from_secs(12));
+        assert_eq!(budget.estimated_shot_duration(), Duration::from_millis(20));
+    }
+}
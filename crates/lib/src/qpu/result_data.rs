@@ -1,11 +1,23 @@
 //! This modules provides types and functions for initializing and working with
 //! data returned from the QPU
 use enum_as_inner::EnumAsInner;
+use ndarray::ArrayView1;
 use num::complex::Complex64;
-use quil_rs::instruction::MemoryReference;
+use quil_rs::instruction::{MemoryReference, ScalarType};
+use quil_rs::quil::Quil;
+use quil_rs::Program;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::num::NonZeroUsize;
+use std::ops::Range;
+use std::str::FromStr;
 
+use crate::{
+    DType, RegisterData, RegisterMap, RegisterMatrixConversionError, Shape, SparseRegisterMap,
+};
+
+#[cfg(feature = "qpu")]
 use qcs_api_client_grpc::models::controller::{
     self, data_value as controller_memory_value, readout_values as controller_readout_values,
     DataValue as ControllerMemoryValues, ReadoutValues as ControllerReadoutValues,
@@ -23,6 +35,179 @@ pub enum ReadoutValues {
     Complex(Vec<Complex64>),
 }
 
+impl ReadoutValues {
+    /// The number of values. This is the number of shots only if the readout node was measured
+    /// exactly once per shot, since it emits a value every time it is measured.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Integer(values) => values.len(),
+            Self::Real(values) => values.len(),
+            Self::Complex(values) => values.len(),
+        }
+    }
+
+    /// Whether there are no values.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The [`Shape`] of the values: one row per value and a single column.
+    #[must_use]
+    pub fn shape(&self) -> Shape {
+        let dtype = match self {
+            Self::Integer(_) => DType::Int64,
+            Self::Real(_) => DType::Float64,
+            Self::Complex(_) => DType::Complex128,
+        };
+        Shape {
+            rows: self.len(),
+            cols: 1,
+            dtype,
+        }
+    }
+
+    /// Borrow the values as a one-dimensional array, without copying them.
+    #[must_use]
+    pub fn as_array_view(&self) -> ReadoutValuesView<'_> {
+        match self {
+            Self::Integer(values) => {
+                ReadoutValuesView::Integer(ArrayView1::from(values.as_slice()))
+            }
+            Self::Real(values) => ReadoutValuesView::Real(ArrayView1::from(values.as_slice())),
+            Self::Complex(values) => {
+                ReadoutValuesView::Complex(ArrayView1::from(values.as_slice()))
+            }
+        }
+    }
+
+    /// Borrow the values in consecutive chunks of `values_per_chunk` values, in the order they
+    /// were emitted. The last chunk is shorter if the number of values isn't a multiple of
+    /// `values_per_chunk`.
+    pub fn chunks(
+        &self,
+        values_per_chunk: NonZeroUsize,
+    ) -> impl Iterator<Item = ReadoutValuesView<'_>> + '_ {
+        let len = self.len();
+        (0..len)
+            .step_by(values_per_chunk.get())
+            .map(move |start| self.view(start..len.min(start + values_per_chunk.get())))
+    }
+
+    /// Borrow the values in `range` as a one-dimensional array.
+    fn view(&self, range: Range<usize>) -> ReadoutValuesView<'_> {
+        match self {
+            Self::Integer(values) => ReadoutValuesView::Integer(ArrayView1::from(&values[range])),
+            Self::Real(values) => ReadoutValuesView::Real(ArrayView1::from(&values[range])),
+            Self::Complex(values) => ReadoutValuesView::Complex(ArrayView1::from(&values[range])),
+        }
+    }
+
+    /// A copy of the values in `range`.
+    fn copy_range(&self, range: Range<usize>) -> Self {
+        match self {
+            Self::Integer(values) => Self::Integer(values[range].to_vec()),
+            Self::Real(values) => Self::Real(values[range].to_vec()),
+            Self::Complex(values) => Self::Complex(values[range].to_vec()),
+        }
+    }
+
+    /// These values followed by those in `other`, or `None` if they are of different types.
+    fn concatenate(&self, other: &Self) -> Option<Self> {
+        fn join<T: Clone>(first: &[T], second: &[T]) -> Vec<T> {
+            first.iter().chain(second).cloned().collect()
+        }
+        match (self, other) {
+            (Self::Integer(first), Self::Integer(second)) => {
+                Some(Self::Integer(join(first, second)))
+            }
+            (Self::Real(first), Self::Real(second)) => Some(Self::Real(join(first, second))),
+            (Self::Complex(first), Self::Complex(second)) => {
+                Some(Self::Complex(join(first, second)))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// [`ReadoutValues`] borrowed as a one-dimensional array, see [`ReadoutValues::as_array_view`].
+#[derive(Debug, Clone, Copy, EnumAsInner, PartialEq)]
+pub enum ReadoutValuesView<'a> {
+    /// Integer readout values
+    Integer(ArrayView1<'a, i64>),
+    /// Real numbered readout values
+    Real(ArrayView1<'a, f64>),
+    /// Complex readout values
+    Complex(ArrayView1<'a, Complex64>),
+}
+
+/// A run of consecutive values emitted by one readout node, see
+/// [`QpuResultData::into_readout_chunks`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReadoutChunk {
+    /// The readout node (ie. "q0") which emitted the values.
+    pub readout_node: String,
+    /// The memory references (ie. "ro\[0\]") the readout node is mapped to, in index order.
+    pub memory_references: Vec<String>,
+    /// The position of the first of these values among all of the values the node emitted.
+    pub offset: usize,
+    /// The values.
+    pub values: ReadoutValues,
+}
+
+/// An iterator which takes the readout values out of a [`QpuResultData`] in [`ReadoutChunk`]s,
+/// see [`QpuResultData::into_readout_chunks`].
+#[derive(Debug)]
+pub struct ReadoutChunks {
+    values_per_chunk: NonZeroUsize,
+    /// The memory references mapped to each readout node.
+    references: HashMap<String, Vec<String>>,
+    /// The readout nodes which haven't been started yet, with the next one last.
+    remaining: Vec<(String, ReadoutValues)>,
+    /// The readout node being split into chunks, and the position of its next chunk.
+    current: Option<(String, ReadoutValues, usize)>,
+    memory_values: HashMap<String, MemoryValues>,
+}
+
+impl ReadoutChunks {
+    /// Get mapping of a memory region (ie. "ro") to the final contents of that memory region,
+    /// which aren't split into chunks.
+    #[must_use]
+    pub fn memory_values(&self) -> &HashMap<String, MemoryValues> {
+        &self.memory_values
+    }
+}
+
+impl Iterator for ReadoutChunks {
+    type Item = ReadoutChunk;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((readout_node, values, offset)) = &mut self.current {
+                if *offset < values.len() {
+                    let end = values.len().min(*offset + self.values_per_chunk.get());
+                    let chunk = ReadoutChunk {
+                        readout_node: readout_node.clone(),
+                        memory_references: self
+                            .references
+                            .get(readout_node.as_str())
+                            .cloned()
+                            .unwrap_or_default(),
+                        offset: *offset,
+                        values: values.copy_range(*offset..end),
+                    };
+                    *offset = end;
+                    return Some(chunk);
+                }
+            }
+            // Replacing the finished node frees its values.
+            let (readout_node, values) = self.remaining.pop()?;
+            self.current = Some((readout_node, values, 0));
+        }
+    }
+}
+
 /// A row of data containing the contents of each memory region at the end of a job.
 #[derive(Debug, Clone, EnumAsInner, PartialEq, Deserialize, Serialize)]
 pub enum MemoryValues {
@@ -34,6 +219,246 @@ pub enum MemoryValues {
     Real(Vec<f64>),
 }
 
+impl MemoryValues {
+    /// The number of values, which is the declared length of the memory region.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Binary(values) => values.len(),
+            Self::Integer(values) => values.len(),
+            Self::Real(values) => values.len(),
+        }
+    }
+
+    /// Whether there are no values.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The values at `indices`, in that order. Indices past the end of the region are skipped.
+    fn select(&self, indices: &[usize]) -> Self {
+        fn pick<T: Copy>(values: &[T], indices: &[usize]) -> Vec<T> {
+            indices
+                .iter()
+                .filter_map(|&index| values.get(index).copied())
+                .collect()
+        }
+
+        match self {
+            Self::Binary(values) => Self::Binary(pick(values, indices)),
+            Self::Integer(values) => Self::Integer(pick(values, indices)),
+            Self::Real(values) => Self::Real(pick(values, indices)),
+        }
+    }
+
+    /// Decode the values as the type their memory region was declared with.
+    ///
+    /// The QPU returns `BIT` and `OCTET` regions alike as [`MemoryValues::Binary`], and may return
+    /// values of a different type than the region was declared with, such as integers for a
+    /// `REAL` region. Values are converted as long as they can be represented exactly.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryValuesConversionError::Unrepresentable`] if a value can't be represented
+    /// as `data_type`, such as a `BIT` other than 0 or 1, or a fractional `INTEGER`.
+    // Large integers lose precision as `REAL`s, just as they would on the QPU.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn decode(
+        &self,
+        data_type: ScalarType,
+    ) -> Result<TypedMemoryValues, MemoryValuesConversionError> {
+        let unrepresentable = |index| MemoryValuesConversionError::Unrepresentable {
+            index,
+            data_type: data_type.to_quil_or_debug(),
+        };
+        let bytes = |max: i64| -> Result<Vec<u8>, MemoryValuesConversionError> {
+            match self {
+                Self::Binary(values) => values
+                    .iter()
+                    .enumerate()
+                    .map(|(index, &value)| {
+                        if i64::from(value) <= max {
+                            Ok(value)
+                        } else {
+                            Err(unrepresentable(index))
+                        }
+                    })
+                    .collect(),
+                Self::Integer(values) => values
+                    .iter()
+                    .enumerate()
+                    .map(|(index, &value)| {
+                        u8::try_from(value)
+                            .ok()
+                            .filter(|_| value <= max)
+                            .ok_or_else(|| unrepresentable(index))
+                    })
+                    .collect(),
+                Self::Real(values) => values
+                    .iter()
+                    .enumerate()
+                    .map(|(index, &value)| {
+                        whole_number(value)
+                            .and_then(|value| u8::try_from(value).ok().filter(|_| value <= max))
+                            .ok_or_else(|| unrepresentable(index))
+                    })
+                    .collect(),
+            }
+        };
+
+        Ok(match data_type {
+            ScalarType::Bit => TypedMemoryValues::Bit(bytes(1)?),
+            ScalarType::Octet => TypedMemoryValues::Octet(bytes(u8::MAX.into())?),
+            ScalarType::Integer => TypedMemoryValues::Integer(match self {
+                Self::Binary(values) => values.iter().map(|&value| i64::from(value)).collect(),
+                Self::Integer(values) => values.clone(),
+                Self::Real(values) => values
+                    .iter()
+                    .enumerate()
+                    .map(|(index, &value)| {
+                        whole_number(value).ok_or_else(|| unrepresentable(index))
+                    })
+                    .collect::<Result<_, _>>()?,
+            }),
+            ScalarType::Real => TypedMemoryValues::Real(match self {
+                Self::Binary(values) => values.iter().map(|&value| f64::from(value)).collect(),
+                Self::Integer(values) => values.iter().map(|&value| value as f64).collect(),
+                Self::Real(values) => values.clone(),
+            }),
+        })
+    }
+}
+
+/// Returns `value` as an `i64` if it is a whole number within its range.
+#[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+pub(crate) fn whole_number(value: f64) -> Option<i64> {
+    // `i64::MAX` isn't representable as an `f64`, so the upper bound is exclusive.
+    if value.fract().abs() < f64::EPSILON && (i64::MIN as f64..i64::MAX as f64).contains(&value) {
+        Some(value as i64)
+    } else {
+        None
+    }
+}
+
+/// The final contents of a memory region, decoded as the type the region was declared with in
+/// the program, e.g. `DECLARE ro BIT[2]`. See [`MemoryValues::decode`].
+#[derive(Debug, Clone, EnumAsInner, PartialEq)]
+pub enum TypedMemoryValues {
+    /// Values of a region declared as `BIT`, each either 0 or 1.
+    Bit(Vec<u8>),
+    /// Values of a region declared as `OCTET`.
+    Octet(Vec<u8>),
+    /// Values of a region declared as `INTEGER`.
+    Integer(Vec<i64>),
+    /// Values of a region declared as `REAL`.
+    Real(Vec<f64>),
+}
+
+impl TypedMemoryValues {
+    /// The type the memory region was declared with.
+    #[must_use]
+    pub fn data_type(&self) -> ScalarType {
+        match self {
+            Self::Bit(_) => ScalarType::Bit,
+            Self::Octet(_) => ScalarType::Octet,
+            Self::Integer(_) => ScalarType::Integer,
+            Self::Real(_) => ScalarType::Real,
+        }
+    }
+}
+
+/// Errors that can occur when converting [`MemoryValues`] into a [`RegisterData`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[non_exhaustive]
+pub enum MemoryValuesConversionError {
+    /// A value can't be represented as the type its memory region was declared with.
+    #[error("the value at index {index} can't be represented as {data_type}")]
+    Unrepresentable {
+        /// The index of the value within the memory region.
+        index: usize,
+        /// The declared type of the memory region, e.g. `BIT`.
+        data_type: String,
+    },
+}
+
+/// The results of two jobs can't be combined with [`QpuResultData::append_shots`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("the values read out to {memory_reference} can't be combined with those of the other job")]
+#[non_exhaustive]
+pub struct AppendShotsError {
+    /// The memory reference, or the readout name if it isn't mapped to one, which was read out in
+    /// only one of the jobs, or with different types of values.
+    pub memory_reference: String,
+}
+
+/// Convert the final contents of a memory region into a [`RegisterData`] with a single row, as
+/// though the job were a single shot.
+///
+/// `BIT` and `OCTET` values become [`RegisterData::I8`], or [`RegisterData::I16`] if any `OCTET`
+/// is above [`i8::MAX`]. `INTEGER` values become [`RegisterData::I64`], and `REAL` values become
+/// [`RegisterData::F64`].
+impl From<&MemoryValues> for RegisterData {
+    fn from(values: &MemoryValues) -> Self {
+        match values {
+            MemoryValues::Binary(values) => match values
+                .iter()
+                .map(|&value| i8::try_from(value))
+                .collect::<Result<Vec<_>, _>>()
+            {
+                Ok(values) => Self::I8(vec![values]),
+                Err(_) => Self::I16(vec![values.iter().map(|&value| i16::from(value)).collect()]),
+            },
+            MemoryValues::Integer(values) => Self::I64(vec![values.clone()]),
+            MemoryValues::Real(values) => Self::F64(vec![values.clone()]),
+        }
+    }
+}
+
+/// Converts memory values returned from the controller service, skipping any without a value.
+#[cfg(feature = "qpu")]
+pub(crate) fn memory_values_from_controller(
+    memory_values: &HashMap<String, ControllerMemoryValues>,
+) -> HashMap<String, MemoryValues> {
+    memory_values
+        .iter()
+        .filter_map(|(key, memory_values)| {
+            memory_values.value.as_ref().map(|value| {
+                (
+                    key.clone(),
+                    match value {
+                        controller_memory_value::Value::Binary(controller::BinaryDataValue {
+                            data: v,
+                        }) => MemoryValues::Binary(v.clone()),
+                        controller_memory_value::Value::Integer(controller::IntegerDataValue {
+                            data: v,
+                        }) => MemoryValues::Integer(v.clone()),
+                        controller_memory_value::Value::Real(controller::RealDataValue {
+                            data: v,
+                        }) => MemoryValues::Real(v.clone()),
+                    },
+                )
+            })
+        })
+        .collect()
+}
+
+/// The values returned by the QPU for a job, exactly as they were received, without mapping
+/// readout values back to memory references or arranging them into registers.
+///
+/// This is a low-level escape hatch for custom processing. Most users will want
+/// [`ResultData::to_register_map`](crate::ResultData::to_register_map) instead.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RawQpuResult<'a> {
+    /// The readout node (ie. "q0") of the filter pipeline which produced the values read out to
+    /// each memory reference (ie. "ro\[0\]").
+    pub mappings: &'a HashMap<String, String>,
+    /// Every value emitted by each readout node (ie. "q0"), in the order they were emitted.
+    pub readout_values: &'a HashMap<String, ReadoutValues>,
+    /// The final contents of each memory region (ie. "ro").
+    pub memory_values: &'a HashMap<String, MemoryValues>,
+}
+
 /// This struct encapsulates data returned from the QPU after executing a job.
 #[allow(clippy::module_name_repetitions)]
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
@@ -42,6 +467,51 @@ pub struct QpuResultData {
     pub(crate) readout_values: HashMap<String, ReadoutValues>,
     /// The final contents of each memory region, keyed on region name.
     pub(crate) memory_values: HashMap<String, MemoryValues>,
+    /// The type each memory region was declared with in the program, if known.
+    #[serde(
+        default,
+        skip_serializing_if = "HashMap::is_empty",
+        with = "declared_types"
+    )]
+    pub(crate) declared_types: HashMap<String, ScalarType>,
+}
+
+/// Serializes declared types by their Quil names, e.g. `BIT`, since [`ScalarType`] doesn't
+/// implement `serde`'s traits.
+mod declared_types {
+    use std::collections::HashMap;
+
+    use quil_rs::{instruction::ScalarType, quil::Quil};
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(
+        types: &HashMap<String, ScalarType>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(
+            types
+                .iter()
+                .map(|(name, data_type)| (name, data_type.to_quil_or_debug())),
+        )
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<HashMap<String, ScalarType>, D::Error> {
+        HashMap::<String, String>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(name, data_type)| {
+                let data_type = match data_type.as_str() {
+                    "BIT" => ScalarType::Bit,
+                    "OCTET" => ScalarType::Octet,
+                    "INTEGER" => ScalarType::Integer,
+                    "REAL" => ScalarType::Real,
+                    other => return Err(D::Error::custom(format!("unknown Quil type {other}"))),
+                };
+                Ok((name, data_type))
+            })
+            .collect()
+    }
 }
 
 impl QpuResultData {
@@ -57,10 +527,72 @@ impl QpuResultData {
             mappings,
             readout_values,
             memory_values,
+            declared_types: HashMap::new(),
         }
     }
 
+    /// Record the type each memory region is declared with in `program`, which should be the
+    /// program that produced these results.
+    ///
+    /// The declared types are used by [`QpuResultData::typed_memory_values`], and by
+    /// [`ResultData::to_register_map`](crate::ResultData::to_register_map), which builds a
+    /// [`RegisterMatrix::Real`](crate::RegisterMatrix::Real) for a `REAL` register even if the
+    /// QPU read integers out to it. Results returned by
+    /// [`Executable`](crate::Executable) already have them.
+    #[must_use]
+    pub fn with_declarations(mut self, program: &Program) -> Self {
+        self.declared_types = program
+            .memory_regions
+            .iter()
+            .map(|(name, region)| (name.clone(), region.size.data_type))
+            .collect();
+        self
+    }
+
+    /// The type the memory region `name` was declared with, or `None` if it isn't known. See
+    /// [`QpuResultData::with_declarations`].
+    #[must_use]
+    pub fn declared_type(&self, name: &str) -> Option<ScalarType> {
+        self.declared_types.get(name).copied()
+    }
+
+    /// Build a [`RegisterMap`] from these results, with a [`RegisterMatrix`](crate::RegisterMatrix)
+    /// for each register. See [`ResultData::to_register_map`](crate::ResultData::to_register_map).
+    ///
+    /// # Errors
+    ///
+    /// Fails if the values of a register don't form a rectangular matrix, such as when some of its
+    /// indices weren't read out. Use [`QpuResultData::to_sparse_register_map`] for those.
+    pub fn to_register_map(&self) -> Result<RegisterMap, RegisterMatrixConversionError> {
+        RegisterMap::from_qpu_result_data(self)
+    }
+
+    /// As [`QpuResultData::to_register_map`], but the [`RegisterMatrix`](crate::RegisterMatrix)
+    /// of each register takes ownership of the values read out to it instead of copying them,
+    /// roughly halving the memory needed to convert results with many shots.
+    ///
+    /// # Errors
+    ///
+    /// Fails as [`QpuResultData::to_register_map`] does.
+    pub fn into_register_map(self) -> Result<RegisterMap, RegisterMatrixConversionError> {
+        RegisterMap::from_owned_qpu_result_data(self)
+    }
+
+    /// Build a [`SparseRegisterMap`] from these results, with the values read out to each index
+    /// of each register, even if some indices weren't read out. See
+    /// [`ResultData::to_sparse_register_map`](crate::ResultData::to_sparse_register_map).
+    ///
+    /// # Errors
+    ///
+    /// Fails if a memory reference can't be parsed, or if its readout has no values.
+    pub fn to_sparse_register_map(
+        &self,
+    ) -> Result<SparseRegisterMap, RegisterMatrixConversionError> {
+        SparseRegisterMap::from_qpu_result_data(self)
+    }
+
     /// Creates a new [`QpuResultData`] using data returned from controller service.
+    #[cfg(feature = "qpu")]
     pub(crate) fn from_controller_mappings_and_values(
         mappings: &HashMap<String, String>,
         readout_values: &HashMap<String, ControllerReadoutValues>,
@@ -92,27 +624,8 @@ impl QpuResultData {
                     )
                 })
                 .collect(),
-            memory_values: memory_values
-                .iter()
-                .filter_map(|(key, memory_values)| {
-                    memory_values.value.as_ref().map(|value| {
-                        (
-                            key.clone(),
-                            match value {
-                                controller_memory_value::Value::Binary(
-                                    controller::BinaryDataValue { data: v },
-                                ) => MemoryValues::Binary(v.clone()),
-                                controller_memory_value::Value::Integer(
-                                    controller::IntegerDataValue { data: v },
-                                ) => MemoryValues::Integer(v.clone()),
-                                controller_memory_value::Value::Real(
-                                    controller::RealDataValue { data: v },
-                                ) => MemoryValues::Real(v.clone()),
-                            },
-                        )
-                    })
-                })
-                .collect(),
+            memory_values: memory_values_from_controller(memory_values),
+            declared_types: HashMap::new(),
         }
     }
 
@@ -140,9 +653,559 @@ impl QpuResultData {
         &self.readout_values
     }
 
+    /// Get the [`Shape`] of the readout values for each readout name, without copying them.
+    #[must_use]
+    pub fn readout_shapes(&self) -> HashMap<String, Shape> {
+        self.readout_values
+            .iter()
+            .map(|(name, values)| (name.clone(), values.shape()))
+            .collect()
+    }
+
+    /// Borrow each readout node's values in consecutive chunks of `values_per_chunk` values (see
+    /// [`ReadoutValues::chunks`]), paired with the name of the readout node (ie. "q0"), so that
+    /// they can be processed a piece at a time without building a [`RegisterMap`]. Readout nodes
+    /// are visited in order of name.
+    pub fn readout_chunks(
+        &self,
+        values_per_chunk: NonZeroUsize,
+    ) -> impl Iterator<Item = (&str, ReadoutValuesView<'_>)> + '_ {
+        let mut readout_values: Vec<_> = self.readout_values.iter().collect();
+        readout_values.sort_by_key(|(readout_node, _)| *readout_node);
+        readout_values
+            .into_iter()
+            .flat_map(move |(readout_node, values)| {
+                values
+                    .chunks(values_per_chunk)
+                    .map(move |chunk| (readout_node.as_str(), chunk))
+            })
+    }
+
+    /// Take each readout node's values out in consecutive chunks of `values_per_chunk` values, as
+    /// [`QpuResultData::readout_chunks`] does, with the memory references each node is mapped to.
+    ///
+    /// Each chunk is a copy, but a readout node's values are freed as soon as its last chunk has
+    /// been taken, so at most one node's values are held alongside the chunks in use.
+    #[must_use]
+    pub fn into_readout_chunks(self, values_per_chunk: NonZeroUsize) -> ReadoutChunks {
+        let mut references: HashMap<String, Vec<String>> = HashMap::new();
+        for (reference, readout_node) in self.mappings {
+            references.entry(readout_node).or_default().push(reference);
+        }
+        for node_references in references.values_mut() {
+            node_references.sort_by_cached_key(|reference| {
+                MemoryReference::from_str(reference).map_or_else(
+                    |_| (reference.clone(), 0),
+                    |parsed| (parsed.name, parsed.index),
+                )
+            });
+        }
+        let mut remaining: Vec<_> = self.readout_values.into_iter().collect();
+        remaining.sort_by(|(first, _), (second, _)| second.cmp(first));
+        ReadoutChunks {
+            values_per_chunk,
+            references,
+            remaining,
+            current: None,
+            memory_values: self.memory_values,
+        }
+    }
+
+    /// Keep only the values read out to `indices` of the memory region `region`, renumbered so
+    /// that the memory reference `region[i]` is mapped to the values read out to
+    /// `region[indices[i]]`. Memory references to other regions are kept as they are, and readout
+    /// values which are no longer mapped to are dropped.
+    ///
+    /// The final contents of `region` in [`QpuResultData::memory_values`] are renumbered in the
+    /// same way, leaving out any indices past the end of the region.
+    pub fn select_indices(&mut self, region: &str, indices: &[usize]) {
+        let mut mappings = HashMap::with_capacity(self.mappings.len());
+        for (reference, alias) in self.mappings.drain() {
+            match MemoryReference::from_str(&reference) {
+                Ok(parsed) if parsed.name == region => {
+                    let index = usize::try_from(parsed.index).ok();
+                    for (position, _) in indices
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, selected)| Some(**selected) == index)
+                    {
+                        mappings.insert(format!("{region}[{position}]"), alias.clone());
+                    }
+                }
+                _ => {
+                    mappings.insert(reference, alias);
+                }
+            }
+        }
+        let mapped: HashSet<&String> = mappings.values().collect();
+        self.readout_values
+            .retain(|alias, _| mapped.contains(alias));
+        self.mappings = mappings;
+        if let Some(values) = self.memory_values.get_mut(region) {
+            *values = values.select(indices);
+        }
+    }
+
     /// Get mapping of a memory region (ie. "ro") to the final contents of that memory region.
     #[must_use]
     pub fn memory_values(&self) -> &HashMap<String, MemoryValues> {
         &self.memory_values
     }
+
+    /// Get the final contents of the memory region `name`, or `None` if the QPU didn't return it.
+    ///
+    /// This includes regions which are never read out, such as the results of classical
+    /// arithmetic. Use [`RegisterData::from`] to convert the values into a [`RegisterData`].
+    #[must_use]
+    pub fn memory_region(&self, name: &str) -> Option<&MemoryValues> {
+        self.memory_values.get(name)
+    }
+
+    /// Get the final contents of each memory region whose declared type is known, decoded as that
+    /// type. See [`MemoryValues::decode`] and [`QpuResultData::with_declarations`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any region holds a value which can't be represented as its declared
+    /// type.
+    pub fn typed_memory_values(
+        &self,
+    ) -> Result<HashMap<String, TypedMemoryValues>, MemoryValuesConversionError> {
+        self.memory_values
+            .iter()
+            .filter_map(|(name, values)| {
+                self.declared_type(name)
+                    .map(|data_type| Ok((name.clone(), values.decode(data_type)?)))
+            })
+            .collect()
+    }
+
+    /// Get the readout and memory values returned by the QPU, without any conversion.
+    #[must_use]
+    pub fn as_raw(&self) -> RawQpuResult<'_> {
+        RawQpuResult {
+            mappings: &self.mappings,
+            readout_values: &self.readout_values,
+            memory_values: &self.memory_values,
+        }
+    }
+
+    /// Add the shots of `other`, the results of another job running the same program, after the
+    /// shots of these results.
+    ///
+    /// Readout values are matched up by the memory reference they were mapped to in each job, so
+    /// the jobs may have been translated separately. The memory values of `other` replace these
+    /// ones, since they are the contents of memory at the end of the later job.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`AppendShotsError`], leaving these results unchanged, if a memory reference
+    /// was read out in only one of the jobs, or with different types of values.
+    pub fn append_shots(&mut self, other: &Self) -> Result<(), AppendShotsError> {
+        let references: HashMap<&str, &str> = self
+            .mappings
+            .iter()
+            .map(|(reference, readout)| (readout.as_str(), reference.as_str()))
+            .collect();
+
+        let mut readout_values = HashMap::with_capacity(self.readout_values.len());
+        let mut appended_readouts = HashSet::with_capacity(other.readout_values.len());
+        for (readout, values) in &self.readout_values {
+            let reference = references.get(readout.as_str()).copied();
+            let other_readout = match reference {
+                Some(reference) => other.mappings.get(reference).map(String::as_str),
+                None => Some(readout.as_str()),
+            };
+            let appended = other_readout
+                .and_then(|other_readout| {
+                    appended_readouts.insert(other_readout);
+                    other.readout_values.get(other_readout)
+                })
+                .and_then(|other_values| values.concatenate(other_values))
+                .ok_or_else(|| AppendShotsError {
+                    memory_reference: reference.unwrap_or(readout).to_string(),
+                })?;
+            readout_values.insert(readout.clone(), appended);
+        }
+
+        if let Some(readout) = other
+            .readout_values
+            .keys()
+            .find(|readout| !appended_readouts.contains(readout.as_str()))
+        {
+            let memory_reference = other
+                .mappings
+                .iter()
+                .find(|(_, mapped)| *mapped == readout)
+                .map_or(readout, |(reference, _)| reference);
+            return Err(AppendShotsError {
+                memory_reference: memory_reference.clone(),
+            });
+        }
+
+        self.readout_values = readout_values;
+        self.memory_values.clone_from(&other.memory_values);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod describe_readout_shapes {
+    use std::num::NonZeroUsize;
+
+    use maplit::hashmap;
+
+    use super::{QpuResultData, ReadoutChunk, ReadoutValues};
+    use crate::{DType, Shape};
+
+    fn result_data() -> QpuResultData {
+        QpuResultData::from_mappings_and_values(
+            hashmap! {
+                "ro[10]".to_string() => "q10".to_string(),
+                "ro[2]".to_string() => "q2".to_string(),
+                "ro[0]".to_string() => "q0".to_string(),
+            },
+            hashmap! {
+                "q0".to_string() => ReadoutValues::Integer(vec![0, 1, 0]),
+                "q2".to_string() => ReadoutValues::Integer(vec![1, 1, 0]),
+                "q10".to_string() => ReadoutValues::Integer(vec![1, 0]),
+            },
+            hashmap! {},
+        )
+    }
+
+    #[test]
+    fn it_reports_the_shape_of_each_readout() {
+        let shapes = result_data().readout_shapes();
+        assert_eq!(shapes.len(), 3);
+        assert_eq!(
+            shapes["q0"],
+            Shape {
+                rows: 3,
+                cols: 1,
+                dtype: DType::Int64
+            }
+        );
+        assert_eq!(shapes["q10"].rows, 2);
+    }
+
+    #[test]
+    fn it_borrows_readout_values_in_chunks() {
+        let data = result_data();
+        let chunks: Vec<(&str, Vec<i64>)> = data
+            .readout_chunks(NonZeroUsize::new(2).unwrap())
+            .map(|(readout_node, chunk)| (readout_node, chunk.into_integer().unwrap().to_vec()))
+            .collect();
+        assert_eq!(
+            chunks,
+            vec![
+                ("q0", vec![0, 1]),
+                ("q0", vec![0]),
+                ("q10", vec![1, 0]),
+                ("q2", vec![1, 1]),
+                ("q2", vec![0]),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_takes_readout_values_out_in_chunks() {
+        let mut data = result_data();
+        data.mappings.insert("ro[1]".to_string(), "q0".to_string());
+        let chunks: Vec<ReadoutChunk> = data
+            .into_readout_chunks(NonZeroUsize::new(2).unwrap())
+            .collect();
+        assert_eq!(chunks.len(), 5);
+        assert_eq!(
+            chunks[0],
+            ReadoutChunk {
+                readout_node: "q0".to_string(),
+                memory_references: vec!["ro[0]".to_string(), "ro[1]".to_string()],
+                offset: 0,
+                values: ReadoutValues::Integer(vec![0, 1]),
+            }
+        );
+        assert_eq!(chunks[1].offset, 2);
+        assert_eq!(chunks[1].values, ReadoutValues::Integer(vec![0]));
+        assert_eq!(chunks[2].memory_references, vec!["ro[10]".to_string()]);
+    }
+}
+
+#[cfg(test)]
+mod describe_select_indices {
+    use maplit::hashmap;
+
+    use super::{MemoryValues, QpuResultData, ReadoutValues};
+
+    #[test]
+    fn it_renumbers_the_selected_indices() {
+        let mut data = QpuResultData::from_mappings_and_values(
+            hashmap! {
+                "ro[0]".to_string() => "q0".to_string(),
+                "ro[1]".to_string() => "q1".to_string(),
+                "ro[2]".to_string() => "q2".to_string(),
+                "other[0]".to_string() => "q3".to_string(),
+            },
+            hashmap! {
+                "q0".to_string() => ReadoutValues::Integer(vec![0]),
+                "q1".to_string() => ReadoutValues::Integer(vec![1]),
+                "q2".to_string() => ReadoutValues::Integer(vec![2]),
+                "q3".to_string() => ReadoutValues::Integer(vec![3]),
+            },
+            hashmap! {
+                "ro".to_string() => MemoryValues::Binary(vec![0, 1, 1]),
+                "other".to_string() => MemoryValues::Integer(vec![7]),
+            },
+        );
+        data.select_indices("ro", &[2, 0]);
+
+        assert_eq!(
+            data.mappings(),
+            &hashmap! {
+                "ro[0]".to_string() => "q2".to_string(),
+                "ro[1]".to_string() => "q0".to_string(),
+                "other[0]".to_string() => "q3".to_string(),
+            }
+        );
+        assert_eq!(data.readout_values().len(), 3);
+        assert!(!data.readout_values().contains_key("q1"));
+        assert_eq!(
+            data.memory_region("ro"),
+            Some(&MemoryValues::Binary(vec![1, 0]))
+        );
+        assert_eq!(
+            data.memory_region("other"),
+            Some(&MemoryValues::Integer(vec![7]))
+        );
+    }
+}
+
+#[cfg(test)]
+mod describe_append_shots {
+    use maplit::hashmap;
+
+    use super::{AppendShotsError, MemoryValues, QpuResultData, ReadoutValues};
+
+    #[test]
+    fn it_matches_values_by_memory_reference() {
+        let mut data = QpuResultData::from_mappings_and_values(
+            hashmap! {
+                "ro[0]".to_string() => "q0".to_string(),
+                "ro[1]".to_string() => "q1".to_string(),
+            },
+            hashmap! {
+                "q0".to_string() => ReadoutValues::Integer(vec![0, 1]),
+                "q1".to_string() => ReadoutValues::Integer(vec![1, 1]),
+            },
+            hashmap! {"theta".to_string() => MemoryValues::Real(vec![0.5])},
+        );
+        let later = QpuResultData::from_mappings_and_values(
+            hashmap! {
+                "ro[0]".to_string() => "q1".to_string(),
+                "ro[1]".to_string() => "q0".to_string(),
+            },
+            hashmap! {
+                "q1".to_string() => ReadoutValues::Integer(vec![0]),
+                "q0".to_string() => ReadoutValues::Integer(vec![1]),
+            },
+            hashmap! {"theta".to_string() => MemoryValues::Real(vec![1.5])},
+        );
+
+        data.append_shots(&later).unwrap();
+
+        assert_eq!(
+            data.readout_values(),
+            &hashmap! {
+                "q0".to_string() => ReadoutValues::Integer(vec![0, 1, 0]),
+                "q1".to_string() => ReadoutValues::Integer(vec![1, 1, 1]),
+            }
+        );
+        assert_eq!(
+            data.memory_region("theta"),
+            Some(&MemoryValues::Real(vec![1.5]))
+        );
+    }
+
+    #[test]
+    fn it_refuses_values_of_a_different_type() {
+        let mut data = QpuResultData::from_mappings_and_values(
+            hashmap! {"ro[0]".to_string() => "q0".to_string()},
+            hashmap! {"q0".to_string() => ReadoutValues::Integer(vec![0])},
+            hashmap! {},
+        );
+        let original = data.clone();
+        let later = QpuResultData::from_mappings_and_values(
+            hashmap! {"ro[0]".to_string() => "q0".to_string()},
+            hashmap! {"q0".to_string() => ReadoutValues::Real(vec![0.5])},
+            hashmap! {},
+        );
+
+        assert_eq!(
+            data.append_shots(&later),
+            Err(AppendShotsError {
+                memory_reference: "ro[0]".to_string()
+            })
+        );
+        assert_eq!(data, original);
+    }
+
+    #[test]
+    fn it_refuses_values_read_out_only_in_the_later_job() {
+        let mut data = QpuResultData::from_mappings_and_values(
+            hashmap! {"ro[0]".to_string() => "q0".to_string()},
+            hashmap! {"q0".to_string() => ReadoutValues::Integer(vec![0])},
+            hashmap! {},
+        );
+        let original = data.clone();
+        let later = QpuResultData::from_mappings_and_values(
+            hashmap! {
+                "ro[0]".to_string() => "q0".to_string(),
+                "ro[1]".to_string() => "q1".to_string(),
+            },
+            hashmap! {
+                "q0".to_string() => ReadoutValues::Integer(vec![1]),
+                "q1".to_string() => ReadoutValues::Integer(vec![1]),
+            },
+            hashmap! {},
+        );
+
+        assert_eq!(
+            data.append_shots(&later),
+            Err(AppendShotsError {
+                memory_reference: "ro[1]".to_string()
+            })
+        );
+        assert_eq!(data, original);
+    }
+}
+
+#[cfg(test)]
+mod describe_memory_region {
+    use std::collections::HashMap;
+
+    use super::{MemoryValues, QpuResultData};
+    use crate::{ExecutionData, RegisterData, ResultData};
+
+    fn data() -> QpuResultData {
+        QpuResultData::from_mappings_and_values(
+            HashMap::new(),
+            HashMap::new(),
+            HashMap::from([
+                ("flags".to_string(), MemoryValues::Binary(vec![0, 1])),
+                ("count".to_string(), MemoryValues::Integer(vec![42])),
+                ("theta".to_string(), MemoryValues::Real(vec![0.5, 1.5])),
+            ]),
+        )
+    }
+
+    #[test]
+    fn it_reads_regions_which_are_not_read_out() {
+        let data = data();
+        assert_eq!(
+            data.memory_region("count"),
+            Some(&MemoryValues::Integer(vec![42]))
+        );
+        assert_eq!(data.memory_region("missing"), None);
+
+        let execution_data = ExecutionData {
+            result_data: ResultData::Qpu(data),
+            duration: None,
+            timings: None,
+        };
+        assert_eq!(
+            execution_data.memory_region("theta").map(MemoryValues::len),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn it_converts_to_a_single_row_of_register_data() {
+        let data = data();
+        let register = |name| RegisterData::from(data.memory_region(name).unwrap());
+        assert_eq!(register("flags"), RegisterData::I8(vec![vec![0, 1]]));
+        assert_eq!(register("count"), RegisterData::I64(vec![vec![42]]));
+        assert_eq!(register("theta"), RegisterData::F64(vec![vec![0.5, 1.5]]));
+
+        assert_eq!(
+            RegisterData::from(&MemoryValues::Binary(vec![1, 255])),
+            RegisterData::I16(vec![vec![1, 255]])
+        );
+        assert_eq!(
+            RegisterData::from(&MemoryValues::Integer(vec![0, 1 << 40])),
+            RegisterData::I64(vec![vec![0, 1 << 40]])
+        );
+    }
+}
+
+#[cfg(test)]
+mod describe_typed_memory_values {
+    use std::collections::HashMap;
+    use std::str::FromStr;
+
+    use quil_rs::{instruction::ScalarType, Program};
+
+    use super::{MemoryValues, MemoryValuesConversionError, QpuResultData, TypedMemoryValues};
+
+    fn data() -> QpuResultData {
+        let program = Program::from_str(
+            "DECLARE flags BIT[2]\nDECLARE bytes OCTET[2]\nDECLARE count INTEGER\n\
+             DECLARE theta REAL[2]",
+        )
+        .unwrap();
+        QpuResultData::from_mappings_and_values(
+            HashMap::new(),
+            HashMap::new(),
+            HashMap::from([
+                ("flags".to_string(), MemoryValues::Binary(vec![0, 1])),
+                ("bytes".to_string(), MemoryValues::Binary(vec![7, 255])),
+                ("count".to_string(), MemoryValues::Integer(vec![42])),
+                ("theta".to_string(), MemoryValues::Integer(vec![1, 2])),
+                ("undeclared".to_string(), MemoryValues::Real(vec![0.5])),
+            ]),
+        )
+        .with_declarations(&program)
+    }
+
+    #[test]
+    fn it_decodes_values_as_their_declared_types() {
+        let typed = data().typed_memory_values().unwrap();
+        assert_eq!(typed.len(), 4);
+        assert_eq!(typed["flags"], TypedMemoryValues::Bit(vec![0, 1]));
+        assert_eq!(typed["bytes"], TypedMemoryValues::Octet(vec![7, 255]));
+        assert_eq!(typed["count"], TypedMemoryValues::Integer(vec![42]));
+        assert_eq!(typed["theta"], TypedMemoryValues::Real(vec![1.0, 2.0]));
+        assert_eq!(typed["theta"].data_type(), ScalarType::Real);
+    }
+
+    #[test]
+    fn it_rejects_values_which_do_not_fit_the_declared_type() {
+        assert_eq!(
+            MemoryValues::Binary(vec![0, 2]).decode(ScalarType::Bit),
+            Err(MemoryValuesConversionError::Unrepresentable {
+                index: 1,
+                data_type: "BIT".to_string()
+            })
+        );
+        assert!(MemoryValues::Integer(vec![256])
+            .decode(ScalarType::Octet)
+            .is_err());
+        assert!(MemoryValues::Real(vec![1.0, 0.5])
+            .decode(ScalarType::Integer)
+            .is_err());
+        assert_eq!(
+            MemoryValues::Real(vec![3.0]).decode(ScalarType::Integer),
+            Ok(TypedMemoryValues::Integer(vec![3]))
+        );
+    }
+
+    #[test]
+    fn it_round_trips_declared_types_through_serde() {
+        let data = data();
+        let json = serde_json::to_value(&data).unwrap();
+        assert_eq!(json["declared_types"]["flags"], "BIT");
+        let restored: QpuResultData = serde_json::from_value(json).unwrap();
+        assert_eq!(restored, data);
+        assert_eq!(restored.declared_type("count"), Some(ScalarType::Integer));
+        assert_eq!(restored.declared_type("undeclared"), None);
+    }
 }
@@ -0,0 +1,345 @@
+//! Retrying QPU API requests which fail for reasons that may be temporary, such as a briefly
+//! unavailable service.
+//!
+//! Every gRPC connection made by this library is already wrapped in the retry layer of
+//! [`wrap_channel_with_retry`](qcs_api_client_grpc::tonic::wrap_channel_with_retry), which retries
+//! requests that never reached the service, e.g. because the connection failed. A [`RetryPolicy`]
+//! builds on that layer: it only retries requests which the service itself rejected with a gRPC
+//! status worth retrying, so that a failure is never retried by both.
+
+use std::{error::Error as _, future::Future, time::Duration};
+
+use derive_builder::Builder;
+use serde::{Deserialize, Serialize};
+use tonic::{Code, Status};
+
+use crate::random::random_fraction;
+
+/// The QPU API requests which can be given their own [`RetryPolicy`] in
+/// [`ExecutionOptions`](super::api::ExecutionOptions).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum QpuApiCall {
+    /// Submitting jobs, see [`submit`](super::api::submit).
+    Submit,
+    /// Cancelling jobs, see [`cancel_jobs`](super::api::cancel_jobs).
+    Cancel,
+    /// Retrieving a job's results, see [`retrieve_results`](super::api::retrieve_results).
+    RetrieveResults,
+    /// Polling a job's status, see [`get_job_status`](super::api::get_job_status). This always
+    /// uses the general [`ExecutionOptions::retry_policy`](super::api::ExecutionOptions::retry_policy).
+    GetJobStatus,
+}
+
+/// How to retry a QPU API request which the service rejects with a gRPC status that may be
+/// temporary. Failures to reach the service are retried by the connection instead, see the
+/// [module documentation](self).
+///
+/// The `n`th retry waits `initial_delay * backoff_factor^(n - 1)`, up to `max_delay`. With
+/// `jitter` enabled, each delay is instead chosen at random between half of that and all of it,
+/// so that clients which failed at the same time don't all retry at the same time.
+///
+/// Use [`Default`] to get a reasonable policy, or start with [`RetryPolicyBuilder`] to build a
+/// custom one.
+#[derive(Builder, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    #[doc = "The number of times to retry a failed request. Zero disables retries."]
+    #[builder(default = "3")]
+    max_retries: u32,
+    #[doc = "The delay before the first retry."]
+    #[builder(default = "Duration::from_millis(500)")]
+    initial_delay: Duration,
+    #[doc = "The factor by which the delay grows after each retry."]
+    #[builder(default = "2")]
+    backoff_factor: u32,
+    #[doc = "The longest delay between two attempts, however many retries there have been."]
+    #[builder(default = "Duration::from_secs(10)")]
+    max_delay: Duration,
+    #[doc = "Whether to randomize delays."]
+    #[builder(default = "true")]
+    jitter: bool,
+    #[doc = "The gRPC status codes which are worth retrying."]
+    #[builder(default = "vec![Code::Unavailable, Code::DeadlineExceeded, Code::Aborted]")]
+    #[serde(with = "serde_codes")]
+    retryable_codes: Vec<Code>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicyBuilder::default()
+            .build()
+            .expect("Should be able to derive a default RetryPolicy from the builder.")
+    }
+}
+
+impl RetryPolicy {
+    /// Get a [`RetryPolicyBuilder`] that can be used to build a custom [`RetryPolicy`].
+    #[must_use]
+    pub fn builder() -> RetryPolicyBuilder {
+        RetryPolicyBuilder::default()
+    }
+
+    /// A policy which never retries.
+    #[must_use]
+    pub fn never() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// The default policy for submitting jobs. It only retries requests which the service was
+    /// unavailable to receive: a submission which timed out or was aborted may still have queued
+    /// the job, and retrying it could run the job twice.
+    #[must_use]
+    pub fn for_submission() -> Self {
+        Self {
+            retryable_codes: vec![Code::Unavailable],
+            ..Self::default()
+        }
+    }
+
+    /// Get the number of times to retry a failed request.
+    #[must_use]
+    pub fn max_retries(&self) -> u32 {
+        self.max_retries
+    }
+
+    /// Get this policy, but retrying a failed request at most `max_retries` times.
+    #[must_use]
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Get the delay before the first retry.
+    #[must_use]
+    pub fn initial_delay(&self) -> Duration {
+        self.initial_delay
+    }
+
+    /// Get the factor by which the delay grows after each retry.
+    #[must_use]
+    pub fn backoff_factor(&self) -> u32 {
+        self.backoff_factor
+    }
+
+    /// Get the longest delay between two attempts.
+    #[must_use]
+    pub fn max_delay(&self) -> Duration {
+        self.max_delay
+    }
+
+    /// Get whether delays are randomized.
+    #[must_use]
+    pub fn jitter(&self) -> bool {
+        self.jitter
+    }
+
+    /// Get the gRPC status codes which are worth retrying.
+    #[must_use]
+    pub fn retryable_codes(&self) -> &[Code] {
+        &self.retryable_codes
+    }
+
+    /// Whether a request which failed with `status` should be retried.
+    ///
+    /// A status made by the client from a transport error, rather than returned by the service,
+    /// is never retried: the connection's retry layer has already retried it.
+    #[must_use]
+    pub fn is_retryable(&self, status: &Status) -> bool {
+        status.source().is_none() && self.retryable_codes.contains(&status.code())
+    }
+
+    /// The delay before a retry, given the number of retries before it, without jitter.
+    #[must_use]
+    pub fn backoff(&self, retries: u32) -> Duration {
+        self.initial_delay
+            .saturating_mul(self.backoff_factor.saturating_pow(retries))
+            .min(self.max_delay)
+    }
+
+    /// The delay before a retry, given the number of retries before it.
+    fn delay(&self, retries: u32) -> Duration {
+        let backoff = self.backoff(retries);
+        if self.jitter {
+            backoff / 2 + (backoff / 2).mul_f64(random_fraction())
+        } else {
+            backoff
+        }
+    }
+
+    /// Send a request, sending it again while it fails with a retryable status and retries remain.
+    pub(crate) async fn retry<T, F, Fut>(&self, mut request: F) -> Result<T, Status>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, Status>>,
+    {
+        let mut retries = 0;
+        loop {
+            match request().await {
+                Err(status) if retries < self.max_retries && self.is_retryable(&status) => {
+                    let delay = self.delay(retries);
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(
+                        retry = retries + 1,
+                        ?delay,
+                        %status,
+                        "QPU API request failed, retrying",
+                    );
+                    tokio::time::sleep(delay).await;
+                    retries += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// (De)serializes gRPC status codes as their numeric values.
+mod serde_codes {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use tonic::Code;
+
+    #[allow(clippy::ptr_arg)]
+    pub(super) fn serialize<S: Serializer>(
+        codes: &Vec<Code>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        codes
+            .iter()
+            .map(|code| *code as i32)
+            .collect::<Vec<_>>()
+            .serialize(serializer)
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Code>, D::Error> {
+        Ok(Vec::<i32>::deserialize(deserializer)?
+            .into_iter()
+            .map(Code::from_i32)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod describe_retry_policy {
+    use std::{
+        sync::atomic::{AtomicU32, Ordering},
+        time::Duration,
+    };
+
+    use tonic::{Code, Status};
+
+    use super::RetryPolicy;
+
+    fn immediate(max_retries: u32) -> RetryPolicy {
+        RetryPolicy::builder()
+            .max_retries(max_retries)
+            .initial_delay(Duration::ZERO)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn it_retries_temporary_failures_by_default() {
+        let policy = RetryPolicy::default();
+        assert!(policy.is_retryable(&Status::unavailable("connection reset")));
+        assert!(policy.is_retryable(&Status::deadline_exceeded("timed out")));
+        assert!(!policy.is_retryable(&Status::not_found("no such job")));
+        assert!(!policy.is_retryable(&Status::permission_denied("no access")));
+
+        let submission = RetryPolicy::for_submission();
+        assert!(submission.is_retryable(&Status::unavailable("connection reset")));
+        assert!(!submission.is_retryable(&Status::deadline_exceeded("timed out")));
+    }
+
+    #[test]
+    fn it_leaves_transport_errors_to_the_connection() {
+        let error = std::io::Error::new(std::io::ErrorKind::ConnectionReset, "connection reset");
+        let status = Status::from_error(Box::new(error));
+        let policy = RetryPolicy::builder()
+            .retryable_codes(vec![status.code()])
+            .build()
+            .unwrap();
+        assert!(!policy.is_retryable(&status));
+        assert!(policy.is_retryable(&Status::new(status.code(), "connection reset")));
+    }
+
+    #[test]
+    fn it_backs_off_exponentially_up_to_the_max_delay() {
+        let policy = RetryPolicy::builder()
+            .max_delay(Duration::MAX)
+            .build()
+            .unwrap();
+        assert_eq!(policy.backoff(0), Duration::from_millis(500));
+        assert_eq!(policy.backoff(1), Duration::from_secs(1));
+        assert_eq!(policy.backoff(3), Duration::from_secs(4));
+        assert_eq!(policy.backoff(u32::MAX), Duration::MAX);
+
+        assert_eq!(RetryPolicy::default().backoff(10), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn it_jitters_within_half_of_the_backoff() {
+        let policy = RetryPolicy::default();
+        for retries in 0..5 {
+            let backoff = policy.backoff(retries);
+            let delay = policy.delay(retries);
+            assert!(
+                delay >= backoff / 2 && delay <= backoff,
+                "{delay:?} for {backoff:?}"
+            );
+        }
+
+        let policy = RetryPolicy::builder().jitter(false).build().unwrap();
+        assert_eq!(policy.delay(2), policy.backoff(2));
+    }
+
+    #[tokio::test]
+    async fn it_retries_until_success_or_out_of_retries() {
+        let attempts = AtomicU32::new(0);
+        let result = immediate(3)
+            .retry(|| async {
+                match attempts.fetch_add(1, Ordering::SeqCst) {
+                    0 | 1 => Err(Status::unavailable("try again")),
+                    _ => Ok("results"),
+                }
+            })
+            .await;
+        assert_eq!(result.unwrap(), "results");
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+
+        let attempts = AtomicU32::new(0);
+        let result: Result<(), _> = immediate(2)
+            .retry(|| async {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err(Status::unavailable("still down"))
+            })
+            .await;
+        assert_eq!(result.unwrap_err().code(), Code::Unavailable);
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn it_does_not_retry_permanent_failures() {
+        let attempts = AtomicU32::new(0);
+        let result: Result<(), _> = immediate(3)
+            .retry(|| async {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err(Status::not_found("no such job"))
+            })
+            .await;
+        assert_eq!(result.unwrap_err().code(), Code::NotFound);
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn it_round_trips_through_json() {
+        let policy = RetryPolicy::for_submission();
+        let json = serde_json::to_string(&policy).unwrap();
+        assert_eq!(serde_json::from_str::<RetryPolicy>(&json).unwrap(), policy);
+    }
+}
@@ -1,25 +1,53 @@
 //! This module provides bindings to translate programs or fetching Quil-T calibrations
 //! from the QCS API.
 
-use std::{collections::HashMap, time::Duration};
+use std::{
+    cell::Cell,
+    collections::HashMap,
+    str::FromStr,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
 
+use lazy_static::lazy_static;
 use qcs_api_client_grpc::{
     models::controller::EncryptedControllerJob,
     services::translation::{
         translate_quil_to_encrypted_controller_job_request::NumShots,
-        translation_options::{self, TranslationBackend},
-        BackendV1Options, BackendV2Options, GetQuantumProcessorQuilCalibrationProgramRequest,
-        TranslateQuilToEncryptedControllerJobRequest, TranslationOptions as ApiTranslationOptions,
+        translation_client::TranslationClient, translation_options,
+        TranslationOptions as ApiTranslationOptions,
     },
 };
+use qcs_api_client_openapi::models::InstructionSetArchitecture;
+use quil_rs::{
+    instruction::ScalarType,
+    program::{Program, ProgramError},
+};
+use serde::{
+    de::{DeserializeOwned, Visitor},
+    Deserializer, Serialize,
+};
 use tokio::time::error::Elapsed;
 #[cfg(feature = "tracing")]
 use tracing::instrument;
 
-use crate::client::{GrpcClientError, Qcs, DEFAULT_HTTP_API_TIMEOUT};
+use crate::client::{GrpcClientError, GrpcConnection, Qcs, DEFAULT_HTTP_API_TIMEOUT};
+use crate::metrics::{self, Cache};
+
+/// The request and response types of the translation service, for use with [`client`].
+pub use qcs_api_client_grpc::services::translation::{
+    BackendV1Options, BackendV2Options, GetQuantumProcessorQuilCalibrationProgramRequest,
+    QuantumProcessorQuilCalibrationProgram, TranslateQuilToEncryptedControllerJobRequest,
+    TranslateQuilToEncryptedControllerJobResponse,
+};
+
+/// The translation backends available on QCS, each with its own options. See
+/// [`TranslationOptions::with_backend`].
+pub use qcs_api_client_grpc::services::translation::translation_options::TranslationBackend;
 
 /// Errors that can occur when making a request to translation service.
 #[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
 pub enum Error {
     /// Error due to gRPC client
     #[error(transparent)]
@@ -27,11 +55,35 @@ pub enum Error {
     /// Error due to client timeout
     #[error("Client configured timeout exceeded")]
     ClientTimeout(#[from] Elapsed),
+    /// The Quil-T calibration program of the quantum processor could not be parsed.
+    #[error("Could not parse the calibration program of the quantum processor: {0}")]
+    CalibrationProgram(#[source] ProgramError),
+}
+
+impl Error {
+    /// Whether a request failed because it couldn't be authenticated, even after refreshing the
+    /// access token.
+    #[must_use]
+    pub fn is_unauthenticated(&self) -> bool {
+        match self {
+            Self::Grpc(error) => error.is_unauthenticated(),
+            Self::ClientTimeout(_) | Self::CalibrationProgram(_) => false,
+        }
+    }
+}
+
+/// How long [`get_calibration_program`] reuses the calibrations it fetched for a quantum processor
+/// before fetching them again.
+pub const DEFAULT_CALIBRATION_CACHE_TTL: Duration = Duration::from_secs(5 * 60);
+
+lazy_static! {
+    static ref CALIBRATION_CACHE: Mutex<HashMap<String, (Instant, Program)>> =
+        Mutex::new(HashMap::new());
 }
 
 /// An encrypted and translated program, along with `readout_map`
 /// to map job `readout_data` back to program-declared variables.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct EncryptedTranslationResult {
     /// The encrypted, translated program.
     pub job: EncryptedControllerJob,
@@ -43,8 +95,78 @@ pub struct EncryptedTranslationResult {
     pub readout_map: HashMap<String, String>,
 }
 
+/// The type and size of a memory region declared by a program.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemoryDescriptor {
+    /// The type of each element of the region.
+    pub data_type: ScalarType,
+    /// The number of elements in the region.
+    pub length: u64,
+}
+
+/// The [`MemoryDescriptor`] of each memory region declared by `program`, by name.
+pub(crate) fn memory_descriptors(program: &Program) -> HashMap<String, MemoryDescriptor> {
+    program
+        .memory_regions
+        .iter()
+        .map(|(name, region)| {
+            (
+                name.clone(),
+                MemoryDescriptor {
+                    data_type: region.size.data_type,
+                    length: region.size.length,
+                },
+            )
+        })
+        .collect()
+}
+
+/// Identifies the calibration state of a quantum processor. When a QPU is recalibrated its
+/// settings change, and any program translated before then must be translated again.
+///
+/// This is the most recent characteristic timestamp in the QPU's
+/// [`InstructionSetArchitecture`], which changes whenever the QPU is recalibrated.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SettingsEpoch(String);
+
+impl SettingsEpoch {
+    /// Determine the settings epoch of a QPU from its [`InstructionSetArchitecture`].
+    ///
+    /// Returns `None` if the ISA has no characteristics, in which case the epoch is unknown.
+    #[must_use]
+    pub fn from_isa(isa: &InstructionSetArchitecture) -> Option<Self> {
+        isa.instructions
+            .iter()
+            .chain(isa.benchmarks.iter())
+            .flat_map(|operation| {
+                operation.characteristics.iter().chain(
+                    operation
+                        .sites
+                        .iter()
+                        .flat_map(|site| site.characteristics.iter()),
+                )
+            })
+            .map(|characteristic| characteristic.timestamp.as_str())
+            .max()
+            .map(|timestamp| Self(timestamp.to_string()))
+    }
+
+    /// The timestamp identifying this epoch.
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
 /// Translate a program, returning an encrypted and translated program.
-#[cfg_attr(feature = "tracing", instrument(skip_all))]
+#[cfg_attr(
+    feature = "tracing",
+    instrument(
+        name = "translation.translate",
+        skip_all,
+        fields(quantum_processor_id = quantum_processor_id, num_shots = num_shots)
+    )
+)]
 pub async fn translate<TO>(
     quantum_processor_id: &str,
     quil_program: &str,
@@ -71,22 +193,20 @@ where
         options,
     };
 
-    let response = client
-        .get_translation_client()
-        .map_err(GrpcClientError::from)?
+    let response = self::client(client)?
         .translate_quil_to_encrypted_controller_job(request)
-        .await
-        .map_err(GrpcClientError::from)?
-        .into_inner();
+        .await?;
 
     Ok(EncryptedTranslationResult {
         job: response
             .job
             .ok_or_else(|| GrpcClientError::ResponseEmpty("Encrypted Job".into()))?,
+        // Programs which don't read anything out, such as pulse-level calibration jobs, may be
+        // translated without any metadata.
         readout_map: response
             .metadata
-            .ok_or_else(|| GrpcClientError::ResponseEmpty("Job Metadata".into()))?
-            .readout_mappings,
+            .map(|metadata| metadata.readout_mappings)
+            .unwrap_or_default(),
     })
 }
 
@@ -102,9 +222,7 @@ pub async fn get_quilt_calibrations(
 
     let timeout = timeout.unwrap_or(DEFAULT_HTTP_API_TIMEOUT);
 
-    let mut translation_client = client
-        .get_translation_client()
-        .map_err(GrpcClientError::from)?;
+    let mut translation_client = self::client(client)?;
 
     tokio::time::timeout(timeout, async move {
         Ok(translation_client
@@ -113,14 +231,164 @@ pub async fn get_quilt_calibrations(
                     quantum_processor_id,
                 },
             )
-            .await
-            .map_err(GrpcClientError::from)?
-            .into_inner()
+            .await?
             .quil_calibration_program)
     })
     .await?
 }
 
+/// Get the current Quil-T calibrations of a quantum processor as a [`Program`].
+///
+/// Calibrations are cached for each quantum processor and reused for
+/// [`DEFAULT_CALIBRATION_CACHE_TTL`] after they were fetched. Use
+/// [`get_calibration_program_with_ttl`] to choose a different expiry, or
+/// [`clear_calibration_cache`] to force the next call to fetch them again.
+///
+/// # Errors
+///
+/// Returns an error if the calibrations can't be fetched or parsed.
+pub async fn get_calibration_program(
+    quantum_processor_id: &str,
+    client: &Qcs,
+) -> Result<Program, Error> {
+    get_calibration_program_with_ttl(quantum_processor_id, client, DEFAULT_CALIBRATION_CACHE_TTL)
+        .await
+}
+
+/// Get the current Quil-T calibrations of a quantum processor as a [`Program`], reusing cached
+/// calibrations fetched less than `ttl` ago. See [`get_calibration_program`].
+///
+/// # Errors
+///
+/// Returns an error if the calibrations can't be fetched or parsed.
+pub async fn get_calibration_program_with_ttl(
+    quantum_processor_id: &str,
+    client: &Qcs,
+    ttl: Duration,
+) -> Result<Program, Error> {
+    let cached = cached_calibration_program(quantum_processor_id, ttl);
+    metrics::record(Cache::Calibrations, cached.is_some());
+    if let Some(program) = cached {
+        #[cfg(feature = "tracing")]
+        tracing::debug!("using cached calibrations for {}", quantum_processor_id);
+        return Ok(program);
+    }
+
+    let calibrations =
+        get_quilt_calibrations(quantum_processor_id.to_string(), client, None).await?;
+    let program = Program::from_str(&calibrations).map_err(Error::CalibrationProgram)?;
+    if let Ok(mut cache) = CALIBRATION_CACHE.lock() {
+        cache.insert(
+            quantum_processor_id.to_string(),
+            (Instant::now(), program.clone()),
+        );
+    }
+    Ok(program)
+}
+
+fn cached_calibration_program(quantum_processor_id: &str, ttl: Duration) -> Option<Program> {
+    let cache = CALIBRATION_CACHE.lock().ok()?;
+    let (fetched_at, program) = cache.get(quantum_processor_id)?;
+    (fetched_at.elapsed() < ttl).then(|| program.clone())
+}
+
+/// Forget all calibrations cached by [`get_calibration_program`].
+pub fn clear_calibration_cache() {
+    if let Ok(mut cache) = CALIBRATION_CACHE.lock() {
+        cache.clear();
+    }
+}
+
+/// Combine a quantum processor's calibrations, as returned by [`get_calibration_program`], with a
+/// program which uses them.
+///
+/// The result holds every frame, waveform, and calibration from `calibrations`, followed by all of
+/// `program`. Where `program` defines a calibration with the same signature as one in
+/// `calibrations`, the definition in `program` is used.
+#[must_use]
+pub fn merge_calibrations(program: &Program, calibrations: &Program) -> Program {
+    let mut merged = calibrations.clone();
+    merged.add_instructions(program.to_instructions());
+    merged
+}
+
+/// Get a client for the QCS translation service, authenticated and configured the same way as the
+/// rest of this library.
+///
+/// This is meant for advanced uses of the translation service which aren't otherwise wrapped,
+/// such as options this library doesn't expose yet. Prefer [`translate`] and
+/// [`get_quilt_calibrations`] where they suffice.
+///
+/// # Errors
+///
+/// Returns [`Error::Grpc`] if the gRPC channel to the translation service can't be set up.
+pub fn client(qcs: &Qcs) -> Result<TranslationServiceClient, Error> {
+    let inner = qcs
+        .get_translation_client()
+        .map_err(GrpcClientError::from)?;
+    Ok(TranslationServiceClient { inner })
+}
+
+/// A client for the QCS translation service, as returned by [`client`].
+///
+/// Its methods take the service's request types as they are, and unwrap its responses and errors
+/// into the types used by the rest of this module. The underlying generated client is available
+/// through [`TranslationServiceClient::into_inner`] for anything not covered here.
+#[allow(clippy::module_name_repetitions)]
+#[derive(Clone)]
+pub struct TranslationServiceClient {
+    inner: TranslationClient<GrpcConnection>,
+}
+
+impl std::fmt::Debug for TranslationServiceClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TranslationServiceClient")
+            .finish_non_exhaustive()
+    }
+}
+
+impl TranslationServiceClient {
+    /// Translate a Quil program into an encrypted job for a quantum processor.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Grpc`] if the request fails.
+    pub async fn translate_quil_to_encrypted_controller_job(
+        &mut self,
+        request: TranslateQuilToEncryptedControllerJobRequest,
+    ) -> Result<TranslateQuilToEncryptedControllerJobResponse, Error> {
+        Ok(self
+            .inner
+            .translate_quil_to_encrypted_controller_job(request)
+            .await
+            .map_err(GrpcClientError::from)?
+            .into_inner())
+    }
+
+    /// Get the Quil-T calibration program of a quantum processor.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Grpc`] if the request fails.
+    pub async fn get_quantum_processor_quil_calibration_program(
+        &mut self,
+        request: GetQuantumProcessorQuilCalibrationProgramRequest,
+    ) -> Result<QuantumProcessorQuilCalibrationProgram, Error> {
+        Ok(self
+            .inner
+            .get_quantum_processor_quil_calibration_program(request)
+            .await
+            .map_err(GrpcClientError::from)?
+            .into_inner())
+    }
+
+    /// Get the generated gRPC client this wraps.
+    #[must_use]
+    pub fn into_inner(self) -> TranslationClient<GrpcConnection> {
+        self.inner
+    }
+}
+
 /// The error returned when a specific Translation backend is expected to be set, but it is not.
 #[allow(clippy::module_name_repetitions)]
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, thiserror::Error)]
@@ -133,6 +401,35 @@ pub enum TranslationBackendMismatch {
     V2,
 }
 
+/// The error returned when an option can't be set with
+/// [`TranslationOptions::with_backend_option`].
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum BackendOptionError {
+    /// No translation backend was selected, so it isn't known which options are available.
+    #[error("select a translation backend before setting its option {0}")]
+    NoBackend(String),
+    /// The selected backend has no option with the given name.
+    #[error("translation backend {backend} has no option {key}")]
+    Unknown {
+        /// The name of the backend, `V1` or `V2`.
+        backend: &'static str,
+        /// The name of the option.
+        key: String,
+    },
+    /// The value has the wrong type for the option.
+    #[error("invalid value for option {key} of translation backend {backend}: {source}")]
+    InvalidValue {
+        /// The name of the backend, `V1` or `V2`.
+        backend: &'static str,
+        /// The name of the option.
+        key: String,
+        /// Why the value was rejected.
+        #[source]
+        source: serde_json::Error,
+    },
+}
+
 /// Options available for Quil program translation.
 ///
 /// This wraps [`ApiTranslationOptions`] in order to improve the user experience,
@@ -156,6 +453,54 @@ impl TranslationOptions {
         self.inner.translation_backend.as_mut()
     }
 
+    /// Use `backend`, with its options, for translation, replacing any backend already selected.
+    pub fn with_backend(&mut self, backend: TranslationBackend) -> &mut Self {
+        self.inner.translation_backend = Some(backend);
+        self
+    }
+
+    /// Set the option `key` of the selected backend to `value`, as it is named and encoded in the
+    /// JSON form of the backend's options in the QCS API, such as
+    /// `"passive_reset_delay_seconds"` (or `"passiveResetDelaySeconds"`) for
+    /// [`BackendV2Options`].
+    ///
+    /// This reaches every option of the backend, including those without a dedicated method such
+    /// as [`TranslationOptions::v2_passive_reset_delay_seconds`]. The options are sent along with
+    /// the rest of the translation options, for example by
+    /// [`Executable::execute_on_qpu`](crate::Executable::execute_on_qpu).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no backend has been selected, the backend has no option `key`, or
+    /// `value` can't be converted to the type of the option.
+    pub fn with_backend_option(
+        &mut self,
+        key: &str,
+        value: serde_json::Value,
+    ) -> Result<&mut Self, BackendOptionError> {
+        match self.inner.translation_backend.as_mut() {
+            None => return Err(BackendOptionError::NoBackend(key.to_string())),
+            Some(TranslationBackend::V1(options)) => set_backend_option(options, "V1", key, value)?,
+            Some(TranslationBackend::V2(options)) => set_backend_option(options, "V2", key, value)?,
+        }
+        Ok(self)
+    }
+
+    /// Set each option in `options` with [`TranslationOptions::with_backend_option`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the error for the first option which can't be set. Options before it are set.
+    pub fn with_backend_options<K: AsRef<str>>(
+        &mut self,
+        options: impl IntoIterator<Item = (K, serde_json::Value)>,
+    ) -> Result<&mut Self, BackendOptionError> {
+        for (key, value) in options {
+            self.with_backend_option(key.as_ref(), value)?;
+        }
+        Ok(self)
+    }
+
     /// Use the first-generation translation backend available on QCS since 2018.
     pub fn with_backend_v1(&mut self) -> &mut BackendV1Options {
         let backend = &mut self.inner.translation_backend;
@@ -266,16 +611,231 @@ impl TranslationOptions {
     }
 }
 
+/// Set the field `key` of the backend `options` by way of their JSON form, which accepts both the
+/// `snake_case` name of the field and its `camelCase` JSON name.
+fn set_backend_option<T: Serialize + DeserializeOwned>(
+    options: &mut T,
+    backend: &'static str,
+    key: &str,
+    value: serde_json::Value,
+) -> Result<(), BackendOptionError> {
+    let invalid = |source| BackendOptionError::InvalidValue {
+        backend,
+        key: key.to_string(),
+        source,
+    };
+    let json_name = camel_case(key);
+    let known = field_names::<T>();
+    if !known.contains(&key) && !known.contains(&json_name.as_str()) {
+        return Err(BackendOptionError::Unknown {
+            backend,
+            key: key.to_string(),
+        });
+    }
+
+    let mut fields = match serde_json::to_value(&*options).map_err(invalid)? {
+        serde_json::Value::Object(fields) => fields,
+        _ => serde_json::Map::new(),
+    };
+    fields.insert(json_name, value);
+    *options = serde_json::from_value(serde_json::Value::Object(fields)).map_err(invalid)?;
+    Ok(())
+}
+
+/// The names of the fields of the struct `T`, which its [`Deserialize`] implementation hands to
+/// the deserializer along with the struct's name. Empty if `T` is not deserialized as a struct.
+fn field_names<T: DeserializeOwned>() -> &'static [&'static str] {
+    struct FieldNames(Cell<&'static [&'static str]>);
+
+    impl<'de> Deserializer<'de> for &FieldNames {
+        type Error = serde::de::value::Error;
+
+        fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+            Err(serde::de::Error::custom("only structs have field names"))
+        }
+
+        fn deserialize_struct<V: Visitor<'de>>(
+            self,
+            _name: &'static str,
+            fields: &'static [&'static str],
+            _visitor: V,
+        ) -> Result<V::Value, Self::Error> {
+            self.0.set(fields);
+            Err(serde::de::Error::custom("only the field names are needed"))
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+            option unit unit_struct newtype_struct seq tuple tuple_struct map enum identifier
+            ignored_any
+        }
+    }
+
+    let names = FieldNames(Cell::new(&[]));
+    // Deserializing always fails; all that matters is which fields it asked for.
+    let _ = T::deserialize(&names);
+    names.0.get()
+}
+
+/// Convert a `snake_case` name to `camelCase`, leaving `camelCase` names unchanged.
+fn camel_case(name: &str) -> String {
+    let mut words = name.split('_');
+    let mut camel = words.next().unwrap_or_default().to_string();
+    for word in words {
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            camel.extend(first.to_uppercase());
+            camel.push_str(chars.as_str());
+        }
+    }
+    camel
+}
+
 impl From<TranslationOptions> for ApiTranslationOptions {
     fn from(options: TranslationOptions) -> Self {
         options.inner
     }
 }
 
+#[cfg(test)]
+mod describe_translation_options {
+    use serde_json::json;
+
+    use super::{
+        field_names, ApiTranslationOptions, BackendOptionError, BackendV2Options,
+        TranslationBackend, TranslationOptions,
+    };
+
+    fn v2_options(options: TranslationOptions) -> BackendV2Options {
+        match ApiTranslationOptions::from(options).translation_backend {
+            Some(TranslationBackend::V2(options)) => options,
+            backend => panic!("expected the V2 backend, got {backend:?}"),
+        }
+    }
+
+    #[test]
+    fn it_selects_a_backend_with_its_options() {
+        let mut options = TranslationOptions::default();
+        options.with_backend(TranslationBackend::V2(BackendV2Options {
+            passive_reset_delay_seconds: Some(0.5),
+            ..BackendV2Options::default()
+        }));
+        assert_eq!(v2_options(options).passive_reset_delay_seconds, Some(0.5));
+    }
+
+    #[test]
+    fn it_passes_options_through_by_name() {
+        let mut options = TranslationOptions::default();
+        options.with_backend_v2();
+        options
+            .with_backend_options([
+                ("passive_reset_delay_seconds", json!(0.25)),
+                ("allowFrameRedefinition", json!(true)),
+            ])
+            .unwrap();
+
+        let options = v2_options(options);
+        assert_eq!(options.passive_reset_delay_seconds, Some(0.25));
+        assert_eq!(options.allow_frame_redefinition, Some(true));
+    }
+
+    #[test]
+    fn it_rejects_options_it_cannot_pass_through() {
+        let mut options = TranslationOptions::default();
+        assert!(matches!(
+            options.with_backend_option("allow_frame_redefinition", json!(true)),
+            Err(BackendOptionError::NoBackend(_))
+        ));
+
+        options.with_backend_v2();
+        assert!(matches!(
+            options.with_backend_option("no_such_option", json!(1)),
+            Err(BackendOptionError::Unknown { backend: "V2", .. })
+        ));
+        assert!(matches!(
+            options.with_backend_option("allow_frame_redefinition", json!("yes")),
+            Err(BackendOptionError::InvalidValue { backend: "V2", .. })
+        ));
+    }
+
+    #[test]
+    fn it_knows_the_fields_of_each_backend() {
+        let names = field_names::<BackendV2Options>();
+        assert!(names.contains(&"allow_frame_redefinition"));
+        assert!(names.contains(&"allowFrameRedefinition"));
+        assert!(field_names::<u32>().is_empty());
+    }
+}
+
+#[cfg(test)]
+mod describe_settings_epoch {
+    use super::SettingsEpoch;
+    use crate::test_support::qvm_isa;
+
+    #[test]
+    fn it_uses_the_latest_characteristic_timestamp() {
+        let mut isa = qvm_isa();
+        let epoch = SettingsEpoch::from_isa(&isa).expect("ISA has characteristics");
+        assert_eq!(epoch.as_str(), "1970-01-01T00:00:00+00:00");
+
+        isa.instructions[0].sites[0].characteristics[0].timestamp =
+            "2024-01-01T00:00:00+00:00".to_string();
+        let recalibrated = SettingsEpoch::from_isa(&isa).expect("ISA has characteristics");
+        assert_ne!(epoch, recalibrated);
+        assert_eq!(recalibrated.as_str(), "2024-01-01T00:00:00+00:00");
+    }
+}
+
+#[cfg(test)]
+mod describe_calibrations {
+    use std::str::FromStr;
+
+    use quil_rs::{quil::Quil, Program};
+
+    use super::merge_calibrations;
+
+    const CALIBRATIONS: &str = r#"DEFFRAME 0 "rf":
+    SAMPLE-RATE: 1.0
+DEFCAL RX(pi/2) 0:
+    NONBLOCKING PULSE 0 "rf" gaussian(duration: 1.0, fwhm: 0.5, t0: 0.5)
+DEFCAL MEASURE 0 addr:
+    CAPTURE 0 "rf" gaussian(duration: 1.0, fwhm: 0.5, t0: 0.5) addr
+"#;
+
+    #[test]
+    fn it_merges_calibrations_into_a_program() {
+        let calibrations = Program::from_str(CALIBRATIONS).unwrap();
+        let program = Program::from_str("DECLARE ro BIT\nRX(pi/2) 0\nMEASURE 0 ro[0]\n").unwrap();
+        let merged = merge_calibrations(&program, &calibrations);
+
+        assert_eq!(merged.calibrations.len(), 2);
+        assert_eq!(merged.frames.len(), 1);
+        assert_eq!(merged.body_instructions().count(), 2);
+        assert!(merged.memory_regions.contains_key("ro"));
+        assert!(Program::from_str(&merged.to_quil().unwrap()).is_ok());
+    }
+
+    #[test]
+    fn it_prefers_the_calibrations_of_the_program() {
+        let calibrations = Program::from_str(CALIBRATIONS).unwrap();
+        let program = Program::from_str("DEFCAL RX(pi/2) 0:\n    FENCE 0\nRX(pi/2) 0\n").unwrap();
+        let merged = merge_calibrations(&program, &calibrations);
+
+        assert_eq!(merged.calibrations.len(), 2);
+        assert!(merged.to_quil().unwrap().contains("FENCE 0"));
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[tokio::test]
+    async fn it_builds_a_client_without_connecting() {
+        let client = client(&Qcs::default()).expect("the channel should connect lazily");
+        assert_eq!(format!("{client:?}"), "TranslationServiceClient { .. }");
+    }
+
     #[test]
     fn creating_new_options_does_not_fail() {
         let mut options = TranslationOptions::default();
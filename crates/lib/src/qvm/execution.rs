@@ -1,11 +1,24 @@
+use std::borrow::Cow;
 use std::str::FromStr;
 use std::{collections::HashMap, num::NonZeroU16};
 
-use quil_rs::Program;
+use indexmap::IndexMap;
+use quil_rs::{quil::Quil, Program};
 
-use crate::{executable::Parameters, qvm::run_program};
+use crate::{
+    executable::Parameters,
+    qvm::{
+        apply_declared_types, apply_parameters_to_program, check_parameter,
+        get_program_wavefunction, measure_expectation, parameter_moves, run_and_measure_program,
+        run_program,
+    },
+};
 
-use super::{http::AddressRequest, Error, QvmResultData};
+use super::noise::{NoiseModel, PauliNoise};
+use super::{
+    http::{AddressRequest, MultishotRequest},
+    Error, QvmResultData, Wavefunction,
+};
 use super::{Client, QvmOptions};
 
 /// Contains all the info needed to execute on a QVM a single time, with the ability to be reused for
@@ -13,6 +26,92 @@ use super::{Client, QvmOptions};
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) struct Execution {
     program: Program,
+    /// The program as it was last sent by [`Execution::run_with_state_reuse`].
+    prepared: Option<PreparedProgram>,
+}
+
+/// The Quil of a program, serialized once and split around the `MOVE` instructions which apply
+/// its parameters, so that it can be run again with new parameters without cloning or
+/// serializing the rest of the program.
+#[derive(Debug, Clone, PartialEq)]
+struct PreparedProgram {
+    /// The noise model which was applied to the program.
+    noise_model: Option<NoiseModel>,
+    measurement_noise: Option<(f64, f64, f64)>,
+    gate_noise: Option<(f64, f64, f64)>,
+    /// Everything which comes before the body of the program, such as its declarations.
+    head: String,
+    /// The instructions of the program.
+    body: String,
+    /// The values last applied to each parameter and the `MOVE` instructions which apply them.
+    moves: IndexMap<Box<str>, (Vec<f64>, String)>,
+}
+
+impl PreparedProgram {
+    fn new(program: &Program, noise_model: Option<&NoiseModel>) -> Result<Self, Error> {
+        let (program, measurement_noise, gate_noise) = with_noise(program, noise_model);
+        let body = program
+            .body_instructions()
+            .map(Quil::to_quil)
+            .collect::<Result<Vec<_>, _>>()?
+            .join("\n");
+        Ok(Self {
+            noise_model: noise_model.cloned(),
+            measurement_noise,
+            gate_noise,
+            head: program.clone_without_body_instructions().to_quil()?,
+            body,
+            moves: IndexMap::new(),
+        })
+    }
+
+    /// Rewrite the `MOVE` instructions of each parameter whose values differ from the last ones
+    /// applied, and drop those of parameters which are no longer set.
+    fn apply_parameters(&mut self, program: &Program, params: &Parameters) -> Result<(), Error> {
+        self.moves.retain(|name, _| params.contains_key(name));
+        for (name, values) in params {
+            if matches!(self.moves.get(name), Some((applied, _)) if applied == values) {
+                continue;
+            }
+            check_parameter(program, name, values)?;
+            let moves = parameter_moves(name, values)
+                .map(|instruction| instruction.to_quil())
+                .collect::<Result<Vec<_>, _>>()?
+                .join("\n");
+            self.moves.insert(name.clone(), (values.clone(), moves));
+        }
+        Ok(())
+    }
+
+    /// The Quil of the program with the parameters last applied.
+    fn to_quil(&self) -> String {
+        std::iter::once(self.head.as_str())
+            .chain(self.moves.values().map(|(_, moves)| moves.as_str()))
+            .chain(std::iter::once(self.body.as_str()))
+            .filter(|section| !section.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// `program` with `noise_model` applied to it, along with the model's measurement and gate
+/// noise to send with the request.
+fn with_noise<'a>(
+    program: &'a Program,
+    noise_model: Option<&NoiseModel>,
+) -> (
+    Cow<'a, Program>,
+    Option<(f64, f64, f64)>,
+    Option<(f64, f64, f64)>,
+) {
+    match noise_model {
+        Some(model) => (
+            Cow::Owned(model.apply_to_program(program)),
+            model.measurement_noise().map(PauliNoise::as_tuple),
+            model.gate_noise().map(PauliNoise::as_tuple),
+        ),
+        None => (Cow::Borrowed(program), None, None),
+    }
 }
 
 impl Execution {
@@ -20,7 +119,36 @@ impl Execution {
     /// there are any problems.
     pub(crate) fn new(quil: &str) -> Result<Self, Error> {
         let program = Program::from_str(quil).map_err(Error::Parsing)?;
-        Ok(Self { program })
+        Ok(Self {
+            program,
+            prepared: None,
+        })
+    }
+
+    /// Construct a new [`Execution`] from a program which is already parsed.
+    pub(crate) fn from_program(program: Program) -> Self {
+        Self {
+            program,
+            prepared: None,
+        }
+    }
+
+    /// The parsed program this [`Execution`] runs.
+    pub(crate) fn program(&self) -> &Program {
+        &self.program
+    }
+
+    /// The program with `noise_model` applied to it, along with the model's measurement and gate
+    /// noise to send with the request.
+    fn with_noise(
+        &self,
+        noise_model: Option<&NoiseModel>,
+    ) -> (
+        Cow<'_, Program>,
+        Option<(f64, f64, f64)>,
+        Option<(f64, f64, f64)>,
+    ) {
+        with_noise(&self.program, noise_model)
     }
 
     /// Run on a QVM.
@@ -34,7 +162,8 @@ impl Execution {
     ///    values should be returned for that address.
     /// 3. `register`: The name of the register containing results that should be read out from QVM.
     /// 4. `params`: Values to substitute for parameters in Quil.
-    /// 5. `config`: A configuration object containing the connection URL of QVM.
+    /// 5. `noise_model`: The [`NoiseModel`] to simulate, if any.
+    /// 6. `config`: A configuration object containing the connection URL of QVM.
     ///
     /// Returns: [`ExecutionResult`].
     ///
@@ -57,24 +186,120 @@ impl Execution {
         shots: NonZeroU16,
         addresses: HashMap<String, AddressRequest>,
         params: &Parameters,
+        noise_model: Option<&NoiseModel>,
         client: &C,
     ) -> Result<QvmResultData, Error> {
+        let (program, measurement_noise, gate_noise) = self.with_noise(noise_model);
         run_program(
-            &self.program,
+            &program,
             shots,
             addresses,
             params,
+            measurement_noise,
+            gate_noise,
             None,
+            client,
+            &QvmOptions::default(),
+        )
+        .await
+    }
+
+    /// Run on a QVM like [`Execution::run`], but keep the serialized program between runs, so
+    /// that only the `MOVE` instructions of parameters whose values changed since the last run
+    /// are rewritten. The program is serialized again if `noise_model` changes.
+    pub(crate) async fn run_with_state_reuse<C: Client + ?Sized>(
+        &mut self,
+        shots: NonZeroU16,
+        addresses: HashMap<String, AddressRequest>,
+        params: &Parameters,
+        noise_model: Option<&NoiseModel>,
+        client: &C,
+    ) -> Result<QvmResultData, Error> {
+        let prepared = match &mut self.prepared {
+            Some(prepared) if prepared.noise_model.as_ref() == noise_model => prepared,
+            prepared => prepared.insert(PreparedProgram::new(&self.program, noise_model)?),
+        };
+        prepared.apply_parameters(&self.program, params)?;
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            %shots,
+            ?addresses,
+            ?params,
+            "executing prepared program on QVM"
+        );
+        let request = MultishotRequest::new(
+            prepared.to_quil(),
+            shots,
+            addresses,
+            prepared.measurement_noise,
+            prepared.gate_noise,
             None,
+        );
+        let response = client.run(&request, &QvmOptions::default()).await?;
+        apply_declared_types(&self.program, response.registers).map(QvmResultData::from_memory_map)
+    }
+
+    /// Run on a QVM once and sample the resulting wavefunction `shots` times. See
+    /// [`run_and_measure_program`].
+    pub(crate) async fn run_and_measure<C: Client + ?Sized>(
+        &self,
+        shots: NonZeroU16,
+        qubits: &[u64],
+        params: &Parameters,
+        noise_model: Option<&NoiseModel>,
+        client: &C,
+    ) -> Result<QvmResultData, Error> {
+        let (program, measurement_noise, gate_noise) = self.with_noise(noise_model);
+        run_and_measure_program(
+            &program,
+            shots,
+            qubits,
+            params,
+            measurement_noise,
+            gate_noise,
             None,
             client,
             &QvmOptions::default(),
         )
         .await
     }
+
+    /// Simulate the program on a QVM once and return its wavefunction. See
+    /// [`get_program_wavefunction`].
+    pub(crate) async fn wavefunction<C: Client + ?Sized>(
+        &self,
+        params: &Parameters,
+        noise_model: Option<&NoiseModel>,
+        client: &C,
+    ) -> Result<Wavefunction, Error> {
+        let (program, measurement_noise, gate_noise) = self.with_noise(noise_model);
+        get_program_wavefunction(
+            &program,
+            params,
+            measurement_noise,
+            gate_noise,
+            None,
+            client,
+            &QvmOptions::default(),
+        )
+        .await
+    }
+
+    /// Measure the expectation values of Pauli operators in the state the program prepares. See
+    /// [`measure_expectation`].
+    pub(crate) async fn expectation<C: Client + ?Sized, S: AsRef<str>>(
+        &self,
+        pauli_operators: &[S],
+        params: &Parameters,
+        client: &C,
+    ) -> Result<IndexMap<String, f64>, Error> {
+        let program = apply_parameters_to_program(&self.program, params)?;
+        measure_expectation(&program, pauli_operators, client, &QvmOptions::default()).await
+    }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "qvm-http"))]
 mod describe_execution {
     use std::{collections::HashMap, num::NonZeroU16};
 
@@ -98,6 +323,7 @@ mod describe_execution {
                 NonZeroU16::new(1).expect("value is non-zero"),
                 HashMap::new(),
                 &params,
+                None,
                 &qvm_client(),
             )
             .await;
@@ -120,6 +346,7 @@ mod describe_execution {
                 NonZeroU16::new(1).expect("value is non-zero"),
                 HashMap::new(),
                 &params,
+                None,
                 &qvm_client(),
             )
             .await;
@@ -133,3 +360,118 @@ mod describe_execution {
         }
     }
 }
+
+#[cfg(test)]
+mod describe_state_reuse {
+    use std::{collections::HashMap, num::NonZeroU16};
+
+    use quil_rs::quil::Quil;
+
+    use super::{Execution, Parameters};
+    use crate::{
+        qvm::{self, http, noise::NoiseModel},
+        test_support::RecordingQvm,
+    };
+
+    const PROGRAM: &str = "DECLARE theta REAL[2]\nDECLARE ro BIT\nRX(theta[0]) 0\nMEASURE 0 ro";
+
+    fn params(theta: f64) -> Parameters {
+        Parameters::from([("theta".into(), vec![theta, 0.0])])
+    }
+
+    async fn run(
+        exe: &mut Execution,
+        params: &Parameters,
+        noise_model: Option<&NoiseModel>,
+        qvm: &RecordingQvm,
+    ) -> Result<qvm::QvmResultData, qvm::Error> {
+        exe.run_with_state_reuse(
+            NonZeroU16::new(1).unwrap(),
+            HashMap::from([("ro".to_string(), http::AddressRequest::IncludeAll)]),
+            params,
+            noise_model,
+            qvm,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn it_only_rewrites_the_parameters() {
+        let qvm = RecordingQvm::default();
+        let mut exe = Execution::new(PROGRAM).unwrap();
+
+        run(&mut exe, &params(0.5), None, &qvm).await.unwrap();
+        let prepared = exe.prepared.clone().unwrap();
+        run(&mut exe, &params(1.5), None, &qvm).await.unwrap();
+
+        let programs = qvm.programs.lock().unwrap();
+        assert!(programs[0].contains("MOVE theta[0] 0.5"));
+        assert!(programs[1].contains("MOVE theta[0] 1.5"));
+        assert!(programs[1].contains("MOVE theta[1] 0"));
+        assert!(programs[1].contains("RX(theta[0]) 0"));
+
+        let reused = exe.prepared.as_ref().unwrap();
+        assert_eq!(reused.head, prepared.head);
+        assert_eq!(reused.body, prepared.body);
+        assert_eq!(reused.moves["theta"].0, vec![1.5, 0.0]);
+    }
+
+    #[tokio::test]
+    async fn it_matches_the_program_with_parameters_applied() {
+        let qvm = RecordingQvm::default();
+        let mut exe = Execution::new(PROGRAM).unwrap();
+        let params = params(0.5);
+
+        run(&mut exe, &params, None, &qvm).await.unwrap();
+
+        let lines = |quil: &str| -> Vec<String> {
+            quil.lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(ToString::to_string)
+                .collect()
+        };
+        let expected = qvm::apply_parameters_to_program(exe.program(), &params)
+            .unwrap()
+            .to_quil()
+            .unwrap();
+        assert_eq!(lines(&qvm.programs.lock().unwrap()[0]), lines(&expected));
+    }
+
+    #[tokio::test]
+    async fn it_checks_changed_parameters() {
+        let qvm = RecordingQvm::default();
+        let mut exe = Execution::new(PROGRAM).unwrap();
+
+        run(&mut exe, &params(0.5), None, &qvm).await.unwrap();
+        let result = run(
+            &mut exe,
+            &Parameters::from([("theta".into(), vec![0.5])]),
+            None,
+            &qvm,
+        )
+        .await;
+
+        assert!(matches!(result, Err(qvm::Error::RegionSizeMismatch { .. })));
+        assert_eq!(qvm.programs.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn it_prepares_the_program_again_for_a_new_noise_model() {
+        let qvm = RecordingQvm::default();
+        let mut exe = Execution::new(PROGRAM).unwrap();
+        let noise_model = NoiseModel::default().readout_noise_for(0, 0.9, 0.8);
+
+        run(&mut exe, &params(0.5), None, &qvm).await.unwrap();
+        run(&mut exe, &params(0.5), Some(&noise_model), &qvm)
+            .await
+            .unwrap();
+
+        let programs = qvm.programs.lock().unwrap();
+        assert!(!programs[0].contains("READOUT-POVM"));
+        assert!(programs[1].contains("READOUT-POVM"));
+        assert_eq!(
+            exe.prepared.as_ref().unwrap().noise_model.as_ref(),
+            Some(&noise_model)
+        );
+    }
+}
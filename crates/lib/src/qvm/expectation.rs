@@ -0,0 +1,138 @@
+//! Measuring the expectation values of Pauli operators on the QVM.
+
+use indexmap::IndexMap;
+use quil_rs::{quil::Quil, Program};
+
+use super::{http::ExpectationRequest, Client, Error, QvmOptions};
+
+/// Measure the expectation value of each of `pauli_operators` in the state prepared by `program`.
+///
+/// Each operator is a product of single-qubit Paulis, each written as its letter followed by the
+/// qubit it acts on, and separated by spaces or `*`: for example `"Z0"`, `"X0 Y1"`, or `"Z0*Z1"`.
+/// `"I"`, or an empty string, is the identity, whose expectation value is always 1.
+///
+/// The values are returned in the same order as `pauli_operators`, keyed by operator.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidPauliOperator`] if an operator can't be parsed, or any error from the
+/// [`Client`].
+pub async fn measure_expectation<C, S>(
+    program: &Program,
+    pauli_operators: &[S],
+    client: &C,
+    options: &QvmOptions,
+) -> Result<IndexMap<String, f64>, Error>
+where
+    C: Client + ?Sized,
+    S: AsRef<str>,
+{
+    #[cfg(feature = "tracing")]
+    tracing::debug!(
+        num_operators = pauli_operators.len(),
+        "measuring expectation values on QVM"
+    );
+
+    let operators = pauli_operators
+        .iter()
+        .map(|operator| pauli_operator_to_quil(operator.as_ref()))
+        .collect::<Result<Vec<_>, _>>()?;
+    // The identity needs no simulation, so only the other operators are sent to the QVM.
+    let requested: Vec<String> = operators.iter().flatten().cloned().collect();
+    let values = if requested.is_empty() {
+        Vec::new()
+    } else {
+        let request = ExpectationRequest::new(program.to_quil()?, &requested, None);
+        client.measure_expectation(&request, options).await?
+    };
+    if values.len() != requested.len() {
+        return Err(Error::Qvm {
+            message: format!(
+                "expected {} expectation values, but received {}",
+                requested.len(),
+                values.len()
+            ),
+        });
+    }
+
+    let mut values = values.into_iter();
+    Ok(pauli_operators
+        .iter()
+        .zip(&operators)
+        .map(|(name, operator)| {
+            let value = match operator {
+                Some(_) => values.next().unwrap_or_default(),
+                None => 1.0,
+            };
+            (name.as_ref().to_string(), value)
+        })
+        .collect())
+}
+
+/// Convert a Pauli operator such as `"X0 Z1"` into the Quil program the QVM expects, e.g.
+/// `"X 0\nZ 1"`, or `None` for the identity.
+fn pauli_operator_to_quil(operator: &str) -> Result<Option<String>, Error> {
+    let invalid = || Error::InvalidPauliOperator {
+        operator: operator.to_string(),
+    };
+    let mut gates = Vec::new();
+    for factor in operator
+        .split(|c: char| c.is_whitespace() || c == '*')
+        .filter(|factor| !factor.is_empty())
+    {
+        let mut chars = factor.chars();
+        let pauli = chars.next().ok_or_else(invalid)?;
+        let qubit = chars.as_str();
+        match pauli {
+            'I' if qubit.is_empty() || qubit.parse::<u64>().is_ok() => {}
+            'X' | 'Y' | 'Z' => {
+                let qubit: u64 = qubit.parse().map_err(|_| invalid())?;
+                gates.push(format!("{pauli} {qubit}"));
+            }
+            _ => return Err(invalid()),
+        }
+    }
+    Ok(if gates.is_empty() {
+        None
+    } else {
+        Some(gates.join("\n"))
+    })
+}
+
+#[cfg(test)]
+mod describe_pauli_operators {
+    use super::{pauli_operator_to_quil, Error};
+
+    #[test]
+    fn it_converts_products_of_paulis_to_quil() {
+        assert_eq!(
+            pauli_operator_to_quil("Z0").unwrap().as_deref(),
+            Some("Z 0")
+        );
+        assert_eq!(
+            pauli_operator_to_quil("X0 Y1").unwrap().as_deref(),
+            Some("X 0\nY 1")
+        );
+        assert_eq!(
+            pauli_operator_to_quil("Z0*I2*Z10").unwrap().as_deref(),
+            Some("Z 0\nZ 10")
+        );
+    }
+
+    #[test]
+    fn it_recognizes_the_identity() {
+        for operator in ["", "I", "I0 I1"] {
+            assert_eq!(pauli_operator_to_quil(operator).unwrap(), None);
+        }
+    }
+
+    #[test]
+    fn it_rejects_invalid_operators() {
+        for operator in ["Z", "W0", "X-1", "ZZ"] {
+            assert!(matches!(
+                pauli_operator_to_quil(operator),
+                Err(Error::InvalidPauliOperator { operator: invalid }) if invalid == operator
+            ));
+        }
+    }
+}
@@ -3,11 +3,17 @@
 //! for running parameterized programs.
 use std::{collections::HashMap, num::NonZeroU16};
 
+#[cfg(feature = "qvm-http")]
 use reqwest::Response;
-use serde::{de::DeserializeOwned, Deserialize, Serialize};
+#[cfg(feature = "qvm-http")]
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
 
-use crate::{client::Qcs, RegisterData};
+#[cfg(feature = "qvm-http")]
+use crate::client::Qcs;
+use crate::RegisterData;
 
+#[cfg(feature = "qvm-http")]
 use super::{Error, QvmOptions};
 
 #[derive(Serialize, Copy, Clone, Debug, Ord, PartialOrd, Eq, PartialEq)]
@@ -19,6 +25,7 @@ enum RequestType {
     Wavefunction,
 }
 
+#[cfg(feature = "qvm-http")]
 #[derive(Debug, Deserialize, Clone, Eq, PartialEq)]
 pub(super) struct Failure {
     /// The message from QVM describing what went wrong.
@@ -27,6 +34,7 @@ pub(super) struct Failure {
 
 /// A QVM response that can be deserialized into some successful response type `T`, or into a
 /// [`Failure`] containing an error message.
+#[cfg(feature = "qvm-http")]
 #[derive(Debug, Clone, Deserialize, PartialEq)]
 #[serde(untagged)]
 pub(super) enum QvmResponse<T>
@@ -40,6 +48,7 @@ where
     Failure(Failure),
 }
 
+#[cfg(feature = "qvm-http")]
 impl<T: DeserializeOwned> QvmResponse<T> {
     /// Converts a [`QvmResponse<T>`] into a [`Result<T, qvm::Error>`] containing the successful response,
     /// as the Ok value or an [`Error`] containing the error message from the [`Failure`] response.
@@ -248,6 +257,7 @@ impl WavefunctionRequest {
 }
 
 /// Provides HTTP-based access to QVM functionality
+#[cfg(feature = "qvm-http")]
 #[derive(Debug, Clone)]
 #[allow(clippy::module_name_repetitions)]
 pub struct HttpClient {
@@ -256,6 +266,7 @@ pub struct HttpClient {
     pub qvm_url: String,
 }
 
+#[cfg(feature = "qvm-http")]
 impl HttpClient {
     /// Build a new [`HttpClient`] to connect to a QVM server at `qvm_url`.
     #[must_use]
@@ -265,12 +276,42 @@ impl HttpClient {
     }
 }
 
+#[cfg(feature = "qvm-http")]
 impl From<&Qcs> for HttpClient {
+    /// Connect to the QVM configured for `qcs`, identifying its
+    /// [`ClientApplication`](crate::client::ClientApplication), if any, in the `User-Agent`, and
+    /// through the proxy and with the certificates of its
+    /// [`ConnectionSettings`](crate::client::ConnectionSettings).
+    ///
+    /// The settings are checked when they are loaded, so this should only fail to apply them if
+    /// the HTTP client itself can't be initialized. In that case the failure is logged, and the
+    /// client connects directly, without the settings.
     fn from(qcs: &Qcs) -> Self {
-        Self::new(qcs.get_config().qvm_url().to_string())
+        let client = qcs
+            .connection()
+            .http_client_builder()
+            .and_then(|builder| Ok(builder.user_agent(qcs.user_agent()).build()?))
+            .unwrap_or_else(|error| {
+                #[cfg(feature = "tracing")]
+                tracing::error!(
+                    %error,
+                    "failed to apply the connection settings to the QVM client, connecting without them"
+                );
+                #[cfg(not(feature = "tracing"))]
+                let _ = error;
+                reqwest::Client::builder()
+                    .user_agent(qcs.user_agent())
+                    .build()
+                    .unwrap_or_default()
+            });
+        Self {
+            client,
+            qvm_url: qcs.get_config().qvm_url().to_string(),
+        }
     }
 }
 
+#[cfg(feature = "qvm-http")]
 #[async_trait::async_trait]
 impl super::Client for HttpClient {
     async fn get_version_info(&self, options: &QvmOptions) -> Result<String, Error> {
@@ -369,6 +410,7 @@ impl super::Client for HttpClient {
     }
 }
 
+#[cfg(feature = "qvm-http")]
 async fn make_request<T>(
     request: &T,
     client: &HttpClient,
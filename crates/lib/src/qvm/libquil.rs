@@ -1,7 +1,13 @@
 //! Provides QVM functionality via libquil
+//!
+//! [`Client`] behaves like [`http::HttpClient`]: every method honors the request's `rng_seed`
+//! and noise parameters, and the [`QvmOptions::timeout`], except that libquil can't add noise
+//! to a wavefunction, so [`get_wavefunction`](crate::qvm::Client::get_wavefunction) rejects
+//! requests for one.
 
 use std::collections::HashMap;
 use std::convert::TryFrom;
+use std::time::Duration;
 
 use crate::RegisterData;
 
@@ -21,11 +27,24 @@ pub enum Error {
     LibquilSysQuilc(#[from] libquil_sys::quilc::Error),
     /// We currently only support requesting a specific set of register indices
     /// or _all_ register indices.
+    ///
+    /// No longer returned: registers requested with [`AddressRequest::ExcludeAll`] are left out
+    /// of the results, as they are by the QVM server.
     #[error("can only request explicit or all indices for multishot programs")]
     UnsupportedIndicesRequestType,
     /// Error raised when trying to cast one integer type into another
     #[error("could not cast value: {0}")]
     InvalidCast(#[from] std::num::TryFromIntError),
+    /// Noise was requested for a simulation libquil can't add noise to.
+    #[error("libquil can't simulate noise for {0} requests")]
+    UnsupportedNoise(&'static str),
+    /// The simulation didn't finish within the [`QvmOptions::timeout`]. It is left to finish in
+    /// the background.
+    #[error("the QVM did not finish within {0:?}")]
+    Timeout(Duration),
+    /// The simulation stopped without finishing, e.g. because it panicked.
+    #[error("the QVM stopped unexpectedly: {0}")]
+    Interrupted(String),
 }
 
 impl From<Error> for super::Error {
@@ -40,160 +59,252 @@ impl From<Error> for super::Error {
 #[derive(Debug, Copy, Clone)]
 pub struct Client;
 
+/// The register [`Client::run_and_measure`](crate::qvm::Client::run_and_measure) measures qubits
+/// into when it has to measure them itself to simulate noise.
+const RUN_AND_MEASURE_REGISTER: &str = "__qcs_run_and_measure";
+
 #[async_trait::async_trait]
 impl crate::qvm::Client for Client {
-    async fn get_version_info(&self, _options: &QvmOptions) -> Result<String, super::Error> {
-        let version = libquil_sys::qvm::get_version_info().map_err(Error::LibquilSysQvm)?;
-        Ok(version.to_string())
+    async fn get_version_info(&self, options: &QvmOptions) -> Result<String, super::Error> {
+        run_blocking(options, || {
+            let version = libquil_sys::qvm::get_version_info()?;
+            Ok(version.to_string())
+        })
+        .await
     }
 
     async fn run(
         &self,
         request: &http::MultishotRequest,
-        _options: &QvmOptions,
+        options: &QvmOptions,
     ) -> Result<http::MultishotResponse, super::Error> {
-        let program = request
-            .compiled_quil
-            .parse()
-            .map_err(Error::LibquilSysQuilc)?;
-        let addresses = request
-            .addresses
-            .iter()
-            .map(|(address, indices)| match indices {
-                AddressRequest::Indices(indices) => Ok((
-                    address.clone(),
-                    libquil_sys::qvm::MultishotAddressRequest::Indices(
-                        indices
-                            .clone()
-                            .into_iter()
-                            .map(u32::try_from)
-                            .collect::<Result<_, _>>()?,
-                    ),
-                )),
-                AddressRequest::IncludeAll => Ok((
-                    address.clone(),
-                    libquil_sys::qvm::MultishotAddressRequest::All,
-                )),
-                AddressRequest::ExcludeAll => Err(Error::UnsupportedIndicesRequestType),
-            })
-            .collect::<Result<_, _>>()?;
-        let result = libquil_sys::qvm::multishot(
-            &program,
-            addresses,
-            i32::from(request.trials.get()),
-            request.gate_noise,
-            request.measurement_noise,
-            request.rng_seed,
-        )
-        .map_err(Error::LibquilSysQvm)?;
-        let mut registers = HashMap::with_capacity(result.len());
-        for (address, values) in result {
-            match values {
-                libquil_sys::qvm::MultishotAddressData::Bit(values)
-                | libquil_sys::qvm::MultishotAddressData::Octet(values) => {
-                    registers.insert(
-                        address,
-                        RegisterData::I8(
-                            values
-                                .iter()
-                                .map(|v| {
-                                    v.iter().map(|i| i8::try_from(*i)).collect::<Result<_, _>>()
-                                })
-                                .collect::<Result<_, _>>()
-                                .map_err(Error::InvalidCast)?,
-                        ),
-                    );
-                }
-                libquil_sys::qvm::MultishotAddressData::Integer(values) => {
-                    registers.insert(
-                        address,
-                        RegisterData::I16(
-                            values
-                                .iter()
-                                .map(|v| {
-                                    v.iter()
-                                        .map(|i| i16::try_from(*i))
-                                        .collect::<Result<_, _>>()
-                                })
-                                .collect::<Result<_, _>>()
-                                .map_err(Error::InvalidCast)?,
-                        ),
-                    );
-                }
-                libquil_sys::qvm::MultishotAddressData::Real(values) => {
-                    registers.insert(address, RegisterData::F64(values));
-                }
-            }
-        }
-        Ok(http::MultishotResponse { registers })
+        let request = request.clone();
+        run_blocking(options, move || multishot(&request)).await
     }
 
     async fn run_and_measure(
         &self,
         request: &http::MultishotMeasureRequest,
-        _options: &QvmOptions,
+        options: &QvmOptions,
     ) -> Result<Vec<Vec<i64>>, super::Error> {
-        let program = request
-            .compiled_quil
-            .parse()
-            .map_err(Error::LibquilSysQuilc)?;
-        let qubits = request
+        if request.gate_noise.is_none() && request.measurement_noise.is_none() {
+            let request = request.clone();
+            return run_blocking(options, move || multishot_measure(&request)).await;
+        }
+        if request.qubits.is_empty() {
+            return Ok(vec![Vec::new(); usize::from(request.trials.get())]);
+        }
+
+        // libquil only simulates noise for `multishot`, so measure the qubits in the program.
+        let measurements: String = request
             .qubits
             .iter()
-            .copied()
-            .map(i32::try_from)
-            .collect::<Result<Vec<_>, _>>()
-            .map_err(Error::InvalidCast)?;
-        let result = libquil_sys::qvm::multishot_measure(
-            &program,
-            qubits.as_slice(),
-            i32::from(request.trials.get()),
+            .enumerate()
+            .map(|(index, qubit)| format!("MEASURE {qubit} {RUN_AND_MEASURE_REGISTER}[{index}]\n"))
+            .collect();
+        let quil = format!(
+            "{}\nDECLARE {RUN_AND_MEASURE_REGISTER} BIT[{}]\n{measurements}",
+            request.compiled_quil,
+            request.qubits.len()
+        );
+        let multishot_request = http::MultishotRequest::new(
+            quil,
+            request.trials,
+            HashMap::from([(
+                RUN_AND_MEASURE_REGISTER.to_string(),
+                AddressRequest::IncludeAll,
+            )]),
+            request.measurement_noise,
+            request.gate_noise,
             request.rng_seed,
-        )
-        .map_err(Error::LibquilSysQvm)?;
-        let result = result
+        );
+        let mut response = run_blocking(options, move || multishot(&multishot_request)).await?;
+        let bits = response
+            .registers
+            .remove(RUN_AND_MEASURE_REGISTER)
+            .and_then(|data| data.into_i8().ok())
+            .unwrap_or_default();
+        Ok(bits
             .into_iter()
-            .map(|i| i.into_iter().map(i64::from).collect())
-            .collect();
-        Ok(result)
+            .map(|shot| shot.into_iter().map(i64::from).collect())
+            .collect())
     }
 
     async fn measure_expectation(
         &self,
         request: &http::ExpectationRequest,
-        _options: &QvmOptions,
+        options: &QvmOptions,
     ) -> Result<Vec<f64>, super::Error> {
-        let program = request
-            .state_preparation
-            .parse()
-            .map_err(Error::LibquilSysQuilc)?;
-        let operators = request
-            .operators
-            .iter()
-            .map(|s| s.parse().map_err(Error::LibquilSysQuilc))
-            .collect::<Result<Vec<_>, _>>()?;
-        let operators = operators.iter().collect();
-        let result = libquil_sys::qvm::expectation(&program, operators, request.rng_seed)
-            .map_err(Error::LibquilSysQvm)?;
-        Ok(result)
+        let request = request.clone();
+        run_blocking(options, move || expectation(&request)).await
     }
 
     async fn get_wavefunction(
         &self,
         request: &http::WavefunctionRequest,
-        _options: &QvmOptions,
+        options: &QvmOptions,
     ) -> Result<Vec<u8>, super::Error> {
-        let program = request
-            .compiled_quil
-            .parse()
-            .map_err(Error::LibquilSysQuilc)?;
-        let amplitudes = libquil_sys::qvm::wavefunction(&program, request.rng_seed)
-            .map_err(Error::LibquilSysQvm)?;
-        let amplitudes = amplitudes
-            .into_iter()
-            .flat_map(|c| vec![c.re, c.im])
-            .flat_map(f64::to_be_bytes)
-            .collect();
-        Ok(amplitudes)
+        if request.gate_noise.is_some() || request.measurement_noise.is_some() {
+            return Err(Error::UnsupportedNoise("wavefunction").into());
+        }
+        let request = request.clone();
+        run_blocking(options, move || wavefunction(&request)).await
     }
 }
+
+/// Run `simulate` on a thread where it may block, waiting at most `options.timeout` for it.
+async fn run_blocking<T, F>(options: &QvmOptions, simulate: F) -> Result<T, super::Error>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, Error> + Send + 'static,
+{
+    let task = tokio::task::spawn_blocking(simulate);
+    let joined = match options.timeout {
+        Some(timeout) => tokio::time::timeout(timeout, task)
+            .await
+            .map_err(|_| Error::Timeout(timeout))?,
+        None => task.await,
+    };
+    Ok(joined.map_err(|error| Error::Interrupted(error.to_string()))??)
+}
+
+fn multishot(request: &http::MultishotRequest) -> Result<http::MultishotResponse, Error> {
+    let program = request
+        .compiled_quil
+        .parse()
+        .map_err(Error::LibquilSysQuilc)?;
+    let addresses = request
+        .addresses
+        .iter()
+        .filter_map(|(address, indices)| match indices {
+            AddressRequest::Indices(indices) => Some(
+                indices
+                    .iter()
+                    .copied()
+                    .map(u32::try_from)
+                    .collect::<Result<_, _>>()
+                    .map(|indices| {
+                        (
+                            address.clone(),
+                            libquil_sys::qvm::MultishotAddressRequest::Indices(indices),
+                        )
+                    }),
+            ),
+            AddressRequest::IncludeAll => Some(Ok((
+                address.clone(),
+                libquil_sys::qvm::MultishotAddressRequest::All,
+            ))),
+            AddressRequest::ExcludeAll => None,
+        })
+        .collect::<Result<_, _>>()?;
+    let result = libquil_sys::qvm::multishot(
+        &program,
+        addresses,
+        i32::from(request.trials.get()),
+        request.gate_noise,
+        request.measurement_noise,
+        request.rng_seed,
+    )?;
+    let mut registers = HashMap::with_capacity(result.len());
+    for (address, values) in result {
+        match values {
+            libquil_sys::qvm::MultishotAddressData::Bit(values) => {
+                registers.insert(
+                    address,
+                    RegisterData::I8(
+                        values
+                            .iter()
+                            .map(|v| v.iter().map(|i| i8::try_from(*i)).collect::<Result<_, _>>())
+                            .collect::<Result<_, _>>()
+                            .map_err(Error::InvalidCast)?,
+                    ),
+                );
+            }
+            libquil_sys::qvm::MultishotAddressData::Octet(values) => {
+                registers.insert(
+                    address,
+                    RegisterData::I16(
+                        values
+                            .iter()
+                            .map(|v| {
+                                v.iter()
+                                    .map(|i| i16::try_from(i64::from(*i)))
+                                    .collect::<Result<_, _>>()
+                            })
+                            .collect::<Result<_, _>>()
+                            .map_err(Error::InvalidCast)?,
+                    ),
+                );
+            }
+            libquil_sys::qvm::MultishotAddressData::Integer(values) => {
+                registers.insert(
+                    address,
+                    RegisterData::I64(
+                        values
+                            .iter()
+                            .map(|v| v.iter().map(|i| i64::from(*i)).collect())
+                            .collect(),
+                    ),
+                );
+            }
+            libquil_sys::qvm::MultishotAddressData::Real(values) => {
+                registers.insert(address, RegisterData::F64(values));
+            }
+        }
+    }
+    Ok(http::MultishotResponse { registers })
+}
+
+fn multishot_measure(request: &http::MultishotMeasureRequest) -> Result<Vec<Vec<i64>>, Error> {
+    let program = request
+        .compiled_quil
+        .parse()
+        .map_err(Error::LibquilSysQuilc)?;
+    let qubits = request
+        .qubits
+        .iter()
+        .copied()
+        .map(i32::try_from)
+        .collect::<Result<Vec<_>, _>>()?;
+    let result = libquil_sys::qvm::multishot_measure(
+        &program,
+        qubits.as_slice(),
+        i32::from(request.trials.get()),
+        request.rng_seed,
+    )?;
+    Ok(result
+        .into_iter()
+        .map(|i| i.into_iter().map(i64::from).collect())
+        .collect())
+}
+
+fn expectation(request: &http::ExpectationRequest) -> Result<Vec<f64>, Error> {
+    let program = request
+        .state_preparation
+        .parse()
+        .map_err(Error::LibquilSysQuilc)?;
+    let operators = request
+        .operators
+        .iter()
+        .map(|s| s.parse().map_err(Error::LibquilSysQuilc))
+        .collect::<Result<Vec<_>, _>>()?;
+    let operators = operators.iter().collect();
+    Ok(libquil_sys::qvm::expectation(
+        &program,
+        operators,
+        request.rng_seed,
+    )?)
+}
+
+fn wavefunction(request: &http::WavefunctionRequest) -> Result<Vec<u8>, Error> {
+    let program = request
+        .compiled_quil
+        .parse()
+        .map_err(Error::LibquilSysQuilc)?;
+    let amplitudes = libquil_sys::qvm::wavefunction(&program, request.rng_seed)?;
+    Ok(amplitudes
+        .into_iter()
+        .flat_map(|c| vec![c.re, c.im])
+        .flat_map(f64::to_be_bytes)
+        .collect())
+}
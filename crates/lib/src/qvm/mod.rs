@@ -4,7 +4,7 @@
 use std::{collections::HashMap, num::NonZeroU16, str::FromStr, sync::Arc, time::Duration};
 
 use quil_rs::{
-    instruction::{ArithmeticOperand, Instruction, MemoryReference, Move},
+    instruction::{ArithmeticOperand, Instruction, MemoryReference, Move, Qubit},
     program::ProgramError,
     quil::{Quil, ToQuilError},
     Program,
@@ -12,15 +12,21 @@ use quil_rs::{
 use serde::{Deserialize, Serialize};
 
 pub(crate) use execution::Execution;
+pub use expectation::measure_expectation;
+pub use wavefunction::Wavefunction;
 
 use crate::{executable::Parameters, RegisterData};
 
 use self::http::AddressRequest;
+use self::noise::{NoiseModel, PauliNoise};
 
 mod execution;
+mod expectation;
 pub mod http;
 #[cfg(feature = "libquil")]
 pub mod libquil;
+pub mod noise;
+mod wavefunction;
 
 /// Number of seconds to wait before timing out.
 const DEFAULT_QVM_TIMEOUT: Duration = Duration::from_secs(30);
@@ -185,11 +191,173 @@ pub async fn run_program<C: Client + ?Sized>(
         gate_noise,
         rng_seed,
     );
-    client
-        .run(&request, options)
-        .await
-        .map(|response| QvmResultData::from_memory_map(response.registers))
-        .map_err(Into::into)
+    let response = client.run(&request, options).await?;
+    apply_declared_types(&program, response.registers).map(QvmResultData::from_memory_map)
+}
+
+/// Run a Quil program on the QVM with the given [`NoiseModel`]. See [`run`].
+#[allow(clippy::too_many_arguments)]
+pub async fn run_with_noise_model<C: Client + Send + Sync + ?Sized>(
+    quil: &str,
+    shots: NonZeroU16,
+    addresses: HashMap<String, AddressRequest>,
+    params: &Parameters,
+    noise_model: &NoiseModel,
+    rng_seed: Option<i64>,
+    client: &C,
+    options: &QvmOptions,
+) -> Result<QvmResultData, Error> {
+    let program = Program::from_str(quil).map_err(Error::Parsing)?;
+    run_program_with_noise_model(
+        &program,
+        shots,
+        addresses,
+        params,
+        noise_model,
+        rng_seed,
+        client,
+        options,
+    )
+    .await
+}
+
+/// Run a [`Program`] on the QVM with the given [`NoiseModel`]. The model's Kraus operators and
+/// readout noise are added to the program as `PRAGMA`s, and its [`NoiseModel::gate_noise`] and
+/// [`NoiseModel::measurement_noise`] are sent with the request. See [`run_program`].
+#[allow(clippy::too_many_arguments)]
+pub async fn run_program_with_noise_model<C: Client + ?Sized>(
+    program: &Program,
+    shots: NonZeroU16,
+    addresses: HashMap<String, AddressRequest>,
+    params: &Parameters,
+    noise_model: &NoiseModel,
+    rng_seed: Option<i64>,
+    client: &C,
+    options: &QvmOptions,
+) -> Result<QvmResultData, Error> {
+    run_program(
+        &noise_model.apply_to_program(program),
+        shots,
+        addresses,
+        params,
+        noise_model.measurement_noise().map(PauliNoise::as_tuple),
+        noise_model.gate_noise().map(PauliNoise::as_tuple),
+        rng_seed,
+        client,
+        options,
+    )
+    .await
+}
+
+/// The name of the register which holds the results of [`run_and_measure_program`].
+pub const RUN_AND_MEASURE_REGISTER: &str = "ro";
+
+/// Run a [`Program`] once on the QVM and sample the resulting wavefunction `shots` times,
+/// measuring `qubits`. If `qubits` is empty, every qubit used by the program is measured, in
+/// ascending order.
+///
+/// This is much faster than [`run_program`] for programs that are expensive to simulate, but only
+/// gives correct results for programs whose outcome doesn't depend on mid-circuit measurement or
+/// classical control flow.
+///
+/// The results are returned as a single register named [`RUN_AND_MEASURE_REGISTER`], where each
+/// row is a shot and column `i` holds the measurement of the `i`th qubit.
+#[allow(clippy::too_many_arguments)]
+pub async fn run_and_measure_program<C: Client + ?Sized>(
+    program: &Program,
+    shots: NonZeroU16,
+    qubits: &[u64],
+    params: &Parameters,
+    measurement_noise: Option<(f64, f64, f64)>,
+    gate_noise: Option<(f64, f64, f64)>,
+    rng_seed: Option<i64>,
+    client: &C,
+    options: &QvmOptions,
+) -> Result<QvmResultData, Error> {
+    let qubits = if qubits.is_empty() {
+        let mut used: Vec<u64> = program
+            .get_used_qubits()
+            .iter()
+            .filter_map(|qubit| match qubit {
+                Qubit::Fixed(index) => Some(*index),
+                _ => None,
+            })
+            .collect();
+        used.sort_unstable();
+        used
+    } else {
+        qubits.to_vec()
+    };
+
+    #[cfg(feature = "tracing")]
+    tracing::debug!(
+        %shots,
+        ?qubits,
+        ?params,
+        "executing program on QVM with run-and-measure"
+    );
+    let program = apply_parameters_to_program(program, params)?;
+    let request = http::MultishotMeasureRequest::new(
+        program.to_quil()?,
+        shots,
+        &qubits,
+        measurement_noise,
+        gate_noise,
+        rng_seed,
+    );
+    let measurements = client.run_and_measure(&request, options).await?;
+    let bits = measurements
+        .into_iter()
+        .map(|shot| shot.into_iter().map(|bit| i8::from(bit != 0)).collect())
+        .collect();
+    Ok(QvmResultData::from_memory_map(HashMap::from([(
+        RUN_AND_MEASURE_REGISTER.to_string(),
+        RegisterData::I8(bits),
+    )])))
+}
+
+/// Simulate a [`Program`] once on the QVM and return the resulting [`Wavefunction`].
+///
+/// Measurements in the program collapse the wavefunction as they would on a real device, so the
+/// result is only deterministic for programs without `MEASURE` instructions (or with a fixed
+/// `rng_seed`).
+pub async fn get_program_wavefunction<C: Client + ?Sized>(
+    program: &Program,
+    params: &Parameters,
+    measurement_noise: Option<(f64, f64, f64)>,
+    gate_noise: Option<(f64, f64, f64)>,
+    rng_seed: Option<i64>,
+    client: &C,
+    options: &QvmOptions,
+) -> Result<Wavefunction, Error> {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(?params, "simulating program wavefunction on QVM");
+    let program = apply_parameters_to_program(program, params)?;
+    let request =
+        http::WavefunctionRequest::new(program.to_quil()?, measurement_noise, gate_noise, rng_seed);
+    let bytes = client.get_wavefunction(&request, options).await?;
+    Wavefunction::from_qvm_bytes(&bytes)
+}
+
+/// Decode each register into the [`RegisterData`] variant matching its `DECLARE`d type, rather
+/// than whichever variant the untyped response data happened to fit first.
+fn apply_declared_types(
+    program: &Program,
+    registers: HashMap<String, RegisterData>,
+) -> Result<HashMap<String, RegisterData>, Error> {
+    registers
+        .into_iter()
+        .map(|(name, data)| match program.memory_regions.get(&name) {
+            Some(region) => match data.into_scalar_type(&region.size.data_type) {
+                Ok(data) => Ok((name, data)),
+                Err(_) => Err(Error::RegisterTypeMismatch {
+                    name,
+                    declared: region.size.data_type.to_quil_or_debug(),
+                }),
+            },
+            None => Ok((name, data)),
+        })
+        .collect()
 }
 
 /// Returns a copy of the [`Program`] with the given parameters applied to it.
@@ -200,37 +368,14 @@ pub fn apply_parameters_to_program(
 ) -> Result<Program, Error> {
     let mut new_program = program.clone_without_body_instructions();
 
-    params.iter().try_for_each(|(name, values)| {
-        match program.memory_regions.get(name.as_ref()) {
-            Some(region) => {
-                if region.size.length == values.len() as u64 {
-                    Ok(())
-                } else {
-                    Err(Error::RegionSizeMismatch {
-                        name: name.to_string(),
-                        declared: region.size.length,
-                        parameters: values.len(),
-                    })
-                }
-            }
-            None => Err(Error::RegionNotFound { name: name.clone() }),
-        }
-    })?;
+    params
+        .iter()
+        .try_for_each(|(name, values)| check_parameter(program, name, values))?;
 
     new_program.add_instructions(
         params
             .iter()
-            .flat_map(|(name, values)| {
-                values.iter().enumerate().map(move |(index, value)| {
-                    Instruction::Move(Move {
-                        destination: MemoryReference {
-                            name: name.to_string(),
-                            index: index as u64,
-                        },
-                        source: ArithmeticOperand::LiteralReal(*value),
-                    })
-                })
-            })
+            .flat_map(|(name, values)| parameter_moves(name, values))
             .chain(program.body_instructions().cloned())
             .collect::<Vec<_>>(),
     );
@@ -238,9 +383,46 @@ pub fn apply_parameters_to_program(
     Ok(new_program)
 }
 
+/// Check that `program` declares a memory region `name` which `values` exactly fill.
+fn check_parameter(program: &Program, name: &str, values: &[f64]) -> Result<(), Error> {
+    match program.memory_regions.get(name) {
+        Some(region) => {
+            if region.size.length == values.len() as u64 {
+                Ok(())
+            } else {
+                Err(Error::RegionSizeMismatch {
+                    name: name.to_string(),
+                    declared: region.size.length,
+                    parameters: values.len(),
+                })
+            }
+        }
+        None => Err(Error::RegionNotFound { name: name.into() }),
+    }
+}
+
+/// The `MOVE` instructions which set the memory region `name` to `values`.
+pub(crate) fn parameter_moves<'a>(
+    name: &'a str,
+    values: &'a [f64],
+) -> impl Iterator<Item = Instruction> + 'a {
+    values.iter().enumerate().map(move |(index, value)| {
+        Instruction::Move(Move {
+            destination: MemoryReference {
+                name: name.to_string(),
+                index: index as u64,
+            },
+            source: ArithmeticOperand::LiteralReal(*value),
+        })
+    })
+}
+
 /// Options avaialable for running programs on the QVM.
+///
+/// Construct this with [`QvmOptions::new`] or [`QvmOptions::default`] and its builder methods.
 #[allow(clippy::module_name_repetitions)]
 #[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
 pub struct QvmOptions {
     /// The timeout to use for requests to the QVM. If set to [`None`], there is no timeout.
     pub timeout: Option<Duration>,
@@ -255,6 +437,13 @@ impl QvmOptions {
     pub fn new() -> Self {
         Self { timeout: None }
     }
+
+    /// Set the timeout to use for requests to the QVM. If set to [`None`], there is no timeout.
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
 }
 
 impl Default for QvmOptions {
@@ -268,6 +457,7 @@ impl Default for QvmOptions {
 /// All of the errors that can occur when running a Quil program on QVM.
 #[allow(missing_docs)]
 #[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
 pub enum Error {
     #[error("Error parsing Quil program: {0}")]
     Parsing(#[from] ProgramError),
@@ -283,6 +473,7 @@ pub enum Error {
     },
     #[error("Could not find region {name} for parameter. Are you missing a DECLARE instruction?")]
     RegionNotFound { name: Box<str> },
+    #[cfg(feature = "qvm-http")]
     #[error("Could not communicate with QVM at {qvm_url}")]
     QvmCommunication {
         qvm_url: String,
@@ -290,6 +481,13 @@ pub enum Error {
     },
     #[error("QVM reported a problem running your program: {message}")]
     Qvm { message: String },
+    #[error("Register {name} is declared as {declared}, but the QVM returned data that is not of that type.")]
+    RegisterTypeMismatch { name: String, declared: String },
+    #[error("The QVM returned a wavefunction of {length} bytes, which is not one amplitude for every state of a whole number of qubits.")]
+    InvalidWavefunction { length: usize },
+    #[error("Invalid Pauli operator {operator:?}: expected factors such as \"X0\" or \"Z1\", separated by spaces or '*'.")]
+    InvalidPauliOperator { operator: String },
+    #[cfg(feature = "qvm-http")]
     #[error("The client failed to make the request: {0}")]
     Client(#[from] reqwest::Error),
 }
@@ -301,7 +499,8 @@ mod test {
     use quil_rs::{quil::Quil, Program};
     use rstest::{fixture, rstest};
 
-    use super::apply_parameters_to_program;
+    use super::{apply_declared_types, apply_parameters_to_program, Error};
+    use crate::RegisterData;
 
     #[fixture]
     fn program() -> Program {
@@ -339,4 +538,58 @@ mod test {
         apply_parameters_to_program(&program, &params)
             .expect_err("should error because bar is not a declared memory region in the program");
     }
+
+    #[rstest]
+    fn test_apply_declared_types() {
+        let program = Program::from_str(
+            "DECLARE ro BIT[2]\nDECLARE count INTEGER\nDECLARE theta REAL\nDECLARE big OCTET",
+        )
+        .expect("should parse valid program");
+        let registers = HashMap::from([
+            ("ro".to_string(), RegisterData::I8(vec![vec![0, 1]])),
+            ("count".to_string(), RegisterData::I8(vec![vec![3]])),
+            ("theta".to_string(), RegisterData::I8(vec![vec![1]])),
+            ("big".to_string(), RegisterData::F64(vec![vec![200.0]])),
+        ]);
+        let registers =
+            apply_declared_types(&program, registers).expect("all registers should convert");
+
+        assert_eq!(registers["ro"], RegisterData::I8(vec![vec![0, 1]]));
+        assert_eq!(registers["count"], RegisterData::I64(vec![vec![3]]));
+        assert_eq!(registers["theta"], RegisterData::F64(vec![vec![1.0]]));
+        assert_eq!(registers["big"], RegisterData::I16(vec![vec![200]]));
+
+        let registers = HashMap::from([("ro".to_string(), RegisterData::F64(vec![vec![0.5]]))]);
+        assert!(matches!(
+            apply_declared_types(&program, registers),
+            Err(Error::RegisterTypeMismatch { .. })
+        ));
+
+        let registers = HashMap::from([("big".to_string(), RegisterData::F64(vec![vec![256.0]]))]);
+        assert!(matches!(
+            apply_declared_types(&program, registers),
+            Err(Error::RegisterTypeMismatch { .. })
+        ));
+    }
+
+    #[rstest]
+    fn test_apply_declared_types_widens_losslessly() {
+        let program = Program::from_str("DECLARE count INTEGER[2]\nDECLARE theta REAL")
+            .expect("should parse valid program");
+        let registers = HashMap::from([
+            (
+                "count".to_string(),
+                RegisterData::F64(vec![vec![1.0, 5e10]]),
+            ),
+            ("theta".to_string(), RegisterData::F32(vec![vec![0.5]])),
+        ]);
+        let registers =
+            apply_declared_types(&program, registers).expect("all registers should convert");
+
+        assert_eq!(
+            registers["count"],
+            RegisterData::I64(vec![vec![1, 50_000_000_000]])
+        );
+        assert_eq!(registers["theta"], RegisterData::F64(vec![vec![0.5]]));
+    }
 }
@@ -0,0 +1,716 @@
+//! Typed noise models for the QVM.
+//!
+//! A [`NoiseModel`] describes per-gate noise as a set of Kraus operators and per-qubit readout
+//! noise as an assignment probability matrix. It is serialized into the `PRAGMA ADD-KRAUS` and
+//! `PRAGMA READOUT-POVM` instructions understood by the QVM, and can be imported from (or
+//! exported to) the JSON produced by pyQuil's `NoiseModel.to_dict()`. It can also carry the
+//! [`PauliNoise`] which the QVM applies after every gate and before every measurement.
+//!
+//! Use a [`NoiseModel`] with [`super::run_with_noise_model`],
+//! [`super::run_program_with_noise_model`], or
+//! [`Executable::with_noise_model`](crate::Executable::with_noise_model).
+//!
+//! Noise is sampled by the QVM, so combining a [`NoiseModel`] with a fixed `rng_seed` (see
+//! [`super::run`]) yields reproducible results.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::TryFrom,
+};
+
+use ndarray::{Array2, Axis};
+use num::complex::Complex64;
+use quil_rs::{
+    instruction::{Instruction, Pragma, PragmaArgument},
+    Program,
+};
+use serde::{Deserialize, Serialize};
+
+/// The absolute tolerance used when checking that Kraus operators are trace-preserving and that
+/// assignment probabilities are normalized.
+const TOLERANCE: f64 = 1e-8;
+
+/// A Pauli channel, given as the probabilities of an X, Y, or Z error occurring on a qubit.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PauliNoise {
+    /// The probability of an X error.
+    pub px: f64,
+    /// The probability of a Y error.
+    pub py: f64,
+    /// The probability of a Z error.
+    pub pz: f64,
+}
+
+impl PauliNoise {
+    /// Create a new [`PauliNoise`] from the probabilities of an X, Y, or Z error.
+    #[must_use]
+    pub fn new(px: f64, py: f64, pz: f64) -> Self {
+        Self { px, py, pz }
+    }
+
+    /// The probabilities as the `(px, py, pz)` tuple used by the QVM API.
+    #[must_use]
+    pub fn as_tuple(self) -> (f64, f64, f64) {
+        (self.px, self.py, self.pz)
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        let probabilities = [self.px, self.py, self.pz];
+        if probabilities.iter().all(|p| (0.0..=1.0).contains(p))
+            && probabilities.iter().sum::<f64>() <= 1.0 + TOLERANCE
+        {
+            Ok(())
+        } else {
+            Err(Error::InvalidPauliProbabilities(*self))
+        }
+    }
+
+    /// The Kraus operators of this channel acting on a single qubit.
+    fn kraus_ops(&self) -> Vec<Array2<Complex64>> {
+        let (zero, one, i) = (
+            Complex64::new(0.0, 0.0),
+            Complex64::new(1.0, 0.0),
+            Complex64::i(),
+        );
+        let p_identity = 1.0 - self.px - self.py - self.pz;
+        [
+            (p_identity, [[one, zero], [zero, one]]),
+            (self.px, [[zero, one], [one, zero]]),
+            (self.py, [[zero, -i], [i, zero]]),
+            (self.pz, [[one, zero], [zero, -one]]),
+        ]
+        .iter()
+        .map(|(p, pauli)| ndarray::arr2(pauli) * Complex64::new(p.max(0.0).sqrt(), 0.0))
+        .collect()
+    }
+
+    /// The Kraus operators of this channel acting independently on each of `num_qubits` qubits.
+    fn kraus_ops_on(&self, num_qubits: usize) -> Vec<Array2<Complex64>> {
+        let single = self.kraus_ops();
+        (0..num_qubits).fold(vec![Array2::eye(1)], |ops, _| {
+            ops.iter()
+                .flat_map(|op| {
+                    single
+                        .iter()
+                        .map(move |pauli| ndarray::linalg::kron(op, pauli))
+                })
+                .collect()
+        })
+    }
+}
+
+impl From<(f64, f64, f64)> for PauliNoise {
+    fn from((px, py, pz): (f64, f64, f64)) -> Self {
+        Self::new(px, py, pz)
+    }
+}
+
+/// Noise applied to a single gate acting on a specific set of qubits.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KrausModel {
+    gate: String,
+    params: Vec<f64>,
+    targets: Vec<u64>,
+    kraus_ops: Vec<Array2<Complex64>>,
+    fidelity: Option<f64>,
+}
+
+impl KrausModel {
+    /// Create a new [`KrausModel`], validating that the Kraus operators have the right shape for
+    /// the number of target qubits and that together they are trace-preserving.
+    ///
+    /// # Errors
+    ///
+    /// See [`Error`].
+    pub fn new(
+        gate: impl Into<String>,
+        params: Vec<f64>,
+        targets: Vec<u64>,
+        kraus_ops: Vec<Array2<Complex64>>,
+    ) -> Result<Self, Error> {
+        let model = Self {
+            gate: gate.into(),
+            params,
+            targets,
+            kraus_ops,
+            fidelity: None,
+        };
+        model.validate()?;
+        Ok(model)
+    }
+
+    /// The name of the gate this noise applies to.
+    #[must_use]
+    pub fn gate(&self) -> &str {
+        &self.gate
+    }
+
+    /// The parameters of the gate this noise applies to.
+    #[must_use]
+    pub fn params(&self) -> &[f64] {
+        &self.params
+    }
+
+    /// The qubits the gate acts on.
+    #[must_use]
+    pub fn targets(&self) -> &[u64] {
+        &self.targets
+    }
+
+    /// The Kraus operators describing the noisy gate.
+    #[must_use]
+    pub fn kraus_ops(&self) -> &[Array2<Complex64>] {
+        &self.kraus_ops
+    }
+
+    /// The fidelity of the noisy gate, if one was provided (e.g. when imported from pyQuil).
+    #[must_use]
+    pub fn fidelity(&self) -> Option<f64> {
+        self.fidelity
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        if self.kraus_ops.is_empty() {
+            return Err(Error::EmptyKrausOperators {
+                gate: self.gate.clone(),
+                targets: self.targets.clone(),
+            });
+        }
+
+        let dimension = 1usize
+            .checked_shl(u32::try_from(self.targets.len()).unwrap_or(u32::MAX))
+            .unwrap_or(0);
+        if let Some(op) = self
+            .kraus_ops
+            .iter()
+            .find(|op| op.dim() != (dimension, dimension))
+        {
+            return Err(Error::InvalidKrausShape {
+                gate: self.gate.clone(),
+                targets: self.targets.clone(),
+                expected: dimension,
+                actual: op.dim(),
+            });
+        }
+
+        let sum = self.kraus_ops.iter().fold(
+            Array2::<Complex64>::zeros((dimension, dimension)),
+            |acc, op| acc + op.t().mapv(|c| c.conj()).dot(op),
+        );
+        let identity = Array2::<Complex64>::eye(dimension);
+        if sum
+            .iter()
+            .zip(identity.iter())
+            .any(|(actual, expected)| (actual - expected).norm() > TOLERANCE)
+        {
+            return Err(Error::NotTracePreserving {
+                gate: self.gate.clone(),
+                targets: self.targets.clone(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// The `PRAGMA ADD-KRAUS` instructions for this gate, one per Kraus operator.
+    fn to_pragmas(&self) -> impl Iterator<Item = Instruction> + '_ {
+        self.kraus_ops.iter().map(move |op| {
+            let arguments = std::iter::once(PragmaArgument::Identifier(self.gate.clone()))
+                .chain(self.targets.iter().copied().map(PragmaArgument::Integer))
+                .collect();
+            let data = format!(
+                "({})",
+                op.iter()
+                    .map(|c| format_complex(*c))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            );
+            Instruction::Pragma(Pragma::new("ADD-KRAUS".to_string(), arguments, Some(data)))
+        })
+    }
+}
+
+/// A noise model for the QVM, made up of per-gate Kraus operators and per-qubit readout
+/// assignment probabilities.
+#[allow(clippy::module_name_repetitions)]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NoiseModel {
+    gates: Vec<KrausModel>,
+    assignment_probs: BTreeMap<u64, Array2<f64>>,
+    gate_noise: Option<PauliNoise>,
+    measurement_noise: Option<PauliNoise>,
+}
+
+impl NoiseModel {
+    /// Start building a new [`NoiseModel`].
+    #[must_use]
+    pub fn builder() -> NoiseModelBuilder {
+        NoiseModelBuilder::default()
+    }
+
+    /// The per-gate noise in this model.
+    #[must_use]
+    pub fn gates(&self) -> &[KrausModel] {
+        &self.gates
+    }
+
+    /// The readout assignment probabilities for each qubit, where entry `[i, j]` is the
+    /// probability of measuring `i` given the qubit was prepared in state `j`.
+    #[must_use]
+    pub fn assignment_probs(&self) -> &BTreeMap<u64, Array2<f64>> {
+        &self.assignment_probs
+    }
+
+    /// The Pauli noise the QVM applies after every gate, if any.
+    #[must_use]
+    pub fn gate_noise(&self) -> Option<PauliNoise> {
+        self.gate_noise
+    }
+
+    /// The Pauli noise the QVM applies before every measurement, if any.
+    #[must_use]
+    pub fn measurement_noise(&self) -> Option<PauliNoise> {
+        self.measurement_noise
+    }
+
+    /// The QVM `PRAGMA` instructions which apply this noise model to a program.
+    #[must_use]
+    pub fn to_pragmas(&self) -> Vec<Instruction> {
+        self.gates
+            .iter()
+            .flat_map(KrausModel::to_pragmas)
+            .chain(self.assignment_probs.iter().map(|(qubit, probs)| {
+                let (p00, p11) = (probs[[0, 0]], probs[[1, 1]]);
+                let data = format!("({} {} {} {})", p00, 1.0 - p11, 1.0 - p00, p11);
+                Instruction::Pragma(Pragma::new(
+                    "READOUT-POVM".to_string(),
+                    vec![PragmaArgument::Integer(*qubit)],
+                    Some(data),
+                ))
+            }))
+            .collect()
+    }
+
+    /// Returns a copy of the [`Program`] with this noise model's `PRAGMA`s prepended to it.
+    #[must_use]
+    pub fn apply_to_program(&self, program: &Program) -> Program {
+        let mut new_program = program.clone_without_body_instructions();
+        new_program.add_instructions(
+            self.to_pragmas()
+                .into_iter()
+                .chain(program.body_instructions().cloned())
+                .collect::<Vec<_>>(),
+        );
+        new_program
+    }
+
+    /// Import a noise model from the JSON produced by pyQuil's `NoiseModel.to_dict()`.
+    ///
+    /// pyQuil's format has no equivalent of [`NoiseModel::gate_noise`] or
+    /// [`NoiseModel::measurement_noise`], so neither is imported or exported.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the JSON is malformed or if the model it describes is invalid.
+    pub fn from_pyquil_json(json: &str) -> Result<Self, Error> {
+        let raw: PyquilNoiseModel = serde_json::from_str(json)?;
+        Self::try_from(raw)
+    }
+
+    /// Export this noise model to JSON that pyQuil's `NoiseModel.from_dict()` can read.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the model could not be serialized.
+    pub fn to_pyquil_json(&self) -> Result<String, Error> {
+        serde_json::to_string(&PyquilNoiseModel::from(self)).map_err(Error::from)
+    }
+}
+
+/// A builder for [`NoiseModel`]. All validation happens in [`NoiseModelBuilder::build`].
+#[allow(clippy::module_name_repetitions)]
+#[derive(Clone, Debug, Default)]
+pub struct NoiseModelBuilder {
+    gates: Vec<KrausModel>,
+    pauli_gates: Vec<(String, Vec<u64>, PauliNoise)>,
+    assignment_probs: BTreeMap<u64, Array2<f64>>,
+    gate_noise: Option<PauliNoise>,
+    measurement_noise: Option<PauliNoise>,
+}
+
+impl NoiseModelBuilder {
+    /// Apply the given Kraus operators whenever `gate` acts on exactly `qubits`.
+    ///
+    /// The QVM matches noisy gates by name and qubits only, so the noise applies regardless of
+    /// the gate's parameters.
+    #[must_use]
+    pub fn gate_noise_for(
+        mut self,
+        gate: impl Into<String>,
+        qubits: impl IntoIterator<Item = u64>,
+        kraus: Vec<Array2<Complex64>>,
+    ) -> Self {
+        self.gates.push(KrausModel {
+            gate: gate.into(),
+            params: Vec::new(),
+            targets: qubits.into_iter().collect(),
+            kraus_ops: kraus,
+            fidelity: None,
+        });
+        self
+    }
+
+    /// Apply `noise` to each qubit independently whenever `gate` acts on exactly `qubits`. This is
+    /// serialized as the equivalent Kraus operators, so see [`NoiseModelBuilder::gate_noise_for`].
+    #[must_use]
+    pub fn pauli_noise_for(
+        mut self,
+        gate: impl Into<String>,
+        qubits: impl IntoIterator<Item = u64>,
+        noise: impl Into<PauliNoise>,
+    ) -> Self {
+        self.pauli_gates
+            .push((gate.into(), qubits.into_iter().collect(), noise.into()));
+        self
+    }
+
+    /// Apply `noise` to the qubits of every gate in the program.
+    #[must_use]
+    pub fn gate_noise(mut self, noise: impl Into<PauliNoise>) -> Self {
+        self.gate_noise = Some(noise.into());
+        self
+    }
+
+    /// Apply `noise` to every qubit before it is measured.
+    #[must_use]
+    pub fn measurement_noise(mut self, noise: impl Into<PauliNoise>) -> Self {
+        self.measurement_noise = Some(noise.into());
+        self
+    }
+
+    /// Apply readout noise to `qubit`, where `p00` and `p11` are the probabilities of correctly
+    /// measuring `0` and `1` respectively.
+    #[must_use]
+    pub fn readout_noise_for(mut self, qubit: u64, p00: f64, p11: f64) -> Self {
+        self.assignment_probs
+            .insert(qubit, ndarray::arr2(&[[p00, 1.0 - p11], [1.0 - p00, p11]]));
+        self
+    }
+
+    /// Build the [`NoiseModel`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any Kraus operators are not trace-preserving or have the wrong shape,
+    /// or if any assignment or Pauli probabilities are not valid probability distributions.
+    pub fn build(mut self) -> Result<NoiseModel, Error> {
+        for (gate, targets, noise) in self.pauli_gates {
+            noise.validate()?;
+            let kraus_ops = noise.kraus_ops_on(targets.len());
+            self.gates.push(KrausModel {
+                gate,
+                params: Vec::new(),
+                targets,
+                kraus_ops,
+                fidelity: None,
+            });
+        }
+        self.gates.iter().try_for_each(KrausModel::validate)?;
+        self.assignment_probs
+            .iter()
+            .try_for_each(|(qubit, probs)| validate_assignment_probs(*qubit, probs))?;
+        self.gate_noise
+            .iter()
+            .chain(&self.measurement_noise)
+            .try_for_each(PauliNoise::validate)?;
+        Ok(NoiseModel {
+            gates: self.gates,
+            assignment_probs: self.assignment_probs,
+            gate_noise: self.gate_noise,
+            measurement_noise: self.measurement_noise,
+        })
+    }
+}
+
+fn validate_assignment_probs(qubit: u64, probs: &Array2<f64>) -> Result<(), Error> {
+    let valid = probs.dim() == (2, 2)
+        && probs.iter().all(|p| (0.0..=1.0).contains(p))
+        && probs
+            .sum_axis(Axis(0))
+            .iter()
+            .all(|sum| (sum - 1.0).abs() <= TOLERANCE);
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::InvalidAssignmentProbabilities { qubit })
+    }
+}
+
+/// Format a complex number the way the QVM expects to read it in a `PRAGMA ADD-KRAUS`.
+fn format_complex(c: Complex64) -> String {
+    if c.im == 0.0 {
+        format!("{}", c.re)
+    } else if c.re == 0.0 {
+        format!("{}i", c.im)
+    } else if c.im < 0.0 {
+        format!("{}-{}i", c.re, -c.im)
+    } else {
+        format!("{}+{}i", c.re, c.im)
+    }
+}
+
+/// The JSON representation of a `KrausModel` used by pyQuil.
+#[derive(Debug, Deserialize, Serialize)]
+struct PyquilKrausModel {
+    gate: String,
+    params: Vec<f64>,
+    targets: Vec<u64>,
+    /// Each operator is stored as a pair of `[real, imaginary]` matrices.
+    kraus_ops: Vec<[Vec<Vec<f64>>; 2]>,
+    fidelity: f64,
+}
+
+/// The JSON representation of a `NoiseModel` used by pyQuil.
+#[derive(Debug, Deserialize, Serialize)]
+struct PyquilNoiseModel {
+    gates: Vec<PyquilKrausModel>,
+    assignment_probs: HashMap<String, Vec<Vec<f64>>>,
+}
+
+fn matrix_from_rows<T: Clone>(rows: &[Vec<T>]) -> Option<Array2<T>> {
+    let ncols = rows.first().map_or(0, Vec::len);
+    let data = rows.iter().flatten().cloned().collect::<Vec<_>>();
+    Array2::from_shape_vec((rows.len(), ncols), data).ok()
+}
+
+fn matrix_to_rows<T: Clone>(matrix: &Array2<T>) -> Vec<Vec<T>> {
+    matrix.outer_iter().map(|row| row.to_vec()).collect()
+}
+
+impl TryFrom<PyquilNoiseModel> for NoiseModel {
+    type Error = Error;
+
+    fn try_from(raw: PyquilNoiseModel) -> Result<Self, Self::Error> {
+        let gates = raw
+            .gates
+            .into_iter()
+            .map(|gate| {
+                let kraus_ops = gate
+                    .kraus_ops
+                    .iter()
+                    .map(|[real, imaginary]| {
+                        let real = matrix_from_rows(real);
+                        let imaginary = matrix_from_rows(imaginary);
+                        match (real, imaginary) {
+                            (Some(real), Some(imaginary)) if real.dim() == imaginary.dim() => {
+                                Ok(ndarray::Zip::from(&real)
+                                    .and(&imaginary)
+                                    .map_collect(|re, im| Complex64::new(*re, *im)))
+                            }
+                            _ => Err(Error::MalformedMatrix {
+                                gate: gate.gate.clone(),
+                                targets: gate.targets.clone(),
+                            }),
+                        }
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(KrausModel {
+                    gate: gate.gate,
+                    params: gate.params,
+                    targets: gate.targets,
+                    kraus_ops,
+                    fidelity: Some(gate.fidelity),
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let assignment_probs = raw
+            .assignment_probs
+            .into_iter()
+            .map(|(qubit, rows)| {
+                let qubit = qubit
+                    .parse::<u64>()
+                    .map_err(|_| Error::InvalidQubit(qubit.clone()))?;
+                let probs = matrix_from_rows(&rows)
+                    .ok_or(Error::InvalidAssignmentProbabilities { qubit })?;
+                Ok((qubit, probs))
+            })
+            .collect::<Result<BTreeMap<_, _>, Error>>()?;
+
+        NoiseModelBuilder {
+            gates,
+            assignment_probs,
+            ..NoiseModelBuilder::default()
+        }
+        .build()
+    }
+}
+
+impl From<&NoiseModel> for PyquilNoiseModel {
+    fn from(model: &NoiseModel) -> Self {
+        Self {
+            gates: model
+                .gates
+                .iter()
+                .map(|gate| PyquilKrausModel {
+                    gate: gate.gate.clone(),
+                    params: gate.params.clone(),
+                    targets: gate.targets.clone(),
+                    kraus_ops: gate
+                        .kraus_ops
+                        .iter()
+                        .map(|op| {
+                            [
+                                matrix_to_rows(&op.mapv(|c| c.re)),
+                                matrix_to_rows(&op.mapv(|c| c.im)),
+                            ]
+                        })
+                        .collect(),
+                    fidelity: gate.fidelity.unwrap_or(1.0),
+                })
+                .collect(),
+            assignment_probs: model
+                .assignment_probs
+                .iter()
+                .map(|(qubit, probs)| (qubit.to_string(), matrix_to_rows(probs)))
+                .collect(),
+        }
+    }
+}
+
+/// Errors that can occur when building or importing a [`NoiseModel`].
+#[allow(missing_docs)]
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    #[error("No Kraus operators were given for gate {gate} on qubits {targets:?}")]
+    EmptyKrausOperators { gate: String, targets: Vec<u64> },
+    #[error("Kraus operators for gate {gate} on qubits {targets:?} must be {expected}x{expected}, but found {actual:?}")]
+    InvalidKrausShape {
+        gate: String,
+        targets: Vec<u64>,
+        expected: usize,
+        actual: (usize, usize),
+    },
+    #[error("Kraus operators for gate {gate} on qubits {targets:?} are not trace-preserving")]
+    NotTracePreserving { gate: String, targets: Vec<u64> },
+    #[error("Kraus operator for gate {gate} on qubits {targets:?} is not a well-formed matrix")]
+    MalformedMatrix { gate: String, targets: Vec<u64> },
+    #[error("Assignment probabilities for qubit {qubit} must be a 2x2 column-stochastic matrix")]
+    InvalidAssignmentProbabilities { qubit: u64 },
+    #[error("Pauli noise probabilities must each be between 0 and 1 and sum to at most 1, but found {0:?}")]
+    InvalidPauliProbabilities(PauliNoise),
+    #[error("Invalid qubit index in noise model: {0}")]
+    InvalidQubit(String),
+    #[error("Could not (de)serialize noise model JSON: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use ndarray::{arr2, Array2};
+    use num::complex::Complex64;
+    use quil_rs::{quil::Quil, Program};
+
+    use super::{Error, NoiseModel, PauliNoise};
+
+    fn real(matrix: [[f64; 2]; 2]) -> Array2<Complex64> {
+        arr2(&matrix).mapv(|re| Complex64::new(re, 0.0))
+    }
+
+    /// A bit flip channel which flips with probability `0.64`.
+    fn bit_flip() -> Vec<Array2<Complex64>> {
+        vec![
+            real([[0.6, 0.0], [0.0, 0.6]]),
+            real([[0.0, 0.8], [0.8, 0.0]]),
+        ]
+    }
+
+    #[test]
+    fn test_rejects_non_trace_preserving_kraus_operators() {
+        let result = NoiseModel::builder()
+            .gate_noise_for("X", [0], vec![real([[0.5, 0.0], [0.0, 0.5]])])
+            .build();
+        assert!(matches!(result, Err(Error::NotTracePreserving { .. })));
+    }
+
+    #[test]
+    fn test_rejects_kraus_operators_with_wrong_shape() {
+        let result = NoiseModel::builder()
+            .gate_noise_for("CZ", [0, 1], bit_flip())
+            .build();
+        assert!(matches!(
+            result,
+            Err(Error::InvalidKrausShape { expected: 4, .. })
+        ));
+    }
+
+    #[test]
+    fn test_serializes_to_pragmas() {
+        let model = NoiseModel::builder()
+            .gate_noise_for("X", [0], bit_flip())
+            .readout_noise_for(0, 0.75, 0.5)
+            .build()
+            .expect("noise model should be valid");
+        let program = Program::from_str("DECLARE ro BIT\nX 0\nMEASURE 0 ro").unwrap();
+        let quil = model.apply_to_program(&program).to_quil_or_debug();
+
+        assert!(quil.contains(r#"PRAGMA ADD-KRAUS X 0 "(0.6 0 0 0.6)""#));
+        assert!(quil.contains(r#"PRAGMA ADD-KRAUS X 0 "(0 0.8 0.8 0)""#));
+        assert!(quil.contains(r#"PRAGMA READOUT-POVM 0 "(0.75 0.5 0.25 0.5)""#));
+        assert!(quil.find("PRAGMA").unwrap() < quil.find("X 0\n").unwrap());
+    }
+
+    #[test]
+    fn test_pauli_noise() {
+        let model = NoiseModel::builder()
+            .pauli_noise_for("CZ", [0, 1], (0.1, 0.0, 0.2))
+            .gate_noise((0.01, 0.01, 0.01))
+            .measurement_noise(PauliNoise::new(0.0, 0.0, 0.05))
+            .build()
+            .expect("Pauli channels should be trace-preserving");
+        assert_eq!(model.gates()[0].kraus_ops().len(), 16);
+        assert_eq!(model.gates()[0].kraus_ops()[0].dim(), (4, 4));
+        assert_eq!(
+            model.gate_noise().map(PauliNoise::as_tuple),
+            Some((0.01, 0.01, 0.01))
+        );
+        assert_eq!(
+            model.measurement_noise(),
+            Some(PauliNoise::new(0.0, 0.0, 0.05))
+        );
+
+        let result = NoiseModel::builder()
+            .pauli_noise_for("X", [0], (0.5, 0.5, 0.5))
+            .build();
+        assert!(matches!(result, Err(Error::InvalidPauliProbabilities(_))));
+        let result = NoiseModel::builder().gate_noise((-0.1, 0.0, 0.0)).build();
+        assert!(matches!(result, Err(Error::InvalidPauliProbabilities(_))));
+    }
+
+    #[test]
+    fn test_pyquil_json_round_trip() {
+        let json = r#"{
+            "gates": [{
+                "gate": "RX",
+                "params": [1.5707963267948966],
+                "targets": [1],
+                "kraus_ops": [
+                    [[[0.0, 0.7071067811865476], [0.7071067811865476, 0.0]], [[0.0, 0.0], [0.0, 0.0]]],
+                    [[[0.0, 0.0], [0.0, 0.0]], [[0.0, -0.7071067811865476], [0.7071067811865476, 0.0]]]
+                ],
+                "fidelity": 0.95
+            }],
+            "assignment_probs": {"1": [[0.95, 0.1], [0.05, 0.9]]}
+        }"#;
+        let model = NoiseModel::from_pyquil_json(json).expect("should import pyQuil noise model");
+        assert_eq!(model.gates()[0].targets(), &[1]);
+        assert_eq!(model.gates()[0].fidelity(), Some(0.95));
+
+        let exported = model.to_pyquil_json().expect("should export noise model");
+        let reimported = NoiseModel::from_pyquil_json(&exported).expect("should re-import");
+        assert_eq!(model, reimported);
+    }
+}
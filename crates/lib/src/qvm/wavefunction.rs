@@ -0,0 +1,146 @@
+//! Wavefunctions produced by simulating a program on the QVM.
+
+use std::ops::Range;
+
+use num::complex::Complex64;
+use serde::{Deserialize, Serialize};
+
+use super::Error;
+
+/// The number of bytes the QVM uses to encode a single amplitude: a big-endian `f64` for the real
+/// part followed by one for the imaginary part.
+const BYTES_PER_AMPLITUDE: usize = 16;
+
+/// The wavefunction of a program, as simulated by the QVM.
+///
+/// The QVM simulates every qubit from 0 up to the highest qubit used by the program. Amplitudes are
+/// indexed by the computational basis state they belong to, where bit `i` of the index is the
+/// state of qubit `i`. For example, with two qubits, the amplitude at index `0b10` is that of qubit
+/// 1 being `|1⟩` and qubit 0 being `|0⟩`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Wavefunction {
+    amplitudes: Vec<Complex64>,
+}
+
+impl Wavefunction {
+    /// Decode a wavefunction from the bytes returned by
+    /// [`Client::get_wavefunction`](super::Client::get_wavefunction).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidWavefunction`] if the bytes don't hold exactly one amplitude for
+    /// every basis state of some number of qubits.
+    pub fn from_qvm_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let count = bytes.len() / BYTES_PER_AMPLITUDE;
+        if bytes.len() % BYTES_PER_AMPLITUDE != 0 || !count.is_power_of_two() {
+            return Err(Error::InvalidWavefunction {
+                length: bytes.len(),
+            });
+        }
+
+        let amplitudes = bytes
+            .chunks_exact(BYTES_PER_AMPLITUDE)
+            .map(|chunk| {
+                let (re, im) = chunk.split_at(BYTES_PER_AMPLITUDE / 2);
+                Complex64::new(read_f64(re), read_f64(im))
+            })
+            .collect();
+        Ok(Self { amplitudes })
+    }
+
+    /// Build a wavefunction from the amplitude of each basis state.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidWavefunction`] if there isn't exactly one amplitude for every basis
+    /// state of some number of qubits.
+    pub(crate) fn from_amplitudes(amplitudes: Vec<Complex64>) -> Result<Self, Error> {
+        if !amplitudes.len().is_power_of_two() {
+            return Err(Error::InvalidWavefunction {
+                length: amplitudes.len() * BYTES_PER_AMPLITUDE,
+            });
+        }
+        Ok(Self { amplitudes })
+    }
+
+    /// The amplitude of each basis state, indexed as described in [`Wavefunction`].
+    #[must_use]
+    pub fn amplitudes(&self) -> &[Complex64] {
+        &self.amplitudes
+    }
+
+    /// The number of qubits in the wavefunction.
+    #[must_use]
+    pub fn num_qubits(&self) -> u32 {
+        self.amplitudes.len().trailing_zeros()
+    }
+
+    /// The qubits in the wavefunction, where qubit `i` corresponds to bit `i` of an amplitude's
+    /// index.
+    #[must_use]
+    pub fn qubits(&self) -> Range<u64> {
+        0..u64::from(self.num_qubits())
+    }
+
+    /// The probability of measuring each basis state, indexed the same way as
+    /// [`Wavefunction::amplitudes`].
+    #[must_use]
+    pub fn probabilities(&self) -> Vec<f64> {
+        self.amplitudes.iter().map(Complex64::norm_sqr).collect()
+    }
+}
+
+fn read_f64(bytes: &[u8]) -> f64 {
+    let mut buffer = [0; 8];
+    buffer.copy_from_slice(bytes);
+    f64::from_be_bytes(buffer)
+}
+
+#[cfg(test)]
+mod describe_wavefunction {
+    use num::complex::Complex64;
+
+    use super::{Error, Wavefunction};
+
+    fn encode(amplitudes: &[Complex64]) -> Vec<u8> {
+        amplitudes
+            .iter()
+            .flat_map(|amplitude| {
+                let mut bytes = amplitude.re.to_be_bytes().to_vec();
+                bytes.extend_from_slice(&amplitude.im.to_be_bytes());
+                bytes
+            })
+            .collect()
+    }
+
+    #[test]
+    fn it_decodes_amplitudes() {
+        let half = std::f64::consts::FRAC_1_SQRT_2;
+        let amplitudes = [
+            Complex64::new(half, 0.0),
+            Complex64::new(0.0, 0.0),
+            Complex64::new(0.0, 0.0),
+            Complex64::new(0.0, -half),
+        ];
+        let wavefunction = Wavefunction::from_qvm_bytes(&encode(&amplitudes))
+            .expect("should decode a two qubit wavefunction");
+
+        assert_eq!(wavefunction.amplitudes(), amplitudes);
+        assert_eq!(wavefunction.num_qubits(), 2);
+        assert_eq!(wavefunction.qubits(), 0..2);
+        let probabilities = wavefunction.probabilities();
+        assert!((probabilities[0] - 0.5).abs() < 1e-12);
+        assert!((probabilities[3] - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn it_rejects_partial_wavefunctions() {
+        let amplitudes = [Complex64::new(1.0, 0.0); 3];
+        for bytes in [encode(&amplitudes), vec![0; 20], vec![]] {
+            assert!(matches!(
+                Wavefunction::from_qvm_bytes(&bytes),
+                Err(Error::InvalidWavefunction { length }) if length == bytes.len()
+            ));
+        }
+    }
+}
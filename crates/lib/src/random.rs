@@ -0,0 +1,13 @@
+//! Randomness for spreading out retries, without depending on a random number generator.
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+};
+
+/// A random number in `[0, 1)`, taken from the randomly keyed hasher of a new [`RandomState`].
+#[allow(clippy::cast_precision_loss)]
+pub(crate) fn random_fraction() -> f64 {
+    let bits = RandomState::new().build_hasher().finish() >> 11;
+    bits as f64 / (1u64 << 53) as f64
+}
@@ -1,5 +1,8 @@
+use std::convert::TryFrom;
+
 use enum_as_inner::EnumAsInner;
-use num::complex::Complex32;
+use num::complex::{Complex32, Complex64};
+use quil_rs::instruction::ScalarType;
 use serde::{Deserialize, Serialize};
 
 /// Data resulting from [`Executable::execute_on_qvm`](`crate::Executable::execute_on_qvm`)
@@ -23,16 +26,129 @@ use serde::{Deserialize, Serialize};
 /// If you are interacting with [`RegisterData`] directly, then you should already know what type of data it _should_
 /// have, so you can  use the [`mod@enum_as_inner`] methods (e.g. [`RegisterData::into_i8`]) in order to
 /// convert any variant type to its inner data.
+///
+/// # Widening
+///
+/// Values are only ever converted between variants without loss, so a register may hold a wider
+/// variant than its Quil type suggests, e.g. [`RegisterData::F64`] for an `INTEGER` register
+/// whose values were read back as floats. The lossless conversions are:
+///
+/// - [`RegisterData::I8`] → [`RegisterData::I16`] → [`RegisterData::I32`] → [`RegisterData::I64`]
+/// - [`RegisterData::I8`], [`RegisterData::I16`] and [`RegisterData::I32`] → [`RegisterData::F64`]
+/// - [`RegisterData::I8`] and [`RegisterData::I16`] → [`RegisterData::F32`] → [`RegisterData::F64`]
+/// - [`RegisterData::Complex32`] → [`RegisterData::Complex64`]
+///
+/// Narrowing, such as from [`RegisterData::F64`] to [`RegisterData::I64`], is done only when every
+/// value is a whole number within range. [`RegisterData::I64`] is never converted to
+/// [`RegisterData::F64`], since not every [`i64`] is exactly representable as an [`f64`].
 #[derive(Clone, Debug, Deserialize, EnumAsInner, PartialEq, Serialize)]
 #[serde(untagged)]
 pub enum RegisterData {
-    /// Corresponds to the Quil `BIT` or `OCTET` types.
+    /// Corresponds to the Quil `BIT` type.
     I8(Vec<Vec<i8>>),
     /// Corresponds to the Quil `REAL` type.
     F64(Vec<Vec<f64>>),
-    /// Corresponds to the Quil `INTEGER` type.
+    /// Corresponds to the Quil `OCTET` type, whose values of up to 255 don't fit in an [`i8`].
     I16(Vec<Vec<i16>>),
     /// Results containing complex numbers.
     #[serde(skip)]
     Complex32(Vec<Vec<Complex32>>),
+    /// 32-bit integer values.
+    I32(Vec<Vec<i32>>),
+    /// Single-precision real values.
+    F32(Vec<Vec<f32>>),
+    /// Results containing double-precision complex numbers. Unlike [`RegisterData::Complex32`],
+    /// these are serialized, so that complex values are deserialized at full precision rather
+    /// than being narrowed to the first complex variant.
+    Complex64(Vec<Vec<Complex64>>),
+    /// Corresponds to the Quil `INTEGER` type.
+    I64(Vec<Vec<i64>>),
+}
+
+impl RegisterData {
+    /// Convert this data into the variant that corresponds to the given Quil type
+    /// (`BIT` → [`RegisterData::I8`], `OCTET` → [`RegisterData::I16`], `INTEGER` →
+    /// [`RegisterData::I64`], `REAL` → [`RegisterData::F64`]).
+    ///
+    /// Untyped numeric data (e.g. from the QVM's JSON responses) is decoded into whichever
+    /// variant fits first, so this is needed to get a deterministic variant for each register.
+    /// See [`RegisterData`] for the conversions which are allowed.
+    ///
+    /// # Errors
+    ///
+    /// Returns the original data if it can't be represented as `data_type` without loss, such
+    /// as an `OCTET` outside of `0..=255`.
+    pub(crate) fn into_scalar_type(self, data_type: &ScalarType) -> Result<Self, Self> {
+        if matches!(
+            (data_type, &self),
+            (ScalarType::Bit, Self::I8(_))
+                | (ScalarType::Octet, Self::I16(_))
+                | (ScalarType::Integer, Self::I64(_))
+                | (ScalarType::Real, Self::F64(_))
+        ) {
+            return Ok(self);
+        }
+
+        let converted = match (data_type, &self) {
+            (ScalarType::Bit, data) => data
+                .integers()
+                .and_then(|data| convert(&data, |value| i8::try_from(*value).ok()))
+                .map(Self::I8),
+            (ScalarType::Octet, data) => data
+                .integers()
+                .and_then(|data| convert(&data, |value| u8::try_from(*value).ok().map(i16::from)))
+                .map(Self::I16),
+            (ScalarType::Integer, data) => data.integers().map(Self::I64),
+            (ScalarType::Real, Self::I8(data)) => {
+                Some(Self::F64(map(data, |value| f64::from(*value))))
+            }
+            (ScalarType::Real, Self::I16(data)) => {
+                Some(Self::F64(map(data, |value| f64::from(*value))))
+            }
+            (ScalarType::Real, Self::I32(data)) => {
+                Some(Self::F64(map(data, |value| f64::from(*value))))
+            }
+            (ScalarType::Real, Self::F32(data)) => {
+                Some(Self::F64(map(data, |value| f64::from(*value))))
+            }
+            (ScalarType::Real, _) => None,
+        };
+        converted.ok_or(self)
+    }
+
+    /// The values as [`i64`]s, if they are all whole numbers.
+    fn integers(&self) -> Option<Vec<Vec<i64>>> {
+        match self {
+            Self::I8(data) => Some(map(data, |value| i64::from(*value))),
+            Self::I16(data) => Some(map(data, |value| i64::from(*value))),
+            Self::I32(data) => Some(map(data, |value| i64::from(*value))),
+            Self::I64(data) => Some(data.clone()),
+            Self::F64(data) => convert(data, |value| whole_number(*value)),
+            Self::F32(_) | Self::Complex32(_) | Self::Complex64(_) => None,
+        }
+    }
+}
+
+/// Convert every value in `data` with `f`.
+fn map<T, U>(data: &[Vec<T>], f: impl Fn(&T) -> U) -> Vec<Vec<U>> {
+    data.iter()
+        .map(|shot| shot.iter().map(&f).collect())
+        .collect()
+}
+
+/// Convert every value in `data` with `f`, or return `None` if any value can't be converted.
+fn convert<T, U>(data: &[Vec<T>], f: impl Fn(&T) -> Option<U>) -> Option<Vec<Vec<U>>> {
+    data.iter()
+        .map(|shot| shot.iter().map(&f).collect())
+        .collect()
+}
+
+/// Returns `value` as a `T` if it is a whole number within the range of `T`.
+#[allow(clippy::cast_possible_truncation)]
+fn whole_number<T: TryFrom<i64>>(value: f64) -> Option<T> {
+    if value.fract().abs() < f64::EPSILON {
+        T::try_from(value as i64).ok()
+    } else {
+        None
+    }
 }
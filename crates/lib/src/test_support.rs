@@ -0,0 +1,76 @@
+//! Fixtures and stand-ins shared by the unit tests of this crate.
+
+#[cfg(feature = "qcs-api")]
+use std::fs::File;
+use std::sync::Mutex;
+
+#[cfg(feature = "qcs-api")]
+use qcs_api_client_openapi::models::InstructionSetArchitecture;
+
+use crate::qvm::{self, http, QvmOptions};
+use crate::RegisterData;
+
+/// The ISA of Aspen-9, as saved in `tests/aspen_9_isa.json`.
+#[cfg(feature = "qcs-api")]
+pub(crate) fn aspen_9_isa() -> InstructionSetArchitecture {
+    serde_json::from_reader(File::open("tests/aspen_9_isa.json").unwrap()).unwrap()
+}
+
+/// The ISA of a fully connected QVM, as saved in `tests/qvm_isa.json`.
+#[cfg(feature = "qcs-api")]
+pub(crate) fn qvm_isa() -> InstructionSetArchitecture {
+    serde_json::from_reader(File::open("tests/qvm_isa.json").unwrap()).unwrap()
+}
+
+/// A QVM which records the program of every run, and answers it with a single `1` in `ro` for
+/// each shot.
+#[derive(Debug, Default)]
+pub(crate) struct RecordingQvm {
+    pub(crate) programs: Mutex<Vec<String>>,
+}
+
+#[async_trait::async_trait]
+impl qvm::Client for RecordingQvm {
+    async fn get_version_info(&self, _: &QvmOptions) -> Result<String, qvm::Error> {
+        Ok("1.17.1".to_string())
+    }
+
+    async fn run(
+        &self,
+        request: &http::MultishotRequest,
+        _: &QvmOptions,
+    ) -> Result<http::MultishotResponse, qvm::Error> {
+        self.programs
+            .lock()
+            .unwrap()
+            .push(request.compiled_quil.clone());
+        let shots = vec![vec![1]; request.trials.get().into()];
+        Ok(http::MultishotResponse {
+            registers: [("ro".to_string(), RegisterData::I8(shots))].into(),
+        })
+    }
+
+    async fn run_and_measure(
+        &self,
+        _: &http::MultishotMeasureRequest,
+        _: &QvmOptions,
+    ) -> Result<Vec<Vec<i64>>, qvm::Error> {
+        unimplemented!("not used by these tests")
+    }
+
+    async fn measure_expectation(
+        &self,
+        _: &http::ExpectationRequest,
+        _: &QvmOptions,
+    ) -> Result<Vec<f64>, qvm::Error> {
+        unimplemented!("not used by these tests")
+    }
+
+    async fn get_wavefunction(
+        &self,
+        _: &http::WavefunctionRequest,
+        _: &QvmOptions,
+    ) -> Result<Vec<u8>, qvm::Error> {
+        unimplemented!("not used by these tests")
+    }
+}
@@ -0,0 +1,16 @@
+//! Stand-ins for QCS, QPUs, and the QVM, so that code which runs [`Executable`](crate::Executable)s
+//! can be tested deterministically, without network access or a local QVM.
+//!
+//! - [`MockQpu`] serves canned results to programs run on a QPU.
+//! - [`MockQvmClient`] is a [`qvm::Client`](crate::qvm::Client) which returns canned data.
+//!
+//! To compile programs without quilc, see `compiler::mock_quilc`, which requires the
+//! `mock-quilc` feature.
+//!
+//! This module requires the `testing` feature.
+
+mod qpu;
+mod qvm;
+
+pub use qpu::{integer_result, Error, MockQpu, MOCK_ENDPOINT_ID};
+pub use qvm::{MockQvmClient, MOCK_QVM_VERSION};
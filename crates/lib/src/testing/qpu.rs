@@ -0,0 +1,611 @@
+//! A stand-in for QCS and a QPU, see [`MockQpu`].
+
+use std::collections::{HashMap, VecDeque};
+use std::convert::TryFrom;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use indexmap::IndexMap;
+use qcs_api_client_common::configuration::{
+    AuthServer, ClientConfiguration, OAuthSession, RefreshToken,
+};
+use qcs_api_client_grpc::models::controller::{
+    controller_job_execution_result, readout_values, ControllerJobExecutionResult,
+    EncryptedControllerJob, IntegerReadoutValues, ReadoutValues,
+};
+use qcs_api_client_grpc::models::translation::QuilTranslationMetadata;
+use qcs_api_client_grpc::services::controller::{
+    controller_server::{Controller, ControllerServer},
+    get_controller_job_status_response, BatchExecuteControllerJobsRequest,
+    BatchExecuteControllerJobsResponse, CancelControllerJobsRequest, CancelControllerJobsResponse,
+    ExecuteControllerJobRequest, ExecuteControllerJobResponse, GetControllerJobResultsRequest,
+    GetControllerJobResultsResponse, GetControllerJobStatusRequest, GetControllerJobStatusResponse,
+};
+use qcs_api_client_grpc::services::translation::{
+    translation_server::{Translation, TranslationServer},
+    GetQuantumProcessorQuilCalibrationProgramRequest, QuantumProcessorQuilCalibrationProgram,
+    TranslateQuilToEncryptedControllerJobRequest, TranslateQuilToEncryptedControllerJobResponse,
+};
+use qcs_api_client_openapi::models::{
+    InstructionSetArchitecture, ListQuantumProcessorAccessorsResponse, QuantumProcessorAccessor,
+    QuantumProcessorAccessorType,
+};
+use serde_json::json;
+use tokio::sync::oneshot;
+use tonic::transport::server::TcpIncoming;
+use tonic::{Request, Response, Status};
+use warp::Filter;
+
+use crate::client::Qcs;
+use crate::qpu::api::{ConnectionStrategy, ExecutionOptions, ExecutionOptionsBuilder, JobId};
+
+/// The ID of the endpoint a [`MockQpu`] serves, see [`MockQpu::execution_options`].
+pub const MOCK_ENDPOINT_ID: &str = "mock-endpoint";
+
+/// An unsigned access token which expires in 2100, so that clients of a [`MockQpu`] never need to
+/// refresh it.
+const MOCK_ACCESS_TOKEN: &str = "eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9.\
+                                 eyJzdWIiOiJtb2NrIiwiZXhwIjo0MTAyNDQ0ODAwfQ.bW9jaw";
+
+/// Errors that can occur when starting a [`MockQpu`].
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// A local port couldn't be bound for one of the mock services.
+    #[error("failed to bind a local port for the mock QPU: {0}")]
+    Bind(#[source] Box<dyn std::error::Error + Send + Sync>),
+}
+
+/// Stand-ins for the QCS API, the translation service, and the controller of one QPU, which run
+/// on the current Tokio runtime, so that code running an [`Executable`](crate::Executable) on a
+/// QPU can be tested without QCS.
+///
+/// Nothing is translated or executed. Translation returns an empty program with the readout
+/// mappings given to [`MockQpu::with_readout_mapping`]. Each job submitted is assigned the next
+/// result given to [`MockQpu::with_result`], which is returned when the job's results are
+/// retrieved. The last result is reused once every other result has been assigned, so a single
+/// result serves any number of jobs. Submitting fails if no result was given.
+///
+/// Connect with the client from [`MockQpu::client`] and the options from
+/// [`MockQpu::execution_options`]. The [`Gateway`](ConnectionStrategy::Gateway) and
+/// [`DirectAccess`](ConnectionStrategy::DirectAccess) strategies work too, but QPU addresses and
+/// ISAs are cached by quantum processor ID for the whole process, so mocks which use them should
+/// each have their own ID.
+///
+/// The services stop when the [`MockQpu`] is dropped.
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use qcs::testing::{integer_result, MockQpu};
+/// use qcs::Executable;
+/// # use qcs_api_client_openapi::models::InstructionSetArchitecture;
+/// # fn load_isa() -> InstructionSetArchitecture { unimplemented!() }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let qpu = MockQpu::start("Aspen-M-3", load_isa())
+///     .await?
+///     .with_readout_mapping("ro[0]", "q0")
+///     .with_result(integer_result([("q0", vec![1])], Duration::from_micros(100)));
+///
+/// let data = Executable::from_quil("DECLARE ro BIT\nX 0\nMEASURE 0 ro[0]")
+///     .with_quilc_client(None::<qcs::compiler::rpcq::Client>)
+///     .with_qcs_client(qpu.client())
+///     .execute_on_qpu(qpu.quantum_processor_id(), None, &qpu.execution_options())
+///     .await?;
+///
+/// assert_eq!(qpu.submitted_jobs().len(), 1);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+#[allow(clippy::module_name_repetitions)]
+pub struct MockQpu {
+    quantum_processor_id: String,
+    api_url: String,
+    grpc_url: String,
+    state: Arc<Mutex<State>>,
+    shutdown: Vec<oneshot::Sender<()>>,
+}
+
+/// What a [`MockQpu`] has been given and has been asked to do.
+#[derive(Debug, Default)]
+struct State {
+    readout_mappings: HashMap<String, String>,
+    results: VecDeque<ControllerJobExecutionResult>,
+    /// The result assigned to each job, and whether the job was cancelled, in submission order.
+    jobs: IndexMap<String, (ControllerJobExecutionResult, bool)>,
+    programs: Vec<String>,
+}
+
+impl MockQpu {
+    /// Start the services for the QPU `quantum_processor_id`, which has the instruction set
+    /// architecture `isa`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a local port can't be bound for one of the services.
+    ///
+    /// # Panics
+    ///
+    /// If it isn't called from within a Tokio runtime.
+    pub async fn start<S: Into<String>>(
+        quantum_processor_id: S,
+        isa: InstructionSetArchitecture,
+    ) -> Result<Self, Error> {
+        let quantum_processor_id = quantum_processor_id.into();
+        let state = Arc::new(Mutex::new(State::default()));
+
+        let listener = tokio::net::TcpListener::bind(("127.0.0.1", 0))
+            .await
+            .map_err(|error| Error::Bind(error.into()))?;
+        let grpc_url = format!(
+            "http://{}",
+            listener
+                .local_addr()
+                .map_err(|error| Error::Bind(error.into()))?
+        );
+        let incoming = TcpIncoming::from_listener(listener, true, None).map_err(Error::Bind)?;
+        let (stop_grpc, grpc_stopped) = oneshot::channel();
+        let grpc = tonic::transport::Server::builder()
+            .add_service(TranslationServer::new(MockServices(state.clone())))
+            .add_service(ControllerServer::new(MockServices(state.clone())))
+            .serve_with_incoming_shutdown(incoming, async {
+                let _ = grpc_stopped.await;
+            });
+        tokio::spawn(grpc);
+
+        let (stop_api, api_stopped) = oneshot::channel();
+        let (address, api) = warp::serve(api_routes(&quantum_processor_id, isa, &grpc_url))
+            .try_bind_with_graceful_shutdown(([127, 0, 0, 1], 0), async {
+                let _ = api_stopped.await;
+            })
+            .map_err(|error| Error::Bind(error.into()))?;
+        tokio::spawn(api);
+
+        Ok(Self {
+            quantum_processor_id,
+            api_url: format!("http://{address}"),
+            grpc_url,
+            state,
+            shutdown: vec![stop_grpc, stop_api],
+        })
+    }
+
+    /// Have translation map the memory reference `memory_reference`, such as `ro[0]`, to the
+    /// readout `readout_name`, such as `q0`, under which its values are given in results.
+    #[must_use]
+    pub fn with_readout_mapping<M, R>(self, memory_reference: M, readout_name: R) -> Self
+    where
+        M: Into<String>,
+        R: Into<String>,
+    {
+        self.state()
+            .readout_mappings
+            .insert(memory_reference.into(), readout_name.into());
+        self
+    }
+
+    /// Add `result` to the results assigned to jobs as they are submitted. See [`integer_result`]
+    /// for building one.
+    #[must_use]
+    pub fn with_result(self, result: ControllerJobExecutionResult) -> Self {
+        self.state().results.push_back(result);
+        self
+    }
+
+    /// The QPU this mock stands in for.
+    #[must_use]
+    pub fn quantum_processor_id(&self) -> &str {
+        &self.quantum_processor_id
+    }
+
+    /// A client which connects to this mock, with credentials it accepts.
+    ///
+    /// # Panics
+    ///
+    /// Never: the client's configuration is always valid.
+    #[must_use]
+    pub fn client(&self) -> Qcs {
+        let session = OAuthSession::from_refresh_token(
+            RefreshToken::new("mock".to_string()),
+            AuthServer::new("mock".to_string(), self.api_url.clone()),
+            Some(MOCK_ACCESS_TOKEN.to_string()),
+        );
+        let config = ClientConfiguration::builder()
+            .api_url(self.api_url.clone())
+            .grpc_api_url(self.grpc_url.clone())
+            .oauth_session(Some(session))
+            .build()
+            .expect("every field of the configuration is valid");
+        Qcs::with_config(config)
+    }
+
+    /// Options which connect to this mock's endpoint directly, see [`MOCK_ENDPOINT_ID`].
+    ///
+    /// # Panics
+    ///
+    /// Never: the options are always valid.
+    #[must_use]
+    pub fn execution_options(&self) -> ExecutionOptions {
+        ExecutionOptionsBuilder::default()
+            .connection_strategy(ConnectionStrategy::EndpointId(MOCK_ENDPOINT_ID.to_string()))
+            .build()
+            .expect("the options are valid")
+    }
+
+    /// Every program sent for translation, in the order they were sent.
+    #[must_use]
+    pub fn translated_programs(&self) -> Vec<String> {
+        self.state().programs.clone()
+    }
+
+    /// Every job submitted, in the order they were submitted.
+    #[must_use]
+    pub fn submitted_jobs(&self) -> Vec<JobId> {
+        self.state().jobs.keys().cloned().map(JobId).collect()
+    }
+
+    /// Every job which was cancelled, in the order they were submitted.
+    #[must_use]
+    pub fn cancelled_jobs(&self) -> Vec<JobId> {
+        self.state()
+            .jobs
+            .iter()
+            .filter(|(_, (_, cancelled))| *cancelled)
+            .map(|(job_id, _)| JobId(job_id.clone()))
+            .collect()
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        lock(&self.state)
+    }
+}
+
+impl Drop for MockQpu {
+    fn drop(&mut self) {
+        for stop in self.shutdown.drain(..) {
+            let _ = stop.send(());
+        }
+    }
+}
+
+/// Lock `state`, even if a request handler panicked while holding it.
+fn lock(state: &Mutex<State>) -> MutexGuard<'_, State> {
+    state
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+/// A successful result in which each readout, such as `q0`, has the given integer value for each
+/// shot, and which took `duration` to execute.
+#[must_use]
+pub fn integer_result<I, S>(readouts: I, duration: Duration) -> ControllerJobExecutionResult
+where
+    I: IntoIterator<Item = (S, Vec<i32>)>,
+    S: Into<String>,
+{
+    ControllerJobExecutionResult {
+        memory_values: HashMap::new(),
+        readout_values: readouts
+            .into_iter()
+            .map(|(name, values)| {
+                let values = readout_values::Values::IntegerValues(IntegerReadoutValues { values });
+                (
+                    name.into(),
+                    ReadoutValues {
+                        values: Some(values),
+                    },
+                )
+            })
+            .collect(),
+        status: controller_job_execution_result::Status::Success.into(),
+        status_message: None,
+        execution_duration_microseconds: u64::try_from(duration.as_micros()).unwrap_or(u64::MAX),
+    }
+}
+
+/// The QCS API routes which clients use before connecting over gRPC: token refresh, the ISA, and
+/// the address of the QPU for each connection strategy.
+fn api_routes(
+    quantum_processor_id: &str,
+    isa: InstructionSetArchitecture,
+    grpc_url: &str,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone + Send + Sync + 'static
+{
+    let token = warp::post().and(warp::path!("v1" / "token")).map(|| {
+        warp::reply::json(&json!({
+            "access_token": MOCK_ACCESS_TOKEN,
+            "refresh_token": "mock",
+        }))
+    });
+
+    let endpoint = json!({
+        "address": grpc_url,
+        "addresses": { "grpc": grpc_url },
+        "datacenter": "mock",
+        "healthy": true,
+        "id": MOCK_ENDPOINT_ID,
+        "mock": true,
+        "quantumProcessorIds": [quantum_processor_id],
+    });
+    let endpoint_by_id = {
+        let endpoint = endpoint.clone();
+        warp::get()
+            .and(warp::path!("v1" / "endpoints" / String))
+            .and_then(move |id: String| {
+                let reply = (id == MOCK_ENDPOINT_ID).then(|| warp::reply::json(&endpoint));
+                async move { reply.ok_or_else(warp::reject::not_found) }
+            })
+    };
+
+    let quantum_processor = warp::path("v1")
+        .and(warp::path("quantumProcessors"))
+        .and(warp::path(quantum_processor_id.to_string()));
+    let isa = quantum_processor
+        .clone()
+        .and(warp::path("instructionSetArchitecture"))
+        .and(warp::path::end())
+        .map(move || warp::reply::json(&isa));
+    let default_endpoint = quantum_processor
+        .clone()
+        .and(warp::path("endpoints:getDefault"))
+        .and(warp::path::end())
+        .map(move || warp::reply::json(&endpoint));
+    let accessors = {
+        let accessors = ListQuantumProcessorAccessorsResponse {
+            accessors: vec![QuantumProcessorAccessor {
+                access_type: Some(Box::new(QuantumProcessorAccessorType::GatewayV1)),
+                live: true,
+                rank: Some(0),
+                id: Some(quantum_processor_id.to_string()),
+                url: grpc_url.to_string(),
+            }],
+            next_page_token: None,
+        };
+        quantum_processor
+            .and(warp::path("accessors"))
+            .and(warp::path::end())
+            .map(move || warp::reply::json(&accessors))
+    };
+
+    token
+        .or(endpoint_by_id)
+        .or(warp::get().and(isa.or(default_endpoint).or(accessors)))
+}
+
+/// The translation and controller services of a [`MockQpu`].
+#[derive(Debug)]
+struct MockServices(Arc<Mutex<State>>);
+
+#[tonic::async_trait]
+impl Translation for MockServices {
+    async fn translate_quil_to_encrypted_controller_job(
+        &self,
+        request: Request<TranslateQuilToEncryptedControllerJobRequest>,
+    ) -> Result<Response<TranslateQuilToEncryptedControllerJobResponse>, Status> {
+        let mut state = lock(&self.0);
+        state.programs.push(request.into_inner().quil_program);
+        Ok(Response::new(
+            TranslateQuilToEncryptedControllerJobResponse {
+                job: Some(EncryptedControllerJob {
+                    job: Vec::new(),
+                    encryption: None,
+                }),
+                metadata: Some(QuilTranslationMetadata {
+                    readout_mappings: state.readout_mappings.clone(),
+                }),
+            },
+        ))
+    }
+
+    async fn get_quantum_processor_quil_calibration_program(
+        &self,
+        _request: Request<GetQuantumProcessorQuilCalibrationProgramRequest>,
+    ) -> Result<Response<QuantumProcessorQuilCalibrationProgram>, Status> {
+        Ok(Response::new(QuantumProcessorQuilCalibrationProgram {
+            quil_calibration_program: String::new(),
+        }))
+    }
+}
+
+#[tonic::async_trait]
+impl Controller for MockServices {
+    async fn execute_controller_job(
+        &self,
+        request: Request<ExecuteControllerJobRequest>,
+    ) -> Result<Response<ExecuteControllerJobResponse>, Status> {
+        let mut state = lock(&self.0);
+        let jobs = request.get_ref().execution_configurations.len().max(1);
+        let mut job_execution_ids = Vec::with_capacity(jobs);
+        for _ in 0..jobs {
+            let next = if state.results.len() > 1 {
+                state.results.pop_front()
+            } else {
+                state.results.front().cloned()
+            };
+            let result = next.ok_or_else(|| {
+                Status::failed_precondition("the mock QPU has no results to return")
+            })?;
+            let job_id = format!("mock-job-{}", state.jobs.len());
+            state.jobs.insert(job_id.clone(), (result, false));
+            job_execution_ids.push(job_id);
+        }
+
+        Ok(Response::new(ExecuteControllerJobResponse {
+            job_execution_ids,
+        }))
+    }
+
+    async fn batch_execute_controller_jobs(
+        &self,
+        _request: Request<BatchExecuteControllerJobsRequest>,
+    ) -> Result<Response<BatchExecuteControllerJobsResponse>, Status> {
+        Err(Status::unimplemented(
+            "the mock QPU does not support batch execution",
+        ))
+    }
+
+    async fn get_controller_job_results(
+        &self,
+        request: Request<GetControllerJobResultsRequest>,
+    ) -> Result<Response<GetControllerJobResultsResponse>, Status> {
+        let state = lock(&self.0);
+        let job_id = &request.get_ref().job_execution_id;
+        match state.jobs.get(job_id) {
+            Some((result, false)) => Ok(Response::new(GetControllerJobResultsResponse {
+                result: Some(result.clone()),
+            })),
+            Some((_, true)) => Err(Status::failed_precondition(format!(
+                "job {job_id} was cancelled"
+            ))),
+            None => Err(Status::not_found(format!("no such job: {job_id}"))),
+        }
+    }
+
+    async fn cancel_controller_jobs(
+        &self,
+        request: Request<CancelControllerJobsRequest>,
+    ) -> Result<Response<CancelControllerJobsResponse>, Status> {
+        let mut state = lock(&self.0);
+        let job_ids = &request.get_ref().job_ids;
+        if let Some(job_id) = job_ids.iter().find(|id| !state.jobs.contains_key(*id)) {
+            return Err(Status::not_found(format!("no such job: {job_id}")));
+        }
+        for job_id in job_ids {
+            if let Some((_, cancelled)) = state.jobs.get_mut(job_id) {
+                *cancelled = true;
+            }
+        }
+        Ok(Response::new(CancelControllerJobsResponse::default()))
+    }
+
+    async fn get_controller_job_status(
+        &self,
+        request: Request<GetControllerJobStatusRequest>,
+    ) -> Result<Response<GetControllerJobStatusResponse>, Status> {
+        let state = lock(&self.0);
+        let job_id = &request.get_ref().job_id;
+        let status = match state.jobs.get(job_id) {
+            Some((_, false)) => get_controller_job_status_response::Status::Succeeded,
+            Some((_, true)) => get_controller_job_status_response::Status::Canceled,
+            None => return Err(Status::not_found(format!("no such job: {job_id}"))),
+        };
+        let mut response = GetControllerJobStatusResponse::default();
+        response.set_status(status);
+        Ok(Response::new(response))
+    }
+}
+
+#[cfg(test)]
+mod describe_mock_qpu {
+    use std::{str::FromStr, time::Duration};
+
+    use quil_rs::Program;
+
+    use super::{integer_result, MockQpu};
+    use crate::compiler::{
+        quilc::{self, CompilationResult, CompilerOpts, TargetDevice},
+        CompilerBackend,
+    };
+    use crate::qpu::api::JobStatus;
+    use crate::test_support::aspen_9_isa;
+    use crate::Executable;
+
+    const BELL_STATE: &str = "DECLARE ro BIT[2]\nH 0\nCNOT 0 1\nMEASURE 0 ro[0]\nMEASURE 1 ro[1]";
+
+    /// A compiler which returns programs unchanged.
+    struct Identity;
+
+    impl CompilerBackend for Identity {
+        fn compile_to_native(
+            &self,
+            quil: &str,
+            _: TargetDevice,
+            _: CompilerOpts,
+        ) -> Result<CompilationResult, quilc::Error> {
+            Ok(CompilationResult {
+                program: Program::from_str(quil).map_err(quilc::Error::Parse)?,
+                native_quil_metadata: None,
+            })
+        }
+
+        fn get_version(&self) -> Result<String, quilc::Error> {
+            Ok("identity".to_string())
+        }
+    }
+
+    async fn bell_state_qpu(quantum_processor_id: &str) -> MockQpu {
+        MockQpu::start(quantum_processor_id, aspen_9_isa())
+            .await
+            .unwrap()
+            .with_readout_mapping("ro[0]", "q0")
+            .with_readout_mapping("ro[1]", "q1")
+            .with_result(integer_result(
+                [("q0", vec![0, 1]), ("q1", vec![0, 1])],
+                Duration::from_micros(8675),
+            ))
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn it_returns_canned_results() {
+        let qpu = bell_state_qpu("mock-canned-results").await;
+
+        let data = Executable::from_quil(BELL_STATE)
+            .with_compiler_backend(Some(Identity))
+            .with_qcs_client(qpu.client())
+            .with_shots(std::num::NonZeroU16::new(2).unwrap())
+            .execute_on_qpu(qpu.quantum_processor_id(), None, &qpu.execution_options())
+            .await
+            .unwrap();
+
+        let registers = data.result_data.to_register_map().unwrap();
+        let ro = registers
+            .get_register_matrix("ro")
+            .unwrap()
+            .as_integer()
+            .unwrap();
+        assert_eq!(ro, ndarray::arr2(&[[0, 0], [1, 1]]));
+        assert_eq!(data.duration, Some(Duration::from_micros(8675)));
+        assert_eq!(qpu.translated_programs().len(), 1);
+        assert_eq!(qpu.submitted_jobs().len(), 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn it_cancels_jobs() {
+        let qpu = bell_state_qpu("mock-cancelled-jobs").await;
+        let client = qpu.client();
+        let mut executable = Executable::from_quil(BELL_STATE)
+            .with_compiler_backend(Some(Identity))
+            .with_qcs_client(client.clone());
+
+        let job_handle = executable
+            .submit_to_qpu(qpu.quantum_processor_id(), None, &qpu.execution_options())
+            .await
+            .unwrap();
+        assert_eq!(
+            job_handle.status(&client).await.unwrap(),
+            JobStatus::Succeeded
+        );
+        executable.cancel_qpu_job(job_handle).await.unwrap();
+
+        assert_eq!(qpu.cancelled_jobs(), qpu.submitted_jobs());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn it_refuses_to_submit_without_results() {
+        let qpu = MockQpu::start("mock-without-results", aspen_9_isa())
+            .await
+            .unwrap();
+
+        let result = Executable::from_quil(BELL_STATE)
+            .with_compiler_backend(Some(Identity))
+            .with_qcs_client(qpu.client())
+            .execute_on_qpu(qpu.quantum_processor_id(), None, &qpu.execution_options())
+            .await;
+
+        assert!(result.is_err());
+        assert!(qpu.submitted_jobs().is_empty());
+    }
+}
@@ -0,0 +1,221 @@
+//! A stand-in for a QVM server, see [`MockQvmClient`].
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::qvm::{self, http, QvmOptions};
+use crate::RegisterData;
+
+/// The version a [`MockQvmClient`] reports from `get_version_info`.
+pub const MOCK_QVM_VERSION: &str = "0.0.0-mock";
+
+/// A [`qvm::Client`] which answers every request with canned data, without simulating anything.
+///
+/// - [`run`](qvm::Client::run) returns each register the request asks for, as given to
+///   [`MockQvmClient::with_register`], whatever the number of shots or the indices asked for.
+/// - [`run_and_measure`](qvm::Client::run_and_measure) returns the data given to
+///   [`MockQvmClient::with_measurements`].
+/// - [`measure_expectation`](qvm::Client::measure_expectation) returns the data given to
+///   [`MockQvmClient::with_expectations`].
+/// - [`get_wavefunction`](qvm::Client::get_wavefunction) returns the data given to
+///   [`MockQvmClient::with_wavefunction`].
+/// - `get_version_info` returns [`MOCK_QVM_VERSION`].
+///
+/// A request for data which wasn't given fails with [`qvm::Error::Qvm`], as would a QVM
+/// reporting a problem. The program of every request is recorded, see
+/// [`MockQvmClient::programs`].
+///
+/// ```
+/// use qcs::{testing::MockQvmClient, Executable, RegisterData};
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let qvm = MockQvmClient::new().with_register("ro", RegisterData::I8(vec![vec![1], vec![0]]));
+/// let data = Executable::from_quil("DECLARE ro BIT\nX 0\nMEASURE 0 ro")
+///     .with_shots(std::num::NonZeroU16::new(2).unwrap())
+///     .execute_on_qvm(&qvm)
+///     .await
+///     .unwrap();
+///
+/// assert_eq!(qvm.programs().len(), 1);
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct MockQvmClient {
+    registers: HashMap<String, RegisterData>,
+    measurements: Option<Vec<Vec<i64>>>,
+    expectations: Option<Vec<f64>>,
+    wavefunction: Option<Vec<u8>>,
+    programs: Mutex<Vec<String>>,
+}
+
+impl MockQvmClient {
+    /// A client without any canned data.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return `data` whenever a run asks for the register `name`, replacing any data already
+    /// given for it.
+    #[must_use]
+    pub fn with_register<S: Into<String>>(mut self, name: S, data: RegisterData) -> Self {
+        self.registers.insert(name.into(), data);
+        self
+    }
+
+    /// Return `measurements`, one row of qubit values per shot, from every `run_and_measure`.
+    #[must_use]
+    pub fn with_measurements(mut self, measurements: Vec<Vec<i64>>) -> Self {
+        self.measurements = Some(measurements);
+        self
+    }
+
+    /// Return `expectations`, one per operator, from every `measure_expectation`.
+    #[must_use]
+    pub fn with_expectations(mut self, expectations: Vec<f64>) -> Self {
+        self.expectations = Some(expectations);
+        self
+    }
+
+    /// Return `wavefunction`, encoded as the QVM encodes it, from every `get_wavefunction`. See
+    /// [`Wavefunction::from_qvm_bytes`](crate::qvm::Wavefunction::from_qvm_bytes).
+    #[must_use]
+    pub fn with_wavefunction(mut self, wavefunction: Vec<u8>) -> Self {
+        self.wavefunction = Some(wavefunction);
+        self
+    }
+
+    /// The program of every request made so far, in the order they were made, with parameters
+    /// already applied.
+    ///
+    /// # Panics
+    ///
+    /// If another thread panicked while recording a request.
+    #[must_use]
+    pub fn programs(&self) -> Vec<String> {
+        self.programs.lock().expect("lock is not poisoned").clone()
+    }
+
+    fn record(&self, program: &str) {
+        self.programs
+            .lock()
+            .expect("lock is not poisoned")
+            .push(program.to_string());
+    }
+}
+
+/// The error returned when a request is made for data which wasn't given.
+fn missing(what: &str) -> qvm::Error {
+    qvm::Error::Qvm {
+        message: format!("the mock QVM has no {what}"),
+    }
+}
+
+#[async_trait::async_trait]
+impl qvm::Client for MockQvmClient {
+    async fn get_version_info(&self, _options: &QvmOptions) -> Result<String, qvm::Error> {
+        Ok(MOCK_QVM_VERSION.to_string())
+    }
+
+    async fn run(
+        &self,
+        request: &http::MultishotRequest,
+        _options: &QvmOptions,
+    ) -> Result<http::MultishotResponse, qvm::Error> {
+        self.record(&request.compiled_quil);
+        let registers = request
+            .addresses
+            .iter()
+            .filter(|(_, address)| **address != http::AddressRequest::ExcludeAll)
+            .map(|(name, _)| {
+                self.registers
+                    .get(name)
+                    .map(|data| (name.clone(), data.clone()))
+                    .ok_or_else(|| missing(&format!("data for register {name}")))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(http::MultishotResponse { registers })
+    }
+
+    async fn run_and_measure(
+        &self,
+        request: &http::MultishotMeasureRequest,
+        _options: &QvmOptions,
+    ) -> Result<Vec<Vec<i64>>, qvm::Error> {
+        self.record(&request.compiled_quil);
+        self.measurements
+            .clone()
+            .ok_or_else(|| missing("measurements"))
+    }
+
+    async fn measure_expectation(
+        &self,
+        request: &http::ExpectationRequest,
+        _options: &QvmOptions,
+    ) -> Result<Vec<f64>, qvm::Error> {
+        self.record(&request.state_preparation);
+        self.expectations
+            .clone()
+            .ok_or_else(|| missing("expectations"))
+    }
+
+    async fn get_wavefunction(
+        &self,
+        request: &http::WavefunctionRequest,
+        _options: &QvmOptions,
+    ) -> Result<Vec<u8>, qvm::Error> {
+        self.record(&request.compiled_quil);
+        self.wavefunction
+            .clone()
+            .ok_or_else(|| missing("wavefunction"))
+    }
+}
+
+#[cfg(test)]
+mod describe_mock_qvm_client {
+    use std::num::NonZeroU16;
+
+    use super::{MockQvmClient, MOCK_QVM_VERSION};
+    use crate::qvm::{Client, QvmOptions};
+    use crate::{Executable, RegisterData};
+
+    #[tokio::test]
+    async fn it_returns_canned_registers() {
+        let qvm =
+            MockQvmClient::new().with_register("ro", RegisterData::I8(vec![vec![1], vec![0]]));
+
+        let data = Executable::from_quil("DECLARE ro BIT\nX 0\nMEASURE 0 ro")
+            .with_shots(NonZeroU16::new(2).unwrap())
+            .execute_on_qvm(&qvm)
+            .await
+            .unwrap();
+
+        let ro = data.result_data.to_register_map().unwrap();
+        let ro = ro.get_register_matrix("ro").unwrap().as_integer().unwrap();
+        assert_eq!(ro.column(0).to_vec(), vec![1, 0]);
+        assert_eq!(qvm.programs().len(), 1);
+        assert!(qvm.programs()[0].contains("MEASURE 0 ro"));
+    }
+
+    #[tokio::test]
+    async fn it_fails_for_registers_without_data() {
+        let qvm = MockQvmClient::new();
+
+        let error = Executable::from_quil("DECLARE ro BIT\nX 0\nMEASURE 0 ro")
+            .execute_on_qvm(&qvm)
+            .await
+            .unwrap_err();
+
+        assert!(error.to_string().contains("no data for register ro"));
+    }
+
+    #[tokio::test]
+    async fn it_reports_its_version() {
+        let version = MockQvmClient::new()
+            .get_version_info(&QvmOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(version, MOCK_QVM_VERSION);
+    }
+}
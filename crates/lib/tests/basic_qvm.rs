@@ -1,4 +1,6 @@
-//! These are the integration tests for [`qcs::Executable::execute_on_qvm`].
+//! These are the integration tests for [`qcs::Executable::execute_on_qvm`] and
+//! [`qcs::Executable::execute_and_measure_on_qvm`], and
+//! [`qcs::Executable::wavefunction_on_qvm`].
 //! In order to run them, QVM's web server must be running at localhost:5000.
 
 use std::num::NonZeroU16;
@@ -68,3 +70,79 @@ async fn test_bell_state() {
         assert_eq!(first, second);
     }
 }
+
+#[tokio::test]
+async fn test_program_without_readout() {
+    let data = Executable::from_quil("H 0\nCNOT 0 1")
+        .execute_on_qvm(&qvm_client().await)
+        .await
+        .expect("a program without readout should still run");
+
+    let registers = data
+        .result_data
+        .to_register_map()
+        .expect("empty result data should convert to a register map");
+    assert!(registers.get_register_matrix("ro").is_none());
+}
+
+#[tokio::test]
+async fn test_bell_state_with_run_and_measure() {
+    let shots: NonZeroU16 = NonZeroU16::new(10).expect("value is non-zero");
+
+    let data = Executable::from_quil("H 0\nCNOT 0 1")
+        .with_qcs_client(Qcs::load())
+        .with_shots(shots)
+        .execute_and_measure_on_qvm(&[], &qvm_client().await)
+        .await
+        .expect("Could not run on QVM");
+
+    let ro = data
+        .result_data
+        .to_register_map()
+        .expect("should convert to readout map")
+        .get_register_matrix(qvm::RUN_AND_MEASURE_REGISTER)
+        .expect("should have the run-and-measure register")
+        .as_integer()
+        .expect("register should be integers")
+        .to_owned();
+
+    assert_eq!(ro.shape(), [shots.get().into(), 2]);
+    for shot in ro.rows() {
+        assert_eq!(shot[0], shot[1]);
+    }
+}
+
+#[tokio::test]
+async fn test_bell_state_wavefunction() {
+    let data = Executable::from_quil("H 0\nCNOT 0 1")
+        .with_qcs_client(Qcs::load())
+        .wavefunction_on_qvm(&qvm_client().await)
+        .await
+        .expect("Could not simulate wavefunction on QVM");
+
+    let wavefunction = data
+        .result_data
+        .as_wavefunction()
+        .expect("should be a wavefunction");
+    assert_eq!(wavefunction.num_qubits(), 2);
+
+    let probabilities = wavefunction.probabilities();
+    for (state, expected) in [(0b00, 0.5), (0b01, 0.0), (0b10, 0.0), (0b11, 0.5)] {
+        assert!((probabilities[state] - expected).abs() < 1e-9);
+    }
+}
+
+#[tokio::test]
+async fn test_bell_state_expectation() {
+    let expectations = Executable::from_quil("H 0\nCNOT 0 1")
+        .with_qcs_client(Qcs::load())
+        .expectation_on_qvm(&["Z0 Z1", "Z0", "I"], &qvm_client().await)
+        .await
+        .expect("Could not measure expectation values on QVM");
+
+    let names: Vec<_> = expectations.keys().map(String::as_str).collect();
+    assert_eq!(names, ["Z0 Z1", "Z0", "I"]);
+    assert!((expectations["Z0 Z1"] - 1.0).abs() < 1e-9);
+    assert!(expectations["Z0"].abs() < 1e-9);
+    assert!((expectations["I"] - 1.0).abs() < 1e-9);
+}
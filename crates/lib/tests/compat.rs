@@ -0,0 +1,197 @@
+//! Compatibility tests against recorded pyQuil results.
+//!
+//! Each JSON file in `tests/compat` describes a deterministic program, the parameters to run it
+//! with, and the registers pyQuil returned when running it on the QVM. These tests run the same
+//! programs with this SDK and check that the results have the same shape, type, and values, to
+//! catch semantic drift that would surprise users migrating from pyQuil.
+//!
+//! `tests/compat/record.py` records each fixture's registers with the pyQuil pinned in
+//! `tests/compat/requirements.txt`, and sets its `recorded_with` to that version. Record them with
+//! `cargo make record-compat`. A fixture whose `recorded_with` is `null` hasn't been recorded, and
+//! fails these tests, since its registers are only a guess at what pyQuil returns.
+//!
+//! QVM's web server must be running at localhost:5000. Run with:
+//!
+//! ```sh
+//! cargo test --features compat-tests --test compat
+//! ```
+
+use std::{collections::HashMap, fs, num::NonZeroU16, path::Path};
+
+use qcs::{client::Qcs, qvm, Executable, RegisterMatrix};
+use serde::Deserialize;
+
+const FIXTURES: &str = "tests/compat";
+
+#[derive(Debug, Deserialize)]
+struct Fixture {
+    description: String,
+    program: String,
+    shots: u16,
+    memory: HashMap<String, Vec<f64>>,
+    /// The version of pyQuil which recorded `registers`, or `None` if they haven't been recorded.
+    recorded_with: Option<String>,
+    registers: HashMap<String, RecordedRegister>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RecordedRegister {
+    /// The numpy dtype pyQuil returned for the register, e.g. `int64`.
+    dtype: String,
+    /// The values of the register, indexed by shot and then by memory offset.
+    values: Vec<Vec<serde_json::Number>>,
+}
+
+fn load_fixtures() -> Vec<(String, Fixture)> {
+    let mut fixtures: Vec<_> = fs::read_dir(FIXTURES)
+        .expect("compat fixtures directory should exist")
+        .map(|entry| entry.expect("should read directory entry").path())
+        .filter(|path| {
+            path.extension()
+                .map_or(false, |extension| extension == "json")
+        })
+        .map(|path| {
+            let name = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .expect("fixture name should be valid UTF-8")
+                .to_string();
+            let fixture =
+                serde_json::from_str(&fs::read_to_string(&path).expect("should read fixture"))
+                    .unwrap_or_else(|error| {
+                        panic!("{} is not a valid fixture: {error}", path.display())
+                    });
+            (name, fixture)
+        })
+        .collect();
+    fixtures.sort_by(|(a, _), (b, _)| a.cmp(b));
+    fixtures
+}
+
+async fn run(fixture: &Fixture) -> HashMap<String, RegisterMatrix> {
+    let qvm_client = qvm::http::HttpClient::from(&Qcs::load());
+    let mut exe = Executable::from_quil(fixture.program.as_str())
+        .with_qcs_client(Qcs::load())
+        .with_shots(NonZeroU16::new(fixture.shots).expect("fixture shots should be non-zero"));
+    for name in fixture.registers.keys() {
+        exe = exe.read_from(name.clone());
+    }
+    for (name, values) in &fixture.memory {
+        for (index, value) in values.iter().enumerate() {
+            exe.with_parameter(name.clone(), index, *value);
+        }
+    }
+
+    let data = exe
+        .execute_on_qvm(&qvm_client)
+        .await
+        .expect("program should run on the QVM");
+    let register_map = data
+        .result_data
+        .to_register_map()
+        .expect("QVM results should convert to a register map");
+    fixture
+        .registers
+        .keys()
+        .map(|name| {
+            let matrix = register_map
+                .get_register_matrix(name)
+                .unwrap_or_else(|| panic!("results should include register {name}"))
+                .clone();
+            (name.clone(), matrix)
+        })
+        .collect()
+}
+
+/// Compare a register against pyQuil's recording, returning a description of each difference.
+fn compare(name: &str, actual: &RegisterMatrix, expected: &RecordedRegister) -> Vec<String> {
+    let shape = [
+        expected.values.len(),
+        expected.values.first().map_or(0, Vec::len),
+    ];
+    let (actual_shape, actual_values): ([usize; 2], Vec<f64>) = match actual {
+        RegisterMatrix::Integer(matrix) if expected.dtype.starts_with("int") => (
+            [matrix.nrows(), matrix.ncols()],
+            #[allow(clippy::cast_precision_loss)]
+            matrix.iter().map(|value| *value as f64).collect(),
+        ),
+        RegisterMatrix::Real(matrix) if expected.dtype.starts_with("float") => (
+            [matrix.nrows(), matrix.ncols()],
+            matrix.iter().copied().collect(),
+        ),
+        _ => {
+            return vec![format!(
+                "{name}: pyQuil returned {}, but the SDK returned {actual:?}",
+                expected.dtype
+            )]
+        }
+    };
+
+    if actual_shape != shape {
+        return vec![format!(
+            "{name}: pyQuil returned shape {shape:?}, but the SDK returned {actual_shape:?}"
+        )];
+    }
+
+    expected
+        .values
+        .iter()
+        .flatten()
+        .map(|value| value.as_f64().expect("recorded values should be numbers"))
+        .zip(actual_values)
+        .enumerate()
+        .filter(|(_, (expected, actual))| (expected - actual).abs() > 1e-9)
+        .map(|(index, (expected, actual))| {
+            format!(
+                "{name}[shot {}][{}]: pyQuil returned {expected}, but the SDK returned {actual}",
+                index / shape[1],
+                index % shape[1],
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_fixtures_are_recorded_with_pyquil() {
+    let unrecorded: Vec<_> = load_fixtures()
+        .into_iter()
+        .filter(|(_, fixture)| fixture.recorded_with.is_none())
+        .map(|(name, _)| name)
+        .collect();
+    assert!(
+        unrecorded.is_empty(),
+        "these fixtures haven't been recorded with pyQuil; run `cargo make record-compat`: {}",
+        unrecorded.join(", ")
+    );
+}
+
+#[tokio::test]
+async fn test_results_match_pyquil() {
+    assert!(Path::new(FIXTURES).is_dir());
+    let fixtures = load_fixtures();
+    assert!(!fixtures.is_empty(), "there should be at least one fixture");
+
+    let mut failures = Vec::new();
+    for (name, fixture) in &fixtures {
+        let results = run(fixture).await;
+        let differences: Vec<_> = fixture
+            .registers
+            .iter()
+            .flat_map(|(register, expected)| compare(register, &results[register], expected))
+            .collect();
+        if !differences.is_empty() {
+            failures.push(format!(
+                "{name} ({}; recorded with {}):\n  {}",
+                fixture.description,
+                fixture.recorded_with.as_deref().unwrap_or("nothing"),
+                differences.join("\n  ")
+            ));
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "results differ from pyQuil:\n{}",
+        failures.join("\n")
+    );
+}
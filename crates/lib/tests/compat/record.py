@@ -0,0 +1,56 @@
+"""
+Record pyQuil's results for each of the programs in this directory, overwriting the expected
+``registers`` of each fixture and setting its ``recorded_with`` to the version of pyQuil used.
+
+The programs are deterministic, so re-recording should only change a fixture if pyQuil's
+behavior has changed. Requires the pyQuil pinned in requirements.txt and a QVM running at the
+address in your QCS settings, both of which ``cargo make record-compat`` sets up:
+
+    pip install -r requirements.txt
+    python record.py
+"""
+
+import json
+from pathlib import Path
+
+import pyquil
+from pyquil import Program, get_qc
+
+REQUIREMENTS = Path(__file__).parent / "requirements.txt"
+
+
+def pinned_pyquil() -> str:
+    """The version of pyQuil pinned in requirements.txt."""
+    for line in REQUIREMENTS.read_text().splitlines():
+        if line.startswith("pyquil=="):
+            return line.removeprefix("pyquil==").strip()
+    raise RuntimeError(f"{REQUIREMENTS} doesn't pin pyquil")
+
+
+def main() -> None:
+    pinned = pinned_pyquil()
+    if pyquil.__version__ != pinned:
+        raise RuntimeError(
+            f"fixtures are recorded with pyquil {pinned}, but {pyquil.__version__} is installed"
+        )
+
+    qc = get_qc("9q-square-qvm")
+    for path in sorted(Path(__file__).parent.glob("*.json")):
+        fixture = json.loads(path.read_text())
+        program = Program(fixture["program"]).wrap_in_numshots_loop(fixture["shots"])
+        result = qc.run(program, memory_map=fixture["memory"])
+        register_map = result.get_register_map()
+
+        registers = {}
+        for name in fixture["registers"]:
+            matrix = register_map.get_register_matrix(name).to_ndarray()
+            registers[name] = {"dtype": str(matrix.dtype), "values": matrix.tolist()}
+        fixture["recorded_with"] = f"pyquil {pyquil.__version__}"
+        fixture["registers"] = registers
+
+        path.write_text(json.dumps(fixture, indent=2) + "\n")
+        print(f"recorded {path.name}")
+
+
+if __name__ == "__main__":
+    main()
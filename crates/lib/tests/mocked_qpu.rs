@@ -1,6 +1,7 @@
 //! Use some local servers to stub out real requests to QCS in order to test the end to end flow of
 //! the `qcs` crate.
 
+use std::collections::HashMap;
 use std::time::Duration;
 
 use futures::future;
@@ -9,8 +10,10 @@ use ndarray::arr2;
 use qcs::{
     client::Qcs,
     compiler::rpcq,
-    qpu::api::{ConnectionStrategy, ExecutionOptionsBuilder},
-    Executable,
+    qpu::api::{
+        retrieve_results_by_id, ConnectionStrategy, ExecutionOptions, ExecutionOptionsBuilder,
+    },
+    Executable, JobHandle,
 };
 use qcs_api_client_common::configuration::{SECRETS_PATH_VAR, SETTINGS_PATH_VAR};
 
@@ -51,6 +54,11 @@ async fn test_qcs_against_mocks() {
         1,
         mock_qcs::ACCESSORS_CALL_COUNT.load(std::sync::atomic::Ordering::SeqCst)
     );
+
+    // Collect results from a handle passed between "processes"
+    retrieve_bell_state_from_saved_handle().await;
+    // Or from just the job ID and the readout map from translation
+    retrieve_bell_state_by_id().await;
 }
 
 async fn setup() {
@@ -94,6 +102,75 @@ async fn run_bell_state(connection_strategy: ConnectionStrategy) {
     assert_eq!(result.duration, Some(Duration::from_micros(8675)));
 }
 
+async fn retrieve_bell_state_from_saved_handle() {
+    let saved = {
+        let mut executable = Executable::from_quil(BELL_STATE)
+            .with_quilc_client(Some(quilc_client().await))
+            .with_shots(std::num::NonZeroU16::new(2).expect("value is non-zero"));
+        let job_handle = executable
+            .submit_to_qpu(QPU_ID, None, &ExecutionOptions::default())
+            .await
+            .expect("should submit the program");
+        serde_json::to_string(&job_handle).expect("should serialize the job handle")
+    };
+
+    let job_handle: JobHandle =
+        serde_json::from_str(&saved).expect("should deserialize the job handle");
+    let result = job_handle
+        .retrieve_results(&Qcs::load())
+        .await
+        .expect("should retrieve results without the Executable");
+    assert_eq!(
+        result
+            .result_data
+            .to_register_map()
+            .expect("should convert to RegisterMap")
+            .get_register_matrix("ro")
+            .expect("should have values for `ro`")
+            .as_integer()
+            .expect("`ro` should have integer values"),
+        arr2(&[[0, 1], [0, 1],]),
+    );
+}
+
+async fn retrieve_bell_state_by_id() {
+    let (job_id, readout_map) = {
+        let mut executable = Executable::from_quil(BELL_STATE)
+            .with_quilc_client(Some(quilc_client().await))
+            .with_shots(std::num::NonZeroU16::new(2).expect("value is non-zero"));
+        let job_handle = executable
+            .submit_to_qpu(QPU_ID, None, &ExecutionOptions::default())
+            .await
+            .expect("should submit the program");
+        let readout_map = serde_json::to_string(job_handle.readout_map())
+            .expect("should serialize the readout map");
+        (job_handle.job_id().to_string(), readout_map)
+    };
+
+    let readout_map: HashMap<String, String> =
+        serde_json::from_str(&readout_map).expect("should deserialize the readout map");
+    let result_data = retrieve_results_by_id(
+        &job_id,
+        Some(QPU_ID),
+        &Qcs::load(),
+        &ExecutionOptions::default(),
+        &readout_map,
+    )
+    .await
+    .expect("should retrieve results knowing only the job ID");
+    assert_eq!(result_data.mappings(), &readout_map);
+    assert_eq!(
+        result_data
+            .to_register_map()
+            .expect("should convert to RegisterMap")
+            .get_register_matrix("ro")
+            .expect("should have values for `ro`")
+            .as_integer()
+            .expect("`ro` should have integer values"),
+        arr2(&[[0, 1], [0, 1],]),
+    );
+}
+
 #[allow(dead_code)]
 mod auth_server {
     use serde::{Deserialize, Serialize};
@@ -0,0 +1,26 @@
+//! Guards the public API of this crate against accidental breaking changes.
+//!
+//! Any change to the public API, intentional or not, changes the snapshot. After an intentional
+//! change, update the snapshot with `cargo insta test --test public_api -- --ignored` and review
+//! it with `cargo insta review`.
+
+/// The nightly toolchain used to build rustdoc JSON. Its JSON format must be one the version of
+/// `public-api` in the dev-dependencies understands, so the two are updated together. Keep this in
+/// sync with the `public-api` task in `Makefile.toml`.
+const NIGHTLY_TOOLCHAIN: &str = "nightly-2023-11-13";
+
+#[test]
+#[ignore = "requires a pinned nightly toolchain; run with `cargo make public-api`"]
+fn public_api_matches_snapshot() {
+    let rustdoc_json = rustdoc_json::Builder::default()
+        .toolchain(NIGHTLY_TOOLCHAIN)
+        .manifest_path(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"))
+        .build()
+        .expect("should build rustdoc JSON");
+
+    let public_api = public_api::Builder::from_rustdoc_json(rustdoc_json)
+        .build()
+        .expect("should parse the public API from rustdoc JSON");
+
+    insta::assert_snapshot!(public_api.to_string());
+}
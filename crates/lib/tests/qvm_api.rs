@@ -118,3 +118,69 @@ async fn test_get_wavefunction<C: qvm::Client>(client: C) {
         .await
         .expect("Should be able to get wavefunction");
 }
+
+#[cfg_attr(feature = "libquil", test_case::test_case(libquil_qvm_client() ; "with libquil client"))]
+#[test_case::test_case(http_qvm_client().await ; "with HTTP client")]
+#[tokio::test]
+async fn test_run_leaves_out_excluded_registers<C: qvm::Client>(client: C) {
+    let request = http::MultishotRequest::new(
+        PROGRAM.to_string(),
+        NonZeroU16::new(2).expect("value is non-zero"),
+        HashMap::from([("ro".to_string(), http::AddressRequest::ExcludeAll)]),
+        None,
+        None,
+        Some(1),
+    );
+    let response = client
+        .run(&request, &QvmOptions::default())
+        .await
+        .expect("Should be able to run");
+    assert!(response.registers.is_empty());
+}
+
+#[cfg_attr(feature = "libquil", test_case::test_case(libquil_qvm_client() ; "with libquil client"))]
+#[test_case::test_case(http_qvm_client().await ; "with HTTP client")]
+#[tokio::test]
+async fn test_run_is_reproducible_with_a_seed<C: qvm::Client>(client: C) {
+    let request = http::MultishotRequest::new(
+        PROGRAM.to_string(),
+        NonZeroU16::new(20).expect("value is non-zero"),
+        HashMap::from([("ro".to_string(), http::AddressRequest::IncludeAll)]),
+        Some((0.1, 0.1, 0.1)),
+        Some((0.1, 0.1, 0.1)),
+        Some(7),
+    );
+    let first = client
+        .run(&request, &QvmOptions::default())
+        .await
+        .expect("Should be able to run");
+    let second = client
+        .run(&request, &QvmOptions::default())
+        .await
+        .expect("Should be able to run again");
+    assert_eq!(first.registers, second.registers);
+}
+
+#[cfg_attr(feature = "libquil", test_case::test_case(libquil_qvm_client() ; "with libquil client"))]
+#[test_case::test_case(http_qvm_client().await ; "with HTTP client")]
+#[tokio::test]
+async fn test_run_and_measure_is_reproducible_with_a_seed<C: qvm::Client>(client: C) {
+    let request = http::MultishotMeasureRequest::new(
+        PROGRAM.to_string(),
+        NonZeroU16::new(20).expect("value is non-zero"),
+        &[0, 1],
+        Some((0.1, 0.1, 0.1)),
+        Some((0.1, 0.1, 0.1)),
+        Some(7),
+    );
+    let first = client
+        .run_and_measure(&request, &QvmOptions::default())
+        .await
+        .expect("Should be able to run and measure");
+    let second = client
+        .run_and_measure(&request, &QvmOptions::default())
+        .await
+        .expect("Should be able to run and measure again");
+    assert_eq!(first.len(), 20);
+    assert_eq!(first, second);
+}
@@ -6,6 +6,7 @@ For more information about QCS, see [the QCS documentation](https://docs.rigetti
 ⚠️ This package is still in early development and breaking changes should be expected between minor versions.
 """
 import datetime
+import os
 from enum import Enum
 from typing import Dict, Iterable, List, Optional, Sequence, Tuple, Union, final
 
@@ -17,9 +18,10 @@ from qcs_sdk.compiler.quilc import CompilerOpts, QuilcClient
 from qcs_sdk.qpu import QPUResultData, RawQPUReadoutData
 from qcs_sdk.qpu.api import ExecutionOptions
 from qcs_sdk.qpu.translation import TranslationOptions
-from qcs_sdk.qvm import QVMClient, QVMResultData, RawQVMReadoutData
+from qcs_sdk.qvm import NoiseModel, QVMClient, QVMResultData, RawQVMReadoutData, Wavefunction
 
 from qcs_sdk import _tracing_subscriber as _tracing_subscriber
+from qcs_sdk import cli as cli
 from qcs_sdk import client as client
 from qcs_sdk import compiler as compiler
 from qcs_sdk import qpu as qpu
@@ -46,7 +48,25 @@ class Executable:
         shots: Optional[int] = None,
         quilc_client: Optional[QuilcClient] = None,
         compiler_options: Optional[CompilerOpts] = None,
-    ) -> "Executable": ...
+        noise_model: Optional[NoiseModel] = None,
+        register_indices: Optional[Dict[str, Sequence[int]]] = None,
+        shot_memory_reset: bool = True,
+        deadline: Optional[float] = None,
+    ) -> "Executable":
+        """
+        :param noise_model: A ``NoiseModel`` to simulate when running on a QVM. It has no effect on a QPU,
+            or on ``expectation_on_qvm``.
+        :param register_indices: Memory regions to read only some indices of, such as ``{"ro": [0, 2, 5]}``.
+            The results hold one value per requested index, in the order given, so ``ro[1]`` holds the value
+            of ``ro[2]`` in the program.
+        :param shot_memory_reset: Whether classical memory is reset at the start of every shot. The QVM and QPUs
+            only support ``True``; with ``False``, executions raise an ``ExecutionError`` instead of resetting memory.
+        :param deadline: The number of seconds each QPU execution may take, across compilation, translation,
+            submission, and retrieval, before raising an ``ExecutionError`` naming the stage it was in.
+
+        :raises ValueError: If ``deadline`` is negative or not finite.
+        """
+        ...
     def execute_on_qvm(self, client: QVMClient) -> ExecutionData:
         """
         Execute on a QVM which is accessible via the provided client.
@@ -62,6 +82,68 @@ class Executable:
         :raises `ExecutionError`: If the job fails to execute.
         """
         ...
+    def execute_and_measure_on_qvm(self, client: QVMClient, qubits: Optional[Sequence[int]] = None) -> ExecutionData:
+        """
+        Execute on a QVM using run-and-measure semantics: the program is simulated once and the resulting
+        wavefunction is sampled once per shot, rather than re-simulating the program for every shot.
+
+        Only use this for programs whose outcome doesn't depend on mid-circuit measurement or classical control flow.
+
+        :param qubits: The qubits to measure. If ``None`` or empty, every qubit used by the program is measured,
+            in ascending order.
+
+        The results are returned in the ``ro`` register, with one column per measured qubit.
+
+        :raises `ExecutionError`: If the job fails to execute.
+        """
+        ...
+    async def execute_and_measure_on_qvm_async(
+        self, client: QVMClient, qubits: Optional[Sequence[int]] = None
+    ) -> ExecutionData:
+        """
+        Execute on a QVM using run-and-measure semantics.
+        (async analog of ``Executable.execute_and_measure_on_qvm``.)
+
+        :raises `ExecutionError`: If the job fails to execute.
+        """
+        ...
+    def wavefunction_on_qvm(self, client: QVMClient) -> ExecutionData:
+        """
+        Simulate the program once on a QVM and return its wavefunction.
+
+        The result data is a ``ResultData`` wrapping a ``Wavefunction``, which can be read with
+        ``ResultData.to_wavefunction()``. The number of shots and the registers to read from are not used.
+
+        :raises `ExecutionError`: If the program could not be simulated.
+        """
+        ...
+    async def wavefunction_on_qvm_async(self, client: QVMClient) -> ExecutionData:
+        """
+        Simulate the program once on a QVM and return its wavefunction.
+        (async analog of ``Executable.wavefunction_on_qvm``.)
+
+        :raises `ExecutionError`: If the program could not be simulated.
+        """
+        ...
+    def expectation_on_qvm(self, pauli_operators: Sequence[str], client: QVMClient) -> Dict[str, float]:
+        """
+        Measure the expectation value of each Pauli operator in the state prepared by the program.
+
+        Each operator is a product of single-qubit Paulis such as ``"Z0"``, ``"X0 Y1"``, or ``"Z0*Z1"``.
+        The values are keyed by operator, in the order given. The number of shots and the registers to
+        read from are not used.
+
+        :raises `ExecutionError`: If an operator is invalid or the program could not be simulated.
+        """
+        ...
+    async def expectation_on_qvm_async(self, pauli_operators: Sequence[str], client: QVMClient) -> Dict[str, float]:
+        """
+        Measure the expectation value of each Pauli operator in the state prepared by the program.
+        (async analog of ``Executable.expectation_on_qvm``.)
+
+        :raises `ExecutionError`: If an operator is invalid or the program could not be simulated.
+        """
+        ...
     def execute_on_qpu(
         self,
         quantum_processor_id: str,
@@ -261,11 +343,80 @@ class RegisterMap:
     def __getitem__(self, item: str) -> RegisterMatrix: ...
     def __contains__(self, key: str) -> bool: ...
     def __len__(self) -> int: ...
+    def shapes(self) -> Dict[str, Tuple[int, int, str]]:
+        """
+        Get the shape of each register as a tuple of its number of rows (shots), number of columns, and NumPy
+        dtype name (one of "int64", "float64", or "complex128").
+        """
+        ...
+    def histogram(self, register: str, bit_order: Optional[BitOrder] = None) -> Dict[str, int]:
+        """
+        Count how many shots produced each bitstring read out to ``register``.
+
+        :param bit_order: The order in which to write the bits of the register. Defaults to
+            ``BitOrder.AscendingIndex``.
+
+        :raises KeyError: If there is no such register.
+        :raises ValueError: If the register contains values other than 0 and 1.
+        """
+        ...
+    def probabilities(self, register: str, bit_order: Optional[BitOrder] = None) -> Dict[str, float]:
+        """
+        The fraction of shots which produced each bitstring read out to ``register``. See ``histogram``.
+        """
+        ...
+    def parity(self, register: str) -> List[bool]:
+        """
+        The parity of the bits read out to ``register`` on each shot: ``True`` if an odd number of them were 1.
+
+        :raises KeyError: If there is no such register.
+        :raises ValueError: If the register contains values other than 0 and 1.
+        """
+        ...
+
+@final
+class SpilledResultData:
+    """
+    Results from the QPU which were too large to keep in memory, and so were decoded into temporary files instead.
+    See ``ExecutionOptions.spill_threshold_bytes``. Registers are read back from the files on request, either whole
+    with ``to_register_map`` or a block of shots at a time with ``read_rows``.
+
+    The temporary files are deleted when the last reference to these results is dropped. They can't be pickled;
+    use ``ResultData.to_json`` instead.
+    """
+
+    @property
+    def mappings(self) -> Dict[str, str]:
+        """
+        Get the mappings of a memory reference (ie. "ro[0]") to the readout node (ie. "q0") which produced its values.
+        """
+        ...
+    def to_register_map(self) -> RegisterMap:
+        """
+        Read every register into memory as a ``RegisterMap``.
+
+        :raises OSError: If a temporary file can't be read.
+        """
+        ...
+    def read_rows(self, register: str, start: int, stop: int) -> RegisterMatrix:
+        """
+        Read the rows ``start`` up to ``stop`` of the register ``register``, that is, its values on those shots, into
+        memory.
+
+        :raises KeyError: If there is no such register.
+        :raises OSError: If there are no such rows, or a temporary file can't be read.
+        """
+        ...
+    def shapes(self) -> Dict[str, Tuple[int, int, str]]:
+        """
+        Get the shape of each register without reading it, as in ``RegisterMap.shapes``.
+        """
+        ...
 
 @final
 class ResultData:
     """
-    Represents the two possible types of data returned from either the QVM or a real QPU.
+    Represents the possible types of data returned from either the QVM or a real QPU.
     Each variant contains the original data returned from its respective executor.
 
     ## Usage
@@ -303,6 +454,8 @@ class ResultData:
     ### Variants:
     - ``qvm``: Data returned from the QVM, stored as ``QVMResultData``
     - ``qpu``: Data returned from the QPU, stored as ``QPUResultData``
+    - ``wavefunction``: The wavefunction of a program simulated by the QVM, stored as a ``Wavefunction``
+    - ``spilled``: Data returned from the QPU which was written to temporary files, stored as ``SpilledResultData``
 
     ### Methods (each per variant):
     - ``is_*``: if the underlying values are that type.
@@ -311,7 +464,7 @@ class ResultData:
     - ``from_*``: wrap underlying values as this enum type.
     """
 
-    def __new__(cls, inner: Union[QPUResultData, QVMResultData]) -> "ResultData":
+    def __new__(cls, inner: Union[QPUResultData, QVMResultData, Wavefunction, SpilledResultData]) -> "ResultData":
         """
         Create a new ResultData from either QVM or QPU result data.
         """
@@ -333,28 +486,85 @@ class ResultData:
         necessitate making assumptions about the data that could skew the data in undesirable ways.
         Instead, it's recommended to manually build a matrix from `QPUResultData` that accurately
         selects the last value per-shot based on the program that was run.
+
+        A `Wavefunction` has no registers, so it always raises a `RegisterMatrixConversionError`. A
+        `SpilledResultData` is always copied into memory.
         """
         ...
-    def to_raw_readout_data(self) -> Union[RawQPUReadoutData, RawQVMReadoutData]:
+    def to_raw_readout_data(self) -> Union[RawQPUReadoutData, RawQVMReadoutData, List[complex], RegisterMap]:
         """
         Get the raw data returned from the QVM or QPU. See `RawQPUReadoutData` and
-        `RawQVMReadoutData` for more information.
+        `RawQVMReadoutData` for more information. For a `Wavefunction`, this is its list of amplitudes. For a
+        `SpilledResultData`, whose raw values were never kept, this is its `RegisterMap`.
         """
     def inner(
         self,
-    ) -> Union[QVMResultData, QPUResultData]:
+    ) -> Union[QVMResultData, QPUResultData, Wavefunction, SpilledResultData]:
         """Returns the inner result data"""
         ...
     def is_qvm(self) -> bool: ...
     def is_qpu(self) -> bool: ...
+    def is_wavefunction(self) -> bool: ...
+    def is_spilled(self) -> bool: ...
     def as_qvm(self) -> Optional[QVMResultData]: ...
     def as_qpu(self) -> Optional[QPUResultData]: ...
+    def as_wavefunction(self) -> Optional[Wavefunction]: ...
+    def as_spilled(self) -> Optional[SpilledResultData]: ...
     def to_qvm(self) -> QVMResultData: ...
     def to_qpu(self) -> QPUResultData: ...
+    def to_wavefunction(self) -> Wavefunction: ...
+    def to_spilled(self) -> SpilledResultData: ...
     @staticmethod
     def from_qvm(inner: QVMResultData) -> "ResultData": ...
     @staticmethod
     def from_qpu(inner: QPUResultData) -> "ResultData": ...
+    @staticmethod
+    def from_wavefunction(inner: Wavefunction) -> "ResultData": ...
+    @staticmethod
+    def from_spilled(inner: SpilledResultData) -> "ResultData": ...
+    def to_json(self) -> str:
+        """
+        Serialize to the canonical, versioned JSON representation shared with other languages. See
+        ``ExecutionData.to_json``.
+        """
+        ...
+    @staticmethod
+    def from_json(json: str) -> "ResultData":
+        """
+        Deserialize from the JSON written by ``to_json`` by this or any older version of the SDK.
+
+        :raises ValueError: If the JSON is invalid or was written by a newer version of the schema.
+        """
+        ...
+
+@final
+class ExecutionTimings:
+    """
+    How long a QPU job spent in each stage after it was submitted, for tracking a QPU's utilization.
+    """
+
+    @property
+    def execution(self) -> Optional[datetime.timedelta]:
+        """
+        How long the program ran on the QPU, as reported by the controller. This is the same as
+        ``ExecutionData.duration``.
+        """
+        ...
+    @property
+    def time_to_results(self) -> Optional[datetime.timedelta]:
+        """
+        The time from the job's submission until its results were received, as measured by this client. Besides
+        the job's execution and any time it spent in the QPU's queue, this includes network latency and however
+        long the caller waited before retrieving the results, so it isn't a measure of the queue. ``None`` if the
+        time the job was submitted isn't known.
+        """
+        ...
+    @property
+    def post_processing(self) -> Optional[datetime.timedelta]:
+        """
+        How long it took to decode the results once they were returned.
+        """
+        ...
 
 @final
 class ExecutionData:
@@ -371,6 +581,81 @@ class ExecutionData:
     def duration(self) -> Optional[datetime.timedelta]: ...
     @duration.setter
     def duration(self, duration: Optional[datetime.timedelta]): ...
+    @property
+    def timings(self) -> Optional[ExecutionTimings]:
+        """
+        How long a QPU job spent waiting, executing, and being decoded, or ``None`` if the program was run
+        on the QVM. These are kept when pickling, but not by ``to_json``.
+        """
+        ...
+    @timings.setter
+    def timings(self, timings: Optional[ExecutionTimings]): ...
+    @property
+    def readout_mappings(self) -> Optional[Dict[str, str]]:
+        """
+        Get the mappings of a memory reference (ie. "ro[0]") to the readout node of the QPU's filter pipeline
+        (ie. "q0") which produced its values, or ``None`` if the program was run on the QVM. Use these to apply
+        custom readout mitigation to the values of each node.
+        """
+        ...
+    def to_counts_json(self, bit_order: Optional[BitOrder] = None) -> str:
+        """
+        Convert the results to a JSON object in the counts/memory format used by toolchains such as Qiskit.
+
+        The object maps each register name to ``{"counts": {bitstring: count}, "memory": [bitstring per shot]}``.
+
+        :param bit_order: The order in which to write the bits of each register. Defaults to
+            ``BitOrder.AscendingIndex``; use ``BitOrder.DescendingIndex`` to match Qiskit.
+
+        :raises ValueError: If the results can't be converted to a ``RegisterMap``, or a register contains
+            values other than 0 and 1.
+        """
+        ...
+    def to_json(self) -> str:
+        """
+        Serialize to the canonical, versioned JSON representation shared with other languages, such as::
+
+            {"schema_version": 2, "duration_microseconds": null,
+             "result_data": {"kind": "qvm", "memory": {"ro": {"dtype": "int8", "data": [[0, 1]]}}}}
+
+        Every array is tagged with its ``dtype``, and complex numbers are written as ``[real, imaginary]`` pairs.
+        """
+        ...
+    @staticmethod
+    def from_json(json: str) -> "ExecutionData":
+        """
+        Deserialize from the JSON written by ``to_json`` by this or any older version of the SDK, including the
+        unversioned JSON used to pickle an ``ExecutionData``.
+
+        :raises ValueError: If the JSON is invalid or was written by a newer version of the schema.
+        """
+        ...
+    def export_report(self, path: Union[str, os.PathLike]) -> None:
+        """
+        Write these results, along with the version and build of the SDK, as a report archive (an uncompressed
+        tarball) at ``path``. Use ``qcs_sdk.cli.export_report`` to include the program and diagnostics too.
+
+        :raises RuntimeError: If the archive could not be written.
+        """
+        ...
+    def to_csv(self, path: Union[str, os.PathLike]) -> None:
+        """
+        Write these results as a CSV file at ``path``, with one row per value read out and the columns ``shot``,
+        ``register``, ``index``, ``value``, ``imaginary`` and ``duration_us``, ready to be loaded with
+        ``pandas.read_csv`` or ``polars.read_csv``.
+
+        :raises ValueError: If the results can't be arranged into a ``RegisterMap``.
+        :raises RuntimeError: If the file could not be written.
+        """
+        ...
+
+class BitOrder(Enum):
+    """The order in which the bits of a register are written in a bitstring."""
+
+    AscendingIndex = "AscendingIndex"
+    """Index 0 is the leftmost bit. This matches the order of each row of a ``RegisterMatrix``."""
+    DescendingIndex = "DescendingIndex"
+    """Index 0 is the rightmost bit. This is the convention used by Qiskit."""
 
 @final
 class RegisterData:
@@ -378,10 +663,18 @@ class RegisterData:
     Values present in a register that are one of a set of variants.
 
     Variants:
-    - ``i8``: Corresponds to the Quil ``BIT`` or ``OCTET`` types.
-    - ``i16``: Corresponds to the Quil ``INTEGER`` type.
+    - ``i8``: Corresponds to the Quil ``BIT`` type.
+    - ``i16``: Corresponds to the Quil ``OCTET`` type.
     - ``f64``: Corresponds to the Quil ``REAL`` type.
     - ``complex32``: Results containing complex numbers.
+    - ``i32``: 32-bit integer values.
+    - ``f32``: Single-precision real values.
+    - ``complex64``: Results containing double-precision complex numbers.
+    - ``i64``: Corresponds to the Quil ``INTEGER`` type.
+
+    Values are only converted between variants without loss, so a register may hold a wider variant than its
+    Quil type suggests: ``i8`` widens to ``i16``, then ``i32``, then ``i64``; ``i32`` and ``f32`` widen to
+    ``f64``; and ``complex32`` widens to ``complex64``.
 
     Methods (each per variant):
     - ``is_*``: if the underlying values are that type.
@@ -403,14 +696,26 @@ class RegisterData:
     def is_i16(self) -> bool: ...
     def is_f64(self) -> bool: ...
     def is_complex32(self) -> bool: ...
+    def is_i32(self) -> bool: ...
+    def is_f32(self) -> bool: ...
+    def is_complex64(self) -> bool: ...
+    def is_i64(self) -> bool: ...
     def as_i8(self) -> Optional[List[List[int]]]: ...
     def as_i16(self) -> Optional[List[List[int]]]: ...
     def as_f64(self) -> Optional[List[List[float]]]: ...
     def as_complex32(self) -> Optional[List[List[complex]]]: ...
+    def as_i32(self) -> Optional[List[List[int]]]: ...
+    def as_f32(self) -> Optional[List[List[float]]]: ...
+    def as_complex64(self) -> Optional[List[List[complex]]]: ...
+    def as_i64(self) -> Optional[List[List[int]]]: ...
     def to_i8(self) -> List[List[int]]: ...
     def to_i16(self) -> List[List[int]]: ...
     def to_f64(self) -> List[List[float]]: ...
     def to_complex32(self) -> List[List[complex]]: ...
+    def to_i32(self) -> List[List[int]]: ...
+    def to_f32(self) -> List[List[float]]: ...
+    def to_complex64(self) -> List[List[complex]]: ...
+    def to_i64(self) -> List[List[int]]: ...
     @staticmethod
     def from_i8(inner: Sequence[Sequence[int]]) -> "RegisterData": ...
     @staticmethod
@@ -419,6 +724,14 @@ class RegisterData:
     def from_f64(inner: Sequence[Sequence[float]]) -> "RegisterData": ...
     @staticmethod
     def from_complex32(inner: Sequence[Sequence[complex]]) -> "RegisterData": ...
+    @staticmethod
+    def from_i32(inner: Sequence[Sequence[int]]) -> "RegisterData": ...
+    @staticmethod
+    def from_f32(inner: Sequence[Sequence[float]]) -> "RegisterData": ...
+    @staticmethod
+    def from_complex64(inner: Sequence[Sequence[complex]]) -> "RegisterData": ...
+    @staticmethod
+    def from_i64(inner: Sequence[Sequence[int]]) -> "RegisterData": ...
 
 def reset_logging():
     """
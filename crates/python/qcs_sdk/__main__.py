@@ -0,0 +1,67 @@
+"""
+Command line tooling for the QCS SDK.
+
+Usage::
+
+    python -m qcs_sdk doctor
+    python -m qcs_sdk list-qpus
+    python -m qcs_sdk run program.quil --shots 10 [--qpu Ankaa-3] [--register ro] [--report report.tar]
+"""
+import argparse
+import sys
+from typing import List, Optional
+
+from qcs_sdk import cli, diagnostics
+
+
+def _doctor(_: argparse.Namespace) -> None:
+    print(diagnostics.get_report())
+
+
+def _list_qpus(args: argparse.Namespace) -> None:
+    for quantum_processor_id in cli.list_quantum_processors(timeout=args.timeout):
+        print(quantum_processor_id)
+
+
+def _run(args: argparse.Namespace) -> None:
+    if args.program == "-":
+        quil = sys.stdin.read()
+    else:
+        with open(args.program) as f:
+            quil = f.read()
+    data = cli.run(quil, shots=args.shots, registers=args.register, quantum_processor_id=args.qpu)
+    for name, matrix in data.result_data.to_register_map().items():
+        print(f"{name}: {matrix.to_ndarray().tolist()}")
+    if args.report is not None:
+        cli.export_report(data, args.report, program=quil)
+
+
+def main(argv: Optional[List[str]] = None) -> None:
+    parser = argparse.ArgumentParser(prog="qcs_sdk", description=__doc__, formatter_class=argparse.RawDescriptionHelpFormatter)
+    commands = parser.add_subparsers(dest="command", required=True)
+
+    doctor = commands.add_parser("doctor", help="Print diagnostic information for bug reports.")
+    doctor.set_defaults(func=_doctor)
+
+    list_qpus = commands.add_parser("list-qpus", help="List the available quantum processors.")
+    list_qpus.add_argument("--timeout", type=float, default=None, help="Timeout in seconds.")
+    list_qpus.set_defaults(func=_list_qpus)
+
+    run = commands.add_parser("run", help="Run a Quil program on the QVM or a QPU.")
+    run.add_argument("program", help="Path to a Quil program, or - to read from stdin.")
+    run.add_argument("--shots", type=int, default=1, help="The number of shots to run.")
+    run.add_argument("--qpu", default=None, help="Run on this QPU instead of the QVM.")
+    run.add_argument(
+        "--register", action="append", default=None, help="A memory region to read back. May be repeated."
+    )
+    run.add_argument(
+        "--report", default=None, help="Also write the program, results, and diagnostics to this report archive."
+    )
+    run.set_defaults(func=_run)
+
+    args = parser.parse_args(argv)
+    args.func(args)
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1 @@
+from qcs_sdk.cli import *
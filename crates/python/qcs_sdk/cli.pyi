@@ -0,0 +1,106 @@
+"""
+Operational tooling for the QCS SDK, also available from the command line as ``python -m qcs_sdk``.
+"""
+import os
+from typing import List, Optional, Sequence, Union
+
+from qcs_sdk import ExecutionData
+from qcs_sdk.client import QCSClient
+
+def doctor() -> str:
+    """
+    Gather a report describing the SDK and the services it is configured to use, for use in bug reporting and diagnosis.
+
+    Note: this format is not stable and its content may change between versions.
+    """
+    ...
+
+def export_report(
+    data: ExecutionData,
+    path: Union[str, os.PathLike],
+    program: Optional[str] = None,
+    include_diagnostics: bool = True,
+) -> None:
+    """
+    Write a report archive (an uncompressed tarball) containing ``data``, the version and build of the SDK, and
+    optionally the program that produced it and the same report as ``doctor``, for reproducing a result or
+    attaching to a support ticket.
+
+    :param data: The results to include.
+    :param path: Where to write the archive. An existing file is replaced.
+    :param program: The Quil program which produced ``data``.
+    :param include_diagnostics: Whether to gather and include diagnostics, which contacts the configured services.
+
+    :raises RuntimeError: If the archive could not be written.
+    """
+    ...
+
+def list_quantum_processors(
+    client: Optional[QCSClient] = None,
+    timeout: Optional[float] = None,
+) -> List[str]:
+    """
+    Returns all available Quantum Processor IDs.
+
+    :param client: The ``QCSClient`` to use. Creates one using environment configuration if unset - see https://docs.rigetti.com/qcs/references/qcs-client-configuration for more details.
+    :param timeout: Maximum duration to wait for API calls to complete, in seconds.
+
+    :raises RuntimeError: If there was a problem listing quantum processors.
+    """
+    ...
+
+async def list_quantum_processors_async(
+    client: Optional[QCSClient] = None,
+    timeout: Optional[float] = None,
+) -> List[str]:
+    """
+    Returns all available Quantum Processor IDs.
+    (async analog of ``list_quantum_processors``)
+
+    :param client: The ``QCSClient`` to use. Creates one using environment configuration if unset - see https://docs.rigetti.com/qcs/references/qcs-client-configuration for more details.
+    :param timeout: Maximum duration to wait for API calls to complete, in seconds.
+
+    :raises RuntimeError: If there was a problem listing quantum processors.
+    """
+    ...
+
+def run(
+    quil: str,
+    shots: int = 1,
+    registers: Optional[Sequence[str]] = None,
+    quantum_processor_id: Optional[str] = None,
+    client: Optional[QCSClient] = None,
+) -> ExecutionData:
+    """
+    Run a program on the QVM, or on a QPU if ``quantum_processor_id`` is given (compiling it with quilc first).
+
+    :param quil: A quil program as a string.
+    :param shots: The number of times to run the program.
+    :param registers: The memory regions to read back. Defaults to ``["ro"]``.
+    :param quantum_processor_id: The QPU to run on. If unset, the program is run on the QVM.
+    :param client: The ``QCSClient`` to use. Creates one using environment configuration if unset.
+
+    :raises ExecutionError: If the program could not be compiled or run.
+    """
+    ...
+
+async def run_async(
+    quil: str,
+    shots: int = 1,
+    registers: Optional[Sequence[str]] = None,
+    quantum_processor_id: Optional[str] = None,
+    client: Optional[QCSClient] = None,
+) -> ExecutionData:
+    """
+    Run a program on the QVM, or on a QPU if ``quantum_processor_id`` is given (compiling it with quilc first).
+    (async analog of ``run``)
+
+    :param quil: A quil program as a string.
+    :param shots: The number of times to run the program.
+    :param registers: The memory regions to read back. Defaults to ``["ro"]``.
+    :param quantum_processor_id: The QPU to run on. If unset, the program is run on the QVM.
+    :param client: The ``QCSClient`` to use. Creates one using environment configuration if unset.
+
+    :raises ExecutionError: If the program could not be compiled or run.
+    """
+    ...
@@ -23,11 +23,38 @@ class QCSClient:
         grpc_api_url: Optional[str] = None,
         quilc_url: Optional[str] = None,
         qvm_url: Optional[str] = None,
+        client_application_name: Optional[str] = None,
+        client_application_version: Optional[str] = None,
     ) -> "QCSClient":
         """
         Manually construct a `QCSClient`.
 
         Prefer to use `QCSClient.load` to construct an environment-based profile.
+
+        :param client_application_name: The name of the application using this client. See `with_client_application`.
+        :param client_application_version: The version of the application using this client. Requires ``client_application_name``.
+
+        :raises ValueError: If the client application name or version is invalid.
+        """
+        ...
+    def with_client_application(self, name: str, version: Optional[str] = None) -> "QCSClient":
+        """
+        Return a copy of this client which identifies the application using it in requests to QCS.
+
+        The application is appended to the ``User-Agent`` of HTTP and gRPC requests as ``name/version``.
+
+        :param name: The name of the application. Must be non-empty visible ASCII without ``/``.
+        :param version: The version of the application, with the same restrictions as ``name``.
+
+        :raises ValueError: If the name or version is invalid.
+        """
+        ...
+    def with_read_only(self, read_only: bool) -> "QCSClient":
+        """
+        Return a copy of this client which refuses, or allows, submitting programs to a QPU.
+
+        A read-only client can still compile programs and retrieve the results of jobs which were already submitted.
+        Clients are read-only by default when the ``QCS_READ_ONLY`` environment variable is ``1``, ``true``, or ``yes``.
         """
         ...
     @staticmethod
@@ -62,6 +89,18 @@ class QCSClient:
         """URL to access the QVM."""
         ...
     @property
+    def client_application_name(self) -> Optional[str]:
+        """The name of the application using this client, if set."""
+        ...
+    @property
+    def client_application_version(self) -> Optional[str]:
+        """The version of the application using this client, if set."""
+        ...
+    @property
+    def read_only(self) -> bool:
+        """Whether this client refuses to submit programs to a QPU. See `with_read_only`."""
+        ...
+    @property
     def oauth_session(self) -> OAuthSession:
         """Get a copy of the OAuth session."""
 
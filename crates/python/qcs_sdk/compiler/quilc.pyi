@@ -38,7 +38,16 @@ class CompilerOpts:
         /,
         timeout: Optional[float] = DEFAULT_COMPILER_TIMEOUT,
         protoquil: Optional[bool] = None,
-    ) -> "CompilerOpts": ...
+        specs: bool = False,
+    ) -> "CompilerOpts":
+        """
+        :param timeout: The number of seconds to wait before timing out, or ``None`` for no timeout.
+        :param protoquil: If the compiler should produce "protoquil" as output. If ``None``, the
+            compiler service's default is used.
+        :param specs: If the fidelities measured for each qubit and edge of the device should be
+            sent to the compiler, so that it can prefer the best ones.
+        """
+        ...
     @staticmethod
     def default() -> "CompilerOpts": ...
 
@@ -51,11 +60,15 @@ class TargetDevice:
     @staticmethod
     def from_isa(
         isa: InstructionSetArchitecture,
+        /,
+        specs: bool = False,
     ) -> "TargetDevice":
         """
         Create a ``TargetDevice`` based on an ``InstructionSetArchitecture``.
 
         :param isa: ``InstructionSetArchitecture`` that describes the target device.
+        :param specs: If the fidelities measured for each qubit and edge of the device, such as
+            ``f1QRB`` and ``fCZ``, should be included for the compiler.
 
         :raises QuilcError: If the ``InstructionSetArchitecture`` cannot be converted
         into a format that Quilc understands.
@@ -4,7 +4,7 @@ The QPU module contains the API for interacting with Rigetti Quantum Processing
 🔨 This page is under construction and the documentation for some submodules is missing.
 In the meantime, you can find documentation in the [the type hints](https://github.com/rigetti/qcs-sdk-rust/tree/main/crates/python/qcs_sdk/qpu).
 """
-from typing import Dict, List, Mapping, Sequence, Optional, Union, final
+from typing import Dict, List, Mapping, Sequence, Optional, Tuple, Union, final
 
 from qcs_sdk.client import QCSClient
 
@@ -134,6 +134,13 @@ class QPUResultData:
         Get mapping of a memory region (ie. "ro") to the final contents of that memory region.
         """
         ...
+    def readout_shapes(self) -> Dict[str, Tuple[int, int, str]]:
+        """
+        Get the shape of the readout values for each readout values identifier (ie. "q0") as a tuple of the number
+        of values, the number of columns (always 1), and the NumPy dtype name (one of "int64", "float64", or
+        "complex128"), without copying the values.
+        """
+        ...
     def to_raw_readout_data(
         self,
     ) -> RawQPUReadoutData:
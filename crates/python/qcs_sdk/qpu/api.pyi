@@ -345,6 +345,44 @@ async def retrieve_results_async(
     """
     ...
 
+def get_job_status(
+    job_id: str,
+    quantum_processor_id: Optional[str] = None,
+    client: Optional[QCSClient] = None,
+    execution_options: Optional[ExecutionOptions] = None,
+) -> str:
+    """
+    Ask the controller for the status of the given QCS Job ID, without waiting for the job to finish.
+
+    :param job_id: The ID of the job.
+    :param quantum_processor_id: The ID of the quantum processor the job was submitted to. This field is required, unless being used with the `ConnectionStrategy.endpoint_id()` execution option.
+    :param client: The ``QCSClient`` to use. Creates one using environment configuration if unset - see https://docs.rigetti.com/qcs/references/qcs-client-configuration
+    :param execution_options: The ``ExecutionOptions`` to use. If the connection strategy option used is `ConnectionStrategy.endpoint_id("endpoint_id")`, then direct access to "endpoint_id" overrides the `quantum_processor_id` parameter.
+
+    :returns: One of "queued", "running", "succeeded", "failed", "canceled", or "unknown".
+
+    :raises LoadClientError: If there is an issue loading the QCS Client configuration.
+    :raises QpuApiError: If there was a problem getting the status.
+    """
+    ...
+
+async def get_job_status_async(
+    job_id: str,
+    quantum_processor_id: Optional[str] = None,
+    client: Optional[QCSClient] = None,
+    execution_options: Optional[ExecutionOptions] = None,
+) -> str:
+    """
+    Ask the controller for the status of the given QCS Job ID, without waiting for the job to finish.
+    (async analog of ``get_job_status``)
+
+    :returns: One of "queued", "running", "succeeded", "failed", "canceled", or "unknown".
+
+    :raises LoadClientError: If there is an issue loading the QCS Client configuration.
+    :raises QpuApiError: If there was a problem getting the status.
+    """
+    ...
+
 @final
 class ExecutionOptions:
     @staticmethod
@@ -363,6 +401,64 @@ class ExecutionOptions:
     @property
     def api_options(self) -> bool:
         """Execution options particular to the API call at the point of execution."""
+    @property
+    def max_request_bytes(self) -> Optional[int]:
+        """
+        The maximum size of a single request, in bytes, when submitting a batch of patch values.
+
+        If set, larger batches are split across multiple requests. If ``None`` (the default), batches are never split.
+        """
+    @property
+    def retry_policy(self) -> RetryPolicy:
+        """
+        How to retry requests which the service rejects for a reason that may be temporary, such as being briefly
+        unavailable, unless overridden for a particular request. Failures to reach the service are retried by the
+        connection itself.
+        """
+    @property
+    def submit_retry_policy(self) -> Optional[RetryPolicy]:
+        """Overrides ``retry_policy`` when submitting jobs. Defaults to ``RetryPolicy.for_submission()``."""
+    @property
+    def cancel_retry_policy(self) -> Optional[RetryPolicy]:
+        """Overrides ``retry_policy`` when cancelling jobs."""
+    @property
+    def retrieve_results_retry_policy(self) -> Optional[RetryPolicy]:
+        """Overrides ``retry_policy`` when retrieving job results."""
+    @property
+    def result_retrieval_retries(self) -> int:
+        """
+        The number of times to retry retrieving a job's results after a temporary failure. Shorthand for the
+        ``max_retries`` of the policy which applies to retrieving results.
+        """
+    @property
+    def spill_threshold_bytes(self) -> Optional[int]:
+        """
+        Results whose readout values would take more than this many bytes once decoded are decoded into
+        temporary files instead, and returned as ``SpilledResultData``. ``None`` never spills results.
+        """
+    @property
+    def spill_directory(self) -> Optional[str]:
+        """
+        The directory to create the temporary files for spilled results in. ``None`` uses the system temporary directory.
+        """
+    @property
+    def wait_for_reservation(self) -> bool:
+        """
+        Whether submitting a job first waits for one of your reservations of the quantum processor to begin. Submitting
+        fails if there is no current or upcoming reservation.
+        """
+    @property
+    def max_reservation_wait_seconds(self) -> Optional[float]:
+        """
+        The longest time in seconds to wait for a reservation to begin when ``wait_for_reservation`` is set. ``None``
+        means there is no limit.
+        """
+    @property
+    def propagate_trace_context(self) -> bool:
+        """
+        Whether the OpenTelemetry context of the current span is sent along with requests to submit jobs and retrieve
+        their results, so that the service's spans join the same trace.
+        """
 
 @final
 class ExecutionOptionsBuilder:
@@ -392,10 +488,157 @@ class ExecutionOptionsBuilder:
     @api_options.setter
     def api_options(self, api_options: APIExecutionOptions):
         """Execution options particular to the API call at the point of execution."""
+    @property
+    def max_request_bytes(self):
+        raise AttributeError("max_request_bytes is not readable")
+    @max_request_bytes.setter
+    def max_request_bytes(self, max_request_bytes: Optional[int]):
+        """
+        Set the maximum size of a single request, in bytes, when submitting a batch of patch values.
+        If set, larger batches are split across multiple requests. If ``None`` (the default), batches are never split.
+        """
+    @property
+    def retry_policy(self):
+        raise AttributeError("retry_policy is not readable")
+    @retry_policy.setter
+    def retry_policy(self, retry_policy: RetryPolicy):
+        """
+        Set how to retry requests which the service rejects for a reason that may be temporary, such as being
+        briefly unavailable, unless overridden for a particular request. Failures to reach the service are retried
+        by the connection itself.
+        """
+    @property
+    def submit_retry_policy(self):
+        raise AttributeError("submit_retry_policy is not readable")
+    @submit_retry_policy.setter
+    def submit_retry_policy(self, submit_retry_policy: Optional[RetryPolicy]):
+        """Override ``retry_policy`` when submitting jobs."""
+    @property
+    def cancel_retry_policy(self):
+        raise AttributeError("cancel_retry_policy is not readable")
+    @cancel_retry_policy.setter
+    def cancel_retry_policy(self, cancel_retry_policy: Optional[RetryPolicy]):
+        """Override ``retry_policy`` when cancelling jobs."""
+    @property
+    def retrieve_results_retry_policy(self):
+        raise AttributeError("retrieve_results_retry_policy is not readable")
+    @retrieve_results_retry_policy.setter
+    def retrieve_results_retry_policy(self, retrieve_results_retry_policy: Optional[RetryPolicy]):
+        """Override ``retry_policy`` when retrieving job results."""
+    @property
+    def result_retrieval_retries(self):
+        raise AttributeError("result_retrieval_retries is not readable")
+    @result_retrieval_retries.setter
+    def result_retrieval_retries(self, result_retrieval_retries: int):
+        """
+        Set the number of times to retry retrieving a job's results after a temporary failure. Shorthand for
+        overriding ``retrieve_results_retry_policy`` with the policy which would otherwise apply, but with
+        ``result_retrieval_retries`` as its ``max_retries``.
+        """
+    @property
+    def spill_threshold_bytes(self):
+        raise AttributeError("spill_threshold_bytes is not readable")
+    @spill_threshold_bytes.setter
+    def spill_threshold_bytes(self, spill_threshold_bytes: Optional[int]):
+        """
+        Set the size, in bytes, above which decoded results are written to temporary files instead of
+        being kept in memory. Results which don't fit a ``RegisterMatrix`` are always kept in memory.
+        """
+    @property
+    def spill_directory(self):
+        raise AttributeError("spill_directory is not readable")
+    @spill_directory.setter
+    def spill_directory(self, spill_directory: Optional[str]):
+        """
+        Set the directory to create the temporary files for spilled results in.
+        """
+    @property
+    def wait_for_reservation(self):
+        raise AttributeError("wait_for_reservation is not readable")
+    @wait_for_reservation.setter
+    def wait_for_reservation(self, wait_for_reservation: bool):
+        """
+        Set whether submitting a job first waits for one of your reservations of the quantum processor to begin.
+        """
+    @property
+    def max_reservation_wait_seconds(self):
+        raise AttributeError("max_reservation_wait_seconds is not readable")
+    @max_reservation_wait_seconds.setter
+    def max_reservation_wait_seconds(self, max_reservation_wait_seconds: Optional[float]):
+        """
+        Set the longest time in seconds to wait for a reservation to begin. If set to ``None`` there is no limit.
+        """
+    @property
+    def propagate_trace_context(self):
+        raise AttributeError("propagate_trace_context is not readable")
+    @propagate_trace_context.setter
+    def propagate_trace_context(self, propagate_trace_context: bool):
+        """
+        Set whether to send the OpenTelemetry context of the current span along with requests to submit jobs and
+        retrieve their results.
+        """
     def build(self) -> ExecutionOptions:
         """Build the ``ExecutionOptions`` using the options set in this builder."""
 
 
+@final
+class RetryPolicy:
+    """
+    How to retry a QPU API request which the service rejects with a gRPC status that may be temporary. Failures to
+    reach the service are retried by the connection itself.
+
+    The ``n``th retry waits ``initial_delay_seconds * backoff_factor ** (n - 1)``, up to ``max_delay_seconds``. With
+    ``jitter`` enabled, each delay is instead chosen at random between half of that and all of it.
+    """
+
+    def __new__(
+        cls,
+        max_retries: int = 3,
+        initial_delay_seconds: float = 0.5,
+        backoff_factor: int = 2,
+        max_delay_seconds: float = 10.0,
+        jitter: bool = True,
+        retryable_status_codes: Optional[List[int]] = None,
+    ) -> "RetryPolicy":
+        """
+        :param retryable_status_codes: The numeric gRPC status codes worth retrying. Defaults to ``UNAVAILABLE``,
+            ``DEADLINE_EXCEEDED``, and ``ABORTED``.
+        """
+        ...
+    @staticmethod
+    def default() -> "RetryPolicy":
+        """Return a reasonable default policy."""
+        ...
+    @staticmethod
+    def never() -> "RetryPolicy":
+        """Return a policy which never retries."""
+        ...
+    @staticmethod
+    def for_submission() -> "RetryPolicy":
+        """
+        Return the default policy for submitting jobs, which only retries requests the service was unavailable to
+        receive, so that a job is never queued twice.
+        """
+        ...
+    @property
+    def max_retries(self) -> int:
+        """The number of times to retry a failed request."""
+    @property
+    def initial_delay_seconds(self) -> float:
+        """The delay before the first retry."""
+    @property
+    def backoff_factor(self) -> int:
+        """The factor by which the delay grows after each retry."""
+    @property
+    def max_delay_seconds(self) -> float:
+        """The longest delay between two attempts."""
+    @property
+    def jitter(self) -> bool:
+        """Whether delays are randomized."""
+    @property
+    def retryable_status_codes(self) -> List[int]:
+        """The numeric gRPC status codes which are worth retrying."""
+
 @final
 class QpuApiDuration:
     def __new__(cls, seconds: int, nanos: int) -> "QpuApiDuration":
@@ -1,4 +1,4 @@
-from typing import Dict, Optional, final
+from typing import Any, Dict, Optional, final
 from typing_extensions import Self
 from enum import Enum, auto
 
@@ -147,10 +147,19 @@ class TranslationOptions:
         should generally leave the `q_ctrl` compile options unset as it is
         specially authorized and not generally available.
         """
+    def set_backend_option(self, key: str, value: Any) -> None:
+        """
+        Set an option of the selected backend by its name in the QCS API, such as ``"passive_reset_delay_seconds"``.
+        This reaches every option of the backend, including those without a dedicated parameter in ``v2()``.
+
+        :raises ValueError: If no backend is selected, the backend has no such option, or ``value`` has the wrong type.
+        """
     @staticmethod
-    def v1() -> "TranslationOptions":
+    def v1(*, backend_options: Optional[Dict[str, Any]] = None) -> "TranslationOptions":
         """
         Use the v1 backend for translation, available on QCS since 2018.
+
+        :param: backend_options: Options of the backend to set, as with ``set_backend_option``.
         """
     @staticmethod
     def v2(
@@ -159,6 +168,7 @@ class TranslationOptions:
         passive_reset_delay_seconds: Optional[float] = None,
         allow_unchecked_pointer_arithmetic: Optional[bool] = None,
         allow_frame_redefinition: Optional[bool] = None,
+        backend_options: Optional[Dict[str, Any]] = None,
     ) -> "TranslationOptions":
         """
         Use the v2 backend for translation, available on QCS since 2023.
@@ -170,6 +180,7 @@ class TranslationOptions:
         certain users.
         :param: allow_frame_redefinition: If True, allow defined frames to differ from Rigetti defaults. Only available to certain users.
         Otherwise, only ``INITIAL-FREQUENCY`` and ``CHANNEL-DELAY`` may be modified.
+        :param: backend_options: Any other options of the backend to set, as with ``set_backend_option``.
         """
     def encode_as_protobuf(self) -> bytes:
         """
@@ -56,6 +56,40 @@ class QVMResultData:
         """
         ...
 
+@final
+class Wavefunction:
+    """
+    The wavefunction of a program, as simulated by the QVM.
+
+    The QVM simulates every qubit from 0 up to the highest qubit used by the program. Amplitudes are
+    indexed by the computational basis state they belong to, where bit ``i`` of the index is the state
+    of qubit ``i``.
+    """
+
+    @staticmethod
+    def from_qvm_bytes(bytes: bytes) -> "Wavefunction":
+        """
+        Decode a wavefunction from the bytes returned by ``qcs_sdk.qvm.api.get_wavefunction``.
+
+        :raises QVMError: If the bytes are not one amplitude for every state of a whole number of qubits.
+        """
+        ...
+    @property
+    def amplitudes(self) -> List[complex]:
+        """The amplitude of each basis state."""
+        ...
+    @property
+    def num_qubits(self) -> int:
+        """The number of qubits in the wavefunction."""
+        ...
+    @property
+    def qubits(self) -> List[int]:
+        """The qubits in the wavefunction, where qubit ``i`` corresponds to bit ``i`` of an amplitude's index."""
+        ...
+    def probabilities(self) -> List[float]:
+        """The probability of measuring each basis state, indexed the same way as ``amplitudes``."""
+        ...
+
 @final
 class RawQVMReadoutData:
     @property
@@ -89,6 +123,62 @@ class QVMOptions:
         """The timeout used for reqeusts to the QVM. If set to none, there is no timeout."""
         ...
 
+@final
+class NoiseModel:
+    """
+    A noise model for the QVM, made up of Pauli noise applied to every gate and measurement, Pauli noise
+    applied to specific gates, per-qubit readout noise, and (when imported from pyQuil) per-gate Kraus operators.
+    """
+
+    def __new__(
+        cls,
+        gate_noise: Optional[Tuple[float, float, float]] = None,
+        measurement_noise: Optional[Tuple[float, float, float]] = None,
+        pauli_noise: Optional[Sequence[Tuple[str, Sequence[int], Tuple[float, float, float]]]] = None,
+        readout_noise: Optional[Mapping[int, Tuple[float, float]]] = None,
+    ) -> "NoiseModel":
+        """
+        :param gate_noise: The ``(px, py, pz)`` probabilities of an X, Y, or Z error after every gate.
+        :param measurement_noise: The ``(px, py, pz)`` probabilities of an X, Y, or Z error before every measurement.
+        :param pauli_noise: ``(gate, qubits, (px, py, pz))`` entries, each applying Pauli noise to every one of
+            ``qubits`` whenever ``gate`` acts on exactly those qubits.
+        :param readout_noise: A mapping of qubit to ``(p00, p11)``, the probabilities of correctly measuring ``0``
+            and ``1``.
+
+        :raises NoiseModelError: If any of the probabilities are invalid.
+        """
+        ...
+    @staticmethod
+    def from_pyquil_json(json: str) -> "NoiseModel":
+        """
+        Import a noise model from the JSON produced by pyQuil's ``NoiseModel.to_dict()``.
+
+        :raises NoiseModelError: If the JSON is malformed or describes an invalid model.
+        """
+        ...
+    def to_pyquil_json(self) -> str:
+        """
+        Export the gate and readout noise to JSON that pyQuil's ``NoiseModel.from_dict()`` can read.
+        ``gate_noise`` and ``measurement_noise`` have no pyQuil equivalent and are not exported.
+        """
+        ...
+    @property
+    def gate_noise(self) -> Optional[Tuple[float, float, float]]:
+        """The Pauli noise applied after every gate."""
+        ...
+    @property
+    def measurement_noise(self) -> Optional[Tuple[float, float, float]]:
+        """The Pauli noise applied before every measurement."""
+        ...
+
+@final
+class NoiseModelError(ValueError):
+    """
+    Errors that can occur when building or importing a ``NoiseModel``.
+    """
+
+    ...
+
 @final
 class QVMError(RuntimeError):
     """
@@ -107,6 +197,7 @@ def run(
     gate_noise: Optional[Tuple[float, float, float]] = None,
     rng_seed: Optional[int] = None,
     options: Optional[QVMOptions] = None,
+    noise_model: Optional[NoiseModel] = None,
 ) -> QVMResultData:
     """
     Runs the given program on the QVM.
@@ -117,6 +208,7 @@ def run(
     :param params: A mapping of memory region names to their desired values.
     :param client: An optional ``QCSClient`` to use. If unset, creates one using the environemnt configuration (see https://docs.rigetti.com/qcs/references/qcs-client-configuration).
     :param options: An optional ``QVMOptions`` to use. If unset, uses ``QVMOptions.default()`` for the request.
+    :param noise_model: An optional ``NoiseModel`` to simulate. Cannot be combined with ``measurement_noise`` or ``gate_noise``.
 
     :returns: A ``QVMResultData`` containing the final state of of memory for the requested readouts after the program finished running.
 
@@ -134,6 +226,7 @@ async def run_async(
     gate_noise: Optional[Tuple[float, float, float]] = None,
     rng_seed: Optional[int] = None,
     options: Optional[QVMOptions] = None,
+    noise_model: Optional[NoiseModel] = None,
 ) -> QVMResultData:
     """
     Asynchronously runs the given program on the QVM.
@@ -144,6 +237,7 @@ async def run_async(
     :param params: A mapping of memory region names to their desired values.
     :param client: An optional ``QCSClient`` to use. If unset, creates one using the environemnt configuration (see https://docs.rigetti.com/qcs/references/qcs-client-configuration).
     :param options: An optional ``QVMOptions`` to use. If unset, uses ``QVMOptions.default()`` for the request.
+    :param noise_model: An optional ``NoiseModel`` to simulate. Cannot be combined with ``measurement_noise`` or ``gate_noise``.
 
     :returns: A ``QVMResultData`` containing the final state of of memory for the requested readouts after the program finished running.
 
@@ -0,0 +1,108 @@
+//! Operational tooling for Python users, exposed as `qcs_sdk.cli` and used by `python -m qcs_sdk`.
+
+use std::num::NonZeroU16;
+
+use qcs::{
+    compiler::rpcq, qpu::api::ExecutionOptions, qvm::http::HttpClient, report::Report, Executable,
+};
+use rigetti_pyo3::{
+    create_init_submodule, py_function_sync_async, py_sync,
+    pyo3::{exceptions::PyRuntimeError, pyfunction, PyResult, Python},
+    PyWrapper, ToPythonError,
+};
+
+use crate::{client::PyQcsClient, executable::RustExecutionError, execution_data::PyExecutionData};
+
+create_init_submodule! {
+    funcs: [
+        doctor,
+        export_report,
+        py_list_quantum_processors,
+        py_list_quantum_processors_async,
+        py_run,
+        py_run_async
+    ],
+}
+
+/// Gather the same diagnostics report as the `diagnostics` binary.
+#[pyfunction]
+fn doctor(py: Python<'_>) -> PyResult<String> {
+    py_sync!(py, async { Ok(qcs::diagnostics::get_report().await) })
+}
+
+/// Write a report archive of `data`, including `program` and diagnostics if given.
+#[pyfunction]
+#[pyo3(signature = (data, path, program = None, include_diagnostics = true))]
+fn export_report(
+    py: Python<'_>,
+    data: PyExecutionData,
+    path: std::path::PathBuf,
+    program: Option<String>,
+    include_diagnostics: bool,
+) -> PyResult<()> {
+    let mut report = Report::new(data.into_inner());
+    if let Some(program) = program {
+        report = report.with_program(program);
+    }
+    if include_diagnostics {
+        let diagnostics = py_sync!(py, async { Ok(qcs::diagnostics::get_report().await) })?;
+        report = report.with_diagnostics(diagnostics);
+    }
+    report
+        .export(path)
+        .map_err(|error| PyRuntimeError::new_err(error.to_string()))
+}
+
+py_function_sync_async! {
+    #[pyfunction]
+    #[pyo3(signature = (client = None, timeout = None))]
+    async fn list_quantum_processors(
+        client: Option<PyQcsClient>,
+        timeout: Option<f64>,
+    ) -> PyResult<Vec<String>> {
+        let client = PyQcsClient::get_or_create_client(client);
+        let timeout = timeout.map(std::time::Duration::from_secs_f64);
+        qcs::qpu::list_quantum_processors(&client, timeout)
+            .await
+            .map_err(|error| PyRuntimeError::new_err(error.to_string()))
+    }
+}
+
+py_function_sync_async! {
+    #[pyfunction]
+    #[pyo3(signature = (quil, shots = 1, registers = None, quantum_processor_id = None, client = None))]
+    async fn run(
+        quil: String,
+        #[pyo3(from_py_with = "crate::from_py::non_zero_u16")]
+        shots: NonZeroU16,
+        registers: Option<Vec<String>>,
+        quantum_processor_id: Option<String>,
+        client: Option<PyQcsClient>,
+    ) -> PyResult<PyExecutionData> {
+        let client = PyQcsClient::get_or_create_client(client);
+        let mut exe = Executable::from_quil(quil).with_shots(shots);
+        for register in registers.unwrap_or_else(|| vec![String::from("ro")]) {
+            exe = exe.read_from(register);
+        }
+
+        let result = match quantum_processor_id {
+            Some(quantum_processor_id) => {
+                let quilc_client = rpcq::Client::new(client.get_config().quilc_url())
+                    .map_err(|error| PyRuntimeError::new_err(error.to_string()))?;
+                exe = exe.with_qcs_client(client).with_quilc_client(Some(quilc_client));
+                exe.execute_on_qpu(quantum_processor_id, None, &ExecutionOptions::default())
+                    .await
+            }
+            None => {
+                let qvm_client = HttpClient::from(&client);
+                exe = exe.with_qcs_client(client);
+                exe.execute_on_qvm(&qvm_client).await
+            }
+        };
+
+        result
+            .map(PyExecutionData::from)
+            .map_err(RustExecutionError::from)
+            .map_err(RustExecutionError::to_py_err)
+    }
+}
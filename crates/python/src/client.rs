@@ -12,7 +12,7 @@ use rigetti_pyo3::{
     wrap_error, PyWrapper, ToPythonError,
 };
 
-use qcs::client::{self, Qcs};
+use qcs::client::{self, ClientApplication, Qcs};
 
 create_init_submodule! {
     classes: [
@@ -69,14 +69,19 @@ impl PyQcsClient {
         api_url = None,
         grpc_api_url = None,
         quilc_url = None,
-        qvm_url = None
+        qvm_url = None,
+        client_application_name = None,
+        client_application_version = None
     ))]
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         oauth_session: Option<OAuthSession>,
         api_url: Option<String>,
         grpc_api_url: Option<String>,
         quilc_url: Option<String>,
         qvm_url: Option<String>,
+        client_application_name: Option<String>,
+        client_application_version: Option<String>,
     ) -> PyResult<Self> {
         let mut builder = ClientConfigurationBuilder::default();
         if let Some(session) = oauth_session {
@@ -100,7 +105,29 @@ impl PyQcsClient {
             .map_err(RustBuildClientError::from)
             .map_err(RustBuildClientError::to_py_err)?;
 
-        Ok(Self(client))
+        let client = Self(client);
+        match client_application_name {
+            Some(name) => client.with_client_application(name, client_application_version),
+            None if client_application_version.is_some() => Err(PyValueError::new_err(
+                "client_application_version requires client_application_name",
+            )),
+            None => Ok(client),
+        }
+    }
+
+    #[pyo3(signature = (name, version = None))]
+    fn with_client_application(&self, name: String, version: Option<String>) -> PyResult<Self> {
+        let application = ClientApplication::new(name).and_then(|application| match version {
+            Some(version) => application.with_version(version),
+            None => Ok(application),
+        });
+        application
+            .map(|application| Self(self.as_inner().clone().with_client_application(application)))
+            .map_err(|error| PyValueError::new_err(error.to_string()))
+    }
+
+    fn with_read_only(&self, read_only: bool) -> Self {
+        Self(self.as_inner().clone().with_read_only(read_only))
     }
 
     #[staticmethod]
@@ -135,6 +162,26 @@ impl PyQcsClient {
         self.as_ref().get_config().qvm_url().to_string()
     }
 
+    #[getter]
+    pub fn client_application_name(&self) -> Option<String> {
+        self.as_ref()
+            .client_application()
+            .map(|application| application.name().to_string())
+    }
+
+    #[getter]
+    pub fn client_application_version(&self) -> Option<String> {
+        self.as_ref()
+            .client_application()
+            .and_then(ClientApplication::version)
+            .map(String::from)
+    }
+
+    #[getter]
+    pub fn read_only(&self) -> bool {
+        self.as_ref().is_read_only()
+    }
+
     #[getter]
     pub fn oauth_session(&self, py: Python<'_>) -> PyResult<OAuthSession> {
         py_get_oauth_session(py, self.clone())
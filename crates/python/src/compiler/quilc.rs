@@ -54,11 +54,12 @@ py_wrap_type! {
 #[pymethods]
 impl PyCompilerOpts {
     #[new]
-    #[pyo3(signature = (/, timeout = DEFAULT_COMPILER_TIMEOUT, protoquil = None))]
-    pub fn new(timeout: Option<f64>, protoquil: Option<bool>) -> Self {
+    #[pyo3(signature = (/, timeout = DEFAULT_COMPILER_TIMEOUT, protoquil = None, specs = false))]
+    pub fn new(timeout: Option<f64>, protoquil: Option<bool>, specs: bool) -> Self {
         let opts = CompilerOpts::new()
             .with_timeout(timeout)
-            .with_protoquil(protoquil);
+            .with_protoquil(protoquil)
+            .with_specs(specs);
         Self(opts)
     }
 
@@ -79,10 +80,15 @@ py_wrap_struct! {
 #[pymethods]
 impl PyTargetDevice {
     #[staticmethod]
-    pub fn from_isa(isa: PyInstructionSetArchitecture) -> PyResult<Self> {
+    #[pyo3(signature = (isa, /, specs = false))]
+    pub fn from_isa(isa: PyInstructionSetArchitecture, specs: bool) -> PyResult<Self> {
         let isa: InstructionSetArchitecture = isa.into();
-        let target: TargetDevice = isa
-            .try_into()
+        let target = if specs {
+            TargetDevice::from_isa_with_specs(isa)
+        } else {
+            isa.try_into()
+        };
+        let target = target
             .map_err(RustQuilcError::from)
             .map_err(RustQuilcError::to_py_err)?;
 
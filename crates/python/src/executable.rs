@@ -1,11 +1,16 @@
-use std::{num::NonZeroU16, sync::Arc};
+use std::{collections::HashMap, num::NonZeroU16, sync::Arc, time::Duration};
 
 use opentelemetry::trace::FutureExt;
 use pyo3::{pyclass, FromPyObject};
 use qcs::{Error, Executable, ExecutionData, JobHandle, Service};
 use rigetti_pyo3::{
     impl_as_mut_for_wrapper, py_async, py_sync, py_wrap_error, py_wrap_simple_enum, py_wrap_type,
-    pyo3::{exceptions::PyRuntimeError, pymethods, types::PyDict, Py, PyAny, PyResult, Python},
+    pyo3::{
+        exceptions::{PyRuntimeError, PyValueError},
+        pymethods,
+        types::PyDict,
+        Py, PyAny, PyResult, Python,
+    },
     wrap_error, PyWrapper, ToPython, ToPythonError,
 };
 use tokio::sync::Mutex;
@@ -15,6 +20,7 @@ use crate::{
     compiler::quilc::{PyCompilerOpts, PyQuilcClient},
     execution_data::PyExecutionData,
     qpu::{api::PyExecutionOptions, translation::PyTranslationOptions},
+    qvm::PyNoiseModel,
 };
 
 wrap_error!(RustExecutionError(Error));
@@ -91,6 +97,32 @@ macro_rules! py_job_handle {
     }};
 }
 
+/// Invoke a PyExecutable's inner Executable::expectation_on_qvm with given arguments,
+/// then mapped to `Future<Output = Result<Py<PyDict>, ExecutionError>>`, keeping the order of
+/// the operators.
+macro_rules! py_expectation {
+    ($self: ident, $pauli_operators: ident, $client: ident) => {{
+        let arc = $self.as_inner().clone();
+        async move {
+            let values = arc
+                .lock()
+                .await
+                .expectation_on_qvm(&$pauli_operators, &$client)
+                .await
+                .map_err(RustExecutionError::from)
+                .map_err(RustExecutionError::to_py_err)?;
+            Python::with_gil(|py| -> PyResult<Py<PyDict>> {
+                let dict = PyDict::new(py);
+                for (operator, value) in values {
+                    dict.set_item(operator, value)?;
+                }
+                Ok(dict.into())
+            })
+        }
+        .with_current_context()
+    }};
+}
+
 #[pyo3_opentelemetry::pypropagate(exclude(new), on_context_extraction_failure = "ignore")]
 #[pymethods]
 impl PyExecutable {
@@ -103,7 +135,12 @@ impl PyExecutable {
         shots = None,
         quilc_client = None,
         compiler_options = None,
+        noise_model = None,
+        register_indices = HashMap::new(),
+        shot_memory_reset = true,
+        deadline = None,
     ))]
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         quil: String,
         registers: Vec<String>,
@@ -111,14 +148,24 @@ impl PyExecutable {
         #[pyo3(from_py_with = "crate::from_py::optional_non_zero_u16")] shots: Option<NonZeroU16>,
         quilc_client: Option<PyQuilcClient>,
         compiler_options: Option<PyCompilerOpts>,
-    ) -> Self {
+        noise_model: Option<PyNoiseModel>,
+        register_indices: HashMap<String, Vec<usize>>,
+        shot_memory_reset: bool,
+        deadline: Option<f64>,
+    ) -> PyResult<Self> {
         let quilc_client = quilc_client.map(|c| c.inner);
-        let mut exe = Executable::from_quil(quil).with_quilc_client(quilc_client);
+        let mut exe = Executable::from_quil(quil)
+            .with_quilc_client(quilc_client)
+            .with_shot_memory_reset(shot_memory_reset);
 
         for reg in registers {
             exe = exe.read_from(reg);
         }
 
+        for (reg, indices) in register_indices {
+            exe = exe.read_indices(reg, &indices);
+        }
+
         for param in parameters {
             exe.with_parameter(param.name, param.index, param.value);
         }
@@ -131,7 +178,17 @@ impl PyExecutable {
             exe = exe.compiler_options(options.into_inner());
         }
 
-        Self::from(Arc::new(Mutex::new(exe)))
+        if let Some(noise_model) = noise_model {
+            exe = exe.with_noise_model(noise_model.into_inner());
+        }
+
+        if let Some(deadline) = deadline {
+            let deadline = Duration::try_from_secs_f64(deadline)
+                .map_err(|error| PyValueError::new_err(error.to_string()))?;
+            exe = exe.with_deadline(deadline);
+        }
+
+        Ok(Self::from(Arc::new(Mutex::new(exe))))
     }
 
     #[instrument(skip_all)]
@@ -152,6 +209,74 @@ impl PyExecutable {
         py_async!(py, py_executable_data!(self, execute_on_qvm, &client))
     }
 
+    #[instrument(skip_all)]
+    #[pyo3(signature = (client, qubits = None))]
+    pub fn execute_and_measure_on_qvm(
+        &self,
+        py: Python<'_>,
+        client: crate::qvm::PyQvmClient,
+        qubits: Option<Vec<u64>>,
+    ) -> PyResult<PyExecutionData> {
+        let qubits = qubits.unwrap_or_default();
+        py_sync!(
+            py,
+            py_executable_data!(self, execute_and_measure_on_qvm, &qubits, &client)
+        )
+    }
+
+    #[instrument(skip_all)]
+    #[pyo3(signature = (client, qubits = None))]
+    pub fn execute_and_measure_on_qvm_async<'py>(
+        &'py self,
+        py: Python<'py>,
+        client: crate::qvm::PyQvmClient,
+        qubits: Option<Vec<u64>>,
+    ) -> PyResult<&PyAny> {
+        let qubits = qubits.unwrap_or_default();
+        py_async!(
+            py,
+            py_executable_data!(self, execute_and_measure_on_qvm, &qubits, &client)
+        )
+    }
+
+    #[instrument(skip_all)]
+    pub fn wavefunction_on_qvm(
+        &self,
+        py: Python<'_>,
+        client: crate::qvm::PyQvmClient,
+    ) -> PyResult<PyExecutionData> {
+        py_sync!(py, py_executable_data!(self, wavefunction_on_qvm, &client))
+    }
+
+    #[instrument(skip_all)]
+    pub fn wavefunction_on_qvm_async<'py>(
+        &'py self,
+        py: Python<'py>,
+        client: crate::qvm::PyQvmClient,
+    ) -> PyResult<&PyAny> {
+        py_async!(py, py_executable_data!(self, wavefunction_on_qvm, &client))
+    }
+
+    #[instrument(skip_all)]
+    pub fn expectation_on_qvm(
+        &self,
+        py: Python<'_>,
+        pauli_operators: Vec<String>,
+        client: crate::qvm::PyQvmClient,
+    ) -> PyResult<Py<PyDict>> {
+        py_sync!(py, py_expectation!(self, pauli_operators, client))
+    }
+
+    #[instrument(skip_all)]
+    pub fn expectation_on_qvm_async<'py>(
+        &'py self,
+        py: Python<'py>,
+        pauli_operators: Vec<String>,
+        client: crate::qvm::PyQvmClient,
+    ) -> PyResult<&PyAny> {
+        py_async!(py, py_expectation!(self, pauli_operators, client))
+    }
+
     #[pyo3(signature = (quantum_processor_id, endpoint_id = None, translation_options = None, execution_options = None))]
     pub fn execute_on_qpu(
         &self,
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::time::Duration;
 
 use numpy::{Complex64, PyArray2};
@@ -7,24 +7,28 @@ use pyo3::pyclass::CompareOp;
 use pyo3::{
     exceptions::PyValueError,
     pyclass, pymethods,
-    types::{PyBytes, PyDelta},
+    types::{PyBytes, PyDelta, PyList},
     IntoPy, Py, PyObject, PyRef, PyRefMut, PyResult, Python, ToPyObject,
 };
+use qcs::interop::{self, BitOrder};
 use qcs::qvm::QvmResultData;
-use qcs::{ExecutionData, RegisterMap, RegisterMatrix, ResultData};
+use qcs::spill::SpilledResultData;
+use qcs::{ExecutionData, ExecutionTimings, RegisterMap, RegisterMatrix, ResultData};
 use rigetti_pyo3::{
-    impl_repr, py_wrap_data_struct, py_wrap_error, py_wrap_type, py_wrap_union_enum, wrap_error,
-    PyTryFrom, PyWrapper, ToPython, ToPythonError,
+    impl_repr, py_wrap_data_struct, py_wrap_error, py_wrap_simple_enum, py_wrap_type,
+    py_wrap_union_enum, wrap_error, PyTryFrom, PyWrapper, ToPython, ToPythonError,
 };
 
 use crate::qpu::PyQpuResultData;
-use crate::qvm::PyQvmResultData;
+use crate::qvm::{PyQvmResultData, PyWavefunction};
 
 py_wrap_union_enum! {
     #[pyo3(module = "qcs_sdk")]
     PyResultData(ResultData) as "ResultData" {
         qpu: Qpu => PyQpuResultData,
-        qvm: Qvm => PyQvmResultData
+        qvm: Qvm => PyQvmResultData,
+        wavefunction: Wavefunction => PyWavefunction,
+        spilled: Spilled => PySpilledResultData
     }
 }
 impl_repr!(PyResultData);
@@ -65,8 +69,77 @@ impl PyResultData {
             ResultData::Qvm(_) => self
                 .to_qvm(py)
                 .map(|data| data.to_raw_readout_data(py).into_py(py)),
+            ResultData::Wavefunction(wavefunction) => {
+                Ok(PyList::new(py, wavefunction.amplitudes()).into_py(py))
+            }
+            ResultData::Spilled(_) => self.to_register_map(py).map(|map| map.into_py(py)),
         }
     }
+
+    pub fn to_json(&self) -> PyResult<String> {
+        self.as_inner()
+            .to_json()
+            .map_err(|error| PyValueError::new_err(error.to_string()))
+    }
+
+    #[staticmethod]
+    pub fn from_json(json: &str) -> PyResult<Self> {
+        ResultData::from_json(json)
+            .map(Self)
+            .map_err(|error| PyValueError::new_err(error.to_string()))
+    }
+}
+
+py_wrap_type! {
+    #[derive(Debug)]
+    PySpilledResultData(SpilledResultData) as "SpilledResultData"
+}
+impl_repr!(PySpilledResultData);
+
+#[pymethods]
+impl PySpilledResultData {
+    #[getter]
+    pub fn mappings(&self) -> HashMap<String, String> {
+        self.as_inner().mappings().clone()
+    }
+
+    pub fn to_register_map(&self, py: Python) -> PyResult<PyRegisterMap> {
+        self.as_inner()
+            .register_map()
+            .to_register_map()?
+            .to_python(py)
+    }
+
+    pub fn read_rows(
+        &self,
+        register: &str,
+        start: usize,
+        stop: usize,
+    ) -> PyResult<PyRegisterMatrix> {
+        self.as_inner()
+            .register_map()
+            .get_register_matrix(register)
+            .ok_or_else(|| PyKeyError::new_err(format!("Key {register} not found in RegisterMap")))?
+            .read_rows(start..stop)
+            .map(PyRegisterMatrix::from)
+            .map_err(Into::into)
+    }
+
+    pub fn shapes(&self) -> HashMap<String, (usize, usize, &'static str)> {
+        self.as_inner()
+            .register_map()
+            .shapes()
+            .into_iter()
+            .map(|(name, shape)| (name, (shape.rows, shape.cols, shape.dtype.as_str())))
+            .collect()
+    }
+}
+
+py_wrap_simple_enum! {
+    PyBitOrder(BitOrder) as "BitOrder" {
+        AscendingIndex,
+        DescendingIndex
+    }
 }
 
 py_wrap_data_struct! {
@@ -74,11 +147,23 @@ py_wrap_data_struct! {
     #[derive(Debug, PartialEq)]
     PyExecutionData(ExecutionData) as "ExecutionData" {
         result_data: ResultData => PyResultData,
-        duration: Option<Duration> => Option<Py<PyDelta>>
+        duration: Option<Duration> => Option<Py<PyDelta>>,
+        timings: Option<ExecutionTimings> => Option<PyExecutionTimings>
     }
 }
 impl_repr!(PyExecutionData);
 
+py_wrap_data_struct! {
+    #[pyo3(module = "qcs_sdk")]
+    #[derive(Debug, PartialEq)]
+    PyExecutionTimings(ExecutionTimings) as "ExecutionTimings" {
+        execution: Option<Duration> => Option<Py<PyDelta>>,
+        time_to_results: Option<Duration> => Option<Py<PyDelta>>,
+        post_processing: Option<Duration> => Option<Py<PyDelta>>
+    }
+}
+impl_repr!(PyExecutionTimings);
+
 #[pymethods]
 impl PyExecutionData {
     #[new]
@@ -100,9 +185,53 @@ impl PyExecutionData {
                         .map(Duration::from_secs_f64)
                 })
                 .transpose()?,
+            timings: None,
         }))
     }
 
+    #[getter]
+    pub fn readout_mappings(&self) -> Option<HashMap<String, String>> {
+        self.as_inner().readout_mappings().cloned()
+    }
+
+    #[pyo3(signature = (bit_order = None))]
+    pub fn to_counts_json(&self, bit_order: Option<PyBitOrder>) -> PyResult<String> {
+        let bit_order = bit_order.map(BitOrder::from).unwrap_or_default();
+        interop::to_counts_json(self.as_inner(), bit_order)
+            .map(|counts| counts.to_string())
+            .map_err(|error| PyValueError::new_err(error.to_string()))
+    }
+
+    pub fn to_json(&self) -> PyResult<String> {
+        self.as_inner()
+            .to_json()
+            .map_err(|error| PyValueError::new_err(error.to_string()))
+    }
+
+    #[staticmethod]
+    pub fn from_json(json: &str) -> PyResult<Self> {
+        ExecutionData::from_json(json)
+            .map(Self)
+            .map_err(|error| PyValueError::new_err(error.to_string()))
+    }
+
+    pub fn export_report(&self, path: std::path::PathBuf) -> PyResult<()> {
+        self.as_inner()
+            .export_report(path)
+            .map_err(|error| PyRuntimeError::new_err(error.to_string()))
+    }
+
+    pub fn to_csv(&self, path: std::path::PathBuf) -> PyResult<()> {
+        let file = std::fs::File::create(path)
+            .map_err(|error| PyRuntimeError::new_err(error.to_string()))?;
+        self.as_inner().to_csv(file).map_err(|error| match error {
+            qcs::export::Error::RegisterMatrixConversion(error) => {
+                PyValueError::new_err(error.to_string())
+            }
+            error => PyRuntimeError::new_err(error.to_string()),
+        })
+    }
+
     pub fn __getstate__<'a>(&self, py: Python<'a>) -> PyResult<&'a PyBytes> {
         Ok(PyBytes::new(
             py,
@@ -236,6 +365,14 @@ impl PyRegisterMap {
         self.as_inner().0.len()
     }
 
+    pub fn shapes(&self) -> HashMap<String, (usize, usize, &'static str)> {
+        self.as_inner()
+            .shapes()
+            .into_iter()
+            .map(|(name, shape)| (name, (shape.rows, shape.cols, shape.dtype.as_str())))
+            .collect()
+    }
+
     pub fn __contains__(&self, key: &str) -> bool {
         self.as_inner().0.contains_key(key)
     }
@@ -281,6 +418,44 @@ impl PyRegisterMap {
     pub fn get(&self, key: &str, default: Option<PyRegisterMatrix>) -> Option<PyRegisterMatrix> {
         self.__getitem__(key).ok().or(default)
     }
+
+    #[pyo3(signature = (register, bit_order = None))]
+    pub fn histogram(
+        &self,
+        register: &str,
+        bit_order: Option<PyBitOrder>,
+    ) -> PyResult<BTreeMap<String, u64>> {
+        let bit_order = bit_order.map(BitOrder::from).unwrap_or_default();
+        self.as_inner()
+            .histogram(register, bit_order)
+            .map_err(bitstring_error)
+    }
+
+    #[pyo3(signature = (register, bit_order = None))]
+    pub fn probabilities(
+        &self,
+        register: &str,
+        bit_order: Option<PyBitOrder>,
+    ) -> PyResult<BTreeMap<String, f64>> {
+        let bit_order = bit_order.map(BitOrder::from).unwrap_or_default();
+        self.as_inner()
+            .probabilities(register, bit_order)
+            .map_err(bitstring_error)
+    }
+
+    pub fn parity(&self, register: &str) -> PyResult<Vec<bool>> {
+        self.as_inner().parity(register).map_err(bitstring_error)
+    }
+}
+
+/// Raise a `KeyError` for a missing register, and a `ValueError` for anything else.
+fn bitstring_error(error: interop::Error) -> pyo3::PyErr {
+    match error {
+        interop::Error::MissingRegister { register } => {
+            PyKeyError::new_err(format!("Key {register} not found in RegisterMap"))
+        }
+        error => PyValueError::new_err(error.to_string()),
+    }
 }
 
 #[pyclass]
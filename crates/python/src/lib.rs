@@ -6,6 +6,7 @@ use rigetti_pyo3::{create_init_submodule, py_sync};
 use executable::ExecutionError;
 use execution_data::RegisterMatrixConversionError;
 
+pub mod cli;
 pub mod client;
 pub mod compiler;
 pub mod executable;
@@ -20,7 +21,10 @@ pub(crate) mod from_py;
 create_init_submodule! {
     classes: [
         execution_data::PyExecutionData,
+        execution_data::PyExecutionTimings,
+        execution_data::PyBitOrder,
         execution_data::PyResultData,
+        execution_data::PySpilledResultData,
         execution_data::PyRegisterMap,
         execution_data::PyRegisterMatrix,
         executable::PyExecutable,
@@ -36,6 +40,7 @@ create_init_submodule! {
     ],
     funcs: [ reset_logging, gather_diagnostics ],
     submodules: [
+        "cli": cli::init_submodule,
         "client": client::init_submodule,
         "compiler": compiler::init_submodule,
         "qpu": qpu::init_submodule,
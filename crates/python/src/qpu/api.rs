@@ -1,5 +1,6 @@
 //! Running programs on a QPU.
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::time::Duration;
 
 use numpy::Complex32;
@@ -15,6 +16,7 @@ use qcs::qpu::api::{
     ApiExecutionOptions, ApiExecutionOptionsBuilder, ConnectionStrategy, ExecutionOptions,
     ExecutionOptionsBuilder, QpuApiDuration,
 };
+use qcs::qpu::retry::RetryPolicy;
 use qcs_api_client_grpc::models::controller::{
     data_value, readout_values, ControllerJobExecutionResult,
 };
@@ -35,6 +37,7 @@ create_init_submodule! {
         PyConnectionStrategy,
         PyExecutionOptions,
         PyExecutionOptionsBuilder,
+        PyRetryPolicy,
         PyApiExecutionOptions,
         PyApiExecutionOptionsBuilder,
         PyQpuApiDuration
@@ -53,7 +56,9 @@ create_init_submodule! {
         py_cancel_jobs,
         py_cancel_jobs_async,
         py_retrieve_results,
-        py_retrieve_results_async
+        py_retrieve_results_async,
+        py_get_job_status,
+        py_get_job_status_async
     ],
 }
 
@@ -96,7 +101,7 @@ py_function_sync_async! {
         // negates the whole purpose of [`submit`] using `Box<str>`,
         // instead of `String` directly, which normally would decrease
         // copies _and_ require less space, since str can't be extended.
-        let patch_values = patch_values
+        let patch_values: HashMap<Box<str>, Vec<f64>> = patch_values
             .into_iter()
             .map(|(k, v)| (k.into_boxed_str(), v))
             .collect();
@@ -379,6 +384,26 @@ py_function_sync_async! {
     }
 }
 
+py_function_sync_async! {
+    #[pyfunction]
+    #[pyo3(signature = (job_id, quantum_processor_id = None, client = None, execution_options = None))]
+    async fn get_job_status(
+        job_id: String,
+        quantum_processor_id: Option<String>,
+        client: Option<PyQcsClient>,
+        execution_options: Option<PyExecutionOptions>
+    ) -> PyResult<String> {
+        let client = PyQcsClient::get_or_create_client(client);
+
+        let status = qcs::qpu::api::get_job_status(job_id.into(), quantum_processor_id.as_deref(), &client, execution_options.unwrap_or_default().as_inner())
+            .await
+            .map_err(RustQpuApiError::from)
+            .map_err(RustQpuApiError::to_py_err)?;
+
+        Ok(status.to_string())
+    }
+}
+
 py_wrap_type! {
     #[derive(Debug, Default)]
     #[pyo3(module = "qcs_sdk.qpu.api")]
@@ -425,6 +450,72 @@ impl PyExecutionOptions {
             .map(|x| PyApiExecutionOptions((*x).into()))
     }
 
+    #[getter]
+    fn max_request_bytes(&self) -> Option<usize> {
+        self.as_inner().max_request_bytes()
+    }
+
+    #[getter]
+    fn retry_policy(&self) -> PyRetryPolicy {
+        PyRetryPolicy(self.as_inner().retry_policy().clone())
+    }
+
+    #[getter]
+    fn submit_retry_policy(&self) -> Option<PyRetryPolicy> {
+        self.as_inner()
+            .submit_retry_policy()
+            .cloned()
+            .map(PyRetryPolicy)
+    }
+
+    #[getter]
+    fn cancel_retry_policy(&self) -> Option<PyRetryPolicy> {
+        self.as_inner()
+            .cancel_retry_policy()
+            .cloned()
+            .map(PyRetryPolicy)
+    }
+
+    #[getter]
+    fn retrieve_results_retry_policy(&self) -> Option<PyRetryPolicy> {
+        self.as_inner()
+            .retrieve_results_retry_policy()
+            .cloned()
+            .map(PyRetryPolicy)
+    }
+
+    #[getter]
+    fn result_retrieval_retries(&self) -> u32 {
+        self.as_inner().result_retrieval_retries()
+    }
+
+    #[getter]
+    fn spill_threshold_bytes(&self) -> Option<usize> {
+        self.as_inner().spill_threshold_bytes()
+    }
+
+    #[getter]
+    fn spill_directory(&self) -> Option<PathBuf> {
+        self.as_inner().spill_directory().map(PathBuf::from)
+    }
+
+    #[getter]
+    fn wait_for_reservation(&self) -> bool {
+        self.as_inner().wait_for_reservation()
+    }
+
+    #[getter]
+    fn max_reservation_wait_seconds(&self) -> Option<f64> {
+        self.as_inner()
+            .max_reservation_wait()
+            .map(|max_wait| max_wait.as_secs_f64())
+    }
+
+    #[getter]
+    fn propagate_trace_context(&self) -> bool {
+        self.as_inner().propagate_trace_context()
+    }
+
     fn __richcmp__(&self, py: Python<'_>, other: &Self, op: CompareOp) -> PyObject {
         match op {
             CompareOp::Eq => (self.as_inner() == other.as_inner()).into_py(py),
@@ -444,6 +535,16 @@ impl PyExecutionOptions {
                         self.connection_strategy().into_py(py),
                         self.timeout_seconds().into_py(py),
                         self.api_options().into_py(py),
+                        self.max_request_bytes().into_py(py),
+                        self.retry_policy().into_py(py),
+                        self.submit_retry_policy().into_py(py),
+                        self.cancel_retry_policy().into_py(py),
+                        self.retrieve_results_retry_policy().into_py(py),
+                        self.spill_threshold_bytes().into_py(py),
+                        self.spill_directory().into_py(py),
+                        self.wait_for_reservation().into_py(py),
+                        self.max_reservation_wait_seconds().into_py(py),
+                        self.propagate_trace_context().into_py(py),
                     ],
                 ),
             ],
@@ -451,15 +552,36 @@ impl PyExecutionOptions {
     }
 
     #[staticmethod]
+    #[allow(clippy::too_many_arguments)]
     fn _from_parts(
         connection_strategy: PyConnectionStrategy,
         timeout_seconds: Option<f64>,
         api_options: Option<PyApiExecutionOptions>,
+        max_request_bytes: Option<usize>,
+        retry_policy: PyRetryPolicy,
+        submit_retry_policy: Option<PyRetryPolicy>,
+        cancel_retry_policy: Option<PyRetryPolicy>,
+        retrieve_results_retry_policy: Option<PyRetryPolicy>,
+        spill_threshold_bytes: Option<usize>,
+        spill_directory: Option<PathBuf>,
+        wait_for_reservation: bool,
+        max_reservation_wait_seconds: Option<f64>,
+        propagate_trace_context: bool,
     ) -> PyResult<Self> {
         let mut builder = Self::builder();
         builder.connection_strategy(connection_strategy);
         builder.timeout_seconds(timeout_seconds);
         builder.api_options(api_options);
+        builder.max_request_bytes(max_request_bytes);
+        builder.retry_policy(retry_policy);
+        builder.submit_retry_policy(submit_retry_policy);
+        builder.cancel_retry_policy(cancel_retry_policy);
+        builder.retrieve_results_retry_policy(retrieve_results_retry_policy);
+        builder.spill_threshold_bytes(spill_threshold_bytes);
+        builder.spill_directory(spill_directory);
+        builder.wait_for_reservation(wait_for_reservation);
+        builder.max_reservation_wait_seconds(max_reservation_wait_seconds);
+        builder.propagate_trace_context(propagate_trace_context);
         builder.build()
     }
 }
@@ -534,6 +656,122 @@ impl PyExecutionOptionsBuilder {
         *self = Self::from(self.as_inner().clone().timeout(timeout).clone());
     }
 
+    #[setter]
+    fn max_request_bytes(&mut self, max_request_bytes: Option<usize>) {
+        *self = Self::from(
+            self.as_inner()
+                .clone()
+                .max_request_bytes(max_request_bytes)
+                .clone(),
+        );
+    }
+
+    #[setter]
+    fn retry_policy(&mut self, retry_policy: PyRetryPolicy) {
+        *self = Self::from(
+            self.as_inner()
+                .clone()
+                .retry_policy(retry_policy.into_inner())
+                .clone(),
+        );
+    }
+
+    #[setter]
+    fn submit_retry_policy(&mut self, submit_retry_policy: Option<PyRetryPolicy>) {
+        *self = Self::from(
+            self.as_inner()
+                .clone()
+                .submit_retry_policy(submit_retry_policy.map(PyRetryPolicy::into_inner))
+                .clone(),
+        );
+    }
+
+    #[setter]
+    fn cancel_retry_policy(&mut self, cancel_retry_policy: Option<PyRetryPolicy>) {
+        *self = Self::from(
+            self.as_inner()
+                .clone()
+                .cancel_retry_policy(cancel_retry_policy.map(PyRetryPolicy::into_inner))
+                .clone(),
+        );
+    }
+
+    #[setter]
+    fn retrieve_results_retry_policy(
+        &mut self,
+        retrieve_results_retry_policy: Option<PyRetryPolicy>,
+    ) {
+        *self = Self::from(
+            self.as_inner()
+                .clone()
+                .retrieve_results_retry_policy(
+                    retrieve_results_retry_policy.map(PyRetryPolicy::into_inner),
+                )
+                .clone(),
+        );
+    }
+
+    #[setter]
+    fn result_retrieval_retries(&mut self, result_retrieval_retries: u32) {
+        *self = Self::from(
+            self.as_inner()
+                .clone()
+                .result_retrieval_retries(result_retrieval_retries)
+                .clone(),
+        );
+    }
+
+    #[setter]
+    fn spill_threshold_bytes(&mut self, spill_threshold_bytes: Option<usize>) {
+        *self = Self::from(
+            self.as_inner()
+                .clone()
+                .spill_threshold_bytes(spill_threshold_bytes)
+                .clone(),
+        );
+    }
+
+    #[setter]
+    fn spill_directory(&mut self, spill_directory: Option<PathBuf>) {
+        *self = Self::from(
+            self.as_inner()
+                .clone()
+                .spill_directory(spill_directory)
+                .clone(),
+        );
+    }
+
+    #[setter]
+    fn wait_for_reservation(&mut self, wait_for_reservation: bool) {
+        *self = Self::from(
+            self.as_inner()
+                .clone()
+                .wait_for_reservation(wait_for_reservation)
+                .clone(),
+        );
+    }
+
+    #[setter]
+    fn max_reservation_wait_seconds(&mut self, max_reservation_wait_seconds: Option<f64>) {
+        let max_wait = max_reservation_wait_seconds.map(Duration::from_secs_f64);
+        *self = Self::from(
+            self.as_inner()
+                .clone()
+                .max_reservation_wait(max_wait)
+                .clone(),
+        );
+    }
+
+    #[setter]
+    fn propagate_trace_context(&mut self, propagate_trace_context: bool) {
+        *self = Self::from(
+            self.as_inner()
+                .clone()
+                .propagate_trace_context(propagate_trace_context)
+                .clone(),
+        );
+    }
+
     fn build(&self) -> PyResult<PyExecutionOptions> {
         Ok(PyExecutionOptions::from(
             self.as_inner()
@@ -543,6 +781,130 @@ impl PyExecutionOptionsBuilder {
     }
 }
 
+py_wrap_type! {
+    #[derive(Debug, Default)]
+    #[pyo3(module = "qcs_sdk.qpu.api")]
+    PyRetryPolicy(RetryPolicy) as "RetryPolicy"
+}
+impl_repr!(PyRetryPolicy);
+
+#[pymethods]
+impl PyRetryPolicy {
+    #[new]
+    #[pyo3(signature = (
+        max_retries = 3,
+        initial_delay_seconds = 0.5,
+        backoff_factor = 2,
+        max_delay_seconds = 10.0,
+        jitter = true,
+        retryable_status_codes = None
+    ))]
+    fn new(
+        max_retries: u32,
+        initial_delay_seconds: f64,
+        backoff_factor: u32,
+        max_delay_seconds: f64,
+        jitter: bool,
+        retryable_status_codes: Option<Vec<i32>>,
+    ) -> PyResult<Self> {
+        let seconds = |seconds: f64| {
+            Duration::try_from_secs_f64(seconds)
+                .map_err(|err| PyValueError::new_err(err.to_string()))
+        };
+        let mut builder = RetryPolicy::builder();
+        builder
+            .max_retries(max_retries)
+            .initial_delay(seconds(initial_delay_seconds)?)
+            .backoff_factor(backoff_factor)
+            .max_delay(seconds(max_delay_seconds)?)
+            .jitter(jitter);
+        if let Some(codes) = retryable_status_codes {
+            builder.retryable_codes(codes.into_iter().map(tonic::Code::from_i32).collect());
+        }
+        builder
+            .build()
+            .map(Self)
+            .map_err(|err| PyValueError::new_err(err.to_string()))
+    }
+
+    #[staticmethod]
+    fn default() -> Self {
+        Self(RetryPolicy::default())
+    }
+
+    #[staticmethod]
+    fn never() -> Self {
+        Self(RetryPolicy::never())
+    }
+
+    #[staticmethod]
+    fn for_submission() -> Self {
+        Self(RetryPolicy::for_submission())
+    }
+
+    #[getter]
+    fn max_retries(&self) -> u32 {
+        self.as_inner().max_retries()
+    }
+
+    #[getter]
+    fn initial_delay_seconds(&self) -> f64 {
+        self.as_inner().initial_delay().as_secs_f64()
+    }
+
+    #[getter]
+    fn backoff_factor(&self) -> u32 {
+        self.as_inner().backoff_factor()
+    }
+
+    #[getter]
+    fn max_delay_seconds(&self) -> f64 {
+        self.as_inner().max_delay().as_secs_f64()
+    }
+
+    #[getter]
+    fn jitter(&self) -> bool {
+        self.as_inner().jitter()
+    }
+
+    #[getter]
+    fn retryable_status_codes(&self) -> Vec<i32> {
+        self.as_inner()
+            .retryable_codes()
+            .iter()
+            .map(|code| *code as i32)
+            .collect()
+    }
+
+    fn __richcmp__(&self, py: Python<'_>, other: &Self, op: CompareOp) -> PyObject {
+        match op {
+            CompareOp::Eq => (self.as_inner() == other.as_inner()).into_py(py),
+            _ => py.NotImplemented(),
+        }
+    }
+
+    fn __reduce__<'py>(&self, py: Python<'py>) -> PyResult<&'py PyTuple> {
+        Ok(PyTuple::new(
+            py,
+            [
+                py.get_type::<Self>().into_py(py),
+                PyTuple::new(
+                    py,
+                    &[
+                        self.max_retries().into_py(py),
+                        self.initial_delay_seconds().into_py(py),
+                        self.backoff_factor().into_py(py),
+                        self.max_delay_seconds().into_py(py),
+                        self.jitter().into_py(py),
+                        self.retryable_status_codes().into_py(py),
+                    ],
+                )
+                .into_py(py),
+            ],
+        ))
+    }
+}
+
 py_wrap_type! {
     PyApiExecutionOptionsBuilder(ApiExecutionOptionsBuilder) as "APIExecutionOptionsBuilder"
 }
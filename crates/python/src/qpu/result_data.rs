@@ -78,6 +78,14 @@ impl PyQpuResultData {
         self.as_inner().memory_values().to_python(py)
     }
 
+    fn readout_shapes(&self) -> HashMap<String, (usize, usize, &'static str)> {
+        self.as_inner()
+            .readout_shapes()
+            .into_iter()
+            .map(|(name, shape)| (name, (shape.rows, shape.cols, shape.dtype.as_str())))
+            .collect()
+    }
+
     pub(crate) fn to_raw_readout_data(&self, py: Python<'_>) -> RawQpuReadoutData {
         RawQpuReadoutData {
             mappings: self.as_inner().mappings().clone(),
@@ -4,7 +4,10 @@ use std::{collections::HashMap, time::Duration};
 use prost::Message;
 use pyo3::types::PyBytes;
 use pyo3::Python;
-use pyo3::{exceptions::PyRuntimeError, pyclass, pyfunction, pymethods, PyResult};
+use pyo3::{
+    exceptions::{PyRuntimeError, PyValueError},
+    pyclass, pyfunction, pymethods, PyAny, PyResult,
+};
 use qcs::qpu::translation::TranslationOptions;
 use qcs_api_client_grpc::services::translation::translation_options;
 use qcs_api_client_grpc::services::translation::{
@@ -120,11 +123,20 @@ impl PyTranslationOptions {
         self.0.q_ctrl(*q_ctrl.as_inner());
     }
 
+    fn set_backend_option(&mut self, py: Python<'_>, key: &str, value: &PyAny) -> PyResult<()> {
+        self.0
+            .with_backend_option(key, to_json(py, value)?)
+            .map_err(|error| PyValueError::new_err(error.to_string()))?;
+        Ok(())
+    }
+
     #[staticmethod]
-    fn v1() -> Self {
-        let mut builder = TranslationOptions::default();
-        builder.with_backend_v1();
-        Self(builder)
+    #[pyo3(signature = (*, backend_options=None))]
+    fn v1(py: Python<'_>, backend_options: Option<HashMap<String, &PyAny>>) -> PyResult<Self> {
+        let mut builder = Self(TranslationOptions::default());
+        builder.0.with_backend_v1();
+        builder.set_backend_options(py, backend_options)?;
+        Ok(builder)
     }
 
     #[staticmethod]
@@ -133,14 +145,17 @@ impl PyTranslationOptions {
         prepend_default_calibrations=None,
         passive_reset_delay_seconds=None,
         allow_unchecked_pointer_arithmetic=None,
-        allow_frame_redefinition=None
+        allow_frame_redefinition=None,
+        backend_options=None
     ))]
     fn v2(
+        py: Python<'_>,
         prepend_default_calibrations: Option<bool>,
         passive_reset_delay_seconds: Option<f64>,
         allow_unchecked_pointer_arithmetic: Option<bool>,
         allow_frame_redefinition: Option<bool>,
-    ) -> Self {
+        backend_options: Option<HashMap<String, &PyAny>>,
+    ) -> PyResult<Self> {
         let mut builder = TranslationOptions::default();
         builder.with_backend_v2();
         if let Some(prepend) = prepend_default_calibrations {
@@ -163,7 +178,9 @@ impl PyTranslationOptions {
                 .v2_allow_frame_redefinition(allow)
                 .expect("using the correct backend");
         }
-        Self(builder)
+        let mut builder = Self(builder);
+        builder.set_backend_options(py, backend_options)?;
+        Ok(builder)
     }
 
     fn encode_as_protobuf<'a>(&'a self, py: Python<'a>) -> &'a PyBytes {
@@ -176,6 +193,28 @@ impl PyTranslationOptions {
     }
 }
 
+impl PyTranslationOptions {
+    fn set_backend_options(
+        &mut self,
+        py: Python<'_>,
+        options: Option<HashMap<String, &PyAny>>,
+    ) -> PyResult<()> {
+        for (key, value) in options.into_iter().flatten() {
+            self.set_backend_option(py, &key, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Convert a Python value to JSON by way of the `json` module.
+fn to_json(py: Python<'_>, value: &PyAny) -> PyResult<serde_json::Value> {
+    let json: String = py
+        .import("json")?
+        .call_method1("dumps", (value,))?
+        .extract()?;
+    serde_json::from_str(&json).map_err(|error| PyValueError::new_err(error.to_string()))
+}
+
 #[derive(Clone, Default, Debug)]
 #[pyclass(name = "QCtrl")]
 pub struct PyQCtrl(translation_options::QCtrl);
@@ -1,12 +1,21 @@
+use numpy::Complex64;
 use pyo3::types::PyList;
 use qcs::{
-    qvm::{self, http, QvmOptions, QvmResultData},
+    qvm::{
+        self, http,
+        noise::{NoiseModel, PauliNoise},
+        QvmOptions, QvmResultData, Wavefunction,
+    },
     RegisterData,
 };
 use rigetti_pyo3::{
     create_init_submodule, impl_as_mut_for_wrapper, impl_repr, py_function_sync_async,
     py_wrap_error, py_wrap_type,
-    pyo3::{exceptions::PyRuntimeError, prelude::*, Python},
+    pyo3::{
+        exceptions::{PyRuntimeError, PyValueError},
+        prelude::*,
+        Python,
+    },
     wrap_error, PyTryFrom, PyWrapper, PyWrapperMut, ToPython, ToPythonError,
 };
 use std::num::NonZeroU16;
@@ -19,8 +28,8 @@ mod api;
 use api::PyAddressRequest;
 
 create_init_submodule! {
-    classes: [PyQvmResultData, PyQvmOptions, RawQvmReadoutData, PyQvmClient],
-    errors: [QVMError],
+    classes: [PyQvmResultData, PyWavefunction, PyQvmOptions, RawQvmReadoutData, PyQvmClient, PyNoiseModel],
+    errors: [QVMError, NoiseModelError],
     funcs: [py_run, py_run_async],
     submodules: [
         "api": api::init_submodule
@@ -30,6 +39,9 @@ create_init_submodule! {
 wrap_error!(RustQvmError(qcs::qvm::Error));
 py_wrap_error!(api, RustQvmError, QVMError, PyRuntimeError);
 
+wrap_error!(RustNoiseModelError(qcs::qvm::noise::Error));
+py_wrap_error!(qvm, RustNoiseModelError, NoiseModelError, PyValueError);
+
 #[derive(Clone)]
 pub enum QvmClient {
     Http(qvm::http::HttpClient),
@@ -186,6 +198,10 @@ impl PyQvmResultData {
                             RegisterData::F64(matrix) => PyList::new(py, matrix).into_py(py),
                             RegisterData::I16(matrix) => PyList::new(py, matrix).into_py(py),
                             RegisterData::Complex32(matrix) => PyList::new(py, matrix).into_py(py),
+                            RegisterData::I32(matrix) => PyList::new(py, matrix).into_py(py),
+                            RegisterData::F32(matrix) => PyList::new(py, matrix).into_py(py),
+                            RegisterData::Complex64(matrix) => PyList::new(py, matrix).into_py(py),
+                            RegisterData::I64(matrix) => PyList::new(py, matrix).into_py(py),
                         },
                     )
                 })
@@ -207,6 +223,105 @@ impl RawQvmReadoutData {
     }
 }
 
+py_wrap_type! {
+    PyWavefunction(Wavefunction) as "Wavefunction"
+}
+impl_repr!(PyWavefunction);
+
+#[pymethods]
+impl PyWavefunction {
+    #[staticmethod]
+    fn from_qvm_bytes(bytes: Vec<u8>) -> PyResult<Self> {
+        Wavefunction::from_qvm_bytes(&bytes)
+            .map(Self)
+            .map_err(RustQvmError::from)
+            .map_err(RustQvmError::to_py_err)
+    }
+
+    #[getter]
+    fn amplitudes(&self) -> Vec<Complex64> {
+        self.as_inner().amplitudes().to_vec()
+    }
+
+    #[getter]
+    fn num_qubits(&self) -> u32 {
+        self.as_inner().num_qubits()
+    }
+
+    #[getter]
+    fn qubits(&self) -> Vec<u64> {
+        self.as_inner().qubits().collect()
+    }
+
+    fn probabilities(&self) -> Vec<f64> {
+        self.as_inner().probabilities()
+    }
+}
+
+py_wrap_type! {
+    #[derive(Default)]
+    PyNoiseModel(NoiseModel) as "NoiseModel"
+}
+impl_repr!(PyNoiseModel);
+
+#[pymethods]
+impl PyNoiseModel {
+    #[new]
+    #[pyo3(signature = (gate_noise = None, measurement_noise = None, pauli_noise = None, readout_noise = None))]
+    fn new(
+        gate_noise: Option<(f64, f64, f64)>,
+        measurement_noise: Option<(f64, f64, f64)>,
+        pauli_noise: Option<Vec<(String, Vec<u64>, (f64, f64, f64))>>,
+        readout_noise: Option<HashMap<u64, (f64, f64)>>,
+    ) -> PyResult<Self> {
+        let mut builder = NoiseModel::builder();
+        if let Some(noise) = gate_noise {
+            builder = builder.gate_noise(noise);
+        }
+        if let Some(noise) = measurement_noise {
+            builder = builder.measurement_noise(noise);
+        }
+        for (gate, qubits, noise) in pauli_noise.unwrap_or_default() {
+            builder = builder.pauli_noise_for(gate, qubits, noise);
+        }
+        for (qubit, (p00, p11)) in readout_noise.unwrap_or_default() {
+            builder = builder.readout_noise_for(qubit, p00, p11);
+        }
+        builder
+            .build()
+            .map(Self)
+            .map_err(RustNoiseModelError::from)
+            .map_err(RustNoiseModelError::to_py_err)
+    }
+
+    #[staticmethod]
+    fn from_pyquil_json(json: &str) -> PyResult<Self> {
+        NoiseModel::from_pyquil_json(json)
+            .map(Self)
+            .map_err(RustNoiseModelError::from)
+            .map_err(RustNoiseModelError::to_py_err)
+    }
+
+    fn to_pyquil_json(&self) -> PyResult<String> {
+        self.as_inner()
+            .to_pyquil_json()
+            .map_err(RustNoiseModelError::from)
+            .map_err(RustNoiseModelError::to_py_err)
+    }
+
+    #[getter]
+    fn gate_noise(&self) -> Option<(f64, f64, f64)> {
+        self.as_inner().gate_noise().map(PauliNoise::as_tuple)
+    }
+
+    #[getter]
+    fn measurement_noise(&self) -> Option<(f64, f64, f64)> {
+        self.as_inner()
+            .measurement_noise()
+            .map(PauliNoise::as_tuple)
+    }
+}
+
 py_wrap_type! {
     #[derive(Default)]
     PyQvmOptions(QvmOptions) as "QVMOptions"
@@ -219,9 +334,7 @@ impl PyQvmOptions {
     #[new]
     #[pyo3(signature = (timeout_seconds = None))]
     pub fn new(timeout_seconds: Option<f64>) -> Self {
-        Self(QvmOptions {
-            timeout: timeout_seconds.map(Duration::from_secs_f64),
-        })
+        Self(QvmOptions::new().with_timeout(timeout_seconds.map(Duration::from_secs_f64)))
     }
 
     #[getter]
@@ -258,12 +371,31 @@ py_function_sync_async! {
         gate_noise: Option<(f64, f64, f64)>,
         rng_seed: Option<i64>,
         options: Option<PyQvmOptions>,
+        noise_model: Option<PyNoiseModel>,
     ) -> PyResult<PyQvmResultData> {
         let params = params.into_iter().map(|(key, value)| (key.into_boxed_str(), value)).collect();
         let addresses = addresses.into_iter().map(|(address, request)| (address, request.as_inner().clone())).collect();
         let options = options.unwrap_or_default();
-        Ok(
-            PyQvmResultData(
+        let result = match noise_model {
+            Some(_) if measurement_noise.is_some() || gate_noise.is_some() => {
+                return Err(PyValueError::new_err(
+                    "measurement_noise and gate_noise cannot be combined with a noise_model; set them on the NoiseModel instead",
+                ));
+            }
+            Some(noise_model) => {
+                qcs::qvm::run_with_noise_model(
+                    &quil,
+                    shots,
+                    addresses,
+                    &params,
+                    noise_model.as_inner(),
+                    rng_seed,
+                    &client,
+                    options.as_inner(),
+                )
+                .await
+            }
+            None => {
                 qcs::qvm::run(
                     &quil,
                     shots,
@@ -273,10 +405,15 @@ py_function_sync_async! {
                     gate_noise,
                     rng_seed,
                     &client,
-                    options.as_inner()
-            )
-            .await
-            .map_err(RustQvmError::from)
-            .map_err(RustQvmError::to_py_err)?))
+                    options.as_inner(),
+                )
+                .await
+            }
+        };
+        Ok(PyQvmResultData(
+            result
+                .map_err(RustQvmError::from)
+                .map_err(RustQvmError::to_py_err)?,
+        ))
     }
 }
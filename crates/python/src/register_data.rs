@@ -12,7 +12,11 @@ py_wrap_union_enum! {
         i8: I8 => Vec<Vec<Py<PyInt>>>,
         f64: F64 => Vec<Vec<Py<PyFloat>>>,
         i16: I16 => Vec<Vec<Py<PyInt>>>,
-        complex32: Complex32 => Vec<Vec<Py<PyComplex>>>
+        complex32: Complex32 => Vec<Vec<Py<PyComplex>>>,
+        i32: I32 => Vec<Vec<Py<PyInt>>>,
+        f32: F32 => Vec<Vec<Py<PyFloat>>>,
+        complex64: Complex64 => Vec<Vec<Py<PyComplex>>>,
+        i64: I64 => Vec<Vec<Py<PyInt>>>
     }
 }
 
@@ -32,6 +36,18 @@ impl PyRegisterData {
             RegisterData::Complex32(matrix) => {
                 PyArray::from_vec2(py, matrix.as_slice()).map(|arr| arr.to_object(py))
             }
+            RegisterData::I32(matrix) => {
+                PyArray::from_vec2(py, matrix.as_slice()).map(|arr| arr.to_object(py))
+            }
+            RegisterData::F32(matrix) => {
+                PyArray::from_vec2(py, matrix.as_slice()).map(|arr| arr.to_object(py))
+            }
+            RegisterData::Complex64(matrix) => {
+                PyArray::from_vec2(py, matrix.as_slice()).map(|arr| arr.to_object(py))
+            }
+            RegisterData::I64(matrix) => {
+                PyArray::from_vec2(py, matrix.as_slice()).map(|arr| arr.to_object(py))
+            }
         }
         .map_err(PyErr::from)
     }
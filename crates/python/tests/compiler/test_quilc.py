@@ -63,6 +63,22 @@ def test_compile_program(
     assert result.native_quil_metadata == snapshot
 
 
+def test_compile_program_with_specs(
+    bell_program: str,
+    aspen_m_3_isa: InstructionSetArchitecture,
+    quilc_rpcq_client: QuilcClient,
+):
+    """A program should compile to the same number of two-qubit gates with and without specs."""
+
+    def count_2q_gates(specs: bool) -> int:
+        target = TargetDevice.from_isa(aspen_m_3_isa, specs=specs)
+        options = CompilerOpts(protoquil=True, specs=specs)
+        result = compile_program(bell_program, target, client=quilc_rpcq_client, options=options)
+        return sum(1 for line in result.program.splitlines() if line.startswith("CZ"))
+
+    assert count_2q_gates(specs=False) == count_2q_gates(specs=True) == 1
+
+
 @pytest.mark.asyncio
 async def test_compile_program_async(
     bell_program: str,
@@ -1,5 +1,7 @@
 from datetime import timedelta
+import json
 import pickle
+import tarfile
 
 import pytest
 import numpy as np
@@ -7,7 +9,7 @@ from numpy.testing import assert_array_equal
 
 from qcs_sdk.qpu import ReadoutValues, MemoryValues, QPUResultData
 from qcs_sdk.qvm import QVMResultData
-from qcs_sdk import ResultData, RegisterData, RegisterMatrix, ExecutionData
+from qcs_sdk import BitOrder, ResultData, RegisterData, RegisterMatrix, ExecutionData
 
 
 class TestResultData:
@@ -64,6 +66,24 @@ class TestResultData:
 
         assert_array_equal(ro, expected)
 
+    def test_to_register_map_widens_register_data(self):
+        qvm_memory_map = {
+            "count": RegisterData.from_i32([[70000], [-70000]]),
+            "theta": RegisterData.from_f32([[0.5], [1.5]]),
+            "iq": RegisterData.from_complex64([[1 + 2j], [3 - 4j]]),
+        }
+        register_map = ResultData.from_qvm(QVMResultData.from_memory_map(qvm_memory_map)).to_register_map()
+
+        count = register_map.get_register_matrix("count")
+        assert count is not None
+        assert_array_equal(count.to_integer(), np.array([[70000], [-70000]]))
+        theta = register_map.get_register_matrix("theta")
+        assert theta is not None
+        assert_array_equal(theta.to_real(), np.array([[0.5], [1.5]]))
+        iq = register_map.get_register_matrix("iq")
+        assert iq is not None
+        assert_array_equal(iq.to_complex(), np.array([[1 + 2j], [3 - 4j]]))
+
 
 class TestRegisterMatrix:
     def test_integer(self):
@@ -113,6 +133,31 @@ class TestRegisterMap:
 
         assert expected_keys == actual_keys == set(register_map.keys())
 
+    def test_shapes(self):
+        memory_map = {
+            "ro": RegisterData.from_i16([[0, 1, 2], [1, 2, 3]]),
+            "theta": RegisterData.from_f64([[0.5], [1.5]]),
+        }
+        register_map = ResultData.from_qvm(QVMResultData.from_memory_map(memory_map)).to_register_map()
+        assert register_map.shapes() == {"ro": (2, 3, "int64"), "theta": (2, 1, "float64")}
+
+    def test_histogram(self):
+        memory_map = {
+            "ro": RegisterData.from_i8([[1, 0], [1, 0], [0, 0], [1, 1]]),
+            "theta": RegisterData.from_f64([[0.5]]),
+        }
+        register_map = ResultData.from_qvm(QVMResultData.from_memory_map(memory_map)).to_register_map()
+
+        assert register_map.histogram("ro") == {"00": 1, "10": 2, "11": 1}
+        assert register_map.histogram("ro", BitOrder.DescendingIndex) == {"00": 1, "01": 2, "11": 1}
+        assert register_map.probabilities("ro") == {"00": 0.25, "10": 0.5, "11": 0.25}
+        assert register_map.parity("ro") == [True, True, False, False]
+
+        with pytest.raises(KeyError):
+            register_map.histogram("missing")
+        with pytest.raises(ValueError):
+            register_map.parity("theta")
+
 
 class TestExecutionData:
     def test_pickle(self):
@@ -132,3 +177,66 @@ class TestExecutionData:
             pickled = pickle.dumps(execution_data)
             unpickled = pickle.loads(pickled)
             assert execution_data == unpickled
+
+    def test_readout_mappings(self):
+        mappings = {"ro[0]": "q0", "ro[1]": "q1"}
+        qpu_result_data = QPUResultData(
+            mappings=mappings,
+            readout_values={"q0": ReadoutValues.from_integer([0, 1]), "q1": ReadoutValues.from_integer([1, 1])},
+            memory_values={},
+        )
+        assert ExecutionData(ResultData.from_qpu(qpu_result_data)).readout_mappings == mappings
+
+        qvm_result_data = QVMResultData.from_memory_map({"ro": RegisterData.from_i8([[0, 1]])})
+        assert ExecutionData(ResultData.from_qvm(qvm_result_data)).readout_mappings is None
+
+    def test_to_counts_json(self):
+        memory_map = {"ro": RegisterData.from_i8([[1, 0], [1, 0], [0, 0]])}
+        execution_data = ExecutionData(ResultData.from_qvm(QVMResultData.from_memory_map(memory_map)))
+
+        counts = json.loads(execution_data.to_counts_json())
+        assert counts == {"ro": {"counts": {"00": 1, "10": 2}, "memory": ["10", "10", "00"]}}
+
+        counts = json.loads(execution_data.to_counts_json(BitOrder.DescendingIndex))
+        assert counts == {"ro": {"counts": {"00": 1, "01": 2}, "memory": ["01", "01", "00"]}}
+
+    def test_json_round_trip(self):
+        memory_map = {"ro": RegisterData.from_i8([[1, 0]])}
+        execution_data = ExecutionData(
+            ResultData.from_qvm(QVMResultData.from_memory_map(memory_map)), timedelta(microseconds=15)
+        )
+
+        document = json.loads(execution_data.to_json())
+        assert document == {
+            "schema_version": 2,
+            "duration_microseconds": 15,
+            "result_data": {"kind": "qvm", "memory": {"ro": {"dtype": "int8", "data": [[1, 0]]}}},
+        }
+        assert ExecutionData.from_json(execution_data.to_json()) == execution_data
+        assert ResultData.from_json(execution_data.result_data.to_json()).to_register_map()["ro"].to_ndarray().tolist() == [[1, 0]]
+
+        with pytest.raises(ValueError):
+            ExecutionData.from_json(json.dumps({**document, "schema_version": 3}))
+
+    def test_export_report(self, tmp_path):
+        memory_map = {"ro": RegisterData.from_i8([[1, 0]])}
+        execution_data = ExecutionData(ResultData.from_qvm(QVMResultData.from_memory_map(memory_map)))
+        path = tmp_path / "report.tar"
+        execution_data.export_report(path)
+
+        with tarfile.open(path) as archive:
+            assert archive.getnames() == ["report/results.json", "report/environment.json"]
+            environment = json.load(archive.extractfile("report/environment.json"))
+        assert "version" in environment
+
+    def test_to_csv(self, tmp_path):
+        memory_map = {"ro": RegisterData.from_i8([[1, 0]])}
+        execution_data = ExecutionData(ResultData.from_qvm(QVMResultData.from_memory_map(memory_map)))
+        path = tmp_path / "results.csv"
+        execution_data.to_csv(path)
+
+        assert path.read_text().splitlines() == [
+            "shot,register,index,value,imaginary,duration_us",
+            "0,ro,0,1,,",
+            "0,ro,1,0,,",
+        ]
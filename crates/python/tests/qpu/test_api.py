@@ -9,6 +9,7 @@ from qcs_sdk.qpu.api import (
     ConnectionStrategy,
     ExecutionOptions,
     Register,
+    RetryPolicy,
     retrieve_results,
     submit,
 )
@@ -60,3 +61,77 @@ class TestPickle():
         pickled = pickle.dumps(options)
         unpickled = pickle.loads(pickled)
         assert unpickled == options
+
+    def test_execution_options_max_request_bytes(self):
+        assert ExecutionOptions.default().max_request_bytes is None
+        builder = ExecutionOptions.builder()
+        builder.max_request_bytes = 1024
+        options = builder.build()
+        assert options.max_request_bytes == 1024
+        unpickled = pickle.loads(pickle.dumps(options))
+        assert unpickled.max_request_bytes == 1024
+
+    def test_execution_options_retry_policies(self):
+        options = ExecutionOptions.default()
+        assert options.retry_policy == RetryPolicy.default()
+        assert options.submit_retry_policy == RetryPolicy.for_submission()
+        assert options.retrieve_results_retry_policy is None
+
+        builder = ExecutionOptions.builder()
+        builder.retry_policy = RetryPolicy.never()
+        builder.retrieve_results_retry_policy = RetryPolicy(max_retries=10, jitter=False, retryable_status_codes=[14])
+        options = builder.build()
+        assert options.retry_policy.max_retries == 0
+        assert options.retrieve_results_retry_policy.max_retries == 10
+        assert options.retrieve_results_retry_policy.retryable_status_codes == [14]
+        unpickled = pickle.loads(pickle.dumps(options))
+        assert unpickled == options
+
+    def test_execution_options_result_retrieval_retries(self):
+        assert ExecutionOptions.default().result_retrieval_retries == 3
+
+        builder = ExecutionOptions.builder()
+        builder.result_retrieval_retries = 0
+        options = builder.build()
+        assert options.result_retrieval_retries == 0
+        assert options.retrieve_results_retry_policy.max_retries == 0
+        unpickled = pickle.loads(pickle.dumps(options))
+        assert unpickled.result_retrieval_retries == 0
+
+    def test_execution_options_spill(self, tmp_path):
+        options = ExecutionOptions.default()
+        assert options.spill_threshold_bytes is None
+        assert options.spill_directory is None
+
+        builder = ExecutionOptions.builder()
+        builder.spill_threshold_bytes = 1 << 30
+        builder.spill_directory = str(tmp_path)
+        options = builder.build()
+        assert options.spill_threshold_bytes == 1 << 30
+        assert options.spill_directory == str(tmp_path)
+        unpickled = pickle.loads(pickle.dumps(options))
+        assert unpickled == options
+
+    def test_execution_options_reservation_wait(self):
+        options = ExecutionOptions.default()
+        assert options.wait_for_reservation is False
+        assert options.max_reservation_wait_seconds == 3600.0
+
+        builder = ExecutionOptions.builder()
+        builder.wait_for_reservation = True
+        builder.max_reservation_wait_seconds = None
+        options = builder.build()
+        assert options.wait_for_reservation is True
+        assert options.max_reservation_wait_seconds is None
+        unpickled = pickle.loads(pickle.dumps(options))
+        assert unpickled == options
+
+    def test_execution_options_trace_context(self):
+        assert ExecutionOptions.default().propagate_trace_context is False
+
+        builder = ExecutionOptions.builder()
+        builder.propagate_trace_context = True
+        options = builder.build()
+        assert options.propagate_trace_context is True
+        unpickled = pickle.loads(pickle.dumps(options))
+        assert unpickled == options
@@ -39,6 +39,7 @@ def test_qpu_result_data():
     assert raw_data.mappings == {"a": "_q0"}
     assert raw_data.readout_values == {"a": [0, 1]}
     assert raw_data.memory_values == {"int": [2, 3], "binary": [0, 1], "real": [3.0, 4.0]}
+    assert result_data.readout_shapes() == {"a": (2, 1, "int64")}
 
 
 @pytest.mark.qcs_session
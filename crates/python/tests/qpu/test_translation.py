@@ -68,3 +68,18 @@ def test_translation_backend():
     assert opts.backend == TranslationBackend.V1
     opts.use_backend_v2()
     assert opts.backend == TranslationBackend.V2
+
+
+def test_translation_backend_options():
+    from qcs_sdk.qpu.translation import TranslationOptions
+
+    opts = TranslationOptions.v2(backend_options={"passive_reset_delay_seconds": 0.5})
+    opts.set_backend_option("allow_frame_redefinition", True)
+    assert opts.encode_as_protobuf() == TranslationOptions.v2(
+        passive_reset_delay_seconds=0.5, allow_frame_redefinition=True
+    ).encode_as_protobuf()
+
+    with pytest.raises(ValueError):
+        opts.set_backend_option("no_such_option", 1)
+    with pytest.raises(ValueError):
+        TranslationOptions().set_backend_option("allow_frame_redefinition", True)
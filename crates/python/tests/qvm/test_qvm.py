@@ -1,8 +1,25 @@
+import pytest
+
 from qcs_sdk import RegisterData
-from qcs_sdk.qvm import QVMResultData
+from qcs_sdk.qvm import NoiseModel, NoiseModelError, QVMResultData
 
 
 def test_qvm_result_data():
     register_data = RegisterData.from_i8([[1, 2, 3], [4, 5, 6]])
     raw_data = QVMResultData({"ro": register_data}).to_raw_readout_data()
     assert raw_data.memory == {"ro": [[1, 2, 3], [4, 5, 6]]}
+
+
+def test_noise_model():
+    noise_model = NoiseModel(
+        gate_noise=(0.01, 0.0, 0.0),
+        pauli_noise=[("CZ", [0, 1], (0.0, 0.0, 0.1))],
+        readout_noise={0: (0.95, 0.9)},
+    )
+    assert noise_model.gate_noise == (0.01, 0.0, 0.0)
+    assert noise_model.measurement_noise is None
+    assert "assignment_probs" in noise_model.to_pyquil_json()
+    assert NoiseModel.from_pyquil_json(noise_model.to_pyquil_json()).gate_noise is None
+
+    with pytest.raises(NoiseModelError):
+        NoiseModel(gate_noise=(0.5, 0.5, 0.5))
@@ -0,0 +1,36 @@
+import tarfile
+
+import pytest
+
+from qcs_sdk import cli
+from qcs_sdk.__main__ import main
+
+
+def test_doctor():
+    """
+    Assert that gathering diagnostics doesn't panic.
+    """
+    assert "qcs-sdk-rust version" in cli.doctor()
+
+
+def test_main_requires_a_command():
+    with pytest.raises(SystemExit):
+        main([])
+
+
+def test_run_on_qvm():
+    data = cli.run("DECLARE ro BIT\nX 0\nMEASURE 0 ro", shots=2)
+    assert data.result_data.to_register_map()["ro"].to_ndarray().tolist() == [[1], [1]]
+
+
+def test_run_writes_a_report(tmp_path):
+    program = tmp_path / "program.quil"
+    program.write_text("DECLARE ro BIT\nX 0\nMEASURE 0 ro")
+    report = tmp_path / "report.tar"
+    main(["run", str(program), "--shots", "2", "--report", str(report)])
+
+    with tarfile.open(report) as archive:
+        names = archive.getnames()
+        assert archive.extractfile("report/program.quil").read().decode() == program.read_text()
+    assert "report/results.json" in names
+    assert "report/diagnostics.txt" in names
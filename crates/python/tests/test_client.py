@@ -72,3 +72,28 @@ def test_client_constructor():
     assert client.quilc_url == "quilc_url"
     assert client.grpc_api_url == "grpc_api_url"
     assert client.api_url == "api_url"
+
+
+def test_client_application(default_client: QCSClient):
+    assert default_client.client_application_name is None
+    assert default_client.client_application_version is None
+
+    client = default_client.with_client_application("my-app", "1.2.3")
+    assert client.client_application_name == "my-app"
+    assert client.client_application_version == "1.2.3"
+    assert default_client.client_application_name is None
+
+    client = QCSClient(client_application_name="my-app")
+    assert client.client_application_name == "my-app"
+    assert client.client_application_version is None
+
+    with pytest.raises(ValueError):
+        default_client.with_client_application("my app")
+    with pytest.raises(ValueError):
+        QCSClient(client_application_version="1.2.3")
+
+
+def test_read_only(default_client: QCSClient):
+    client = default_client.with_read_only(True)
+    assert client.read_only
+    assert not client.with_read_only(False).read_only
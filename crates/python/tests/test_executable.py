@@ -65,3 +65,54 @@ def test_execute_qvm(
     shot_value = shot[0]
 
     assert shot_value in [ 0, 1 ]
+
+
+def test_execute_and_measure_qvm(
+    qvm_http_client: QVMClient,
+):
+    executable = Executable("H 0\nCNOT 0 1", shots=5)
+    results = executable.execute_and_measure_on_qvm(qvm_http_client)
+    ro = results.result_data.to_register_map()["ro"].to_integer()
+
+    assert ro.shape == (5, 2)
+    for shot in ro:
+        assert shot[0] == shot[1]
+
+
+def test_wavefunction_on_qvm(
+    qvm_http_client: QVMClient,
+):
+    executable = Executable("H 0\nCNOT 0 1")
+    results = executable.wavefunction_on_qvm(qvm_http_client)
+    wavefunction = results.result_data.to_wavefunction()
+
+    assert wavefunction.num_qubits == 2
+    assert wavefunction.probabilities() == pytest.approx([0.5, 0.0, 0.0, 0.5])
+
+
+def test_expectation_on_qvm(
+    qvm_http_client: QVMClient,
+):
+    executable = Executable("H 0\nCNOT 0 1")
+    expectations = executable.expectation_on_qvm(["Z0*Z1", "Z0", "I"], qvm_http_client)
+
+    assert list(expectations) == ["Z0*Z1", "Z0", "I"]
+    assert expectations["Z0*Z1"] == pytest.approx(1.0)
+    assert expectations["Z0"] == pytest.approx(0.0)
+    assert expectations["I"] == 1.0
+
+
+def test_shot_memory_persistence_not_supported(
+    qvm_http_client: QVMClient,
+):
+    executable = Executable("DECLARE count INTEGER\nADD count[0] 1", shot_memory_reset=False)
+
+    with pytest.raises(ExecutionError):
+        executable.execute_on_qvm(qvm_http_client)
+
+
+def test_invalid_deadline():
+    Executable("X 0", deadline=30.0)
+
+    with pytest.raises(ValueError):
+        Executable("X 0", deadline=-1.0)